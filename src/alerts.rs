@@ -0,0 +1,160 @@
+//! Keyword alerts: rules stored with `tgcli alerts add` and evaluated by the
+//! daemon against incoming messages.
+//!
+//! A pattern is a list of alternatives separated by `|` ("invoice|urgent"),
+//! each matched case-insensitively anywhere in the text. Matches are sent to:
+//!
+//! - `desktop`: a desktop notification (`notify-send`, or `osascript` on macOS)
+//! - `webhook`: a JSON POST of the event to a URL
+//! - `command`: a shell command, with the event as JSON on stdin and in
+//!   `TGCLI_ALERT_*` environment variables
+
+use crate::store::Alert;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Notify {
+    Desktop,
+    Webhook,
+    Command,
+}
+
+impl std::fmt::Display for Notify {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Notify::Desktop => write!(f, "desktop"),
+            Notify::Webhook => write!(f, "webhook"),
+            Notify::Command => write!(f, "command"),
+        }
+    }
+}
+
+/// A message that matched an alert.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertEvent {
+    pub alert_id: i64,
+    pub pattern: String,
+    pub chat_id: i64,
+    pub chat_name: String,
+    pub msg_id: i64,
+    pub sender_id: i64,
+    pub ts: String,
+    pub text: String,
+}
+
+/// Whether `text` contains any of the `|`-separated alternatives of `pattern`.
+pub fn pattern_matches(pattern: &str, text: &str) -> bool {
+    let text = text.to_lowercase();
+    pattern
+        .split('|')
+        .map(|alt| alt.trim().to_lowercase())
+        .any(|alt| !alt.is_empty() && text.contains(&alt))
+}
+
+/// Whether an alert applies to a message in `chat_id` with this text.
+pub fn alert_matches(alert: &Alert, chat_id: i64, text: &str) -> bool {
+    alert.chat_id.is_none_or(|id| id == chat_id) && pattern_matches(&alert.pattern, text)
+}
+
+/// Deliver an alert.
+pub async fn fire(alert: &Alert, event: &AlertEvent) -> Result<()> {
+    match alert.notify.as_str() {
+        "desktop" => desktop(event).await,
+        "webhook" => {
+            let url = alert
+                .target
+                .as_deref()
+                .context("Webhook alert has no URL")?;
+            reqwest::Client::new()
+                .post(url)
+                .json(event)
+                .send()
+                .await
+                .with_context(|| format!("Webhook request to {} failed", url))?
+                .error_for_status()
+                .with_context(|| format!("Webhook {} returned an error", url))?;
+            Ok(())
+        }
+        "command" => {
+            let command = alert
+                .target
+                .as_deref()
+                .context("Command alert has no command")?;
+            let mut child = tokio::process::Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("TGCLI_ALERT_ID", event.alert_id.to_string())
+                .env("TGCLI_ALERT_CHAT_ID", event.chat_id.to_string())
+                .env("TGCLI_ALERT_CHAT_NAME", &event.chat_name)
+                .env("TGCLI_ALERT_MSG_ID", event.msg_id.to_string())
+                .env("TGCLI_ALERT_TEXT", &event.text)
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+                .with_context(|| format!("Failed to run '{}'", command))?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(serde_json::to_string(event)?.as_bytes())
+                    .await?;
+            }
+            let status = child.wait().await?;
+            if !status.success() {
+                anyhow::bail!("'{}' exited with {}", command, status);
+            }
+            Ok(())
+        }
+        other => anyhow::bail!("Unknown alert notifier '{}'", other),
+    }
+}
+
+async fn desktop(event: &AlertEvent) -> Result<()> {
+    let title = format!("Telegram: {}", event.chat_name);
+    let body = crate::out::truncate(&event.text, 200);
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(&body),
+            applescript_string(&title)
+        );
+        let mut c = tokio::process::Command::new("osascript");
+        c.arg("-e").arg(script);
+        c
+    } else {
+        let mut c = tokio::process::Command::new("notify-send");
+        // The text comes from other people; `--` keeps a leading `-` from
+        // being read as an option
+        c.arg("--").arg(&title).arg(&body);
+        c
+    };
+    let status = command
+        .status()
+        .await
+        .context("Failed to show a desktop notification")?;
+    if !status.success() {
+        anyhow::bail!("Desktop notification failed ({})", status);
+    }
+    Ok(())
+}
+
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_matches_any_alternative_ignoring_case() {
+        assert!(pattern_matches(
+            "invoice|urgent",
+            "Please pay the INVOICE today"
+        ));
+        assert!(pattern_matches("invoice | urgent", "this is urgent"));
+        assert!(!pattern_matches("invoice|urgent", "see you tomorrow"));
+        // Empty alternatives never match everything
+        assert!(!pattern_matches("invoice||", "hello"));
+    }
+}
@@ -0,0 +1,68 @@
+//! File name, size, duration and dimensions of a message's photo or
+//! document, so media can be listed and planned without downloading it.
+
+use crate::store::MediaFileInfo;
+use grammers_tl_types as tl;
+
+/// The photo or document of a raw message (None for other media).
+pub(crate) fn message_attachment(raw: &tl::enums::Message) -> Option<MediaFileInfo> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    match m.media.as_ref()? {
+        tl::enums::MessageMedia::Photo(p) => match p.photo.as_ref()? {
+            tl::enums::Photo::Photo(photo) => {
+                // The largest size is the one a download fetches
+                let (size, w, h) = photo
+                    .sizes
+                    .iter()
+                    .filter_map(|s| match s {
+                        tl::enums::PhotoSize::Size(s) => Some((s.size as i64, s.w, s.h)),
+                        tl::enums::PhotoSize::Progressive(s) => {
+                            s.sizes.iter().max().map(|&b| (b as i64, s.w, s.h))
+                        }
+                        _ => None,
+                    })
+                    .max_by_key(|(size, _, _)| *size)?;
+                Some(MediaFileInfo {
+                    size,
+                    width: Some(w),
+                    height: Some(h),
+                    ..Default::default()
+                })
+            }
+            tl::enums::Photo::Empty(_) => None,
+        },
+        tl::enums::MessageMedia::Document(d) => match d.document.as_ref()? {
+            tl::enums::Document::Document(doc) => {
+                let mut info = MediaFileInfo {
+                    size: doc.size,
+                    ..Default::default()
+                };
+                for attribute in &doc.attributes {
+                    match attribute {
+                        tl::enums::DocumentAttribute::Filename(f) => {
+                            info.name = Some(f.file_name.clone());
+                        }
+                        tl::enums::DocumentAttribute::Video(v) => {
+                            info.duration = Some(v.duration);
+                            info.width = Some(v.w);
+                            info.height = Some(v.h);
+                        }
+                        tl::enums::DocumentAttribute::Audio(a) => {
+                            info.duration = Some(a.duration as f64);
+                        }
+                        tl::enums::DocumentAttribute::ImageSize(s) => {
+                            info.width = Some(s.w);
+                            info.height = Some(s.h);
+                        }
+                        _ => {}
+                    }
+                }
+                Some(info)
+            }
+            tl::enums::Document::Empty(_) => None,
+        },
+        _ => None,
+    }
+}
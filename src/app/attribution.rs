@@ -0,0 +1,18 @@
+//! Inline bot (`via @bot`) and channel signature of messages.
+
+use crate::store::Attribution;
+use grammers_tl_types as tl;
+
+/// Attribution of a raw message (None if it has neither a bot nor a signature).
+pub(crate) fn message_attribution(raw: &tl::enums::Message) -> Option<Attribution> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    if m.via_bot_id.is_none() && m.post_author.is_none() {
+        return None;
+    }
+    Some(Attribution {
+        via_bot_id: m.via_bot_id,
+        post_author: m.post_author.clone(),
+    })
+}
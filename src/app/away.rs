@@ -0,0 +1,41 @@
+//! Away mode (`tgcli away on`): the daemon answers private messages while
+//! you're away, once per sender per interval.
+
+use crate::app::send::TextOptions;
+use crate::app::App;
+use anyhow::Result;
+use chrono::{Duration, Utc};
+
+impl App {
+    /// Answer a private message from `sender_id` if away mode is on, they
+    /// may be answered, and they weren't answered within the interval.
+    /// Returns the ID of the reply.
+    pub async fn away_reply(&mut self, chat_id: i64, sender_id: i64) -> Result<Option<i64>> {
+        let store = self.get_store().await?;
+        let Some(away) = store.away_mode().await? else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        if away.until.is_some_and(|until| now >= until) {
+            store.clear_away_mode().await?;
+            log::info!("Away mode ended (--until reached)");
+            return Ok(None);
+        }
+        if away.deny.contains(&sender_id)
+            || (!away.allow.is_empty() && !away.allow.contains(&sender_id))
+        {
+            return Ok(None);
+        }
+        if let Some(last) = store.away_last_reply(sender_id).await? {
+            if now - last < Duration::minutes(away.interval_minutes) {
+                return Ok(None);
+            }
+        }
+
+        let msg_id = self
+            .send_text(chat_id, &away.message, "none", TextOptions::default())
+            .await?;
+        store.record_away_reply(sender_id, now).await?;
+        Ok(Some(msg_id))
+    }
+}
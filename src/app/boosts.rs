@@ -0,0 +1,125 @@
+//! Channel boosts: a chat's boost level and boosters, and applying your own
+//! Premium boost slots to it.
+
+use crate::app::{format_date, peer_bare_id, App};
+use anyhow::{Context, Result};
+use grammers_tl_types as tl;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BoostStatus {
+    pub level: i32,
+    pub boosts: i32,
+    pub current_level_boosts: i32,
+    /// Boosts needed for the next level (None at the maximum level)
+    pub next_level_boosts: Option<i32>,
+    pub boost_url: String,
+    /// Whether one of your slots already boosts this chat
+    pub my_boost: bool,
+    /// Your boost slots assigned to this chat
+    pub my_boost_slots: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BoostInfo {
+    pub id: String,
+    pub user_id: Option<i64>,
+    pub date: String,
+    pub expires: String,
+    /// Counts as this many boosts
+    pub multiplier: i32,
+    pub gift: bool,
+    pub giveaway: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BoostSlot {
+    pub slot: i32,
+    /// Chat the slot currently boosts
+    pub chat_id: Option<i64>,
+    pub expires: String,
+    /// Slot can't be reassigned until this time
+    pub cooldown_until: Option<String>,
+}
+
+impl App {
+    /// Boost level of a chat.
+    pub async fn boost_status(&self, chat_id: i64) -> Result<BoostStatus> {
+        let peer = self.resolve_peer_ref(chat_id).await?;
+        let tl::enums::premium::BoostsStatus::Status(status) = self
+            .tg
+            .invoke(&tl::functions::premium::GetBoostsStatus { peer: peer.into() })
+            .await
+            .context(format!("Failed to get boost status of chat {}", chat_id))?;
+        Ok(BoostStatus {
+            level: status.level,
+            boosts: status.boosts,
+            current_level_boosts: status.current_level_boosts,
+            next_level_boosts: status.next_level_boosts,
+            boost_url: status.boost_url,
+            my_boost: status.my_boost,
+            my_boost_slots: status.my_boost_slots.unwrap_or_default(),
+        })
+    }
+
+    /// Boosts applied to a chat (requires admin rights).
+    pub async fn list_boosts(&self, chat_id: i64, limit: i32) -> Result<Vec<BoostInfo>> {
+        let peer = self.resolve_peer_ref(chat_id).await?;
+        let tl::enums::premium::BoostsList::List(list) = self
+            .tg
+            .invoke(&tl::functions::premium::GetBoostsList {
+                gifts: false,
+                peer: peer.into(),
+                offset: String::new(),
+                limit,
+            })
+            .await
+            .context(format!("Failed to list boosts of chat {}", chat_id))?;
+        Ok(list
+            .boosts
+            .into_iter()
+            .map(|tl::enums::Boost::Boost(b)| BoostInfo {
+                id: b.id,
+                user_id: b.user_id,
+                date: format_date(b.date),
+                expires: format_date(b.expires),
+                multiplier: b.multiplier.unwrap_or(1),
+                gift: b.gift,
+                giveaway: b.giveaway,
+            })
+            .collect())
+    }
+
+    /// Your Premium boost slots and what they boost.
+    pub async fn my_boost_slots(&self) -> Result<Vec<BoostSlot>> {
+        let tl::enums::premium::MyBoosts::Boosts(boosts) = self
+            .tg
+            .invoke(&tl::functions::premium::GetMyBoosts {})
+            .await
+            .context("Failed to get your boost slots")?;
+        Ok(boosts
+            .my_boosts
+            .into_iter()
+            .map(|tl::enums::MyBoost::Boost(b)| BoostSlot {
+                slot: b.slot,
+                chat_id: b.peer.as_ref().map(peer_bare_id),
+                expires: format_date(b.expires),
+                cooldown_until: b.cooldown_until_date.map(format_date),
+            })
+            .collect())
+    }
+
+    /// Boost a chat with the given slots (Telegram picks a free slot if empty).
+    pub async fn apply_boost(&self, chat_id: i64, slots: &[i32]) -> Result<()> {
+        crate::guard::check_chat("apply boosts", chat_id)?;
+        let peer = self.resolve_peer_ref(chat_id).await?;
+        self.tg
+            .invoke(&tl::functions::premium::ApplyBoost {
+                slots: (!slots.is_empty()).then(|| slots.to_vec()),
+                peer: peer.into(),
+            })
+            .await
+            .context(format!("Failed to boost chat {}", chat_id))?;
+        Ok(())
+    }
+}
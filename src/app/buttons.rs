@@ -0,0 +1,166 @@
+//! Bot keyboards (reply markup) and pressing their buttons.
+
+use crate::app::App;
+use crate::store::MessageButton;
+use anyhow::{Context, Result};
+use grammers_tl_types as tl;
+
+/// The keyboard of a raw message, row by row (empty if it has none).
+pub(crate) fn message_buttons(raw: &tl::enums::Message) -> Vec<MessageButton> {
+    let tl::enums::Message::Message(m) = raw else {
+        return Vec::new();
+    };
+    let rows = match &m.reply_markup {
+        Some(tl::enums::ReplyMarkup::ReplyInlineMarkup(markup)) => &markup.rows,
+        Some(tl::enums::ReplyMarkup::ReplyKeyboardMarkup(markup)) => &markup.rows,
+        _ => return Vec::new(),
+    };
+    let mut buttons = Vec::new();
+    for (row, tl::enums::KeyboardButtonRow::Row(r)) in rows.iter().enumerate() {
+        for (col, button) in r.buttons.iter().enumerate() {
+            let (kind, text, url) = describe(button);
+            buttons.push(MessageButton {
+                row: row as i32,
+                col: col as i32,
+                text: text.to_string(),
+                kind: kind.to_string(),
+                url: url.map(str::to_string),
+            });
+        }
+    }
+    buttons
+}
+
+/// Kind, label and URL (if any) of a keyboard button.
+fn describe(button: &tl::enums::KeyboardButton) -> (&'static str, &str, Option<&str>) {
+    use tl::enums::KeyboardButton as B;
+    match button {
+        B::Button(b) => ("keyboard", &b.text, None),
+        B::Callback(b) => ("callback", &b.text, None),
+        B::Url(b) => ("url", &b.text, Some(&b.url)),
+        B::UrlAuth(b) => ("url", &b.text, Some(&b.url)),
+        B::InputKeyboardButtonUrlAuth(b) => ("url", &b.text, Some(&b.url)),
+        B::WebView(b) => ("webview", &b.text, Some(&b.url)),
+        B::SimpleWebView(b) => ("webview", &b.text, Some(&b.url)),
+        B::SwitchInline(b) => ("switch_inline", &b.text, None),
+        B::Game(b) => ("game", &b.text, None),
+        B::Buy(b) => ("buy", &b.text, None),
+        B::RequestPhone(b) => ("request_phone", &b.text, None),
+        B::RequestGeoLocation(b) => ("request_location", &b.text, None),
+        B::RequestPoll(b) => ("request_poll", &b.text, None),
+        B::RequestPeer(b) => ("request_peer", &b.text, None),
+        B::InputKeyboardButtonRequestPeer(b) => ("request_peer", &b.text, None),
+        B::UserProfile(b) => ("user_profile", &b.text, None),
+        B::InputKeyboardButtonUserProfile(b) => ("user_profile", &b.text, None),
+        B::Copy(b) => ("copy", &b.text, None),
+    }
+}
+
+/// What the bot answered to a button press.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ButtonAnswer {
+    /// Label of the pressed button
+    pub button: String,
+    /// Notification or alert text shown by the bot
+    pub message: Option<String>,
+    /// The bot asked to show `message` as an alert rather than a toast
+    pub alert: bool,
+    /// URL the bot asked to open
+    pub url: Option<String>,
+}
+
+impl App {
+    /// Press the callback button labelled `label` (case-insensitive) under a
+    /// bot message. The keyboard is fetched fresh, since bots edit it.
+    pub async fn press_button(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        label: &str,
+    ) -> Result<ButtonAnswer> {
+        crate::guard::check_chat("press buttons", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let msg = self
+            .tg
+            .client
+            .get_messages_by_id(peer_ref, &[msg_id as i32])
+            .await
+            .context(format!(
+                "Failed to get message {} in chat {}",
+                msg_id, chat_id
+            ))?
+            .into_iter()
+            .next()
+            .flatten()
+            .with_context(|| format!("Message {} not found in chat {}", msg_id, chat_id))?;
+
+        // Keep the stored keyboard in sync with what we're pressing
+        let buttons = message_buttons(&msg.raw);
+        self.get_store()
+            .await?
+            .set_message_buttons(chat_id, msg_id, &buttons)
+            .await?;
+
+        let tl::enums::Message::Message(m) = &msg.raw else {
+            anyhow::bail!("Message {} has no buttons", msg_id);
+        };
+        let Some(tl::enums::ReplyMarkup::ReplyInlineMarkup(markup)) = &m.reply_markup else {
+            anyhow::bail!("Message {} has no inline buttons", msg_id);
+        };
+        let all = || {
+            markup
+                .rows
+                .iter()
+                .flat_map(|tl::enums::KeyboardButtonRow::Row(r)| r.buttons.iter())
+        };
+        let button = all()
+            .find(|b| describe(b).1 == label)
+            .or_else(|| all().find(|b| describe(b).1.eq_ignore_ascii_case(label)))
+            .with_context(|| {
+                let labels: Vec<&str> = all().map(|b| describe(b).1).collect();
+                format!(
+                    "No button \"{}\" on message {} (buttons: {})",
+                    label,
+                    msg_id,
+                    labels.join(", ")
+                )
+            })?;
+
+        let tl::enums::KeyboardButton::Callback(callback) = button else {
+            let (kind, text, url) = describe(button);
+            match url {
+                Some(url) => anyhow::bail!("\"{}\" is a {} button, open {}", text, kind, url),
+                None => anyhow::bail!("\"{}\" is a {} button and can't be pressed", text, kind),
+            }
+        };
+        if callback.requires_password {
+            anyhow::bail!(
+                "\"{}\" asks for your 2FA password; press it in a Telegram app",
+                callback.text
+            );
+        }
+
+        let peer: tl::enums::InputPeer = peer_ref.into();
+        let tl::enums::messages::BotCallbackAnswer::Answer(answer) = self
+            .tg
+            .invoke(&tl::functions::messages::GetBotCallbackAnswer {
+                game: false,
+                peer,
+                msg_id: msg_id as i32,
+                data: Some(callback.data.clone()),
+                password: None,
+            })
+            .await
+            .context(format!(
+                "Failed to press \"{}\" on message {} in chat {}",
+                callback.text, msg_id, chat_id
+            ))?;
+
+        Ok(ButtonAnswer {
+            button: callback.text.clone(),
+            message: answer.message,
+            alert: answer.alert,
+            url: answer.url,
+        })
+    }
+}
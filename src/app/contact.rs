@@ -0,0 +1,42 @@
+//! Contact cards shared in messages.
+
+use crate::store::{SharedContact, Store};
+use anyhow::Result;
+use grammers_tl_types as tl;
+
+/// The contact card in a raw message (None if it doesn't share one).
+pub(crate) fn shared_contact(raw: &tl::enums::Message) -> Option<SharedContact> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    let Some(tl::enums::MessageMedia::Contact(contact)) = &m.media else {
+        return None;
+    };
+    Some(SharedContact {
+        // Telegram sends 0 for people who aren't on Telegram
+        user_id: (contact.user_id != 0).then_some(contact.user_id),
+        phone: contact.phone_number.clone(),
+        first_name: contact.first_name.clone(),
+        last_name: contact.last_name.clone(),
+    })
+}
+
+/// Add the contact card in a raw message to `contacts`, if it belongs to a
+/// Telegram user (`sync --save-shared-contacts`).
+pub(crate) async fn save_shared_contact(store: &Store, raw: &tl::enums::Message) -> Result<()> {
+    let Some(contact) = shared_contact(raw) else {
+        return Ok(());
+    };
+    let Some(user_id) = contact.user_id else {
+        return Ok(());
+    };
+    store
+        .upsert_contact(
+            user_id,
+            None,
+            &contact.first_name,
+            &contact.last_name,
+            &contact.phone,
+        )
+        .await
+}
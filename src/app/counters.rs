@@ -0,0 +1,64 @@
+//! View and forward counters of channel posts.
+
+use crate::app::App;
+use anyhow::{Context, Result};
+use grammers_tl_types as tl;
+
+pub(crate) struct Counters {
+    pub views: Option<i64>,
+    pub forwards: Option<i64>,
+}
+
+/// Views and forwards of a raw message (None outside channels).
+pub(crate) fn message_counters(raw: &tl::enums::Message) -> Option<Counters> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    if m.views.is_none() && m.forwards.is_none() {
+        return None;
+    }
+    Some(Counters {
+        views: m.views.map(i64::from),
+        forwards: m.forwards.map(i64::from),
+    })
+}
+
+/// Telegram returns counters for at most this many messages per request.
+const VIEWS_BATCH: usize = 100;
+
+impl App {
+    /// Fetch the current view and forward counts of channel posts and store
+    /// them. Returns the number of posts updated.
+    pub async fn refresh_counters(&self, chat_id: i64, msg_ids: &[i64]) -> Result<usize> {
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let peer: tl::enums::InputPeer = peer_ref.into();
+        let store = self.get_store().await?;
+
+        let mut updated = 0;
+        for batch in msg_ids.chunks(VIEWS_BATCH) {
+            let tl::enums::messages::MessageViews::Views(result) = self
+                .tg
+                .invoke(&tl::functions::messages::GetMessagesViews {
+                    peer: peer.clone(),
+                    id: batch.iter().map(|&id| id as i32).collect(),
+                    increment: false,
+                })
+                .await
+                .context(format!("Failed to get view counts in chat {}", chat_id))?;
+            // Results come back in the order of the requested IDs
+            for (&msg_id, tl::enums::MessageViews::Views(counts)) in batch.iter().zip(result.views)
+            {
+                store
+                    .set_counters(
+                        chat_id,
+                        msg_id,
+                        counts.views.map(i64::from),
+                        counts.forwards.map(i64::from),
+                    )
+                    .await?;
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+}
@@ -0,0 +1,107 @@
+//! Message details stored next to the core row: service actions, forward
+//! origins, attribution, counters, albums, contact cards, link previews,
+//! locations, polls, bot keyboards, reactions, paid media prices and file
+//! details (name, size, duration, dimensions).
+//!
+//! Every place that stores a message calls `record_message_details` right
+//! after `upsert_message`, and listings read them back with
+//! `annotate_messages`, so new kinds of details only need wiring here.
+
+use crate::app::attachment::message_attachment;
+use crate::app::attribution::message_attribution;
+use crate::app::buttons::message_buttons;
+use crate::app::contact::shared_contact;
+use crate::app::counters::message_counters;
+use crate::app::emoji;
+use crate::app::forward::forward_origin;
+use crate::app::geo::message_geo;
+use crate::app::paid::paid_media;
+use crate::app::poll::message_poll;
+use crate::app::reactions::message_reactions;
+use crate::app::service::service_action;
+use crate::app::webpage::message_webpage;
+use crate::store::{Message, Store};
+use anyhow::Result;
+use grammers_tl_types as tl;
+
+/// The message carried by a new-message update.
+pub(crate) fn update_message(raw: &tl::enums::Update) -> Option<&tl::enums::Message> {
+    match raw {
+        tl::enums::Update::NewMessage(u) => Some(&u.message),
+        tl::enums::Update::NewChannelMessage(u) => Some(&u.message),
+        _ => None,
+    }
+}
+
+/// Store the details of a raw message that was just upserted.
+pub(crate) async fn record_message_details(
+    store: &Store,
+    chat_id: i64,
+    msg_id: i64,
+    raw: &tl::enums::Message,
+) -> Result<()> {
+    if let Some(action) = service_action(raw) {
+        store
+            .set_service_action(chat_id, msg_id, &action.json)
+            .await?;
+    }
+    if let Some(origin) = forward_origin(raw) {
+        store.set_forward_origin(chat_id, msg_id, &origin).await?;
+    }
+    if let Some(attribution) = message_attribution(raw) {
+        store.set_attribution(chat_id, msg_id, &attribution).await?;
+    }
+    if let Some(counters) = message_counters(raw) {
+        store
+            .set_counters(chat_id, msg_id, counters.views, counters.forwards)
+            .await?;
+    }
+    if let tl::enums::Message::Message(m) = raw {
+        if let Some(grouped_id) = m.grouped_id {
+            store.set_grouped_id(chat_id, msg_id, grouped_id).await?;
+        }
+    }
+    if let Some(contact) = shared_contact(raw) {
+        store.set_shared_contact(chat_id, msg_id, &contact).await?;
+    }
+    if let Some(webpage) = message_webpage(raw) {
+        store
+            .upsert_message_webpage(chat_id, msg_id, &webpage)
+            .await?;
+    }
+    if let Some(geo) = message_geo(raw) {
+        store.upsert_message_geo(chat_id, msg_id, &geo).await?;
+    }
+    if let Some(poll) = message_poll(chat_id, raw) {
+        store.upsert_message_poll(&poll).await?;
+    }
+    if let Some(file) = message_attachment(raw) {
+        store.set_media_file_info(chat_id, msg_id, &file).await?;
+    }
+    if let Some(paid) = paid_media(raw) {
+        store.set_paid_media(chat_id, msg_id, paid.stars).await?;
+    }
+    if let Some(reactions) = message_reactions(raw) {
+        store
+            .set_message_reactions(chat_id, msg_id, &reactions)
+            .await?;
+    }
+    let buttons = message_buttons(raw);
+    if !buttons.is_empty() {
+        store.set_message_buttons(chat_id, msg_id, &buttons).await?;
+    }
+    Ok(())
+}
+
+/// Fill in what's stored outside the messages row (custom emoji labels,
+/// link previews, locations, keyboards, reactions), for display.
+pub(crate) async fn annotate_messages(store: &Store, msgs: &mut [Message]) -> Result<()> {
+    emoji::annotate_messages(store, msgs).await?;
+    for m in msgs.iter_mut() {
+        m.webpage = store.message_webpage(m.chat_id, m.id).await?;
+        m.geo = store.message_geo(m.chat_id, m.id).await?;
+        m.buttons = store.message_buttons(m.chat_id, m.id).await?;
+        m.reactions = store.message_reactions(m.chat_id, m.id).await?;
+    }
+    Ok(())
+}
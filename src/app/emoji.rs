@@ -0,0 +1,228 @@
+//! Custom emoji resolution for message text.
+//!
+//! Custom emoji are sent as a fallback emoji plus a `MessageEntityCustomEmoji`
+//! pointing at a document. Sync records where they sit in each message and
+//! resolves their documents once (cached in the store); output then replaces
+//! the fallback with a label naming the emoji and its pack, e.g.
+//! `[:PartyBlobs/🎉:]`. The stored text stays as sent, for search and export.
+
+use crate::store::{CustomEmoji, CustomEmojiSpan, Message, Store};
+use anyhow::Result;
+use grammers_client::Client;
+use grammers_tl_types as tl;
+
+/// Entities of a raw message (empty for service messages).
+pub(crate) fn message_entities(raw: &tl::enums::Message) -> &[tl::enums::MessageEntity] {
+    match raw {
+        tl::enums::Message::Message(m) => m.entities.as_deref().unwrap_or_default(),
+        _ => &[],
+    }
+}
+
+/// Entities of the message carried by a new/edited message update.
+pub(crate) fn update_entities(raw: &tl::enums::Update) -> &[tl::enums::MessageEntity] {
+    match raw {
+        tl::enums::Update::NewMessage(u) => message_entities(&u.message),
+        tl::enums::Update::NewChannelMessage(u) => message_entities(&u.message),
+        tl::enums::Update::EditMessage(u) => message_entities(&u.message),
+        tl::enums::Update::EditChannelMessage(u) => message_entities(&u.message),
+        _ => &[],
+    }
+}
+
+/// Custom emoji among a message's entities.
+pub(crate) fn custom_emoji_spans(entities: &[tl::enums::MessageEntity]) -> Vec<CustomEmojiSpan> {
+    entities
+        .iter()
+        .filter_map(|e| match e {
+            tl::enums::MessageEntity::CustomEmoji(c) => Some(CustomEmojiSpan {
+                offset: c.offset as i64,
+                length: c.length as i64,
+                doc_id: c.document_id,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Record a message's custom emoji and resolve documents not seen before.
+/// Resolution failures are logged; those emoji are shown unlabeled.
+pub(crate) async fn record_custom_emoji(
+    client: &Client,
+    store: &Store,
+    chat_id: i64,
+    msg_id: i64,
+    spans: &[CustomEmojiSpan],
+) -> Result<()> {
+    store
+        .set_message_custom_emoji(chat_id, msg_id, spans)
+        .await?;
+    if spans.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<i64> = spans.iter().map(|s| s.doc_id).collect();
+    if let Err(e) = resolve_custom_emoji(client, store, &ids).await {
+        log::warn!("Failed to resolve custom emoji: {}", e);
+    }
+    Ok(())
+}
+
+/// Replace custom emoji in the messages' text with their labels, for display.
+pub(crate) async fn annotate_messages(store: &Store, msgs: &mut [Message]) -> Result<()> {
+    for m in msgs {
+        let labels: Vec<(usize, usize, String)> = store
+            .message_custom_emoji(m.chat_id, m.id)
+            .await?
+            .into_iter()
+            .filter_map(|(span, emoji)| {
+                let label = emoji?.label();
+                Some((span.offset as usize, span.length as usize, label))
+            })
+            .collect();
+        if !labels.is_empty() {
+            m.text = label_text(&m.text, labels);
+        }
+    }
+    Ok(())
+}
+
+/// Label the custom emoji in a live message's text, for output that isn't
+/// read back from the store. Resolution failures leave the text as sent.
+pub(crate) async fn label_live_text(
+    client: &Client,
+    store: &Store,
+    text: &str,
+    entities: &[tl::enums::MessageEntity],
+) -> String {
+    let spans = custom_emoji_spans(entities);
+    if spans.is_empty() {
+        return text.to_string();
+    }
+    let ids: Vec<i64> = spans.iter().map(|s| s.doc_id).collect();
+    if let Err(e) = resolve_custom_emoji(client, store, &ids).await {
+        log::warn!("Failed to resolve custom emoji: {}", e);
+        return text.to_string();
+    }
+    let mut labels = Vec::with_capacity(spans.len());
+    for span in spans {
+        if let Ok(Some(emoji)) = store.get_custom_emoji(span.doc_id).await {
+            labels.push((span.offset as usize, span.length as usize, emoji.label()));
+        }
+    }
+    label_text(text, labels)
+}
+
+/// Splice `labels` (UTF-16 offset, length, label) into `text`. Spans that
+/// overlap or fall outside the text are skipped.
+fn label_text(text: &str, mut labels: Vec<(usize, usize, String)>) -> String {
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let mut out: Vec<u16> = Vec::with_capacity(units.len() + labels.len() * 16);
+    let mut pos = 0;
+    labels.sort_by_key(|&(offset, _, _)| offset);
+    for (offset, length, label) in labels {
+        let end = (offset + length).min(units.len());
+        if offset < pos || offset > end {
+            continue;
+        }
+        out.extend_from_slice(&units[pos..offset]);
+        out.extend(label.encode_utf16());
+        pos = end;
+    }
+    out.extend_from_slice(&units[pos..]);
+    String::from_utf16_lossy(&out)
+}
+
+/// Fetch and cache the custom emoji documents not seen before.
+async fn resolve_custom_emoji(client: &Client, store: &Store, ids: &[i64]) -> Result<()> {
+    let mut missing = Vec::new();
+    for &id in ids {
+        if !missing.contains(&id) && store.get_custom_emoji(id).await?.is_none() {
+            missing.push(id);
+        }
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let request = tl::functions::messages::GetCustomEmojiDocuments {
+        document_id: missing,
+    };
+    for doc in crate::tg::trace::invoke(client, &request).await? {
+        let tl::enums::Document::Document(d) = doc else {
+            continue;
+        };
+        let Some(attr) = d.attributes.iter().find_map(|a| match a {
+            tl::enums::DocumentAttribute::CustomEmoji(c) => Some(c),
+            _ => None,
+        }) else {
+            continue;
+        };
+
+        let (set_id, set_name) = match &attr.stickerset {
+            tl::enums::InputStickerSet::ShortName(s) => (None, Some(s.short_name.clone())),
+            tl::enums::InputStickerSet::Id(s) => {
+                let name = match store.get_custom_emoji_set_name(s.id).await? {
+                    Some(name) => Some(name),
+                    None => fetch_set_name(client, s).await,
+                };
+                (Some(s.id), name)
+            }
+            _ => (None, None),
+        };
+
+        let emoji = CustomEmoji {
+            doc_id: d.id,
+            alt: attr.alt.clone(),
+            set_id,
+            set_name,
+        };
+        store.upsert_custom_emoji(&emoji).await?;
+    }
+    Ok(())
+}
+
+async fn fetch_set_name(client: &Client, set: &tl::types::InputStickerSetId) -> Option<String> {
+    let request = tl::functions::messages::GetStickerSet {
+        stickerset: tl::enums::InputStickerSet::Id(set.clone()),
+        hash: 0,
+    };
+    match crate::tg::trace::invoke(client, &request).await {
+        Ok(tl::enums::messages::StickerSet::Set(s)) => {
+            let tl::enums::StickerSet::Set(info) = s.set;
+            Some(info.short_name)
+        }
+        Ok(tl::enums::messages::StickerSet::NotModified) => None,
+        Err(e) => {
+            log::debug!("Failed to fetch emoji pack {}: {}", set.id, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(offset: usize, length: usize, text: &str) -> (usize, usize, String) {
+        (offset, length, text.to_string())
+    }
+
+    #[test]
+    fn labels_use_utf16_offsets() {
+        // "🎉" is two UTF-16 units, so "🔥" starts at 6
+        let text = "hi 🎉 🔥!";
+        let labels = vec![label(6, 2, "[:Fire:]"), label(3, 2, "[:Party/🎉:]")];
+        assert_eq!(label_text(text, labels), "hi [:Party/🎉:] [:Fire:]!");
+    }
+
+    #[test]
+    fn skips_overlapping_and_out_of_range_spans() {
+        let text = "🎉x";
+        let labels = vec![
+            label(0, 2, "[:a:]"),
+            label(1, 2, "[:b:]"),
+            label(9, 2, "[:c:]"),
+        ];
+        assert_eq!(label_text(text, labels), "[:a:]x");
+    }
+}
@@ -0,0 +1,106 @@
+//! Chat folders (dialog filters) looked up by name, for `tgcli sync --folder`.
+
+use crate::app::App;
+use anyhow::{Context, Result};
+use grammers_tl_types as tl;
+use std::collections::HashSet;
+
+/// Which chats a folder holds: the ones added to it by hand, plus whole
+/// categories (all groups, all channels) when the folder includes them.
+#[derive(Debug, Clone)]
+pub struct Folder {
+    pub id: i32,
+    pub title: String,
+    include: HashSet<i64>,
+    exclude: HashSet<i64>,
+    groups: bool,
+    broadcasts: bool,
+    pub exclude_archived: bool,
+}
+
+impl Folder {
+    /// Whether a chat of this `kind` ("user", "group", "channel") is in the folder.
+    pub fn contains(&self, chat_id: i64, kind: &str) -> bool {
+        if self.exclude.contains(&chat_id) {
+            return false;
+        }
+        self.include.contains(&chat_id)
+            || (self.groups && kind == "group")
+            || (self.broadcasts && kind == "channel")
+    }
+}
+
+fn peer_ids(peers: &[tl::enums::InputPeer]) -> impl Iterator<Item = i64> + '_ {
+    peers.iter().filter_map(|peer| match peer {
+        tl::enums::InputPeer::User(u) => Some(u.user_id),
+        tl::enums::InputPeer::Chat(c) => Some(c.chat_id),
+        tl::enums::InputPeer::Channel(c) => Some(c.channel_id),
+        _ => None,
+    })
+}
+
+fn text(title: &tl::enums::TextWithEntities) -> String {
+    match title {
+        tl::enums::TextWithEntities::Entities(t) => t.text.clone(),
+    }
+}
+
+impl App {
+    /// Find a folder by title (case-insensitive) or numeric ID.
+    pub async fn find_folder(&self, name: &str) -> Result<Folder> {
+        let result = self
+            .tg
+            .invoke(&tl::functions::messages::GetDialogFilters {})
+            .await
+            .context("Failed to fetch chat folders")?;
+        let tl::enums::messages::DialogFilters::Filters(filters) = result;
+
+        for filter in filters.filters {
+            let (folder, users_by_category) = match filter {
+                tl::enums::DialogFilter::Filter(f) => (
+                    Folder {
+                        id: f.id,
+                        title: text(&f.title),
+                        include: peer_ids(&f.pinned_peers)
+                            .chain(peer_ids(&f.include_peers))
+                            .collect(),
+                        exclude: peer_ids(&f.exclude_peers).collect(),
+                        groups: f.groups,
+                        broadcasts: f.broadcasts,
+                        exclude_archived: f.exclude_archived,
+                    },
+                    f.contacts || f.non_contacts || f.bots,
+                ),
+                tl::enums::DialogFilter::Chatlist(c) => (
+                    Folder {
+                        id: c.id,
+                        title: text(&c.title),
+                        include: peer_ids(&c.pinned_peers)
+                            .chain(peer_ids(&c.include_peers))
+                            .collect(),
+                        exclude: HashSet::new(),
+                        groups: false,
+                        broadcasts: false,
+                        exclude_archived: false,
+                    },
+                    false,
+                ),
+                tl::enums::DialogFilter::Default => continue,
+            };
+            if folder.title.to_lowercase() == name.to_lowercase() || folder.id.to_string() == name {
+                if users_by_category {
+                    // A user's contact or bot status isn't known until it's fetched
+                    eprintln!(
+                        "Warning: folder '{}' includes contacts or bots by category; only users added to it explicitly are synced.",
+                        folder.title
+                    );
+                }
+                return Ok(folder);
+            }
+        }
+        anyhow::bail!(
+            "Folder '{}' not found. Run `tgcli folders list` to see available folders.",
+            name
+        )
+    }
+}
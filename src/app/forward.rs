@@ -0,0 +1,20 @@
+//! Origin of forwarded messages, taken from their `MessageFwdHeader`.
+
+use crate::app::peer_bare_id;
+use crate::store::ForwardOrigin;
+use chrono::{TimeZone, Utc};
+use grammers_tl_types as tl;
+
+/// Where a raw message was forwarded from (None if it isn't a forward).
+pub(crate) fn forward_origin(raw: &tl::enums::Message) -> Option<ForwardOrigin> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    let tl::enums::MessageFwdHeader::Header(header) = m.fwd_from.as_ref()?;
+    Some(ForwardOrigin {
+        // Set when the original sender hides their account
+        name: header.from_name.clone(),
+        from_id: header.from_id.as_ref().map(peer_bare_id),
+        date: Utc.timestamp_opt(header.date as i64, 0).single()?,
+    })
+}
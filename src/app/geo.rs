@@ -0,0 +1,53 @@
+//! Locations, venues and live locations shared in messages.
+
+use crate::store::MessageGeo;
+use grammers_tl_types as tl;
+
+/// The location or venue in a raw message (None if it shares none).
+pub(crate) fn message_geo(raw: &tl::enums::Message) -> Option<MessageGeo> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    match m.media.as_ref()? {
+        tl::enums::MessageMedia::Geo(media) => point("geo", &media.geo),
+        tl::enums::MessageMedia::Venue(venue) => {
+            let geo = point("venue", &venue.geo)?;
+            Some(MessageGeo {
+                title: non_empty(&venue.title),
+                address: non_empty(&venue.address),
+                provider: non_empty(&venue.provider),
+                venue_id: non_empty(&venue.venue_id),
+                ..geo
+            })
+        }
+        tl::enums::MessageMedia::GeoLive(live) => {
+            let geo = point("geolive", &live.geo)?;
+            Some(MessageGeo {
+                live_period: Some(live.period),
+                ..geo
+            })
+        }
+        _ => None,
+    }
+}
+
+fn point(kind: &str, geo: &tl::enums::GeoPoint) -> Option<MessageGeo> {
+    let tl::enums::GeoPoint::Point(p) = geo else {
+        return None;
+    };
+    Some(MessageGeo {
+        kind: kind.to_string(),
+        lat: p.lat,
+        lon: p.long,
+        accuracy_radius: p.accuracy_radius,
+        title: None,
+        address: None,
+        provider: None,
+        venue_id: None,
+        live_period: None,
+    })
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    (!s.is_empty()).then(|| s.to_string())
+}
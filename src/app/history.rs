@@ -0,0 +1,138 @@
+//! How much history a chat has on Telegram, to plan a full backfill: the
+//! message total and, per media type, a count and estimated download size.
+
+use crate::app::attachment::message_attachment;
+use crate::app::App;
+use anyhow::{Context, Result};
+use grammers_tl_types as tl;
+use serde::Serialize;
+
+/// Newest files per media type whose sizes are averaged for the estimate.
+const SIZE_SAMPLE: i32 = 20;
+
+const MEDIA_FILTERS: &[(&str, tl::enums::MessagesFilter)] = &[
+    (
+        "photo",
+        tl::enums::MessagesFilter::InputMessagesFilterPhotos,
+    ),
+    ("video", tl::enums::MessagesFilter::InputMessagesFilterVideo),
+    (
+        "document",
+        tl::enums::MessagesFilter::InputMessagesFilterDocument,
+    ),
+    ("audio", tl::enums::MessagesFilter::InputMessagesFilterMusic),
+    ("voice", tl::enums::MessagesFilter::InputMessagesFilterVoice),
+    (
+        "round",
+        tl::enums::MessagesFilter::InputMessagesFilterRoundVideo,
+    ),
+    ("gif", tl::enums::MessagesFilter::InputMessagesFilterGif),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaCount {
+    pub media_type: &'static str,
+    pub count: i64,
+    /// Average size of the newest files times `count`
+    pub estimated_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryCount {
+    pub messages: i64,
+    /// Telegram reported an approximate total
+    pub inexact: bool,
+    pub media: Vec<MediaCount>,
+    pub estimated_media_bytes: u64,
+}
+
+/// Total, whether it's approximate, and the messages returned.
+fn unpack(result: tl::enums::messages::Messages) -> (i64, bool, Vec<tl::enums::Message>) {
+    match result {
+        tl::enums::messages::Messages::Messages(m) => (m.messages.len() as i64, false, m.messages),
+        tl::enums::messages::Messages::Slice(m) => (m.count as i64, m.inexact, m.messages),
+        tl::enums::messages::Messages::ChannelMessages(m) => {
+            (m.count as i64, m.inexact, m.messages)
+        }
+        tl::enums::messages::Messages::NotModified(m) => (m.count as i64, false, Vec::new()),
+    }
+}
+
+impl App {
+    /// Count a chat's messages on Telegram without fetching them: one
+    /// `messages.getHistory` with limit 1, plus one search per media type.
+    pub async fn count_history(&self, chat_id: i64) -> Result<HistoryCount> {
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let peer: tl::enums::InputPeer = peer_ref.into();
+
+        let history = self
+            .tg
+            .invoke(&tl::functions::messages::GetHistory {
+                peer: peer.clone(),
+                offset_id: 0,
+                offset_date: 0,
+                add_offset: 0,
+                limit: 1,
+                max_id: 0,
+                min_id: 0,
+                hash: 0,
+            })
+            .await
+            .with_context(|| format!("Failed to count messages in chat {}", chat_id))?;
+        let (messages, inexact, _) = unpack(history);
+
+        let mut media = Vec::new();
+        for (media_type, filter) in MEDIA_FILTERS {
+            let result = self
+                .tg
+                .invoke(&tl::functions::messages::Search {
+                    peer: peer.clone(),
+                    q: String::new(),
+                    from_id: None,
+                    saved_peer_id: None,
+                    saved_reaction: None,
+                    top_msg_id: None,
+                    filter: filter.clone(),
+                    min_date: 0,
+                    max_date: 0,
+                    offset_id: 0,
+                    add_offset: 0,
+                    limit: SIZE_SAMPLE,
+                    max_id: 0,
+                    min_id: 0,
+                    hash: 0,
+                })
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to count {} messages in chat {}",
+                        media_type, chat_id
+                    )
+                })?;
+            let (count, _, sample) = unpack(result);
+            if count == 0 {
+                continue;
+            }
+            let sizes: Vec<u64> = sample
+                .iter()
+                .filter_map(|m| message_attachment(m).map(|a| a.size as u64))
+                .collect();
+            let average = match sizes.len() {
+                0 => 0,
+                n => sizes.iter().sum::<u64>() / n as u64,
+            };
+            media.push(MediaCount {
+                media_type,
+                count,
+                estimated_bytes: average * count as u64,
+            });
+        }
+
+        Ok(HistoryCount {
+            messages,
+            inexact,
+            estimated_media_bytes: media.iter().map(|m| m.estimated_bytes).sum(),
+            media,
+        })
+    }
+}
@@ -1,5 +1,27 @@
+pub mod attachment;
+pub mod attribution;
+pub mod away;
+pub mod boosts;
+pub mod buttons;
+pub mod contact;
+pub mod counters;
+pub mod details;
+pub mod emoji;
+pub mod folder;
+pub mod forward;
+pub mod geo;
+pub mod history;
+pub mod paid;
+pub mod payments;
+pub mod poll;
+pub mod reactions;
+pub mod report;
+pub mod schedule;
 pub mod send;
+pub mod service;
 pub mod sync;
+pub mod todo;
+pub mod webpage;
 
 use crate::store::Store;
 use crate::tg::TgClient;
@@ -8,11 +30,14 @@ use anyhow::{Context, Result};
 use grammers_session::defs::PeerRef;
 use grammers_session::updates::UpdatesLike;
 use grammers_tl_types as tl;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 pub struct App {
     pub tg: TgClient,
     pub store_dir: String,
+    /// Opened once, so its prepared statements live as long as the App
+    store: Arc<Store>,
     #[allow(dead_code)]
     pub json: bool,
     #[allow(dead_code)]
@@ -20,21 +45,22 @@ pub struct App {
 }
 
 impl App {
-    /// Get a fresh Store instance with a new Database connection.
-    /// The Database will be dropped when the Store goes out of scope.
-    pub async fn get_store(&self) -> Result<Store> {
-        Store::open(&self.store_dir).await
+    /// The store, shared by everything this App does.
+    pub async fn get_store(&self) -> Result<Arc<Store>> {
+        Ok(Arc::clone(&self.store))
     }
     pub async fn new(cli: &Cli) -> Result<Self> {
         let store_dir = cli.store_dir();
         std::fs::create_dir_all(&store_dir)
             .with_context(|| format!("Failed to create store directory '{}'", store_dir))?;
+        crate::lock::acquire(&store_dir, cli.wait).await?;
 
-        let session_path = format!("{}/session.db", store_dir);
+        let session_path = crate::paths::session_path(&store_dir);
         // SqliteSession::open creates the file if it doesn't exist
 
-        let (tg, updates_rx) = TgClient::connect_with_updates(&session_path)
-            .context("Failed to connect to Telegram")?;
+        let (tg, updates_rx) =
+            TgClient::connect_with_updates(&session_path, &cli.connect_options())
+                .context("Failed to connect to Telegram")?;
 
         if !tg
             .client
@@ -45,9 +71,11 @@ impl App {
             anyhow::bail!("Session expired or not authenticated. Run `tgcli auth` first.");
         }
 
+        let store = Arc::new(Store::open(&store_dir).await?);
         Ok(App {
             tg,
             store_dir,
+            store,
             json: cli.output.is_json(),
             updates_rx: Some(updates_rx),
         })
@@ -58,14 +86,18 @@ impl App {
         let store_dir = cli.store_dir();
         std::fs::create_dir_all(&store_dir)
             .with_context(|| format!("Failed to create store directory '{}'", store_dir))?;
+        crate::lock::acquire(&store_dir, cli.wait).await?;
 
-        let session_path = format!("{}/session.db", store_dir);
+        let session_path = crate::paths::session_path(&store_dir);
 
-        let (tg, updates_rx) = TgClient::connect_with_updates(&session_path)
-            .context("Failed to connect to Telegram")?;
+        let (tg, updates_rx) =
+            TgClient::connect_with_updates(&session_path, &cli.connect_options())
+                .context("Failed to connect to Telegram")?;
+        let store = Arc::new(Store::open(&store_dir).await?);
         Ok(App {
             tg,
             store_dir,
+            store,
             json: cli.output.is_json(),
             updates_rx: Some(updates_rx),
         })
@@ -92,7 +124,6 @@ impl App {
 
         let result = self
             .tg
-            .client
             .invoke(&request)
             .await
             .with_context(|| format!("Failed to fetch forum topics for chat {}", chat_id))?;
@@ -117,6 +148,7 @@ impl App {
                             topic.icon_color,
                             icon_emoji.as_deref(),
                             topic.unread_count,
+                            Some(topic.top_message),
                         )
                         .await?;
                     count += 1;
@@ -150,6 +182,7 @@ impl App {
     /// Mark all forum topics in a chat as read.
     /// Returns the number of topics marked as read.
     pub async fn mark_read_all_topics(&self, chat_id: i64) -> Result<usize> {
+        crate::guard::check_chat("mark chats as read", chat_id)?;
         let peer_ref = self.resolve_peer_ref_for_topics(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -165,7 +198,6 @@ impl App {
 
         let result = self
             .tg
-            .client
             .invoke(&request)
             .await
             .with_context(|| format!("Failed to fetch forum topics for chat {}", chat_id))?;
@@ -184,8 +216,12 @@ impl App {
                     read_max_id: topic.top_message,
                 };
 
-                match self.tg.client.invoke(&read_request).await {
+                match self.tg.invoke(&read_request).await {
                     Ok(_) => {
+                        self.get_store()
+                            .await?
+                            .mark_topic_read(chat_id, topic.id, topic.top_message)
+                            .await?;
                         count += 1;
                     }
                     Err(e) => {
@@ -198,3 +234,19 @@ impl App {
         Ok(count)
     }
 }
+
+/// RFC 3339 form of a Telegram Unix timestamp.
+pub(crate) fn format_date(ts: i32) -> String {
+    chrono::DateTime::from_timestamp(ts as i64, 0)
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Bare user/chat/channel ID of a raw peer, as used for chat IDs in the store.
+pub(crate) fn peer_bare_id(peer: &tl::enums::Peer) -> i64 {
+    match peer {
+        tl::enums::Peer::User(u) => u.user_id,
+        tl::enums::Peer::Chat(c) => c.chat_id,
+        tl::enums::Peer::Channel(c) => c.channel_id,
+    }
+}
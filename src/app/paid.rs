@@ -0,0 +1,105 @@
+//! Paid media: posts whose photos and videos unlock for a price in stars.
+
+use crate::app::App;
+use anyhow::{Context, Result};
+use grammers_client::types::Media;
+use grammers_tl_types as tl;
+
+pub(crate) struct PaidMedia {
+    /// Price in stars
+    pub stars: i64,
+    /// Only blurred previews are visible until someone pays
+    pub locked: bool,
+}
+
+/// The paid media of a raw message (None if it isn't a paid post).
+pub(crate) fn paid_media(raw: &tl::enums::Message) -> Option<PaidMedia> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    let Some(tl::enums::MessageMedia::PaidMedia(paid)) = &m.media else {
+        return None;
+    };
+    Some(PaidMedia {
+        stars: paid.stars_amount,
+        locked: paid
+            .extended_media
+            .iter()
+            .any(|m| matches!(m, tl::enums::MessageExtendedMedia::Preview(_))),
+    })
+}
+
+/// The first photo or video of an unlocked paid post, ready to download.
+pub(crate) fn unlocked_media(raw: &tl::enums::Message) -> Option<Media> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    let Some(tl::enums::MessageMedia::PaidMedia(paid)) = &m.media else {
+        return None;
+    };
+    paid.extended_media.iter().find_map(|m| match m {
+        tl::enums::MessageExtendedMedia::Media(m) => Media::from_raw(m.media.clone()),
+        tl::enums::MessageExtendedMedia::Preview(_) => None,
+    })
+}
+
+impl App {
+    /// Price and lock state of a paid post, fetched from Telegram.
+    pub(crate) async fn paid_media_status(&self, chat_id: i64, msg_id: i64) -> Result<PaidMedia> {
+        let msg = self.fetch_message(chat_id, msg_id).await?;
+        let paid = paid_media(&msg.raw)
+            .with_context(|| format!("Message {} in chat {} is not paid media", msg_id, chat_id))?;
+        self.get_store()
+            .await?
+            .set_paid_media(chat_id, msg_id, paid.stars)
+            .await?;
+        Ok(paid)
+    }
+
+    /// Pay for a paid post with stars. Returns the number of stars paid.
+    pub async fn unlock_paid_media(&self, chat_id: i64, msg_id: i64) -> Result<i64> {
+        crate::guard::check_chat("unlock paid media", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let invoice = tl::enums::InputInvoice::Message(tl::types::InputInvoiceMessage {
+            peer: peer_ref.into(),
+            msg_id: msg_id as i32,
+        });
+
+        let form = self
+            .tg
+            .invoke(&tl::functions::payments::GetPaymentForm {
+                invoice: invoice.clone(),
+                theme_params: None,
+            })
+            .await
+            .context(format!(
+                "Failed to get the payment form of message {} in chat {}",
+                msg_id, chat_id
+            ))?;
+        let tl::enums::payments::PaymentForm::Stars(form) = form else {
+            anyhow::bail!("Message {} can't be paid for with stars", msg_id);
+        };
+        let tl::enums::Invoice::Invoice(details) = &form.invoice;
+        let stars: i64 = details
+            .prices
+            .iter()
+            .map(|tl::enums::LabeledPrice::Price(p)| p.amount)
+            .sum();
+
+        let result = self
+            .tg
+            .invoke(&tl::functions::payments::SendStarsForm {
+                form_id: form.form_id,
+                invoice,
+            })
+            .await
+            .context(format!(
+                "Failed to pay {} stars for message {} in chat {}",
+                stars, msg_id, chat_id
+            ))?;
+        if let tl::enums::payments::PaymentResult::PaymentVerificationNeeded(v) = result {
+            anyhow::bail!("Telegram asks to verify this payment at {}", v.url);
+        }
+        Ok(stars)
+    }
+}
@@ -0,0 +1,204 @@
+//! Premium, Telegram Stars, and gifts (read-only views of the account).
+
+use crate::app::{format_date, peer_bare_id, App};
+use anyhow::{Context, Result};
+use grammers_tl_types as tl;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PremiumStatus {
+    pub premium: bool,
+    pub stars_balance: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StarsTransaction {
+    pub id: String,
+    /// Positive for incoming Stars, negative for spent ones
+    pub amount: i64,
+    pub date: String,
+    /// Who the Stars came from or went to (a peer ID or a store like "fragment")
+    pub peer: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub refund: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GiftInfo {
+    pub gift_id: i64,
+    pub title: Option<String>,
+    pub from_id: Option<i64>,
+    pub date: String,
+    /// Price paid by the sender
+    pub stars: Option<i64>,
+    /// Stars you'd get for converting the gift
+    pub convert_stars: Option<i64>,
+    /// Upgraded (collectible) gift
+    pub unique: bool,
+    /// Hidden from your profile
+    pub hidden: bool,
+    pub message: Option<String>,
+}
+
+/// Which transactions `stars_transactions` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StarsDirection {
+    #[default]
+    All,
+    Inbound,
+    Outbound,
+}
+
+impl App {
+    /// Whether the account has Premium, plus its Stars balance.
+    pub async fn premium_status(&self) -> Result<PremiumStatus> {
+        let users = self
+            .tg
+            .invoke(&tl::functions::users::GetUsers {
+                id: vec![tl::enums::InputUser::UserSelf],
+            })
+            .await
+            .context("Failed to get account info")?;
+        let premium = users.iter().any(|u| match u {
+            tl::enums::User::User(u) => u.is_self && u.premium,
+            _ => false,
+        });
+        Ok(PremiumStatus {
+            premium,
+            stars_balance: self.stars_balance().await?,
+        })
+    }
+
+    /// Current Stars balance (whole Stars).
+    pub async fn stars_balance(&self) -> Result<i64> {
+        let tl::enums::payments::StarsStatus::Status(status) = self
+            .tg
+            .invoke(&tl::functions::payments::GetStarsStatus {
+                ton: false,
+                peer: tl::enums::InputPeer::PeerSelf,
+            })
+            .await
+            .context("Failed to get Stars balance")?;
+        Ok(stars_amount(&status.balance))
+    }
+
+    /// One page of Stars transactions, newest first. Returns the transactions
+    /// and the offset for the next page, if any.
+    pub async fn stars_transactions(
+        &self,
+        direction: StarsDirection,
+        offset: Option<&str>,
+        limit: i32,
+    ) -> Result<(Vec<StarsTransaction>, Option<String>)> {
+        let tl::enums::payments::StarsStatus::Status(status) = self
+            .tg
+            .invoke(&tl::functions::payments::GetStarsTransactions {
+                inbound: direction == StarsDirection::Inbound,
+                outbound: direction == StarsDirection::Outbound,
+                ascending: false,
+                ton: false,
+                subscription_id: None,
+                peer: tl::enums::InputPeer::PeerSelf,
+                offset: offset.unwrap_or_default().to_string(),
+                limit,
+            })
+            .await
+            .context("Failed to get Stars transactions")?;
+
+        let transactions = status
+            .history
+            .unwrap_or_default()
+            .into_iter()
+            .map(
+                |tl::enums::StarsTransaction::Transaction(t)| StarsTransaction {
+                    id: t.id,
+                    amount: stars_amount(&t.amount),
+                    date: format_date(t.date),
+                    peer: transaction_peer(&t.peer),
+                    title: t.title,
+                    description: t.description,
+                    refund: t.refund,
+                },
+            )
+            .collect();
+        Ok((transactions, status.next_offset))
+    }
+
+    /// Gifts received by the account, as shown on its profile.
+    pub async fn list_gifts(&self, limit: i32) -> Result<Vec<GiftInfo>> {
+        let tl::enums::payments::SavedStarGifts::Gifts(saved) = self
+            .tg
+            .invoke(&tl::functions::payments::GetSavedStarGifts {
+                exclude_unsaved: false,
+                exclude_saved: false,
+                exclude_unlimited: false,
+                exclude_unique: false,
+                sort_by_value: false,
+                exclude_upgradable: false,
+                exclude_unupgradable: false,
+                peer_color_available: false,
+                exclude_hosted: false,
+                peer: tl::enums::InputPeer::PeerSelf,
+                collection_id: None,
+                offset: String::new(),
+                limit,
+            })
+            .await
+            .context("Failed to get gifts")?;
+
+        Ok(saved
+            .gifts
+            .into_iter()
+            .map(|tl::enums::SavedStarGift::Gift(g)| {
+                let (gift_id, title, stars, unique) = match &g.gift {
+                    tl::enums::StarGift::Gift(gift) => {
+                        (gift.id, gift.title.clone(), Some(gift.stars), false)
+                    }
+                    tl::enums::StarGift::Unique(gift) => (
+                        gift.id,
+                        Some(format!("{} #{}", gift.title, gift.num)),
+                        None,
+                        true,
+                    ),
+                };
+                GiftInfo {
+                    gift_id,
+                    title,
+                    from_id: g.from_id.as_ref().map(peer_bare_id),
+                    date: format_date(g.date),
+                    stars,
+                    convert_stars: g.convert_stars,
+                    unique,
+                    hidden: g.unsaved,
+                    message: g
+                        .message
+                        .map(|tl::enums::TextWithEntities::Entities(m)| m.text),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Whole Stars in an amount (fractional nanostars are dropped). Only the
+/// TON balance is counted in nanotons, and it isn't requested here.
+fn stars_amount(amount: &tl::enums::StarsAmount) -> i64 {
+    match amount {
+        tl::enums::StarsAmount::Amount(a) => a.amount,
+        tl::enums::StarsAmount::StarsTonAmount(a) => a.amount,
+    }
+}
+
+fn transaction_peer(peer: &tl::enums::StarsTransactionPeer) -> String {
+    use tl::enums::StarsTransactionPeer as P;
+    match peer {
+        P::Peer(p) => peer_bare_id(&p.peer).to_string(),
+        P::AppStore => "app_store".to_string(),
+        P::PlayMarket => "play_market".to_string(),
+        P::PremiumBot => "premium_bot".to_string(),
+        P::Fragment => "fragment".to_string(),
+        P::Ads => "ads".to_string(),
+        P::Api => "api".to_string(),
+        _ => "unknown".to_string(),
+    }
+}
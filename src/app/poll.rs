@@ -0,0 +1,57 @@
+//! Polls and their results, taken from `MessageMediaPoll`.
+
+use crate::store::{MessagePoll, PollOption};
+use chrono::{TimeZone, Utc};
+use grammers_tl_types as tl;
+
+/// The poll in a raw message (None if it isn't a poll).
+pub(crate) fn message_poll(chat_id: i64, raw: &tl::enums::Message) -> Option<MessagePoll> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    let Some(tl::enums::MessageMedia::Poll(media)) = &m.media else {
+        return None;
+    };
+    let tl::enums::Poll::Poll(poll) = &media.poll;
+    let tl::enums::PollResults::Results(results) = &media.results;
+    let voters: Vec<tl::types::PollAnswerVoters> = results
+        .results
+        .iter()
+        .flatten()
+        .map(|tl::enums::PollAnswerVoters::Voters(v)| v.clone())
+        .collect();
+
+    let options = poll
+        .answers
+        .iter()
+        .enumerate()
+        .map(|(index, tl::enums::PollAnswer::Answer(answer))| {
+            let tl::enums::TextWithEntities::Entities(text) = &answer.text;
+            let result = voters.iter().find(|v| v.option == answer.option);
+            PollOption {
+                index: index as i32,
+                text: text.text.clone(),
+                voters: result.map(|v| v.voters),
+                chosen: result.is_some_and(|v| v.chosen),
+                correct: result.is_some_and(|v| v.correct),
+            }
+        })
+        .collect();
+
+    let tl::enums::TextWithEntities::Entities(question) = &poll.question;
+    Some(MessagePoll {
+        chat_id,
+        msg_id: m.id as i64,
+        poll_id: poll.id,
+        question: question.text.clone(),
+        closed: poll.closed,
+        multiple_choice: poll.multiple_choice,
+        quiz: poll.quiz,
+        public_voters: poll.public_voters,
+        total_voters: results.total_voters,
+        close_date: poll
+            .close_date
+            .and_then(|t| Utc.timestamp_opt(t as i64, 0).single()),
+        options,
+    })
+}
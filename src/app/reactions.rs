@@ -0,0 +1,40 @@
+//! Reactions on messages, from synced messages and from the daemon's
+//! `updateMessageReactions` updates.
+
+use crate::store::MessageReaction;
+use grammers_tl_types as tl;
+
+/// The reactions of a raw message (None if Telegram sent none, which is
+/// not the same as an empty list: that clears them).
+pub(crate) fn message_reactions(raw: &tl::enums::Message) -> Option<Vec<MessageReaction>> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    m.reactions.as_ref().map(reaction_counts)
+}
+
+/// Reaction counts as stored, in Telegram's order.
+pub(crate) fn reaction_counts(reactions: &tl::enums::MessageReactions) -> Vec<MessageReaction> {
+    let tl::enums::MessageReactions::Reactions(r) = reactions;
+    r.results
+        .iter()
+        .filter_map(|result| {
+            let tl::enums::ReactionCount::Count(c) = result;
+            Some(MessageReaction {
+                reaction: reaction_label(&c.reaction)?,
+                count: c.count,
+                chosen: c.chosen_order.is_some(),
+            })
+        })
+        .collect()
+}
+
+/// The emoji itself, `custom:<document id>` or `paid`.
+fn reaction_label(reaction: &tl::enums::Reaction) -> Option<String> {
+    match reaction {
+        tl::enums::Reaction::Emoji(e) => Some(e.emoticon.clone()),
+        tl::enums::Reaction::CustomEmoji(e) => Some(format!("custom:{}", e.document_id)),
+        tl::enums::Reaction::Paid => Some("paid".to_string()),
+        tl::enums::Reaction::Empty => None,
+    }
+}
@@ -0,0 +1,155 @@
+//! Reporting chats and messages to Telegram moderation.
+//!
+//! Chats are reported with a fixed reason (`account.reportPeer`). Message
+//! reports (`messages.report`) are a dialog: Telegram answers with a list of
+//! options to choose from, possibly several levels deep, and may ask for a
+//! comment. Each level is answered by matching the option text against the
+//! reason's keywords.
+
+use crate::app::App;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use grammers_tl_types as tl;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportReason {
+    Spam,
+    Violence,
+    Pornography,
+    ChildAbuse,
+    Copyright,
+    Fake,
+    IllegalDrugs,
+    PersonalDetails,
+    GeoIrrelevant,
+    Other,
+}
+
+impl ReportReason {
+    fn to_tl(self) -> tl::enums::ReportReason {
+        use tl::enums::ReportReason as R;
+        match self {
+            ReportReason::Spam => R::InputReportReasonSpam,
+            ReportReason::Violence => R::InputReportReasonViolence,
+            ReportReason::Pornography => R::InputReportReasonPornography,
+            ReportReason::ChildAbuse => R::InputReportReasonChildAbuse,
+            ReportReason::Copyright => R::InputReportReasonCopyright,
+            ReportReason::Fake => R::InputReportReasonFake,
+            ReportReason::IllegalDrugs => R::InputReportReasonIllegalDrugs,
+            ReportReason::PersonalDetails => R::InputReportReasonPersonalDetails,
+            ReportReason::GeoIrrelevant => R::InputReportReasonGeoIrrelevant,
+            ReportReason::Other => R::InputReportReasonOther,
+        }
+    }
+
+    /// Words that identify this reason among the options Telegram offers.
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            ReportReason::Spam => &["spam", "scam"],
+            ReportReason::Violence => &["violence", "terror"],
+            ReportReason::Pornography => &["adult", "porn", "sexual"],
+            ReportReason::ChildAbuse => &["child"],
+            ReportReason::Copyright => &["copyright"],
+            ReportReason::Fake => &["fake", "impersonat"],
+            ReportReason::IllegalDrugs => &["drug", "illegal goods"],
+            ReportReason::PersonalDetails => &["personal"],
+            ReportReason::GeoIrrelevant => &["location", "geo"],
+            ReportReason::Other => &["other"],
+        }
+    }
+}
+
+impl App {
+    /// Report a chat or user.
+    pub async fn report_peer(
+        &self,
+        chat_id: i64,
+        reason: ReportReason,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        crate::guard::check_chat("report chats", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        self.tg
+            .invoke(&tl::functions::account::ReportPeer {
+                peer: peer_ref.into(),
+                reason: reason.to_tl(),
+                message: comment.unwrap_or_default().to_string(),
+            })
+            .await
+            .context(format!("Failed to report chat {}", chat_id))?;
+        Ok(())
+    }
+
+    /// Report messages, walking Telegram's option dialog. Returns the option
+    /// texts that were chosen.
+    pub async fn report_messages(
+        &self,
+        chat_id: i64,
+        msg_ids: &[i64],
+        reason: ReportReason,
+        comment: Option<&str>,
+    ) -> Result<Vec<String>> {
+        crate::guard::check_chat("report messages", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let peer: tl::enums::InputPeer = peer_ref.into();
+        let id: Vec<i32> = msg_ids.iter().map(|&id| id as i32).collect();
+
+        let mut option = Vec::new();
+        let mut message = String::new();
+        let mut chosen = Vec::new();
+        // Guard against a dialog that never ends
+        for _ in 0..8 {
+            let result = self
+                .tg
+                .invoke(&tl::functions::messages::Report {
+                    peer: peer.clone(),
+                    id: id.clone(),
+                    option: option.clone(),
+                    message: message.clone(),
+                })
+                .await
+                .context(format!("Failed to report messages in chat {}", chat_id))?;
+
+            match result {
+                tl::enums::ReportResult::Reported => return Ok(chosen),
+                tl::enums::ReportResult::ChooseOption(choose) => {
+                    let options: Vec<(String, Vec<u8>)> = choose
+                        .options
+                        .into_iter()
+                        .map(|tl::enums::MessageReportOption::Option(o)| (o.text, o.option))
+                        .collect();
+                    let picked = options.iter().find(|(text, _)| {
+                        let text = text.to_lowercase();
+                        reason.keywords().iter().any(|k| text.contains(k))
+                    });
+                    match picked {
+                        Some((text, next)) => {
+                            chosen.push(text.clone());
+                            option = next.clone();
+                        }
+                        None => anyhow::bail!(
+                            "Telegram asks \"{}\" and no option matches --reason {:?}. Options: {}",
+                            choose.title,
+                            reason,
+                            options
+                                .iter()
+                                .map(|(text, _)| text.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    }
+                }
+                tl::enums::ReportResult::AddComment(add) => {
+                    if comment.is_none() && !add.optional {
+                        anyhow::bail!(
+                            "Telegram requires a comment for this report; pass --comment"
+                        );
+                    }
+                    option = add.option;
+                    message = comment.unwrap_or_default().to_string();
+                }
+            }
+        }
+        anyhow::bail!("Telegram did not accept the report after several steps")
+    }
+}
@@ -0,0 +1,82 @@
+//! Recurring messages (`tgcli schedule`), sent by the daemon when their cron
+//! expression comes due. Unlike `send --schedule`, Telegram isn't involved
+//! until the message is sent.
+
+use crate::app::send::TextOptions;
+use crate::app::App;
+use crate::cron::Cron;
+use crate::store::Schedule;
+use anyhow::Result;
+use chrono::{Duration, Local, Utc};
+
+/// Runs missed by more than this (daemon stopped, machine asleep) are
+/// skipped rather than sent late.
+const MAX_LATENESS_MINUTES: i64 = 10;
+
+impl App {
+    /// Send a recurring message now and count the run. Returns the message ID.
+    pub async fn send_schedule(&mut self, schedule: &Schedule) -> Result<i64> {
+        let msg_id = self
+            .send_text(
+                schedule.chat_id,
+                &schedule.text,
+                "none",
+                TextOptions::default(),
+            )
+            .await?;
+        self.get_store()
+            .await?
+            .record_schedule_run(schedule.id, Utc::now(), true)
+            .await?;
+        Ok(msg_id)
+    }
+
+    /// Send the recurring messages that came due since their last run.
+    /// Returns the chat and message IDs of what was sent.
+    pub async fn run_due_schedules(&mut self) -> Result<Vec<(i64, i64)>> {
+        let store = self.get_store().await?;
+        let now = Utc::now();
+        let mut sent = Vec::new();
+        for schedule in store.list_schedules().await? {
+            let cron: Cron = match schedule.cron.parse() {
+                Ok(cron) => cron,
+                Err(e) => {
+                    log::error!(
+                        "Schedule {} has an invalid cron expression: {:#}",
+                        schedule.id,
+                        e
+                    );
+                    continue;
+                }
+            };
+            let since = schedule.last_run.unwrap_or(schedule.created_at);
+            let Some(due) = cron.next_after(since.with_timezone(&Local)) else {
+                continue;
+            };
+            let due = due.with_timezone(&Utc);
+            if due > now {
+                continue;
+            }
+            if now - due > Duration::minutes(MAX_LATENESS_MINUTES) {
+                log::warn!(
+                    "Skipping schedule {} run due at {} (too late)",
+                    schedule.id,
+                    due.to_rfc3339()
+                );
+                store.record_schedule_run(schedule.id, now, false).await?;
+                continue;
+            }
+            let mark = crate::guard::mark();
+            match self.send_schedule(&schedule).await {
+                Ok(msg_id) => sent.push((schedule.chat_id, msg_id)),
+                Err(e) => {
+                    crate::guard::note_failure(mark, &e);
+                    log::error!(chat_id = schedule.chat_id; "Schedule {} failed: {:#}", schedule.id, e);
+                    // Don't retry every tick; wait for the next run
+                    store.record_schedule_run(schedule.id, now, false).await?;
+                }
+            }
+        }
+        Ok(sent)
+    }
+}
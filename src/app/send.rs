@@ -1,6 +1,10 @@
-use crate::app::App;
+use crate::app::details::record_message_details;
+use crate::app::paid::{paid_media, unlocked_media};
+use crate::app::service::service_action;
+use crate::app::{peer_bare_id, App};
 use crate::error::TgErrorContext;
-use crate::store::UpsertMessageParams;
+use crate::out::progress::Progress;
+use crate::store::{MessagePoll, PollOption, UpsertMessageParams};
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use chrono::Utc;
@@ -14,6 +18,9 @@ use std::path::Path;
 use std::time::Duration;
 use tl::enums::SendMessageAction;
 
+/// Max message IDs per messages.translateText request.
+const TRANSLATE_BATCH_SIZE: usize = 20;
+
 /// Parse message text according to parse_mode, returning (text, entities).
 /// parse_mode: "markdown", "html", or anything else for plain text.
 fn apply_parse_mode(
@@ -43,6 +50,18 @@ fn apply_parse_mode(
     }
 }
 
+/// Options for outgoing text messages (`send --preview`, `--as-channel`).
+/// The default sends no link preview, as yourself.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextOptions {
+    /// Let Telegram attach a preview of the first link in the text
+    pub preview: bool,
+    /// Show the preview above the text instead of below it
+    pub preview_above: bool,
+    /// Post as this channel (one you admin, or the group's linked channel)
+    pub send_as: Option<i64>,
+}
+
 /// Result from searching chats via Telegram API.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct SearchChatResult {
@@ -67,25 +86,52 @@ fn decode_file_id(file_id: &str) -> Result<(i64, i64, Vec<u8>)> {
 
 impl App {
     /// Send a text message to a chat by ID, returns the message ID.
-    pub async fn send_text(&mut self, chat_id: i64, text: &str, parse_mode: &str) -> Result<i64> {
+    pub async fn send_text(
+        &mut self,
+        chat_id: i64,
+        text: &str,
+        parse_mode: &str,
+        opts: TextOptions,
+    ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
-        let input_msg = match parse_mode {
-            "markdown" => InputMessage::new().markdown(text),
-            "html" => InputMessage::new().html(text),
-            _ => InputMessage::new().text(text),
+        let input_peer: tl::enums::InputPeer = peer_ref.into();
+        let send_as = self.send_as_peer(&opts).await?;
+
+        let random_id: i64 = rand::rng().random();
+        let (message_text, entities) = apply_parse_mode(text, parse_mode);
+
+        let request = tl::functions::messages::SendMessage {
+            no_webpage: !opts.preview,
+            silent: false,
+            background: false,
+            clear_draft: false,
+            noforwards: false,
+            update_stickersets_order: false,
+            invert_media: opts.preview_above,
+            allow_paid_floodskip: false,
+            peer: input_peer,
+            reply_to: None,
+            message: message_text,
+            random_id,
+            reply_markup: None,
+            entities,
+            schedule_date: None,
+            send_as,
+            quick_reply_shortcut: None,
+            effect: None,
+            allow_paid_stars: None,
+            suggested_post: None,
         };
-        let msg = self
-            .tg
-            .client
-            .send_message(peer_ref, input_msg)
-            .await
-            .context_send(chat_id)?;
+
+        let updates = self.tg.invoke(&request).await.context_send(chat_id)?;
+        let msg_id = Self::extract_message_id_from_updates(&updates)?;
 
         let now = Utc::now();
         self.get_store()
             .await?
             .upsert_message(UpsertMessageParams {
-                id: msg.id() as i64,
+                id: msg_id,
                 chat_id,
                 sender_id: 0,
                 ts: now,
@@ -105,7 +151,7 @@ impl App {
             .upsert_chat(chat_id, "user", "", None, Some(now), false, None, false)
             .await?;
 
-        Ok(msg.id() as i64)
+        Ok(msg_id)
     }
 
     /// Send a scheduled text message to a chat by ID, returns the message ID.
@@ -116,22 +162,25 @@ impl App {
         text: &str,
         schedule_time: chrono::DateTime<Utc>,
         parse_mode: &str,
+        opts: TextOptions,
     ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
+        let send_as = self.send_as_peer(&opts).await?;
 
         let random_id: i64 = rand::rng().random();
         let schedule_date = schedule_time.timestamp() as i32;
         let (message_text, entities) = apply_parse_mode(text, parse_mode);
 
         let request = tl::functions::messages::SendMessage {
-            no_webpage: true,
+            no_webpage: !opts.preview,
             silent: false,
             background: false,
             clear_draft: false,
             noforwards: false,
             update_stickersets_order: false,
-            invert_media: false,
+            invert_media: opts.preview_above,
             allow_paid_floodskip: false,
             peer: input_peer,
             reply_to: None,
@@ -140,19 +189,14 @@ impl App {
             reply_markup: None,
             entities,
             schedule_date: Some(schedule_date),
-            send_as: None,
+            send_as,
             quick_reply_shortcut: None,
             effect: None,
             allow_paid_stars: None,
             suggested_post: None,
         };
 
-        let updates = self
-            .tg
-            .client
-            .invoke(&request)
-            .await
-            .context_send(chat_id)?;
+        let updates = self.tg.invoke(&request).await.context_send(chat_id)?;
         let msg_id = Self::extract_message_id_from_updates(&updates)?;
 
         // Note: We don't store scheduled messages in the local DB since they haven't been sent yet.
@@ -168,21 +212,24 @@ impl App {
         text: &str,
         reply_to_msg_id: i32,
         parse_mode: &str,
+        opts: TextOptions,
     ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
+        let send_as = self.send_as_peer(&opts).await?;
 
         let random_id: i64 = rand::rng().random();
         let (message_text, entities) = apply_parse_mode(text, parse_mode);
 
         let request = tl::functions::messages::SendMessage {
-            no_webpage: true,
+            no_webpage: !opts.preview,
             silent: false,
             background: false,
             clear_draft: false,
             noforwards: false,
             update_stickersets_order: false,
-            invert_media: false,
+            invert_media: opts.preview_above,
             allow_paid_floodskip: false,
             peer: input_peer,
             reply_to: Some(
@@ -203,19 +250,14 @@ impl App {
             reply_markup: None,
             entities,
             schedule_date: None,
-            send_as: None,
+            send_as,
             quick_reply_shortcut: None,
             effect: None,
             allow_paid_stars: None,
             suggested_post: None,
         };
 
-        let updates = self
-            .tg
-            .client
-            .invoke(&request)
-            .await
-            .context_send(chat_id)?;
+        let updates = self.tg.invoke(&request).await.context_send(chat_id)?;
         let msg_id = Self::extract_message_id_from_updates(&updates)?;
 
         let now = Utc::now();
@@ -253,21 +295,24 @@ impl App {
         topic_id: i32,
         text: &str,
         parse_mode: &str,
+        opts: TextOptions,
     ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
+        let send_as = self.send_as_peer(&opts).await?;
 
         let random_id: i64 = rand::rng().random();
         let (message_text, entities) = apply_parse_mode(text, parse_mode);
 
         let request = tl::functions::messages::SendMessage {
-            no_webpage: true,
+            no_webpage: !opts.preview,
             silent: false,
             background: false,
             clear_draft: false,
             noforwards: false,
             update_stickersets_order: false,
-            invert_media: false,
+            invert_media: opts.preview_above,
             allow_paid_floodskip: false,
             peer: input_peer,
             reply_to: Some(
@@ -288,19 +333,14 @@ impl App {
             reply_markup: None,
             entities,
             schedule_date: None,
-            send_as: None,
+            send_as,
             quick_reply_shortcut: None,
             effect: None,
             allow_paid_stars: None,
             suggested_post: None,
         };
 
-        let updates = self
-            .tg
-            .client
-            .invoke(&request)
-            .await
-            .context_send(chat_id)?;
+        let updates = self.tg.invoke(&request).await.context_send(chat_id)?;
 
         // Extract message ID from updates
         let msg_id = Self::extract_message_id_from_updates(&updates)?;
@@ -333,7 +373,15 @@ impl App {
     }
 
     /// Extract message ID from Updates response
-    fn extract_message_id_from_updates(updates: &tl::enums::Updates) -> Result<i64> {
+    /// The channel to post as (`TextOptions::send_as`), as an input peer.
+    async fn send_as_peer(&self, opts: &TextOptions) -> Result<Option<tl::enums::InputPeer>> {
+        match opts.send_as {
+            Some(channel_id) => Ok(Some(self.resolve_peer_ref(channel_id).await?.into())),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn extract_message_id_from_updates(updates: &tl::enums::Updates) -> Result<i64> {
         match updates {
             tl::enums::Updates::Updates(u) => {
                 for update in &u.updates {
@@ -373,6 +421,7 @@ impl App {
         silent: bool,
         pm_oneside: bool,
     ) -> Result<()> {
+        crate::guard::check_chat("pin messages", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -385,7 +434,6 @@ impl App {
         };
 
         self.tg
-            .client
             .invoke(&request)
             .await
             .context_pin(chat_id, msg_id, true)?;
@@ -394,6 +442,7 @@ impl App {
 
     /// Unpin a message in a chat.
     pub async fn unpin_message(&self, chat_id: i64, msg_id: i64, pm_oneside: bool) -> Result<()> {
+        crate::guard::check_chat("unpin messages", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -405,7 +454,7 @@ impl App {
             id: msg_id as i32,
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to unpin message {} in chat {}",
             msg_id, chat_id
         ))?;
@@ -414,6 +463,7 @@ impl App {
 
     /// Edit a message's text.
     pub async fn edit_message(&self, chat_id: i64, msg_id: i64, new_text: &str) -> Result<()> {
+        crate::guard::check_chat("edit messages", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -430,7 +480,7 @@ impl App {
             quick_reply_shortcut_id: None,
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to edit message {} in chat {}",
             msg_id, chat_id
         ))?;
@@ -438,7 +488,7 @@ impl App {
         // Update local store
         self.get_store()
             .await?
-            .update_message_text(chat_id, msg_id, new_text)
+            .update_message_text(chat_id, msg_id, new_text, Utc::now())
             .await?;
 
         Ok(())
@@ -453,6 +503,7 @@ impl App {
         to_chat_id: i64,
         to_topic_id: Option<i32>,
     ) -> Result<i64> {
+        crate::guard::check_send(to_chat_id)?;
         let from_peer = self.resolve_peer_ref(from_chat_id).await?;
         let to_peer = self.resolve_peer_ref(to_chat_id).await?;
 
@@ -483,7 +534,7 @@ impl App {
             suggested_post: None,
         };
 
-        let updates = self.tg.client.invoke(&request).await.context(format!(
+        let updates = self.tg.invoke(&request).await.context(format!(
             "Failed to forward message {} from chat {} to chat {}",
             msg_id, from_chat_id, to_chat_id
         ))?;
@@ -494,35 +545,82 @@ impl App {
 
     /// Mark a chat (or topic in a forum) as read.
     pub async fn mark_read(&self, chat_id: i64, topic_id: Option<i32>) -> Result<()> {
-        let peer_ref = self.resolve_peer_ref(chat_id).await?;
-
         if let Some(tid) = topic_id {
-            // For forum topics, use ReadDiscussion to mark the topic as read
-            let input_peer: tl::enums::InputPeer = peer_ref.into();
-            let request = tl::functions::messages::ReadDiscussion {
-                peer: input_peer,
-                msg_id: tid,
-                read_max_id: i32::MAX,
-            };
-            self.tg.client.invoke(&request).await.context(format!(
-                "Failed to mark topic {} in chat {} as read",
-                tid, chat_id
-            ))?;
-        } else {
-            self.tg
-                .client
-                .mark_as_read(peer_ref)
-                .await
-                .context(format!("Failed to mark chat {} as read", chat_id))?;
+            self.mark_topic_read_up_to(chat_id, tid, None).await?;
+            return Ok(());
         }
+        crate::guard::check_chat("mark chats as read", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        self.tg
+            .client
+            .mark_as_read(peer_ref)
+            .await
+            .context(format!("Failed to mark chat {} as read", chat_id))?;
         Ok(())
     }
 
+    /// Mark a forum topic as read up to `max_id` (default: its latest
+    /// message, looked up fresh). Returns the message ID read up to.
+    pub async fn mark_topic_read_up_to(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+        max_id: Option<i64>,
+    ) -> Result<i32> {
+        crate::guard::check_chat("mark chats as read", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let input_peer: tl::enums::InputPeer = peer_ref.into();
+        let store = self.get_store().await?;
+
+        let read_max_id = match max_id {
+            Some(id) => id as i32,
+            None => {
+                let tl::enums::messages::ForumTopics::Topics(result) = self
+                    .tg
+                    .invoke(&tl::functions::messages::GetForumTopicsById {
+                        peer: input_peer.clone(),
+                        topics: vec![topic_id],
+                    })
+                    .await
+                    .with_context(|| {
+                        format!("Failed to get topic {} in chat {}", topic_id, chat_id)
+                    })?;
+                let top_message = result.topics.iter().find_map(|t| match t {
+                    tl::enums::ForumTopic::Topic(t) if t.id == topic_id => Some(t.top_message),
+                    _ => None,
+                });
+                let Some(top_message) = top_message else {
+                    anyhow::bail!("Topic {} not found in chat {}", topic_id, chat_id);
+                };
+                store
+                    .set_topic_top_message(chat_id, topic_id, top_message)
+                    .await?;
+                top_message
+            }
+        };
+
+        // For forum topics, use ReadDiscussion to mark the topic as read
+        let request = tl::functions::messages::ReadDiscussion {
+            peer: input_peer,
+            msg_id: topic_id,
+            read_max_id,
+        };
+        self.tg.invoke(&request).await.context(format!(
+            "Failed to mark topic {} in chat {} as read",
+            topic_id, chat_id
+        ))?;
+        store
+            .mark_topic_read(chat_id, topic_id, read_max_id)
+            .await?;
+        Ok(read_max_id)
+    }
+
     /// Delete messages from a chat.
     /// Returns the number of affected messages.
     /// Note: revoke is effectively always true (grammers hardcodes it).
     /// Delete messages from a chat. Always deletes for everyone (revoke=true).
     pub async fn delete_messages(&self, chat_id: i64, msg_ids: &[i64]) -> Result<usize> {
+        crate::guard::check_chat("delete messages", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         // grammers expects i32 message IDs
         let ids: Vec<i32> = msg_ids.iter().map(|&id| id as i32).collect();
@@ -539,8 +637,113 @@ impl App {
         Ok(affected)
     }
 
+    /// Clear a chat's history on Telegram, either just for us or for every
+    /// participant (`for_everyone`). With `before`, only messages older than
+    /// that are deleted (not supported for supergroups and channels).
+    pub async fn clear_history(
+        &self,
+        chat_id: i64,
+        for_everyone: bool,
+        before: Option<chrono::DateTime<Utc>>,
+    ) -> Result<()> {
+        crate::guard::check_chat("clear chat history", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let input_peer: tl::enums::InputPeer = peer_ref.into();
+
+        if let tl::enums::InputPeer::Channel(channel) = &input_peer {
+            if before.is_some() {
+                anyhow::bail!(
+                    "--keep-days is not supported for supergroups and channels (chat {})",
+                    chat_id
+                );
+            }
+            self.tg
+                .invoke(&tl::functions::channels::DeleteHistory {
+                    for_everyone,
+                    channel: tl::enums::InputChannel::Channel(tl::types::InputChannel {
+                        channel_id: channel.channel_id,
+                        access_hash: channel.access_hash,
+                    }),
+                    max_id: 0,
+                })
+                .await
+                .context(format!("Failed to clear history of chat {}", chat_id))?;
+            return Ok(());
+        }
+
+        self.delete_history(
+            input_peer,
+            !for_everyone,
+            for_everyone,
+            before.map(|t| t.timestamp() as i32),
+        )
+        .await
+        .context(format!("Failed to clear history of chat {}", chat_id))
+    }
+
+    /// `messages.deleteHistory` for users and basic groups.
+    async fn delete_history(
+        &self,
+        peer: tl::enums::InputPeer,
+        just_clear: bool,
+        revoke: bool,
+        max_date: Option<i32>,
+    ) -> Result<()> {
+        // Telegram deletes in batches and returns a non-zero offset until done
+        loop {
+            let tl::enums::messages::AffectedHistory::History(affected) = self
+                .tg
+                .invoke(&tl::functions::messages::DeleteHistory {
+                    just_clear,
+                    revoke,
+                    peer: peer.clone(),
+                    max_id: 0,
+                    min_date: None,
+                    max_date,
+                })
+                .await?;
+            if affected.offset <= 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Delete a chat on Telegram. Private chats lose their history for both
+    /// sides; groups and channels are left and dropped from the chat list.
+    pub async fn delete_dialog(&self, chat_id: i64) -> Result<()> {
+        crate::guard::check_chat("delete chats", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let input_peer: tl::enums::InputPeer = peer_ref.into();
+
+        match input_peer {
+            tl::enums::InputPeer::User(_)
+            | tl::enums::InputPeer::UserFromMessage(_)
+            | tl::enums::InputPeer::PeerSelf => self
+                .delete_history(input_peer, false, true, None)
+                .await
+                .context(format!("Failed to delete chat {}", chat_id)),
+            tl::enums::InputPeer::Chat(ref ch) => {
+                // Leave first; the dialog itself is removed by deleting the history
+                self.tg
+                    .invoke(&tl::functions::messages::DeleteChatUser {
+                        revoke_history: true,
+                        chat_id: ch.chat_id,
+                        user_id: tl::enums::InputUser::UserSelf,
+                    })
+                    .await
+                    .context(format!("Failed to leave chat {}", chat_id))?;
+                self.delete_history(input_peer, false, false, None)
+                    .await
+                    .context(format!("Failed to delete chat {}", chat_id))
+            }
+            tl::enums::InputPeer::Channel(_) => self.leave_chat(chat_id).await,
+            _ => anyhow::bail!("Cannot delete chat {} from Telegram", chat_id),
+        }
+    }
+
     /// Send a sticker to a chat by ID, returns the message ID.
     pub async fn send_sticker(&mut self, chat_id: i64, sticker_file_id: &str) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
         // Decode the file_id to get document components
@@ -599,14 +802,22 @@ impl App {
     }
 
     /// Send a photo to a chat by ID, returns the message ID.
-    pub async fn send_photo(&mut self, chat_id: i64, path: &Path, caption: &str) -> Result<i64> {
+    pub async fn send_photo(
+        &mut self,
+        chat_id: i64,
+        path: &Path,
+        caption: &str,
+        show_progress: bool,
+    ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
         // Upload the file
+        let mut upload = crate::tg::transfer::open_upload(path, None, show_progress).await?;
         let uploaded = self
             .tg
             .client
-            .upload_file(path)
+            .upload_stream(&mut upload.reader, upload.size, upload.name)
             .await
             .context(format!("Failed to upload photo '{}'", path.display()))?;
 
@@ -646,14 +857,22 @@ impl App {
     }
 
     /// Send a video to a chat by ID, returns the message ID.
-    pub async fn send_video(&mut self, chat_id: i64, path: &Path, caption: &str) -> Result<i64> {
+    pub async fn send_video(
+        &mut self,
+        chat_id: i64,
+        path: &Path,
+        caption: &str,
+        show_progress: bool,
+    ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
         // Upload the file
+        let mut upload = crate::tg::transfer::open_upload(path, None, show_progress).await?;
         let uploaded = self
             .tg
             .client
-            .upload_file(path)
+            .upload_stream(&mut upload.reader, upload.size, upload.name)
             .await
             .context(format!("Failed to upload video '{}'", path.display()))?;
 
@@ -705,17 +924,41 @@ impl App {
     }
 
     /// Send a file as document to a chat by ID, returns the message ID.
-    /// Preserves the original filename.
-    pub async fn send_file(&mut self, chat_id: i64, path: &Path, caption: &str) -> Result<i64> {
+    /// Preserves the original filename unless `filename` is given. A path of
+    /// `-` uploads stdin, which requires `filename`.
+    pub async fn send_file(
+        &mut self,
+        chat_id: i64,
+        path: &Path,
+        filename: Option<&str>,
+        caption: &str,
+        show_progress: bool,
+    ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
         // Upload the file
+        let from_stdin = path == Path::new("-");
+        let mut upload = if from_stdin {
+            let name = filename.context("--filename is required when sending stdin")?;
+            crate::tg::transfer::spool_stdin(name, show_progress)
+                .await
+                .context("Failed to read stdin")?
+        } else {
+            crate::tg::transfer::open_upload(path, filename, show_progress).await?
+        };
         let uploaded = self
             .tg
             .client
-            .upload_file(path)
+            .upload_stream(&mut upload.reader, upload.size, upload.name)
             .await
-            .context(format!("Failed to upload file '{}'", path.display()))?;
+            .with_context(|| {
+                if from_stdin {
+                    "Failed to upload from stdin".to_string()
+                } else {
+                    format!("Failed to upload file '{}'", path.display())
+                }
+            })?;
 
         // Send as document (grammers automatically preserves filename)
         let msg = self
@@ -740,7 +983,7 @@ impl App {
                 from_me: true,
                 text: caption.to_string(),
                 media_type: Some("document".to_string()),
-                media_path: Some(path.to_string_lossy().to_string()),
+                media_path: (!from_stdin).then(|| path.to_string_lossy().to_string()),
                 reply_to_id: None,
                 topic_id: None,
             })
@@ -757,14 +1000,22 @@ impl App {
 
     /// Send an audio file as a voice message to a chat by ID, returns the message ID.
     /// Voice messages play inline in Telegram clients.
-    pub async fn send_voice(&mut self, chat_id: i64, path: &Path, caption: &str) -> Result<i64> {
+    pub async fn send_voice(
+        &mut self,
+        chat_id: i64,
+        path: &Path,
+        caption: &str,
+        show_progress: bool,
+    ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
         // Upload the file
+        let mut upload = crate::tg::transfer::open_upload(path, None, show_progress).await?;
         let uploaded = self
             .tg
             .client
-            .upload_file(path)
+            .upload_stream(&mut upload.reader, upload.size, upload.name)
             .await
             .context(format!("Failed to upload voice file '{}'", path.display()))?;
 
@@ -821,6 +1072,7 @@ impl App {
         emoji: &str,
         remove: bool,
     ) -> Result<()> {
+        crate::guard::check_chat("send reactions", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -842,7 +1094,7 @@ impl App {
             reaction,
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to {} reaction {} on message {} in chat {}",
             if remove { "remove" } else { "add" },
             emoji,
@@ -853,9 +1105,42 @@ impl App {
         Ok(())
     }
 
+    /// Translate messages with Telegram's translation service (messages.translateText).
+    /// Returns the translated texts in the same order as `msg_ids`.
+    pub async fn translate_messages(
+        &self,
+        chat_id: i64,
+        msg_ids: &[i64],
+        to_lang: &str,
+    ) -> Result<Vec<String>> {
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let input_peer: tl::enums::InputPeer = peer_ref.into();
+
+        let mut translations = Vec::with_capacity(msg_ids.len());
+        // The server caps the number of IDs per request
+        for chunk in msg_ids.chunks(TRANSLATE_BATCH_SIZE) {
+            let request = tl::functions::messages::TranslateText {
+                peer: Some(input_peer.clone()),
+                id: Some(chunk.iter().map(|&id| id as i32).collect()),
+                text: None,
+                to_lang: to_lang.to_string(),
+            };
+            let tl::enums::messages::TranslatedText::TranslateResult(result) =
+                self.tg.invoke(&request).await.context(format!(
+                    "Failed to translate messages in chat {} to '{}'",
+                    chat_id, to_lang
+                ))?;
+            translations.extend(result.result.into_iter().map(|t| {
+                let tl::enums::TextWithEntities::Entities(t) = t;
+                t.text
+            }));
+        }
+        Ok(translations)
+    }
+
     /// Resolve a chat ID to a PeerRef we can use for API calls.
     /// Iterates dialogs to find the matching peer.
-    async fn resolve_peer_ref(&self, chat_id: i64) -> Result<PeerRef> {
+    pub(super) async fn resolve_peer_ref(&self, chat_id: i64) -> Result<PeerRef> {
         let mut dialogs = self.tg.client.iter_dialogs();
         while let Some(dialog) = dialogs.next().await? {
             let peer = dialog.peer();
@@ -873,7 +1158,6 @@ impl App {
     /// Fetches messages older than `offset_id` (going backwards in time).
     /// If `offset_id` is None, fetches from the latest messages.
     /// Returns the number of new messages fetched and stored.
-    #[allow(dead_code)]
     pub async fn backfill_messages(
         &self,
         chat_id: i64,
@@ -897,7 +1181,8 @@ impl App {
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
         // Check if this chat is a forum
-        let chat = self.get_store().await?.get_chat(chat_id).await?;
+        let store = self.get_store().await?;
+        let chat = store.get_chat(chat_id).await?;
         let is_forum = chat.map(|c| c.is_forum).unwrap_or(false);
 
         let mut message_iter = self.tg.client.iter_messages(peer_ref);
@@ -907,13 +1192,7 @@ impl App {
             message_iter = message_iter.offset_id(oid as i32);
         }
 
-        // Progress tracking
-        let progress_interval = std::time::Duration::from_millis(500);
-        let mut last_progress_time = std::time::Instant::now();
-
-        if show_progress {
-            eprint!("\rFetching... 0/{} messages", limit);
-        }
+        let bar = Progress::new(show_progress).items(limit as u64, "messages");
 
         let mut count = 0;
         while let Some(msg) = message_iter.next().await? {
@@ -934,12 +1213,15 @@ impl App {
 
             let sender_id = msg.sender().map(|s| s.id().bare_id()).unwrap_or(0);
             let from_me = msg.outgoing();
-            let text = msg.text().to_string();
+            let service = service_action(&msg.raw);
+            let text = match &service {
+                Some(action) => action.text.clone(),
+                None => msg.text().to_string(),
+            };
             let reply_to_id = msg.reply_to_message_id().map(|id| id as i64);
             let media_type = msg.media().map(|_| "media".to_string());
 
-            self.get_store()
-                .await?
+            store
                 .upsert_message(UpsertMessageParams {
                     id: msg.id() as i64,
                     chat_id,
@@ -954,23 +1236,220 @@ impl App {
                     topic_id: msg_topic_id,
                 })
                 .await?;
+            record_message_details(&store, chat_id, msg.id() as i64, &msg.raw).await?;
             count += 1;
-
-            // Show progress periodically
-            if show_progress && last_progress_time.elapsed() >= progress_interval {
-                eprint!("\rFetching... {}/{} messages", count, limit);
-                last_progress_time = std::time::Instant::now();
-            }
-        }
-
-        if show_progress {
-            // Clear progress line
-            eprint!("\r\x1b[K");
+            bar.inc(1);
         }
+        bar.finish_and_clear();
 
         Ok(count)
     }
 
+    /// Comment on a channel post: find the post's copy in the linked
+    /// discussion group and reply to it there. Returns the discussion
+    /// group's chat ID and the new message ID.
+    pub async fn send_comment(
+        &mut self,
+        channel_id: i64,
+        post_id: i64,
+        text: &str,
+        parse_mode: &str,
+        opts: TextOptions,
+    ) -> Result<(i64, i64)> {
+        crate::guard::check_send(channel_id)?;
+        let peer_ref = self.resolve_peer_ref(channel_id).await?;
+        let tl::enums::messages::DiscussionMessage::Message(discussion) = self
+            .tg
+            .invoke(&tl::functions::messages::GetDiscussionMessage {
+                peer: peer_ref.into(),
+                msg_id: post_id as i32,
+            })
+            .await
+            .with_context(|| {
+                format!(
+                    "Post {} in channel {} has no comments section",
+                    post_id, channel_id
+                )
+            })?;
+
+        // The thread starts at the post's copy in the discussion group
+        let (group_id, thread_id) = discussion
+            .messages
+            .iter()
+            .find_map(|raw| match raw {
+                tl::enums::Message::Message(m) => Some((peer_bare_id(&m.peer_id), m.id)),
+                _ => None,
+            })
+            .with_context(|| format!("No discussion thread found for post {}", post_id))?;
+        // The group may not be in our dialogs, so use the access hash sent along
+        let group_peer = discussion
+            .chats
+            .iter()
+            .find_map(|chat| match chat {
+                tl::enums::Chat::Channel(c) if c.id == group_id => {
+                    Some(tl::enums::InputPeer::Channel(tl::types::InputPeerChannel {
+                        channel_id: c.id,
+                        access_hash: c.access_hash.unwrap_or(0),
+                    }))
+                }
+                _ => None,
+            })
+            .with_context(|| format!("Discussion group {} not found", group_id))?;
+        let send_as = self.send_as_peer(&opts).await?;
+
+        let (message_text, entities) = apply_parse_mode(text, parse_mode);
+        let request = tl::functions::messages::SendMessage {
+            no_webpage: !opts.preview,
+            silent: false,
+            background: false,
+            clear_draft: false,
+            noforwards: false,
+            update_stickersets_order: false,
+            invert_media: opts.preview_above,
+            allow_paid_floodskip: false,
+            peer: group_peer,
+            reply_to: Some(
+                tl::types::InputReplyToMessage {
+                    reply_to_msg_id: thread_id,
+                    top_msg_id: None,
+                    reply_to_peer_id: None,
+                    quote_text: None,
+                    quote_entities: None,
+                    quote_offset: None,
+                    monoforum_peer_id: None,
+                    todo_item_id: None,
+                }
+                .into(),
+            ),
+            message: message_text,
+            random_id: rand::rng().random(),
+            reply_markup: None,
+            entities,
+            schedule_date: None,
+            send_as,
+            quick_reply_shortcut: None,
+            effect: None,
+            allow_paid_stars: None,
+            suggested_post: None,
+        };
+        let updates = self.tg.invoke(&request).await.context_send(group_id)?;
+        let msg_id = Self::extract_message_id_from_updates(&updates)?;
+
+        let store = self.get_store().await?;
+        store
+            .upsert_message(UpsertMessageParams {
+                id: msg_id,
+                chat_id: group_id,
+                sender_id: 0,
+                ts: Utc::now(),
+                edit_ts: None,
+                from_me: true,
+                text: text.to_string(),
+                media_type: None,
+                media_path: None,
+                reply_to_id: Some(thread_id as i64),
+                topic_id: None,
+            })
+            .await?;
+        // Listed by `messages comments` along with the fetched ones
+        store.set_thread_root(group_id, msg_id, post_id).await?;
+
+        Ok((group_id, msg_id))
+    }
+
+    /// Fetch the comments on a channel post from its linked discussion group
+    /// (`messages.getReplies`) and store them with `thread_root_id` set to the
+    /// post. Returns the discussion group's chat ID (None if there were no
+    /// comments) and the number of comments stored.
+    pub async fn fetch_comments(
+        &self,
+        chat_id: i64,
+        post_id: i64,
+        limit: usize,
+    ) -> Result<(Option<i64>, usize)> {
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let input_peer: tl::enums::InputPeer = peer_ref.into();
+        let store = self.get_store().await?;
+
+        let mut group_id = None;
+        let mut count = 0;
+        let mut offset_id = 0;
+        while count < limit {
+            let result = self
+                .tg
+                .invoke(&tl::functions::messages::GetReplies {
+                    peer: input_peer.clone(),
+                    msg_id: post_id as i32,
+                    offset_id,
+                    offset_date: 0,
+                    add_offset: 0,
+                    limit: (limit - count).min(100) as i32,
+                    max_id: 0,
+                    min_id: 0,
+                    hash: 0,
+                })
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to fetch comments for message {} in chat {}",
+                        post_id, chat_id
+                    )
+                })?;
+            let messages = match result {
+                tl::enums::messages::Messages::Messages(m) => m.messages,
+                tl::enums::messages::Messages::Slice(m) => m.messages,
+                tl::enums::messages::Messages::ChannelMessages(m) => m.messages,
+                tl::enums::messages::Messages::NotModified(_) => Vec::new(),
+            };
+            let Some(last) = messages.last() else {
+                break;
+            };
+            offset_id = match last {
+                tl::enums::Message::Message(m) => m.id,
+                tl::enums::Message::Service(m) => m.id,
+                tl::enums::Message::Empty(m) => m.id,
+            };
+
+            for raw in &messages {
+                // Service messages (e.g. "pinned a message") aren't comments
+                let tl::enums::Message::Message(m) = raw else {
+                    continue;
+                };
+                let comment_chat = peer_bare_id(&m.peer_id);
+                group_id = Some(comment_chat);
+                let reply_to_id = match &m.reply_to {
+                    Some(tl::enums::MessageReplyHeader::Header(h)) => {
+                        h.reply_to_msg_id.map(|id| id as i64)
+                    }
+                    _ => None,
+                };
+                store
+                    .upsert_message(UpsertMessageParams {
+                        id: m.id as i64,
+                        chat_id: comment_chat,
+                        sender_id: m.from_id.as_ref().map(peer_bare_id).unwrap_or(0),
+                        ts: chrono::DateTime::from_timestamp(m.date as i64, 0)
+                            .unwrap_or_else(Utc::now),
+                        edit_ts: m
+                            .edit_date
+                            .and_then(|d| chrono::DateTime::from_timestamp(d as i64, 0)),
+                        from_me: m.out,
+                        text: m.message.clone(),
+                        media_type: m.media.as_ref().map(|_| "media".to_string()),
+                        media_path: None,
+                        reply_to_id,
+                        topic_id: None,
+                    })
+                    .await?;
+                store
+                    .set_thread_root(comment_chat, m.id as i64, post_id)
+                    .await?;
+                count += 1;
+            }
+        }
+        Ok((group_id, count))
+    }
+
     /// Send a poll to a chat by ID, returns the message ID.
     pub async fn send_poll(
         &mut self,
@@ -980,6 +1459,7 @@ impl App {
         multiple_choice: bool,
         public_voters: bool,
     ) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -1052,7 +1532,6 @@ impl App {
 
         let updates = self
             .tg
-            .client
             .invoke(&request)
             .await
             .context(format!("Failed to send poll to chat {}", chat_id))?;
@@ -1076,6 +1555,32 @@ impl App {
                 topic_id: None,
             })
             .await?;
+        self.get_store()
+            .await?
+            .upsert_message_poll(&MessagePoll {
+                chat_id,
+                msg_id,
+                poll_id,
+                question: question.to_string(),
+                closed: false,
+                multiple_choice,
+                quiz: false,
+                public_voters,
+                total_voters: Some(0),
+                close_date: None,
+                options: options
+                    .iter()
+                    .enumerate()
+                    .map(|(i, text)| PollOption {
+                        index: i as i32,
+                        text: text.clone(),
+                        voters: Some(0),
+                        chosen: false,
+                        correct: false,
+                    })
+                    .collect(),
+            })
+            .await?;
 
         // Update chat's last_message_ts
         self.get_store()
@@ -1093,6 +1598,7 @@ impl App {
         msg_id: i64,
         option_indices: &[usize],
     ) -> Result<()> {
+        crate::guard::check_chat("vote in polls", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -1105,7 +1611,7 @@ impl App {
             options,
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to vote in poll (message {} in chat {})",
             msg_id, chat_id
         ))?;
@@ -1115,6 +1621,7 @@ impl App {
 
     /// Send typing indicator to a chat (or topic in a forum).
     pub async fn set_typing(&self, chat_id: i64, topic_id: Option<i32>) -> Result<()> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
         if let Some(tid) = topic_id {
@@ -1125,7 +1632,7 @@ impl App {
                 top_msg_id: Some(tid),
                 action: SendMessageAction::SendMessageTypingAction,
             };
-            self.tg.client.invoke(&request).await.context(format!(
+            self.tg.invoke(&request).await.context(format!(
                 "Failed to set typing indicator in topic {} of chat {}",
                 tid, chat_id
             ))?;
@@ -1145,6 +1652,7 @@ impl App {
 
     /// Cancel typing indicator in a chat (or topic in a forum).
     pub async fn cancel_typing(&self, chat_id: i64, topic_id: Option<i32>) -> Result<()> {
+        crate::guard::check_send(chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
         if let Some(tid) = topic_id {
@@ -1155,7 +1663,7 @@ impl App {
                 top_msg_id: Some(tid),
                 action: SendMessageAction::SendMessageCancelAction,
             };
-            self.tg.client.invoke(&request).await.context(format!(
+            self.tg.invoke(&request).await.context(format!(
                 "Failed to cancel typing indicator in topic {} of chat {}",
                 tid, chat_id
             ))?;
@@ -1176,6 +1684,7 @@ impl App {
     /// Ban a user from a group or channel.
     /// until_date: 0 = forever, otherwise Unix timestamp
     pub async fn ban_user(&self, chat_id: i64, user_id: i64, until_date: i32) -> Result<()> {
+        crate::guard::check_chat("ban users", chat_id)?;
         let channel_peer = self.resolve_channel_input(chat_id).await?;
         let user_peer = self.resolve_user_input_peer(user_id).await?;
 
@@ -1209,7 +1718,7 @@ impl App {
             banned_rights: tl::enums::ChatBannedRights::Rights(banned_rights),
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to ban user {} from chat {}",
             user_id, chat_id
         ))?;
@@ -1219,6 +1728,7 @@ impl App {
 
     /// Kick a user from a group or channel (they can rejoin).
     pub async fn kick_user(&self, chat_id: i64, user_id: i64) -> Result<()> {
+        crate::guard::check_chat("kick users", chat_id)?;
         let channel_peer = self.resolve_channel_input(chat_id).await?;
         let user_peer = self.resolve_user_input_peer(user_id).await?;
 
@@ -1253,7 +1763,7 @@ impl App {
             banned_rights: tl::enums::ChatBannedRights::Rights(banned_rights),
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to kick user {} from chat {}",
             user_id, chat_id
         ))?;
@@ -1289,20 +1799,17 @@ impl App {
             banned_rights: tl::enums::ChatBannedRights::Rights(unbanned_rights),
         };
 
-        self.tg
-            .client
-            .invoke(&unban_request)
-            .await
-            .context(format!(
-                "Failed to unban user {} after kick from chat {}",
-                user_id, chat_id
-            ))?;
+        self.tg.invoke(&unban_request).await.context(format!(
+            "Failed to unban user {} after kick from chat {}",
+            user_id, chat_id
+        ))?;
 
         Ok(())
     }
 
     /// Unban a user from a group or channel.
     pub async fn unban_user(&self, chat_id: i64, user_id: i64) -> Result<()> {
+        crate::guard::check_chat("unban users", chat_id)?;
         let channel_peer = self.resolve_channel_input(chat_id).await?;
         let user_peer = self.resolve_user_input_peer(user_id).await?;
 
@@ -1336,7 +1843,7 @@ impl App {
             banned_rights: tl::enums::ChatBannedRights::Rights(unbanned_rights),
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to unban user {} from chat {}",
             user_id, chat_id
         ))?;
@@ -1351,6 +1858,7 @@ impl App {
         user_id: i64,
         title: Option<&str>,
     ) -> Result<()> {
+        crate::guard::check_chat("promote users", chat_id)?;
         let channel_peer = self.resolve_channel_input(chat_id).await?;
         let user_peer = self.resolve_user_input(user_id).await?;
 
@@ -1381,7 +1889,7 @@ impl App {
             rank: title.unwrap_or("Admin").to_string(),
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to promote user {} in chat {}",
             user_id, chat_id
         ))?;
@@ -1391,6 +1899,7 @@ impl App {
 
     /// Demote an admin to regular user.
     pub async fn demote_user(&self, chat_id: i64, user_id: i64) -> Result<()> {
+        crate::guard::check_chat("demote users", chat_id)?;
         let channel_peer = self.resolve_channel_input(chat_id).await?;
         let user_peer = self.resolve_user_input(user_id).await?;
 
@@ -1421,7 +1930,7 @@ impl App {
             rank: String::new(),
         };
 
-        self.tg.client.invoke(&request).await.context(format!(
+        self.tg.invoke(&request).await.context(format!(
             "Failed to demote user {} in chat {}",
             user_id, chat_id
         ))?;
@@ -1485,7 +1994,6 @@ impl App {
 
         let result = self
             .tg
-            .client
             .invoke(&request)
             .await
             .context(format!("Failed to search for '{}'", query))?;
@@ -1597,7 +2105,7 @@ impl App {
                     media_path: None,
                     reply_to_id: msg.reply_to_message_id().map(|id| id as i64),
                     topic_id: None,
-                    snippet: String::new(),
+                    ..Default::default()
                 });
             }
         } else {
@@ -1627,7 +2135,7 @@ impl App {
                     media_path: None,
                     reply_to_id: msg.reply_to_message_id().map(|id| id as i64),
                     topic_id: None,
-                    snippet: String::new(),
+                    ..Default::default()
                 });
             }
         }
@@ -1643,6 +2151,7 @@ impl App {
         chat_type: &str,
         description: Option<&str>,
     ) -> Result<CreateChatResult> {
+        crate::guard::check("create chats")?;
         match chat_type {
             "group" => {
                 // Create a supergroup (megagroup) which supports descriptions
@@ -1661,7 +2170,6 @@ impl App {
 
                 let updates = self
                     .tg
-                    .client
                     .invoke(&request)
                     .await
                     .context("Failed to create group")?;
@@ -1690,7 +2198,6 @@ impl App {
 
                 let updates = self
                     .tg
-                    .client
                     .invoke(&request)
                     .await
                     .context("Failed to create channel")?;
@@ -1794,6 +2301,7 @@ impl App {
         link: Option<&str>,
         username: Option<&str>,
     ) -> Result<JoinChatResult> {
+        crate::guard::check("join chats")?;
         if let Some(invite_link) = link {
             // Extract hash from invite link
             let hash = extract_invite_hash(invite_link)?;
@@ -1801,7 +2309,6 @@ impl App {
             let request = tl::functions::messages::ImportChatInvite { hash };
             let updates = self
                 .tg
-                .client
                 .invoke(&request)
                 .await
                 .context("Failed to join chat via invite link")?;
@@ -1837,7 +2344,6 @@ impl App {
                         }),
                     };
                     self.tg
-                        .client
                         .invoke(&request)
                         .await
                         .context("Failed to join channel")?;
@@ -1872,6 +2378,7 @@ impl App {
 
     /// Leave a chat.
     pub async fn leave_chat(&self, chat_id: i64) -> Result<()> {
+        crate::guard::check_chat("leave chats", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -1884,7 +2391,6 @@ impl App {
                     }),
                 };
                 self.tg
-                    .client
                     .invoke(&request)
                     .await
                     .context(format!("Failed to leave channel {}", chat_id))?;
@@ -1897,7 +2403,6 @@ impl App {
                     user_id: tl::enums::InputUser::UserSelf,
                 };
                 self.tg
-                    .client
                     .invoke(&request)
                     .await
                     .context(format!("Failed to leave chat {}", chat_id))?;
@@ -1926,7 +2431,6 @@ impl App {
         };
         let result = self
             .tg
-            .client
             .invoke(&request)
             .await
             .context(format!("Failed to get invite link for chat {}", chat_id))?;
@@ -1946,6 +2450,7 @@ impl App {
         expire_date: Option<i32>,
         usage_limit: Option<i32>,
     ) -> Result<InviteLinkResult> {
+        crate::guard::check_chat("create invite links", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -1961,7 +2466,6 @@ impl App {
 
         let result = self
             .tg
-            .client
             .invoke(&request)
             .await
             .context(format!("Failed to create invite link for chat {}", chat_id))?;
@@ -1987,6 +2491,7 @@ impl App {
 
     /// Mute notifications for a chat.
     pub async fn mute_chat(&self, chat_id: i64, mute_until: i32) -> Result<()> {
+        crate::guard::check_chat("mute chats", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -2006,7 +2511,6 @@ impl App {
         };
 
         self.tg
-            .client
             .invoke(&request)
             .await
             .context(format!("Failed to mute chat {}", chat_id))?;
@@ -2016,6 +2520,7 @@ impl App {
 
     /// Unmute notifications for a chat.
     pub async fn unmute_chat(&self, chat_id: i64) -> Result<()> {
+        crate::guard::check_chat("unmute chats", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -2035,7 +2540,6 @@ impl App {
         };
 
         self.tg
-            .client
             .invoke(&request)
             .await
             .context(format!("Failed to unmute chat {}", chat_id))?;
@@ -2043,20 +2547,14 @@ impl App {
         Ok(())
     }
 
-    /// Download media from a message with progress indicator.
-    /// Returns download result with path, media type, and size.
-    pub async fn download_media(
+    /// Fetch a single message from Telegram by ID.
+    pub async fn fetch_message(
         &self,
         chat_id: i64,
         msg_id: i64,
-        output_path: Option<&str>,
-    ) -> Result<DownloadResult> {
-        use grammers_client::types::Downloadable;
-        use std::io::Write;
-
+    ) -> Result<grammers_client::types::Message> {
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
 
-        // Fetch the specific message
         let mut message_iter = self.tg.client.iter_messages(peer_ref);
         message_iter = message_iter.offset_id(msg_id as i32 + 1).limit(1);
 
@@ -2068,10 +2566,35 @@ impl App {
         if msg.id() != msg_id as i32 {
             anyhow::bail!("Message {} not found in chat {}", msg_id, chat_id);
         }
+        Ok(msg)
+    }
 
-        let media = msg
-            .media()
-            .ok_or_else(|| anyhow::anyhow!("Message {} has no media", msg_id))?;
+    /// Download media from a message with progress indicator.
+    /// Returns download result with path, media type, and size.
+    pub async fn download_media(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        output_path: Option<&str>,
+    ) -> Result<DownloadResult> {
+        use grammers_client::types::Downloadable;
+        use std::io::Write;
+
+        let msg = self.fetch_message(chat_id, msg_id).await?;
+
+        let media = match msg.media().or_else(|| unlocked_media(&msg.raw)) {
+            Some(media) => media,
+            None => match paid_media(&msg.raw) {
+                Some(paid) => anyhow::bail!(
+                    "Message {} is paid media ({} stars); unlock it with `tgcli messages unlock --chat {} --message {}`",
+                    msg_id,
+                    paid.stars,
+                    chat_id,
+                    msg_id
+                ),
+                None => anyhow::bail!("Message {} has no media", msg_id),
+            },
+        };
 
         // Get media type
         let media_type = get_media_type(&media);
@@ -2101,8 +2624,7 @@ impl App {
         // Download with progress
         let mut downloaded: u64 = 0;
         let mut download_iter = self.tg.client.iter_download(&media);
-        let progress_interval = std::time::Duration::from_millis(100);
-        let mut last_progress = std::time::Instant::now();
+        let bar = Progress::new(true).bytes(total_size.map(|s| s as u64), "Downloading");
 
         while let Some(chunk) = download_iter
             .next()
@@ -2111,28 +2633,10 @@ impl App {
         {
             file.write_all(&chunk).context("Failed to write to file")?;
             downloaded += chunk.len() as u64;
-
-            // Show progress
-            if last_progress.elapsed() >= progress_interval {
-                if let Some(total) = total_size {
-                    let percent = (downloaded as f64 / total as f64 * 100.0) as u32;
-                    eprint!(
-                        "\rDownloading... {}% ({}/{})",
-                        percent,
-                        format_size(downloaded),
-                        format_size(total as u64)
-                    );
-                } else {
-                    eprint!("\rDownloading... {}", format_size(downloaded));
-                }
-                let _ = std::io::stderr().flush();
-                last_progress = std::time::Instant::now();
-            }
+            bar.set_position(downloaded);
+            crate::tg::transfer::throttle_download(chunk.len()).await;
         }
-
-        // Clear progress line
-        eprint!("\r\x1b[K");
-        let _ = std::io::stderr().flush();
+        bar.finish_and_clear();
 
         Ok(DownloadResult {
             path: final_path.to_string_lossy().to_string(),
@@ -2142,8 +2646,8 @@ impl App {
     }
 
     /// Mark messages up to a specific message ID as read.
-    #[allow(dead_code)]
     pub async fn mark_read_up_to(&self, chat_id: i64, max_id: i64) -> Result<()> {
+        crate::guard::check_chat("mark chats as read", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -2157,7 +2661,7 @@ impl App {
                     }),
                     max_id: max_id as i32,
                 };
-                self.tg.client.invoke(&request).await.context(format!(
+                self.tg.invoke(&request).await.context(format!(
                     "Failed to mark messages up to {} as read in channel {}",
                     max_id, chat_id
                 ))?;
@@ -2167,7 +2671,7 @@ impl App {
                     peer: input_peer,
                     max_id: max_id as i32,
                 };
-                self.tg.client.invoke(&request).await.context(format!(
+                self.tg.invoke(&request).await.context(format!(
                     "Failed to mark messages up to {} as read in chat {}",
                     max_id, chat_id
                 ))?;
@@ -2181,7 +2685,6 @@ impl App {
         let request = tl::functions::messages::GetAllDrafts {};
         let updates = self
             .tg
-            .client
             .invoke(&request)
             .await
             .context("Failed to get drafts")?;
@@ -2231,6 +2734,7 @@ impl App {
 
     /// Clear draft for a specific chat.
     pub async fn clear_draft(&self, chat_id: i64) -> Result<()> {
+        crate::guard::check_chat("clear drafts", chat_id)?;
         let peer_ref = self.resolve_peer_ref(chat_id).await?;
         let input_peer: tl::enums::InputPeer = peer_ref.into();
 
@@ -2247,7 +2751,6 @@ impl App {
         };
 
         self.tg
-            .client
             .invoke(&request)
             .await
             .context(format!("Failed to clear draft for chat {}", chat_id))?;
@@ -2332,11 +2835,12 @@ fn get_media_filename(media: &grammers_client::types::Media, msg_id: i64) -> (St
         Media::Document(doc) => {
             // Try to get original filename
             if !doc.name().is_empty() {
-                let name = doc.name();
+                // The sender picks this name; keep it inside the target directory
+                let name = crate::paths::sanitize_file_name(doc.name());
                 if let Some(pos) = name.rfind('.') {
                     return (name[..pos].to_string(), name[pos + 1..].to_string());
                 }
-                return (name.to_string(), "bin".to_string());
+                return (name, "bin".to_string());
             }
 
             // Determine extension from mime type
@@ -2401,7 +2905,7 @@ fn media_mime_to_ext(mime: &str) -> String {
 }
 
 /// Get media type string from Media enum
-fn get_media_type(media: &grammers_client::types::Media) -> String {
+pub(crate) fn get_media_type(media: &grammers_client::types::Media) -> String {
     use grammers_client::types::Media;
 
     match media {
@@ -2428,20 +2932,3 @@ fn get_media_type(media: &grammers_client::types::Media) -> String {
         _ => "media".to_string(),
     }
 }
-
-/// Format file size for human readability
-fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
-    }
-}
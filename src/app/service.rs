@@ -0,0 +1,218 @@
+//! Service messages (joins, leaves, pins, title changes, calls, ...).
+//!
+//! They are stored like any other message with `media_type = 'service'`, a
+//! readable description as the text (so they show up in search), and the
+//! action's details as JSON in the `service_action` column, e.g.
+//! `{"type": "chat_add_user", "users": [123]}`.
+
+use grammers_tl_types as tl;
+use serde_json::json;
+
+pub(crate) struct ServiceAction {
+    /// Human-readable description, e.g. `changed the title to "Team"`
+    pub text: String,
+    pub json: serde_json::Value,
+}
+
+/// The action of a raw service message (None for regular messages).
+pub(crate) fn service_action(raw: &tl::enums::Message) -> Option<ServiceAction> {
+    match raw {
+        tl::enums::Message::Service(m) => Some(describe(&m.action)),
+        _ => None,
+    }
+}
+
+fn describe(action: &tl::enums::MessageAction) -> ServiceAction {
+    use tl::enums::MessageAction as A;
+    let (text, json) = match action {
+        A::ChatCreate(a) => (
+            format!("created the group \"{}\"", a.title),
+            json!({"type": "chat_create", "title": a.title, "users": a.users}),
+        ),
+        A::ChannelCreate(a) => (
+            format!("created the channel \"{}\"", a.title),
+            json!({"type": "channel_create", "title": a.title}),
+        ),
+        A::ChatEditTitle(a) => (
+            format!("changed the title to \"{}\"", a.title),
+            json!({"type": "chat_edit_title", "title": a.title}),
+        ),
+        A::ChatEditPhoto(_) => (
+            "changed the group photo".to_string(),
+            json!({"type": "chat_edit_photo"}),
+        ),
+        A::ChatDeletePhoto => (
+            "removed the group photo".to_string(),
+            json!({"type": "chat_delete_photo"}),
+        ),
+        A::ChatAddUser(a) => (
+            format!("added {}", user_list(&a.users)),
+            json!({"type": "chat_add_user", "users": a.users}),
+        ),
+        A::ChatDeleteUser(a) => (
+            format!("removed user {}", a.user_id),
+            json!({"type": "chat_delete_user", "user_id": a.user_id}),
+        ),
+        A::ChatJoinedByLink(a) => (
+            "joined via invite link".to_string(),
+            json!({"type": "chat_joined_by_link", "inviter_id": a.inviter_id}),
+        ),
+        A::ChatJoinedByRequest => (
+            "joined after their request was approved".to_string(),
+            json!({"type": "chat_joined_by_request"}),
+        ),
+        A::ChatMigrateTo(a) => (
+            format!("upgraded to supergroup {}", a.channel_id),
+            json!({"type": "chat_migrate_to", "channel_id": a.channel_id}),
+        ),
+        A::ChannelMigrateFrom(a) => (
+            format!("upgraded from group \"{}\"", a.title),
+            json!({"type": "channel_migrate_from", "title": a.title, "chat_id": a.chat_id}),
+        ),
+        A::PinMessage => (
+            "pinned a message".to_string(),
+            json!({"type": "pin_message"}),
+        ),
+        A::HistoryClear => (
+            "cleared the history".to_string(),
+            json!({"type": "history_clear"}),
+        ),
+        A::PhoneCall(a) => {
+            let kind = if a.video { "video call" } else { "call" };
+            let missed = matches!(
+                a.reason,
+                Some(tl::enums::PhoneCallDiscardReason::Missed)
+                    | Some(tl::enums::PhoneCallDiscardReason::Busy)
+            );
+            let text = match a.duration {
+                Some(secs) if !missed => format!("{} ({})", kind, format_duration(secs)),
+                _ => format!("missed {}", kind),
+            };
+            (
+                text,
+                json!({
+                    "type": "phone_call",
+                    "video": a.video,
+                    "missed": missed,
+                    "duration": a.duration,
+                }),
+            )
+        }
+        A::GroupCall(a) => {
+            let text = match a.duration {
+                Some(secs) => format!("video chat ended ({})", format_duration(secs)),
+                None => "started a video chat".to_string(),
+            };
+            (text, json!({"type": "group_call", "duration": a.duration}))
+        }
+        A::ScreenshotTaken => (
+            "took a screenshot".to_string(),
+            json!({"type": "screenshot_taken"}),
+        ),
+        A::ContactSignUp => (
+            "joined Telegram".to_string(),
+            json!({"type": "contact_sign_up"}),
+        ),
+        A::TopicCreate(a) => (
+            format!("created the topic \"{}\"", a.title),
+            json!({"type": "topic_create", "title": a.title}),
+        ),
+        A::SetMessagesTtl(a) => {
+            let text = if a.period == 0 {
+                "disabled auto-delete".to_string()
+            } else {
+                format!(
+                    "set messages to auto-delete after {}",
+                    format_duration(a.period)
+                )
+            };
+            (
+                text,
+                json!({"type": "set_messages_ttl", "period": a.period}),
+            )
+        }
+        A::CustomAction(a) => (
+            a.message.clone(),
+            json!({"type": "custom_action", "message": a.message}),
+        ),
+        other => {
+            let kind = action_type(other);
+            (
+                format!("service message ({})", kind.replace('_', " ")),
+                json!({"type": kind}),
+            )
+        }
+    };
+    ServiceAction { text, json }
+}
+
+fn user_list(users: &[i64]) -> String {
+    let ids: Vec<String> = users.iter().map(|u| u.to_string()).collect();
+    match ids.len() {
+        1 => format!("user {}", ids[0]),
+        _ => format!("users {}", ids.join(", ")),
+    }
+}
+
+/// `ChatEditPhoto(...)` -> `chat_edit_photo`
+fn action_type(action: &tl::enums::MessageAction) -> String {
+    let debug = format!("{:?}", action);
+    let name = debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default();
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn format_duration(secs: i32) -> String {
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m {}s", s / 60, s % 60),
+        s if s < 86400 => format!("{}h {}m", s / 3600, (s % 3600) / 60),
+        s => format!("{}d", s / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_title_change() {
+        let action =
+            tl::enums::MessageAction::ChatEditTitle(tl::types::MessageActionChatEditTitle {
+                title: "Team".to_string(),
+            });
+        let described = describe(&action);
+        assert_eq!(described.text, "changed the title to \"Team\"");
+        assert_eq!(described.json["type"], "chat_edit_title");
+        assert_eq!(described.json["title"], "Team");
+    }
+
+    #[test]
+    fn test_action_type_fallback() {
+        assert_eq!(
+            action_type(&tl::enums::MessageAction::HistoryClear),
+            "history_clear"
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(125), "2m 5s");
+        assert_eq!(format_duration(7260), "2h 1m");
+        assert_eq!(format_duration(86400 * 7), "7d");
+    }
+}
@@ -1,8 +1,16 @@
+use crate::app::contact::save_shared_contact;
+use crate::app::details::record_message_details;
+use crate::app::emoji::{custom_emoji_spans, message_entities, record_custom_emoji};
+use crate::app::folder::Folder;
+use crate::app::service::service_action;
 use crate::app::App;
+use crate::out::progress::Progress;
 use crate::shutdown;
-use crate::store::UpsertMessageParams;
+use crate::store::{Chat, ChatSort, CustomEmojiSpan, UpsertMessageParams, SERVICE_MEDIA_TYPE};
+use crate::tg::transfer;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use futures::stream::{self, StreamExt};
 use grammers_client::types::{Media, Message as TgMessage, Peer};
 use grammers_client::Client;
@@ -10,11 +18,11 @@ use grammers_session::defs::{PeerAuth, PeerId, PeerRef};
 use grammers_session::storages::SqliteSession;
 use grammers_session::Session;
 use grammers_tl_types as tl;
+use regex::Regex;
 use std::collections::HashSet;
-use std::path::Path;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::sync::Semaphore;
 
 /// Maximum messages to fetch per chat during incremental sync (effectively unlimited).
@@ -29,6 +37,7 @@ pub enum OutputMode {
     Stream,
 }
 
+#[derive(Clone)]
 pub struct SyncOptions {
     pub output: OutputMode,
     #[allow(dead_code)]
@@ -40,14 +49,83 @@ pub struct SyncOptions {
     pub incremental: bool,
     pub messages_per_chat: usize,
     pub concurrency: usize,
-    /// If set, only sync this specific chat
-    pub chat_filter: Option<i64>,
+    /// If not empty, only sync these chats
+    pub chat_filter: Vec<i64>,
     /// After sync, prune messages keeping only the N most recent per chat
     pub prune_after: Option<usize>,
     /// Skip archived chats entirely (don't fetch dialogs or messages from archived folder)
     pub skip_archived: bool,
     /// Sync ONLY archived chats (opposite of --skip-archived)
     pub archived_only: bool,
+    /// Add contact cards shared in messages to `contacts`
+    pub save_shared_contacts: bool,
+    /// If set, only sync chats in this folder
+    pub folder: Option<Folder>,
+    /// Chat kinds to skip before fetching any of their messages
+    pub skip_kinds: Vec<SkipKind>,
+    /// Skip chats whose name matches
+    pub skip_name: Option<Regex>,
+}
+
+/// Chat kinds `--skip-kind` can leave out of a sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SkipKind {
+    User,
+    Bot,
+    Group,
+    Channel,
+}
+
+impl SyncOptions {
+    /// Whether `--skip-kind` or `--skip-name-regex` leave a chat out, judged
+    /// from its dialog metadata alone.
+    fn skips(&self, kind: &str, name: &str, is_bot: bool) -> bool {
+        let skip_kind = match kind {
+            "user" if is_bot => SkipKind::Bot,
+            "user" => SkipKind::User,
+            "group" => SkipKind::Group,
+            _ => SkipKind::Channel,
+        };
+        self.skip_kinds.contains(&skip_kind)
+            || self.skip_name.as_ref().is_some_and(|re| re.is_match(name))
+    }
+
+    /// Whether a local chat is synced by `sync msgs` and `--backfill`.
+    fn keeps(&self, chat: &Chat) -> bool {
+        // If chat_filter is set, only include those chats
+        if !self.chat_filter.is_empty() && !self.chat_filter.contains(&chat.id) {
+            return false;
+        }
+        if self.ignore_chat_ids.contains(&chat.id) {
+            return false;
+        }
+        if self.ignore_channels && chat.kind == "channel" {
+            return false;
+        }
+        if let Some(folder) = &self.folder {
+            if !folder.contains(chat.id, &chat.kind) {
+                return false;
+            }
+        }
+        // Bot usernames always end in "bot"; sessions don't record more
+        let is_bot = chat.kind == "user"
+            && chat
+                .username
+                .as_deref()
+                .is_some_and(|u| u.to_lowercase().ends_with("bot"));
+        if self.skips(&chat.kind, &chat.name, is_bot) {
+            return false;
+        }
+        // Filter by archived status
+        if self.skip_archived && chat.archived {
+            return false;
+        }
+        !self.archived_only || chat.archived
+    }
+}
+
+fn is_bot(peer: &Peer) -> bool {
+    matches!(peer, Peer::User(user) if user.is_bot())
 }
 
 /// Get media type string and file extension from grammers Media enum
@@ -162,6 +240,14 @@ pub struct SyncResult {
     pub per_chat: Vec<ChatSyncSummary>,
 }
 
+/// What the backfill phase of `sync --backfill` fetched.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct BackfillResult {
+    /// Chats that had older history to fetch
+    pub chats: u64,
+    pub messages: u64,
+}
+
 /// Result from syncing a single chat (used for concurrent processing)
 struct ChatSyncTaskResult {
     chat_id: i64,
@@ -188,8 +274,12 @@ struct FetchedMessage {
     text: String,
     media_type: Option<String>,
     media_path: Option<String>,
+    media_sha256: Option<String>,
     reply_to_id: Option<i64>,
     topic_id: Option<i32>,
+    custom_emoji: Vec<CustomEmojiSpan>,
+    /// Raw message, for the details stored after the row
+    raw: tl::enums::Message,
 }
 
 /// Output representation of a synced message (used for Text/Json/Stream modes)
@@ -361,68 +451,76 @@ impl App {
         None
     }
 
-    /// Download media from a message if present and return (media_type, media_path)
-    async fn download_message_media(
+    /// Download media from a message into the store's media directory.
+    async fn download_message_media(&self, msg: &TgMessage, chat_id: i64) -> Result<SavedMedia> {
+        download_message_media_static(&self.tg.client, msg, chat_id, &self.store_dir).await
+    }
+
+    /// Download a stored message's media again, replacing any file already
+    /// in the media directory, and record its path and checksum.
+    pub async fn redownload_message_media(&self, chat_id: i64, msg_id: i64) -> Result<SavedMedia> {
+        let msg = self.fetch_message(chat_id, msg_id).await?;
+        if let Some(media) = msg.media() {
+            let (_, ext) = media_info(&media);
+            if !ext.is_empty() {
+                let _ =
+                    std::fs::remove_file(media_file_path(&self.store_dir, chat_id, msg_id, &ext));
+            }
+        }
+        self.save_message_media(&msg, chat_id).await
+    }
+
+    /// Download media for a stored message that was synced without it.
+    /// Returns `None` (without downloading) when `media_type` is given and the
+    /// message's media turns out to be of another type.
+    pub async fn fetch_missing_media(
         &self,
-        msg: &TgMessage,
         chat_id: i64,
-    ) -> Result<(Option<String>, Option<String>)> {
+        msg_id: i64,
+        media_type: Option<&str>,
+    ) -> Result<Option<SavedMedia>> {
+        let msg = self.fetch_message(chat_id, msg_id).await?;
         let media = match msg.media() {
-            Some(m) => m,
-            None => return Ok((None, None)),
+            Some(media) => media,
+            None => anyhow::bail!("Message {} in chat {} has no media", msg_id, chat_id),
         };
-
-        let (media_type, ext) = media_info(&media);
-
-        // Skip non-downloadable media types
-        if ext.is_empty() {
-            return Ok((Some(media_type), None));
-        }
-
-        // Build path: {store_dir}/media/{chat_id}/{message_id}.{ext}
-        let media_dir = Path::new(&self.store_dir)
-            .join("media")
-            .join(chat_id.to_string());
-
-        // Create directory if needed
-        std::fs::create_dir_all(&media_dir)?;
-
-        let file_name = format!("{}.{}", msg.id(), ext);
-        let file_path = media_dir.join(&file_name);
-
-        // Skip if file already exists (idempotent)
-        if file_path.exists() {
-            return Ok((
-                Some(media_type),
-                Some(file_path.to_string_lossy().to_string()),
-            ));
-        }
-
-        // Download the media
-        match self.tg.client.download_media(&media, &file_path).await {
-            Ok(()) => {
-                log::info!(
-                    "Downloaded media: chat={} msg={} -> {}",
-                    chat_id,
-                    msg.id(),
-                    file_path.display()
-                );
-                Ok((
-                    Some(media_type),
-                    Some(file_path.to_string_lossy().to_string()),
-                ))
-            }
-            Err(e) => {
-                log::warn!(
-                    "Failed to download media for chat={} msg={}: {}",
-                    chat_id,
-                    msg.id(),
-                    e
-                );
-                // Return media type but no path on failure
-                Ok((Some(media_type), None))
+        if let Some(wanted) = media_type {
+            if media_info(&media).0 != wanted {
+                return Ok(None);
             }
         }
+        self.save_message_media(&msg, chat_id).await.map(Some)
+    }
+
+    /// Download a message's media into the store and record its type, path,
+    /// and checksum on the stored message.
+    async fn save_message_media(&self, msg: &TgMessage, chat_id: i64) -> Result<SavedMedia> {
+        let mut saved = self.download_message_media(msg, chat_id).await?;
+        let path = match &saved.path {
+            Some(path) => path.clone(),
+            None => anyhow::bail!(
+                "Could not download media for message {} in chat {}",
+                msg.id(),
+                chat_id
+            ),
+        };
+        // A file left over from an earlier download is hashed as found
+        let sha256 = match &saved.sha256 {
+            Some(sha256) => sha256.clone(),
+            None => transfer::sha256_file(Path::new(&path))?,
+        };
+        self.get_store()
+            .await?
+            .set_media_file(
+                chat_id,
+                msg.id() as i64,
+                saved.media_type.as_deref(),
+                &path,
+                &sha256,
+            )
+            .await?;
+        saved.sha256 = Some(sha256);
+        Ok(saved)
     }
 
     /// Sync only chat list from Telegram dialogs (no messages).
@@ -436,22 +534,27 @@ impl App {
         // Build ignore set for fast lookup.
         let ignore_set: HashSet<i64> = opts.ignore_chat_ids.iter().copied().collect();
 
-        let should_ignore = |chat_id: i64, kind: &str| -> bool {
+        let should_ignore = |chat_id: i64, kind: &str, name: &str, is_bot: bool| -> bool {
             if ignore_set.contains(&chat_id) {
                 return true;
             }
             if opts.ignore_channels && kind == "channel" {
                 return true;
             }
+            if opts.skips(kind, name, is_bot) {
+                return true;
+            }
+            if let Some(folder) = &opts.folder {
+                return !folder.contains(chat_id, kind);
+            }
             false
         };
 
         let client = &self.tg.client;
 
         // Phase 1: Fetch active dialogs
-        if opts.show_progress {
-            eprint!("\rSyncing chats... 0");
-        }
+        let progress = Progress::new(opts.show_progress);
+        let bar = progress.spinner("Syncing chats... 0");
 
         let mut dialogs = client.iter_dialogs();
         while let Some(dialog) = dialogs
@@ -461,9 +564,7 @@ impl App {
         {
             // Check for shutdown
             if shutdown_ctrl.is_triggered() {
-                if opts.show_progress {
-                    eprint!("\r\x1b[K");
-                }
+                bar.finish_and_clear();
                 eprintln!("Sync interrupted by shutdown");
                 return Ok(SyncResult {
                     messages_stored: 0,
@@ -475,7 +576,7 @@ impl App {
             let (kind, name, username, is_forum, access_hash) = peer_info(peer);
             let id = peer.id().bare_id();
 
-            if should_ignore(id, &kind) {
+            if should_ignore(id, &kind, &name, is_bot(peer)) {
                 continue;
             }
 
@@ -492,6 +593,10 @@ impl App {
                     false, // Not archived (regular dialogs)
                 )
                 .await?;
+            self.get_store()
+                .await?
+                .set_chat_unread_count(id, extract_unread_count(&dialog.raw))
+                .await?;
             chats_stored += 1;
 
             // Also store as contact if it's a user
@@ -511,20 +616,16 @@ impl App {
             // If it's a forum, sync topics
             if is_forum {
                 if let Ok(topic_count) = self.sync_topics(id).await {
-                    log::info!("Synced {} topics for forum chat {}", topic_count, id);
+                    log::info!(chat_id = id; "Synced {} topics for forum chat {}", topic_count, id);
                 }
             }
 
-            if opts.show_progress && chats_stored.is_multiple_of(10) {
-                eprint!("\rSyncing chats... {}", chats_stored);
-            }
+            bar.set_message(format!("Syncing chats... {}", chats_stored));
         }
 
         // Check for shutdown before archived dialogs
         if shutdown_ctrl.is_triggered() {
-            if opts.show_progress {
-                eprint!("\r\x1b[K");
-            }
+            bar.finish_and_clear();
             eprintln!("Sync interrupted by shutdown");
             return Ok(SyncResult {
                 messages_stored: 0,
@@ -535,9 +636,7 @@ impl App {
 
         // Phase 2: Fetch archived dialogs (unless --skip-archived is set)
         if !opts.skip_archived {
-            if opts.show_progress {
-                eprint!("\rSyncing archived chats... {}", chats_stored);
-            }
+            bar.set_message(format!("Syncing archived chats... {}", chats_stored));
 
             let archived_peers = self.fetch_archived_dialogs().await?;
             for peer in archived_peers {
@@ -548,7 +647,7 @@ impl App {
                 let (kind, name, username, is_forum, access_hash) = peer_info(&peer);
                 let id = peer.id().bare_id();
 
-                if should_ignore(id, &kind) {
+                if should_ignore(id, &kind, &name, is_bot(&peer)) {
                     continue;
                 }
 
@@ -566,6 +665,7 @@ impl App {
                     )
                     .await?;
                 chats_stored += 1;
+                bar.set_message(format!("Syncing archived chats... {}", chats_stored));
 
                 // Also store as contact if it's a user
                 if let Peer::User(ref user) = peer {
@@ -594,9 +694,7 @@ impl App {
             }
         }
 
-        if opts.show_progress {
-            eprint!("\r\x1b[K"); // Clear line
-        }
+        bar.finish_and_clear();
         eprintln!("Chats sync complete: {} chats", chats_stored);
 
         Ok(SyncResult {
@@ -615,42 +713,18 @@ impl App {
         let shutdown_ctrl = shutdown::global();
         let cancellation_token = shutdown_ctrl.child_token();
 
-        // Build ignore set for fast lookup.
-        let ignore_set: HashSet<i64> = opts.ignore_chat_ids.iter().copied().collect();
-        let ignore_channels = opts.ignore_channels;
-
         // Get all chats that have sync checkpoints
         let all_chats = self.get_store().await?.list_chats_with_checkpoint().await?;
 
         // Filter chats to process
-        let chat_filter = opts.chat_filter;
-        let skip_archived = opts.skip_archived;
-        let archived_only = opts.archived_only;
         let chats_to_sync: Vec<_> = all_chats
             .into_iter()
             .filter(|chat| {
-                // If chat_filter is set, only include that specific chat
-                if let Some(filter_id) = chat_filter {
-                    if chat.id != filter_id {
-                        return false;
-                    }
-                }
-                if ignore_set.contains(&chat.id) {
-                    return false;
-                }
-                if ignore_channels && chat.kind == "channel" {
-                    return false;
-                }
-                // Filter by archived status
-                if skip_archived && chat.archived {
-                    return false;
-                }
-                if archived_only && !chat.archived {
-                    return false;
-                }
                 // Must have peer info to sync
-                self.resolve_peer_from_session(chat.id, &chat.kind, chat.access_hash)
-                    .is_some()
+                opts.keeps(chat)
+                    && self
+                        .resolve_peer_from_session(chat.id, &chat.kind, chat.access_hash)
+                        .is_some()
             })
             .collect();
 
@@ -671,9 +745,8 @@ impl App {
         let mut unread_counts: std::collections::HashMap<i64, i32> =
             std::collections::HashMap::new();
 
-        if opts.show_progress {
-            eprint!("\rFetching unread counts...");
-        }
+        let progress = Progress::new(opts.show_progress);
+        let unread_bar = progress.spinner("Fetching unread counts...");
 
         let client = &self.tg.client;
         let mut dialogs = client.iter_dialogs();
@@ -689,14 +762,18 @@ impl App {
             }
         }
 
-        if opts.show_progress {
-            eprint!("\r\x1b[K"); // Clear line
+        unread_bar.finish_and_clear();
+
+        {
+            let store = self.get_store().await?;
+            for (&chat_id, &unread) in &unread_counts {
+                store.set_chat_unread_count(chat_id, unread).await?;
+            }
         }
 
-        // Progress tracking with atomics for thread-safety
-        let chats_done = Arc::new(AtomicU64::new(0));
+        // Overall bar over chats; each running chat gets its own bar below it
+        let chats_bar = progress.items(total_chats as u64, "chats");
         let messages_fetched = Arc::new(AtomicU64::new(0));
-        let cancelled = Arc::new(AtomicBool::new(false));
 
         // Semaphore for concurrency control
         let concurrency = opts.concurrency.max(1);
@@ -715,41 +792,6 @@ impl App {
         let messages_per_chat = opts.messages_per_chat;
         let output_mode = opts.output;
 
-        // Progress output task
-        let show_progress = opts.show_progress;
-        let chats_done_progress = chats_done.clone();
-        let messages_fetched_progress = messages_fetched.clone();
-        let cancelled_progress = cancelled.clone();
-
-        // Spawn progress reporter if needed
-        let progress_handle = if show_progress {
-            let total = total_chats;
-            let cancel_token = cancellation_token.clone();
-            Some(tokio::spawn(async move {
-                let mut interval = tokio::time::interval(Duration::from_millis(500));
-                loop {
-                    tokio::select! {
-                        _ = interval.tick() => {
-                            let done = chats_done_progress.load(Ordering::Relaxed);
-                            let msgs = messages_fetched_progress.load(Ordering::Relaxed);
-                            eprint!(
-                                "\rSyncing messages... {}/{} chats, {} messages",
-                                done, total, msgs
-                            );
-                            if done >= total as u64 || cancelled_progress.load(Ordering::Relaxed) {
-                                break;
-                            }
-                        }
-                        _ = cancel_token.cancelled() => {
-                            break;
-                        }
-                    }
-                }
-            }))
-        } else {
-            None
-        };
-
         // Create concurrent stream of chat sync tasks
         let results: Vec<ChatSyncTaskResult> = stream::iter(chats_to_sync)
             .map(|chat| {
@@ -757,10 +799,10 @@ impl App {
                 let client = client.clone();
                 let session = session.clone();
                 let store_dir = store_dir.clone();
-                let chats_done = chats_done.clone();
+                let chats_bar = chats_bar.clone();
+                let progress = progress.clone();
                 let messages_fetched = messages_fetched.clone();
                 let cancel_token = cancellation_token.clone();
-                let cancelled_flag = cancelled.clone();
                 let chat_name_for_output = chat.name.clone();
 
                 async move {
@@ -768,7 +810,7 @@ impl App {
 
                     // Check for cancellation before starting
                     if cancel_token.is_cancelled() {
-                        chats_done.fetch_add(1, Ordering::Relaxed);
+                        chats_bar.inc(1);
                         return ChatSyncTaskResult {
                             chat_id: chat.id,
                             chat_name: chat.name.clone(),
@@ -796,7 +838,7 @@ impl App {
                     let peer_ref = match peer_ref {
                         Some(p) => p,
                         None => {
-                            chats_done.fetch_add(1, Ordering::Relaxed);
+                            chats_bar.inc(1);
                             return ChatSyncTaskResult {
                                 chat_id: chat.id,
                                 chat_name: chat.name.clone(),
@@ -828,11 +870,11 @@ impl App {
                     let mut topic_counts: std::collections::HashMap<i32, u64> =
                         std::collections::HashMap::new();
                     let mut error: Option<String> = None;
+                    let chat_bar = progress.task(&chat.name, "messages");
 
                     loop {
                         // Check for cancellation during message fetching
                         if cancel_token.is_cancelled() {
-                            cancelled_flag.store(true, Ordering::Relaxed);
                             break;
                         }
 
@@ -869,7 +911,11 @@ impl App {
 
                                 let sender_id = msg.sender().map(|s| s.id().bare_id()).unwrap_or(0);
                                 let from_me = msg.outgoing();
-                                let text = msg.text().to_string();
+                                let service = service_action(&msg.raw);
+                                let text = match &service {
+                                    Some(action) => action.text.clone(),
+                                    None => msg.text().to_string(),
+                                };
                                 let reply_to_id = msg.reply_to_message_id().map(|id| id as i64);
                                 let topic_id = if chat.is_forum {
                                     extract_topic_id(&msg)
@@ -883,14 +929,14 @@ impl App {
                                 }
 
                                 // Handle media
-                                let (media_type, media_path) = if download_media {
+                                let media = if download_media {
                                     download_message_media_static(
                                         &client, &msg, chat.id, &store_dir,
                                     )
                                     .await
-                                    .unwrap_or((None, None))
+                                    .unwrap_or_default()
                                 } else {
-                                    (msg.media().map(|_| "media".to_string()), None)
+                                    SavedMedia::placeholder(&msg)
                                 };
 
                                 let fetched_msg = FetchedMessage {
@@ -900,10 +946,16 @@ impl App {
                                     edit_ts: msg.edit_date(),
                                     from_me,
                                     text,
-                                    media_type,
-                                    media_path,
+                                    media_type: match &service {
+                                        Some(_) => Some(SERVICE_MEDIA_TYPE.to_string()),
+                                        None => media.media_type,
+                                    },
+                                    media_path: media.path,
+                                    media_sha256: media.sha256,
                                     reply_to_id,
                                     topic_id,
+                                    custom_emoji: custom_emoji_spans(message_entities(&msg.raw)),
+                                    raw: msg.raw.clone(),
                                 };
 
                                 // Stream output immediately (before collecting all results)
@@ -938,7 +990,9 @@ impl App {
 
                                 messages.push(fetched_msg);
 
-                                messages_fetched.fetch_add(1, Ordering::Relaxed);
+                                chat_bar.inc(1);
+                                let total = messages_fetched.fetch_add(1, Ordering::Relaxed) + 1;
+                                chats_bar.set_message(format!("{} messages", total));
                             }
                             Ok(None) => break,
                             Err(e) => {
@@ -951,7 +1005,8 @@ impl App {
                         }
                     }
 
-                    chats_done.fetch_add(1, Ordering::Relaxed);
+                    chat_bar.finish_and_clear();
+                    chats_bar.inc(1);
 
                     ChatSyncTaskResult {
                         chat_id: chat.id,
@@ -973,15 +1028,7 @@ impl App {
             .collect()
             .await;
 
-        // Stop progress reporter
-        cancelled.store(true, Ordering::Relaxed);
-        if let Some(handle) = progress_handle {
-            handle.abort();
-        }
-
-        if show_progress {
-            eprint!("\r\x1b[K"); // Clear line
-        }
+        chats_bar.finish_and_clear();
 
         // Check if we were cancelled
         if cancellation_token.is_cancelled() {
@@ -999,6 +1046,13 @@ impl App {
             if let Some(err) = &result.error {
                 log::warn!("{}", err);
             }
+            // Chats skipped by a shutdown weren't synced
+            if result.error.is_some() || !cancellation_token.is_cancelled() {
+                self.get_store()
+                    .await?
+                    .record_chat_sync(result.chat_id, Utc::now(), result.error.as_deref())
+                    .await?;
+            }
 
             if result.messages.is_empty() && result.highest_msg_id.is_none() {
                 continue;
@@ -1007,9 +1061,9 @@ impl App {
             chats_processed += 1;
 
             // Write messages to store (output was already streamed in the task)
+            let store = self.get_store().await?;
             for msg in &result.messages {
-                self.get_store()
-                    .await?
+                store
                     .upsert_message(UpsertMessageParams {
                         id: msg.id,
                         chat_id: result.chat_id,
@@ -1024,6 +1078,23 @@ impl App {
                         topic_id: msg.topic_id,
                     })
                     .await?;
+                if let (Some(path), Some(sha256)) = (&msg.media_path, &msg.media_sha256) {
+                    store
+                        .set_media_file(result.chat_id, msg.id, None, path, sha256)
+                        .await?;
+                }
+                record_message_details(&store, result.chat_id, msg.id, &msg.raw).await?;
+                if opts.save_shared_contacts {
+                    save_shared_contact(&store, &msg.raw).await?;
+                }
+                record_custom_emoji(
+                    &self.tg.client,
+                    &store,
+                    result.chat_id,
+                    msg.id,
+                    &msg.custom_emoji,
+                )
+                .await?;
                 messages_stored += 1;
             }
 
@@ -1119,22 +1190,19 @@ impl App {
 
         // Prune old messages if --prune-after is set
         if let Some(keep_count) = opts.prune_after {
-            if show_progress {
-                eprint!("Pruning old messages (keeping {} per chat)...", keep_count);
-            }
-            match self.get_store().await?.prune_all_chats(keep_count).await {
+            let bar = progress.spinner(format!(
+                "Pruning old messages (keeping {} per chat)...",
+                keep_count
+            ));
+            let pruned = self.get_store().await?.prune_all_chats(keep_count).await;
+            bar.finish_and_clear();
+            match pruned {
                 Ok(deleted) => {
-                    if show_progress {
-                        eprint!("\r\x1b[K");
-                    }
                     if deleted > 0 {
                         eprintln!("Pruned {} old messages", deleted);
                     }
                 }
                 Err(e) => {
-                    if show_progress {
-                        eprint!("\r\x1b[K");
-                    }
                     log::warn!("Failed to prune messages: {}", e);
                 }
             }
@@ -1146,7 +1214,7 @@ impl App {
             .map(|mut summary| {
                 summary
                     .topics
-                    .sort_by(|a, b| b.messages_synced.cmp(&a.messages_synced));
+                    .sort_by_key(|t| std::cmp::Reverse(t.messages_synced));
                 summary
             })
             .collect();
@@ -1173,26 +1241,29 @@ impl App {
         let ignore_set: HashSet<i64> = opts.ignore_chat_ids.iter().copied().collect();
 
         // Helper to check if a chat should be ignored.
-        let should_ignore = |chat_id: i64, kind: &str| -> bool {
+        let should_ignore = |chat_id: i64, kind: &str, name: &str, is_bot: bool| -> bool {
             if ignore_set.contains(&chat_id) {
                 return true;
             }
             if opts.ignore_channels && kind == "channel" {
                 return true;
             }
+            if opts.skips(kind, name, is_bot) {
+                return true;
+            }
+            if let Some(folder) = &opts.folder {
+                return !folder.contains(chat_id, kind);
+            }
             false
         };
 
         let client = &self.tg.client;
 
-        // Progress tracking
-        let mut last_progress_time = std::time::Instant::now();
-        let progress_interval = Duration::from_millis(500);
+        // Overall status line, plus a bar for the chat currently being fetched
+        let progress = Progress::new(opts.show_progress);
+        let bar = progress.spinner("Syncing... 0 chats, 0 messages");
 
         // Phase 1: Bootstrap — fetch recent dialogs and their messages
-        if opts.show_progress {
-            eprint!("\rSyncing... 0 chats, 0 messages");
-        }
         let mut dialogs = client.iter_dialogs();
         while let Some(dialog) = dialogs
             .next()
@@ -1201,9 +1272,7 @@ impl App {
         {
             // Check for shutdown
             if shutdown_ctrl.is_triggered() {
-                if opts.show_progress {
-                    eprint!("\r\x1b[K");
-                }
+                bar.finish_and_clear();
                 eprintln!("Sync interrupted by shutdown");
                 let per_chat: Vec<ChatSyncSummary> = per_chat_map.into_values().collect();
                 return Ok(SyncResult {
@@ -1217,7 +1286,7 @@ impl App {
             let id = peer.id().bare_id();
 
             // Skip ignored chats.
-            if should_ignore(id, &kind) {
+            if should_ignore(id, &kind, &name, is_bot(peer)) {
                 continue;
             }
 
@@ -1252,10 +1321,16 @@ impl App {
 
             // Track unread_count for filtering output later
             let unread_count = extract_unread_count(&dialog.raw);
+            self.get_store()
+                .await?
+                .set_chat_unread_count(id, unread_count)
+                .await?;
 
             // Fetch messages for this chat
             let peer_ref = PeerRef::from(peer);
             let mut message_iter = client.iter_messages(peer_ref);
+            let store = self.get_store().await?;
+            let chat_bar = progress.task(&name, "messages");
             let mut count = 0;
             let mut latest_ts: Option<DateTime<Utc>> = None;
             let mut highest_msg_id: Option<i64> = None;
@@ -1325,7 +1400,11 @@ impl App {
                 let sender_id = msg.sender().map(|s| s.id().bare_id()).unwrap_or(0);
                 let from_me = msg.outgoing();
 
-                let text = msg.text().to_string();
+                let service = service_action(&msg.raw);
+                let text = match &service {
+                    Some(action) => action.text.clone(),
+                    None => msg.text().to_string(),
+                };
                 let reply_to_id = msg.reply_to_message_id().map(|id| id as i64);
                 let topic_id = if is_forum {
                     extract_topic_id(&msg)
@@ -1339,17 +1418,18 @@ impl App {
                 }
 
                 // Download media if enabled
-                let (media_type, media_path) = if opts.download_media {
+                let media = if opts.download_media {
                     self.download_message_media(&msg, id).await?
                 } else {
-                    (msg.media().map(|_| "media".to_string()), None)
+                    SavedMedia::placeholder(&msg)
                 };
 
-                // Clone media_type for use in output after the move
-                let media_type_out = media_type.clone();
+                let media_type_out = match &service {
+                    Some(_) => Some(SERVICE_MEDIA_TYPE.to_string()),
+                    None => media.media_type.clone(),
+                };
 
-                self.get_store()
-                    .await?
+                store
                     .upsert_message(UpsertMessageParams {
                         id: msg.id() as i64,
                         chat_id: id,
@@ -1358,22 +1438,35 @@ impl App {
                         edit_ts: msg.edit_date(),
                         from_me,
                         text: text.clone(),
-                        media_type,
-                        media_path,
+                        media_type: media.media_type.clone(),
+                        media_path: media.path.clone(),
                         reply_to_id,
                         topic_id,
                     })
                     .await?;
-                messages_stored += 1;
-
-                // Show progress periodically
-                if opts.show_progress && last_progress_time.elapsed() >= progress_interval {
-                    eprint!(
-                        "\rSyncing... {} chats, {} messages",
-                        chats_stored, messages_stored
-                    );
-                    last_progress_time = std::time::Instant::now();
+                if let (Some(path), Some(sha256)) = (&media.path, &media.sha256) {
+                    store
+                        .set_media_file(id, msg.id() as i64, None, path, sha256)
+                        .await?;
+                }
+                record_message_details(&store, id, msg.id() as i64, &msg.raw).await?;
+                if opts.save_shared_contacts {
+                    save_shared_contact(&store, &msg.raw).await?;
                 }
+                record_custom_emoji(
+                    &self.tg.client,
+                    &store,
+                    id,
+                    msg.id() as i64,
+                    &custom_emoji_spans(message_entities(&msg.raw)),
+                )
+                .await?;
+                messages_stored += 1;
+                chat_bar.inc(1);
+                bar.set_message(format!(
+                    "Syncing... {} chats, {} messages",
+                    chats_stored, messages_stored
+                ));
 
                 // Output using SyncMessageOutput struct
                 match opts.output {
@@ -1418,6 +1511,7 @@ impl App {
                     OutputMode::None => {}
                 }
             }
+            chat_bar.finish_and_clear();
 
             // Update chat's last_message_ts
             if let Some(ts) = latest_ts {
@@ -1443,11 +1537,17 @@ impl App {
                     .update_last_sync_message_id(id, high_id)
                     .await?;
             }
+            if !shutdown_ctrl.is_triggered() {
+                self.get_store()
+                    .await?
+                    .record_chat_sync(id, Utc::now(), None)
+                    .await?;
+            }
 
             // If it's a forum, sync topics first so we can get names
             if is_forum {
                 if let Ok(topic_count) = self.sync_topics(id).await {
-                    log::info!("Synced {} topics for forum chat {}", topic_count, id);
+                    log::info!(chat_id = id; "Synced {} topics for forum chat {}", topic_count, id);
                 }
             }
 
@@ -1513,9 +1613,7 @@ impl App {
 
         // Check for shutdown before archived dialogs
         if shutdown_ctrl.is_triggered() {
-            if opts.show_progress {
-                eprint!("\r\x1b[K");
-            }
+            bar.finish_and_clear();
             eprintln!("Sync interrupted by shutdown");
             let per_chat: Vec<ChatSyncSummary> = per_chat_map.into_values().collect();
             return Ok(SyncResult {
@@ -1527,12 +1625,10 @@ impl App {
 
         // Phase 1b: Also sync archived dialogs (folder_id=1) (unless --skip-archived is set)
         if !opts.skip_archived {
-            if opts.show_progress {
-                eprint!(
-                    "\rSyncing archived... {} chats, {} messages",
-                    chats_stored, messages_stored
-                );
-            }
+            bar.set_message(format!(
+                "Syncing archived... {} chats, {} messages",
+                chats_stored, messages_stored
+            ));
 
             let archived_peers = self.fetch_archived_dialogs().await?;
             for peer in archived_peers {
@@ -1545,7 +1641,7 @@ impl App {
                 let id = peer.id().bare_id();
 
                 // Skip ignored chats.
-                if should_ignore(id, &kind) {
+                if should_ignore(id, &kind, &name, is_bot(&peer)) {
                     continue;
                 }
 
@@ -1581,6 +1677,8 @@ impl App {
                 // Fetch messages for this chat
                 let peer_ref = PeerRef::from(&peer);
                 let mut message_iter = client.iter_messages(peer_ref);
+                let store = self.get_store().await?;
+                let chat_bar = progress.task(&name, "messages");
                 let mut count = 0;
                 let mut latest_ts: Option<DateTime<Utc>> = None;
                 let mut highest_msg_id: Option<i64> = None;
@@ -1651,7 +1749,11 @@ impl App {
                     let sender_id = msg.sender().map(|s| s.id().bare_id()).unwrap_or(0);
                     let from_me = msg.outgoing();
 
-                    let text = msg.text().to_string();
+                    let service = service_action(&msg.raw);
+                    let text = match &service {
+                        Some(action) => action.text.clone(),
+                        None => msg.text().to_string(),
+                    };
                     let reply_to_id = msg.reply_to_message_id().map(|id| id as i64);
                     let topic_id = if is_forum {
                         extract_topic_id(&msg)
@@ -1665,14 +1767,13 @@ impl App {
                     }
 
                     // Download media if enabled
-                    let (media_type, media_path) = if opts.download_media {
+                    let media = if opts.download_media {
                         self.download_message_media(&msg, id).await?
                     } else {
-                        (msg.media().map(|_| "media".to_string()), None)
+                        SavedMedia::placeholder(&msg)
                     };
 
-                    self.get_store()
-                        .await?
+                    store
                         .upsert_message(UpsertMessageParams {
                             id: msg.id() as i64,
                             chat_id: id,
@@ -1681,23 +1782,37 @@ impl App {
                             edit_ts: msg.edit_date(),
                             from_me,
                             text: text.clone(),
-                            media_type,
-                            media_path,
+                            media_type: media.media_type.clone(),
+                            media_path: media.path.clone(),
                             reply_to_id,
                             topic_id,
                         })
                         .await?;
-                    messages_stored += 1;
-
-                    // Show progress periodically
-                    if opts.show_progress && last_progress_time.elapsed() >= progress_interval {
-                        eprint!(
-                            "\rSyncing archived... {} chats, {} messages",
-                            chats_stored, messages_stored
-                        );
-                        last_progress_time = std::time::Instant::now();
+                    if let (Some(path), Some(sha256)) = (&media.path, &media.sha256) {
+                        store
+                            .set_media_file(id, msg.id() as i64, None, path, sha256)
+                            .await?;
+                    }
+                    record_message_details(&store, id, msg.id() as i64, &msg.raw).await?;
+                    if opts.save_shared_contacts {
+                        save_shared_contact(&store, &msg.raw).await?;
                     }
+                    record_custom_emoji(
+                        &self.tg.client,
+                        &store,
+                        id,
+                        msg.id() as i64,
+                        &custom_emoji_spans(message_entities(&msg.raw)),
+                    )
+                    .await?;
+                    messages_stored += 1;
+                    chat_bar.inc(1);
+                    bar.set_message(format!(
+                        "Syncing archived... {} chats, {} messages",
+                        chats_stored, messages_stored
+                    ));
                 }
+                chat_bar.finish_and_clear();
 
                 // Update chat's last_message_ts
                 if let Some(ts) = latest_ts {
@@ -1723,6 +1838,12 @@ impl App {
                         .update_last_sync_message_id(id, high_id)
                         .await?;
                 }
+                if !shutdown_ctrl.is_triggered() {
+                    self.get_store()
+                        .await?
+                        .record_chat_sync(id, Utc::now(), None)
+                        .await?;
+                }
 
                 // If it's a forum, sync topics first so we can get names
                 if is_forum {
@@ -1796,10 +1917,7 @@ impl App {
             }
         }
 
-        if opts.show_progress {
-            // Clear progress line and print final status
-            eprint!("\r\x1b[K"); // Clear line
-        }
+        bar.finish_and_clear();
 
         // Check if we were interrupted
         if shutdown_ctrl.is_triggered() {
@@ -1816,22 +1934,19 @@ impl App {
 
         // Prune old messages if --prune-after is set
         if let Some(keep_count) = opts.prune_after {
-            if opts.show_progress {
-                eprint!("Pruning old messages (keeping {} per chat)...", keep_count);
-            }
-            match self.get_store().await?.prune_all_chats(keep_count).await {
+            let bar = progress.spinner(format!(
+                "Pruning old messages (keeping {} per chat)...",
+                keep_count
+            ));
+            let pruned = self.get_store().await?.prune_all_chats(keep_count).await;
+            bar.finish_and_clear();
+            match pruned {
                 Ok(deleted) => {
-                    if opts.show_progress {
-                        eprint!("\r\x1b[K");
-                    }
                     if deleted > 0 {
                         eprintln!("Pruned {} old messages", deleted);
                     }
                 }
                 Err(e) => {
-                    if opts.show_progress {
-                        eprint!("\r\x1b[K");
-                    }
                     log::warn!("Failed to prune messages: {}", e);
                 }
             }
@@ -1843,7 +1958,7 @@ impl App {
             .map(|mut summary| {
                 summary
                     .topics
-                    .sort_by(|a, b| b.messages_synced.cmp(&a.messages_synced));
+                    .sort_by_key(|t| std::cmp::Reverse(t.messages_synced));
                 summary
             })
             .collect();
@@ -1855,6 +1970,65 @@ impl App {
         })
     }
 
+    /// Second phase of `sync --backfill`: once every chat has its recent
+    /// messages, fetch up to `per_chat` messages older than the oldest stored
+    /// one, most recently active chats first.
+    pub async fn backfill_history(
+        &self,
+        opts: &SyncOptions,
+        per_chat: usize,
+    ) -> Result<BackfillResult> {
+        let shutdown_ctrl = shutdown::global();
+        let store = self.get_store().await?;
+        let chats: Vec<Chat> = store
+            .list_chats(None, i64::MAX, None, false, None, ChatSort::Recent)
+            .await?
+            .into_iter()
+            .filter(|chat| opts.keeps(chat))
+            .collect();
+
+        let progress = Progress::new(opts.show_progress);
+        let bar = progress.items(chats.len() as u64, "chats");
+        bar.set_message("Backfilling older history");
+        let mut result = BackfillResult::default();
+        for chat in &chats {
+            if shutdown_ctrl.is_triggered() {
+                break;
+            }
+            // Chats without stored messages have no history to go back from
+            let oldest_id = store.get_oldest_message_id(chat.id, None).await?;
+            if let Some(oldest_id) = oldest_id.filter(|&id| id > 1) {
+                match self
+                    .backfill_messages(chat.id, None, Some(oldest_id), per_chat)
+                    .await
+                {
+                    Ok(0) => {}
+                    Ok(fetched) => {
+                        result.chats += 1;
+                        result.messages += fetched as u64;
+                        bar.set_message(format!(
+                            "Backfilling older history: {} messages",
+                            result.messages
+                        ));
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to backfill chat {} ({}): {}", chat.name, chat.id, e);
+                        store
+                            .record_chat_sync(
+                                chat.id,
+                                Utc::now(),
+                                Some(&format!("Backfill failed: {}", e)),
+                            )
+                            .await?;
+                    }
+                }
+            }
+            bar.inc(1);
+        }
+        bar.finish_and_clear();
+        Ok(result)
+    }
+
     /// Fetch archived dialogs (folder_id=1) using raw API.
     /// Returns a Vec of Peer objects (resolved from users/chats).
     async fn fetch_archived_dialogs(&self) -> Result<Vec<Peer>> {
@@ -1876,7 +2050,6 @@ impl App {
 
             let response = self
                 .tg
-                .client
                 .invoke(&request)
                 .await
                 .context("Failed to fetch archived dialogs")?;
@@ -2110,54 +2283,85 @@ fn resolve_peer_from_session_static(
     None
 }
 
+/// Media found on a message. `path` is set once the file is on disk, and
+/// `sha256` only when this call downloaded it (an existing file keeps the
+/// checksum recorded at its original download).
+#[derive(Debug, Default)]
+pub(crate) struct SavedMedia {
+    pub media_type: Option<String>,
+    pub path: Option<String>,
+    pub sha256: Option<String>,
+}
+
+impl SavedMedia {
+    /// Media type only, for syncs that don't download files.
+    fn placeholder(msg: &TgMessage) -> Self {
+        SavedMedia {
+            media_type: msg.media().map(|_| "media".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Where synced media lives: `{store_dir}/media/{chat_id}/{message_id}.{ext}`
+fn media_file_path(store_dir: &str, chat_id: i64, msg_id: i64, ext: &str) -> PathBuf {
+    Path::new(store_dir)
+        .join("media")
+        .join(chat_id.to_string())
+        .join(format!("{}.{}", msg_id, ext))
+}
+
 /// Static version of download_message_media for use in async tasks
 async fn download_message_media_static(
     client: &Client,
     msg: &TgMessage,
     chat_id: i64,
     store_dir: &str,
-) -> Result<(Option<String>, Option<String>)> {
+) -> Result<SavedMedia> {
     let media = match msg.media() {
         Some(m) => m,
-        None => return Ok((None, None)),
+        None => return Ok(SavedMedia::default()),
     };
 
     let (media_type, ext) = media_info(&media);
 
     // Skip non-downloadable media types
     if ext.is_empty() {
-        return Ok((Some(media_type), None));
+        return Ok(SavedMedia {
+            media_type: Some(media_type),
+            ..Default::default()
+        });
     }
 
-    // Build path: {store_dir}/media/{chat_id}/{message_id}.{ext}
-    let media_dir = Path::new(store_dir).join("media").join(chat_id.to_string());
-
-    std::fs::create_dir_all(&media_dir)?;
-
-    let file_name = format!("{}.{}", msg.id(), ext);
-    let file_path = media_dir.join(&file_name);
+    let file_path = media_file_path(store_dir, chat_id, msg.id() as i64, &ext);
+    if let Some(dir) = file_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
 
     // Skip if file already exists (idempotent)
     if file_path.exists() {
-        return Ok((
-            Some(media_type),
-            Some(file_path.to_string_lossy().to_string()),
-        ));
+        return Ok(SavedMedia {
+            media_type: Some(media_type),
+            path: Some(file_path.to_string_lossy().to_string()),
+            sha256: None,
+        });
     }
 
     // Download the media
-    match client.download_media(&media, &file_path).await {
-        Ok(()) => {
+    match transfer::download_to_file(client, &media, &file_path).await {
+        Ok(downloaded) => {
             log::info!(
-                "Downloaded media: chat={} msg={} -> {}",
+                "Downloaded media: chat={} msg={} -> {} ({} bytes)",
                 chat_id,
                 msg.id(),
-                file_path.display()
+                file_path.display(),
+                downloaded.size
             );
-            Ok((
-                Some(media_type),
-                Some(file_path.to_string_lossy().to_string()),
-            ))
+            Ok(SavedMedia {
+                media_type: Some(media_type),
+                path: Some(file_path.to_string_lossy().to_string()),
+                sha256: Some(downloaded.sha256),
+            })
         }
         Err(e) => {
             log::warn!(
@@ -2166,7 +2370,11 @@ async fn download_message_media_static(
                 msg.id(),
                 e
             );
-            Ok((Some(media_type), None))
+            // Return media type but no path on failure
+            Ok(SavedMedia {
+                media_type: Some(media_type),
+                ..Default::default()
+            })
         }
     }
 }
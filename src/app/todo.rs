@@ -0,0 +1,129 @@
+//! Todo lists (checklists): sending them and ticking their items.
+
+use crate::app::App;
+use crate::store::UpsertMessageParams;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use grammers_tl_types as tl;
+use rand::Rng;
+
+fn plain(text: &str) -> tl::enums::TextWithEntities {
+    tl::enums::TextWithEntities::Entities(tl::types::TextWithEntities {
+        text: text.to_string(),
+        entities: vec![],
+    })
+}
+
+impl App {
+    /// Send a todo list, returns the message ID. Items are numbered from 1,
+    /// which is what `todo_check` takes.
+    pub async fn send_todo(&mut self, chat_id: i64, title: &str, items: &[String]) -> Result<i64> {
+        crate::guard::check_send(chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let input_peer: tl::enums::InputPeer = peer_ref.into();
+
+        let list = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                tl::enums::TodoItem::Item(tl::types::TodoItem {
+                    id: i as i32 + 1,
+                    title: plain(item),
+                })
+            })
+            .collect();
+        let input_media = tl::enums::InputMedia::Todo(tl::types::InputMediaTodo {
+            todo: tl::enums::TodoList::List(tl::types::TodoList {
+                others_can_append: false,
+                others_can_complete: true,
+                title: plain(title),
+                list,
+            }),
+        });
+
+        let request = tl::functions::messages::SendMedia {
+            silent: false,
+            background: false,
+            clear_draft: false,
+            noforwards: false,
+            update_stickersets_order: false,
+            invert_media: false,
+            allow_paid_floodskip: false,
+            peer: input_peer,
+            reply_to: None,
+            media: input_media,
+            message: String::new(),
+            random_id: rand::rng().random(),
+            reply_markup: None,
+            entities: None,
+            schedule_date: None,
+            send_as: None,
+            quick_reply_shortcut: None,
+            effect: None,
+            allow_paid_stars: None,
+            suggested_post: None,
+        };
+
+        let updates = self
+            .tg
+            .invoke(&request)
+            .await
+            .context(format!("Failed to send todo list to chat {}", chat_id))?;
+        let msg_id = Self::extract_message_id_from_updates(&updates)?;
+
+        let now = Utc::now();
+        let store = self.get_store().await?;
+        store
+            .upsert_message(UpsertMessageParams {
+                id: msg_id,
+                chat_id,
+                sender_id: 0,
+                ts: now,
+                edit_ts: None,
+                from_me: true,
+                text: title.to_string(),
+                media_type: Some("todo".to_string()),
+                media_path: None,
+                reply_to_id: None,
+                topic_id: None,
+            })
+            .await?;
+        store
+            .upsert_chat(chat_id, "user", "", None, Some(now), false, None, false)
+            .await?;
+
+        Ok(msg_id)
+    }
+
+    /// Tick (or untick, with `completed = false`) items of a todo list.
+    pub async fn todo_check(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        items: &[i32],
+        completed: bool,
+    ) -> Result<()> {
+        crate::guard::check_chat("update checklists", chat_id)?;
+        let peer_ref = self.resolve_peer_ref(chat_id).await?;
+        let input_peer: tl::enums::InputPeer = peer_ref.into();
+
+        let (completed, incompleted) = if completed {
+            (items.to_vec(), Vec::new())
+        } else {
+            (Vec::new(), items.to_vec())
+        };
+        self.tg
+            .invoke(&tl::functions::messages::ToggleTodoCompleted {
+                peer: input_peer,
+                msg_id: msg_id as i32,
+                completed,
+                incompleted,
+            })
+            .await
+            .context(format!(
+                "Failed to update todo list {} in chat {}",
+                msg_id, chat_id
+            ))?;
+        Ok(())
+    }
+}
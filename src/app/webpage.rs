@@ -0,0 +1,37 @@
+//! Link previews (`MessageMediaWebPage`) attached to messages.
+
+use crate::store::{url_domain, MessageWebpage};
+use grammers_tl_types as tl;
+
+/// The link preview of a raw message (None if it has none).
+pub(crate) fn message_webpage(raw: &tl::enums::Message) -> Option<MessageWebpage> {
+    let tl::enums::Message::Message(m) = raw else {
+        return None;
+    };
+    let Some(tl::enums::MessageMedia::WebPage(media)) = &m.media else {
+        return None;
+    };
+    match &media.webpage {
+        tl::enums::WebPage::Page(page) => Some(MessageWebpage {
+            domain: url_domain(&page.url),
+            url: page.url.clone(),
+            site_name: page.site_name.clone(),
+            title: page.title.clone(),
+            description: page.description.clone(),
+        }),
+        // Telegram hasn't fetched the page (yet): only the URL is known
+        tl::enums::WebPage::Pending(page) => url_only(page.url.as_deref()?),
+        tl::enums::WebPage::Empty(page) => url_only(page.url.as_deref()?),
+        tl::enums::WebPage::NotModified(_) => None,
+    }
+}
+
+fn url_only(url: &str) -> Option<MessageWebpage> {
+    Some(MessageWebpage {
+        url: url.to_string(),
+        domain: url_domain(url),
+        site_name: None,
+        title: None,
+        description: None,
+    })
+}
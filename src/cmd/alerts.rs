@@ -0,0 +1,165 @@
+use crate::alerts::{alert_matches, fire, AlertEvent, Notify};
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::{Context, Result};
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AlertsCommand {
+    /// Add a keyword alert, evaluated by `tgcli daemon` on incoming messages
+    Add {
+        /// Alternatives separated by | (case-insensitive), e.g. "invoice|urgent"
+        #[arg(long)]
+        pattern: String,
+        /// Only messages in this chat
+        #[arg(
+            long,
+            value_parser = crate::config::parse_chat,
+            required_unless_present = "chat_any",
+            conflicts_with = "chat_any"
+        )]
+        chat: Option<i64>,
+        /// Messages in any chat
+        #[arg(long)]
+        chat_any: bool,
+        /// How to deliver the alert
+        #[arg(long, value_enum)]
+        notify: Notify,
+        /// URL to POST the matched message to (--notify webhook)
+        #[arg(long)]
+        url: Option<String>,
+        /// Shell command to run, gets the message as JSON on stdin (--notify command)
+        #[arg(long)]
+        command: Option<String>,
+    },
+    /// List alerts
+    List,
+    /// Remove an alert
+    Remove {
+        /// Alert ID (see `alerts list`)
+        id: i64,
+    },
+    /// Check an alert against a sample text and send a test notification
+    Test {
+        /// Alert ID (see `alerts list`)
+        id: i64,
+        /// Sample message text (default: notify without matching)
+        #[arg(long)]
+        text: Option<String>,
+        /// Chat the sample message is in
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+    },
+}
+
+pub async fn run(cli: &Cli, cmd: &AlertsCommand) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    match cmd {
+        AlertsCommand::Add {
+            pattern,
+            chat,
+            chat_any: _,
+            notify,
+            url,
+            command,
+        } => {
+            if pattern.split('|').all(|alt| alt.trim().is_empty()) {
+                anyhow::bail!("--pattern needs at least one non-empty alternative");
+            }
+            let target = match notify {
+                Notify::Desktop => None,
+                Notify::Webhook => Some(url.as_deref().context("--notify webhook needs --url")?),
+                Notify::Command => Some(
+                    command
+                        .as_deref()
+                        .context("--notify command needs --command")?,
+                ),
+            };
+            let id = store
+                .add_alert(pattern, *chat, &notify.to_string(), target)
+                .await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "id": id,
+                    "pattern": pattern,
+                    "chat_id": chat,
+                    "notify": notify.to_string(),
+                }))?;
+            } else {
+                println!(
+                    "Added alert {}. It runs while `tgcli daemon` is running.",
+                    id
+                );
+            }
+        }
+        AlertsCommand::List => {
+            let alerts = store.list_alerts().await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "count": alerts.len(),
+                    "alerts": alerts,
+                }))?;
+            } else if alerts.is_empty() {
+                println!("No alerts. Add one with `tgcli alerts add`.");
+            } else {
+                cli.output.write(&alerts)?;
+            }
+        }
+        AlertsCommand::Remove { id } => {
+            if !store.remove_alert(*id).await? {
+                anyhow::bail!("No alert with ID {}", id);
+            }
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "removed": id }))?;
+            } else {
+                println!("Removed alert {}.", id);
+            }
+        }
+        AlertsCommand::Test { id, text, chat } => {
+            let alert = store
+                .list_alerts()
+                .await?
+                .into_iter()
+                .find(|a| a.id == *id)
+                .with_context(|| format!("No alert with ID {}", id))?;
+            let chat_id = chat.or(alert.chat_id).unwrap_or(0);
+            let matched = text.as_deref().map(|t| alert_matches(&alert, chat_id, t));
+
+            if matched != Some(false) {
+                let chat_name = match store.get_chat(chat_id).await? {
+                    Some(c) => c.name,
+                    None => "tgcli alert test".to_string(),
+                };
+                let event = AlertEvent {
+                    alert_id: alert.id,
+                    pattern: alert.pattern.clone(),
+                    chat_id,
+                    chat_name,
+                    msg_id: 0,
+                    sender_id: 0,
+                    ts: chrono::Utc::now().to_rfc3339(),
+                    text: text
+                        .clone()
+                        .unwrap_or_else(|| format!("Test of alert \"{}\"", alert.pattern)),
+                };
+                fire(&alert, &event).await?;
+            }
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "id": id,
+                    "matched": matched,
+                    "notified": matched != Some(false),
+                }))?;
+            } else if matched == Some(false) {
+                println!("Alert {} does not match that text; nothing sent.", id);
+            } else {
+                println!("Sent a test notification via {}.", alert.notify);
+            }
+        }
+    }
+    Ok(())
+}
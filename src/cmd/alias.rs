@@ -0,0 +1,103 @@
+use crate::config::Config;
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+use serde::Serialize;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AliasCommand {
+    /// Name a chat, then use the name wherever a chat ID goes (`--chat boss`)
+    Set {
+        /// Alias (letters, digits, - and _)
+        name: String,
+        /// Chat ID
+        chat: i64,
+    },
+    /// Remove an alias
+    Remove { name: String },
+    /// List aliases
+    List,
+}
+
+#[derive(Serialize)]
+struct AliasInfo {
+    alias: String,
+    chat_id: i64,
+    name: Option<String>,
+}
+
+fn validate_alias(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!("Alias '{}' may only use letters, digits, - and _", name);
+    }
+    if name.parse::<i64>().is_ok() {
+        anyhow::bail!("Alias '{}' would be read as a chat ID", name);
+    }
+    Ok(())
+}
+
+pub async fn run(cli: &Cli, cmd: &AliasCommand) -> Result<()> {
+    let config_dir = cli.config_dir();
+    let mut config = Config::load(&config_dir)?;
+
+    match cmd {
+        AliasCommand::Set { name, chat } => {
+            validate_alias(name)?;
+            let previous = config.aliases.insert(name.clone(), *chat);
+            config.save(&config_dir)?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "alias": name,
+                    "chat_id": chat,
+                    "previous": previous,
+                }))?;
+            } else {
+                match previous {
+                    Some(old) if old != *chat => {
+                        println!("{} now points to {} (was {}).", name, chat, old)
+                    }
+                    _ => println!("{} points to {}.", name, chat),
+                }
+            }
+        }
+        AliasCommand::Remove { name } => {
+            let Some(chat) = config.aliases.remove(name) else {
+                anyhow::bail!("No alias '{}'", name);
+            };
+            config.save(&config_dir)?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "removed": name, "chat_id": chat }))?;
+            } else {
+                println!("Removed alias {} ({}).", name, chat);
+            }
+        }
+        AliasCommand::List => {
+            let store = Store::open(&cli.store_dir()).await?;
+            let mut aliases = Vec::new();
+            for (alias, chat_id) in &config.aliases {
+                aliases.push(AliasInfo {
+                    alias: alias.clone(),
+                    chat_id: *chat_id,
+                    name: store.get_chat(*chat_id).await?.map(|c| c.name),
+                });
+            }
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "aliases": aliases }))?;
+            } else if aliases.is_empty() {
+                println!("No aliases. Add one with `tgcli alias set <name> <chat_id>`.");
+            } else {
+                cli.output.write_titled(&aliases, "Aliases")?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use crate::cmd::messages::parse_time;
+use crate::guard::Attempt;
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AuditCommand {
+    /// Changes made through tgcli (sends, edits, deletes, bans, ...), newest first
+    List {
+        /// Only entries after this time (RFC 3339, YYYY-MM-DD, or 12h/30d/2w)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only changes to this chat
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+        /// Only changes that failed or were refused
+        #[arg(long)]
+        failed: bool,
+        #[arg(long, default_value = "100")]
+        limit: i64,
+    },
+}
+
+/// Write changes from [`crate::guard::take_attempts`] to the audit log.
+pub(crate) async fn record(store: &Store, command: &str, attempts: Vec<Attempt>) -> Result<()> {
+    for attempt in attempts {
+        store
+            .add_audit_entry(
+                attempt.ts,
+                command,
+                &attempt.action,
+                attempt.chat_id,
+                attempt.error.as_deref(),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn run(cli: &Cli, cmd: &AuditCommand) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    match cmd {
+        AuditCommand::List {
+            since,
+            chat,
+            failed,
+            limit,
+        } => {
+            let since = since.as_deref().map(parse_time).transpose()?;
+            let entries = store.audit_entries(since, *chat, *failed, *limit).await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "entries": entries }))?;
+            } else if entries.is_empty() {
+                println!("No audited changes.");
+            } else {
+                cli.output.write(&entries)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use crate::app::App;
+use crate::config::Config;
 use crate::out;
-use crate::tg;
 use crate::Cli;
 use anyhow::{Context, Result};
 use clap::Args;
@@ -28,7 +28,16 @@ pub async fn run(cli: &Cli, args: &AuthArgs) -> Result<()> {
     }
 }
 
-async fn interactive_auth(cli: &Cli) -> Result<()> {
+pub async fn interactive_auth(cli: &Cli) -> Result<()> {
+    // Custom API credentials belong to this store's session from now on
+    if cli.api_id.is_some() || cli.api_hash.is_some() {
+        let config_dir = cli.config_dir();
+        let mut config = Config::load(&config_dir)?;
+        config.api_id = cli.api_id.or(config.api_id);
+        config.api_hash = cli.api_hash.clone().or(config.api_hash);
+        config.save(&config_dir)?;
+    }
+
     let app = App::new_unauthed(cli).await?;
     let client = &app.tg.client;
 
@@ -47,7 +56,7 @@ async fn interactive_auth(cli: &Cli) -> Result<()> {
 
     // Request login code
     let token = client
-        .request_login_code(&phone, tg::API_HASH)
+        .request_login_code(&phone, &cli.connect_options().api.api_hash)
         .await
         .with_context(|| format!("Failed to request login code for {}", phone))?;
     eprintln!("Login code sent via Telegram.");
@@ -106,7 +115,7 @@ async fn interactive_auth(cli: &Cli) -> Result<()> {
 
 async fn status(cli: &Cli) -> Result<()> {
     let store_dir = cli.store_dir();
-    let session_path = format!("{}/session.db", store_dir);
+    let session_path = crate::paths::session_path(&store_dir);
 
     if !std::path::Path::new(&session_path).exists() {
         if cli.output.is_json() {
@@ -149,7 +158,7 @@ async fn status(cli: &Cli) -> Result<()> {
 
 async fn logout(cli: &Cli) -> Result<()> {
     let store_dir = cli.store_dir();
-    let session_path = format!("{}/session.db", store_dir);
+    let session_path = crate::paths::session_path(&store_dir);
 
     if !std::path::Path::new(&session_path).exists() {
         anyhow::bail!("No session found. Nothing to logout from.");
@@ -0,0 +1,144 @@
+use crate::out;
+use crate::store::{AwayMode, Store};
+use crate::Cli;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use clap::{ArgAction, Subcommand};
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AwayCommand {
+    /// Answer private messages automatically while `tgcli daemon` runs.
+    /// Turns itself off when you send a message yourself.
+    On {
+        /// Reply text
+        #[arg(long)]
+        message: String,
+        /// Turn off at this time: HH:MM (next occurrence), "YYYY-MM-DD HH:MM" or RFC3339
+        #[arg(long)]
+        until: Option<String>,
+        /// Answer each person at most once per this many minutes
+        #[arg(long, default_value = "60")]
+        interval: i64,
+        /// Only answer this user (can be specified multiple times)
+        #[arg(long, action = ArgAction::Append)]
+        allow: Vec<i64>,
+        /// Never answer this user (can be specified multiple times)
+        #[arg(long, action = ArgAction::Append)]
+        deny: Vec<i64>,
+    },
+    /// Turn away mode off
+    Off,
+    /// Show whether away mode is on
+    Status,
+}
+
+/// "18:00" is the next 18:00 (today or tomorrow); anything else is a date.
+fn parse_until(s: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = NaiveTime::parse_from_str(s, "%H:%M") {
+        let now = Local::now();
+        let mut day = now.date_naive();
+        if time <= now.time() {
+            day += Duration::days(1);
+        }
+        let until = Local
+            .from_local_datetime(&day.and_time(time))
+            .earliest()
+            .ok_or_else(|| anyhow::anyhow!("{} doesn't exist in local time", s))?;
+        return Ok(until.with_timezone(&Utc));
+    }
+    let until = crate::cmd::messages::parse_time(s)?;
+    if until <= Utc::now() {
+        anyhow::bail!("--until {} is in the past", s);
+    }
+    Ok(until)
+}
+
+pub async fn run(cli: &Cli, cmd: &AwayCommand) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    match cmd {
+        AwayCommand::On {
+            message,
+            until,
+            interval,
+            allow,
+            deny,
+        } => {
+            if *interval < 1 {
+                anyhow::bail!("--interval must be at least 1 minute");
+            }
+            let away = AwayMode {
+                message: message.clone(),
+                since: Utc::now(),
+                until: until.as_deref().map(parse_until).transpose()?,
+                interval_minutes: *interval,
+                allow: allow.clone(),
+                deny: deny.clone(),
+            };
+            store.set_away_mode(&away).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&away)?;
+            } else {
+                match away.until {
+                    Some(until) => println!(
+                        "Away mode on until {}. Replies are sent while `tgcli daemon` is running.",
+                        until.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                    ),
+                    None => println!(
+                        "Away mode on until you send a message. Replies are sent while `tgcli daemon` is running."
+                    ),
+                }
+            }
+        }
+        AwayCommand::Off => {
+            let was_on = store.away_mode().await?.is_some();
+            let replied = store.clear_away_mode().await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "was_on": was_on,
+                    "replied": replied,
+                }))?;
+            } else if was_on {
+                println!("Away mode off ({} people were answered).", replied);
+            } else {
+                println!("Away mode was already off.");
+            }
+        }
+        AwayCommand::Status => {
+            let away = store.away_mode().await?;
+            // An expired --until only clears once the daemon sees a message
+            let away = away.filter(|a| a.until.is_none_or(|until| until > Utc::now()));
+            let replied = match &away {
+                Some(_) => store.count_away_replies().await?,
+                None => 0,
+            };
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "on": away.is_some(),
+                    "away": away,
+                    "replied": replied,
+                }))?;
+            } else if let Some(away) = away {
+                println!(
+                    "Away mode on since {}{}.",
+                    away.since.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    away.until
+                        .map(|t| format!(
+                            ", until {}",
+                            t.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                        ))
+                        .unwrap_or_default()
+                );
+                println!("Message: {}", away.message);
+                println!(
+                    "Answered {} people (at most once per {} minutes each).",
+                    replied, away.interval_minutes
+                );
+            } else {
+                println!("Away mode is off.");
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,13 +1,15 @@
+use crate::app::report::ReportReason;
 use crate::app::App;
 use crate::out;
-use crate::store::Store;
+use crate::store::{ChatMember, ChatSort, Store};
 use crate::Cli;
 use anyhow::Result;
-use clap::{ArgAction, Subcommand};
-use grammers_session::defs::{PeerAuth, PeerId, PeerRef};
+use clap::{ArgAction, Subcommand, ValueEnum};
+use grammers_session::defs::{PeerAuth, PeerId, PeerKind, PeerRef};
 use grammers_session::Session;
 use grammers_tl_types as tl;
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum ChatsCommand {
@@ -22,55 +24,126 @@ pub enum ChatsCommand {
         /// Filter by folder ID (fetches from Telegram API)
         #[arg(long)]
         folder: Option<i32>,
+        /// Show only chats of a folder recorded by `tgcli sync --folder` (local DB)
+        #[arg(long, value_name = "NAME", conflicts_with = "folder")]
+        in_folder: Option<String>,
         /// Show only archived chats (from local DB, or API with --folder 1)
         #[arg(long)]
         archived: bool,
         /// Show only non-archived chats (local DB filter)
         #[arg(long, conflicts_with = "archived")]
         active: bool,
+        /// Show only chats with unread messages (as of the last sync)
+        #[arg(long)]
+        unread_only: bool,
+        /// Sort order
+        #[arg(long, value_enum, default_value = "recent")]
+        sort: ChatSort,
     },
     /// Show a single chat
+    #[command(visible_alias = "info")]
     Show {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
         id: i64,
+        /// Also ask Telegram how many messages and media files the chat has,
+        /// to plan a full backfill
+        #[arg(long)]
+        count_messages: bool,
     },
-    /// Delete a chat from local database
+    /// Delete a chat from the local database (--hard also deletes it on Telegram)
     Delete {
         /// Chat ID to delete
         chat_id: i64,
         /// Soft delete (local DB only, default behavior)
         #[arg(long, default_value = "true")]
         soft: bool,
-        /// Hard delete (also delete from Telegram) - NOT IMPLEMENTED
+        /// Hard delete: also delete the chat on Telegram (private chats are
+        /// deleted for both sides; groups and channels are left)
         #[arg(long, conflicts_with = "soft")]
         hard: bool,
+        /// Skip the confirmation prompt for --hard
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Clear a chat's message history on Telegram (and in the local database)
+    ClearHistory {
+        /// Chat ID
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
+        id: i64,
+        /// Delete the messages for all participants, not just for you
+        #[arg(long)]
+        for_everyone: bool,
+        /// Keep messages from the last N days
+        #[arg(long, value_name = "DAYS")]
+        keep_days: Option<u32>,
+        /// Skip the confirmation prompt for --for-everyone
+        #[arg(long, short = 'y')]
+        yes: bool,
     },
     /// List members of a group or channel
     Members {
         /// Chat ID (group or channel)
-        #[arg(long)]
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
         id: i64,
         /// Limit results (0 = all)
         #[arg(long, default_value = "100")]
         limit: usize,
+        /// Only list admins, bots, banned users or recent members
+        #[arg(long, value_enum)]
+        filter: Option<MemberFilter>,
+        /// Only list members whose name or username contains this
+        #[arg(long)]
+        search: Option<String>,
+        /// Write CSV instead of a table (for spreadsheets and audits)
+        #[arg(long)]
+        csv: bool,
+        /// Record the full member list for `chats members-diff` (ignores --limit)
+        #[arg(long, conflicts_with_all = ["filter", "search"])]
+        snapshot: bool,
+    },
+    /// Who joined or left between member snapshots (see `members --snapshot`)
+    MembersDiff {
+        /// Chat ID (group or channel)
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
+        id: i64,
+        /// Compare against the last snapshot taken at or before this time
+        /// (RFC3339, YYYY-MM-DD, "yesterday", "7 days ago"); defaults to the
+        /// snapshot before the latest
+        #[arg(long)]
+        since: Option<String>,
     },
     /// Archive chats (move to Archive folder)
     Archive {
         /// Chat ID(s) to archive (can be specified multiple times)
-        #[arg(long, action = ArgAction::Append)]
+        #[arg(
+            long,
+            action = ArgAction::Append,
+            value_name = "CHAT_ID",
+            value_parser = crate::config::parse_chat
+        )]
         id: Vec<i64>,
     },
     /// Unarchive chats (move out of Archive folder)
     Unarchive {
         /// Chat ID(s) to unarchive (can be specified multiple times)
-        #[arg(long, action = ArgAction::Append)]
+        #[arg(
+            long,
+            action = ArgAction::Append,
+            value_name = "CHAT_ID",
+            value_parser = crate::config::parse_chat
+        )]
         id: Vec<i64>,
     },
     /// Pin chats
     Pin {
         /// Chat ID(s) to pin (can be specified multiple times)
-        #[arg(long, action = ArgAction::Append)]
+        #[arg(
+            long,
+            action = ArgAction::Append,
+            value_name = "CHAT_ID",
+            value_parser = crate::config::parse_chat
+        )]
         id: Vec<i64>,
         /// Folder ID (0 = main chat list, 1 = archive, etc.)
         #[arg(long, default_value = "0")]
@@ -79,16 +152,29 @@ pub enum ChatsCommand {
     /// Unpin chats
     Unpin {
         /// Chat ID(s) to unpin (can be specified multiple times)
-        #[arg(long, action = ArgAction::Append)]
+        #[arg(
+            long,
+            action = ArgAction::Append,
+            value_name = "CHAT_ID",
+            value_parser = crate::config::parse_chat
+        )]
         id: Vec<i64>,
+        /// Unpin every pinned chat in the folder
+        #[arg(long, conflicts_with = "id")]
+        all: bool,
         /// Folder ID (0 = main chat list, 1 = archive, etc.)
         #[arg(long, default_value = "0")]
         folder: i32,
     },
+    /// List or reorder pinned chats
+    Pinned {
+        #[command(subcommand)]
+        cmd: PinnedCommand,
+    },
     /// Ban a user from a group/channel
     Ban {
         /// Chat ID (group or channel)
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// User ID to ban
         #[arg(long)]
@@ -100,7 +186,7 @@ pub enum ChatsCommand {
     /// Kick a user from a group/channel (they can rejoin)
     Kick {
         /// Chat ID (group or channel)
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// User ID to kick
         #[arg(long)]
@@ -109,7 +195,7 @@ pub enum ChatsCommand {
     /// Unban a user from a group/channel
     Unban {
         /// Chat ID (group or channel)
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// User ID to unban
         #[arg(long)]
@@ -118,7 +204,7 @@ pub enum ChatsCommand {
     /// Promote a user to admin in a group/channel
     Promote {
         /// Chat ID (group or channel)
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// User ID to promote
         #[arg(long)]
@@ -130,7 +216,7 @@ pub enum ChatsCommand {
     /// Demote an admin to regular user
     Demote {
         /// Chat ID (group or channel)
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// User ID to demote
         #[arg(long)]
@@ -168,13 +254,13 @@ pub enum ChatsCommand {
     /// Leave a group or channel
     Leave {
         /// Chat ID to leave
-        #[arg(long)]
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
         id: i64,
     },
     /// Get or create invite links for a chat
     InviteLink {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
         id: i64,
         /// Create a new invite link (instead of getting existing)
         #[arg(long)]
@@ -189,7 +275,7 @@ pub enum ChatsCommand {
     /// Mute notifications for a chat
     Mute {
         /// Chat ID to mute
-        #[arg(long)]
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
         id: i64,
         /// Mute duration (e.g., "1h", "8h", "1d", "forever")
         #[arg(long, default_value = "forever")]
@@ -198,11 +284,75 @@ pub enum ChatsCommand {
     /// Unmute notifications for a chat
     Unmute {
         /// Chat ID to unmute
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
+        id: i64,
+    },
+    /// Show a channel's boost level and boosters, or boost it with --apply
+    Boosts {
+        /// Chat ID (channel or supergroup)
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
+        id: i64,
+        /// Boost the chat with one of your Premium boost slots
         #[arg(long)]
+        apply: bool,
+        /// Boost slot to use with --apply (see the slot list; default: any free slot)
+        #[arg(long, requires = "apply")]
+        slot: Vec<i32>,
+        /// Maximum number of boosters to list
+        #[arg(long, default_value = "50")]
+        limit: i32,
+    },
+    /// Report a chat or user to Telegram
+    Report {
+        /// Chat ID to report
+        #[arg(long, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
         id: i64,
+        /// Reason for the report
+        #[arg(long, value_enum)]
+        reason: ReportReason,
+        /// Additional details for the moderators
+        #[arg(long)]
+        comment: Option<String>,
     },
 }
 
+#[derive(Subcommand, Debug, Clone)]
+pub enum PinnedCommand {
+    /// List pinned chats in pin order
+    List {
+        /// Folder ID (0 = main chat list, 1 = archive, etc.)
+        #[arg(long, default_value = "0")]
+        folder: i32,
+    },
+    /// Set the pin order (chats not listed stay pinned after these)
+    Reorder {
+        /// Chat IDs in the desired order (can be specified multiple times)
+        #[arg(
+            long,
+            action = ArgAction::Append,
+            required = true,
+            value_name = "CHAT_ID",
+            value_parser = crate::config::parse_chat
+        )]
+        id: Vec<i64>,
+        /// Folder ID (0 = main chat list, 1 = archive, etc.)
+        #[arg(long, default_value = "0")]
+        folder: i32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MemberFilter {
+    /// Members, most recently joined first
+    Recent,
+    /// Creator and admins
+    Admins,
+    /// Bots
+    Bots,
+    /// Banned and kicked users
+    Banned,
+}
+
 #[derive(Serialize)]
 struct MemberInfo {
     id: i64,
@@ -211,6 +361,207 @@ struct MemberInfo {
     last_name: Option<String>,
     status: String,
     role: String,
+    bot: bool,
+    /// When they joined (or were promoted/banned), RFC 3339
+    joined: Option<String>,
+    inviter_id: Option<i64>,
+}
+
+impl MemberInfo {
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        [&self.username, &self.first_name, &self.last_name]
+            .into_iter()
+            .flatten()
+            .any(|s| s.to_lowercase().contains(&query))
+    }
+}
+
+fn member_name(first: Option<&str>, last: Option<&str>) -> String {
+    [first, last]
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A member who joined or left between two snapshots.
+#[derive(Serialize)]
+struct MemberChange {
+    change: &'static str,
+    user_id: i64,
+    username: Option<String>,
+    name: String,
+    role: String,
+}
+
+fn member_changes(before: &[ChatMember], after: &[ChatMember]) -> Vec<MemberChange> {
+    let before_ids: HashSet<i64> = before.iter().map(|m| m.user_id).collect();
+    let after_ids: HashSet<i64> = after.iter().map(|m| m.user_id).collect();
+    let change = |change, m: &ChatMember| MemberChange {
+        change,
+        user_id: m.user_id,
+        username: m.username.clone(),
+        name: m.name.clone(),
+        role: m.role.clone(),
+    };
+    let joined = after
+        .iter()
+        .filter(|m| !before_ids.contains(&m.user_id))
+        .map(|m| change("joined", m));
+    let left = before
+        .iter()
+        .filter(|m| !after_ids.contains(&m.user_id))
+        .map(|m| change("left", m));
+    joined.chain(left).collect()
+}
+
+const MEMBER_PAGE: i32 = 200;
+
+/// Members of a channel or supergroup, paging `channels.getParticipants`
+/// until `limit` (0 = all) or the end of the list.
+async fn channel_members(
+    app: &App,
+    peer_ref: PeerRef,
+    filter: Option<MemberFilter>,
+    search: Option<&str>,
+    limit: usize,
+) -> Result<Vec<MemberInfo>> {
+    let q = search.unwrap_or_default().to_string();
+    let tl_filter = match filter {
+        None | Some(MemberFilter::Recent) if !q.is_empty() => {
+            tl::enums::ChannelParticipantsFilter::ChannelParticipantsSearch(
+                tl::types::ChannelParticipantsSearch { q: q.clone() },
+            )
+        }
+        None | Some(MemberFilter::Recent) => {
+            tl::enums::ChannelParticipantsFilter::ChannelParticipantsRecent
+        }
+        Some(MemberFilter::Admins) => {
+            tl::enums::ChannelParticipantsFilter::ChannelParticipantsAdmins
+        }
+        Some(MemberFilter::Bots) => tl::enums::ChannelParticipantsFilter::ChannelParticipantsBots,
+        Some(MemberFilter::Banned) => {
+            tl::enums::ChannelParticipantsFilter::ChannelParticipantsKicked(
+                tl::types::ChannelParticipantsKicked { q: q.clone() },
+            )
+        }
+    };
+
+    let mut members = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = match app
+            .tg
+            .invoke(&tl::functions::channels::GetParticipants {
+                channel: peer_ref.into(),
+                filter: tl_filter.clone(),
+                offset,
+                limit: MEMBER_PAGE,
+                hash: 0,
+            })
+            .await?
+        {
+            tl::enums::channels::ChannelParticipants::Participants(p) => p,
+            tl::enums::channels::ChannelParticipants::NotModified => break,
+        };
+        let fetched = page.participants.len() as i32;
+        let users: HashMap<i64, tl::types::User> = page
+            .users
+            .into_iter()
+            .filter_map(|u| match u {
+                tl::enums::User::User(u) => Some((u.id, u)),
+                tl::enums::User::Empty(_) => None,
+            })
+            .collect();
+
+        for participant in page.participants {
+            let Some(member) = channel_member(&participant, &users) else {
+                continue;
+            };
+            // Admin and bot filters don't take a query, so search locally
+            if !q.is_empty() && !member.matches(&q) {
+                continue;
+            }
+            members.push(member);
+            if limit > 0 && members.len() >= limit {
+                return Ok(members);
+            }
+        }
+
+        offset += fetched;
+        if fetched < MEMBER_PAGE || offset >= page.count {
+            break;
+        }
+    }
+    Ok(members)
+}
+
+fn channel_member(
+    participant: &tl::enums::ChannelParticipant,
+    users: &HashMap<i64, tl::types::User>,
+) -> Option<MemberInfo> {
+    use tl::enums::ChannelParticipant as P;
+    let (user_id, role, date, inviter_id) = match participant {
+        P::Participant(p) => (p.user_id, "member", Some(p.date), None),
+        P::ParticipantSelf(p) => (p.user_id, "member", Some(p.date), Some(p.inviter_id)),
+        P::Creator(p) => (p.user_id, "creator", None, None),
+        P::Admin(p) => (p.user_id, "admin", Some(p.date), p.inviter_id),
+        P::Banned(p) => match &p.peer {
+            tl::enums::Peer::User(u) => (u.user_id, "banned", Some(p.date), Some(p.kicked_by)),
+            _ => return None,
+        },
+        P::Left(p) => match &p.peer {
+            tl::enums::Peer::User(u) => (u.user_id, "left", None, None),
+            _ => return None,
+        },
+    };
+    let user = users.get(&user_id)?;
+    Some(MemberInfo {
+        id: user.id,
+        username: user.username.clone(),
+        first_name: user.first_name.clone(),
+        last_name: user.last_name.clone(),
+        status: user
+            .status
+            .as_ref()
+            .map(format_user_status)
+            .unwrap_or_else(|| "unknown".to_string()),
+        role: role.to_string(),
+        bot: user.bot,
+        joined: date
+            .and_then(|d| chrono::DateTime::from_timestamp(d as i64, 0).map(|dt| dt.to_rfc3339())),
+        inviter_id,
+    })
+}
+
+/// Quote a CSV field if it needs it.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_members_csv(members: &[MemberInfo]) {
+    println!("id,username,first_name,last_name,status,role,bot,joined,inviter_id");
+    for m in members {
+        let fields = [
+            m.id.to_string(),
+            m.username.clone().unwrap_or_default(),
+            m.first_name.clone().unwrap_or_default(),
+            m.last_name.clone().unwrap_or_default(),
+            m.status.clone(),
+            m.role.clone(),
+            m.bot.to_string(),
+            m.joined.clone().unwrap_or_default(),
+            m.inviter_id.map(|i| i.to_string()).unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        println!("{}", row.join(","));
+    }
 }
 
 fn format_user_status(status: &tl::enums::UserStatus) -> String {
@@ -249,8 +600,11 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
             query,
             limit,
             folder,
+            in_folder,
             archived,
             active,
+            unread_only,
+            sort,
         } => {
             // Use unlimited (i64::MAX) if no limit specified
             let effective_limit = limit.unwrap_or(i64::MAX);
@@ -268,14 +622,33 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
                 } else {
                     None // Show all chats
                 };
+                let folder_id = match in_folder {
+                    Some(name) => Some(store.find_folder_id(name).await?.ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Folder '{}' has not been synced. Run `tgcli sync --folder \"{}\"` first.",
+                            name,
+                            name
+                        )
+                    })?),
+                    None => None,
+                };
                 let chats = store
-                    .list_chats(query.as_deref(), effective_limit, archived_filter)
+                    .list_chats(
+                        query.as_deref(),
+                        effective_limit,
+                        archived_filter,
+                        *unread_only,
+                        folder_id,
+                        *sort,
+                    )
                     .await?;
 
                 if cli.output.is_json() {
                     out::write_json(&chats)?;
                 } else if cli.output.is_markdown() {
-                    let title = if *archived {
+                    let title = if *unread_only {
+                        "Unread Chats"
+                    } else if *archived {
                         "Archived Chats"
                     } else if *active {
                         "Active Chats"
@@ -288,16 +661,51 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
                 }
             }
         }
-        ChatsCommand::Show { id } => {
-            let chat = store.get_chat(*id).await?;
-            match chat {
-                Some(c) => {
-                    cli.output.write(&c)?;
-                }
-                None => {
-                    anyhow::bail!(
-                        "Chat {} not found. Run `tgcli sync` to refresh your chat list.",
-                        id
+        ChatsCommand::Show { id, count_messages } => {
+            let Some(chat) = store.get_chat(*id).await? else {
+                anyhow::bail!(
+                    "Chat {} not found. Run `tgcli sync` to refresh your chat list.",
+                    id
+                );
+            };
+            if !*count_messages {
+                cli.output.write(&chat)?;
+                return Ok(());
+            }
+
+            let app = App::new(cli).await?;
+            let history = app.count_history(*id).await?;
+            let local = store.count_chat_messages(*id).await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat": chat,
+                    "history": history,
+                    "local_messages": local,
+                }))?;
+            } else {
+                cli.output.write(&chat)?;
+                println!();
+                println!(
+                    "Messages on Telegram: {}{}",
+                    if history.inexact { "~" } else { "" },
+                    history.messages
+                );
+                println!("Messages stored locally: {}", local);
+                if history.media.is_empty() {
+                    println!("No media.");
+                } else {
+                    println!("{:<10} {:>8} {:>12}", "MEDIA", "COUNT", "EST. SIZE");
+                    for m in &history.media {
+                        println!(
+                            "{:<10} {:>8} {:>12}",
+                            m.media_type,
+                            m.count,
+                            out::format_size(m.estimated_bytes)
+                        );
+                    }
+                    println!(
+                        "Estimated media volume: {}",
+                        out::format_size(history.estimated_media_bytes)
                     );
                 }
             }
@@ -306,11 +714,8 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
             chat_id,
             soft: _,
             hard,
+            yes,
         } => {
-            if *hard {
-                anyhow::bail!("Hard delete not implemented. Use --soft (default) to delete from local DB only.");
-            }
-
             // Get chat info before deletion for confirmation message
             let chat = store.get_chat(*chat_id).await?;
             let chat_name = chat
@@ -318,6 +723,24 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
                 .map(|c| c.name.clone())
                 .unwrap_or_else(|| format!("(unknown chat {})", chat_id));
 
+            if *hard {
+                let what = match chat.as_ref().map(|c| c.kind.as_str()) {
+                    Some("user") => "delete the whole conversation for both sides",
+                    _ => "leave the chat and remove it from your chat list",
+                };
+                if !*yes
+                    && !confirm(&format!(
+                        "This will {} for \"{}\" ({}) on Telegram. Continue?",
+                        what, chat_name, chat_id
+                    ))?
+                {
+                    println!("Aborted.");
+                    return Ok(());
+                }
+                let app = App::new(cli).await?;
+                app.delete_dialog(*chat_id).await?;
+            }
+
             // Delete messages first, then the chat
             let messages_deleted = store.delete_messages_by_chat(*chat_id).await?;
             let chat_deleted = store.delete_chat(*chat_id).await?;
@@ -326,10 +749,14 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
                 out::write_json(&serde_json::json!({
                     "chat_id": chat_id,
                     "chat_name": chat_name,
+                    "deleted_on_telegram": hard,
                     "chat_deleted": chat_deleted,
                     "messages_deleted": messages_deleted,
                 }))?;
             } else if chat_deleted {
+                if *hard {
+                    println!("Deleted chat \"{}\" ({}) on Telegram", chat_name, chat_id);
+                }
                 println!("Deleted chat \"{}\" ({})", chat_name, chat_id);
                 println!("Deleted {} message(s)", messages_deleted);
             } else {
@@ -339,7 +766,71 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
                 }
             }
         }
-        ChatsCommand::Members { id, limit } => {
+        ChatsCommand::ClearHistory {
+            id,
+            for_everyone,
+            keep_days,
+            yes,
+        } => {
+            if *for_everyone && !*yes {
+                let chat_name = store
+                    .get_chat(*id)
+                    .await?
+                    .map(|c| c.name)
+                    .unwrap_or_else(|| format!("(unknown chat {})", id));
+                let what = match keep_days {
+                    Some(days) => format!("messages older than {} days", days),
+                    None => "all messages".to_string(),
+                };
+                if !confirm(&format!(
+                    "This will delete {} in \"{}\" ({}) for every participant. Continue?",
+                    what, chat_name, id
+                ))? {
+                    println!("Aborted.");
+                    return Ok(());
+                }
+            }
+            let before =
+                keep_days.map(|days| chrono::Utc::now() - chrono::Duration::days(days as i64));
+            let app = App::new(cli).await?;
+            app.clear_history(*id, *for_everyone, before).await?;
+
+            let messages_deleted = match before {
+                Some(before) => store.delete_messages_before(*id, before).await?,
+                None => store.delete_messages_by_chat(*id).await?,
+            };
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": id,
+                    "for_everyone": for_everyone,
+                    "keep_days": keep_days,
+                    "local_messages_deleted": messages_deleted,
+                }))?;
+            } else {
+                let scope = if *for_everyone {
+                    "for everyone"
+                } else {
+                    "for you"
+                };
+                match keep_days {
+                    Some(days) => println!(
+                        "Cleared history of chat {} older than {} days ({})",
+                        id, days, scope
+                    ),
+                    None => println!("Cleared history of chat {} ({})", id, scope),
+                }
+                println!("Deleted {} local message(s)", messages_deleted);
+            }
+        }
+        ChatsCommand::Members {
+            id,
+            limit,
+            filter,
+            search,
+            csv,
+            snapshot,
+        } => {
             // Look up the chat to get its name and username for display
             let chat = store.get_chat(*id).await?;
             let chat_name = chat
@@ -397,31 +888,74 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
                 }
             };
 
-            let mut participants = app.tg.client.iter_participants(peer_ref);
-
-            let mut members: Vec<MemberInfo> = Vec::new();
-            let mut count = 0usize;
-
-            while let Some(participant) = participants.next().await? {
-                let user = &participant.user;
-                let member = MemberInfo {
-                    id: user.bare_id(),
-                    username: user.username().map(|s| s.to_string()),
-                    first_name: user.first_name().map(|s| s.to_string()),
-                    last_name: user.last_name().map(|s| s.to_string()),
-                    status: format_user_status(user.status()),
-                    role: format_role(&participant.role),
-                };
-                members.push(member);
-                count += 1;
+            // A snapshot must be complete, or the diff reports everyone past
+            // the limit as having left
+            let limit = if *snapshot { 0 } else { *limit };
+            let members = if peer_ref.id.kind() == PeerKind::Channel {
+                channel_members(&app, peer_ref, *filter, search.as_deref(), limit).await?
+            } else {
+                // Basic groups return everyone at once, so filter locally
+                let mut participants = app.tg.client.iter_participants(peer_ref);
+                let mut members: Vec<MemberInfo> = Vec::new();
+                while let Some(participant) = participants.next().await? {
+                    use grammers_client::types::Role;
+                    let user = &participant.user;
+                    let (joined, inviter_id) = match &participant.role {
+                        Role::User(n) => (Some(n.date()), n.inviter_id()),
+                        Role::Admin(a) => (Some(a.date()), a.inviter_id()),
+                        _ => (None, None),
+                    };
+                    let keep = match filter {
+                        None | Some(MemberFilter::Recent) => true,
+                        Some(MemberFilter::Admins) => {
+                            matches!(participant.role, Role::Admin(_) | Role::Creator(_))
+                        }
+                        Some(MemberFilter::Bots) => user.is_bot(),
+                        Some(MemberFilter::Banned) => matches!(participant.role, Role::Banned(_)),
+                    };
+                    let member = MemberInfo {
+                        id: user.bare_id(),
+                        username: user.username().map(|s| s.to_string()),
+                        first_name: user.first_name().map(|s| s.to_string()),
+                        last_name: user.last_name().map(|s| s.to_string()),
+                        status: format_user_status(user.status()),
+                        role: format_role(&participant.role),
+                        bot: user.is_bot(),
+                        joined: joined.map(|d| d.to_rfc3339()),
+                        inviter_id,
+                    };
+                    if !keep || search.as_deref().is_some_and(|q| !member.matches(q)) {
+                        continue;
+                    }
+                    members.push(member);
 
-                // Check limit (0 = unlimited)
-                if *limit > 0 && count >= *limit {
-                    break;
+                    // Check limit (0 = unlimited)
+                    if limit > 0 && members.len() >= limit {
+                        break;
+                    }
                 }
+                members
+            };
+
+            if *snapshot {
+                let snapshot: Vec<ChatMember> = members
+                    .iter()
+                    .map(|m| ChatMember {
+                        user_id: m.id,
+                        username: m.username.clone(),
+                        name: member_name(m.first_name.as_deref(), m.last_name.as_deref()),
+                        role: m.role.clone(),
+                    })
+                    .collect();
+                store
+                    .save_member_snapshot(*id, chrono::Utc::now(), &snapshot)
+                    .await?;
+                eprintln!("Saved a snapshot of {} members", snapshot.len());
             }
 
-            if cli.output.is_json() {
+            if *csv {
+                write_members_csv(&members);
+            } else if cli.output.is_json() {
                 out::write_json(&serde_json::json!({
                     "chat_id": id,
                     "chat_name": chat_name,
@@ -435,6 +969,59 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
                 cli.output.write(&members)?;
             }
         }
+        ChatsCommand::MembersDiff { id, since } => {
+            let snapshots = store.member_snapshots(*id).await?;
+            let Some(&latest) = snapshots.last() else {
+                anyhow::bail!(
+                    "No member snapshots of chat {}. Run `tgcli chats members --id {} --snapshot` first.",
+                    id,
+                    id
+                );
+            };
+            let base = match since {
+                Some(since) => {
+                    let since = crate::cmd::messages::parse_time(since)?;
+                    snapshots
+                        .iter()
+                        .rev()
+                        .find(|ts| **ts <= since)
+                        .or(snapshots.first())
+                        .copied()
+                }
+                None => snapshots.iter().rev().nth(1).copied(),
+            };
+            let base = match base {
+                Some(base) if base < latest => base,
+                _ => anyhow::bail!(
+                    "Only one member snapshot of chat {} to compare; take another with `tgcli chats members --id {} --snapshot`",
+                    id,
+                    id
+                ),
+            };
+
+            let before = store.member_snapshot(*id, base).await?;
+            let after = store.member_snapshot(*id, latest).await?;
+            let changes = member_changes(&before, &after);
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": id,
+                    "from": base.to_rfc3339(),
+                    "to": latest.to_rfc3339(),
+                    "members_before": before.len(),
+                    "members_after": after.len(),
+                    "changes": changes,
+                }))?;
+            } else {
+                let title = format!(
+                    "Member changes in {} from {} to {}",
+                    id,
+                    base.format("%Y-%m-%d %H:%M"),
+                    latest.format("%Y-%m-%d %H:%M")
+                );
+                cli.output.write_titled(&changes, &title)?;
+            }
+        }
         ChatsCommand::Archive { id } => {
             if id.is_empty() {
                 anyhow::bail!("At least one --id is required");
@@ -453,12 +1040,77 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
             }
             batch_pin(cli, id, true, *folder).await?;
         }
-        ChatsCommand::Unpin { id, folder } => {
-            if id.is_empty() {
-                anyhow::bail!("At least one --id is required");
+        ChatsCommand::Unpin { id, all, folder } => {
+            if *all {
+                unpin_all(cli, *folder).await?;
+            } else {
+                if id.is_empty() {
+                    anyhow::bail!("At least one --id is required (or --all)");
+                }
+                batch_pin(cli, id, false, *folder).await?;
             }
-            batch_pin(cli, id, false, *folder).await?;
         }
+        ChatsCommand::Pinned { cmd } => match cmd {
+            PinnedCommand::List { folder } => {
+                let app = App::new(cli).await?;
+                let pinned = get_pinned_chats(&app, *folder).await?;
+                if cli.output.is_json() {
+                    out::write_json(&serde_json::json!({
+                        "folder_id": folder,
+                        "count": pinned.len(),
+                        "chats": pinned,
+                    }))?;
+                } else if pinned.is_empty() {
+                    println!("No pinned chats in folder {}", folder);
+                } else {
+                    println!(
+                        "{:<4} {:<12} {:<30} {:<16} USERNAME",
+                        "#", "KIND", "NAME", "ID"
+                    );
+                    for (i, c) in pinned.iter().enumerate() {
+                        println!(
+                            "{:<4} {:<12} {:<30} {:<16} {}",
+                            i + 1,
+                            c.kind,
+                            out::truncate(&c.name, 28),
+                            c.id,
+                            c.username.as_deref().unwrap_or("-")
+                        );
+                    }
+                }
+            }
+            PinnedCommand::Reorder { id, folder } => {
+                let app = App::new(cli).await?;
+                let mut order = Vec::with_capacity(id.len());
+                for &chat_id in id {
+                    let peer = resolve_chat_to_input_peer(&app, chat_id).await?;
+                    order.push(tl::enums::InputDialogPeer::Peer(
+                        tl::types::InputDialogPeer { peer },
+                    ));
+                }
+                app.tg
+                    .invoke(&tl::functions::messages::ReorderPinnedDialogs {
+                        force: false,
+                        folder_id: *folder,
+                        order,
+                    })
+                    .await?;
+
+                let pinned = get_pinned_chats(&app, *folder).await?;
+                if cli.output.is_json() {
+                    out::write_json(&serde_json::json!({
+                        "action": "reorder",
+                        "folder_id": folder,
+                        "chats": pinned,
+                    }))?;
+                } else {
+                    println!("Pinned chats in folder {}:", folder);
+                    for (i, c) in pinned.iter().enumerate() {
+                        println!("{:>3}. \"{}\" ({})", i + 1, c.name, c.id);
+                    }
+                }
+            }
+        },
         ChatsCommand::Ban {
             chat,
             user,
@@ -697,6 +1349,103 @@ pub async fn run(cli: &Cli, cmd: &ChatsCommand) -> Result<()> {
                 println!("Unmuted chat {}", id);
             }
         }
+        ChatsCommand::Boosts {
+            id,
+            apply,
+            slot,
+            limit,
+        } => {
+            let app = App::new(cli).await?;
+            if *apply {
+                app.apply_boost(*id, slot).await?;
+            }
+            let status = app.boost_status(*id).await?;
+            // Only admins can see who boosted; others just get the level
+            let boosts = match app.list_boosts(*id, *limit).await {
+                Ok(boosts) => Some(boosts),
+                Err(e) => {
+                    log::debug!("Cannot list boosts of chat {}: {:#}", id, e);
+                    None
+                }
+            };
+            let slots = app.my_boost_slots().await.unwrap_or_default();
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": id,
+                    "applied": apply,
+                    "status": status,
+                    "boosts": boosts,
+                    "my_slots": slots,
+                }))?;
+            } else {
+                if *apply {
+                    println!("Boosted chat {}", id);
+                }
+                let next = status
+                    .next_level_boosts
+                    .map(|n| format!("{}/{}", status.boosts, n))
+                    .unwrap_or_else(|| format!("{} (max level)", status.boosts));
+                println!("Level:  {}", status.level);
+                println!("Boosts: {}", next);
+                println!("Link:   {}", status.boost_url);
+                if status.my_boost {
+                    println!("You boost this chat (slots {:?})", status.my_boost_slots);
+                }
+                if let Some(boosts) = &boosts {
+                    println!(
+                        "\n{:<16} {:<26} {:<26} {:>5}",
+                        "USER", "SINCE", "EXPIRES", "BOOSTS"
+                    );
+                    for b in boosts {
+                        let user = b
+                            .user_id
+                            .map(|u| u.to_string())
+                            .unwrap_or_else(|| "-".to_string());
+                        println!(
+                            "{:<16} {:<26} {:<26} {:>5}",
+                            user, b.date, b.expires, b.multiplier
+                        );
+                    }
+                }
+                if !slots.is_empty() {
+                    println!("\nYour boost slots:");
+                    for s in &slots {
+                        let target = s
+                            .chat_id
+                            .map(|c| format!("boosting {}", c))
+                            .unwrap_or_else(|| "free".to_string());
+                        let cooldown = s
+                            .cooldown_until
+                            .as_ref()
+                            .map(|c| format!(", locked until {}", c))
+                            .unwrap_or_default();
+                        println!(
+                            "  slot {}: {} (expires {}{})",
+                            s.slot, target, s.expires, cooldown
+                        );
+                    }
+                }
+            }
+        }
+        ChatsCommand::Report {
+            id,
+            reason,
+            comment,
+        } => {
+            let app = App::new(cli).await?;
+            app.report_peer(*id, *reason, comment.as_deref()).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "reported": true,
+                    "chat_id": id,
+                    "reason": reason.to_possible_value().map(|v| v.get_name().to_string()),
+                }))?;
+            } else {
+                println!("Reported chat {}", id);
+            }
+        }
     }
     Ok(())
 }
@@ -775,60 +1524,41 @@ fn parse_expire_duration(duration: &str) -> Result<i32> {
     Ok((now.timestamp() + secs) as i32)
 }
 
-/// List chats from a specific folder
-async fn list_folder_chats(
-    cli: &Cli,
-    _store: &Store,
-    folder_id: i32,
-    query: Option<&str>,
-    limit: i64,
-) -> Result<()> {
-    let app = App::new(cli).await?;
-
-    // Fetch dialogs with folder filter
-    let request = tl::functions::messages::GetDialogs {
-        exclude_pinned: false,
-        folder_id: Some(folder_id),
-        offset_date: 0,
-        offset_id: 0,
-        offset_peer: tl::enums::InputPeer::Empty,
-        limit: limit as i32,
-        hash: 0,
-    };
-
-    let result = app.tg.client.invoke(&request).await?;
+#[derive(Serialize)]
+struct FolderChat {
+    id: i64,
+    kind: String,
+    name: String,
+    username: Option<String>,
+}
 
-    #[derive(Serialize)]
-    struct FolderChat {
-        id: i64,
-        kind: String,
-        name: String,
-        username: Option<String>,
+/// Yes/no prompt on stderr (defaults to no). Fails instead of prompting when
+/// stdin is not a terminal, so scripts must pass --yes explicitly.
+pub(crate) fn confirm(label: &str) -> Result<bool> {
+    use std::io::{IsTerminal, Write};
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("Refusing to continue without confirmation; pass --yes to skip the prompt");
     }
+    eprint!("{} [y/N] ", label);
+    std::io::stderr().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
 
-    let mut chats: Vec<FolderChat> = Vec::new();
-
-    // Extract chats from the response
-    let (dialogs, users, chat_list) = match result {
-        tl::enums::messages::Dialogs::Dialogs(d) => (d.dialogs, d.users, d.chats),
-        tl::enums::messages::Dialogs::Slice(d) => (d.dialogs, d.users, d.chats),
-        tl::enums::messages::Dialogs::NotModified(_) => {
-            if cli.output.is_json() {
-                out::write_json(&Vec::<FolderChat>::new())?;
-            } else {
-                println!("No chats in folder {}", folder_id);
-            }
-            return Ok(());
-        }
-    };
-
+/// Resolve dialog peers to names using the users and chats sent alongside them.
+fn dialog_chats(
+    dialogs: &[tl::enums::Dialog],
+    users: &[tl::enums::User],
+    chat_list: &[tl::enums::Chat],
+) -> Vec<FolderChat> {
     // Build lookup maps for users and chats
     let mut user_map: std::collections::HashMap<i64, (String, Option<String>)> =
         std::collections::HashMap::new();
     let mut chat_map: std::collections::HashMap<i64, (String, Option<String>, String)> =
         std::collections::HashMap::new();
 
-    for user in &users {
+    for user in users {
         if let tl::enums::User::User(u) = user {
             let name = format!(
                 "{} {}",
@@ -841,7 +1571,7 @@ async fn list_folder_chats(
         }
     }
 
-    for chat in &chat_list {
+    for chat in chat_list {
         match chat {
             tl::enums::Chat::Chat(c) => {
                 chat_map.insert(c.id, (c.title.clone(), None, "group".to_string()));
@@ -857,8 +1587,8 @@ async fn list_folder_chats(
         }
     }
 
-    // Process dialogs
-    for dialog in &dialogs {
+    let mut chats = Vec::new();
+    for dialog in dialogs {
         let peer = match dialog {
             tl::enums::Dialog::Dialog(d) => &d.peer,
             tl::enums::Dialog::Folder(_) => continue,
@@ -892,19 +1622,6 @@ async fn list_folder_chats(
             }
         };
 
-        // Apply query filter if provided
-        if let Some(q) = query {
-            let q_lower = q.to_lowercase();
-            let name_matches = name.to_lowercase().contains(&q_lower);
-            let username_matches = username
-                .as_ref()
-                .map(|u| u.to_lowercase().contains(&q_lower))
-                .unwrap_or(false);
-            if !name_matches && !username_matches {
-                continue;
-            }
-        }
-
         chats.push(FolderChat {
             id,
             kind,
@@ -912,6 +1629,91 @@ async fn list_folder_chats(
             username,
         });
     }
+    chats
+}
+
+/// Pinned chats of a folder, in pin order.
+async fn get_pinned_chats(app: &App, folder_id: i32) -> Result<Vec<FolderChat>> {
+    let result = app
+        .tg
+        .invoke(&tl::functions::messages::GetPinnedDialogs { folder_id })
+        .await?;
+    let tl::enums::messages::PeerDialogs::Dialogs(d) = result;
+    Ok(dialog_chats(&d.dialogs, &d.users, &d.chats))
+}
+
+/// Unpin every chat in a folder with a single `messages.reorderPinnedDialogs` call.
+async fn unpin_all(cli: &Cli, folder_id: i32) -> Result<()> {
+    let app = App::new(cli).await?;
+    let pinned = get_pinned_chats(&app, folder_id).await?;
+    if !pinned.is_empty() {
+        // `force` unpins every chat missing from `order`
+        app.tg
+            .invoke(&tl::functions::messages::ReorderPinnedDialogs {
+                force: true,
+                folder_id,
+                order: Vec::new(),
+            })
+            .await?;
+    }
+
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "action": "unpinned",
+            "folder_id": folder_id,
+            "count": pinned.len(),
+            "chats": pinned,
+        }))?;
+    } else {
+        for c in &pinned {
+            println!("Unpinned \"{}\" ({})", c.name, c.id);
+        }
+        println!("Unpinned {} chats in folder {}", pinned.len(), folder_id);
+    }
+    Ok(())
+}
+
+/// List chats from a specific folder
+async fn list_folder_chats(
+    cli: &Cli,
+    _store: &Store,
+    folder_id: i32,
+    query: Option<&str>,
+    limit: i64,
+) -> Result<()> {
+    let app = App::new(cli).await?;
+
+    // Fetch dialogs with folder filter
+    let request = tl::functions::messages::GetDialogs {
+        exclude_pinned: false,
+        folder_id: Some(folder_id),
+        offset_date: 0,
+        offset_id: 0,
+        offset_peer: tl::enums::InputPeer::Empty,
+        limit: limit as i32,
+        hash: 0,
+    };
+
+    let result = app.tg.invoke(&request).await?;
+
+    // Extract chats from the response
+    let mut chats = match result {
+        tl::enums::messages::Dialogs::Dialogs(d) => dialog_chats(&d.dialogs, &d.users, &d.chats),
+        tl::enums::messages::Dialogs::Slice(d) => dialog_chats(&d.dialogs, &d.users, &d.chats),
+        tl::enums::messages::Dialogs::NotModified(_) => Vec::new(),
+    };
+
+    // Apply query filter if provided
+    if let Some(q) = query {
+        let q_lower = q.to_lowercase();
+        chats.retain(|c| {
+            c.name.to_lowercase().contains(&q_lower)
+                || c.username
+                    .as_ref()
+                    .map(|u| u.to_lowercase().contains(&q_lower))
+                    .unwrap_or(false)
+        });
+    }
 
     if cli.output.is_json() {
         out::write_json(&chats)?;
@@ -970,7 +1772,7 @@ async fn batch_archive(cli: &Cli, chat_ids: &[i64], archive: bool) -> Result<()>
 
     // Single API call for all chats
     let request = tl::functions::folders::EditPeerFolders { folder_peers };
-    app.tg.client.invoke(&request).await?;
+    app.tg.invoke(&request).await?;
 
     let action = if archive { "Archived" } else { "Unarchived" };
 
@@ -1033,7 +1835,7 @@ async fn batch_pin(cli: &Cli, chat_ids: &[i64], pin: bool, folder_id: i32) -> Re
             peer: input_dialog_peer,
         };
 
-        match app.tg.client.invoke(&request).await {
+        match app.tg.invoke(&request).await {
             Ok(_) => {
                 results.push((chat_id, true, None));
             }
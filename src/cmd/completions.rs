@@ -1,5 +1,8 @@
+use crate::config::Config;
+use crate::store::{ChatSort, Store};
 use crate::Cli;
 use anyhow::Result;
+use clap::builder::PossibleValuesParser;
 use clap::{CommandFactory, ValueEnum};
 use clap_complete::{generate, Shell};
 use std::io;
@@ -25,14 +28,186 @@ impl From<ShellType> for Shell {
     }
 }
 
-pub fn run(shell: &ShellType) -> Result<()> {
+/// Stand-in value for chat arguments, replaced in the generated script by a
+/// call to `tgcli complete-chats`.
+const CHATS_PLACEHOLDER: &str = "__TGCLI_CHATS__";
+
+/// Chats offered by `complete-chats`, most recent first.
+const MAX_CHAT_COMPLETIONS: i64 = 500;
+
+/// Set the completion values of every chat argument (`--chat`, `--to`,
+/// `--ignore`, ...).
+fn with_chat_values(mut cmd: clap::Command, values: &[&'static str]) -> clap::Command {
+    let chat_args: Vec<String> = cmd
+        .get_arguments()
+        .filter(|a| {
+            a.get_id() == "chat"
+                || a.get_value_names().is_some_and(|names| {
+                    names
+                        .iter()
+                        .any(|n| matches!(n.as_str(), "CHAT" | "CHAT_ID"))
+                })
+        })
+        .map(|a| a.get_id().to_string())
+        .collect();
+    for id in chat_args {
+        cmd = cmd.mut_arg(id, |a| {
+            a.value_parser(PossibleValuesParser::new(values.iter().copied()))
+        });
+    }
+    let subcommands: Vec<String> = cmd
+        .get_subcommands()
+        .map(|s| s.get_name().to_string())
+        .collect();
+    for name in subcommands {
+        cmd = cmd.mut_subcommand(name, |s| with_chat_values(s, values));
+    }
+    cmd
+}
+
+/// Swap the placeholder for a call to `complete-chats`, so chat names and
+/// aliases come from the local store at completion time.
+fn hook_chat_completion(script: &str, shell: &ShellType, bin: &str) -> String {
+    match shell {
+        ShellType::Bash => {
+            let call = format!(
+                "$({} complete-chats --shell bash -- \"${{cur}}\" 2>/dev/null)",
+                bin
+            );
+            // Positional chats also end up in a command's word list
+            script
+                .replace(
+                    &format!("$(compgen -W \"{}\" -- \"${{cur}}\")", CHATS_PLACEHOLDER),
+                    &call,
+                )
+                .replace(CHATS_PLACEHOLDER, &call)
+        }
+        ShellType::Zsh => {
+            let script = script.replace(&format!("({})", CHATS_PLACEHOLDER), "_tgcli_chats");
+            let helper = format!(
+                "\n_tgcli_chats() {{\n    local -a chats\n    chats=(${{(f)\"$({} complete-chats --shell zsh 2>/dev/null)\"}})\n    _describe -t chats 'chat' chats\n}}\n",
+                bin
+            );
+            // Define the helper before the script runs its completion function
+            match script.split_once('\n') {
+                Some((first, rest)) => format!("{}\n{}{}", first, helper, rest),
+                None => script,
+            }
+        }
+        ShellType::Fish => script.replace(
+            &format!("\"{}\\t''\"", CHATS_PLACEHOLDER),
+            &format!("\"({} complete-chats --shell fish)\"", bin),
+        ),
+        // No hook for these; aliases are listed statically instead
+        ShellType::PowerShell | ShellType::Elvish => script.to_string(),
+    }
+}
+
+pub fn run(cli: &Cli, shell: &ShellType) -> Result<()> {
     let mut cmd = Cli::command();
     let bin_name = cmd.get_name().to_string();
+    match shell {
+        ShellType::Bash | ShellType::Zsh | ShellType::Fish => {
+            cmd = with_chat_values(cmd, &[CHATS_PLACEHOLDER]);
+        }
+        ShellType::PowerShell | ShellType::Elvish => {
+            let config = Config::load(&cli.config_dir())?;
+            if !config.aliases.is_empty() {
+                // clap wants 'static names; this runs once per process
+                let aliases: Vec<&'static str> = config
+                    .aliases
+                    .into_keys()
+                    .map(|a| &*Box::leak(a.into_boxed_str()))
+                    .collect();
+                cmd = with_chat_values(cmd, &aliases);
+            }
+        }
+    }
+
+    let mut script = Vec::new();
     generate(
         Shell::from(shell.clone()),
         &mut cmd,
-        bin_name,
-        &mut io::stdout(),
+        bin_name.clone(),
+        &mut script,
     );
+    let script = String::from_utf8(script)?;
+    io::Write::write_all(
+        &mut io::stdout(),
+        hook_chat_completion(&script, shell, &bin_name).as_bytes(),
+    )?;
     Ok(())
 }
+
+/// Print completion candidates for a chat argument: aliases, then known
+/// chats, with names as descriptions where the shell shows them.
+pub async fn complete_chats(cli: &Cli, shell: &ShellType, query: Option<&str>) -> Result<()> {
+    let aliases = Config::load(&cli.config_dir())?.aliases;
+    let store = Store::open(&cli.store_dir()).await?;
+    let chats = store
+        .list_chats(
+            None,
+            MAX_CHAT_COMPLETIONS,
+            None,
+            false,
+            None,
+            ChatSort::Recent,
+        )
+        .await?;
+    let name_of = |id: i64| {
+        chats
+            .iter()
+            .find(|c| c.id == id)
+            .map(|c| c.name.clone())
+            .unwrap_or_default()
+    };
+
+    let mut candidates: Vec<(String, String)> = aliases
+        .iter()
+        .map(|(alias, id)| (alias.clone(), name_of(*id)))
+        .collect();
+    candidates.extend(chats.iter().map(|c| (c.id.to_string(), c.name.clone())));
+
+    // Bash has no descriptions, so match names here and complete to the ID
+    if let Some(query) = query.filter(|q| !q.is_empty()) {
+        let query = query.to_lowercase();
+        candidates.retain(|(value, name)| {
+            value.to_lowercase().starts_with(&query) || name.to_lowercase().contains(&query)
+        });
+    }
+
+    for (value, name) in candidates {
+        let name = name.replace(['\n', '\t'], " ");
+        match shell {
+            ShellType::Zsh => println!("{}:{}", value.replace(':', "\\:"), name),
+            ShellType::Fish => println!("{}\t{}", value, name),
+            _ => println!("{}", value),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(shell: ShellType) -> String {
+        let mut cmd = with_chat_values(Cli::command(), &[CHATS_PLACEHOLDER]);
+        let mut script = Vec::new();
+        generate(Shell::from(shell.clone()), &mut cmd, "tgcli", &mut script);
+        hook_chat_completion(&String::from_utf8(script).unwrap(), &shell, "tgcli")
+    }
+
+    #[test]
+    fn chat_arguments_call_the_completion_helper() {
+        for shell in [ShellType::Bash, ShellType::Zsh, ShellType::Fish] {
+            let script = script(shell.clone());
+            assert!(
+                !script.contains(CHATS_PLACEHOLDER),
+                "{:?} script still has the placeholder",
+                shell
+            );
+            assert!(script.contains("tgcli complete-chats"), "{:?}", shell);
+        }
+    }
+}
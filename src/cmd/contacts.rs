@@ -2,7 +2,9 @@ use crate::out;
 use crate::store::Store;
 use crate::Cli;
 use anyhow::Result;
+use chrono::Local;
 use clap::Subcommand;
+use serde::Serialize;
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum ContactsCommand {
@@ -27,6 +29,22 @@ pub enum ContactsCommand {
         #[arg(long)]
         id: i64,
     },
+    /// First and most recent message exchanged with a person, across all chats
+    Timeline {
+        /// User ID
+        #[arg(long)]
+        id: i64,
+    },
+}
+
+#[derive(Serialize)]
+struct TimelineRow {
+    which: &'static str,
+    ts: String,
+    chat_id: i64,
+    msg_id: i64,
+    from: &'static str,
+    text: String,
 }
 
 pub async fn run(cli: &Cli, cmd: &ContactsCommand) -> Result<()> {
@@ -70,6 +88,46 @@ pub async fn run(cli: &Cli, cmd: &ContactsCommand) -> Result<()> {
                 }
             }
         }
+        ContactsCommand::Timeline { id } => {
+            let timeline = store.user_timeline(*id).await?;
+            if timeline.first.is_none() {
+                anyhow::bail!(
+                    "No messages with {} in the local store. Run `tgcli sync` first.",
+                    id
+                );
+            }
+
+            if cli.output.is_json() {
+                out::write_json(&timeline)?;
+            } else {
+                let name = crate::cmd::messages::sender_names(&store, std::iter::once(*id))
+                    .await?
+                    .remove(id)
+                    .unwrap_or_else(|| format!("User {}", id));
+                let rows: Vec<TimelineRow> = [("first", &timeline.first), ("last", &timeline.last)]
+                    .into_iter()
+                    .filter_map(|(which, m)| {
+                        m.as_ref().map(|m| TimelineRow {
+                            which,
+                            ts: m
+                                .ts
+                                .with_timezone(&Local)
+                                .format("%Y-%m-%d %H:%M")
+                                .to_string(),
+                            chat_id: m.chat_id,
+                            msg_id: m.id,
+                            from: if m.from_me { "you" } else { "them" },
+                            text: out::truncate(&m.text, 80),
+                        })
+                    })
+                    .collect();
+                let title = format!(
+                    "{}: {} private messages, {} in {} other chats",
+                    name, timeline.private_messages, timeline.group_messages, timeline.group_chats
+                );
+                cli.output.write_titled(&rows, &title)?;
+            }
+        }
     }
     Ok(())
 }
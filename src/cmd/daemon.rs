@@ -4,10 +4,26 @@
 //! 1. Immediately subscribes to real-time updates
 //! 2. Saves incoming messages to the local database as they arrive
 //! 3. Optionally runs background incremental sync to catch up on missed messages
-
-use crate::app::App;
+//! 4. Raises the keyword alerts added with `tgcli alerts add`
+//! 5. Runs the auto-forward rules added with `tgcli forwards add`
+//! 6. Sends the recurring messages added with `tgcli schedule add`
+//! 7. Answers private messages while away mode (`tgcli away on`) is on
+//! 8. Keeps message reactions current from reaction updates
+
+use crate::alerts::{alert_matches, fire, pattern_matches, AlertEvent};
+use crate::app::details::{record_message_details, update_message};
+use crate::app::emoji::{
+    custom_emoji_spans, label_live_text, record_custom_emoji, update_entities,
+};
+use crate::app::reactions::reaction_counts;
+use crate::app::service::service_action;
+use crate::app::{peer_bare_id, App};
+use crate::cmd::audit;
+use crate::config::Config;
+use crate::guard;
+use crate::metrics::DaemonMetrics;
 use crate::shutdown;
-use crate::store::UpsertMessageParams;
+use crate::store::{UpsertMessageParams, SERVICE_MEDIA_TYPE};
 use crate::Cli;
 use anyhow::{Context, Result};
 use chrono::Utc;
@@ -16,9 +32,12 @@ use grammers_client::types::Peer;
 use grammers_client::{Update, UpdatesConfiguration};
 use grammers_tl_types as tl;
 use std::collections::HashSet;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
+/// Older messages the background sync fetches per chat once every chat is current
+const BACKFILL_PER_CHAT: usize = 1000;
+
 #[derive(Args, Debug, Clone)]
 pub struct DaemonArgs {
     /// Don't run background sync (only listen for new updates)
@@ -30,7 +49,7 @@ pub struct DaemonArgs {
     pub download_media: bool,
 
     /// Chat IDs to ignore (skip during sync and updates)
-    #[arg(long = "ignore", value_name = "CHAT_ID")]
+    #[arg(long = "ignore", value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
     pub ignore_chat_ids: Vec<i64>,
 
     /// Skip all channel updates
@@ -44,6 +63,26 @@ pub struct DaemonArgs {
     /// Output updates as JSONL stream to stdout
     #[arg(long, default_value_t = false)]
     pub stream: bool,
+
+    /// Serve Prometheus metrics on this port (GET /metrics)
+    #[arg(long, value_name = "PORT")]
+    pub metrics_port: Option<u16>,
+
+    /// Address to bind the metrics listener to
+    #[arg(long, default_value = "127.0.0.1", requires = "metrics_port")]
+    pub metrics_bind: std::net::IpAddr,
+}
+
+/// Write what the daemon just sent or forwarded to the audit log.
+async fn flush_audit(app: &App) {
+    let attempts = guard::take_attempts();
+    if attempts.is_empty() {
+        return;
+    }
+    let logged = async { audit::record(&*app.get_store().await?, "daemon", attempts).await };
+    if let Err(e) = logged.await {
+        log::warn!("Failed to write the audit log: {:#}", e);
+    }
 }
 
 /// Extract chat_id from a Peer
@@ -52,12 +91,12 @@ fn extract_chat_id_from_peer(peer: &Peer) -> i64 {
 }
 
 /// Extract sender_id from a Message update
-fn extract_sender_id(msg: &grammers_client::types::update::Message) -> i64 {
+pub(crate) fn extract_sender_id(msg: &grammers_client::types::update::Message) -> i64 {
     msg.sender().map(|s| s.id().bare_id()).unwrap_or(0)
 }
 
 /// Extract topic_id from a raw update if present
-fn extract_topic_id_from_raw(raw: &tl::enums::Update) -> Option<i32> {
+pub(crate) fn extract_topic_id_from_raw(raw: &tl::enums::Update) -> Option<i32> {
     match raw {
         tl::enums::Update::NewChannelMessage(m) => extract_topic_from_message(&m.message),
         tl::enums::Update::EditChannelMessage(m) => extract_topic_from_message(&m.message),
@@ -65,7 +104,7 @@ fn extract_topic_id_from_raw(raw: &tl::enums::Update) -> Option<i32> {
     }
 }
 
-fn extract_topic_from_message(msg: &tl::enums::Message) -> Option<i32> {
+pub(crate) fn extract_topic_from_message(msg: &tl::enums::Message) -> Option<i32> {
     if let tl::enums::Message::Message(m) = msg {
         if let Some(tl::enums::MessageReplyHeader::Header(header)) = &m.reply_to {
             if header.forum_topic {
@@ -77,7 +116,7 @@ fn extract_topic_from_message(msg: &tl::enums::Message) -> Option<i32> {
 }
 
 /// Determine chat kind from peer
-fn chat_kind_from_peer(peer: &Peer) -> &'static str {
+pub(crate) fn chat_kind_from_peer(peer: &Peer) -> &'static str {
     match peer {
         Peer::User(_) => "user",
         Peer::Group(_) => "group",
@@ -93,7 +132,7 @@ fn chat_kind_from_peer(peer: &Peer) -> &'static str {
 }
 
 /// Get chat name from Peer
-fn chat_name_from_peer(peer: &Peer) -> String {
+pub(crate) fn chat_name_from_peer(peer: &Peer) -> String {
     match peer {
         Peer::User(u) => u
             .first_name()
@@ -108,7 +147,7 @@ fn chat_name_from_peer(peer: &Peer) -> String {
 }
 
 /// Get username from Peer if available
-fn username_from_peer(peer: &Peer) -> Option<String> {
+pub(crate) fn username_from_peer(peer: &Peer) -> Option<String> {
     match peer {
         Peer::User(u) => u.username().map(|s| s.to_string()),
         Peer::Channel(c) => c.username().map(|s| s.to_string()),
@@ -117,12 +156,12 @@ fn username_from_peer(peer: &Peer) -> Option<String> {
 }
 
 /// Check if Peer is a forum
-fn is_forum_peer(peer: &Peer) -> bool {
+pub(crate) fn is_forum_peer(peer: &Peer) -> bool {
     matches!(peer, Peer::Channel(c) if c.raw.forum)
 }
 
 /// Get access_hash from Peer
-fn access_hash_from_peer(peer: &Peer) -> Option<i64> {
+pub(crate) fn access_hash_from_peer(peer: &Peer) -> Option<i64> {
     match peer {
         Peer::User(u) => {
             // User.raw is tl::enums::User, need to match to get inner tl::types::User
@@ -144,17 +183,34 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
         .updates_rx
         .take()
         .context("Updates receiver not available")?;
+    let store = app.get_store().await?;
 
-    let ignore_set: HashSet<i64> = args.ignore_chat_ids.iter().copied().collect();
+    // `--ignore` plus the persistent list from `tgcli ignore`
+    let mut ignore_chat_ids = args.ignore_chat_ids.clone();
+    ignore_chat_ids.extend(Config::load(&cli.config_dir())?.sync.ignore_chat_ids);
+    let ignore_set: HashSet<i64> = ignore_chat_ids.iter().copied().collect();
     let ignore_channels = args.ignore_channels;
 
     // Get global shutdown controller
     let shutdown_ctrl = shutdown::global();
 
-    // Counters for statistics
-    let messages_received = Arc::new(AtomicU64::new(0));
-    let messages_stored = Arc::new(AtomicU64::new(0));
-    let backfill_running = Arc::new(AtomicBool::new(false));
+    // Counters for statistics (also exported with --metrics-port)
+    let metrics = Arc::new(DaemonMetrics::default());
+    if let Some(port) = args.metrics_port {
+        // SocketAddr brackets IPv6 addresses ([::1]:9090)
+        let addr = std::net::SocketAddr::new(args.metrics_bind, port);
+        // Once any token exists, scrapers need one with read:metrics
+        crate::metrics::serve(
+            addr,
+            Arc::clone(&metrics),
+            Arc::clone(&store),
+            shutdown_ctrl.clone(),
+        )
+        .await?;
+        if !args.quiet {
+            eprintln!("  Metrics at http://{}/metrics", addr);
+        }
+    }
 
     if !args.quiet {
         eprintln!("Daemon starting...");
@@ -177,9 +233,9 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
     // Spawn background sync task if backfill is enabled
     let backfill_handle = if !args.no_backfill {
         let cli_clone = cli.clone();
-        let ignore_ids = args.ignore_chat_ids.clone();
+        let ignore_ids = ignore_chat_ids.clone();
         let ignore_chans = args.ignore_channels;
-        let backfill_running_clone = Arc::clone(&backfill_running);
+        let metrics_clone = Arc::clone(&metrics);
         let shutdown_ctrl_clone = shutdown_ctrl.clone();
         let quiet = args.quiet;
 
@@ -196,7 +252,9 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                 return Ok::<_, anyhow::Error>(());
             }
 
-            backfill_running_clone.store(true, Ordering::Relaxed);
+            metrics_clone
+                .backfill_running
+                .store(true, Ordering::Relaxed);
             if !quiet {
                 eprintln!("Background sync starting...");
             }
@@ -214,23 +272,49 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                 incremental: true,
                 messages_per_chat: 50,
                 concurrency: 4,
-                chat_filter: None,
+                chat_filter: Vec::new(),
                 prune_after: None,
                 skip_archived: false,
                 archived_only: false,
+                save_shared_contacts: false,
+                folder: None,
+                skip_kinds: Vec::new(),
+                skip_name: None,
             };
 
-            let result = backfill_app.sync(opts).await;
-            backfill_running_clone.store(false, Ordering::Relaxed);
-
-            match result {
+            // Recent messages first, then older history for the most active chats
+            let result = match backfill_app.sync(opts.clone()).await {
                 Ok(res) => {
+                    metrics_clone
+                        .messages_stored
+                        .fetch_add(res.messages_stored, Ordering::Relaxed);
                     if !quiet {
                         eprintln!(
                             "Background sync complete: {} chats, {} messages",
                             res.chats_stored, res.messages_stored
                         );
                     }
+                    backfill_app
+                        .backfill_history(&opts, BACKFILL_PER_CHAT)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            metrics_clone
+                .backfill_running
+                .store(false, Ordering::Relaxed);
+
+            match result {
+                Ok(res) => {
+                    metrics_clone
+                        .messages_stored
+                        .fetch_add(res.messages, Ordering::Relaxed);
+                    if !quiet {
+                        eprintln!(
+                            "Background backfill complete: {} older messages from {} chats",
+                            res.messages, res.chats
+                        );
+                    }
                 }
                 Err(e) => {
                     // Don't log error if we're shutting down
@@ -253,6 +337,17 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
         eprintln!("Daemon ready. Press Ctrl+C to stop.");
     }
 
+    // Messages the daemon sent itself (forwards, scheduled messages, away
+    // replies): never forwarded again and don't end away mode
+    let mut sent_by_daemon: HashSet<(i64, i64)> = HashSet::new();
+
+    // Recurring messages (`tgcli schedule`) are checked twice a minute
+    let mut schedule_tick = tokio::time::interval(std::time::Duration::from_secs(30));
+    // Retention policies (`tgcli retention`) are applied hourly
+    let mut prune_tick = tokio::time::interval(std::time::Duration::from_secs(3600));
+    // The WAL is folded back into the database every 10 minutes
+    let mut checkpoint_tick = tokio::time::interval(std::time::Duration::from_secs(600));
+
     // Main update loop
     loop {
         tokio::select! {
@@ -262,10 +357,40 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                 }
                 break;
             }
+            _ = schedule_tick.tick() => {
+                match app.run_due_schedules().await {
+                    Ok(sent) => {
+                        if !sent.is_empty() && !args.quiet {
+                            eprintln!("Sent {} scheduled messages", sent.len());
+                        }
+                        sent_by_daemon.extend(sent);
+                    }
+                    Err(e) => log::error!("Failed to run scheduled messages: {:#}", e),
+                }
+                flush_audit(&app).await;
+            }
+            _ = prune_tick.tick() => {
+                match crate::cmd::store::apply_retention(&store, &cli.store_dir()).await {
+                    Ok(pruned) => {
+                        let messages: u64 = pruned.iter().map(|p| p.messages).sum();
+                        if messages > 0 {
+                            log::info!("Pruned {} messages past their chat's retention", messages);
+                        }
+                    }
+                    Err(e) => log::error!("Failed to apply retention policies: {:#}", e),
+                }
+            }
+            _ = checkpoint_tick.tick() => {
+                match store.checkpoint().await {
+                    Ok(true) => log::debug!("WAL checkpoint incomplete (database in use), retrying later"),
+                    Ok(false) => log::debug!("WAL checkpointed"),
+                    Err(e) => log::warn!("Failed to checkpoint the WAL: {:#}", e),
+                }
+            }
             update_result = update_stream.next() => {
                 match update_result {
                     Ok(update) => {
-                        messages_received.fetch_add(1, Ordering::Relaxed);
+                        metrics.updates_received.fetch_add(1, Ordering::Relaxed);
 
                         match update {
                             Update::NewMessage(msg) => {
@@ -273,7 +398,7 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                                 let peer = match msg.peer() {
                                     Ok(p) => p.clone(),
                                     Err(_) => {
-                                        log::warn!("Could not resolve peer for message {}", msg.id());
+                                        log::warn!(msg_id = msg.id(); "Could not resolve peer for message {}", msg.id());
                                         continue;
                                     }
                                 };
@@ -291,15 +416,23 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
 
                                 let sender_id = extract_sender_id(&msg);
                                 let from_me = msg.outgoing();
-                                let text = msg.text().to_string();
+                                let service = update_message(&msg.raw).and_then(service_action);
+                                let text = match &service {
+                                    Some(action) => action.text.clone(),
+                                    None => msg.text().to_string(),
+                                };
                                 let ts = msg.date();
                                 let reply_to_id = msg.reply_to_message_id().map(|id| id as i64);
                                 let topic_id = extract_topic_id_from_raw(&msg.raw);
-                                let media_type = msg.media().map(|_| "media".to_string());
+                                let media_type = match &service {
+                                    Some(_) => Some(SERVICE_MEDIA_TYPE.to_string()),
+                                    None => msg.media().map(|_| "media".to_string()),
+                                };
 
                                 // Stream output if enabled
                                 if args.stream {
                                     use std::io::Write;
+                                    let text = label_live_text(&app.tg.client, &store, &text, update_entities(&msg.raw)).await;
                                     let obj = serde_json::json!({
                                         "type": "new_message",
                                         "chat_id": chat_id,
@@ -311,12 +444,12 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                                         "topic_id": topic_id,
                                         "media_type": media_type,
                                     });
-                                    println!("{}", serde_json::to_string(&obj).unwrap_or_default());
+                                    println!("{}", crate::out::redact::to_json_line(&obj).unwrap_or_default());
                                     let _ = std::io::stdout().flush();
                                 }
 
-                                // Store message directly - get fresh store for each operation
-                                if let Err(e) = app.get_store().await?.upsert_message(UpsertMessageParams {
+                                // Store message directly
+                                if let Err(e) = store.upsert_message(UpsertMessageParams {
                                     id: msg.id() as i64,
                                     chat_id,
                                     sender_id,
@@ -329,11 +462,110 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                                     reply_to_id,
                                     topic_id,
                                 }).await {
-                                    log::error!("Failed to store message: {}", e);
+                                    log::error!(chat_id = chat_id; "Failed to store message: {}", e);
+                                    metrics.errors.fetch_add(1, Ordering::Relaxed);
                                 } else {
-                                    messages_stored.fetch_add(1, Ordering::Relaxed);
+                                    metrics.messages_stored.fetch_add(1, Ordering::Relaxed);
+                                }
+                                if let Err(e) = record_custom_emoji(&app.tg.client, &store, chat_id, msg.id() as i64, &custom_emoji_spans(update_entities(&msg.raw))).await {
+                                    log::error!(chat_id = chat_id; "Failed to store custom emoji: {}", e);
+                                }
+                                if let Some(raw) = update_message(&msg.raw) {
+                                    if let Err(e) = record_message_details(&store, chat_id, msg.id() as i64, raw).await {
+                                        log::error!(chat_id = chat_id; "Failed to store message details: {}", e);
+                                    }
                                 }
 
+                                // Keyword alerts, delivered in the background
+                                if !from_me && !msg.text().is_empty() {
+                                    match store.list_alerts().await {
+                                        Ok(alerts) => {
+                                            for alert in alerts.into_iter().filter(|a| alert_matches(a, chat_id, msg.text())) {
+                                                let event = AlertEvent {
+                                                    alert_id: alert.id,
+                                                    pattern: alert.pattern.clone(),
+                                                    chat_id,
+                                                    chat_name: chat_name_from_peer(&peer),
+                                                    msg_id: msg.id() as i64,
+                                                    sender_id,
+                                                    ts: ts.to_rfc3339(),
+                                                    text: msg.text().to_string(),
+                                                };
+                                                tokio::spawn(async move {
+                                                    if let Err(e) = fire(&alert, &event).await {
+                                                        log::error!(chat_id = event.chat_id; "Alert {} failed: {:#}", alert.id, e);
+                                                    }
+                                                });
+                                            }
+                                        }
+                                        Err(e) => log::error!("Failed to load alerts: {}", e),
+                                    }
+                                }
+
+                                let own = sent_by_daemon.remove(&(chat_id, msg.id() as i64));
+
+                                // Away mode: answer private messages, and end when
+                                // you send a message yourself
+                                if from_me && !own {
+                                    match store.away_mode().await {
+                                        Ok(Some(_)) => {
+                                            if let Err(e) = store.clear_away_mode().await {
+                                                log::error!("Failed to turn away mode off: {}", e);
+                                            } else if !args.quiet {
+                                                eprintln!("Away mode off (you sent a message)");
+                                            }
+                                        }
+                                        Ok(None) => {}
+                                        Err(e) => log::error!("Failed to load away mode: {}", e),
+                                    }
+                                }
+                                let from_bot = matches!(msg.sender(), Some(Peer::User(u)) if u.is_bot());
+                                if !from_me && chat_kind == "user" && !from_bot && service.is_none() {
+                                    let mark = guard::mark();
+                                    match app.away_reply(chat_id, sender_id).await {
+                                        Ok(Some(reply_id)) => {
+                                            sent_by_daemon.insert((chat_id, reply_id));
+                                        }
+                                        Ok(None) => {}
+                                        Err(e) => {
+                                            guard::note_failure(mark, &e);
+                                            log::error!(chat_id = chat_id; "Away reply failed: {:#}", e);
+                                        }
+                                    }
+                                }
+
+                                // Auto-forward rules. Messages they forwarded are never
+                                // forwarded again, so rules can't loop (A -> B -> A)
+                                if service.is_none() && !own {
+                                    match store.list_forward_rules().await {
+                                        Ok(rules) => {
+                                            let matching = rules.into_iter().filter(|r| {
+                                                r.from_chat_id == chat_id
+                                                    && r.pattern.as_deref().is_none_or(|p| pattern_matches(p, msg.text()))
+                                            });
+                                            for rule in matching {
+                                                let mark = guard::mark();
+                                                let ok = match app.forward_message(chat_id, msg.id() as i64, rule.to_chat_id, None).await {
+                                                    Ok(new_id) => {
+                                                        sent_by_daemon.insert((rule.to_chat_id, new_id));
+                                                        true
+                                                    }
+                                                    Err(e) => {
+                                                        guard::note_failure(mark, &e);
+                                                        log::error!(chat_id = chat_id; "Forward rule {} failed: {:#}", rule.id, e);
+                                                        false
+                                                    }
+                                                };
+                                                if let Err(e) = store.record_forward(rule.id, ok, Utc::now()).await {
+                                                    log::error!("Failed to update forward rule {}: {}", rule.id, e);
+                                                }
+                                            }
+                                        }
+                                        Err(e) => log::error!("Failed to load forward rules: {}", e),
+                                    }
+                                }
+                                flush_audit(&app).await;
+
                                 // Update chat metadata
                                 let chat_name = chat_name_from_peer(&peer);
                                 let username = username_from_peer(&peer);
@@ -341,9 +573,7 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                                 let access_hash = access_hash_from_peer(&peer);
 
                                 // Check existing chat's archived status, default to false for new chats
-                                let archived = app
-                                    .get_store()
-                                    .await?
+                                let archived = store
                                     .get_chat(chat_id)
                                     .await
                                     .ok()
@@ -351,7 +581,7 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                                     .map(|c| c.archived)
                                     .unwrap_or(false);
 
-                                if let Err(e) = app.get_store().await?.upsert_chat(
+                                if let Err(e) = store.upsert_chat(
                                     chat_id,
                                     chat_kind,
                                     &chat_name,
@@ -361,12 +591,12 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                                     access_hash,
                                     archived,
                                 ).await {
-                                    log::error!("Failed to update chat metadata: {}", e);
+                                    log::error!(chat_id = chat_id; "Failed to update chat metadata: {}", e);
                                 }
 
                                 // Update last sync message ID
-                                if let Err(e) = app.get_store().await?.update_last_sync_message_id(chat_id, msg.id() as i64).await {
-                                    log::error!("Failed to update last_sync_message_id: {}", e);
+                                if let Err(e) = store.update_last_sync_message_id(chat_id, msg.id() as i64).await {
+                                    log::error!(chat_id = chat_id; "Failed to update last_sync_message_id: {}", e);
                                 }
                             }
                             Update::MessageEdited(msg) => {
@@ -391,20 +621,25 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                                 // Stream output if enabled
                                 if args.stream {
                                     use std::io::Write;
+                                    let text = label_live_text(&app.tg.client, &store, &text, update_entities(&msg.raw)).await;
                                     let obj = serde_json::json!({
                                         "type": "message_edited",
                                         "chat_id": chat_id,
                                         "id": msg.id(),
                                         "text": text,
-                                        "edit_ts": Utc::now().to_rfc3339(),
+                                        "edit_ts": msg.edit_date().unwrap_or_else(Utc::now).to_rfc3339(),
                                     });
-                                    println!("{}", serde_json::to_string(&obj).unwrap_or_default());
+                                    println!("{}", crate::out::redact::to_json_line(&obj).unwrap_or_default());
                                     let _ = std::io::stdout().flush();
                                 }
 
-                                // Update message text - get fresh store for each operation
-                                if let Err(e) = app.get_store().await?.update_message_text(chat_id, msg.id() as i64, &text).await {
-                                    log::error!("Failed to update edited message: {}", e);
+                                // Update message text
+                                let edit_ts = msg.edit_date().unwrap_or_else(Utc::now);
+                                if let Err(e) = store.update_message_text(chat_id, msg.id() as i64, &text, edit_ts).await {
+                                    log::error!(chat_id = chat_id; "Failed to update edited message: {}", e);
+                                }
+                                if let Err(e) = record_custom_emoji(&app.tg.client, &store, chat_id, msg.id() as i64, &custom_emoji_spans(update_entities(&msg.raw))).await {
+                                    log::error!(chat_id = chat_id; "Failed to store custom emoji: {}", e);
                                 }
                             }
                             Update::MessageDeleted(deletion) => {
@@ -426,16 +661,42 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                                         "chat_id": chat_id,
                                         "message_ids": msg_ids,
                                     });
-                                    println!("{}", serde_json::to_string(&obj).unwrap_or_default());
+                                    println!("{}", crate::out::redact::to_json_line(&obj).unwrap_or_default());
                                     let _ = std::io::stdout().flush();
                                 }
 
-                                // Note: We don't delete from local DB by default
-                                // Messages remain for history. Add --delete-on-remote-delete flag if needed.
+                                // Messages stay in the local DB for history, marked deleted
+                                if let Err(e) = store.mark_messages_deleted(chat_id, &msg_ids, Utc::now()).await {
+                                    log::error!("Failed to mark deleted messages: {}", e);
+                                }
                             }
                             Update::Raw(raw) => {
-                                // Log unhandled update types for debugging
-                                log::debug!("Unhandled raw update: {:?}", raw.raw);
+                                let tl::enums::Update::MessageReactions(update) = &raw.raw else {
+                                    // Log unhandled update types for debugging
+                                    log::debug!("Unhandled raw update: {:?}", raw.raw);
+                                    continue;
+                                };
+                                let chat_id = peer_bare_id(&update.peer);
+                                if ignore_set.contains(&chat_id) {
+                                    continue;
+                                }
+                                let reactions = reaction_counts(&update.reactions);
+
+                                if args.stream {
+                                    use std::io::Write;
+                                    let obj = serde_json::json!({
+                                        "type": "reaction",
+                                        "chat_id": chat_id,
+                                        "id": update.msg_id,
+                                        "reactions": reactions,
+                                    });
+                                    println!("{}", crate::out::redact::to_json_line(&obj).unwrap_or_default());
+                                    let _ = std::io::stdout().flush();
+                                }
+
+                                if let Err(e) = store.set_message_reactions(chat_id, update.msg_id as i64, &reactions).await {
+                                    log::error!(chat_id = chat_id; "Failed to update reactions: {}", e);
+                                }
                             }
                             _ => {
                                 // CallbackQuery, InlineQuery, etc. - not relevant for message sync
@@ -444,6 +705,7 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
                     }
                     Err(e) => {
                         log::error!("Update stream error: {}", e);
+                        metrics.errors.fetch_add(1, Ordering::Relaxed);
                         if !args.quiet {
                             eprintln!("Update stream error: {}", e);
                         }
@@ -459,7 +721,7 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
 
     // Wait for backfill to finish if running (with timeout)
     if let Some(handle) = backfill_handle {
-        if backfill_running.load(Ordering::Relaxed) && !args.quiet {
+        if metrics.backfill_running.load(Ordering::Relaxed) && !args.quiet {
             eprintln!("Waiting for background sync to complete...");
         }
         // Give backfill a chance to finish, but don't wait forever
@@ -475,8 +737,8 @@ pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<()> {
     if !args.quiet {
         eprintln!(
             "Daemon stopped. Updates received: {}, stored: {}",
-            messages_received.load(Ordering::Relaxed),
-            messages_stored.load(Ordering::Relaxed)
+            metrics.updates_received.load(Ordering::Relaxed),
+            metrics.messages_stored.load(Ordering::Relaxed)
         );
     }
 
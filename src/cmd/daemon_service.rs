@@ -68,9 +68,9 @@ pub enum DaemonServiceSubcommand {
 
 #[derive(Args, Debug, Clone)]
 pub struct DaemonInstallArgs {
-    /// Store directory (e.g., ~/.tgcli or ~/.tgcli-uae)
-    #[arg(long, default_value = "~/.tgcli")]
-    pub store: String,
+    /// Store directory (e.g., ~/.tgcli-uae; default: the global --store or the default location)
+    #[arg(long)]
+    pub store: Option<String>,
 
     /// Don't run backfill on startup
     #[arg(long, default_value_t = true)]
@@ -81,7 +81,7 @@ pub struct DaemonInstallArgs {
     pub quiet: bool,
 
     /// Chat IDs to ignore (can be specified multiple times)
-    #[arg(long = "ignore", value_name = "CHAT_ID")]
+    #[arg(long = "ignore", value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
     pub ignore_chat_ids: Vec<i64>,
 }
 
@@ -114,12 +114,12 @@ pub async fn run(cli: &crate::Cli, args: &DaemonServiceArgs) -> Result<()> {
     }
 }
 
-async fn install_service(
-    _cli: &crate::Cli,
-    args: &DaemonInstallArgs,
-    platform: &str,
-) -> Result<()> {
-    let store = shellexpand::tilde(&args.store).to_string();
+async fn install_service(cli: &crate::Cli, args: &DaemonInstallArgs, platform: &str) -> Result<()> {
+    let explicit_store = args.store.as_deref().or(cli.store.as_deref());
+    let store = match explicit_store {
+        Some(store) => shellexpand::tilde(store).to_string(),
+        None => cli.store_dir(),
+    };
     let binary_path = std::env::current_exe()?
         .parent()
         .expect("binary path should have parent")
@@ -128,7 +128,12 @@ async fn install_service(
         .to_string();
 
     // Build command arguments
-    let mut cmd_args = vec!["--store".to_string(), store.clone(), "daemon".to_string()];
+    // Without --store the service resolves the default layout itself
+    let mut cmd_args = Vec::new();
+    if explicit_store.is_some() {
+        cmd_args.extend(["--store".to_string(), store.clone()]);
+    }
+    cmd_args.push("daemon".to_string());
 
     if args.no_backfill {
         cmd_args.push("--no-backfill".to_string());
@@ -0,0 +1,87 @@
+use crate::Cli;
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Subcommand};
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DocsCommand {
+    /// Write man pages: one per command (tgcli.1, tgcli-messages-list.1, ...)
+    Man {
+        /// Directory to write the pages to (default: print tgcli.1 to stdout)
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Print the help of every command as one markdown reference
+    Markdown {
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+/// Append the help of `cmd` and its visible subcommands, depth first.
+fn write_markdown(cmd: &mut clap::Command, doc: &mut String) {
+    let name = cmd
+        .get_bin_name()
+        .unwrap_or_else(|| cmd.get_name())
+        .to_string();
+    let depth = name.split_whitespace().count().min(6);
+    doc.push_str(&format!("{} {}\n\n", "#".repeat(depth), name));
+    if let Some(about) = cmd.get_about() {
+        doc.push_str(&format!("{}\n\n", about));
+    }
+    doc.push_str(&format!(
+        "```text\n{}\n```\n\n",
+        cmd.render_long_help().to_string().trim_end()
+    ));
+    for sub in cmd.get_subcommands_mut() {
+        if sub.is_hide_set() || sub.get_name() == "help" {
+            continue;
+        }
+        write_markdown(sub, doc);
+    }
+}
+
+pub fn run(cmd: &DocsCommand) -> Result<()> {
+    let mut app = Cli::command();
+    app.build();
+
+    match cmd {
+        DocsCommand::Man { out_dir: Some(dir) } => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+            clap_mangen::generate_to(app, dir)
+                .with_context(|| format!("Failed to write man pages to '{}'", dir.display()))?;
+            eprintln!("Wrote man pages to {}", dir.display());
+        }
+        DocsCommand::Man { out_dir: None } => {
+            let mut page = Vec::new();
+            clap_mangen::Man::new(app).render(&mut page)?;
+            std::io::stdout().write_all(&page)?;
+        }
+        DocsCommand::Markdown { out } => {
+            let mut doc = String::from("# tgcli command reference\n\n");
+            doc.push_str("Generated by `tgcli docs markdown`.\n\n");
+            doc.push_str(&format!(
+                "```text\n{}\n```\n\n",
+                app.render_long_help().to_string().trim_end()
+            ));
+            for sub in app.get_subcommands_mut() {
+                if sub.is_hide_set() || sub.get_name() == "help" {
+                    continue;
+                }
+                write_markdown(sub, &mut doc);
+            }
+            match out {
+                Some(path) => {
+                    std::fs::write(path, &doc)
+                        .with_context(|| format!("Failed to write '{}'", path.display()))?;
+                    eprintln!("Wrote {}", path.display());
+                }
+                None => print!("{}", doc),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use crate::out;
+use crate::store::{self, Store};
+use crate::tg::{ConnectOptions, TgClient};
+use crate::Cli;
+use anyhow::{Context, Result};
+use clap::Args;
+use grammers_tl_types as tl;
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+/// Warn when the store's filesystem has less free space than this
+const MIN_FREE_BYTES: u64 = 1024 * 1024 * 1024;
+/// Warn when the local clock differs from Telegram's by more than this (seconds)
+const MAX_CLOCK_SKEW_SECS: i64 = 30;
+/// Give up on the Telegram checks after this long
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Args, Debug, Clone)]
+pub struct DoctorArgs {
+    /// Skip checks that need network access (session, DC, clock skew)
+    #[arg(long)]
+    pub offline: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+pub async fn run(cli: &Cli, args: &DoctorArgs) -> Result<()> {
+    let store_dir = cli.store_dir();
+    let mut checks = Vec::new();
+
+    checks.extend(store_checks(&store_dir).await);
+    checks.push(disk_check(&store_dir));
+    if !args.offline {
+        checks.extend(network_checks(&store_dir, &cli.connect_options()).await);
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "ok": failed == 0,
+            "checks": checks,
+        }))?;
+    } else if !cli.output.is_none() {
+        let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for c in &checks {
+            let tag = match c.status {
+                Status::Ok => "ok",
+                Status::Warn => "warn",
+                Status::Fail => "FAIL",
+            };
+            println!(
+                "[{:<4}] {:<width$}  {}",
+                tag,
+                c.name,
+                c.detail,
+                width = width
+            );
+            if let Some(hint) = &c.hint {
+                println!("       {:<width$}  → {}", "", hint, width = width);
+            }
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+/// Schema version and FTS availability of the local database.
+async fn store_checks(store_dir: &str) -> Vec<Check> {
+    let store = match Store::open(store_dir).await {
+        Ok(store) => store,
+        Err(e) => {
+            return vec![Check::fail(
+                "store",
+                format!("cannot open database: {:#}", e),
+                "Check permissions on the store directory, or run `tgcli wipe` to start over",
+            )]
+        }
+    };
+
+    let mut checks = Vec::new();
+    match store.schema_version().await {
+        Ok(v) if v == store::SCHEMA_VERSION => {
+            checks.push(Check::ok("schema", format!("version {}", v)))
+        }
+        Ok(v) if v > store::SCHEMA_VERSION => checks.push(Check::warn(
+            "schema",
+            format!(
+                "version {} is newer than this tgcli supports ({})",
+                v,
+                store::SCHEMA_VERSION
+            ),
+            "Upgrade tgcli",
+        )),
+        Ok(v) => checks.push(Check::fail(
+            "schema",
+            format!("version {} (expected {})", v, store::SCHEMA_VERSION),
+            "Migration did not complete; run `tgcli wipe` and sync again",
+        )),
+        Err(e) => checks.push(Check::fail(
+            "schema",
+            format!("cannot read version: {:#}", e),
+            "Run `tgcli wipe` and sync again",
+        )),
+    }
+
+    if store.has_fts() {
+        checks.push(Check::ok(
+            "fts",
+            format!(
+                "FTS5 full-text search available ({} tokenizer)",
+                store.fts_tokenizer().name()
+            ),
+        ));
+    } else {
+        checks.push(Check::warn(
+            "fts",
+            "FTS5 not available; search falls back to LIKE",
+            "Search still works but is slower on large stores",
+        ));
+    }
+    checks
+}
+
+/// Free space on the filesystem holding the store (and downloaded media).
+fn disk_check(store_dir: &str) -> Check {
+    match free_bytes(Path::new(store_dir)) {
+        Some(free) if free < MIN_FREE_BYTES => Check::warn(
+            "disk",
+            format!("{} MB free in {}", free / (1024 * 1024), store_dir),
+            "Free up space before syncing with --download-media",
+        ),
+        Some(free) => Check::ok(
+            "disk",
+            format!("{:.1} GB free", free as f64 / (1024.0 * 1024.0 * 1024.0)),
+        ),
+        None => Check::warn(
+            "disk",
+            "could not determine free space",
+            "`df` is needed for this check",
+        ),
+    }
+}
+
+/// Available bytes on the filesystem containing `dir`, via `df -Pk`.
+fn free_bytes(dir: &Path) -> Option<u64> {
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().nth(1)?;
+    let available_kb: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available_kb * 1024)
+}
+
+/// Session validity, DC connectivity, and clock skew against Telegram's servers.
+async fn network_checks(store_dir: &str, opts: &ConnectOptions) -> Vec<Check> {
+    let session_path = crate::paths::session_path(store_dir);
+    if !Path::new(&session_path).exists() {
+        return vec![Check::fail(
+            "session",
+            "no session file",
+            "Run `tgcli auth` (or `tgcli init`)",
+        )];
+    }
+
+    match tokio::time::timeout(NETWORK_TIMEOUT, telegram_checks(&session_path, opts)).await {
+        Ok(Ok(checks)) => checks,
+        Ok(Err(e)) => vec![Check::fail(
+            "dc",
+            format!("cannot reach Telegram: {:#}", e),
+            "Check your network connection or firewall",
+        )],
+        Err(_) => vec![Check::fail(
+            "dc",
+            format!("no response within {}s", NETWORK_TIMEOUT.as_secs()),
+            "Check your network connection or firewall",
+        )],
+    }
+}
+
+async fn telegram_checks(session_path: &str, opts: &ConnectOptions) -> Result<Vec<Check>> {
+    let (tg, _updates) = TgClient::connect_with_updates(session_path, opts)?;
+    let mut checks = Vec::new();
+
+    let started = std::time::Instant::now();
+    let tl::enums::Config::Config(config) = tg
+        .invoke(&tl::functions::help::GetConfig {})
+        .await
+        .context("help.getConfig failed")?;
+    let rtt = started.elapsed();
+    checks.push(Check::ok(
+        "dc",
+        format!(
+            "connected to DC {} ({} ms)",
+            config.this_dc,
+            rtt.as_millis()
+        ),
+    ));
+
+    let authorized = tg
+        .client
+        .is_authorized()
+        .await
+        .context("Failed to check authorization status")?;
+    if authorized {
+        checks.push(Check::ok("session", "authorized"));
+    } else {
+        checks.push(Check::fail(
+            "session",
+            "session exists but is not authorized",
+            "Run `tgcli auth` to log in again",
+        ));
+    }
+
+    let skew = chrono::Utc::now().timestamp() - config.date as i64;
+    if skew.abs() > MAX_CLOCK_SKEW_SECS {
+        checks.push(Check::warn(
+            "clock",
+            format!("local clock is {}s off from Telegram", skew),
+            "Enable NTP time sync; large skew breaks MTProto message IDs",
+        ));
+    } else {
+        checks.push(Check::ok("clock", format!("skew {}s", skew)));
+    }
+
+    Ok(checks)
+}
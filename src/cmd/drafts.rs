@@ -18,7 +18,7 @@ pub enum DraftsCommand {
     /// Clear draft for a specific chat
     Clear {
         /// Chat ID to clear draft from
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
     },
 }
@@ -46,8 +46,8 @@ pub async fn run(cli: &Cli, cmd: &DraftsCommand) -> Result<()> {
                 let chat = store.get_chat(draft.chat_id).await?;
                 enriched.push(DraftInfo {
                     chat_id: draft.chat_id,
-                    chat_name: chat.map(|c| c.name),
-                    text: draft.text,
+                    chat_name: chat.map(|c| out::redact::text(&c.name).into_owned()),
+                    text: out::redact::text(&draft.text).into_owned(),
                     date: draft.date,
                     reply_to_msg_id: draft.reply_to_msg_id,
                 });
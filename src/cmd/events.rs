@@ -0,0 +1,388 @@
+//! `tgcli events`: live Telegram updates as a JSONL stream with a fixed
+//! schema, one event per line, for scripts and pipelines.
+//!
+//! Unlike `tgcli daemon --stream`, nothing is written to the local store;
+//! the schema is documented in the README and only grows new fields.
+
+use crate::app::reactions::reaction_counts;
+use crate::app::send::get_media_type;
+use crate::app::service::service_action;
+use crate::app::{format_date, peer_bare_id, App};
+use crate::cmd::daemon::extract_topic_from_message;
+use crate::config::Config;
+use crate::store::MessageReaction;
+use crate::Cli;
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use grammers_client::{Update, UpdatesConfiguration};
+use grammers_tl_types as tl;
+use serde::Serialize;
+use std::collections::HashSet;
+
+#[derive(Args, Debug, Clone)]
+pub struct EventsArgs {
+    /// Only events of this type (repeatable; default: all)
+    #[arg(long = "type", value_enum, value_name = "TYPE")]
+    pub types: Vec<EventType>,
+
+    /// Only events in this chat (repeatable). Presence events, and deletions
+    /// in private chats and basic groups, carry no chat and are dropped
+    #[arg(long, value_parser = crate::config::parse_chat)]
+    pub chat: Vec<i64>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    Message,
+    Edit,
+    Delete,
+    Reaction,
+    Typing,
+    Presence,
+    MemberJoin,
+}
+
+/// One line of `tgcli events` output; `type` says which.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A new message (service messages such as joins too, with `service: true`)
+    Message {
+        chat_id: i64,
+        id: i32,
+        sender_id: i64,
+        from_me: bool,
+        ts: String,
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        topic_id: Option<i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reply_to_id: Option<i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        media_type: Option<String>,
+        service: bool,
+    },
+    /// New text of an edited message
+    Edit {
+        chat_id: i64,
+        id: i32,
+        text: String,
+        edit_ts: String,
+    },
+    /// Telegram doesn't say which chat for private chats and basic groups,
+    /// so `chat_id` is null there
+    Delete { chat_id: Option<i64>, ids: Vec<i32> },
+    /// All reactions of a message after a change
+    Reaction {
+        chat_id: i64,
+        id: i32,
+        reactions: Vec<MessageReaction>,
+    },
+    /// Someone is typing, recording, uploading, ... (`action`); `cancel`
+    /// when they stopped
+    Typing {
+        chat_id: i64,
+        user_id: i64,
+        action: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        topic_id: Option<i32>,
+    },
+    /// A user went online or offline, or only shares a rough last seen
+    Presence {
+        user_id: i64,
+        /// `online`, `offline`, `recently`, `last_week`, `last_month` or `unknown`
+        status: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expires: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        last_seen: Option<String>,
+    },
+    /// A user joined a group, was added, or was let in after a join request
+    MemberJoin {
+        chat_id: i64,
+        user_id: i64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        inviter_id: Option<i64>,
+        ts: String,
+    },
+}
+
+impl Event {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            Event::Message { .. } => EventType::Message,
+            Event::Edit { .. } => EventType::Edit,
+            Event::Delete { .. } => EventType::Delete,
+            Event::Reaction { .. } => EventType::Reaction,
+            Event::Typing { .. } => EventType::Typing,
+            Event::Presence { .. } => EventType::Presence,
+            Event::MemberJoin { .. } => EventType::MemberJoin,
+        }
+    }
+
+    pub fn chat_id(&self) -> Option<i64> {
+        match self {
+            Event::Message { chat_id, .. }
+            | Event::Edit { chat_id, .. }
+            | Event::Reaction { chat_id, .. }
+            | Event::Typing { chat_id, .. }
+            | Event::MemberJoin { chat_id, .. } => Some(*chat_id),
+            Event::Delete { chat_id, .. } => *chat_id,
+            Event::Presence { .. } => None,
+        }
+    }
+}
+
+fn typing_action(action: &tl::enums::SendMessageAction) -> &'static str {
+    use tl::enums::SendMessageAction as A;
+    match action {
+        A::SendMessageTypingAction => "typing",
+        A::SendMessageCancelAction => "cancel",
+        A::SendMessageRecordVideoAction => "record_video",
+        A::SendMessageUploadVideoAction(_) => "upload_video",
+        A::SendMessageRecordAudioAction => "record_audio",
+        A::SendMessageUploadAudioAction(_) => "upload_audio",
+        A::SendMessageUploadPhotoAction(_) => "upload_photo",
+        A::SendMessageUploadDocumentAction(_) => "upload_document",
+        A::SendMessageGeoLocationAction => "location",
+        A::SendMessageChooseContactAction => "contact",
+        A::SendMessageGamePlayAction => "game",
+        A::SendMessageRecordRoundAction => "record_round",
+        A::SendMessageUploadRoundAction(_) => "upload_round",
+        A::SpeakingInGroupCallAction => "speaking_in_call",
+        A::SendMessageChooseStickerAction => "sticker",
+        _ => "other",
+    }
+}
+
+fn presence(user_id: i64, status: &tl::enums::UserStatus) -> Event {
+    let (status, expires, last_seen) = match status {
+        tl::enums::UserStatus::Empty => ("unknown", None, None),
+        tl::enums::UserStatus::Online(s) => ("online", Some(format_date(s.expires)), None),
+        tl::enums::UserStatus::Offline(s) => ("offline", None, Some(format_date(s.was_online))),
+        tl::enums::UserStatus::Recently(_) => ("recently", None, None),
+        tl::enums::UserStatus::LastWeek(_) => ("last_week", None, None),
+        tl::enums::UserStatus::LastMonth(_) => ("last_month", None, None),
+    };
+    Event::Presence {
+        user_id,
+        status,
+        expires,
+        last_seen,
+    }
+}
+
+/// A new message, plus a `member_join` per user when it's a join.
+fn message_events(raw: &tl::enums::Message, media_type: Option<String>) -> Vec<Event> {
+    match raw {
+        tl::enums::Message::Message(m) => {
+            let chat_id = peer_bare_id(&m.peer_id);
+            let reply_to_id = match &m.reply_to {
+                Some(tl::enums::MessageReplyHeader::Header(h)) => h.reply_to_msg_id,
+                _ => None,
+            };
+            vec![Event::Message {
+                chat_id,
+                id: m.id,
+                sender_id: m.from_id.as_ref().map_or(chat_id, peer_bare_id),
+                from_me: m.out,
+                ts: format_date(m.date),
+                text: m.message.clone(),
+                topic_id: extract_topic_from_message(raw),
+                reply_to_id,
+                media_type,
+                service: false,
+            }]
+        }
+        tl::enums::Message::Service(m) => {
+            let chat_id = peer_bare_id(&m.peer_id);
+            let sender_id = m.from_id.as_ref().map_or(chat_id, peer_bare_id);
+            let ts = format_date(m.date);
+            let mut events = vec![Event::Message {
+                chat_id,
+                id: m.id,
+                sender_id,
+                from_me: m.out,
+                ts: ts.clone(),
+                text: service_action(raw).map(|a| a.text).unwrap_or_default(),
+                topic_id: None,
+                reply_to_id: None,
+                media_type: None,
+                service: true,
+            }];
+            let join = |user_id, inviter_id| Event::MemberJoin {
+                chat_id,
+                user_id,
+                inviter_id,
+                ts: ts.clone(),
+            };
+            match &m.action {
+                tl::enums::MessageAction::ChatAddUser(a) => events.extend(
+                    a.users
+                        .iter()
+                        .map(|&user_id| join(user_id, Some(sender_id).filter(|&s| s != user_id))),
+                ),
+                tl::enums::MessageAction::ChatJoinedByLink(a) => {
+                    events.push(join(sender_id, Some(a.inviter_id)))
+                }
+                tl::enums::MessageAction::ChatJoinedByRequest => events.push(join(sender_id, None)),
+                _ => {}
+            }
+            events
+        }
+        tl::enums::Message::Empty(_) => Vec::new(),
+    }
+}
+
+/// The events in a raw update (none for updates outside the schema).
+pub fn raw_events(raw: &tl::enums::Update, media_type: Option<String>) -> Vec<Event> {
+    use tl::enums::Update as U;
+    let edit = |message: &tl::enums::Message| match message {
+        tl::enums::Message::Message(m) => vec![Event::Edit {
+            chat_id: peer_bare_id(&m.peer_id),
+            id: m.id,
+            text: m.message.clone(),
+            edit_ts: format_date(m.edit_date.unwrap_or(m.date)),
+        }],
+        _ => Vec::new(),
+    };
+    match raw {
+        U::NewMessage(u) => message_events(&u.message, media_type),
+        U::NewChannelMessage(u) => message_events(&u.message, media_type),
+        U::EditMessage(u) => edit(&u.message),
+        U::EditChannelMessage(u) => edit(&u.message),
+        U::DeleteMessages(u) => vec![Event::Delete {
+            chat_id: None,
+            ids: u.messages.clone(),
+        }],
+        U::DeleteChannelMessages(u) => vec![Event::Delete {
+            chat_id: Some(u.channel_id),
+            ids: u.messages.clone(),
+        }],
+        U::MessageReactions(u) => vec![Event::Reaction {
+            chat_id: peer_bare_id(&u.peer),
+            id: u.msg_id,
+            reactions: reaction_counts(&u.reactions),
+        }],
+        U::UserTyping(u) => vec![Event::Typing {
+            chat_id: u.user_id,
+            user_id: u.user_id,
+            action: typing_action(&u.action),
+            topic_id: u.top_msg_id,
+        }],
+        U::ChatUserTyping(u) => vec![Event::Typing {
+            chat_id: u.chat_id,
+            user_id: peer_bare_id(&u.from_id),
+            action: typing_action(&u.action),
+            topic_id: None,
+        }],
+        U::ChannelUserTyping(u) => vec![Event::Typing {
+            chat_id: u.channel_id,
+            user_id: peer_bare_id(&u.from_id),
+            action: typing_action(&u.action),
+            topic_id: u.top_msg_id,
+        }],
+        U::UserStatus(u) => vec![presence(u.user_id, &u.status)],
+        _ => Vec::new(),
+    }
+}
+
+pub async fn run(cli: &Cli, args: &EventsArgs) -> Result<()> {
+    use std::io::Write;
+
+    let mut app = App::new(cli).await?;
+    let updates_rx = app
+        .updates_rx
+        .take()
+        .context("Updates receiver not available")?;
+    let ignored: HashSet<i64> = Config::load(&cli.config_dir())?
+        .sync
+        .ignore_chat_ids
+        .into_iter()
+        .collect();
+    let mut update_stream = app.tg.client.stream_updates(
+        updates_rx,
+        UpdatesConfiguration {
+            catch_up: false,
+            ..Default::default()
+        },
+    );
+    let shutdown_ctrl = crate::shutdown::global();
+
+    loop {
+        let update = tokio::select! {
+            _ = shutdown_ctrl.cancelled() => break,
+            update = update_stream.next() => update,
+        };
+        let events = match update {
+            Ok(Update::NewMessage(msg)) => {
+                raw_events(&msg.raw, msg.media().map(|m| get_media_type(&m)))
+            }
+            Ok(Update::MessageEdited(msg)) => raw_events(&msg.raw, None),
+            Ok(Update::MessageDeleted(deletion)) => raw_events(&deletion.raw, None),
+            Ok(Update::Raw(raw)) => raw_events(&raw.raw, None),
+            Ok(_) => continue,
+            Err(e) => {
+                log::error!("Update stream error: {}", e);
+                if e.to_string().contains("Dropped") {
+                    break;
+                }
+                continue;
+            }
+        };
+        for event in events {
+            if !args.types.is_empty() && !args.types.contains(&event.event_type()) {
+                continue;
+            }
+            let chat_id = event.chat_id();
+            if chat_id.is_some_and(|id| ignored.contains(&id)) {
+                continue;
+            }
+            if !args.chat.is_empty() && !chat_id.is_some_and(|id| args.chat.contains(&id)) {
+                continue;
+            }
+            println!("{}", crate::out::redact::to_json_line(&event)?);
+        }
+        let _ = std::io::stdout().flush();
+    }
+
+    update_stream.sync_update_state();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_typing_and_presence_updates() {
+        let typing = tl::enums::Update::ChatUserTyping(tl::types::UpdateChatUserTyping {
+            chat_id: 5,
+            from_id: tl::enums::Peer::User(tl::types::PeerUser { user_id: 7 }),
+            action: tl::enums::SendMessageAction::SendMessageRecordAudioAction,
+        });
+        let events = raw_events(&typing, None);
+        assert_eq!(events[0].chat_id(), Some(5));
+        assert_eq!(
+            serde_json::to_value(&events[0]).unwrap(),
+            serde_json::json!({
+                "type": "typing",
+                "chat_id": 5,
+                "user_id": 7,
+                "action": "record_audio",
+            })
+        );
+
+        let status = tl::enums::Update::UserStatus(tl::types::UpdateUserStatus {
+            user_id: 7,
+            status: tl::enums::UserStatus::Recently(tl::types::UserStatusRecently { by_me: false }),
+        });
+        let events = raw_events(&status, None);
+        assert_eq!(events[0].event_type(), EventType::Presence);
+        assert_eq!(events[0].chat_id(), None);
+        assert_eq!(
+            serde_json::to_value(&events[0]).unwrap(),
+            serde_json::json!({"type": "presence", "user_id": 7, "status": "recently"})
+        );
+    }
+}
@@ -1,31 +1,48 @@
+use crate::app::contact::shared_contact;
+use crate::app::forward::forward_origin;
+use crate::app::geo::message_geo;
+use crate::app::service::service_action;
 use crate::app::App;
-use crate::store::Store;
+use crate::out;
+use crate::out::encrypt::{Sink, Tool};
+use crate::store::{MessageGeo, MessageRows, SharedContact, Store, Topic, SERVICE_MEDIA_TYPE};
 use crate::Cli;
 use anyhow::Result;
 use chrono::{DateTime, Local, NaiveTime, TimeZone, Utc};
 use clap::{Args, ValueEnum};
-use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::Write;
+use std::path::Path;
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum ExportFormat {
     Json,
     Html,
+    Markdown,
+    /// Shared locations and venues as a GeoJSON FeatureCollection
+    Geojson,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct ExportArgs {
     /// Chat ID to export
-    #[arg(long)]
+    #[arg(long, value_parser = crate::config::parse_chat)]
     pub chat: i64,
 
     /// Output format
     #[arg(long, value_enum, default_value = "json")]
     pub format: ExportFormat,
 
-    /// Output file path (defaults to stdout for JSON, chat_<id>.html for HTML)
-    #[arg(long, short = 'o')]
-    pub output: Option<String>,
+    /// Output file path (defaults to stdout for JSON, GeoJSON and Markdown,
+    /// chat_<id>.html for HTML); the directory for --split-topics;
+    /// sqlite:PATH writes the messages to a SQLite table instead
+    // Not `--output`: that's the global output mode
+    #[arg(long = "dest", short = 'o', id = "dest", value_name = "PATH")]
+    pub dest: Option<String>,
+
+    /// Forum chats: write one file per topic plus an index (HTML and
+    /// Markdown) instead of one file with a section per topic
+    #[arg(long)]
+    pub split_topics: bool,
 
     /// Only messages after this date (YYYY-MM-DD or RFC3339)
     #[arg(long)]
@@ -42,6 +59,11 @@ pub struct ExportArgs {
     /// Maximum number of messages to export (default: all)
     #[arg(long)]
     pub limit: Option<usize>,
+
+    /// Encrypt the export with age (an age1... or ssh- public key) or gpg
+    /// (a key ID or email) before it's written
+    #[arg(long, value_name = "RECIPIENT", conflicts_with = "split_topics")]
+    pub encrypt_to: Option<String>,
 }
 
 pub async fn run(cli: &Cli, args: &ExportArgs) -> Result<()> {
@@ -58,6 +80,25 @@ pub async fn run(cli: &Cli, args: &ExportArgs) -> Result<()> {
     let since = args.since.as_deref().map(parse_date).transpose()?;
     let until = args.until.as_deref().map(parse_date).transpose()?;
 
+    let sqlite = sqlite_output(cli, args)?;
+
+    if matches!(args.format, ExportFormat::Json) && !args.fetch && args.limit.is_none() {
+        // A whole local chat can be far bigger than memory; write it as it's read
+        eprintln!("Exporting messages from \"{}\"...", chat_name);
+        if let Some(path) = sqlite {
+            return stream_sqlite_from_store(&store, args.chat, since, until, path).await;
+        }
+        return stream_json_from_store(
+            &store,
+            args.chat,
+            since,
+            until,
+            args.dest.as_deref(),
+            args.encrypt_to.as_deref(),
+        )
+        .await;
+    }
+
     // Collect messages
     let messages = if args.fetch {
         // Fetch from Telegram API
@@ -76,16 +117,43 @@ pub async fn run(cli: &Cli, args: &ExportArgs) -> Result<()> {
 
     // Export based on format
     match args.format {
-        ExportFormat::Json => {
-            export_json(&messages, args.output.as_deref())?;
+        ExportFormat::Json => match sqlite {
+            Some(path) => out::sqlite::write_rows(path, "messages", &messages).await?,
+            None => export_json(&messages, args.dest.as_deref(), args.encrypt_to.as_deref())?,
+        },
+        ExportFormat::Html | ExportFormat::Markdown => {
+            // Forums get one section (or file) per topic
+            let is_forum = chat.as_ref().is_some_and(|c| c.is_forum);
+            if args.split_topics && !is_forum {
+                anyhow::bail!("--split-topics needs a forum chat");
+            }
+            let topics = if is_forum {
+                store.list_topics(args.chat).await?
+            } else {
+                Vec::new()
+            };
+            let sections = is_forum.then(|| topic_sections(&messages, &topics));
+            let output = match args.format {
+                ExportFormat::Html if !args.split_topics => {
+                    Some(args.dest.clone().unwrap_or_else(|| {
+                        match args.encrypt_to.as_deref().map(Tool::for_recipient) {
+                            Some(tool) => format!("chat_{}.html.{}", args.chat, tool.extension()),
+                            None => format!("chat_{}.html", args.chat),
+                        }
+                    }))
+                }
+                _ => args.dest.clone(),
+            };
+            export_document(
+                args,
+                &messages,
+                sections.as_deref(),
+                output.as_deref(),
+                &chat_name,
+            )?;
         }
-        ExportFormat::Html => {
-            let output_path = args
-                .output
-                .clone()
-                .unwrap_or_else(|| format!("chat_{}.html", args.chat));
-            export_html(&messages, &output_path, &chat_name, args.chat)?;
-            eprintln!("Exported to: {}", output_path);
+        ExportFormat::Geojson => {
+            export_geojson(&messages, args.dest.as_deref(), args.encrypt_to.as_deref())?;
         }
     }
 
@@ -104,6 +172,16 @@ struct ExportMessage {
     media_type: Option<String>,
     reply_to_id: Option<i64>,
     topic_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    service_action: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    forwarded_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fwd_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shared_contact: Option<SharedContact>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geo: Option<MessageGeo>,
 }
 
 async fn fetch_messages_from_store(
@@ -127,24 +205,121 @@ async fn fetch_messages_from_store(
             before: until,
             ignore_chats: vec![],
             ignore_channels: false,
+            with_location: false,
+            cursor: None,
         })
         .await?;
 
-    Ok(msgs
-        .into_iter()
-        .map(|m| ExportMessage {
-            id: m.id,
-            chat_id: m.chat_id,
-            sender_id: m.sender_id,
-            from_me: m.from_me,
-            ts: m.ts.to_rfc3339(),
-            edit_ts: m.edit_ts.map(|t| t.to_rfc3339()),
-            text: m.text,
-            media_type: m.media_type,
-            reply_to_id: m.reply_to_id,
-            topic_id: m.topic_id,
+    let mut messages = Vec::with_capacity(msgs.len());
+    for m in msgs {
+        messages.push(export_message(store, m).await?);
+    }
+    Ok(messages)
+}
+
+/// The database of `-o sqlite:PATH` (or the global `--output sqlite:PATH`),
+/// which only takes the JSON rows, unencrypted.
+fn sqlite_output<'a>(cli: &'a Cli, args: &'a ExportArgs) -> Result<Option<&'a Path>> {
+    let path = match args.dest.as_deref().and_then(|o| o.strip_prefix("sqlite:")) {
+        Some(path) => Some(Path::new(path)),
+        None => cli.output.sqlite_path(),
+    };
+    if path.is_some() {
+        if !matches!(args.format, ExportFormat::Json) {
+            anyhow::bail!("sqlite: output writes message rows; use it with --format json");
+        }
+        if args.encrypt_to.is_some() {
+            anyhow::bail!("--encrypt-to can't be used with sqlite: output");
+        }
+    }
+    Ok(path)
+}
+
+/// Every stored message of a chat in the date range, oldest first.
+async fn chat_rows(
+    store: &Store,
+    chat_id: i64,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<MessageRows> {
+    store
+        .stream_messages(crate::store::ListMessagesParams {
+            chat_id: Some(chat_id),
+            topic_id: None,
+            limit: 0,
+            after: since,
+            before: until,
+            ignore_chats: vec![],
+            ignore_channels: false,
+            with_location: false,
+            cursor: None,
         })
-        .collect())
+        .await
+}
+
+/// Write every stored message of a chat as JSONL, oldest first, without
+/// holding them all in memory.
+async fn stream_json_from_store(
+    store: &Store,
+    chat_id: i64,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    output: Option<&str>,
+    encrypt_to: Option<&str>,
+) -> Result<()> {
+    let mut rows = chat_rows(store, chat_id, since, until).await?;
+
+    let mut sink = Sink::open(output, encrypt_to)?;
+    let mut count = 0usize;
+    while let Some(m) = rows.next().await? {
+        let msg = export_message(store, m).await?;
+        writeln!(sink, "{}", out::redact::to_json_line(&msg)?)?;
+        count += 1;
+    }
+    sink.finish()?;
+
+    eprintln!("Exported {} messages.", count);
+    if let Some(path) = output {
+        eprintln!("Exported to: {}", path);
+    }
+    Ok(())
+}
+
+/// Like `stream_json_from_store`, into a `messages` table of a SQLite file.
+async fn stream_sqlite_from_store(
+    store: &Store,
+    chat_id: i64,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    path: &Path,
+) -> Result<()> {
+    let mut rows = chat_rows(store, chat_id, since, until).await?;
+    let mut table = out::sqlite::Table::create(path, "messages").await?;
+    while let Some(m) = rows.next().await? {
+        table.insert(&export_message(store, m).await?).await?;
+    }
+    table.finish().await
+}
+
+async fn export_message(store: &Store, m: crate::store::Message) -> Result<ExportMessage> {
+    let geo = store.message_geo(m.chat_id, m.id).await?;
+    Ok(ExportMessage {
+        geo,
+        forwarded_from: m.forwarded_from(),
+        fwd_date: m.fwd_date.map(|t| t.to_rfc3339()),
+        id: m.id,
+        chat_id: m.chat_id,
+        sender_id: m.sender_id,
+        from_me: m.from_me,
+        ts: m.ts.to_rfc3339(),
+        edit_ts: m.edit_ts.map(|t| t.to_rfc3339()),
+        text: m.text,
+        media_type: m.media_type,
+        reply_to_id: m.reply_to_id,
+        topic_id: m.topic_id,
+        service_action: m.service_action,
+        shared_contact: m.shared_contact,
+    })
 }
 
 async fn fetch_messages_from_api(
@@ -179,6 +354,16 @@ async fn fetch_messages_from_api(
         let sender_id = msg.sender().map(|s| s.id().bare_id()).unwrap_or(0);
         let from_me = msg.outgoing();
 
+        let service = service_action(&msg.raw);
+        let origin = forward_origin(&msg.raw);
+        let (text, media_type) = match &service {
+            Some(action) => (action.text.clone(), Some(SERVICE_MEDIA_TYPE.to_string())),
+            None => (
+                msg.text().to_string(),
+                msg.media().map(|_| "media".to_string()),
+            ),
+        };
+
         messages.push(ExportMessage {
             id: msg.id() as i64,
             chat_id,
@@ -186,10 +371,19 @@ async fn fetch_messages_from_api(
             from_me,
             ts: msg_ts.to_rfc3339(),
             edit_ts: msg.edit_date().map(|t| t.to_rfc3339()),
-            text: msg.text().to_string(),
-            media_type: msg.media().map(|_| "media".to_string()),
+            text,
+            media_type,
             reply_to_id: msg.reply_to_message_id().map(|id| id as i64),
-            topic_id: None, // TODO: extract topic_id if needed
+            topic_id: crate::cmd::daemon::extract_topic_from_message(&msg.raw),
+            service_action: service.map(|action| action.json),
+            forwarded_from: origin.as_ref().map(|o| match (&o.name, o.from_id) {
+                (Some(name), _) => name.clone(),
+                (None, Some(id)) => id.to_string(),
+                (None, None) => "hidden user".to_string(),
+            }),
+            fwd_date: origin.map(|o| o.date.to_rfc3339()),
+            shared_contact: shared_contact(&msg.raw),
+            geo: message_geo(&msg.raw),
         });
 
         if messages.len() >= max_count {
@@ -202,40 +396,252 @@ async fn fetch_messages_from_api(
     Ok(messages)
 }
 
-fn export_json(messages: &[ExportMessage], output: Option<&str>) -> Result<()> {
+fn export_json(
+    messages: &[ExportMessage],
+    output: Option<&str>,
+    encrypt_to: Option<&str>,
+) -> Result<()> {
+    // One JSON object per line, to the file or stdout
+    let mut sink = Sink::open(output, encrypt_to)?;
+    for msg in messages {
+        writeln!(sink, "{}", out::redact::to_json_line(msg)?)?;
+    }
+    sink.finish()?;
     if let Some(path) = output {
-        // Write to file as JSONL
-        let file = File::create(path)?;
-        let mut writer = BufWriter::new(file);
+        eprintln!("Exported to: {}", path);
+    }
+    Ok(())
+}
 
-        for msg in messages {
-            serde_json::to_writer(&mut writer, msg)?;
-            writeln!(writer)?;
-        }
+/// Write the messages that share a location as GeoJSON point features.
+fn export_geojson(
+    messages: &[ExportMessage],
+    output: Option<&str>,
+    encrypt_to: Option<&str>,
+) -> Result<()> {
+    let features: Vec<serde_json::Value> = messages
+        .iter()
+        .filter_map(|msg| {
+            let geo = msg.geo.as_ref()?;
+            Some(serde_json::json!({
+                "type": "Feature",
+                // GeoJSON positions are [longitude, latitude]
+                "geometry": {"type": "Point", "coordinates": [geo.lon, geo.lat]},
+                "properties": {
+                    "id": msg.id,
+                    "chat_id": msg.chat_id,
+                    "sender_id": msg.sender_id,
+                    "from_me": msg.from_me,
+                    "ts": msg.ts,
+                    "text": msg.text,
+                    "kind": geo.kind,
+                    "title": geo.title,
+                    "address": geo.address,
+                    "accuracy_radius": geo.accuracy_radius,
+                    "live_period": geo.live_period,
+                },
+            }))
+        })
+        .collect();
+    let count = features.len();
+    let collection = serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
 
-        writer.flush()?;
-        eprintln!("Exported to: {}", path);
-    } else {
-        // Write to stdout as JSONL
-        for msg in messages {
-            println!("{}", serde_json::to_string(msg)?);
-        }
+    let mut sink = Sink::open(output, encrypt_to)?;
+    writeln!(sink, "{}", out::redact::to_json_pretty(&collection)?)?;
+    sink.finish()?;
+    if let Some(path) = output {
+        eprintln!("Exported {} locations to: {}", count, path);
     }
     Ok(())
 }
 
-fn export_html(
+/// Messages of one forum topic, for topic-grouped exports.
+struct TopicSection<'a> {
+    /// None for messages outside any topic (the General topic)
+    id: Option<i32>,
+    name: String,
+    messages: Vec<&'a ExportMessage>,
+}
+
+impl TopicSection<'_> {
+    fn anchor(&self) -> String {
+        format!("topic-{}", self.id.unwrap_or(0))
+    }
+
+    fn file_name(&self, ext: &str) -> String {
+        format!("topic_{}.{}", self.id.unwrap_or(0), ext)
+    }
+}
+
+/// Group a forum's messages by topic (names from the `topics` table), General
+/// first, then by topic ID.
+fn topic_sections<'a>(messages: &'a [ExportMessage], topics: &[Topic]) -> Vec<TopicSection<'a>> {
+    let mut sections: Vec<TopicSection> = Vec::new();
+    for msg in messages {
+        match sections.iter_mut().find(|s| s.id == msg.topic_id) {
+            Some(section) => section.messages.push(msg),
+            None => sections.push(TopicSection {
+                id: msg.topic_id,
+                name: match msg.topic_id {
+                    None => "General".to_string(),
+                    Some(id) => topics
+                        .iter()
+                        .find(|t| t.topic_id == id)
+                        .map(|t| t.name.clone())
+                        .unwrap_or_else(|| format!("Topic {}", id)),
+                },
+                messages: vec![msg],
+            }),
+        }
+    }
+    sections.sort_by_key(|s| s.id);
+    sections
+}
+
+/// Write an HTML or Markdown export: one stream, one section per topic, or
+/// (with `--split-topics`) one file per topic plus an index in the output
+/// directory.
+fn export_document(
+    args: &ExportArgs,
     messages: &[ExportMessage],
-    output_path: &str,
+    sections: Option<&[TopicSection]>,
+    output: Option<&str>,
     chat_name: &str,
-    chat_id: i64,
 ) -> Result<()> {
-    let file = File::create(output_path)?;
-    let mut writer = BufWriter::new(file);
+    let chat_id = args.chat;
+    let encrypt_to = args.encrypt_to.as_deref();
+    let html = matches!(args.format, ExportFormat::Html);
+    let ext = if html { "html" } else { "md" };
+    let exported = Local::now().format("%Y-%m-%d %H:%M:%S");
+    let meta = format!(
+        "Chat ID: {} | {} messages | Exported: {}",
+        chat_id,
+        messages.len(),
+        exported
+    );
+    let render = |title: &str, meta: &str, body: &str| {
+        if html {
+            html_page(title, meta, body)
+        } else {
+            format!("# {}\n\n_{}_\n\n{}", title, meta, body)
+        }
+    };
+    let render_messages = |messages: &[&ExportMessage]| {
+        if html {
+            html_messages(messages)
+        } else {
+            markdown_messages(messages)
+        }
+    };
+
+    match sections {
+        Some(sections) if args.split_topics => {
+            let dir = output
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("chat_{}", chat_id));
+            let dir = std::path::Path::new(&dir);
+            std::fs::create_dir_all(dir)?;
+            let index = topic_index(html, sections, |s| s.file_name(ext));
+            let index_path = dir.join(format!("index.{}", ext));
+            std::fs::write(&index_path, render(chat_name, &meta, &index))?;
+            for section in sections {
+                let title = format!("{}: {}", chat_name, section.name);
+                let meta = format!(
+                    "{} messages | Exported: {}",
+                    section.messages.len(),
+                    exported
+                );
+                let back = if html {
+                    format!("    <p><a href=\"index.{}\">All topics</a></p>\n", ext)
+                } else {
+                    format!("[All topics](index.{})\n\n", ext)
+                };
+                let body = back + &render_messages(&section.messages);
+                std::fs::write(
+                    dir.join(section.file_name(ext)),
+                    render(&title, &meta, &body),
+                )?;
+            }
+            eprintln!(
+                "Exported {} topics to: {}",
+                sections.len(),
+                index_path.display()
+            );
+        }
+        _ => {
+            let body = match sections {
+                Some(sections) => {
+                    let mut body = topic_index(html, sections, |s| format!("#{}", s.anchor()));
+                    for section in sections {
+                        body.push_str(&if html {
+                            format!(
+                                "    <h2 class=\"topic\" id=\"{}\">{}</h2>\n",
+                                section.anchor(),
+                                html_escape(&section.name)
+                            )
+                        } else {
+                            format!(
+                                "<a id=\"{}\"></a>\n\n## {}\n\n",
+                                section.anchor(),
+                                section.name
+                            )
+                        });
+                        body.push_str(&render_messages(&section.messages));
+                    }
+                    body
+                }
+                None => render_messages(&messages.iter().collect::<Vec<_>>()),
+            };
+            let doc = render(chat_name, &meta, &body);
+            let mut sink = Sink::open(output, encrypt_to)?;
+            sink.write_all(doc.as_bytes())?;
+            sink.finish()?;
+            if let Some(path) = output {
+                eprintln!("Exported to: {}", path);
+            }
+        }
+    }
+    Ok(())
+}
 
-    // Write HTML header
-    writeln!(
-        writer,
+/// Topics with their message counts, linking to `link(section)`.
+fn topic_index(
+    html: bool,
+    sections: &[TopicSection],
+    link: impl Fn(&TopicSection) -> String,
+) -> String {
+    let mut index = String::new();
+    if html {
+        index.push_str("    <nav class=\"topics\">\n        <h2>Topics</h2>\n        <ul>\n");
+        for s in sections {
+            index.push_str(&format!(
+                "            <li><a href=\"{}\">{}</a> <span class=\"count\">{} messages</span></li>\n",
+                link(s),
+                html_escape(&s.name),
+                s.messages.len()
+            ));
+        }
+        index.push_str("        </ul>\n    </nav>\n");
+    } else {
+        index.push_str("## Topics\n\n");
+        for s in sections {
+            index.push_str(&format!(
+                "- [{}]({}) ({} messages)\n",
+                s.name,
+                link(s),
+                s.messages.len()
+            ));
+        }
+        index.push('\n');
+    }
+    index
+}
+
+fn html_page(title: &str, meta: &str, body: &str) -> String {
+    format!(
         r#"<!DOCTYPE html>
 <html lang="en">
 <head>
@@ -342,105 +748,145 @@ fn export_html(
             background: #ddd;
             z-index: -1;
         }}
+        .topics {{
+            background: white;
+            border-radius: 12px;
+            padding: 12px 24px;
+            margin-bottom: 30px;
+        }}
+        .topics .count {{
+            color: #999;
+            font-size: 13px;
+        }}
+        h2.topic {{
+            color: #2196F3;
+            border-bottom: 1px solid #ddd;
+            padding-bottom: 6px;
+            margin-top: 40px;
+        }}
     </style>
 </head>
 <body>
     <h1>{}</h1>
     <div class="meta">
-        Chat ID: {} | {} messages | Exported: {}
+        {}
     </div>
-    <div class="messages">"#,
-        html_escape(chat_name),
-        html_escape(chat_name),
-        chat_id,
-        messages.len(),
-        Local::now().format("%Y-%m-%d %H:%M:%S")
-    )?;
+{}</body>
+</html>
+"#,
+        html_escape(title),
+        html_escape(title),
+        html_escape(meta),
+        body
+    )
+}
 
+fn message_time(msg: &ExportMessage) -> (String, String) {
+    let ts = DateTime::parse_from_rfc3339(&msg.ts)
+        .ok()
+        .map(|dt| dt.with_timezone(&Local));
+    (
+        ts.as_ref()
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        ts.as_ref()
+            .map(|t| t.format("%H:%M").to_string())
+            .unwrap_or_default(),
+    )
+}
+
+fn sender_label(msg: &ExportMessage) -> String {
+    if msg.from_me {
+        "You".to_string()
+    } else {
+        format!("User {}", msg.sender_id)
+    }
+}
+
+fn html_messages(messages: &[&ExportMessage]) -> String {
+    let mut html = String::from("    <div class=\"messages\">\n");
     // Track current date for date separators
     let mut current_date: Option<String> = None;
 
     for msg in messages {
-        let ts = DateTime::parse_from_rfc3339(&msg.ts)
-            .ok()
-            .map(|dt| dt.with_timezone(&Local));
-
-        let date_str = ts
-            .as_ref()
-            .map(|t| t.format("%Y-%m-%d").to_string())
-            .unwrap_or_default();
-        let time_str = ts
-            .as_ref()
-            .map(|t| t.format("%H:%M").to_string())
-            .unwrap_or_default();
+        let (date_str, time_str) = message_time(msg);
 
         // Insert date separator if date changed
         if current_date.as_ref() != Some(&date_str) {
-            current_date = Some(date_str.clone());
-            writeln!(
-                writer,
-                r#"        <div class="date-separator"><span>{}</span></div>"#,
+            html.push_str(&format!(
+                "        <div class=\"date-separator\"><span>{}</span></div>\n",
                 date_str
-            )?;
+            ));
+            current_date = Some(date_str);
         }
 
         let class = if msg.from_me { "outgoing" } else { "incoming" };
-        let sender = if msg.from_me {
-            "You".to_string()
-        } else {
-            format!("User {}", msg.sender_id)
-        };
-
-        writeln!(writer, r#"        <div class="message {}">"#, class)?;
+        html.push_str(&format!("        <div class=\"message {}\">\n", class));
 
         // Reply indicator
         if let Some(reply_id) = msg.reply_to_id {
-            writeln!(
-                writer,
-                r#"            <div class="reply-indicator">Reply to message #{}</div>"#,
+            html.push_str(&format!(
+                "            <div class=\"reply-indicator\">Reply to message #{}</div>\n",
                 reply_id
-            )?;
+            ));
         }
 
-        writeln!(
-            writer,
+        html.push_str(&format!(
             r#"            <div class="message-header">
                 <span class="sender">{}</span>
                 <span class="time">{}</span>
-            </div>"#,
-            html_escape(&sender),
+            </div>
+"#,
+            html_escape(&sender_label(msg)),
             time_str
-        )?;
+        ));
 
         if !msg.text.is_empty() {
-            writeln!(
-                writer,
-                r#"            <div class="text">{}</div>"#,
-                html_escape(&msg.text)
-            )?;
+            html.push_str(&format!(
+                "            <div class=\"text\">{}</div>\n",
+                html_escape(&out::redact::text(&msg.text))
+            ));
         }
 
         if let Some(ref media_type) = msg.media_type {
-            writeln!(
-                writer,
-                r#"            <span class="media-badge">📎 {}</span>"#,
+            html.push_str(&format!(
+                "            <span class=\"media-badge\">📎 {}</span>\n",
                 html_escape(media_type)
-            )?;
+            ));
         }
 
-        writeln!(writer, r#"        </div>"#)?;
+        html.push_str("        </div>\n");
     }
+    html.push_str("    </div>\n");
+    html
+}
 
-    // Write HTML footer
-    writeln!(
-        writer,
-        r#"    </div>
-</body>
-</html>"#
-    )?;
-
-    writer.flush()?;
-    Ok(())
+fn markdown_messages(messages: &[&ExportMessage]) -> String {
+    let mut md = String::new();
+    let mut current_date: Option<String> = None;
+    for msg in messages {
+        let (date_str, time_str) = message_time(msg);
+        if current_date.as_ref() != Some(&date_str) {
+            md.push_str(&format!("### {}\n\n", date_str));
+            current_date = Some(date_str);
+        }
+        md.push_str(&format!("**{}** {}", sender_label(msg), time_str));
+        if let Some(reply_id) = msg.reply_to_id {
+            md.push_str(&format!(" (reply to #{})", reply_id));
+        }
+        md.push('\n');
+        if !msg.text.is_empty() {
+            // Blockquote keeps multi-line texts together
+            for line in out::redact::text(&msg.text).lines() {
+                md.push_str(&format!("> {}\n", line));
+            }
+        }
+        if let Some(ref media_type) = msg.media_type {
+            md.push_str(&format!("> 📎 {}\n", media_type));
+        }
+        md.push('\n');
+    }
+    md
 }
 
 fn html_escape(s: &str) -> String {
@@ -504,3 +950,228 @@ async fn resolve_peer_ref(app: &App, chat_id: i64) -> Result<grammers_session::d
         chat_id
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: i64, topic_id: Option<i32>) -> ExportMessage {
+        ExportMessage {
+            id,
+            chat_id: 1,
+            sender_id: 2,
+            from_me: false,
+            ts: "2025-01-31T12:00:00Z".to_string(),
+            edit_ts: None,
+            text: format!("message {}", id),
+            media_type: None,
+            reply_to_id: None,
+            topic_id,
+            service_action: None,
+            forwarded_from: None,
+            fwd_date: None,
+            shared_contact: None,
+            geo: None,
+        }
+    }
+
+    #[test]
+    fn groups_forum_messages_by_topic() {
+        let messages = vec![
+            message(1, Some(7)),
+            message(2, None),
+            message(3, Some(7)),
+            message(4, Some(9)),
+        ];
+        let topics = vec![Topic {
+            chat_id: 1,
+            topic_id: 7,
+            name: "Releases".to_string(),
+            icon_color: 0,
+            icon_emoji: None,
+            unread_count: 0,
+            top_message: None,
+        }];
+        let sections = topic_sections(&messages, &topics);
+        let summary: Vec<(&str, usize)> = sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.messages.len()))
+            .collect();
+        assert_eq!(summary, [("General", 1), ("Releases", 2), ("Topic 9", 1)]);
+        assert_eq!(sections[1].file_name("html"), "topic_7.html");
+    }
+
+    fn parse(args: &[&str]) -> (crate::Cli, ExportArgs) {
+        use clap::Parser;
+        let cli = crate::Cli::try_parse_from(args).unwrap();
+        let crate::cmd::Command::Export(export) = cli.command.clone() else {
+            panic!("not an export: {:?}", cli.command);
+        };
+        (cli, export)
+    }
+
+    /// A message of chat 5 for a temporary store.
+    fn stored(id: i64, topic_id: Option<i32>, text: &str) -> crate::store::UpsertMessageParams {
+        crate::store::UpsertMessageParams {
+            id,
+            chat_id: 5,
+            sender_id: 2,
+            ts: Utc::now(),
+            edit_ts: None,
+            from_me: false,
+            text: text.to_string(),
+            media_type: None,
+            media_path: None,
+            reply_to_id: None,
+            topic_id,
+        }
+    }
+
+    #[test]
+    fn export_destination_is_not_the_output_mode() {
+        let (cli, args) = parse(&["tgcli", "export", "--chat", "1", "-o", "sqlite:chat.db"]);
+        assert_eq!(args.dest.as_deref(), Some("sqlite:chat.db"));
+        assert_eq!(
+            sqlite_output(&cli, &args).unwrap(),
+            Some(Path::new("chat.db"))
+        );
+
+        let (cli, args) = parse(&[
+            "tgcli",
+            "--output",
+            "sqlite:all.db",
+            "export",
+            "--chat",
+            "1",
+        ]);
+        assert!(args.dest.is_none());
+        assert_eq!(
+            sqlite_output(&cli, &args).unwrap(),
+            Some(Path::new("all.db"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn encrypted_exports_decrypt_to_the_messages() {
+        use std::process::Command;
+        let dir = tempfile::tempdir().unwrap();
+        // A throwaway keyring (private, so gpg doesn't warn)
+        let gnupg = tempfile::tempdir().unwrap();
+        let mut perms = std::fs::metadata(gnupg.path()).unwrap().permissions();
+        std::os::unix::fs::PermissionsExt::set_mode(&mut perms, 0o700);
+        std::fs::set_permissions(gnupg.path(), perms).unwrap();
+        let gpg = |args: &[&str]| {
+            Command::new("gpg")
+                .env("GNUPGHOME", gnupg.path())
+                .args(["--batch", "--passphrase", ""])
+                .args(args)
+                .output()
+        };
+        // Needs gpg; age works the same way through Sink
+        match gpg(&[
+            "--quick-gen-key",
+            "export@tgcli.invalid",
+            "future-default",
+            "default",
+        ]) {
+            Ok(out) if out.status.success() => {}
+            _ => return,
+        }
+        std::env::set_var("GNUPGHOME", gnupg.path());
+
+        let store_dir = dir.path().join("store");
+        let store = Store::open(&store_dir.to_string_lossy()).await.unwrap();
+        store
+            .upsert_message(stored(1, None, "meet at noon"))
+            .await
+            .unwrap();
+        drop(store);
+
+        let dest = dir.path().join("chat.json.gpg");
+        let (cli, args) = parse(&[
+            "tgcli",
+            "--store",
+            &store_dir.to_string_lossy(),
+            "export",
+            "--chat",
+            "5",
+            "-o",
+            &dest.to_string_lossy(),
+            "--encrypt-to",
+            "export@tgcli.invalid",
+        ]);
+        run(&cli, &args).await.unwrap();
+
+        let encrypted = std::fs::read(&dest).unwrap();
+        assert!(!String::from_utf8_lossy(&encrypted).contains("meet at noon"));
+        let plain = gpg(&["--decrypt", &dest.to_string_lossy()]).unwrap();
+        assert!(plain.status.success());
+        assert!(String::from_utf8_lossy(&plain.stdout).contains("meet at noon"));
+
+        // Key generation started an agent for the keyring
+        let _ = Command::new("gpgconf")
+            .env("GNUPGHOME", gnupg.path())
+            .args(["--kill", "gpg-agent"])
+            .status();
+    }
+
+    #[tokio::test]
+    async fn split_topics_writes_a_file_per_topic() {
+        use clap::Parser;
+        assert!(crate::Cli::try_parse_from([
+            "tgcli",
+            "export",
+            "--chat",
+            "5",
+            "--split-topics",
+            "--encrypt-to",
+            "alice@example.com",
+        ])
+        .is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let store_dir = dir.path().join("store");
+        let store = Store::open(&store_dir.to_string_lossy()).await.unwrap();
+        store
+            .upsert_chat(5, "channel", "Forum", None, None, true, None, false)
+            .await
+            .unwrap();
+        store
+            .upsert_topic(5, 7, "Releases", 0, None, 0, None)
+            .await
+            .unwrap();
+        store
+            .upsert_message(stored(1, Some(7), "v1.0 is out"))
+            .await
+            .unwrap();
+        store
+            .upsert_message(stored(2, None, "hello"))
+            .await
+            .unwrap();
+        drop(store);
+
+        let dest = dir.path().join("forum");
+        let (cli, args) = parse(&[
+            "tgcli",
+            "--store",
+            &store_dir.to_string_lossy(),
+            "export",
+            "--chat",
+            "5",
+            "--format",
+            "markdown",
+            "--split-topics",
+            "-o",
+            &dest.to_string_lossy(),
+        ]);
+        assert!(args.split_topics);
+        run(&cli, &args).await.unwrap();
+
+        let index = std::fs::read_to_string(dest.join("index.md")).unwrap();
+        assert!(index.contains("Releases"));
+        let topic = std::fs::read_to_string(dest.join("topic_7.md")).unwrap();
+        assert!(topic.contains("v1.0 is out"));
+        assert!(!topic.contains("hello"));
+    }
+}
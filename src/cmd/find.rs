@@ -0,0 +1,121 @@
+use crate::out;
+use crate::store::{self, Chat, ChatSort, Contact, Message, Store, Topic};
+use crate::Cli;
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+
+#[derive(Args, Debug, Clone)]
+pub struct FindArgs {
+    /// Text to look for in chat names, contacts, topics, and messages
+    pub query: String,
+
+    /// Max results per group
+    #[arg(long, default_value = "10")]
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct FindResult {
+    query: String,
+    chats: Vec<Chat>,
+    contacts: Vec<Contact>,
+    topics: Vec<Topic>,
+    messages: Vec<Message>,
+}
+
+pub async fn run(cli: &Cli, args: &FindArgs) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    let chats = store
+        .list_chats(
+            Some(&args.query),
+            args.limit,
+            None,
+            false,
+            None,
+            ChatSort::Recent,
+        )
+        .await?;
+    let contacts = store.search_contacts(&args.query, args.limit).await?;
+    let topics = store.search_topics(&args.query, args.limit).await?;
+    // Don't let a failed message search sink the other groups
+    let messages = match store
+        .search_messages(store::SearchMessagesParams {
+            query: args.query.clone(),
+            match_mode: store::MatchMode::Plain,
+            sort: store::SearchSort::Recent,
+            half_life_days: 0.0,
+            chat_id: None,
+            topic_id: None,
+            topic_name: None,
+            from_id: None,
+            limit: args.limit,
+            media_type: None,
+            ignore_chats: Vec::new(),
+            ignore_channels: false,
+            forwarded_from: None,
+            via_bot_id: None,
+            has_link: false,
+            domain: None,
+            cursor: None,
+        })
+        .await
+    {
+        Ok(msgs) => msgs,
+        Err(e) => {
+            log::warn!("Message search failed for {:?}: {}", args.query, e);
+            Vec::new()
+        }
+    };
+
+    let result = FindResult {
+        query: args.query.clone(),
+        chats,
+        contacts,
+        topics,
+        messages,
+    };
+
+    if cli.output.is_json() {
+        return out::write_json(&result);
+    }
+    if cli.output.is_none() {
+        return Ok(());
+    }
+
+    let markdown = cli.output.is_markdown();
+    let mut doc = String::new();
+    if markdown {
+        doc.push_str(&format!("# Results for \"{}\"\n\n", result.query));
+    }
+    push_group(&mut doc, markdown, "Chats", &result.chats);
+    push_group(&mut doc, markdown, "Contacts", &result.contacts);
+    push_group(&mut doc, markdown, "Topics", &result.topics);
+    push_group(&mut doc, markdown, "Messages", &result.messages);
+
+    if result.chats.is_empty()
+        && result.contacts.is_empty()
+        && result.topics.is_empty()
+        && result.messages.is_empty()
+    {
+        doc.push_str(&format!("No matches for \"{}\"\n", result.query));
+    }
+    out::pager::print(&doc);
+    Ok(())
+}
+
+/// Append one result group with a count heading; empty groups are skipped.
+fn push_group<T: Serialize>(doc: &mut String, markdown: bool, title: &str, items: &[T]) {
+    if items.is_empty() {
+        return;
+    }
+    if markdown {
+        doc.push_str(&out::to_markdown_with_title(&items, title));
+        doc.push_str("\n\n");
+    } else {
+        doc.push_str(&format!("== {} ({}) ==\n", title, items.len()));
+        doc.push_str(&out::to_text(&items));
+        doc.push('\n');
+    }
+}
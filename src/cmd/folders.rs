@@ -28,7 +28,12 @@ pub enum FoldersCommand {
         #[arg(long)]
         emoticon: Option<String>,
         /// Chat IDs to include in the folder (can be repeated)
-        #[arg(long = "include", action = clap::ArgAction::Append)]
+        #[arg(
+            long = "include",
+            action = clap::ArgAction::Append,
+            value_name = "CHAT_ID",
+            value_parser = crate::config::parse_chat
+        )]
         include: Vec<i64>,
     },
     /// Delete a folder
@@ -40,7 +45,7 @@ pub enum FoldersCommand {
     /// Add a chat to a folder
     Add {
         /// Chat ID to add
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Folder ID
         #[arg(long)]
@@ -49,7 +54,7 @@ pub enum FoldersCommand {
     /// Remove a chat from a folder
     Remove {
         /// Chat ID to remove
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Folder ID
         #[arg(long)]
@@ -104,7 +109,7 @@ async fn list_folders(cli: &Cli) -> Result<()> {
     let app = App::new(cli).await?;
 
     let request = tl::functions::messages::GetDialogFilters {};
-    let result = app.tg.client.invoke(&request).await?;
+    let result = app.tg.invoke(&request).await?;
 
     let filters = match result {
         tl::enums::messages::DialogFilters::Filters(f) => f.filters,
@@ -235,7 +240,7 @@ async fn create_folder(
 
     // Get existing folders to find next available ID
     let request = tl::functions::messages::GetDialogFilters {};
-    let result = app.tg.client.invoke(&request).await?;
+    let result = app.tg.invoke(&request).await?;
 
     let filters = match result {
         tl::enums::messages::DialogFilters::Filters(f) => f.filters,
@@ -293,7 +298,7 @@ async fn create_folder(
         filter: Some(tl::enums::DialogFilter::Filter(new_filter)),
     };
 
-    app.tg.client.invoke(&create_request).await?;
+    app.tg.invoke(&create_request).await?;
 
     if cli.output.is_json() {
         out::write_json(&serde_json::json!({
@@ -314,7 +319,7 @@ async fn delete_folder(cli: &Cli, folder_id: i32) -> Result<()> {
 
     // Verify the folder exists first
     let request = tl::functions::messages::GetDialogFilters {};
-    let result = app.tg.client.invoke(&request).await?;
+    let result = app.tg.invoke(&request).await?;
 
     let filters = match result {
         tl::enums::messages::DialogFilters::Filters(f) => f.filters,
@@ -358,7 +363,7 @@ async fn delete_folder(cli: &Cli, folder_id: i32) -> Result<()> {
         filter: None,
     };
 
-    app.tg.client.invoke(&delete_request).await?;
+    app.tg.invoke(&delete_request).await?;
 
     if cli.output.is_json() {
         out::write_json(&serde_json::json!({
@@ -378,7 +383,7 @@ async fn show_folder(cli: &Cli, folder_id: i32) -> Result<()> {
 
     // Get folder filters
     let request = tl::functions::messages::GetDialogFilters {};
-    let result = app.tg.client.invoke(&request).await?;
+    let result = app.tg.invoke(&request).await?;
 
     let filters = match result {
         tl::enums::messages::DialogFilters::Filters(f) => f.filters,
@@ -446,7 +451,7 @@ async fn show_folder(cli: &Cli, folder_id: i32) -> Result<()> {
             .iter()
             .map(|c| FolderChatMd {
                 id: c.id,
-                name: c.name.clone(),
+                name: out::redact::text(&c.name).into_owned(),
                 kind: c.kind.clone(),
                 pinned: c.pinned,
             })
@@ -459,7 +464,7 @@ async fn show_folder(cli: &Cli, folder_id: i32) -> Result<()> {
             println!(
                 "{:<16} {:<30} {:<10} {}",
                 c.id,
-                out::truncate(&c.name, 28),
+                out::truncate(&out::redact::text(&c.name), 28),
                 c.kind,
                 if c.pinned { "yes" } else { "" }
             );
@@ -504,7 +509,7 @@ async fn add_to_folder(cli: &Cli, chat_id: i64, folder_id: i32) -> Result<()> {
 
     // Get current folder filters
     let request = tl::functions::messages::GetDialogFilters {};
-    let result = app.tg.client.invoke(&request).await?;
+    let result = app.tg.invoke(&request).await?;
 
     let filters = match result {
         tl::enums::messages::DialogFilters::Filters(f) => f.filters,
@@ -567,7 +572,7 @@ async fn add_to_folder(cli: &Cli, chat_id: i64, folder_id: i32) -> Result<()> {
                     filter: Some(tl::enums::DialogFilter::Filter(updated)),
                 };
 
-                app.tg.client.invoke(&update_request).await?;
+                app.tg.invoke(&update_request).await?;
                 break;
             }
             tl::enums::DialogFilter::Chatlist(c) if c.id == folder_id => {
@@ -615,7 +620,7 @@ async fn add_to_folder(cli: &Cli, chat_id: i64, folder_id: i32) -> Result<()> {
                     filter: Some(tl::enums::DialogFilter::Chatlist(updated)),
                 };
 
-                app.tg.client.invoke(&update_request).await?;
+                app.tg.invoke(&update_request).await?;
                 break;
             }
             _ => {}
@@ -647,7 +652,7 @@ async fn remove_from_folder(cli: &Cli, chat_id: i64, folder_id: i32) -> Result<(
 
     // Get current folder filters
     let request = tl::functions::messages::GetDialogFilters {};
-    let result = app.tg.client.invoke(&request).await?;
+    let result = app.tg.invoke(&request).await?;
 
     let filters = match result {
         tl::enums::messages::DialogFilters::Filters(f) => f.filters,
@@ -716,7 +721,7 @@ async fn remove_from_folder(cli: &Cli, chat_id: i64, folder_id: i32) -> Result<(
                     filter: Some(tl::enums::DialogFilter::Filter(updated)),
                 };
 
-                app.tg.client.invoke(&update_request).await?;
+                app.tg.invoke(&update_request).await?;
                 break;
             }
             tl::enums::DialogFilter::Chatlist(c) if c.id == folder_id => {
@@ -770,7 +775,7 @@ async fn remove_from_folder(cli: &Cli, chat_id: i64, folder_id: i32) -> Result<(
                     filter: Some(tl::enums::DialogFilter::Chatlist(updated)),
                 };
 
-                app.tg.client.invoke(&update_request).await?;
+                app.tg.invoke(&update_request).await?;
                 break;
             }
             _ => {}
@@ -0,0 +1,87 @@
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ForwardsCommand {
+    /// Forward new messages from one chat to another while `tgcli daemon` runs
+    Add {
+        /// Chat to watch
+        #[arg(long, value_parser = crate::config::parse_chat, value_name = "CHAT")]
+        from: i64,
+        /// Chat to forward to
+        #[arg(long, value_parser = crate::config::parse_chat, value_name = "CHAT")]
+        to: i64,
+        /// Only messages containing one of these |-separated words (case-insensitive)
+        #[arg(long)]
+        pattern: Option<String>,
+    },
+    /// List forward rules with how many messages each has forwarded
+    List,
+    /// Remove a forward rule
+    Remove {
+        /// Rule ID (see `forwards list`)
+        id: i64,
+    },
+}
+
+pub async fn run(cli: &Cli, cmd: &ForwardsCommand) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    match cmd {
+        ForwardsCommand::Add { from, to, pattern } => {
+            if from == to {
+                anyhow::bail!("--from and --to must be different chats");
+            }
+            if pattern
+                .as_deref()
+                .is_some_and(|p| p.split('|').all(|alt| alt.trim().is_empty()))
+            {
+                anyhow::bail!("--pattern needs at least one non-empty alternative");
+            }
+            let id = store
+                .add_forward_rule(*from, *to, pattern.as_deref())
+                .await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "id": id,
+                    "from_chat_id": from,
+                    "to_chat_id": to,
+                    "pattern": pattern,
+                }))?;
+            } else {
+                println!(
+                    "Added forward rule {}. It runs while `tgcli daemon` is running.",
+                    id
+                );
+            }
+        }
+        ForwardsCommand::List => {
+            let rules = store.list_forward_rules().await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "count": rules.len(),
+                    "rules": rules,
+                }))?;
+            } else if rules.is_empty() {
+                println!("No forward rules. Add one with `tgcli forwards add`.");
+            } else {
+                cli.output.write(&rules)?;
+            }
+        }
+        ForwardsCommand::Remove { id } => {
+            if !store.remove_forward_rule(*id).await? {
+                anyhow::bail!("No forward rule with ID {}", id);
+            }
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "removed": id }))?;
+            } else {
+                println!("Removed forward rule {}.", id);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::app::App;
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum GiftsCommand {
+    /// List gifts you have received
+    List {
+        /// Limit results
+        #[arg(long, default_value = "50")]
+        limit: i32,
+    },
+}
+
+pub async fn run(cli: &Cli, cmd: &GiftsCommand) -> Result<()> {
+    match cmd {
+        GiftsCommand::List { limit } => {
+            let app = App::new(cli).await?;
+            let gifts = app.list_gifts(*limit).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "count": gifts.len(),
+                    "gifts": gifts,
+                }))?;
+            } else if gifts.is_empty() {
+                println!("No gifts received.");
+            } else {
+                let store = Store::open(&cli.store_dir()).await?;
+                println!("{:<26} {:<24} {:>7} FROM", "DATE", "GIFT", "STARS");
+                for g in &gifts {
+                    let from = match g.from_id {
+                        Some(id) => store
+                            .get_chat(id)
+                            .await?
+                            .map(|c| c.name)
+                            .unwrap_or_else(|| id.to_string()),
+                        None => "anonymous".to_string(),
+                    };
+                    let mut title = g
+                        .title
+                        .clone()
+                        .unwrap_or_else(|| format!("Gift {}", g.gift_id));
+                    if g.hidden {
+                        title.push_str(" (hidden)");
+                    }
+                    println!(
+                        "{:<26} {:<24} {:>7} {}",
+                        g.date,
+                        out::truncate(&title, 22),
+                        g.stars
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                        from
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
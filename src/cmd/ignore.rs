@@ -0,0 +1,100 @@
+use crate::config::Config;
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+use serde::Serialize;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum IgnoreCommand {
+    /// Ignore chats: skipped by sync and the daemon, hidden from `messages list/search`
+    Add {
+        /// Chat IDs
+        #[arg(required = true, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
+        ids: Vec<i64>,
+    },
+    /// Stop ignoring chats
+    Remove {
+        /// Chat IDs
+        #[arg(required = true, value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
+        ids: Vec<i64>,
+    },
+    /// List ignored chats
+    List,
+}
+
+#[derive(Serialize)]
+struct IgnoredChat {
+    id: i64,
+    name: Option<String>,
+    kind: Option<String>,
+}
+
+pub async fn run(cli: &Cli, cmd: &IgnoreCommand) -> Result<()> {
+    let config_dir = cli.config_dir();
+    let mut config = Config::load(&config_dir)?;
+
+    match cmd {
+        IgnoreCommand::Add { ids } => {
+            let mut added = Vec::new();
+            for id in ids {
+                if !config.sync.ignore_chat_ids.contains(id) {
+                    config.sync.ignore_chat_ids.push(*id);
+                    added.push(*id);
+                }
+            }
+            config.save(&config_dir)?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "added": added,
+                    "ignored": config.sync.ignore_chat_ids,
+                }))?;
+            } else if added.is_empty() {
+                println!("Already ignored.");
+            } else {
+                println!(
+                    "Ignoring {} chat(s). Messages already stored are hidden, not deleted.",
+                    added.len()
+                );
+            }
+        }
+        IgnoreCommand::Remove { ids } => {
+            let before = config.sync.ignore_chat_ids.len();
+            config.sync.ignore_chat_ids.retain(|id| !ids.contains(id));
+            let removed = before - config.sync.ignore_chat_ids.len();
+            config.save(&config_dir)?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "removed": removed,
+                    "ignored": config.sync.ignore_chat_ids,
+                }))?;
+            } else {
+                println!("Stopped ignoring {} chat(s).", removed);
+            }
+        }
+        IgnoreCommand::List => {
+            let store = Store::open(&cli.store_dir()).await?;
+            let mut chats = Vec::new();
+            for id in &config.sync.ignore_chat_ids {
+                let chat = store.get_chat(*id).await?;
+                chats.push(IgnoredChat {
+                    id: *id,
+                    name: chat.as_ref().map(|c| c.name.clone()),
+                    kind: chat.map(|c| c.kind),
+                });
+            }
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "ignored": chats }))?;
+            } else if chats.is_empty() {
+                println!("No ignored chats. Add one with `tgcli ignore add <chat_id>`.");
+            } else {
+                cli.output.write_titled(&chats, "Ignored chats")?;
+            }
+        }
+    }
+    Ok(())
+}
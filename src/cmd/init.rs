@@ -0,0 +1,208 @@
+use crate::app::sync::{OutputMode as SyncOutputMode, SyncOptions};
+use crate::app::App;
+use crate::cmd::auth;
+use crate::config::Config;
+use crate::out::{self, OutputMode};
+use crate::store::{ChatSort, Store};
+use crate::Cli;
+use anyhow::Result;
+use clap::Args;
+use std::io::{self, Write};
+
+/// Recent chats shown when picking chats to exclude
+const CHAT_PREVIEW: i64 = 20;
+
+#[derive(Args, Debug, Clone)]
+pub struct InitArgs {
+    /// Write the config without running the first sync
+    #[arg(long)]
+    pub no_sync: bool,
+}
+
+pub async fn run(cli: &Cli, args: &InitArgs) -> Result<()> {
+    let store_dir = cli.store_dir();
+    let config_dir = cli.config_dir();
+    let mut config = Config::load(&config_dir)?;
+
+    eprintln!("Welcome to tgcli! This will set up {}.", store_dir);
+    eprintln!();
+
+    // Connection settings are needed before anything touches the network
+    if cli.proxy.is_none() {
+        let current = config.proxy.clone().unwrap_or_default();
+        let proxy = prompt("SOCKS5 proxy URL (blank for a direct connection)", &current)?;
+        config.proxy = Some(proxy).filter(|p| !p.is_empty());
+        crate::tg::ConnectOptions {
+            proxy: config.proxy.clone(),
+            ..cli.connect_options()
+        }
+        .validate()?;
+        config.save(&config_dir)?;
+    }
+
+    // Step 1: authentication
+    let authed = {
+        let app = App::new_unauthed(cli).await?;
+        app.tg.client.is_authorized().await?
+    };
+    if authed {
+        eprintln!("[1/4] Already authenticated.");
+    } else {
+        eprintln!("[1/4] Authentication");
+        auth::interactive_auth(cli).await?;
+    }
+
+    // Step 2: which chats to sync
+    eprintln!();
+    eprintln!("[2/4] Chats");
+    eprintln!("Fetching chat list…");
+    let mut app = App::new(cli).await?;
+    app.sync_chats(sync_options(&config, 0, false)).await?;
+
+    let store = Store::open(&store_dir).await?;
+    let chats = store
+        .list_chats(None, CHAT_PREVIEW, None, false, None, ChatSort::Recent)
+        .await?;
+    for chat in &chats {
+        eprintln!("  {:>14}  {:<8} {}", chat.id, chat.kind, chat.name);
+    }
+    config.sync.skip_archived = confirm("Skip archived chats?", config.sync.skip_archived)?;
+    config.sync.ignore_channels = confirm("Skip channels?", config.sync.ignore_channels)?;
+    let current = config
+        .sync
+        .ignore_chat_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let ignored = prompt(
+        "Chat IDs to exclude (comma-separated, see `tgcli chats list`)",
+        &current,
+    )?;
+    config.sync.ignore_chat_ids = parse_ids(&ignored)?;
+
+    // Step 3: media
+    eprintln!();
+    eprintln!("[3/4] Media");
+    config.sync.download_media = confirm(
+        "Download photos, videos, and files during sync?",
+        config.sync.download_media,
+    )?;
+
+    // Step 4: output defaults
+    eprintln!();
+    eprintln!("[4/4] Output");
+    let default_output = output_name(config.output.unwrap_or_default());
+    loop {
+        let answer = prompt("Default output (text, json, markdown)", default_output)?;
+        match <OutputMode as clap::ValueEnum>::from_str(&answer, true) {
+            Ok(mode) => {
+                config.output = Some(mode);
+                break;
+            }
+            Err(_) => eprintln!("Unknown output mode '{}'.", answer),
+        }
+    }
+
+    config.save(&config_dir)?;
+    let config_path = Config::path(&config_dir);
+    eprintln!();
+    eprintln!("Saved {}.", config_path.display());
+
+    let mut messages_synced = None;
+    if !args.no_sync && confirm("Run the first sync now?", true)? {
+        let per_chat: usize = prompt("Messages per chat", "50")?.parse()?;
+        let result = app.sync(sync_options(&config, per_chat, true)).await?;
+        eprintln!(
+            "Synced {} messages from {} chats.",
+            result.messages_stored, result.chats_stored
+        );
+        messages_synced = Some(result.messages_stored);
+    }
+
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "config_path": config_path,
+            "config": config,
+            "messages_synced": messages_synced,
+        }))?;
+    } else {
+        eprintln!("Setup complete. Try `tgcli chats list` or `tgcli daemon`.");
+    }
+    Ok(())
+}
+
+fn sync_options(config: &Config, messages_per_chat: usize, show_progress: bool) -> SyncOptions {
+    SyncOptions {
+        output: SyncOutputMode::None,
+        mark_read: false,
+        download_media: config.sync.download_media,
+        ignore_chat_ids: config.sync.ignore_chat_ids.clone(),
+        ignore_channels: config.sync.ignore_channels,
+        show_progress,
+        incremental: true,
+        messages_per_chat,
+        concurrency: 4,
+        chat_filter: Vec::new(),
+        prune_after: None,
+        skip_archived: config.sync.skip_archived,
+        archived_only: false,
+        save_shared_contacts: config.sync.save_shared_contacts,
+        folder: None,
+        skip_kinds: Vec::new(),
+        skip_name: None,
+    }
+}
+
+fn output_name(mode: OutputMode) -> &'static str {
+    match mode {
+        OutputMode::None => "none",
+        // Never read from or saved to config
+        OutputMode::Text | OutputMode::Sqlite(_) => "text",
+        OutputMode::Json => "json",
+        OutputMode::Markdown => "markdown",
+        OutputMode::Llm => "llm",
+    }
+}
+
+/// Prompt on stderr; an empty answer returns `default`.
+fn prompt(label: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        eprint!("{}: ", label);
+    } else {
+        eprint!("{} [{}]: ", label, default);
+    }
+    io::stderr().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim();
+    Ok(if input.is_empty() { default } else { input }.to_string())
+}
+
+/// Yes/no prompt on stderr; an empty answer returns `default`.
+fn confirm(label: &str, default: bool) -> Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    loop {
+        eprint!("{} [{}] ", label, hint);
+        io::stderr().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        match input.trim().to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => eprintln!("Please answer y or n."),
+        }
+    }
+}
+
+fn parse_ids(s: &str) -> Result<Vec<i64>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            part.parse()
+                .map_err(|_| anyhow::anyhow!("Invalid chat ID '{}'", part))
+        })
+        .collect()
+}
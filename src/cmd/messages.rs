@@ -1,9 +1,17 @@
+use crate::app::emoji;
+use crate::app::report::ReportReason;
 use crate::app::App;
+use crate::app::{details, service};
+use crate::config::Config;
+use crate::llm;
 use crate::out;
+use crate::out::progress::Progress;
 use crate::store::{self, Store};
 use crate::Cli;
 use anyhow::Result;
-use clap::{Subcommand, ValueEnum};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum ExportFormat {
@@ -16,7 +24,7 @@ pub enum MessagesCommand {
     /// Fetch older messages from Telegram (backfill history)
     Fetch {
         /// Chat ID (required)
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Topic ID (for forum groups)
         #[arg(long)]
@@ -31,14 +39,17 @@ pub enum MessagesCommand {
     /// List messages
     List {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: Option<i64>,
         /// Topic ID (for forum groups)
         #[arg(long)]
         topic: Option<i32>,
-        /// Limit results
-        #[arg(long, default_value = "50")]
+        /// Limit results (page size when paging with --cursor); 0 streams all of them
+        #[arg(long, visible_alias = "page-size", default_value = "50")]
         limit: i64,
+        /// Continue from a `next_cursor` token returned by a previous page
+        #[arg(long)]
+        cursor: Option<String>,
         /// Only messages after this time (RFC3339, YYYY-MM-DD, 'today', 'yesterday', or relative like '1 week ago')
         #[arg(long, visible_alias = "since")]
         after: Option<String>,
@@ -52,7 +63,7 @@ pub enum MessagesCommand {
         #[arg(long)]
         yesterday: bool,
         /// Chat IDs to exclude (repeatable)
-        #[arg(long = "ignore", value_name = "CHAT_ID")]
+        #[arg(long = "ignore", value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
         ignore_chats: Vec<i64>,
         /// Exclude channels
         #[arg(long)]
@@ -60,26 +71,93 @@ pub enum MessagesCommand {
         /// Stream messages as JSONL (one JSON object per line)
         #[arg(long)]
         stream: bool,
+        /// Token budget for `--output llm` (oldest messages are dropped first)
+        #[arg(long)]
+        max_tokens: Option<usize>,
+        /// List each part of an album separately instead of one entry per album
+        #[arg(long)]
+        expand_albums: bool,
+        /// Only messages that share a location or venue
+        #[arg(long)]
+        with_location: bool,
+    },
+    /// Show a chat as a conversation transcript (day separators, replies, media)
+    View {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Topic ID (for forum groups)
+        #[arg(long)]
+        topic: Option<i32>,
+        /// Number of most recent messages to show
+        #[arg(long, default_value = "200")]
+        last: i64,
+    },
+    /// Print the latest messages in a chat, optionally following new ones (like `tail -f`)
+    Tail {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Topic ID (for forum groups)
+        #[arg(long)]
+        topic: Option<i32>,
+        /// Number of recent messages to print first
+        #[arg(long, short = 'n', default_value = "10")]
+        lines: i64,
+        /// Keep running and print new messages as they arrive
+        #[arg(long, short = 'f')]
+        follow: bool,
     },
     /// Search messages (FTS5 for local, Telegram API for global)
     Search {
         /// Search query
         query: String,
+        /// fts: "quoted phrases", term* prefixes and AND/OR/NOT; plain: every
+        /// word literally (local search only)
+        #[arg(long, value_enum, default_value = "fts", conflicts_with = "global")]
+        match_mode: store::MatchMode,
+        /// recent: newest first; relevance: best match first, favouring
+        /// newer messages (local search only)
+        #[arg(long, value_enum, default_value = "recent", conflicts_with_all = ["global", "cursor"])]
+        sort: store::SearchSort,
+        /// With --sort relevance, the age in days at which a match counts
+        /// half as much (0 ranks by relevance alone)
+        #[arg(long, value_name = "DAYS", default_value = "180")]
+        half_life: f64,
         /// Chat ID filter
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: Option<i64>,
         /// Topic ID (for forum groups)
         #[arg(long)]
         topic: Option<i32>,
+        /// Only forum topics whose name contains this, across chats unless
+        /// --chat is given (local search only)
+        #[arg(long, value_name = "NAME", conflicts_with = "global")]
+        topic_name: Option<String>,
         /// Sender ID filter
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat, value_name = "CHAT")]
         from: Option<i64>,
-        /// Limit results
-        #[arg(long, default_value = "50")]
+        /// Limit results (page size when paging with --cursor); 0 streams all of them
+        #[arg(long, visible_alias = "page-size", default_value = "50")]
         limit: i64,
+        /// Continue from a `next_cursor` token returned by a previous page (local search only)
+        #[arg(long, conflicts_with = "global")]
+        cursor: Option<String>,
         /// Media type filter
         #[arg(long, name = "type")]
         media_type: Option<String>,
+        /// Only messages forwarded from this origin (@username, ID, or name)
+        #[arg(long, value_name = "ORIGIN", conflicts_with = "global")]
+        forwarded_from: Option<String>,
+        /// Only messages sent through this inline bot (@username or ID)
+        #[arg(long, value_name = "BOT", conflicts_with = "global")]
+        via_bot: Option<String>,
+        /// Only messages with a link preview
+        #[arg(long, conflicts_with = "global")]
+        has_link: bool,
+        /// Only messages linking to this domain or its subdomains (e.g. github.com)
+        #[arg(long, conflicts_with = "global")]
+        domain: Option<String>,
         /// Only messages after this time (RFC3339, YYYY-MM-DD, 'today', or 'yesterday')
         #[arg(long)]
         after: Option<String>,
@@ -93,7 +171,7 @@ pub enum MessagesCommand {
         #[arg(long)]
         yesterday: bool,
         /// Chat IDs to exclude (repeatable)
-        #[arg(long = "ignore", value_name = "CHAT_ID")]
+        #[arg(long = "ignore", value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
         ignore_chats: Vec<i64>,
         /// Exclude channels
         #[arg(long)]
@@ -102,10 +180,28 @@ pub enum MessagesCommand {
         #[arg(long)]
         global: bool,
     },
+    /// Rank a channel's posts by views or forwards (counts as of the last sync)
+    Top {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Counter to rank by
+        #[arg(long, value_enum, default_value_t)]
+        by: store::MessageRank,
+        /// Number of posts to show
+        #[arg(long, default_value = "20")]
+        limit: i64,
+        /// Only posts after this time (RFC3339, YYYY-MM-DD, 'today', or 'yesterday')
+        #[arg(long)]
+        after: Option<String>,
+        /// Fetch current counts from Telegram first
+        #[arg(long)]
+        refresh: bool,
+    },
     /// Export messages to stdout (JSON or CSV)
     Export {
         /// Chat ID (required)
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Output format
         #[arg(long, value_enum, default_value = "json")]
@@ -129,7 +225,7 @@ pub enum MessagesCommand {
     /// Show message context around a message
     Context {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID
         #[arg(long)]
@@ -141,10 +237,38 @@ pub enum MessagesCommand {
         #[arg(long, default_value = "5")]
         after: i64,
     },
+    /// Messages sent on today's date in previous years, across chats
+    #[command(name = "onthisday")]
+    OnThisDay {
+        /// How many years to look back (default: as far as the local store goes)
+        #[arg(long)]
+        years_back: Option<i32>,
+        /// Only this chat
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+        /// Max messages per year
+        #[arg(long, default_value = "20")]
+        limit: i64,
+        /// Exclude channels
+        #[arg(long)]
+        ignore_channels: bool,
+    },
+    /// Show the first messages of a chat in the local store (or the most recent with --last)
+    First {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Show the most recent messages instead
+        #[arg(long)]
+        last: bool,
+        /// Number of messages
+        #[arg(long, default_value = "1")]
+        limit: i64,
+    },
     /// Show a single message
     Show {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID
         #[arg(long)]
@@ -153,7 +277,7 @@ pub enum MessagesCommand {
     /// Delete messages from a chat (always deletes for everyone)
     Delete {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID(s) to delete (repeatable)
         #[arg(long = "id", value_name = "MSG_ID")]
@@ -162,13 +286,13 @@ pub enum MessagesCommand {
     /// Forward a message to another chat
     Forward {
         /// Source chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID to forward
         #[arg(long)]
         id: i64,
         /// Destination chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat, value_name = "CHAT")]
         to: i64,
         /// Destination topic ID (for forum groups)
         #[arg(long)]
@@ -177,7 +301,7 @@ pub enum MessagesCommand {
     /// Edit a message's text
     Edit {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID to edit
         #[arg(long)]
@@ -189,7 +313,7 @@ pub enum MessagesCommand {
     /// Pin a message in a chat
     Pin {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID to pin
         #[arg(long)]
@@ -204,7 +328,7 @@ pub enum MessagesCommand {
     /// Unpin a message in a chat
     Unpin {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID to unpin
         #[arg(long)]
@@ -216,7 +340,7 @@ pub enum MessagesCommand {
     /// Add or remove a reaction from a message
     React {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID to react to
         #[arg(long, name = "message")]
@@ -228,10 +352,77 @@ pub enum MessagesCommand {
         #[arg(long)]
         remove: bool,
     },
+    /// Press an inline button under a bot message
+    Press {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Message ID with the button
+        #[arg(long, name = "message")]
+        msg_id: i64,
+        /// Button label (as shown by `messages view`)
+        #[arg(long)]
+        button: String,
+    },
+    /// Tick items of a todo list (checklist)
+    Todo {
+        #[command(subcommand)]
+        cmd: TodoCommand,
+    },
+    /// Translate messages using Telegram's translation service
+    Translate {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Message ID(s) to translate (repeatable)
+        #[arg(
+            long = "message",
+            value_name = "MSG_ID",
+            required_unless_present = "range"
+        )]
+        msg_ids: Vec<i64>,
+        /// Translate the last N messages of the chat instead
+        #[arg(long, conflicts_with = "msg_ids")]
+        range: Option<i64>,
+        /// Target language (ISO 639-1 code, e.g. "en", "es")
+        #[arg(long, default_value = "en")]
+        to: String,
+    },
+    /// Summarize a chat's messages with an LLM backend
+    Summarize {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Topic ID (for forum groups)
+        #[arg(long)]
+        topic: Option<i32>,
+        /// Only messages after this time (RFC3339, YYYY-MM-DD, 'today', 'yesterday', or relative like '1 week ago')
+        #[arg(long, default_value = "yesterday")]
+        since: String,
+        /// Only messages before this time
+        #[arg(long)]
+        until: Option<String>,
+        /// Max messages to summarize (most recent)
+        #[arg(long, default_value = "2000")]
+        limit: i64,
+        /// LLM backend
+        #[arg(long, value_enum, default_value = "openai")]
+        backend: llm::Backend,
+        /// Model name (default: gpt-4o-mini for openai, llama3.2 for ollama)
+        #[arg(long)]
+        model: Option<String>,
+        /// Command for the cmd backend (program and args, no shell); receives
+        /// the prompt on stdin
+        #[arg(long = "cmd", value_name = "COMMAND")]
+        command: Option<String>,
+        /// Max characters of transcript sent per request
+        #[arg(long, default_value = "12000")]
+        chunk_size: usize,
+    },
     /// Download media from a message
     Download {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Message ID containing media
         #[arg(long = "message")]
@@ -240,6 +431,108 @@ pub enum MessagesCommand {
         #[arg(long, short)]
         dest: Option<String>,
     },
+    /// Pay stars to unlock a paid media post, then download it
+    Unlock {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Message ID of the paid post
+        #[arg(long = "message")]
+        msg_id: i64,
+        /// Output path (default: current directory with auto-detected filename)
+        #[arg(long, short)]
+        dest: Option<String>,
+        /// Pay without asking for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Fetch and store the comments on a channel post (from its discussion group)
+    Comments {
+        /// Channel ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Channel post ID
+        #[arg(long = "message")]
+        msg_id: i64,
+        /// Maximum number of comments to fetch
+        #[arg(long, default_value = "100")]
+        limit: usize,
+    },
+    /// Report messages to Telegram
+    Report {
+        /// Chat ID
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Message ID(s) to report (can be specified multiple times)
+        #[arg(long = "message", required = true)]
+        ids: Vec<i64>,
+        /// Reason for the report
+        #[arg(long, value_enum)]
+        reason: ReportReason,
+        /// Additional details for the moderators
+        #[arg(long)]
+        comment: Option<String>,
+    },
+    /// List stored messages with attachments: file names, sizes and local paths
+    Media {
+        /// Only messages from this chat
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+        /// Only this media type (photo, video, audio, file, sticker, ...)
+        #[arg(long = "type")]
+        media_type: Option<String>,
+        /// Only messages after this time (RFC3339, YYYY-MM-DD, 'today', 'yesterday', or relative like '1 week ago')
+        #[arg(long)]
+        after: Option<String>,
+        /// Only messages before this time (RFC3339, YYYY-MM-DD, 'today', 'yesterday', or relative like '1 week ago')
+        #[arg(long)]
+        before: Option<String>,
+        /// Only media that hasn't been downloaded (see `messages fetch-media`)
+        #[arg(long)]
+        missing_local: bool,
+        /// Maximum number of messages, newest first (0 for all)
+        #[arg(long, default_value = "50")]
+        limit: i64,
+    },
+    /// Download media for stored messages that were synced without it
+    FetchMedia {
+        /// Only messages from this chat
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+        /// Only this media type (photo, video, audio, document, sticker, ...)
+        #[arg(long = "type")]
+        media_type: Option<String>,
+        /// Only messages after this time (RFC3339, YYYY-MM-DD, 'today', 'yesterday', or relative like '1 week ago')
+        #[arg(long)]
+        after: Option<String>,
+        /// Only messages before this time (RFC3339, YYYY-MM-DD, 'today', 'yesterday', or relative like '1 week ago')
+        #[arg(long)]
+        before: Option<String>,
+        /// Maximum number of messages to download (newest first)
+        #[arg(long, default_value = "100")]
+        limit: i64,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TodoCommand {
+    /// Mark items as done
+    Check(TodoArgs),
+    /// Mark items as not done
+    Uncheck(TodoArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TodoArgs {
+    /// Chat ID
+    #[arg(long, value_parser = crate::config::parse_chat)]
+    pub chat: i64,
+    /// Message ID of the todo list
+    #[arg(long, name = "message")]
+    pub msg_id: i64,
+    /// Item number, counting from 1 (repeatable)
+    #[arg(long = "item", required = true)]
+    pub items: Vec<i32>,
 }
 
 pub async fn run(cli: &Cli, cmd: &MessagesCommand) -> Result<()> {
@@ -290,61 +583,190 @@ pub async fn run(cli: &Cli, cmd: &MessagesCommand) -> Result<()> {
             chat,
             topic,
             limit,
+            cursor,
             after,
             before,
             ignore_chats,
             ignore_channels,
             stream,
+            max_tokens,
+            expand_albums,
+            with_location,
             ..
         } => {
             let after_ts = after.as_deref().map(parse_time).transpose()?;
             let before_ts = before.as_deref().map(parse_time).transpose()?;
+            let cursor = cursor
+                .as_deref()
+                .map(store::MessageCursor::decode)
+                .transpose()?;
 
-            let msgs = store
-                .list_messages(store::ListMessagesParams {
-                    chat_id: *chat,
-                    topic_id: *topic,
-                    limit: *limit,
-                    after: after_ts,
-                    before: before_ts,
-                    ignore_chats: ignore_chats.clone(),
-                    ignore_channels: *ignore_channels,
-                })
-                .await?;
+            let params = store::ListMessagesParams {
+                chat_id: *chat,
+                topic_id: *topic,
+                limit: *limit,
+                after: after_ts,
+                before: before_ts,
+                ignore_chats: ignored_chats(cli, ignore_chats, *chat)?,
+                ignore_channels: *ignore_channels,
+                with_location: *with_location,
+                cursor,
+            };
+            if *limit <= 0 {
+                // Everything, oldest first, printed as it's read
+                let mut rows = store.stream_messages(params).await?;
+                if let Some(path) = cli.output.sqlite_path() {
+                    let mut table = out::sqlite::Table::create(path, "messages").await?;
+                    while let Some(mut m) = rows.next().await? {
+                        details::annotate_messages(&store, std::slice::from_mut(&mut m)).await?;
+                        table.insert(&m).await?;
+                    }
+                    return table.finish().await;
+                }
+                while let Some(mut m) = rows.next().await? {
+                    details::annotate_messages(&store, std::slice::from_mut(&mut m)).await?;
+                    print_stream_line(cli, &store, &m, *stream).await?;
+                }
+                return Ok(());
+            }
+            let mut msgs = store.list_messages(params).await?;
+            details::annotate_messages(&store, &mut msgs).await?;
+            // Messages come back in chronological order, so the oldest is first
+            let next_cursor = next_page_cursor(msgs.first(), msgs.len(), *limit);
+            if !*stream && !*expand_albums {
+                msgs = store::collapse_albums(msgs);
+            }
 
-            if *stream {
+            if let Some(path) = cli.output.sqlite_path() {
+                out::sqlite::write_rows(path, "messages", &msgs).await?;
+            } else if *stream {
                 // Stream as JSONL (one JSON object per line)
                 for m in &msgs {
-                    let obj = serde_json::json!({
-                        "id": m.id,
-                        "chat_id": m.chat_id,
-                        "sender_id": m.sender_id,
-                        "from_me": m.from_me,
-                        "ts": m.ts.to_rfc3339(),
-                        "text": m.text,
-                        "media_type": m.media_type,
-                        "topic_id": m.topic_id,
-                        "reply_to_id": m.reply_to_id,
-                    });
-                    println!("{}", serde_json::to_string(&obj).unwrap_or_default());
+                    println!(
+                        "{}",
+                        out::redact::to_json_line(&stream_json(m)).unwrap_or_default()
+                    );
                 }
             } else if cli.output.is_json() {
                 out::write_json(&serde_json::json!({
                     "messages": msgs,
+                    "next_cursor": next_cursor,
                 }))?;
             } else if cli.output.is_markdown() {
                 cli.output.write_titled(&msgs, "Messages")?;
+            } else if cli.output.is_llm() {
+                let mut names = sender_names(&store, msgs.iter().map(|m| m.sender_id)).await?;
+                let title = match chat {
+                    Some(id) => store.get_chat(*id).await?.map(|c| c.name),
+                    None => None,
+                };
+                let mut msgs = msgs;
+                msgs.iter_mut().for_each(out::redact::message);
+                out::redact::names(&mut names);
+                let title = title.as_deref().map(out::redact::text);
+                let context = out::llm::LlmContext {
+                    messages: &msgs,
+                    names: &names,
+                    title: title.as_deref(),
+                };
+                print!("{}", context.render(*max_tokens));
             } else {
                 cli.output.write(&msgs)?;
             }
         }
+        MessagesCommand::View { chat, topic, last } => {
+            let mut msgs = store
+                .list_messages(store::ListMessagesParams {
+                    chat_id: Some(*chat),
+                    topic_id: *topic,
+                    limit: *last,
+                    after: None,
+                    before: None,
+                    ignore_chats: Vec::new(),
+                    ignore_channels: false,
+                    with_location: false,
+                    cursor: None,
+                })
+                .await?;
+            details::annotate_messages(&store, &mut msgs).await?;
+            let msgs = store::collapse_albums(msgs);
+            let (names, replies) = transcript_context(&store, *chat, &msgs).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": chat,
+                    "topic_id": topic,
+                    "names": names,
+                    "messages": msgs,
+                }))?;
+            } else if !cli.output.is_none() {
+                let title = store
+                    .get_chat(*chat)
+                    .await?
+                    .map(|c| c.name)
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| format!("Chat {}", chat));
+                let (mut msgs, mut names, mut replies) = (msgs, names, replies);
+                msgs.iter_mut()
+                    .chain(replies.values_mut())
+                    .for_each(out::redact::message);
+                out::redact::names(&mut names);
+                let transcript = out::transcript::Transcript {
+                    messages: &msgs,
+                    names: &names,
+                    replies: &replies,
+                };
+                if cli.output.is_markdown() {
+                    out::write_markdown(&transcript.to_markdown(&title));
+                } else {
+                    out::pager::print(&transcript.to_text(out::transcript::DEFAULT_WIDTH));
+                }
+            }
+        }
+        MessagesCommand::Tail {
+            chat,
+            topic,
+            lines,
+            follow,
+        } => {
+            let mut msgs = store
+                .list_messages(store::ListMessagesParams {
+                    chat_id: Some(*chat),
+                    topic_id: *topic,
+                    limit: *lines,
+                    after: None,
+                    before: None,
+                    ignore_chats: Vec::new(),
+                    ignore_channels: false,
+                    with_location: false,
+                    cursor: None,
+                })
+                .await?;
+            details::annotate_messages(&store, &mut msgs).await?;
+            for m in &msgs {
+                print_tail_line(cli, &store, m).await?;
+            }
+
+            if *follow {
+                follow_chat(cli, *chat, *topic).await?;
+            }
+        }
         MessagesCommand::Search {
             query,
+            match_mode,
+            sort,
+            half_life,
             chat,
             topic,
+            topic_name,
             from,
             limit,
+            cursor,
             media_type,
+            forwarded_from,
+            via_bot,
+            has_link,
+            domain,
             ignore_chats,
             ignore_channels,
             global,
@@ -371,22 +793,66 @@ pub async fn run(cli: &Cli, cmd: &MessagesCommand) -> Result<()> {
                 }
             } else {
                 // Local FTS search
-                let msgs = store
-                    .search_messages(store::SearchMessagesParams {
-                        query: query.clone(),
-                        chat_id: *chat,
-                        topic_id: *topic,
-                        from_id: *from,
-                        limit: *limit,
-                        media_type: media_type.clone(),
-                        ignore_chats: ignore_chats.clone(),
-                        ignore_channels: *ignore_channels,
-                    })
-                    .await?;
+                let cursor = cursor
+                    .as_deref()
+                    .map(store::MessageCursor::decode)
+                    .transpose()?;
+                let via_bot_id = match via_bot {
+                    Some(bot) => Some(resolve_bot_id(cli, &store, bot).await?),
+                    None => None,
+                };
+                let params = store::SearchMessagesParams {
+                    query: query.clone(),
+                    match_mode: *match_mode,
+                    sort: *sort,
+                    half_life_days: *half_life,
+                    chat_id: *chat,
+                    topic_id: *topic,
+                    topic_name: topic_name.clone(),
+                    from_id: *from,
+                    limit: *limit,
+                    media_type: media_type.clone(),
+                    ignore_chats: ignored_chats(cli, ignore_chats, *chat)?,
+                    ignore_channels: *ignore_channels,
+                    forwarded_from: forwarded_from.clone(),
+                    via_bot_id,
+                    has_link: *has_link,
+                    domain: domain.clone(),
+                    cursor,
+                };
+                if *limit <= 0 {
+                    // Every match, newest first, printed as it's read
+                    let mut rows = store.stream_search(params).await?;
+                    if let Some(path) = cli.output.sqlite_path() {
+                        let mut table = out::sqlite::Table::create(path, "search_results").await?;
+                        while let Some(mut m) = rows.next().await? {
+                            details::annotate_messages(&store, std::slice::from_mut(&mut m))
+                                .await?;
+                            table.insert(&m).await?;
+                        }
+                        return table.finish().await;
+                    }
+                    while let Some(mut m) = rows.next().await? {
+                        details::annotate_messages(&store, std::slice::from_mut(&mut m)).await?;
+                        print_tail_line(cli, &store, &m).await?;
+                    }
+                    return Ok(());
+                }
+                let mut msgs = store.search_messages(params).await?;
+                details::annotate_messages(&store, &mut msgs).await?;
+                // Search results are newest first, so the oldest is last;
+                // relevance order can't be resumed from a position
+                let next_cursor = match sort {
+                    store::SearchSort::Recent => next_page_cursor(msgs.last(), msgs.len(), *limit),
+                    store::SearchSort::Relevance => None,
+                };
 
-                if cli.output.is_json() {
+                if let Some(path) = cli.output.sqlite_path() {
+                    out::sqlite::write_rows(path, "search_results", &msgs).await?;
+                } else if cli.output.is_json() {
                     out::write_json(&serde_json::json!({
                         "messages": msgs,
+                        "next_cursor": next_cursor,
                         "fts": store.has_fts(),
                         "global": false,
                     }))?;
@@ -401,13 +867,63 @@ pub async fn run(cli: &Cli, cmd: &MessagesCommand) -> Result<()> {
                 }
             }
         }
+        MessagesCommand::Top {
+            chat,
+            by,
+            limit,
+            after,
+            refresh,
+        } => {
+            let after_ts = after.as_deref().map(parse_time).transpose()?;
+            if *refresh {
+                // Refresh every post that has counters, since ranks can change
+                let posts = store.top_messages(*chat, *by, after_ts, i64::MAX).await?;
+                let ids: Vec<i64> = posts.iter().map(|m| m.id).collect();
+                let app = App::new(cli).await?;
+                app.refresh_counters(*chat, &ids).await?;
+            }
+
+            let msgs = store.top_messages(*chat, *by, after_ts, *limit).await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": chat,
+                    "by": by,
+                    "messages": msgs,
+                }))?;
+            } else if cli.output.is_markdown() {
+                cli.output
+                    .write_titled(&msgs, &format!("Top Posts by {:?}", by))?;
+            } else if msgs.is_empty() {
+                println!(
+                    "No view counts stored for chat {}. Sync the channel first.",
+                    chat
+                );
+            } else {
+                println!(
+                    "{:>8} {:>6}  {:<8} {:<10}  TEXT",
+                    "VIEWS", "FWDS", "ID", "DATE"
+                );
+                for m in &msgs {
+                    let text = out::transcript::message_body(m).replace('\n', " ");
+                    println!(
+                        "{:>8} {:>6}  {:<8} {:<10}  {}",
+                        m.views.unwrap_or(0),
+                        m.forwards.unwrap_or(0),
+                        m.id,
+                        m.ts.with_timezone(&chrono::Local).format("%Y-%m-%d"),
+                        out::truncate(&text, 60)
+                    );
+                }
+            }
+        }
         MessagesCommand::Context {
             chat,
             id,
             before,
             after,
         } => {
-            let msgs = store.message_context(*chat, *id, *before, *after).await?;
+            let mut msgs = store.message_context(*chat, *id, *before, *after).await?;
+            details::annotate_messages(&store, &mut msgs).await?;
 
             if cli.output.is_json() {
                 out::write_json(&msgs)?;
@@ -418,8 +934,61 @@ pub async fn run(cli: &Cli, cmd: &MessagesCommand) -> Result<()> {
                 cli.output.write(&msgs)?;
             }
         }
+        MessagesCommand::OnThisDay {
+            years_back,
+            chat,
+            limit,
+            ignore_channels,
+        } => {
+            let years = on_this_day(&store, *years_back, *chat, *limit, *ignore_channels).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "date": chrono::Local::now().format("%m-%d").to_string(),
+                    "years": years,
+                }))?;
+            } else if years.is_empty() {
+                println!("Nothing from this day in previous years in the local store.");
+            } else {
+                for year in &years {
+                    let title = match year.years_ago {
+                        1 => format!("{} (1 year ago)", year.year),
+                        n => format!("{} ({} years ago)", year.year, n),
+                    };
+                    cli.output.write_titled(&year.messages, &title)?;
+                }
+            }
+        }
+        MessagesCommand::First { chat, last, limit } => {
+            let mut msgs = store.edge_messages(*chat, *last, *limit).await?;
+            if msgs.is_empty() {
+                anyhow::bail!(
+                    "No messages for chat {} in the local store. Run `tgcli messages fetch --chat {}` first.",
+                    chat,
+                    chat
+                );
+            }
+            details::annotate_messages(&store, &mut msgs).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "messages": msgs }))?;
+            } else if cli.output.is_markdown() {
+                let title = if *last {
+                    "Last messages"
+                } else {
+                    "First messages"
+                };
+                cli.output.write_titled(&msgs, title)?;
+            } else {
+                cli.output.write(&msgs)?;
+            }
+        }
         MessagesCommand::Show { chat, id } => {
-            let msg = store.get_message(*chat, *id).await?;
+            let mut msg = store.get_message(*chat, *id).await?;
+            if let Some(m) = msg.as_mut() {
+                details::annotate_messages(&store, std::slice::from_mut(m)).await?;
+                m.edits = store.message_edits(*chat, *id).await?;
+            }
             match msg {
                 Some(m) => {
                     cli.output.write(&m)?;
@@ -581,6 +1150,261 @@ pub async fn run(cli: &Cli, cmd: &MessagesCommand) -> Result<()> {
                 );
             }
         }
+        MessagesCommand::Press {
+            chat,
+            msg_id,
+            button,
+        } => {
+            // Pressing requires network access
+            let app = App::new(cli).await?;
+
+            let answer = app.press_button(*chat, *msg_id, button).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "pressed": true,
+                    "chat_id": chat,
+                    "message_id": msg_id,
+                    "button": answer.button,
+                    "answer": answer.message,
+                    "alert": answer.alert,
+                    "url": answer.url,
+                }))?;
+            } else {
+                println!("Pressed \"{}\" on message {}", answer.button, msg_id);
+                if let Some(message) = &answer.message {
+                    println!(
+                        "{}: {}",
+                        if answer.alert { "Alert" } else { "Bot" },
+                        message
+                    );
+                }
+                if let Some(url) = &answer.url {
+                    println!("Open: {}", url);
+                }
+            }
+        }
+        MessagesCommand::Todo { cmd } => {
+            let (args, completed) = match cmd {
+                TodoCommand::Check(args) => (args, true),
+                TodoCommand::Uncheck(args) => (args, false),
+            };
+            // Todo lists live on Telegram, this requires network access
+            let app = App::new(cli).await?;
+
+            app.todo_check(args.chat, args.msg_id, &args.items, completed)
+                .await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "success": true,
+                    "chat_id": args.chat,
+                    "message_id": args.msg_id,
+                    "items": args.items,
+                    "completed": completed,
+                }))?;
+            } else {
+                let items: Vec<String> = args.items.iter().map(|i| i.to_string()).collect();
+                println!(
+                    "Marked item(s) {} as {} on message {}",
+                    items.join(", "),
+                    if completed { "done" } else { "not done" },
+                    args.msg_id
+                );
+            }
+        }
+        MessagesCommand::Translate {
+            chat,
+            msg_ids,
+            range,
+            to,
+        } => {
+            // Originals come from the local store; missing ones are still translated
+            let originals: Vec<store::Message> = match range {
+                Some(n) => {
+                    store
+                        .list_messages(store::ListMessagesParams {
+                            chat_id: Some(*chat),
+                            topic_id: None,
+                            limit: *n,
+                            after: None,
+                            before: None,
+                            ignore_chats: Vec::new(),
+                            ignore_channels: false,
+                            with_location: false,
+                            cursor: None,
+                        })
+                        .await?
+                }
+                None => {
+                    let mut msgs = Vec::new();
+                    for id in msg_ids {
+                        if let Some(m) = store.get_message(*chat, *id).await? {
+                            msgs.push(m);
+                        }
+                    }
+                    msgs
+                }
+            };
+            let ids: Vec<i64> = match range {
+                Some(_) => originals.iter().map(|m| m.id).collect(),
+                None => msg_ids.clone(),
+            };
+            if ids.is_empty() {
+                anyhow::bail!(
+                    "No messages to translate in chat {}. Run `tgcli sync` first.",
+                    chat
+                );
+            }
+
+            // Translation requires network access
+            let app = App::new(cli).await?;
+            let translations = app.translate_messages(*chat, &ids, to).await?;
+
+            let results: Vec<serde_json::Value> = ids
+                .iter()
+                .zip(translations.iter())
+                .map(|(id, translation)| {
+                    let original = originals.iter().find(|m| m.id == *id);
+                    serde_json::json!({
+                        "id": id,
+                        "ts": original.map(|m| m.ts.to_rfc3339()),
+                        "text": original.map(|m| m.text.as_str()),
+                        "translation": translation,
+                    })
+                })
+                .collect();
+            // Text and markdown are laid out below, from redacted values
+            let mut originals = originals;
+            originals.iter_mut().for_each(out::redact::message);
+            let translations: Vec<String> = translations
+                .iter()
+                .map(|t| out::redact::text(t).into_owned())
+                .collect();
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": chat,
+                    "to": to,
+                    "translations": results,
+                }))?;
+            } else if cli.output.is_markdown() {
+                let mut doc = format!("# Translations ({})\n", to);
+                for (id, translation) in ids.iter().zip(translations.iter()) {
+                    doc.push_str(&format!("\n## Message {}\n\n", id));
+                    if let Some(m) = originals.iter().find(|m| m.id == *id) {
+                        doc.push_str(&format!("> {}\n\n", m.text.replace('\n', "\n> ")));
+                    }
+                    doc.push_str(translation);
+                    doc.push('\n');
+                }
+                out::write_markdown(&doc);
+            } else if !cli.output.is_none() {
+                let mut doc = String::new();
+                for (id, translation) in ids.iter().zip(translations.iter()) {
+                    match originals.iter().find(|m| m.id == *id) {
+                        Some(m) => doc.push_str(&format!(
+                            "[{}] {}\n  → {}\n",
+                            id,
+                            out::truncate(&m.text.replace('\n', " "), 80),
+                            translation
+                        )),
+                        None => doc.push_str(&format!("[{}] → {}\n", id, translation)),
+                    }
+                }
+                out::pager::print(&doc);
+            }
+        }
+        MessagesCommand::Summarize {
+            chat,
+            topic,
+            since,
+            until,
+            limit,
+            backend,
+            model,
+            command,
+            chunk_size,
+        } => {
+            let msgs = store
+                .list_messages(store::ListMessagesParams {
+                    chat_id: Some(*chat),
+                    topic_id: *topic,
+                    limit: *limit,
+                    after: Some(parse_time(since)?),
+                    before: until.as_deref().map(parse_time).transpose()?,
+                    ignore_chats: Vec::new(),
+                    ignore_channels: false,
+                    with_location: false,
+                    cursor: None,
+                })
+                .await?;
+            if msgs.is_empty() {
+                anyhow::bail!(
+                    "No messages in chat {} since {}. Run `tgcli sync` first.",
+                    chat,
+                    since
+                );
+            }
+
+            let chat_name = store
+                .get_chat(*chat)
+                .await?
+                .map(|c| c.name)
+                .filter(|n| !n.is_empty())
+                .unwrap_or_else(|| format!("Chat {}", chat));
+            let (names, _) = transcript_context(&store, *chat, &msgs).await?;
+            let lines: Vec<String> = msgs
+                .iter()
+                .map(|m| {
+                    let name = if m.from_me {
+                        "Me".to_string()
+                    } else {
+                        names
+                            .get(&m.sender_id)
+                            .cloned()
+                            .unwrap_or_else(|| format!("User {}", m.sender_id))
+                    };
+                    format!(
+                        "[{}] {}: {}",
+                        m.ts.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                        name,
+                        out::transcript::message_body(m).replace('\n', " ")
+                    )
+                })
+                .collect();
+
+            let config = llm::LlmConfig {
+                backend: *backend,
+                model: model.clone(),
+                command: command.clone(),
+            };
+            let chunks = llm::chunk_lines(&lines, *chunk_size);
+            let summary = summarize_chunks(&config, &chat_name, &chunks).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": chat,
+                    "topic_id": topic,
+                    "since": msgs.first().map(|m| m.ts.to_rfc3339()),
+                    "until": msgs.last().map(|m| m.ts.to_rfc3339()),
+                    "messages": msgs.len(),
+                    "chunks": chunks.len(),
+                    "backend": backend.to_string(),
+                    "summary": summary,
+                }))?;
+            } else if cli.output.is_markdown() {
+                out::write_markdown(&format!(
+                    "# Summary of {}\n\n_{} messages since {}_\n\n{}\n",
+                    out::redact::text(&chat_name),
+                    msgs.len(),
+                    since,
+                    out::redact::text(&summary)
+                ));
+            } else if !cli.output.is_none() {
+                out::pager::print(&format!("{}\n", out::redact::text(&summary)));
+            }
+        }
         MessagesCommand::Download { chat, msg_id, dest } => {
             // Download requires network access
             let app = App::new(cli).await?;
@@ -601,11 +1425,618 @@ pub async fn run(cli: &Cli, cmd: &MessagesCommand) -> Result<()> {
                 println!("Size: {} bytes", result.size);
             }
         }
+        MessagesCommand::Unlock {
+            chat,
+            msg_id,
+            dest,
+            yes,
+        } => {
+            let app = App::new(cli).await?;
+
+            let paid = app.paid_media_status(*chat, *msg_id).await?;
+            let mut stars_paid = None;
+            if paid.locked {
+                if !*yes
+                    && !crate::cmd::chats::confirm(&format!(
+                        "Pay {} stars to unlock message {}?",
+                        paid.stars, msg_id
+                    ))?
+                {
+                    anyhow::bail!("Cancelled");
+                }
+                stars_paid = Some(app.unlock_paid_media(*chat, *msg_id).await?);
+            }
+            let result = app.download_media(*chat, *msg_id, dest.as_deref()).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "success": true,
+                    "chat_id": chat,
+                    "message_id": msg_id,
+                    "stars_paid": stars_paid,
+                    "path": result.path,
+                    "media_type": result.media_type,
+                    "size": result.size,
+                }))?;
+            } else {
+                match stars_paid {
+                    Some(stars) => println!("Paid {} stars for message {}", stars, msg_id),
+                    None => println!("Message {} is already unlocked", msg_id),
+                }
+                println!("Downloaded {} to {}", result.media_type, result.path);
+            }
+        }
+        MessagesCommand::Comments {
+            chat,
+            msg_id,
+            limit,
+        } => {
+            let app = App::new(cli).await?;
+            let (group_id, fetched) = app.fetch_comments(*chat, *msg_id, *limit).await?;
+            let mut msgs = match group_id {
+                Some(group_id) => store.list_thread(group_id, *msg_id).await?,
+                None => Vec::new(),
+            };
+            details::annotate_messages(&store, &mut msgs).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": chat,
+                    "message_id": msg_id,
+                    "discussion_chat_id": group_id,
+                    "fetched": fetched,
+                    "messages": msgs,
+                }))?;
+            } else if msgs.is_empty() {
+                println!("No comments on message {} in chat {}", msg_id, chat);
+            } else if cli.output.is_markdown() {
+                cli.output
+                    .write_titled(&msgs, &format!("Comments on message {}", msg_id))?;
+            } else {
+                cli.output.write(&msgs)?;
+            }
+        }
+        MessagesCommand::Report {
+            chat,
+            ids,
+            reason,
+            comment,
+        } => {
+            let app = App::new(cli).await?;
+            let chosen = app
+                .report_messages(*chat, ids, *reason, comment.as_deref())
+                .await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "reported": true,
+                    "chat_id": chat,
+                    "message_ids": ids,
+                    "options": chosen,
+                }))?;
+            } else {
+                println!("Reported {} message(s) in chat {}", ids.len(), chat);
+                if !chosen.is_empty() {
+                    println!("Reason: {}", chosen.join(" > "));
+                }
+            }
+        }
+        MessagesCommand::Media {
+            chat,
+            media_type,
+            after,
+            before,
+            missing_local,
+            limit,
+        } => {
+            // Documents are "files" to most people
+            let media_type = media_type.as_deref().map(|t| match t {
+                "file" => "document".to_string(),
+                t => t.to_string(),
+            });
+            let msgs = store
+                .list_media_messages(store::MediaMessagesParams {
+                    chat_id: *chat,
+                    media_type,
+                    after: after.as_deref().map(parse_time).transpose()?,
+                    before: before.as_deref().map(parse_time).transpose()?,
+                    missing_local: *missing_local,
+                    limit: *limit,
+                })
+                .await?;
+
+            if cli.output.is_json() {
+                let media: Vec<_> = msgs
+                    .iter()
+                    .map(|m| {
+                        serde_json::json!({
+                            "chat_id": m.chat_id,
+                            "message_id": m.id,
+                            "ts": m.ts.to_rfc3339(),
+                            "media_type": m.media_type,
+                            "file_name": m.media_name,
+                            "size": m.media_size,
+                            "duration": m.media_duration,
+                            "width": m.media_width,
+                            "height": m.media_height,
+                            "path": m.media_path,
+                        })
+                    })
+                    .collect();
+                out::write_json(&serde_json::json!({ "media": media }))?;
+            } else if msgs.is_empty() {
+                println!("No media found.");
+            } else {
+                for m in &msgs {
+                    println!(
+                        "chat {} msg {}  {}  {:<8} {:>9} {:>10}  {}  {}",
+                        m.chat_id,
+                        m.id,
+                        m.ts.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                        m.media_type.as_deref().unwrap_or_default(),
+                        m.media_size
+                            .map(|b| out::format_size(b as u64))
+                            .unwrap_or_else(|| "?".to_string()),
+                        media_shape(m),
+                        m.media_name.as_deref().unwrap_or("-"),
+                        m.media_path.as_deref().unwrap_or("(not downloaded)"),
+                    );
+                }
+            }
+        }
+        MessagesCommand::FetchMedia {
+            chat,
+            media_type,
+            after,
+            before,
+            limit,
+        } => {
+            let candidates = store
+                .list_missing_media(store::MissingMediaParams {
+                    chat_id: *chat,
+                    media_type: media_type.clone(),
+                    after: after.as_deref().map(parse_time).transpose()?,
+                    before: before.as_deref().map(parse_time).transpose()?,
+                    limit: *limit,
+                })
+                .await?;
+
+            let mut fetched = Vec::new();
+            let mut skipped = 0usize;
+            let mut failed = Vec::new();
+            if !candidates.is_empty() {
+                let app = App::new(cli).await?;
+                let bar =
+                    Progress::new(!cli.output.is_json()).items(candidates.len() as u64, "messages");
+                for msg in &candidates {
+                    match app
+                        .fetch_missing_media(msg.chat_id, msg.id, media_type.as_deref())
+                        .await
+                    {
+                        Ok(Some(saved)) => fetched.push(serde_json::json!({
+                            "chat_id": msg.chat_id,
+                            "message_id": msg.id,
+                            "media_type": saved.media_type,
+                            "path": saved.path,
+                        })),
+                        Ok(None) => skipped += 1,
+                        Err(e) => {
+                            log::warn!(
+                                "Failed to fetch media for chat={} msg={}: {:#}",
+                                msg.chat_id,
+                                msg.id,
+                                e
+                            );
+                            failed.push(serde_json::json!({
+                                "chat_id": msg.chat_id,
+                                "message_id": msg.id,
+                                "error": format!("{:#}", e),
+                            }));
+                        }
+                    }
+                    bar.set_message(format!("{} downloaded", fetched.len()));
+                    bar.inc(1);
+                }
+                bar.finish_and_clear();
+            }
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "candidates": candidates.len(),
+                    "downloaded": fetched,
+                    "skipped": skipped,
+                    "failed": failed,
+                }))?;
+            } else {
+                for f in &fetched {
+                    println!(
+                        "chat {} msg {}  {}",
+                        f["chat_id"],
+                        f["message_id"],
+                        f["path"].as_str().unwrap_or_default()
+                    );
+                }
+                println!(
+                    "Downloaded media for {} of {} messages ({} of another type, {} failed).",
+                    fetched.len(),
+                    candidates.len(),
+                    skipped,
+                    failed.len()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Look up sender display names and replied-to messages for a transcript.
+async fn transcript_context(
+    store: &Store,
+    chat_id: i64,
+    msgs: &[store::Message],
+) -> Result<(HashMap<i64, String>, HashMap<i64, store::Message>)> {
+    let mut replies: HashMap<i64, store::Message> = HashMap::new();
+    for reply_id in msgs.iter().filter_map(|m| m.reply_to_id) {
+        if replies.contains_key(&reply_id) {
+            continue;
+        }
+        let found = match msgs.iter().find(|m| m.id == reply_id) {
+            Some(m) => Some(m.clone()),
+            None => store.get_message(chat_id, reply_id).await?,
+        };
+        if let Some(m) = found {
+            replies.insert(reply_id, m);
+        }
+    }
+
+    let senders = msgs.iter().chain(replies.values()).map(|m| m.sender_id);
+    let bots = msgs.iter().filter_map(|m| m.via_bot_id);
+    let names = sender_names(store, senders.chain(bots)).await?;
+
+    Ok((names, replies))
+}
+
+#[derive(Serialize)]
+struct OnThisDayYear {
+    year: i32,
+    years_ago: i32,
+    messages: Vec<store::Message>,
+}
+
+/// Messages from today's local date in each previous year, newest year first.
+/// Years with nothing stored (or without the date, e.g. Feb 29) are skipped.
+async fn on_this_day(
+    store: &Store,
+    years_back: Option<i32>,
+    chat: Option<i64>,
+    limit: i64,
+    ignore_channels: bool,
+) -> Result<Vec<OnThisDayYear>> {
+    use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone};
+
+    let today = Local::now().date_naive();
+    let first_year = match years_back {
+        Some(n) => today.year() - n,
+        None => match store.oldest_message_ts(chat).await? {
+            Some(ts) => ts.with_timezone(&Local).year(),
+            None => return Ok(Vec::new()),
+        },
+    };
+    let mut years = Vec::new();
+    for year in (first_year..today.year()).rev() {
+        let Some(day) = NaiveDate::from_ymd_opt(year, today.month(), today.day()) else {
+            continue;
+        };
+        let local_midnight = |d: NaiveDate| {
+            Local
+                .from_local_datetime(&d.and_time(chrono::NaiveTime::MIN))
+                .earliest()
+                .map(|t| t.with_timezone(&chrono::Utc))
+        };
+        let (Some(start), Some(end)) =
+            (local_midnight(day), local_midnight(day + Duration::days(1)))
+        else {
+            continue;
+        };
+        let mut messages = store
+            .list_messages(store::ListMessagesParams {
+                chat_id: chat,
+                topic_id: None,
+                limit,
+                // `after` is exclusive
+                after: Some(start - Duration::seconds(1)),
+                before: Some(end),
+                ignore_chats: Vec::new(),
+                ignore_channels,
+                with_location: false,
+                cursor: None,
+            })
+            .await?;
+        if messages.is_empty() {
+            continue;
+        }
+        details::annotate_messages(store, &mut messages).await?;
+        years.push(OnThisDayYear {
+            year,
+            years_ago: today.year() - year,
+            messages,
+        });
+    }
+    Ok(years)
+}
+
+/// `--ignore` chats plus the persistent ignore list (`tgcli ignore`). The
+/// list doesn't apply when a single chat is asked for.
+fn ignored_chats(cli: &Cli, flags: &[i64], chat: Option<i64>) -> Result<Vec<i64>> {
+    let mut ids = flags.to_vec();
+    if chat.is_none() {
+        ids.extend(Config::load(&cli.config_dir())?.sync.ignore_chat_ids);
+    }
+    Ok(ids)
+}
+
+/// Display names for the given senders, keyed by sender ID.
+pub(crate) async fn sender_names(
+    store: &Store,
+    sender_ids: impl Iterator<Item = i64>,
+) -> Result<HashMap<i64, String>> {
+    let mut names: HashMap<i64, String> = HashMap::new();
+    for sender_id in sender_ids {
+        if sender_id == 0 || names.contains_key(&sender_id) {
+            continue;
+        }
+        if let Some(name) = sender_name(store, sender_id).await? {
+            names.insert(sender_id, name);
+        }
+    }
+    Ok(names)
+}
+
+/// User ID of a bot given as an ID or @username. Usernames not known locally
+/// are resolved through Telegram.
+async fn resolve_bot_id(cli: &Cli, store: &Store, bot: &str) -> Result<i64> {
+    if let Ok(id) = bot.parse::<i64>() {
+        return Ok(id);
+    }
+    let username = bot.trim_start_matches('@');
+    if let Some(id) = store.find_peer_by_username(username).await? {
+        return Ok(id);
+    }
+    let app = App::new(cli).await?;
+    match app.tg.client.resolve_username(username).await? {
+        Some(peer) => Ok(peer.id().bare_id()),
+        None => anyhow::bail!("Could not resolve bot @{}", username),
+    }
+}
+
+/// Display name for a sender from local contacts, falling back to chat names.
+async fn sender_name(store: &Store, sender_id: i64) -> Result<Option<String>> {
+    if let Some(c) = store.get_contact(sender_id).await? {
+        let full = format!("{} {}", c.first_name, c.last_name)
+            .trim()
+            .to_string();
+        if !full.is_empty() {
+            return Ok(Some(full));
+        }
+        return Ok(c.username.map(|u| format!("@{}", u)));
+    }
+    Ok(store
+        .get_chat(sender_id)
+        .await?
+        .map(|c| c.name)
+        .filter(|n| !n.is_empty()))
+}
+
+/// Duration (m:ss) of videos and audio, else dimensions of images.
+fn media_shape(m: &store::Message) -> String {
+    match (m.media_duration, m.media_width, m.media_height) {
+        (Some(secs), _, _) => {
+            let secs = secs.round() as i64;
+            format!("{}:{:02}", secs / 60, secs % 60)
+        }
+        (None, Some(w), Some(h)) => format!("{}x{}", w, h),
+        _ => String::new(),
+    }
+}
+
+/// The fields `messages list --stream` prints for a message.
+fn stream_json(m: &store::Message) -> serde_json::Value {
+    serde_json::json!({
+        "id": m.id,
+        "chat_id": m.chat_id,
+        "sender_id": m.sender_id,
+        "from_me": m.from_me,
+        "ts": m.ts.to_rfc3339(),
+        "text": m.text,
+        "media_type": m.media_type,
+        "topic_id": m.topic_id,
+        "reply_to_id": m.reply_to_id,
+    })
+}
+
+/// One message of an unlimited listing: a `--stream` JSONL line, or a
+/// `tail` line.
+async fn print_stream_line(
+    cli: &Cli,
+    store: &Store,
+    m: &store::Message,
+    stream: bool,
+) -> Result<()> {
+    if stream {
+        println!("{}", out::redact::to_json_line(&stream_json(m))?);
+        Ok(())
+    } else {
+        print_tail_line(cli, store, m).await
+    }
+}
+
+/// Print one message as a single `tail` line (text) or JSON object (json).
+async fn print_tail_line(cli: &Cli, store: &Store, m: &store::Message) -> Result<()> {
+    use std::io::Write;
+
+    if cli.output.is_json() {
+        println!("{}", out::redact::to_json_line(m)?);
+    } else if !cli.output.is_none() {
+        let name = if m.from_me {
+            "You".to_string()
+        } else {
+            sender_name(store, m.sender_id)
+                .await?
+                .unwrap_or_else(|| format!("User {}", m.sender_id))
+        };
+        println!(
+            "{} {}: {}",
+            m.ts.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+            name,
+            out::redact::text(&out::transcript::message_body(m)).replace('\n', "\n  ")
+        );
+    }
+    let _ = std::io::stdout().flush();
+    Ok(())
+}
+
+/// Follow new messages in a chat via the updates stream, storing and printing each one.
+async fn follow_chat(cli: &Cli, chat: i64, topic: Option<i32>) -> Result<()> {
+    use crate::cmd::daemon;
+    use grammers_client::{Update, UpdatesConfiguration};
+
+    let mut app = App::new(cli).await?;
+    let updates_rx = app
+        .updates_rx
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("Updates receiver not available"))?;
+    let mut update_stream = app.tg.client.stream_updates(
+        updates_rx,
+        UpdatesConfiguration {
+            catch_up: false,
+            ..Default::default()
+        },
+    );
+    let shutdown_ctrl = crate::shutdown::global();
+
+    loop {
+        let update = tokio::select! {
+            _ = shutdown_ctrl.cancelled() => break,
+            update = update_stream.next() => update,
+        };
+        let msg = match update {
+            Ok(Update::NewMessage(msg)) => msg,
+            Ok(_) => continue,
+            Err(e) => {
+                log::error!("Update stream error: {}", e);
+                if e.to_string().contains("Dropped") {
+                    break;
+                }
+                continue;
+            }
+        };
+        let Ok(peer) = msg.peer() else {
+            continue;
+        };
+        if peer.id().bare_id() != chat {
+            continue;
+        }
+        let topic_id = daemon::extract_topic_id_from_raw(&msg.raw);
+        if topic.is_some() && topic_id != topic {
+            continue;
+        }
+
+        let action = details::update_message(&msg.raw).and_then(service::service_action);
+        let params = store::UpsertMessageParams {
+            id: msg.id() as i64,
+            chat_id: chat,
+            sender_id: daemon::extract_sender_id(&msg),
+            ts: msg.date(),
+            edit_ts: None,
+            from_me: msg.outgoing(),
+            text: match &action {
+                Some(action) => action.text.clone(),
+                None => msg.text().to_string(),
+            },
+            media_type: msg.media().map(|_| "media".to_string()),
+            media_path: None,
+            reply_to_id: msg.reply_to_message_id().map(|id| id as i64),
+            topic_id,
+        };
+        let store = app.get_store().await?;
+        store.upsert_message(params).await?;
+        let custom_emoji = emoji::custom_emoji_spans(emoji::update_entities(&msg.raw));
+        emoji::record_custom_emoji(&app.tg.client, &store, chat, msg.id() as i64, &custom_emoji)
+            .await?;
+        if let Some(raw) = details::update_message(&msg.raw) {
+            details::record_message_details(&store, chat, msg.id() as i64, raw).await?;
+        }
+        let archived = store
+            .get_chat(chat)
+            .await?
+            .map(|c| c.archived)
+            .unwrap_or(false);
+        store
+            .upsert_chat(
+                chat,
+                daemon::chat_kind_from_peer(peer),
+                &daemon::chat_name_from_peer(peer),
+                daemon::username_from_peer(peer).as_deref(),
+                Some(msg.date()),
+                daemon::is_forum_peer(peer),
+                daemon::access_hash_from_peer(peer),
+                archived,
+            )
+            .await?;
+        store
+            .update_last_sync_message_id(chat, msg.id() as i64)
+            .await?;
+
+        if let Some(mut m) = store.get_message(chat, msg.id() as i64).await? {
+            details::annotate_messages(&store, std::slice::from_mut(&mut m)).await?;
+            print_tail_line(cli, &store, &m).await?;
+        }
     }
+
+    update_stream.sync_update_state();
     Ok(())
 }
 
-fn parse_time(s: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+const SUMMARY_PROMPT: &str = "You summarize Telegram chat transcripts. \
+Write a concise summary of the main topics, decisions, questions, and action items, \
+mentioning who said what when it matters. Reply in the language of the conversation.";
+
+/// Summarize each chunk, then merge the partial summaries when there was more than one.
+async fn summarize_chunks(
+    config: &llm::LlmConfig,
+    chat_name: &str,
+    chunks: &[String],
+) -> Result<String> {
+    let mut partials = Vec::with_capacity(chunks.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        if chunks.len() > 1 {
+            eprintln!("Summarizing part {}/{}...", i + 1, chunks.len());
+        }
+        let prompt = format!("Transcript of \"{}\":\n\n{}", chat_name, chunk);
+        partials.push(config.complete(SUMMARY_PROMPT, &prompt).await?);
+    }
+    if partials.len() == 1 {
+        return Ok(partials.remove(0));
+    }
+
+    let prompt = format!(
+        "These are summaries of consecutive parts of \"{}\". \
+         Combine them into a single summary:\n\n{}",
+        chat_name,
+        partials.join("\n\n---\n\n")
+    );
+    config.complete(SUMMARY_PROMPT, &prompt).await
+}
+
+/// Continuation token for the next page, or None when this page was the last one.
+fn next_page_cursor(oldest: Option<&store::Message>, count: usize, limit: i64) -> Option<String> {
+    if (count as i64) < limit {
+        return None;
+    }
+    oldest.map(|m| store::MessageCursor::from_message(m).encode())
+}
+
+pub(crate) fn parse_time(s: &str) -> Result<chrono::DateTime<chrono::Utc>> {
     use chrono::{Duration, Local, NaiveTime, TimeZone};
 
     let s_lower = s.to_lowercase();
@@ -621,6 +2052,21 @@ fn parse_time(s: &str) -> Result<chrono::DateTime<chrono::Utc>> {
         return Ok(yesterday.and_time(NaiveTime::MIN).and_utc());
     }
 
+    // Handle "30d", "12h", "2w" (ago implied)
+    if let Some(unit) = s_lower.chars().last() {
+        if let Ok(n) = s_lower[..s_lower.len() - unit.len_utf8()].parse::<i64>() {
+            let duration = match unit {
+                'h' => Some(Duration::hours(n)),
+                'd' => Some(Duration::days(n)),
+                'w' => Some(Duration::weeks(n)),
+                _ => None,
+            };
+            if let Some(duration) = duration {
+                return Ok(chrono::Utc::now() - duration);
+            }
+        }
+    }
+
     // Handle "N days ago", "N weeks ago", "N months ago", "N hours ago"
     if s_lower.ends_with(" ago") {
         let parts: Vec<&str> = s_lower
@@ -703,7 +2149,8 @@ fn parse_time(s: &str) -> Result<chrono::DateTime<chrono::Utc>> {
          - RFC3339: 2024-01-15T10:30:00Z\n  \
          - Date: 2024-01-15\n  \
          - DateTime: 2024-01-15 10:30:00\n  \
-         - Relative: today, yesterday, 1 week ago, 3 days ago, 2 hours ago",
+         - Relative: today, yesterday, 1 week ago, 3 days ago, 2 hours ago\n  \
+         - Short: 12h, 30d, 2w",
         s
     );
 }
@@ -1,31 +1,54 @@
+pub mod alerts;
+pub mod alias;
+pub mod audit;
 pub mod auth;
+pub mod away;
 pub mod chats;
 pub mod clear;
 pub mod completions;
 pub mod contacts;
 pub mod daemon;
 pub mod daemon_service;
+pub mod docs;
+pub mod doctor;
 pub mod drafts;
+pub mod events;
 pub mod export;
+pub mod find;
 pub mod folders;
+pub mod forwards;
+pub mod gifts;
+pub mod ignore;
+pub mod init;
 pub mod messages;
 pub mod polls;
+pub mod premium;
 pub mod profile;
 pub mod read;
+pub mod retention;
+pub mod schedule;
+pub mod self_update;
 pub mod send;
+pub mod stars;
+pub mod stats;
 pub mod stickers;
+pub mod store;
 pub mod sync;
+pub mod tokens;
 pub mod topics;
 pub mod typing;
 pub mod users;
 pub mod version;
 pub mod wipe;
 
+use crate::guard::{self, Access};
 use crate::Cli;
 use clap::Subcommand;
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum Command {
+    /// Interactive first-run setup (auth, sync choices, output defaults)
+    Init(init::InitArgs),
     /// Authenticate with Telegram
     Auth(auth::AuthArgs),
     /// Sync chats and messages from Telegram
@@ -36,10 +59,67 @@ pub enum Command {
 
     /// Manage daemon as a background service (install/start/stop)
     DaemonService(daemon_service::DaemonServiceArgs),
+    /// Stream live updates (messages, edits, reactions, typing, ...) as JSONL
+    Events(events::EventsArgs),
+    /// Keyword alerts raised by the daemon (desktop, webhook, command)
+    Alerts {
+        #[command(subcommand)]
+        cmd: alerts::AlertsCommand,
+    },
+    /// Auto-forward rules run by the daemon
+    Forwards {
+        #[command(subcommand)]
+        cmd: forwards::ForwardsCommand,
+    },
+    /// Recurring messages sent by the daemon (cron-like)
+    Schedule {
+        #[command(subcommand)]
+        cmd: schedule::ScheduleCommand,
+    },
+    /// Auto-reply to private messages while you're away (daemon)
+    Away {
+        #[command(subcommand)]
+        cmd: away::AwayCommand,
+    },
+    /// Activity statistics from the local store
+    Stats {
+        #[command(subcommand)]
+        cmd: stats::StatsCommand,
+    },
+    /// Short names for chats, usable wherever a chat ID is
+    Alias {
+        #[command(subcommand)]
+        cmd: alias::AliasCommand,
+    },
+    /// Chats to skip everywhere (persistent `--ignore`)
+    Ignore {
+        #[command(subcommand)]
+        cmd: ignore::IgnoreCommand,
+    },
+    /// How long local messages of a chat are kept (applied by `store prune`)
+    Retention {
+        #[command(subcommand)]
+        cmd: retention::RetentionCommand,
+    },
+    /// Log of changes made through tgcli (sends, deletes, bans, ...)
+    Audit {
+        #[command(subcommand)]
+        cmd: audit::AuditCommand,
+    },
+    /// Scoped API tokens for scripts and agents (`--token`)
+    Tokens {
+        #[command(subcommand)]
+        cmd: tokens::TokensCommand,
+    },
     /// Clear local database (keeps session)
     Clear(clear::ClearArgs),
     /// Wipe local database file (keeps session)
     Wipe(wipe::WipeArgs),
+    /// Maintain the local store (media integrity checks, retention)
+    Store {
+        #[command(subcommand)]
+        cmd: store::StoreCommand,
+    },
     /// List and show chats
     Chats {
         #[command(subcommand)]
@@ -50,6 +130,8 @@ pub enum Command {
         #[command(subcommand)]
         cmd: messages::MessagesCommand,
     },
+    /// Find chats, contacts, topics, and messages matching a query
+    Find(find::FindArgs),
     /// Send a message
     Send(send::SendArgs),
     /// Search and show contacts
@@ -91,6 +173,21 @@ pub enum Command {
         #[command(subcommand)]
         cmd: profile::ProfileCommand,
     },
+    /// Show Telegram Premium status
+    Premium {
+        #[command(subcommand)]
+        cmd: premium::PremiumCommand,
+    },
+    /// Show Telegram Stars balance and transactions
+    Stars {
+        #[command(subcommand)]
+        cmd: stars::StarsCommand,
+    },
+    /// List received gifts
+    Gifts {
+        #[command(subcommand)]
+        cmd: gifts::GiftsCommand,
+    },
     /// Export chat history to file
     Export(export::ExportArgs),
     /// Manage message drafts
@@ -98,42 +195,169 @@ pub enum Command {
         #[command(subcommand)]
         cmd: drafts::DraftsCommand,
     },
+    /// Diagnose session, connectivity, store, and environment problems
+    Doctor(doctor::DoctorArgs),
     /// Show version info
-    Version,
+    Version(version::VersionArgs),
+    /// Update tgcli to the latest GitHub release (checksum-verified)
+    SelfUpdate(self_update::SelfUpdateArgs),
     /// Generate shell completions
     Completions {
         /// Shell type to generate completions for
         #[arg(value_enum)]
         shell: completions::ShellType,
     },
+    /// Generate man pages or a markdown command reference
+    Docs {
+        #[command(subcommand)]
+        cmd: docs::DocsCommand,
+    },
+    /// Chat candidates for shell completion scripts. Not `__complete-chats`:
+    /// the bash generator splits command paths on `__`.
+    #[command(name = "complete-chats", hide = true)]
+    CompleteChats {
+        #[arg(long, value_enum, default_value = "bash")]
+        shell: completions::ShellType,
+        /// Word being completed
+        query: Option<String>,
+    },
+}
+
+impl Command {
+    /// What an API token must grant for this command to run. Changes made by
+    /// `Read` commands are checked again where they happen.
+    fn access(&self) -> Access {
+        match self {
+            Command::Version(_)
+            | Command::Completions { .. }
+            | Command::Docs { .. }
+            | Command::CompleteChats { .. }
+            | Command::Send(_)
+            | Command::Read(_)
+            | Command::Typing(_) => Access::Open,
+            Command::Sync(_)
+            | Command::Daemon(_)
+            | Command::Events(_)
+            | Command::Stats { .. }
+            | Command::Messages { .. }
+            | Command::Find(_)
+            | Command::Polls { .. }
+            | Command::Topics { .. }
+            | Command::Export(_)
+            | Command::Drafts { .. } => Access::Read("messages"),
+            Command::Chats { .. } | Command::Folders { .. } | Command::Stickers { .. } => {
+                Access::Read("chats")
+            }
+            Command::Contacts { .. } | Command::Users { .. } => Access::Read("contacts"),
+            Command::Profile { .. }
+            | Command::Premium { .. }
+            | Command::Stars { .. }
+            | Command::Gifts { .. }
+            | Command::Doctor(_) => Access::Read("account"),
+            Command::Init(_)
+            | Command::Auth(_)
+            | Command::DaemonService(_)
+            | Command::Alerts { .. }
+            | Command::Forwards { .. }
+            | Command::Schedule { .. }
+            | Command::Away { .. }
+            | Command::Alias { .. }
+            | Command::Ignore { .. }
+            | Command::Retention { .. }
+            | Command::Tokens { .. }
+            | Command::Audit { .. }
+            | Command::Clear(_)
+            | Command::Wipe(_)
+            | Command::Store { .. }
+            | Command::SelfUpdate(_) => Access::Full,
+        }
+    }
+}
+
+/// Run the command; `name` (e.g. `messages delete`) labels its audit entries.
+pub async fn run(cli: Cli, name: &str) -> anyhow::Result<()> {
+    match &cli.token {
+        Some(token) => {
+            tokens::activate(&cli, token).await?;
+            guard::check_access(cli.command.access())?;
+        }
+        // A store that requires tokens grants nothing without one
+        None if crate::config::Config::load(&cli.config_dir())
+            .unwrap_or_default()
+            .require_token =>
+        {
+            guard::restrict(Vec::new());
+            guard::check_access(cli.command.access()).map_err(|e| {
+                e.context("This store requires an API token (--token or TGCLI_TOKEN)")
+            })?;
+        }
+        None => {}
+    }
+
+    let mark = guard::mark();
+    let result = dispatch(&cli).await;
+    if let Err(e) = &result {
+        guard::note_failure(mark, e);
+    }
+    let attempts = guard::take_attempts();
+    if !attempts.is_empty() {
+        let logged = async {
+            let store = crate::store::Store::open(&cli.store_dir()).await?;
+            audit::record(&store, name, attempts).await
+        };
+        if let Err(e) = logged.await {
+            log::warn!("Failed to write the audit log: {:#}", e);
+        }
+    }
+    result
 }
 
-pub async fn run(cli: Cli) -> anyhow::Result<()> {
+async fn dispatch(cli: &Cli) -> anyhow::Result<()> {
     match &cli.command {
-        Command::Auth(args) => auth::run(&cli, args).await,
-        Command::Sync(args) => sync::run(&cli, args).await,
-        Command::Daemon(args) => daemon::run(&cli, args).await,
-        Command::DaemonService(subcmd) => daemon_service::run(&cli, subcmd).await,
-        Command::Clear(args) => clear::run(&cli, args).await,
-        Command::Wipe(args) => wipe::run(&cli, args).await,
-        Command::Chats { cmd } => chats::run(&cli, cmd).await,
-        Command::Messages { cmd } => messages::run(&cli, cmd).await,
-        Command::Send(args) => send::run(&cli, args).await,
-        Command::Contacts { cmd } => contacts::run(&cli, cmd).await,
-        Command::Read(args) => read::run(&cli, args).await,
-        Command::Stickers { cmd } => stickers::run(&cli, cmd).await,
-        Command::Polls { cmd } => polls::run(&cli, cmd).await,
-        Command::Topics { cmd } => topics::run(&cli, cmd).await,
-        Command::Folders { cmd } => folders::run(&cli, cmd).await,
-        Command::Users { cmd } => users::run(&cli, cmd).await,
-        Command::Typing(args) => typing::run(&cli, args).await,
-        Command::Profile { cmd } => profile::run(&cli, cmd).await,
-        Command::Export(args) => export::run(&cli, args).await,
-        Command::Drafts { cmd } => drafts::run(&cli, cmd).await,
-        Command::Version => {
-            version::run(&cli);
-            Ok(())
+        Command::Init(args) => init::run(cli, args).await,
+        Command::Auth(args) => auth::run(cli, args).await,
+        Command::Sync(args) => sync::run(cli, args).await,
+        Command::Daemon(args) => daemon::run(cli, args).await,
+        Command::DaemonService(subcmd) => daemon_service::run(cli, subcmd).await,
+        Command::Events(args) => events::run(cli, args).await,
+        Command::Alerts { cmd } => alerts::run(cli, cmd).await,
+        Command::Forwards { cmd } => forwards::run(cli, cmd).await,
+        Command::Schedule { cmd } => schedule::run(cli, cmd).await,
+        Command::Away { cmd } => away::run(cli, cmd).await,
+        Command::Stats { cmd } => stats::run(cli, cmd).await,
+        Command::Alias { cmd } => alias::run(cli, cmd).await,
+        Command::Ignore { cmd } => ignore::run(cli, cmd).await,
+        Command::Retention { cmd } => retention::run(cli, cmd).await,
+        Command::Tokens { cmd } => tokens::run(cli, cmd).await,
+        Command::Audit { cmd } => audit::run(cli, cmd).await,
+        Command::Clear(args) => clear::run(cli, args).await,
+        Command::Wipe(args) => wipe::run(cli, args).await,
+        Command::Store { cmd } => store::run(cli, cmd).await,
+        Command::Chats { cmd } => chats::run(cli, cmd).await,
+        Command::Messages { cmd } => messages::run(cli, cmd).await,
+        Command::Find(args) => find::run(cli, args).await,
+        Command::Send(args) => send::run(cli, args).await,
+        Command::Contacts { cmd } => contacts::run(cli, cmd).await,
+        Command::Read(args) => read::run(cli, args).await,
+        Command::Stickers { cmd } => stickers::run(cli, cmd).await,
+        Command::Polls { cmd } => polls::run(cli, cmd).await,
+        Command::Topics { cmd } => topics::run(cli, cmd).await,
+        Command::Folders { cmd } => folders::run(cli, cmd).await,
+        Command::Users { cmd } => users::run(cli, cmd).await,
+        Command::Typing(args) => typing::run(cli, args).await,
+        Command::Profile { cmd } => profile::run(cli, cmd).await,
+        Command::Premium { cmd } => premium::run(cli, cmd).await,
+        Command::Stars { cmd } => stars::run(cli, cmd).await,
+        Command::Gifts { cmd } => gifts::run(cli, cmd).await,
+        Command::Export(args) => export::run(cli, args).await,
+        Command::Drafts { cmd } => drafts::run(cli, cmd).await,
+        Command::Doctor(args) => doctor::run(cli, args).await,
+        Command::SelfUpdate(args) => self_update::run(cli, args).await,
+        Command::Version(args) => version::run(cli, args).await,
+        Command::Completions { shell } => completions::run(cli, shell),
+        Command::Docs { cmd } => docs::run(cmd),
+        Command::CompleteChats { shell, query } => {
+            completions::complete_chats(cli, shell, query.as_deref()).await
         }
-        Command::Completions { shell } => completions::run(shell),
     }
 }
@@ -1,5 +1,6 @@
 use crate::app::App;
 use crate::out;
+use crate::store::{MessagePoll, Store};
 use crate::Cli;
 use anyhow::Result;
 use clap::{Args, Subcommand};
@@ -10,12 +11,16 @@ pub enum PollsCommand {
     Create(CreateArgs),
     /// Vote in a poll
     Vote(VoteArgs),
+    /// Show a poll's results as of the last sync (works offline)
+    Results(ResultsArgs),
+    /// Search synced polls by question
+    Search(SearchArgs),
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct CreateArgs {
     /// Chat ID to send the poll to
-    #[arg(long)]
+    #[arg(long, value_parser = crate::config::parse_chat)]
     pub chat: i64,
 
     /// Poll question
@@ -38,7 +43,7 @@ pub struct CreateArgs {
 #[derive(Args, Debug, Clone)]
 pub struct VoteArgs {
     /// Chat ID where the poll is
-    #[arg(long)]
+    #[arg(long, value_parser = crate::config::parse_chat)]
     pub chat: i64,
 
     /// Message ID of the poll
@@ -50,10 +55,37 @@ pub struct VoteArgs {
     pub options: Vec<usize>,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct ResultsArgs {
+    /// Chat ID where the poll is
+    #[arg(long, value_parser = crate::config::parse_chat)]
+    pub chat: i64,
+
+    /// Message ID of the poll
+    #[arg(long)]
+    pub message: i64,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SearchArgs {
+    /// Text to look for in poll questions
+    pub query: String,
+
+    /// Chat ID filter
+    #[arg(long, value_parser = crate::config::parse_chat)]
+    pub chat: Option<i64>,
+
+    /// Limit results
+    #[arg(long, default_value = "20")]
+    pub limit: i64,
+}
+
 pub async fn run(cli: &Cli, cmd: &PollsCommand) -> Result<()> {
     match cmd {
         PollsCommand::Create(args) => create_poll(cli, args).await,
         PollsCommand::Vote(args) => vote_poll(cli, args).await,
+        PollsCommand::Results(args) => poll_results(cli, args).await,
+        PollsCommand::Search(args) => search_polls(cli, args).await,
     }
 }
 
@@ -118,3 +150,107 @@ async fn vote_poll(cli: &Cli, args: &VoteArgs) -> Result<()> {
     }
     Ok(())
 }
+
+async fn poll_results(cli: &Cli, args: &ResultsArgs) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+    let Some(poll) = store.get_message_poll(args.chat, args.message).await? else {
+        anyhow::bail!(
+            "No poll stored for message {} in chat {}. Run `tgcli sync` or `tgcli messages fetch --chat {}` first.",
+            args.message,
+            args.chat,
+            args.chat
+        );
+    };
+
+    if cli.output.is_json() {
+        out::write_json(&poll)?;
+    } else {
+        print_poll(&poll);
+    }
+    Ok(())
+}
+
+async fn search_polls(cli: &Cli, args: &SearchArgs) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+    let polls = store
+        .search_polls(&args.query, args.chat, args.limit)
+        .await?;
+
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "count": polls.len(),
+            "polls": polls,
+        }))?;
+    } else if polls.is_empty() {
+        println!("No polls found.");
+    } else {
+        for (i, poll) in polls.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("Chat {}, message {}", poll.chat_id, poll.msg_id);
+            print_poll(poll);
+        }
+    }
+    Ok(())
+}
+
+/// Question, flags, and one line per option with its share of the votes.
+fn print_poll(poll: &MessagePoll) {
+    let mut flags = Vec::new();
+    if poll.closed {
+        flags.push("closed");
+    }
+    if poll.quiz {
+        flags.push("quiz");
+    }
+    if poll.multiple_choice {
+        flags.push("multiple choice");
+    }
+    if poll.public_voters {
+        flags.push("public");
+    }
+    if flags.is_empty() {
+        println!("{}", poll.question);
+    } else {
+        println!("{} ({})", poll.question, flags.join(", "));
+    }
+
+    let total: i32 = poll.options.iter().filter_map(|o| o.voters).sum();
+    for option in &poll.options {
+        let marker = match (option.chosen, option.correct) {
+            (_, true) => "✓",
+            (true, false) => "*",
+            _ => " ",
+        };
+        match option.voters {
+            Some(voters) => {
+                let share = if total > 0 {
+                    voters as f64 / total as f64
+                } else {
+                    0.0
+                };
+                println!(
+                    "{} {:>2}  {:<30} {:>6} {:>4.0}%  {}",
+                    marker,
+                    option.index,
+                    out::truncate(&option.text, 30),
+                    voters,
+                    share * 100.0,
+                    "█".repeat((share * 20.0).round() as usize)
+                );
+            }
+            None => println!(
+                "{} {:>2}  {:<30} {:>6}",
+                marker,
+                option.index,
+                out::truncate(&option.text, 30),
+                "-"
+            ),
+        }
+    }
+    match poll.total_voters {
+        Some(voters) => println!("{} voters", voters),
+        None => println!("No results synced yet"),
+    }
+}
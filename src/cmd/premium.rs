@@ -0,0 +1,35 @@
+use crate::app::App;
+use crate::out;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum PremiumCommand {
+    /// Show whether the account has Premium and its Stars balance
+    Status,
+}
+
+pub async fn run(cli: &Cli, cmd: &PremiumCommand) -> Result<()> {
+    match cmd {
+        PremiumCommand::Status => {
+            let app = App::new(cli).await?;
+            let status = app.premium_status().await?;
+
+            if cli.output.is_json() {
+                out::write_json(&status)?;
+            } else {
+                println!(
+                    "Premium: {}",
+                    if status.premium {
+                        "active"
+                    } else {
+                        "not active"
+                    }
+                );
+                println!("Stars:   {}", status.stars_balance);
+            }
+        }
+    }
+    Ok(())
+}
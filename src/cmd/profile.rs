@@ -56,7 +56,6 @@ pub async fn run(cli: &Cli, cmd: &ProfileCommand) -> Result<()> {
             let request = tl::functions::users::GetFullUser { id: input_user };
             let full_user = app
                 .tg
-                .client
                 .invoke(&request)
                 .await
                 .context("Failed to get full profile info")?;
@@ -95,7 +94,7 @@ pub async fn run(cli: &Cli, cmd: &ProfileCommand) -> Result<()> {
                     println!("Username: @{}", username);
                 }
                 if let Some(ref phone) = profile.phone {
-                    println!("Phone: {}", phone);
+                    println!("Phone: {}", out::redact::phone(phone));
                 }
                 if let Some(ref bio) = profile.bio {
                     println!("Bio: {}", bio);
@@ -139,7 +138,6 @@ pub async fn run(cli: &Cli, cmd: &ProfileCommand) -> Result<()> {
                 };
 
                 app.tg
-                    .client
                     .invoke(&request)
                     .await
                     .context("Failed to update name")?;
@@ -161,7 +159,6 @@ pub async fn run(cli: &Cli, cmd: &ProfileCommand) -> Result<()> {
                 };
 
                 app.tg
-                    .client
                     .invoke(&request)
                     .await
                     .context("Failed to update bio")?;
@@ -176,7 +173,6 @@ pub async fn run(cli: &Cli, cmd: &ProfileCommand) -> Result<()> {
                 };
 
                 app.tg
-                    .client
                     .invoke(&request)
                     .await
                     .context("Failed to update username. It may already be taken or invalid.")?;
@@ -7,12 +7,17 @@ use clap::Args;
 #[derive(Args, Debug, Clone)]
 pub struct ReadArgs {
     /// Chat ID
-    #[arg(long)]
+    #[arg(long, value_parser = crate::config::parse_chat)]
     pub chat: i64,
 
-    /// Message ID (mark up to this message as read)
-    #[arg(long)]
-    pub message: Option<i64>,
+    /// Only mark messages up to this ID as read (in the chat, or in --topic)
+    #[arg(
+        long,
+        alias = "message",
+        value_name = "MSG_ID",
+        conflicts_with = "all_topics"
+    )]
+    pub up_to: Option<i64>,
 
     /// Topic ID (for forum groups - marks a specific topic as read)
     #[arg(long)]
@@ -47,19 +52,33 @@ pub async fn run(cli: &Cli, args: &ReadArgs) -> Result<()> {
             println!("Marked {} topics as read.", count);
         }
     } else if let Some(topic_id) = args.topic {
-        // Mark a specific topic as read
-        app.mark_read(args.chat, Some(topic_id)).await?;
+        // Mark a specific topic as read, up to its latest message by default
+        let read_max_id = app
+            .mark_topic_read_up_to(args.chat, topic_id, args.up_to)
+            .await?;
 
         if cli.output.is_json() {
             out::write_json(&serde_json::json!({
                 "marked_read": true,
-                "topic_id": topic_id
+                "topic_id": topic_id,
+                "up_to": read_max_id
             }))?;
         } else {
-            println!("Marked topic {} as read.", topic_id);
+            println!(
+                "Marked topic {} as read up to message {}.",
+                topic_id, read_max_id
+            );
+        }
+    } else if let Some(max_id) = args.up_to {
+        app.mark_read_up_to(args.chat, max_id).await?;
+
+        if cli.output.is_json() {
+            out::write_json(&serde_json::json!({ "marked_read": true, "up_to": max_id }))?;
+        } else {
+            println!("Marked as read up to message {}.", max_id);
         }
     } else {
-        // Mark the whole chat as read (or a single topic if --topic was given but not --all-topics)
+        // Mark the whole chat as read
         app.mark_read(args.chat, None).await?;
 
         if cli.output.is_json() {
@@ -0,0 +1,113 @@
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum RetentionCommand {
+    /// Keep a chat's local messages only for a while; older ones are deleted
+    /// by `tgcli store prune` and the daemon (Telegram is not touched)
+    Set {
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// How long to keep messages: days, weeks, months or years (90d, 12w, 6m, 1y)
+        #[arg(long, value_parser = parse_keep, value_name = "AGE")]
+        keep: i64,
+        /// Also delete the downloaded media of pruned messages
+        #[arg(long)]
+        delete_media: bool,
+    },
+    /// List retention policies with how much each has pruned
+    List,
+    /// Keep a chat's messages forever again
+    Remove {
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+    },
+}
+
+/// `90d`, `12w`, `6m` or `1y` as a number of days.
+pub fn parse_keep(s: &str) -> Result<i64, String> {
+    let err = || format!("Invalid age '{}' (expected e.g. 90d, 12w, 6m or 1y)", s);
+    let unit = s.chars().last().ok_or_else(err)?;
+    let n: i64 = s[..s.len() - unit.len_utf8()].parse().map_err(|_| err())?;
+    let days = match unit.to_ascii_lowercase() {
+        'd' => n,
+        'w' => n * 7,
+        'm' => n * 30,
+        'y' => n * 365,
+        _ => return Err(err()),
+    };
+    if days <= 0 {
+        return Err(err());
+    }
+    Ok(days)
+}
+
+pub async fn run(cli: &Cli, cmd: &RetentionCommand) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    match cmd {
+        RetentionCommand::Set {
+            chat,
+            keep,
+            delete_media,
+        } => {
+            store
+                .set_retention_policy(*chat, *keep, *delete_media)
+                .await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": chat,
+                    "keep_days": keep,
+                    "delete_media": delete_media,
+                }))?;
+            } else {
+                println!(
+                    "Chat {} keeps {} days of local messages{}. Prune now with `tgcli store prune`; the daemon prunes hourly.",
+                    chat,
+                    keep,
+                    if *delete_media { " and media" } else { "" }
+                );
+            }
+        }
+        RetentionCommand::List => {
+            let policies = store.list_retention_policies().await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "policies": policies }))?;
+            } else if policies.is_empty() {
+                println!("No retention policies. Add one with `tgcli retention set`.");
+            } else {
+                cli.output.write(&policies)?;
+            }
+        }
+        RetentionCommand::Remove { chat } => {
+            if !store.remove_retention_policy(*chat).await? {
+                anyhow::bail!("Chat {} has no retention policy", chat);
+            }
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "removed": chat }))?;
+            } else {
+                println!("Removed the retention policy of chat {}.", chat);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keep_ages() {
+        assert_eq!(parse_keep("90d"), Ok(90));
+        assert_eq!(parse_keep("2w"), Ok(14));
+        assert_eq!(parse_keep("6m"), Ok(180));
+        assert_eq!(parse_keep("1Y"), Ok(365));
+        assert!(parse_keep("0d").is_err());
+        assert!(parse_keep("12h").is_err());
+        assert!(parse_keep("d").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::app::App;
+use crate::cron::Cron;
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::{Context, Result};
+use chrono::Local;
+use clap::Subcommand;
+use serde::Serialize;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ScheduleCommand {
+    /// Send a message on a recurring schedule while `tgcli daemon` runs
+    Add {
+        /// Chat to send to
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: i64,
+        /// Cron expression in local time: minute hour day month weekday
+        /// (e.g. "0 9 * * 1" = Mondays at 09:00)
+        #[arg(long)]
+        cron: String,
+        /// Message text
+        #[arg(long)]
+        text: String,
+    },
+    /// List recurring messages and when they next run
+    List,
+    /// Remove a recurring message
+    Remove {
+        /// Schedule ID (see `schedule list`)
+        id: i64,
+    },
+    /// Send a recurring message now (counts as a run)
+    RunNow {
+        /// Schedule ID (see `schedule list`)
+        id: i64,
+    },
+}
+
+#[derive(Serialize)]
+struct ScheduleInfo {
+    id: i64,
+    chat_id: i64,
+    cron: String,
+    text: String,
+    next_run: Option<String>,
+    last_run: Option<String>,
+    runs: i64,
+}
+
+pub async fn run(cli: &Cli, cmd: &ScheduleCommand) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    match cmd {
+        ScheduleCommand::Add { chat, cron, text } => {
+            let parsed: Cron = cron.parse()?;
+            let next = parsed
+                .next_after(Local::now())
+                .with_context(|| format!("'{}' never runs", cron))?;
+            let id = store.add_schedule(*chat, cron, text).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "id": id,
+                    "chat_id": chat,
+                    "cron": cron,
+                    "next_run": next.to_rfc3339(),
+                }))?;
+            } else {
+                println!(
+                    "Added schedule {}, next run {}. It runs while `tgcli daemon` is running.",
+                    id,
+                    next.format("%Y-%m-%d %H:%M")
+                );
+            }
+        }
+        ScheduleCommand::List => {
+            let now = Local::now();
+            let schedules: Vec<ScheduleInfo> = store
+                .list_schedules()
+                .await?
+                .into_iter()
+                .map(|s| ScheduleInfo {
+                    next_run: s
+                        .cron
+                        .parse::<Cron>()
+                        .ok()
+                        .and_then(|c| c.next_after(now))
+                        .map(|t| t.format("%Y-%m-%d %H:%M").to_string()),
+                    last_run: s
+                        .last_run
+                        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()),
+                    id: s.id,
+                    chat_id: s.chat_id,
+                    cron: s.cron,
+                    text: s.text,
+                    runs: s.runs,
+                })
+                .collect();
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "count": schedules.len(),
+                    "schedules": schedules,
+                }))?;
+            } else if schedules.is_empty() {
+                println!("No recurring messages. Add one with `tgcli schedule add`.");
+            } else {
+                cli.output.write(&schedules)?;
+            }
+        }
+        ScheduleCommand::Remove { id } => {
+            if !store.remove_schedule(*id).await? {
+                anyhow::bail!("No schedule with ID {}", id);
+            }
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "removed": id }))?;
+            } else {
+                println!("Removed schedule {}.", id);
+            }
+        }
+        ScheduleCommand::RunNow { id } => {
+            let schedule = store
+                .list_schedules()
+                .await?
+                .into_iter()
+                .find(|s| s.id == *id)
+                .with_context(|| format!("No schedule with ID {}", id))?;
+            let mut app = App::new(cli).await?;
+            let msg_id = app.send_schedule(&schedule).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "id": id,
+                    "chat_id": schedule.chat_id,
+                    "message_id": msg_id,
+                }))?;
+            } else {
+                println!(
+                    "Sent schedule {} to chat {} (message {}).",
+                    id, schedule.chat_id, msg_id
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use crate::out;
+use crate::Cli;
+use anyhow::{Context, Result};
+use clap::Args;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+const REPO: &str = "dgrr/tgcli";
+/// Published next to the binaries by the release workflow
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+#[derive(Args, Debug, Clone)]
+pub struct SelfUpdateArgs {
+    /// Only report whether a newer release exists
+    #[arg(long)]
+    pub check_only: bool,
+    /// Install this release tag instead of the latest (e.g. v0.5.0)
+    #[arg(long)]
+    pub version: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Release {
+    pub tag_name: String,
+    pub html_url: String,
+    /// Release notes (markdown)
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(serde::Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Release asset for this platform, as named by the release workflow.
+fn asset_name() -> Result<String> {
+    let os = match std::env::consts::OS {
+        "linux" => "linux",
+        "macos" => "darwin",
+        other => anyhow::bail!("No tgcli release builds for {}", other),
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => anyhow::bail!("No tgcli release builds for {}", other),
+    };
+    Ok(format!("tgcli-{}-{}", os, arch))
+}
+
+/// `v1.2.3` / `1.2.3-rc1` as comparable numbers (pre-release suffixes ignored).
+pub(crate) fn parse_version(v: &str) -> Option<(u64, u64, u64)> {
+    let v = v.trim_start_matches('v');
+    let v = v.split(['-', '+']).next()?;
+    let mut parts = v.split('.').map(|p| p.parse::<u64>().ok());
+    Some((
+        parts.next()??,
+        parts.next()??,
+        parts.next().unwrap_or(Some(0))?,
+    ))
+}
+
+pub(crate) fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent(concat!("tgcli/", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
+
+pub(crate) async fn fetch_release(client: &reqwest::Client, tag: Option<&str>) -> Result<Release> {
+    let url = match tag {
+        Some(tag) => format!(
+            "https://api.github.com/repos/{}/releases/tags/{}",
+            REPO, tag
+        ),
+        None => format!("https://api.github.com/repos/{}/releases/latest", REPO),
+    };
+    client
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("Failed to look up the release ({})", url))?
+        .json()
+        .await
+        .context("Unexpected response from GitHub")
+}
+
+/// Recent releases, newest first.
+pub(crate) async fn fetch_releases(client: &reqwest::Client) -> Result<Vec<Release>> {
+    let url = format!("https://api.github.com/repos/{}/releases?per_page=50", REPO);
+    client
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("Failed to list releases ({})", url))?
+        .json()
+        .await
+        .context("Unexpected response from GitHub")
+}
+
+async fn download(client: &reqwest::Client, asset: &Asset) -> Result<Vec<u8>> {
+    let bytes = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("Failed to download {}", asset.name))?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+/// The expected SHA-256 of `name` from a `sha256sum`-style file.
+fn expected_checksum(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, file) = line.split_once(char::is_whitespace)?;
+        (file.trim().trim_start_matches('*') == name).then(|| hash.to_lowercase())
+    })
+}
+
+/// Write the new binary next to the old one, then rename it over it, so the
+/// path never points at a half-written file.
+fn replace_binary(exe: &Path, data: &[u8]) -> Result<()> {
+    let dir = exe.parent().context("Binary has no parent directory")?;
+    let tmp = dir.join(format!(".tgcli-update-{}", std::process::id()));
+    let result = (|| -> Result<()> {
+        std::fs::write(&tmp, data)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o755))?;
+        }
+        std::fs::rename(&tmp, exe)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result.with_context(|| {
+        format!(
+            "Failed to replace {} (no write access? try with sudo)",
+            exe.display()
+        )
+    })
+}
+
+pub async fn run(cli: &Cli, args: &SelfUpdateArgs) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let client = client()?;
+    let release = fetch_release(&client, args.version.as_deref()).await?;
+    let newer = match (parse_version(&release.tag_name), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => release.tag_name.trim_start_matches('v') != current,
+    };
+
+    if args.check_only {
+        if cli.output.is_json() {
+            out::write_json(&serde_json::json!({
+                "current": current,
+                "latest": release.tag_name,
+                "update_available": newer,
+                "url": release.html_url,
+            }))?;
+        } else if newer {
+            println!(
+                "tgcli {} is available (you have {}): {}",
+                release.tag_name, current, release.html_url
+            );
+        } else {
+            println!("tgcli {} is up to date.", current);
+        }
+        return Ok(());
+    }
+    if !newer && args.version.is_none() {
+        println!("tgcli {} is up to date.", current);
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe()?
+        .canonicalize()
+        .context("Failed to locate the running binary")?;
+    if exe.components().any(|c| c.as_os_str() == "Cellar") {
+        anyhow::bail!("tgcli was installed with Homebrew; update it with `brew upgrade tgcli`");
+    }
+
+    let name = asset_name()?;
+    let find = |n: &str| release.assets.iter().find(|a| a.name == n);
+    let binary = find(&name)
+        .with_context(|| format!("Release {} has no {} build", release.tag_name, name))?;
+    let sums = find(CHECKSUMS_ASSET).with_context(|| {
+        format!(
+            "Release {} has no {}, so the download can't be verified",
+            release.tag_name, CHECKSUMS_ASSET
+        )
+    })?;
+    let sums = String::from_utf8(download(&client, sums).await?)?;
+    let expected = expected_checksum(&sums, &name)
+        .with_context(|| format!("{} has no entry for {}", CHECKSUMS_ASSET, name))?;
+
+    eprintln!("Downloading {} {}...", name, release.tag_name);
+    let data = download(&client, binary).await?;
+    let actual = format!("{:x}", Sha256::digest(&data));
+    if actual != expected {
+        anyhow::bail!(
+            "Checksum mismatch for {} (expected {}, got {}); not installing",
+            name,
+            expected,
+            actual
+        );
+    }
+    replace_binary(&exe, &data)?;
+
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "updated": true,
+            "from": current,
+            "to": release.tag_name,
+            "path": exe,
+        }))?;
+    } else {
+        println!(
+            "Updated tgcli {} -> {} ({}).",
+            current,
+            release.tag_name,
+            exe.display()
+        );
+    }
+    Ok(())
+}
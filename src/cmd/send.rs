@@ -1,10 +1,13 @@
+use crate::app::send::TextOptions;
 use crate::app::App;
+use crate::cmd::stickers;
 use crate::out;
 use crate::Cli;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use clap::Args;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 #[derive(clap::ValueEnum, Debug, Clone, Default, PartialEq)]
 pub enum ParseMode {
@@ -17,14 +20,19 @@ pub enum ParseMode {
 #[derive(Args, Debug, Clone)]
 pub struct SendArgs {
     /// Recipient chat ID
-    #[arg(long)]
-    pub to: i64,
-
-    /// Message text (required unless --sticker or media is provided)
-    #[arg(long, required_unless_present_any = ["sticker", "photo", "video", "file", "voice"])]
+    #[arg(
+        long,
+        value_parser = crate::config::parse_chat,
+        value_name = "CHAT",
+        required_unless_present = "comment_on"
+    )]
+    pub to: Option<i64>,
+
+    /// Message text (required unless --sticker, media or --todo is provided; the list title with --todo)
+    #[arg(long, required_unless_present_any = ["sticker", "photo", "video", "file", "voice", "todo"])]
     pub message: Option<String>,
 
-    /// Sticker file_id (from `tgcli stickers show --pack <pack>`)
+    /// Sticker file_id (from `tgcli stickers show --pack <pack>`), or an emoji to send the top match
     #[arg(long, conflicts_with_all = ["message", "photo", "video", "file", "voice"])]
     pub sticker: Option<String>,
 
@@ -36,14 +44,31 @@ pub struct SendArgs {
     #[arg(long, conflicts_with_all = ["sticker", "photo", "file", "voice"])]
     pub video: Option<PathBuf>,
 
-    /// Send a file as document (any file type, preserves original filename)
+    /// Send a file as document (any file type, preserves original filename); `-` reads stdin
     #[arg(long, conflicts_with_all = ["sticker", "photo", "video", "voice"])]
     pub file: Option<PathBuf>,
 
+    /// File name shown in Telegram for --file (required with `--file -`)
+    #[arg(long, requires = "file")]
+    pub filename: Option<String>,
+
     /// Send an audio file as voice message (inline playback in Telegram)
     #[arg(long, conflicts_with_all = ["sticker", "photo", "video", "file"])]
     pub voice: Option<PathBuf>,
 
+    /// Send a todo list (checklist) of `;`-separated items, e.g. "milk;eggs;bread"
+    #[arg(long, conflicts_with_all = ["sticker", "photo", "video", "file", "voice"])]
+    pub todo: Option<String>,
+
+    /// Comment on a channel post instead: `<channel_id>/<post_id>` (the
+    /// comment goes to the post's thread in the linked discussion group)
+    #[arg(
+        long,
+        value_name = "CHANNEL/POST",
+        conflicts_with_all = ["to", "sticker", "photo", "video", "file", "voice", "todo", "topic", "reply_to", "schedule", "schedule_in"]
+    )]
+    pub comment_on: Option<String>,
+
     /// Forum topic ID (for sending to a specific topic in a forum/supergroup)
     #[arg(long)]
     pub topic: Option<i32>,
@@ -67,6 +92,43 @@ pub struct SendArgs {
     /// Message parse mode: none (plain text), markdown, or html
     #[arg(long, value_enum, default_value = "none")]
     pub parse_mode: ParseMode,
+
+    /// Show a link preview for the first URL in the message
+    #[arg(long, conflicts_with = "no_preview")]
+    pub preview: bool,
+
+    /// Don't show a link preview (default)
+    #[arg(long)]
+    pub no_preview: bool,
+
+    /// Show the link preview above the message text (implies --preview)
+    #[arg(long, conflicts_with = "no_preview")]
+    pub preview_above: bool,
+
+    /// Post as this channel (a channel you admin, or the group's linked channel)
+    #[arg(
+        long,
+        value_name = "CHANNEL_ID",
+        conflicts_with_all = ["sticker", "photo", "video", "file", "voice", "todo"]
+    )]
+    pub as_channel: Option<i64>,
+
+    /// Don't show upload progress
+    #[arg(long, short)]
+    pub quiet: bool,
+}
+
+/// Parse a `--comment-on` target: `<channel_id>/<post_id>`.
+fn parse_comment_target(target: &str) -> Result<(i64, i64)> {
+    target
+        .split_once('/')
+        .and_then(|(channel, post)| Some((channel.trim().parse().ok()?, post.trim().parse().ok()?)))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid --comment-on '{}'. Use <channel_id>/<post_id>, e.g. 1234567890/42",
+                target
+            )
+        })
 }
 
 /// Parse schedule arguments and return the scheduled DateTime if provided
@@ -105,6 +167,80 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
     // Parse schedule options
     let schedule_time = parse_schedule(&args.schedule, &args.schedule_in)?;
 
+    let parse_mode = match args.parse_mode {
+        ParseMode::Markdown => "markdown",
+        ParseMode::Html => "html",
+        ParseMode::None => "none",
+    };
+    let opts = TextOptions {
+        preview: args.preview || args.preview_above,
+        preview_above: args.preview_above,
+        send_as: args.as_channel,
+    };
+
+    // Handle comments on channel posts
+    if let Some(ref target) = args.comment_on {
+        let (channel_id, post_id) = parse_comment_target(target)?;
+        let Some(message) = args.message.as_ref() else {
+            anyhow::bail!("--message is required with --comment-on");
+        };
+        let mut app = App::new(cli).await?;
+        let (group_id, msg_id) = app
+            .send_comment(channel_id, post_id, message, parse_mode, opts)
+            .await?;
+
+        if cli.output.is_json() {
+            out::write_json(&serde_json::json!({
+                "sent": true,
+                "channel": channel_id,
+                "post": post_id,
+                "to": group_id,
+                "id": msg_id,
+            }))?;
+        } else {
+            println!(
+                "Commented on post {} of channel {} (discussion group {})",
+                post_id, channel_id, group_id
+            );
+        }
+        return Ok(());
+    }
+    let Some(to) = args.to else {
+        anyhow::bail!("--to is required unless --comment-on is given");
+    };
+
+    // Handle todo list sending
+    if let Some(ref todo) = args.todo {
+        if args.topic.is_some() || args.reply_to.is_some() || schedule_time.is_some() {
+            anyhow::bail!("--topic, --reply-to and scheduling are not supported with --todo yet");
+        }
+        let items: Vec<String> = todo
+            .split(';')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect();
+        if items.is_empty() {
+            anyhow::bail!("--todo needs at least one item");
+        }
+        let title = args.message.as_deref().unwrap_or("Todo");
+        let mut app = App::new(cli).await?;
+        let msg_id = app.send_todo(to, title, &items).await?;
+
+        if cli.output.is_json() {
+            out::write_json(&serde_json::json!({
+                "sent": true,
+                "to": to,
+                "id": msg_id,
+                "type": "todo",
+                "items": items,
+            }))?;
+        } else {
+            println!("Todo list sent to {} ({} items)", to, items.len());
+        }
+        return Ok(());
+    }
+
     // Handle sticker sending
     if let Some(ref sticker_id) = args.sticker {
         if args.topic.is_some() {
@@ -112,17 +248,28 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
         }
         // Stickers always use direct connection (no socket support yet)
         let mut app = App::new(cli).await?;
-        let msg_id = app.send_sticker(args.to, sticker_id).await?;
+        let file_id = stickers::resolve_sticker(&app, sticker_id).await?;
+        let mark = crate::guard::mark();
+        let msg_id = match app.send_sticker(to, &file_id).await {
+            // An emoji resolved to a cached sticker whose reference expired:
+            // fetch it again and retry once
+            Err(e) if file_id != *sticker_id && stickers::is_expired_reference(&e) => {
+                crate::guard::note_failure(mark, &e);
+                let file_id = stickers::refresh_sticker(&app, sticker_id).await?;
+                app.send_sticker(to, &file_id).await?
+            }
+            result => result?,
+        };
 
         if cli.output.is_json() {
             out::write_json(&serde_json::json!({
                 "sent": true,
-                "to": args.to,
+                "to": to,
                 "id": msg_id,
                 "type": "sticker",
             }))?;
         } else {
-            println!("Sticker sent to {}", args.to);
+            println!("Sticker sent to {}", to);
         }
         return Ok(());
     }
@@ -134,17 +281,17 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
         }
         let mut app = App::new(cli).await?;
         let caption = args.caption.as_deref().unwrap_or("");
-        let msg_id = app.send_photo(args.to, photo_path, caption).await?;
+        let msg_id = app.send_photo(to, photo_path, caption, !args.quiet).await?;
 
         if cli.output.is_json() {
             out::write_json(&serde_json::json!({
                 "sent": true,
-                "to": args.to,
+                "to": to,
                 "id": msg_id,
                 "type": "photo",
             }))?;
         } else {
-            println!("Photo sent to {}", args.to);
+            println!("Photo sent to {}", to);
         }
         return Ok(());
     }
@@ -156,17 +303,17 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
         }
         let mut app = App::new(cli).await?;
         let caption = args.caption.as_deref().unwrap_or("");
-        let msg_id = app.send_video(args.to, video_path, caption).await?;
+        let msg_id = app.send_video(to, video_path, caption, !args.quiet).await?;
 
         if cli.output.is_json() {
             out::write_json(&serde_json::json!({
                 "sent": true,
-                "to": args.to,
+                "to": to,
                 "id": msg_id,
                 "type": "video",
             }))?;
         } else {
-            println!("Video sent to {}", args.to);
+            println!("Video sent to {}", to);
         }
         return Ok(());
     }
@@ -176,19 +323,35 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
         if args.topic.is_some() {
             anyhow::bail!("--topic is not supported with --file yet");
         }
+        if file_path == Path::new("-") {
+            if args.filename.is_none() {
+                anyhow::bail!("--filename is required with --file -");
+            }
+            if std::io::stdin().is_terminal() {
+                anyhow::bail!("--file - reads from stdin, but nothing is piped in");
+            }
+        }
         let mut app = App::new(cli).await?;
         let caption = args.caption.as_deref().unwrap_or("");
-        let msg_id = app.send_file(args.to, file_path, caption).await?;
+        let msg_id = app
+            .send_file(
+                to,
+                file_path,
+                args.filename.as_deref(),
+                caption,
+                !args.quiet,
+            )
+            .await?;
 
         if cli.output.is_json() {
             out::write_json(&serde_json::json!({
                 "sent": true,
-                "to": args.to,
+                "to": to,
                 "id": msg_id,
                 "type": "document",
             }))?;
         } else {
-            println!("File sent to {}", args.to);
+            println!("File sent to {}", to);
         }
         return Ok(());
     }
@@ -200,17 +363,17 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
         }
         let mut app = App::new(cli).await?;
         let caption = args.caption.as_deref().unwrap_or("");
-        let msg_id = app.send_voice(args.to, voice_path, caption).await?;
+        let msg_id = app.send_voice(to, voice_path, caption, !args.quiet).await?;
 
         if cli.output.is_json() {
             out::write_json(&serde_json::json!({
                 "sent": true,
-                "to": args.to,
+                "to": to,
                 "id": msg_id,
                 "type": "voice",
             }))?;
         } else {
-            println!("Voice message sent to {}", args.to);
+            println!("Voice message sent to {}", to);
         }
         return Ok(());
     }
@@ -221,12 +384,6 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
         .as_ref()
         .expect("message required when no sticker");
 
-    let parse_mode = match args.parse_mode {
-        ParseMode::Markdown => "markdown",
-        ParseMode::Html => "html",
-        ParseMode::None => "none",
-    };
-
     // Direct connection
     let mut app = App::new(cli).await?;
 
@@ -234,25 +391,25 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
         if schedule_time.is_some() {
             anyhow::bail!("--schedule/--schedule-in is not supported with --topic yet");
         }
-        app.send_text_to_topic(args.to, topic_id, message, parse_mode)
+        app.send_text_to_topic(to, topic_id, message, parse_mode, opts)
             .await?
     } else if let Some(reply_to_id) = args.reply_to {
         if schedule_time.is_some() {
             anyhow::bail!("--schedule/--schedule-in is not supported with --reply-to yet");
         }
-        app.send_text_reply(args.to, message, reply_to_id, parse_mode)
+        app.send_text_reply(to, message, reply_to_id, parse_mode, opts)
             .await?
     } else if let Some(schedule_dt) = schedule_time {
-        app.send_text_scheduled(args.to, message, schedule_dt, parse_mode)
+        app.send_text_scheduled(to, message, schedule_dt, parse_mode, opts)
             .await?
     } else {
-        app.send_text(args.to, message, parse_mode).await?
+        app.send_text(to, message, parse_mode, opts).await?
     };
 
     if cli.output.is_json() {
         let mut json = serde_json::json!({
             "sent": true,
-            "to": args.to,
+            "to": to,
             "id": msg_id,
         });
         if let Some(topic_id) = args.topic {
@@ -267,15 +424,18 @@ pub async fn run(cli: &Cli, args: &SendArgs) -> Result<()> {
         if let Some(schedule_in_secs) = args.schedule_in {
             json["scheduled_in"] = serde_json::json!(schedule_in_secs);
         }
+        if let Some(channel_id) = args.as_channel {
+            json["as_channel"] = serde_json::json!(channel_id);
+        }
         out::write_json(&json)?;
     } else if let Some(topic_id) = args.topic {
-        println!("Sent to {} topic {}", args.to, topic_id);
+        println!("Sent to {} topic {}", to, topic_id);
     } else if let Some(reply_to_id) = args.reply_to {
-        println!("Sent reply to {} (replying to {})", args.to, reply_to_id);
+        println!("Sent reply to {} (replying to {})", to, reply_to_id);
     } else if schedule_time.is_some() {
-        println!("Scheduled message to {}", args.to);
+        println!("Scheduled message to {}", to);
     } else {
-        println!("Sent to {}", args.to);
+        println!("Sent to {}", to);
     }
     Ok(())
 }
@@ -0,0 +1,93 @@
+use crate::app::payments::StarsDirection;
+use crate::app::App;
+use crate::out;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum StarsCommand {
+    /// Show your Telegram Stars balance
+    Balance,
+    /// List Stars transactions, newest first
+    Transactions {
+        /// Only Stars received
+        #[arg(long, conflicts_with = "outbound")]
+        inbound: bool,
+        /// Only Stars spent
+        #[arg(long)]
+        outbound: bool,
+        /// Limit results
+        #[arg(long, default_value = "50")]
+        limit: i32,
+        /// Continue from a `next_offset` returned by a previous page
+        #[arg(long)]
+        offset: Option<String>,
+    },
+}
+
+pub async fn run(cli: &Cli, cmd: &StarsCommand) -> Result<()> {
+    match cmd {
+        StarsCommand::Balance => {
+            let app = App::new(cli).await?;
+            let balance = app.stars_balance().await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "balance": balance }))?;
+            } else {
+                println!("{} Stars", balance);
+            }
+        }
+        StarsCommand::Transactions {
+            inbound,
+            outbound,
+            limit,
+            offset,
+        } => {
+            let direction = if *inbound {
+                StarsDirection::Inbound
+            } else if *outbound {
+                StarsDirection::Outbound
+            } else {
+                StarsDirection::All
+            };
+            let app = App::new(cli).await?;
+            let (transactions, next_offset) = app
+                .stars_transactions(direction, offset.as_deref(), *limit)
+                .await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "count": transactions.len(),
+                    "transactions": transactions,
+                    "next_offset": next_offset,
+                }))?;
+            } else if transactions.is_empty() {
+                println!("No Stars transactions.");
+            } else {
+                println!("{:<26} {:>8} {:<16} TITLE", "DATE", "STARS", "PEER");
+                for t in &transactions {
+                    let mut title = t
+                        .title
+                        .clone()
+                        .or_else(|| t.description.clone())
+                        .unwrap_or_else(|| "-".to_string());
+                    if t.refund {
+                        title.push_str(" (refund)");
+                    }
+                    println!(
+                        "{:<26} {:>+8} {:<16} {}",
+                        t.date,
+                        t.amount,
+                        t.peer,
+                        out::truncate(&title, 40)
+                    );
+                }
+                if let Some(next) = next_offset {
+                    println!("\nMore: --offset {}", next);
+                }
+            }
+        }
+    }
+    Ok(())
+}
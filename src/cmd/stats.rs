@@ -0,0 +1,355 @@
+use crate::out;
+use crate::out::heatmap::{self, Heatmap};
+use crate::store::Store;
+use crate::words::WordCounts;
+use crate::Cli;
+use anyhow::{Context, Result};
+use chrono::Local;
+use clap::{Subcommand, ValueEnum};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum StatsCommand {
+    /// Message activity by weekday and hour (local time), from the local store
+    Heatmap {
+        /// Chat ID (default: all chats)
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+        /// Only count messages after this time (RFC3339, YYYY-MM-DD, "30d" or "1 week ago")
+        #[arg(long)]
+        since: Option<String>,
+        /// Write the matrix as CSV or render it to a PNG instead of a terminal grid
+        #[arg(long, value_enum)]
+        format: Option<HeatmapFormat>,
+        /// Output file (required for png; csv defaults to stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Most frequent words and two-word phrases in stored messages
+    Words {
+        /// Chat ID (default: all chats)
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+        /// Only count messages after this time (RFC3339, YYYY-MM-DD, "30d" or "1 week ago")
+        #[arg(long)]
+        since: Option<String>,
+        /// Words and bigrams to show (per group with --by)
+        #[arg(long, default_value = "50")]
+        top: usize,
+        /// Leave out common English words ("the", "and", ...)
+        #[arg(long)]
+        exclude_stopwords: bool,
+        /// Report per sender or per forum topic instead of overall
+        #[arg(long, value_enum)]
+        by: Option<WordsBy>,
+    },
+    /// Who talks most: messages, average length, media and replies per sender
+    Senders {
+        /// Chat ID (default: all chats)
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+        /// Only count messages after this time (RFC3339, YYYY-MM-DD, "30d" or "1 week ago")
+        #[arg(long)]
+        since: Option<String>,
+        /// Max senders to show (0 = all)
+        #[arg(long, default_value = "50")]
+        limit: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HeatmapFormat {
+    Csv,
+    /// Needs a build with `--features heatmap`
+    Png,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WordsBy {
+    Sender,
+    /// Needs --chat
+    Topic,
+}
+
+#[derive(Serialize)]
+struct TermCount {
+    term: String,
+    count: u64,
+}
+
+#[derive(Serialize)]
+struct WordGroup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sender_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    topic_id: Option<i64>,
+    name: String,
+    messages: u64,
+    words: Vec<TermCount>,
+    bigrams: Vec<TermCount>,
+}
+
+/// Words and bigrams side by side, for text and markdown tables.
+#[derive(Serialize)]
+struct WordRow {
+    rank: usize,
+    word: Option<String>,
+    count: Option<u64>,
+    bigram: Option<String>,
+    bigram_count: Option<u64>,
+}
+
+fn term_counts(top: Vec<(String, u64)>) -> Vec<TermCount> {
+    top.into_iter()
+        .map(|(term, count)| TermCount { term, count })
+        .collect()
+}
+
+fn word_rows(group: &WordGroup) -> Vec<WordRow> {
+    let len = group.words.len().max(group.bigrams.len());
+    (0..len)
+        .map(|i| {
+            let word = group.words.get(i);
+            let bigram = group.bigrams.get(i);
+            WordRow {
+                rank: i + 1,
+                word: word.map(|w| w.term.clone()),
+                count: word.map(|w| w.count),
+                bigram: bigram.map(|b| b.term.clone()),
+                bigram_count: bigram.map(|b| b.count),
+            }
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct SenderRow {
+    sender_id: i64,
+    name: String,
+    messages: i64,
+    /// Percent of all messages in the range
+    share: f64,
+    avg_length: f64,
+    /// Percent of the sender's messages with media
+    media_pct: f64,
+    /// Percent of the sender's messages that reply to another message
+    reply_pct: f64,
+    /// Replies by others to this sender
+    replied_to: i64,
+    first: String,
+    last: String,
+}
+
+/// `part` as a percentage of `total`, to one decimal.
+fn percent(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (part as f64 * 1000.0 / total as f64).round() / 10.0
+}
+
+/// Count words overall or per sender/topic, busiest group first.
+async fn word_groups(
+    store: &Store,
+    chat: Option<i64>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    top: usize,
+    exclude_stopwords: bool,
+    by: Option<WordsBy>,
+) -> Result<Vec<WordGroup>> {
+    let mut counts: HashMap<i64, WordCounts> = HashMap::new();
+    let mut own: HashSet<i64> = HashSet::new();
+    for m in store.message_texts(chat, since).await? {
+        let key = match by {
+            Some(WordsBy::Sender) => m.sender_id,
+            Some(WordsBy::Topic) => m.topic_id.map(i64::from).unwrap_or(0),
+            None => 0,
+        };
+        if m.from_me {
+            own.insert(m.sender_id);
+        }
+        counts
+            .entry(key)
+            .or_default()
+            .add(&m.text, exclude_stopwords);
+    }
+
+    let names = match by {
+        Some(WordsBy::Sender) => {
+            crate::cmd::messages::sender_names(store, counts.keys().copied()).await?
+        }
+        _ => HashMap::new(),
+    };
+    let mut groups = Vec::new();
+    for (key, c) in counts {
+        let name = match (by, chat) {
+            (Some(WordsBy::Sender), _) if own.contains(&key) => "You".to_string(),
+            (Some(WordsBy::Sender), _) => names
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| format!("User {}", key)),
+            (Some(WordsBy::Topic), Some(chat)) if key != 0 => store
+                .get_topic(chat, key as i32)
+                .await?
+                .map(|t| t.name)
+                .unwrap_or_else(|| format!("Topic {}", key)),
+            (Some(WordsBy::Topic), _) => "(no topic)".to_string(),
+            (None, _) => "All messages".to_string(),
+        };
+        groups.push(WordGroup {
+            sender_id: (by == Some(WordsBy::Sender)).then_some(key),
+            topic_id: (by == Some(WordsBy::Topic)).then_some(key),
+            name,
+            messages: c.messages,
+            words: term_counts(c.top_words(top)),
+            bigrams: term_counts(c.top_bigrams(top)),
+        });
+    }
+    groups.sort_by(|a, b| b.messages.cmp(&a.messages).then(a.name.cmp(&b.name)));
+    Ok(groups)
+}
+
+pub async fn run(cli: &Cli, cmd: &StatsCommand) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    match cmd {
+        StatsCommand::Heatmap {
+            chat,
+            since,
+            format,
+            out: dest,
+        } => {
+            let since = since
+                .as_deref()
+                .map(crate::cmd::messages::parse_time)
+                .transpose()?;
+            let map = Heatmap::from_times(store.message_times(*chat, since).await?);
+
+            match format {
+                Some(HeatmapFormat::Csv) => match dest {
+                    Some(path) => {
+                        std::fs::write(path, map.to_csv())
+                            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+                        eprintln!("Wrote {} ({} messages)", path.display(), map.total());
+                    }
+                    None => print!("{}", map.to_csv()),
+                },
+                Some(HeatmapFormat::Png) => {
+                    let path = dest
+                        .as_ref()
+                        .context("--format png needs --out <file.png>")?;
+                    heatmap::write_png(&map, path)?;
+                    eprintln!("Wrote {} ({} messages)", path.display(), map.total());
+                }
+                None if cli.output.is_json() => {
+                    out::write_json(&serde_json::json!({
+                        "chat_id": chat,
+                        "total": map.total(),
+                        "weekdays": heatmap::WEEKDAYS,
+                        "counts": map.counts,
+                    }))?;
+                }
+                None => {
+                    if map.total() == 0 {
+                        println!("No messages in the local store for this range. Run `tgcli sync` first.");
+                        return Ok(());
+                    }
+                    print!("{}", map.to_text());
+                    println!("{} messages, busiest hour has {}.", map.total(), map.max());
+                }
+            }
+        }
+        StatsCommand::Words {
+            chat,
+            since,
+            top,
+            exclude_stopwords,
+            by,
+        } => {
+            if *by == Some(WordsBy::Topic) && chat.is_none() {
+                anyhow::bail!("--by topic needs --chat");
+            }
+            let since = since
+                .as_deref()
+                .map(crate::cmd::messages::parse_time)
+                .transpose()?;
+            let groups = word_groups(&store, *chat, since, *top, *exclude_stopwords, *by).await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": chat,
+                    "groups": groups,
+                }))?;
+            } else if groups.is_empty() {
+                println!("No messages in the local store for this range. Run `tgcli sync` first.");
+            } else {
+                for group in &groups {
+                    let title = format!("{} ({} messages)", group.name, group.messages);
+                    cli.output.write_titled(&word_rows(group), &title)?;
+                }
+            }
+        }
+        StatsCommand::Senders { chat, since, limit } => {
+            let since = since
+                .as_deref()
+                .map(crate::cmd::messages::parse_time)
+                .transpose()?;
+            let stats = store.sender_stats(*chat, since).await?;
+            let total: i64 = stats.iter().map(|s| s.messages).sum();
+            let shown = if *limit == 0 { stats.len() } else { *limit };
+            let names = crate::cmd::messages::sender_names(
+                &store,
+                stats.iter().take(shown).map(|s| s.sender_id),
+            )
+            .await?;
+            let rows: Vec<SenderRow> = stats
+                .iter()
+                .take(shown)
+                .map(|s| SenderRow {
+                    sender_id: s.sender_id,
+                    name: if s.from_me {
+                        "You".to_string()
+                    } else {
+                        names
+                            .get(&s.sender_id)
+                            .cloned()
+                            .unwrap_or_else(|| format!("User {}", s.sender_id))
+                    },
+                    messages: s.messages,
+                    share: percent(s.messages, total),
+                    avg_length: (s.avg_length.unwrap_or(0.0) * 10.0).round() / 10.0,
+                    media_pct: percent(s.media, s.messages),
+                    reply_pct: percent(s.replies, s.messages),
+                    replied_to: s.replied_to,
+                    first: s
+                        .first_ts
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d")
+                        .to_string(),
+                    last: s
+                        .last_ts
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d")
+                        .to_string(),
+                })
+                .collect();
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "chat_id": chat,
+                    "messages": total,
+                    "senders": stats.len(),
+                    "ranking": rows,
+                }))?;
+            } else if rows.is_empty() {
+                println!("No messages in the local store for this range. Run `tgcli sync` first.");
+            } else {
+                let title = format!("{} senders, {} messages", stats.len(), total);
+                cli.output.write_titled(&rows, &title)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,15 @@
 use crate::app::App;
 use crate::out;
 use crate::out::markdown::{format_sticker_packs, format_stickers, StickerMd, StickerPackMd};
+use crate::store::Sticker;
+use crate::tgs;
 use crate::Cli;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Subcommand;
+use grammers_mtsender::InvocationError;
 use grammers_tl_types as tl;
 use serde::Serialize;
+use std::path::{Path, PathBuf};
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum StickersCommand {
@@ -17,15 +21,40 @@ pub enum StickersCommand {
         #[arg(long)]
         pack: String,
     },
-    /// Search stickers by emoji
+    /// Search stickers by emoji and/or keyword (results are cached for `send --sticker <emoji>`)
     Search {
         /// Emoji to search for
+        #[arg(long, required_unless_present = "query")]
+        emoji: Option<String>,
+        /// Keyword to search sticker packs for (e.g. "cat")
         #[arg(long)]
-        emoji: String,
+        query: Option<String>,
         /// Limit results
         #[arg(long, default_value = "20")]
         limit: usize,
     },
+    /// Render an animated (TGS) sticker to a GIF, or one frame to a PNG
+    Render {
+        /// Sticker file_id (from `stickers show` or `stickers search`)
+        #[arg(long, required_unless_present = "input", conflicts_with = "input")]
+        file_id: Option<String>,
+        /// Local .tgs file to render instead of downloading one
+        #[arg(long)]
+        input: Option<PathBuf>,
+        /// Output path; the extension picks the format (.gif or .png)
+        #[arg(long)]
+        dest: PathBuf,
+        /// Output width and height in pixels
+        #[arg(
+            long,
+            default_value_t = tgs::DEFAULT_SIZE,
+            value_parser = clap::value_parser!(u16).range(1..)
+        )]
+        size: u16,
+        /// Frame to render for PNG output
+        #[arg(long)]
+        frame: Option<usize>,
+    },
 }
 
 #[derive(Serialize)]
@@ -39,14 +68,6 @@ struct StickerPackInfo {
     emojis: bool,
 }
 
-#[derive(Serialize)]
-struct StickerInfo {
-    emoji: String,
-    file_id: String, // Encoded as doc_id:access_hash:file_ref_base64
-    doc_id: i64,
-    animated: bool,
-}
-
 /// Encode a sticker's document info into a portable file_id string.
 /// Format: {doc_id}:{access_hash}:{file_ref_base64}
 fn encode_file_id(doc_id: i64, access_hash: i64, file_reference: &[u8]) -> String {
@@ -57,7 +78,6 @@ fn encode_file_id(doc_id: i64, access_hash: i64, file_reference: &[u8]) -> Strin
 
 /// Decode a file_id string back to its components.
 /// Returns (doc_id, access_hash, file_reference)
-#[allow(dead_code)]
 pub fn decode_file_id(file_id: &str) -> Result<(i64, i64, Vec<u8>)> {
     use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
     let parts: Vec<&str> = file_id.split(':').collect();
@@ -71,19 +91,106 @@ pub fn decode_file_id(file_id: &str) -> Result<(i64, i64, Vec<u8>)> {
 }
 
 pub async fn run(cli: &Cli, cmd: &StickersCommand) -> Result<()> {
+    // Rendering a local file doesn't need a Telegram session
+    if let StickersCommand::Render {
+        input: Some(input),
+        dest,
+        size,
+        frame,
+        ..
+    } = cmd
+    {
+        let data = std::fs::read(input)
+            .with_context(|| format!("Failed to read '{}'", input.display()))?;
+        return render_sticker(cli, &data, dest, *size, *frame);
+    }
+
     let app = App::new(cli).await?;
 
     match cmd {
         StickersCommand::List => list_sticker_packs(&app, cli).await,
         StickersCommand::Show { pack } => show_sticker_pack(&app, cli, pack).await,
-        StickersCommand::Search { emoji, limit } => search_stickers(&app, cli, emoji, *limit).await,
+        StickersCommand::Search {
+            emoji,
+            query,
+            limit,
+        } => search_stickers(&app, cli, emoji.as_deref(), query.as_deref(), *limit).await,
+        StickersCommand::Render {
+            file_id,
+            dest,
+            size,
+            frame,
+            ..
+        } => {
+            let file_id = file_id.as_deref().unwrap_or_default();
+            let data = download_sticker(&app, file_id).await?;
+            render_sticker(cli, &data, dest, *size, *frame)
+        }
     }
 }
 
+fn render_sticker(
+    cli: &Cli,
+    data: &[u8],
+    dest: &Path,
+    size: u16,
+    frame: Option<usize>,
+) -> Result<()> {
+    let result = tgs::render(data, dest, &tgs::RenderOptions { size, frame })?;
+
+    if cli.output.is_json() {
+        out::write_json(&result)?;
+    } else if !cli.output.is_none() {
+        println!(
+            "Rendered {} frame(s) at {:.0}fps ({}x{}) to {}",
+            result.frames, result.fps, result.size, result.size, result.path
+        );
+    }
+    Ok(())
+}
+
+/// Download a sticker document by file_id (upload.getFile, in 512 KiB chunks).
+async fn download_sticker(app: &App, file_id: &str) -> Result<Vec<u8>> {
+    const CHUNK: i32 = 512 * 1024;
+    let (id, access_hash, file_reference) = decode_file_id(file_id)?;
+    let location = tl::enums::InputFileLocation::InputDocumentFileLocation(
+        tl::types::InputDocumentFileLocation {
+            id,
+            access_hash,
+            file_reference,
+            thumb_size: String::new(),
+        },
+    );
+
+    let mut data = Vec::new();
+    loop {
+        let request = tl::functions::upload::GetFile {
+            precise: false,
+            cdn_supported: false,
+            location: location.clone(),
+            offset: data.len() as i64,
+            limit: CHUNK,
+        };
+        let bytes = match app.tg.invoke(&request).await? {
+            tl::enums::upload::File::File(f) => f.bytes,
+            tl::enums::upload::File::CdnRedirect(_) => {
+                anyhow::bail!("Sticker is served from a CDN, which is not supported")
+            }
+        };
+        let done = (bytes.len() as i32) < CHUNK;
+        crate::tg::transfer::throttle_download(bytes.len()).await;
+        data.extend(bytes);
+        if done {
+            break;
+        }
+    }
+    Ok(data)
+}
+
 async fn list_sticker_packs(app: &App, cli: &Cli) -> Result<()> {
     // Get all installed sticker sets
     let request = tl::functions::messages::GetAllStickers { hash: 0 };
-    let result = app.tg.client.invoke(&request).await?;
+    let result = app.tg.invoke(&request).await?;
 
     let sets = match result {
         tl::enums::messages::AllStickers::Stickers(stickers) => stickers.sets,
@@ -162,7 +269,7 @@ async fn show_sticker_pack(app: &App, cli: &Cli, pack: &str) -> Result<()> {
         hash: 0,
     };
 
-    let result = app.tg.client.invoke(&request).await?;
+    let result = app.tg.invoke(&request).await?;
 
     let (set_info, documents) = match result {
         tl::enums::messages::StickerSet::Set(s) => (s.set, s.documents),
@@ -174,38 +281,9 @@ async fn show_sticker_pack(app: &App, cli: &Cli, pack: &str) -> Result<()> {
     let tl::enums::StickerSet::Set(set_data) = &set_info;
     let set_title = set_data.title.clone();
 
-    let stickers: Vec<StickerInfo> = documents
+    let stickers: Vec<Sticker> = documents
         .into_iter()
-        .filter_map(|doc| {
-            if let tl::enums::Document::Document(d) = doc {
-                // Find the sticker attribute to get emoji
-                let emoji = d
-                    .attributes
-                    .iter()
-                    .find_map(|attr| {
-                        if let tl::enums::DocumentAttribute::Sticker(s) = attr {
-                            Some(s.alt.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or_default();
-
-                let animated = d
-                    .attributes
-                    .iter()
-                    .any(|attr| matches!(attr, tl::enums::DocumentAttribute::Animated));
-
-                Some(StickerInfo {
-                    emoji,
-                    file_id: encode_file_id(d.id, d.access_hash, &d.file_reference),
-                    doc_id: d.id,
-                    animated,
-                })
-            } else {
-                None
-            }
-        })
+        .filter_map(|doc| sticker_from_document(doc, Some(pack)))
         .collect();
 
     if cli.output.is_json() {
@@ -243,59 +321,37 @@ async fn show_sticker_pack(app: &App, cli: &Cli, pack: &str) -> Result<()> {
     Ok(())
 }
 
-async fn search_stickers(app: &App, cli: &Cli, emoji: &str, limit: usize) -> Result<()> {
-    // Search for stickers by emoji using getStickers
-    let request = tl::functions::messages::GetStickers {
-        emoticon: emoji.to_string(),
-        hash: 0,
-    };
-
-    let result = app.tg.client.invoke(&request).await?;
-
-    let documents = match result {
-        tl::enums::messages::Stickers::Stickers(s) => s.stickers,
-        tl::enums::messages::Stickers::NotModified => {
-            anyhow::bail!("Stickers not modified (unexpected)");
+async fn search_stickers(
+    app: &App,
+    cli: &Cli,
+    emoji: Option<&str>,
+    query: Option<&str>,
+    limit: usize,
+) -> Result<()> {
+    let mut stickers = match query {
+        Some(q) => {
+            let mut found = search_sticker_sets(app, q, limit).await?;
+            if let Some(e) = emoji {
+                found.retain(|s| s.emoji.contains(e));
+            }
+            found
         }
+        None => fetch_stickers_by_emoji(app, emoji.unwrap_or_default()).await?,
     };
+    stickers.truncate(limit);
+    cache_stickers(app, &stickers).await?;
 
-    let stickers: Vec<StickerInfo> = documents
-        .into_iter()
-        .take(limit)
-        .filter_map(|doc| {
-            if let tl::enums::Document::Document(d) = doc {
-                let sticker_emoji = d
-                    .attributes
-                    .iter()
-                    .find_map(|attr| {
-                        if let tl::enums::DocumentAttribute::Sticker(s) = attr {
-                            Some(s.alt.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or_else(|| emoji.to_string());
-
-                let animated = d
-                    .attributes
-                    .iter()
-                    .any(|attr| matches!(attr, tl::enums::DocumentAttribute::Animated));
-
-                Some(StickerInfo {
-                    emoji: sticker_emoji,
-                    file_id: encode_file_id(d.id, d.access_hash, &d.file_reference),
-                    doc_id: d.id,
-                    animated,
-                })
-            } else {
-                None
-            }
-        })
-        .collect();
+    let label = match (emoji, query) {
+        (Some(e), Some(q)) => format!("{} \"{}\"", e, q),
+        (Some(e), None) => e.to_string(),
+        (None, Some(q)) => format!("\"{}\"", q),
+        (None, None) => String::new(),
+    };
 
     if cli.output.is_json() {
         out::write_json(&serde_json::json!({
             "emoji": emoji,
+            "query": query,
             "count": stickers.len(),
             "stickers": stickers,
         }))?;
@@ -311,17 +367,18 @@ async fn search_stickers(app: &App, cli: &Cli, emoji: &str, limit: usize) -> Res
             .collect();
         out::write_markdown(&format_stickers(
             &stickers_md,
-            emoji,
-            &format!("Stickers for {}", emoji),
+            &label,
+            &format!("Stickers for {}", label),
         ));
     } else {
-        println!("Stickers for emoji: {}\n", emoji);
-        println!("{:<6} {:<20} FILE_ID", "EMOJI", "DOC_ID");
+        println!("Stickers for {}\n", label);
+        println!("{:<6} {:<20} {:<24} FILE_ID", "EMOJI", "DOC_ID", "PACK");
         for s in &stickers {
             println!(
-                "{:<6} {:<20} {}",
+                "{:<6} {:<20} {:<24} {}",
                 s.emoji,
                 s.doc_id,
+                out::truncate(s.set_name.as_deref().unwrap_or("-"), 22),
                 &s.file_id[..s.file_id.len().min(50)]
             );
         }
@@ -329,3 +386,164 @@ async fn search_stickers(app: &App, cli: &Cli, emoji: &str, limit: usize) -> Res
     }
     Ok(())
 }
+
+/// Resolve the `send --sticker` argument to a file_id.
+/// Accepts a file_id as-is, or an emoji which resolves to the best cached match
+/// (fetching and caching stickers for that emoji if none are cached yet).
+pub async fn resolve_sticker(app: &App, sticker: &str) -> Result<String> {
+    if sticker.contains(':') {
+        return Ok(sticker.to_string());
+    }
+
+    let store = app.get_store().await?;
+    if let Some(s) = store
+        .find_stickers_by_emoji(sticker, 1)
+        .await?
+        .into_iter()
+        .next()
+    {
+        return Ok(s.file_id);
+    }
+
+    refresh_sticker(app, sticker).await
+}
+
+/// Fetch the stickers for `emoji` again and cache them, returning the best
+/// match. Cached file references expire, so a send that fails with
+/// FILE_REFERENCE_EXPIRED retries with this.
+pub async fn refresh_sticker(app: &App, emoji: &str) -> Result<String> {
+    let fetched = fetch_stickers_by_emoji(app, emoji).await?;
+    cache_stickers(app, &fetched).await?;
+    match fetched.into_iter().next() {
+        Some(s) => Ok(s.file_id),
+        None => anyhow::bail!(
+            "No stickers found for '{}'. Try `tgcli stickers search --emoji {}`.",
+            emoji,
+            emoji
+        ),
+    }
+}
+
+/// Whether Telegram refused a cached file reference as expired.
+pub fn is_expired_reference(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| {
+        matches!(
+            e.downcast_ref::<InvocationError>(),
+            Some(InvocationError::Rpc(rpc))
+                if rpc.is("FILE_REFERENCE_EXPIRED") || rpc.is("FILE_REFERENCE_INVALID")
+        )
+    })
+}
+
+/// Stickers matching an emoji (messages.getStickers), best match first.
+async fn fetch_stickers_by_emoji(app: &App, emoji: &str) -> Result<Vec<Sticker>> {
+    let request = tl::functions::messages::GetStickers {
+        emoticon: emoji.to_string(),
+        hash: 0,
+    };
+
+    let documents = match app.tg.invoke(&request).await? {
+        tl::enums::messages::Stickers::Stickers(s) => s.stickers,
+        tl::enums::messages::Stickers::NotModified => {
+            anyhow::bail!("Stickers not modified (unexpected)");
+        }
+    };
+
+    Ok(documents
+        .into_iter()
+        .filter_map(|doc| {
+            sticker_from_document(doc, None).map(|mut s| {
+                if s.emoji.is_empty() {
+                    s.emoji = emoji.to_string();
+                }
+                s
+            })
+        })
+        .collect())
+}
+
+/// Stickers from packs matching a keyword (messages.searchStickerSets),
+/// fetching packs in result order until `limit` stickers are collected.
+async fn search_sticker_sets(app: &App, query: &str, limit: usize) -> Result<Vec<Sticker>> {
+    let request = tl::functions::messages::SearchStickerSets {
+        exclude_featured: false,
+        q: query.to_string(),
+        hash: 0,
+    };
+
+    let sets = match app.tg.invoke(&request).await? {
+        tl::enums::messages::FoundStickerSets::Sets(found) => found.sets,
+        tl::enums::messages::FoundStickerSets::NotModified => Vec::new(),
+    };
+
+    let mut stickers = Vec::new();
+    for covered in sets {
+        if stickers.len() >= limit {
+            break;
+        }
+        let tl::enums::StickerSet::Set(set) = match covered {
+            tl::enums::StickerSetCovered::Covered(c) => c.set,
+            tl::enums::StickerSetCovered::StickerSetMultiCovered(c) => c.set,
+            tl::enums::StickerSetCovered::StickerSetFullCovered(c) => c.set,
+            tl::enums::StickerSetCovered::StickerSetNoCovered(c) => c.set,
+        };
+
+        let request = tl::functions::messages::GetStickerSet {
+            stickerset: tl::enums::InputStickerSet::Id(tl::types::InputStickerSetId {
+                id: set.id,
+                access_hash: set.access_hash,
+            }),
+            hash: 0,
+        };
+        let documents = match app.tg.invoke(&request).await? {
+            tl::enums::messages::StickerSet::Set(s) => s.documents,
+            tl::enums::messages::StickerSet::NotModified => continue,
+        };
+        stickers.extend(
+            documents
+                .into_iter()
+                .filter_map(|doc| sticker_from_document(doc, Some(set.short_name.as_str()))),
+        );
+    }
+    Ok(stickers)
+}
+
+/// Remember search results so `send --sticker <emoji>` can reuse them.
+async fn cache_stickers(app: &App, stickers: &[Sticker]) -> Result<()> {
+    let store = app.get_store().await?;
+    for (rank, s) in stickers.iter().enumerate() {
+        store.upsert_sticker(s, rank as i64).await?;
+    }
+    Ok(())
+}
+
+fn sticker_from_document(doc: tl::enums::Document, set_name: Option<&str>) -> Option<Sticker> {
+    let tl::enums::Document::Document(d) = doc else {
+        return None;
+    };
+    // The sticker attribute carries the emoji
+    let emoji = d
+        .attributes
+        .iter()
+        .find_map(|attr| {
+            if let tl::enums::DocumentAttribute::Sticker(s) = attr {
+                Some(s.alt.clone())
+            } else {
+                None
+            }
+        })
+        .unwrap_or_default();
+
+    let animated = d
+        .attributes
+        .iter()
+        .any(|attr| matches!(attr, tl::enums::DocumentAttribute::Animated));
+
+    Some(Sticker {
+        doc_id: d.id,
+        file_id: encode_file_id(d.id, d.access_hash, &d.file_reference),
+        emoji,
+        set_name: set_name.map(|n| n.to_string()),
+        animated,
+    })
+}
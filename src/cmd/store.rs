@@ -0,0 +1,637 @@
+use crate::app::App;
+use crate::out;
+use crate::out::progress::Progress;
+use crate::store::{DuplicateMessage, FtsTokenizer, MediaFile, Store};
+use crate::tg::transfer;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum StoreCommand {
+    /// Re-hash downloaded media and report missing or corrupted files
+    VerifyMedia {
+        /// Only check media from this chat
+        #[arg(long, value_parser = crate::config::parse_chat)]
+        chat: Option<i64>,
+        /// Download missing and corrupted files again from Telegram
+        #[arg(long)]
+        redownload: bool,
+    },
+    /// Find media files no message refers to (and references to missing files)
+    GcMedia {
+        /// Delete orphaned files and forget missing ones (default: report only)
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Find identical texts and media re-posted across chats (e.g. spam waves)
+    DedupeReport {
+        /// Ignore texts shorter than this (short replies repeat naturally)
+        #[arg(long, default_value = "20")]
+        min_length: i64,
+        /// Only report clusters spread over at least this many chats
+        #[arg(long, default_value = "2")]
+        min_chats: usize,
+        /// List at most this many clusters, largest first (0 = all)
+        #[arg(long, default_value = "50")]
+        limit: usize,
+        /// Also write every cluster as JSON to this file
+        #[arg(long)]
+        export: Option<PathBuf>,
+        /// Delete the local copies of duplicates, keeping the earliest message
+        /// of each cluster (Telegram is not touched)
+        #[arg(long)]
+        delete: bool,
+        /// Skip the confirmation prompt for --delete
+        #[arg(long, requires = "delete")]
+        yes: bool,
+    },
+    /// Delete local messages older than their chat's retention policy
+    /// (`tgcli retention`); the daemon does this hourly
+    Prune,
+    /// Rebuild the full-text search index, optionally with another tokenizer
+    /// (trigram for Chinese, Japanese, Korean and emoji-heavy chats)
+    RebuildFts {
+        /// How message text is split into searchable tokens
+        #[arg(long, value_enum, default_value = "unicode61")]
+        tokenizer: FtsTokenizer,
+        /// Extra characters unicode61 keeps inside words (e.g. "#@" to
+        /// search hashtags and mentions whole)
+        #[arg(long)]
+        tokenchars: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum MediaStatus {
+    Missing,
+    Corrupted,
+}
+
+#[derive(Debug, Serialize)]
+struct MediaProblem {
+    chat_id: i64,
+    msg_id: i64,
+    path: String,
+    status: MediaStatus,
+    /// Whether `--redownload` replaced the file
+    redownloaded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub async fn run(cli: &Cli, cmd: &StoreCommand) -> Result<()> {
+    match cmd {
+        StoreCommand::VerifyMedia { chat, redownload } => {
+            verify_media(cli, *chat, *redownload).await
+        }
+        StoreCommand::GcMedia { apply } => gc_media(cli, *apply).await,
+        StoreCommand::DedupeReport {
+            min_length,
+            min_chats,
+            limit,
+            export,
+            delete,
+            yes,
+        } => {
+            dedupe_report(
+                cli,
+                *min_length,
+                *min_chats,
+                *limit,
+                export.as_deref(),
+                *delete,
+                *yes,
+            )
+            .await
+        }
+        StoreCommand::Prune => prune(cli).await,
+        StoreCommand::RebuildFts {
+            tokenizer,
+            tokenchars,
+        } => rebuild_fts(cli, *tokenizer, tokenchars.as_deref()).await,
+    }
+}
+
+async fn verify_media(cli: &Cli, chat: Option<i64>, redownload: bool) -> Result<()> {
+    let store_dir = cli.store_dir();
+    let store = Store::open(&store_dir).await?;
+    let media_dir = Path::new(&store_dir).join("media");
+
+    // Only files tgcli downloaded; sent files point at the sender's own paths
+    let files: Vec<MediaFile> = store
+        .list_media_files(chat)
+        .await?
+        .into_iter()
+        .filter(|f| Path::new(&f.path).starts_with(&media_dir))
+        .collect();
+
+    let bar = Progress::new(!cli.output.is_json()).items(files.len() as u64, "files");
+    let mut ok = 0usize;
+    let mut recorded = 0usize;
+    let mut problems = Vec::new();
+    for file in &files {
+        bar.inc(1);
+        let path = Path::new(&file.path);
+        let status = match transfer::sha256_file(path) {
+            Err(_) if !path.exists() => MediaStatus::Missing,
+            Err(e) => {
+                log::warn!("Failed to read {}: {}", file.path, e);
+                MediaStatus::Corrupted
+            }
+            Ok(actual) => match &file.sha256 {
+                Some(expected) if *expected == actual => {
+                    ok += 1;
+                    continue;
+                }
+                Some(_) => MediaStatus::Corrupted,
+                None => {
+                    // Downloaded before checksums were recorded: take it as the baseline
+                    store
+                        .set_media_file(file.chat_id, file.msg_id, None, &file.path, &actual)
+                        .await?;
+                    recorded += 1;
+                    continue;
+                }
+            },
+        };
+        problems.push(MediaProblem {
+            chat_id: file.chat_id,
+            msg_id: file.msg_id,
+            path: file.path.clone(),
+            status,
+            redownloaded: false,
+            error: None,
+        });
+    }
+    bar.finish_and_clear();
+
+    if redownload && !problems.is_empty() {
+        let app = App::new(cli).await?;
+        for problem in &mut problems {
+            match app
+                .redownload_message_media(problem.chat_id, problem.msg_id)
+                .await
+            {
+                Ok(saved) => {
+                    problem.redownloaded = true;
+                    if let Some(path) = saved.path {
+                        problem.path = path;
+                    }
+                }
+                Err(e) => problem.error = Some(format!("{:#}", e)),
+            }
+        }
+    }
+
+    let unresolved = problems.iter().filter(|p| !p.redownloaded).count();
+    let count = |status| problems.iter().filter(|p| p.status == status).count();
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "checked": files.len(),
+            "ok": ok,
+            "recorded": recorded,
+            "missing": count(MediaStatus::Missing),
+            "corrupted": count(MediaStatus::Corrupted),
+            "redownloaded": problems.len() - unresolved,
+            "problems": problems,
+        }))?;
+    } else {
+        for p in &problems {
+            let status = match p.status {
+                MediaStatus::Missing => "missing",
+                MediaStatus::Corrupted => "corrupted",
+            };
+            let outcome = match (&p.error, p.redownloaded) {
+                (_, true) => " (re-downloaded)".to_string(),
+                (Some(e), _) => format!(" (re-download failed: {})", e),
+                (None, false) => String::new(),
+            };
+            println!(
+                "{:<10} chat {} msg {}  {}{}",
+                status, p.chat_id, p.msg_id, p.path, outcome
+            );
+        }
+        println!(
+            "Checked {} files: {} ok, {} missing, {} corrupted.",
+            files.len(),
+            ok,
+            count(MediaStatus::Missing),
+            count(MediaStatus::Corrupted)
+        );
+        if recorded > 0 {
+            println!("Recorded checksums for {} files that had none.", recorded);
+        }
+        if unresolved > 0 && !redownload {
+            println!("Run with --redownload to fetch them again from Telegram.");
+        }
+    }
+
+    if unresolved > 0 {
+        anyhow::bail!("{} media files failed verification", unresolved);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct OrphanFile {
+    path: String,
+    size: u64,
+}
+
+async fn gc_media(cli: &Cli, apply: bool) -> Result<()> {
+    let store_dir = cli.store_dir();
+    let store = Store::open(&store_dir).await?;
+    let media_dir = Path::new(&store_dir).join("media");
+
+    // Match by path under media/, so paths recorded before the store moved
+    // still count; a file whose name is referenced anywhere is kept too
+    let referenced = store.list_media_files(None).await?;
+    let referenced_paths: HashSet<PathBuf> = referenced
+        .iter()
+        .filter_map(|f| media_relative(Path::new(&f.path), &media_dir))
+        .collect();
+    let referenced_names: HashSet<&std::ffi::OsStr> = referenced
+        .iter()
+        .filter_map(|f| Path::new(&f.path).file_name())
+        .collect();
+
+    let mut on_disk = Vec::new();
+    if media_dir.is_dir() {
+        collect_files(&media_dir, &mut on_disk)?;
+    }
+    let orphans: Vec<OrphanFile> = on_disk
+        .into_iter()
+        .filter(|path| {
+            path.strip_prefix(&media_dir)
+                .is_ok_and(|rel| !referenced_paths.contains(rel))
+                && path
+                    .file_name()
+                    .is_some_and(|name| !referenced_names.contains(name))
+        })
+        .map(|path| OrphanFile {
+            size: path.metadata().map(|m| m.len()).unwrap_or(0),
+            path: path.to_string_lossy().to_string(),
+        })
+        .collect();
+    let reclaimable: u64 = orphans.iter().map(|o| o.size).sum();
+
+    // Only files under media/ are tgcli's to manage
+    let dangling: Vec<&MediaFile> = referenced
+        .iter()
+        .filter(|f| Path::new(&f.path).starts_with(&media_dir) && !Path::new(&f.path).exists())
+        .collect();
+
+    if apply {
+        for orphan in &orphans {
+            if let Err(e) = std::fs::remove_file(&orphan.path) {
+                log::warn!("Failed to delete {}: {}", orphan.path, e);
+            }
+        }
+        remove_empty_dirs(&media_dir);
+        for file in &dangling {
+            store.clear_media_file(file.chat_id, file.msg_id).await?;
+        }
+    }
+
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "applied": apply,
+            "orphaned_files": orphans,
+            "reclaimable_bytes": reclaimable,
+            "missing_files": dangling,
+        }))?;
+        return Ok(());
+    }
+
+    for orphan in &orphans {
+        println!(
+            "orphaned  {}  ({})",
+            orphan.path,
+            out::format_size(orphan.size)
+        );
+    }
+    for file in &dangling {
+        println!(
+            "missing   chat {} msg {}  {}",
+            file.chat_id, file.msg_id, file.path
+        );
+    }
+    if apply {
+        println!(
+            "Deleted {} orphaned files ({}), forgot {} missing files.",
+            orphans.len(),
+            out::format_size(reclaimable),
+            dangling.len()
+        );
+    } else {
+        println!(
+            "{} orphaned files ({} reclaimable), {} references to missing files.",
+            orphans.len(),
+            out::format_size(reclaimable),
+            dangling.len()
+        );
+        if !orphans.is_empty() || !dangling.is_empty() {
+            println!("Run with --apply to clean up.");
+        }
+    }
+    Ok(())
+}
+
+/// What applying one chat's retention policy deleted.
+#[derive(Debug, Serialize)]
+pub(crate) struct Pruned {
+    pub chat_id: i64,
+    pub messages: u64,
+    pub media_files: usize,
+    pub media_bytes: u64,
+}
+
+/// Apply every retention policy: delete local messages older than the
+/// policy and, with `delete_media`, their downloaded files.
+pub(crate) async fn apply_retention(store: &Store, store_dir: &str) -> Result<Vec<Pruned>> {
+    let media_dir = Path::new(store_dir).join("media");
+    let now = chrono::Utc::now();
+    let mut pruned = Vec::new();
+    for policy in store.list_retention_policies().await? {
+        let before = now - chrono::Duration::days(policy.keep_days);
+        let mut media_files = 0;
+        let mut media_bytes = 0;
+        if policy.delete_media {
+            let files = store.media_files_before(policy.chat_id, before).await?;
+            // Only files under media/ are tgcli's to delete
+            for file in files
+                .iter()
+                .filter(|f| Path::new(&f.path).starts_with(&media_dir))
+            {
+                let size = Path::new(&file.path)
+                    .metadata()
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                match std::fs::remove_file(&file.path) {
+                    Ok(()) => {
+                        media_files += 1;
+                        media_bytes += size;
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => log::warn!("Failed to delete {}: {}", file.path, e),
+                }
+            }
+        }
+        let messages = store.delete_messages_before(policy.chat_id, before).await?;
+        if messages > 0 {
+            store.record_prune(policy.chat_id, messages, now).await?;
+        }
+        pruned.push(Pruned {
+            chat_id: policy.chat_id,
+            messages,
+            media_files,
+            media_bytes,
+        });
+    }
+    if pruned.iter().any(|p| p.media_files > 0) {
+        remove_empty_dirs(&media_dir);
+    }
+    Ok(pruned)
+}
+
+async fn prune(cli: &Cli) -> Result<()> {
+    let store_dir = cli.store_dir();
+    let store = Store::open(&store_dir).await?;
+    let pruned = apply_retention(&store, &store_dir).await?;
+
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({ "chats": pruned }))?;
+        return Ok(());
+    }
+    if pruned.is_empty() {
+        println!("No retention policies. Add one with `tgcli retention set`.");
+        return Ok(());
+    }
+    for p in &pruned {
+        println!(
+            "chat {}  {} messages, {} media files ({})",
+            p.chat_id,
+            p.messages,
+            p.media_files,
+            out::format_size(p.media_bytes)
+        );
+    }
+    Ok(())
+}
+
+async fn rebuild_fts(cli: &Cli, tokenizer: FtsTokenizer, tokenchars: Option<&str>) -> Result<()> {
+    if tokenchars.is_some() && tokenizer != FtsTokenizer::Unicode61 {
+        anyhow::bail!("--tokenchars only applies to --tokenizer unicode61");
+    }
+    let store_dir = cli.store_dir();
+    // The daemon indexes new messages through the triggers being replaced
+    crate::lock::acquire(&store_dir, cli.wait).await?;
+    let mut store = Store::open(&store_dir).await?;
+    eprintln!("Rebuilding the search index...");
+    let indexed = store.rebuild_fts(tokenizer, tokenchars).await?;
+
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "tokenizer": tokenizer,
+            "tokenchars": tokenchars,
+            "indexed": indexed,
+        }))?;
+        return Ok(());
+    }
+    println!(
+        "Indexed {} messages with the {} tokenizer.",
+        indexed,
+        tokenizer.name()
+    );
+    if tokenizer == FtsTokenizer::Trigram {
+        println!("Search terms shorter than 3 characters fall back to a slower full scan.");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct DuplicateCluster {
+    kind: &'static str,
+    /// Normalized text, or the SHA-256 of the media file
+    key: String,
+    chats: usize,
+    /// Earliest first; that one is kept by --delete
+    messages: Vec<ClusterMessage>,
+}
+
+#[derive(Debug, Serialize)]
+struct ClusterMessage {
+    chat_id: i64,
+    msg_id: i64,
+    ts: String,
+}
+
+/// Group duplicates (ordered by kind and key) into clusters, largest first.
+fn duplicate_clusters(dups: Vec<DuplicateMessage>, min_chats: usize) -> Vec<DuplicateCluster> {
+    let mut clusters: Vec<DuplicateCluster> = Vec::new();
+    for dup in dups {
+        let message = ClusterMessage {
+            chat_id: dup.chat_id,
+            msg_id: dup.msg_id,
+            ts: dup.ts.to_rfc3339(),
+        };
+        match clusters.last_mut() {
+            Some(c) if c.kind == dup.kind && c.key == dup.key => c.messages.push(message),
+            _ => clusters.push(DuplicateCluster {
+                kind: dup.kind,
+                key: dup.key,
+                chats: 0,
+                messages: vec![message],
+            }),
+        }
+    }
+    for c in &mut clusters {
+        let chats: HashSet<i64> = c.messages.iter().map(|m| m.chat_id).collect();
+        c.chats = chats.len();
+    }
+    clusters.retain(|c| c.chats >= min_chats);
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.messages.len()));
+    clusters
+}
+
+async fn dedupe_report(
+    cli: &Cli,
+    min_length: i64,
+    min_chats: usize,
+    limit: usize,
+    export: Option<&Path>,
+    delete: bool,
+    yes: bool,
+) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+    let clusters = duplicate_clusters(store.duplicate_messages(min_length).await?, min_chats);
+    let duplicates: usize = clusters.iter().map(|c| c.messages.len() - 1).sum();
+
+    if let Some(path) = export {
+        std::fs::write(path, out::redact::to_json_pretty(&clusters)?)?;
+    }
+
+    let mut deleted = 0usize;
+    if delete && duplicates > 0 {
+        let label = format!(
+            "Delete {} duplicate messages from the local database?",
+            duplicates
+        );
+        if !yes && !crate::cmd::chats::confirm(&label)? {
+            anyhow::bail!("Aborted");
+        }
+        for c in &clusters {
+            for m in c.messages.iter().skip(1) {
+                // A message can be in a text and a media cluster
+                if store.delete_message(m.chat_id, m.msg_id).await? {
+                    deleted += 1;
+                }
+            }
+        }
+    }
+
+    let shown = if limit > 0 {
+        &clusters[..clusters.len().min(limit)]
+    } else {
+        &clusters[..]
+    };
+    if cli.output.is_json() {
+        out::write_json(&serde_json::json!({
+            "clusters": clusters.len(),
+            "duplicates": duplicates,
+            "deleted": deleted,
+            "shown": shown,
+        }))?;
+        return Ok(());
+    }
+
+    for c in shown {
+        println!(
+            "{:<5}  {} messages in {} chats  {}",
+            c.kind,
+            c.messages.len(),
+            c.chats,
+            out::truncate(&out::redact::text(&c.key).replace('\n', " "), 60)
+        );
+        for m in &c.messages {
+            println!("         chat {} msg {}  {}", m.chat_id, m.msg_id, m.ts);
+        }
+    }
+    println!(
+        "{} clusters, {} duplicate messages.",
+        clusters.len(),
+        duplicates
+    );
+    if let Some(path) = export {
+        println!("Wrote the clusters to {}.", path.display());
+    }
+    if delete {
+        println!("Deleted {} local duplicates.", deleted);
+    } else if duplicates > 0 {
+        println!("Run with --delete to remove the local duplicates.");
+    }
+    Ok(())
+}
+
+/// `path` relative to the store's `media/` directory. Paths recorded under
+/// another store location are matched on what follows their own `media/`.
+fn media_relative(path: &Path, media_dir: &Path) -> Option<PathBuf> {
+    if let Ok(rel) = path.strip_prefix(media_dir) {
+        return Some(rel.to_path_buf());
+    }
+    let components: Vec<_> = path.components().collect();
+    let last = components.len().checked_sub(1)?;
+    let media = components[..last]
+        .iter()
+        .rposition(|c| c.as_os_str() == "media")?;
+    Some(components[media + 1..].iter().collect())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Remove per-chat directories left empty after deleting files.
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                // Fails (and is ignored) unless the directory is empty
+                let _ = std::fs::remove_dir(&path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_paths_match_across_store_moves() {
+        let media_dir = Path::new("/home/me/.local/share/tgcli/media");
+        assert_eq!(
+            media_relative(&media_dir.join("7/10.jpg"), media_dir),
+            Some(PathBuf::from("7/10.jpg"))
+        );
+        assert_eq!(
+            media_relative(Path::new("/home/me/.tgcli/media/7/10.jpg"), media_dir),
+            Some(PathBuf::from("7/10.jpg"))
+        );
+        assert_eq!(media_relative(Path::new("/tmp/photo.jpg"), media_dir), None);
+    }
+}
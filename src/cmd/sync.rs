@@ -1,8 +1,13 @@
+use crate::app::folder::Folder;
+use crate::app::sync::{BackfillResult, SkipKind, SyncOptions, SyncResult};
 use crate::app::App;
+use crate::config::Config;
 use crate::out;
+use crate::store::{ChatSort, Store};
 use crate::Cli;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
+use regex::Regex;
 
 /// Common flags for all sync operations
 #[derive(Args, Debug, Clone)]
@@ -12,9 +17,13 @@ pub struct CommonSyncArgs {
     pub output: out::OutputMode,
 
     /// Download media files
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, overrides_with = "no_download_media")]
     pub download_media: bool,
 
+    /// Don't download media, even if config.json says to
+    #[arg(long, default_value_t = false, overrides_with = "download_media")]
+    pub no_download_media: bool,
+
     /// Automatically mark incoming messages as read
     #[arg(long, default_value_t = false)]
     pub mark_read: bool,
@@ -24,14 +33,18 @@ pub struct CommonSyncArgs {
     pub stream: bool,
 
     /// Chat IDs to ignore (skip during sync)
-    #[arg(long = "ignore", value_name = "CHAT_ID")]
+    #[arg(long = "ignore", value_name = "CHAT_ID", value_parser = crate::config::parse_chat)]
     pub ignore_chat_ids: Vec<i64>,
 
     /// Skip all channels
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, overrides_with = "no_ignore_channels")]
     pub ignore_channels: bool,
 
-    /// Suppress progress output
+    /// Sync channels, even if config.json skips them
+    #[arg(long, default_value_t = false, overrides_with = "ignore_channels")]
+    pub no_ignore_channels: bool,
+
+    /// Suppress progress bars (always off when stderr is not a terminal)
     #[arg(long, default_value_t = false)]
     pub no_progress: bool,
 
@@ -52,12 +65,50 @@ pub struct CommonSyncArgs {
     pub prune_after: Option<usize>,
 
     /// Skip archived chats entirely (don't fetch dialogs or messages from archived folder)
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, overrides_with = "no_skip_archived")]
     pub skip_archived: bool,
 
+    /// Sync archived chats, even if config.json skips them
+    #[arg(long, default_value_t = false, overrides_with = "skip_archived")]
+    pub no_skip_archived: bool,
+
     /// Sync ONLY archived chats (opposite of --skip-archived)
     #[arg(long, default_value_t = false, conflicts_with = "skip_archived")]
     pub archived_only: bool,
+
+    /// Add contact cards shared in messages to your local contacts
+    #[arg(
+        long,
+        default_value_t = false,
+        overrides_with = "no_save_shared_contacts"
+    )]
+    pub save_shared_contacts: bool,
+
+    /// Don't add shared contact cards, even if config.json says to
+    #[arg(long, default_value_t = false, overrides_with = "save_shared_contacts")]
+    pub no_save_shared_contacts: bool,
+
+    /// Sync only the chats in this folder (by name or ID), and record them
+    /// for `tgcli chats list --in-folder`
+    #[arg(long, value_name = "NAME")]
+    pub folder: Option<String>,
+
+    /// Skip these kinds of chats without fetching their messages
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KIND")]
+    pub skip_kind: Vec<SkipKind>,
+
+    /// Skip chats whose name matches this regex (e.g. "(?i)crypto")
+    #[arg(long, value_parser = parse_regex, value_name = "REGEX")]
+    pub skip_name_regex: Option<Regex>,
+
+    /// After every chat has its recent messages, fetch up to N older ones per
+    /// chat, most active chats first (default N: 1000; not for `sync chats`)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1000")]
+    pub backfill: Option<usize>,
+}
+
+fn parse_regex(s: &str) -> Result<Regex, String> {
+    Regex::new(s).map_err(|e| e.to_string())
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -73,8 +124,25 @@ pub enum SyncCommand {
         common: CommonSyncArgs,
 
         /// Sync only this specific chat (by chat ID)
-        #[arg(long, value_name = "CHAT_ID")]
+        #[arg(long, value_parser = crate::config::parse_chat, value_name = "CHAT_ID")]
+        chat: Option<i64>,
+    },
+    /// Sync messages again for just the chats whose last sync failed
+    RetryFailed {
+        #[command(flatten)]
+        common: CommonSyncArgs,
+    },
+    /// Per-chat sync progress: last sync, message and backfill cursors,
+    /// media not yet downloaded, and errors from the last run (local DB)
+    Status {
+        /// Only this chat
+        #[arg(long, value_parser = crate::config::parse_chat, value_name = "CHAT_ID")]
         chat: Option<i64>,
+        /// Only chats whose last sync failed
+        #[arg(long)]
+        errors: bool,
+        #[arg(long, default_value = "100")]
+        limit: i64,
     },
 }
 
@@ -94,47 +162,146 @@ fn build_output_mode(common: &CommonSyncArgs) -> crate::app::sync::OutputMode {
         // Use sync-specific --output flag (defaults to none for summary-only output)
         match common.output {
             crate::out::OutputMode::Json => crate::app::sync::OutputMode::Json,
-            crate::out::OutputMode::Text => crate::app::sync::OutputMode::Text,
+            crate::out::OutputMode::Text
+            | crate::out::OutputMode::Llm
+            | crate::out::OutputMode::Sqlite(_) => crate::app::sync::OutputMode::Text,
             crate::out::OutputMode::Markdown => crate::app::sync::OutputMode::Text, // Markdown falls back to text for sync
             crate::out::OutputMode::None => crate::app::sync::OutputMode::None,
         }
     }
 }
 
-fn build_sync_options(common: &CommonSyncArgs) -> crate::app::sync::SyncOptions {
+/// A config default, unless `--flag` or `--no-flag` says otherwise.
+fn flag_or_config(on: bool, off: bool, config: bool) -> bool {
+    on || (config && !off)
+}
+
+fn build_sync_options(common: &CommonSyncArgs, config: &Config) -> crate::app::sync::SyncOptions {
     let output_mode = build_output_mode(common);
+    let mut ignore_chat_ids = common.ignore_chat_ids.clone();
+    ignore_chat_ids.extend(&config.sync.ignore_chat_ids);
 
     crate::app::sync::SyncOptions {
         output: output_mode,
         mark_read: common.mark_read,
-        download_media: common.download_media,
-        ignore_chat_ids: common.ignore_chat_ids.clone(),
-        ignore_channels: common.ignore_channels,
+        download_media: flag_or_config(
+            common.download_media,
+            common.no_download_media,
+            config.sync.download_media,
+        ),
+        ignore_chat_ids,
+        ignore_channels: flag_or_config(
+            common.ignore_channels,
+            common.no_ignore_channels,
+            config.sync.ignore_channels,
+        ),
         show_progress: !common.no_progress,
         incremental: true, // Always incremental
         messages_per_chat: common.messages_per_chat,
         concurrency: common.concurrency,
-        chat_filter: None,
+        chat_filter: Vec::new(),
         prune_after: common.prune_after,
-        skip_archived: common.skip_archived,
+        skip_archived: flag_or_config(
+            common.skip_archived,
+            common.no_skip_archived,
+            config.sync.skip_archived,
+        ) && !common.archived_only,
         archived_only: common.archived_only,
+        save_shared_contacts: flag_or_config(
+            common.save_shared_contacts,
+            common.no_save_shared_contacts,
+            config.sync.save_shared_contacts,
+        ),
+        folder: None,
+        skip_kinds: common.skip_kind.clone(),
+        skip_name: common.skip_name_regex.clone(),
+    }
+}
+
+/// Resolve `--folder` into the sync options.
+async fn apply_folder(app: &App, common: &CommonSyncArgs, opts: &mut SyncOptions) -> Result<()> {
+    if let Some(name) = &common.folder {
+        let folder = app.find_folder(name).await?;
+        if folder.exclude_archived {
+            opts.skip_archived = true;
+        }
+        opts.folder = Some(folder);
     }
+    Ok(())
+}
+
+/// Record which local chats belong to the synced folder.
+async fn record_folder(app: &App, folder: Option<&Folder>) -> Result<()> {
+    let Some(folder) = folder else {
+        return Ok(());
+    };
+    let store = app.get_store().await?;
+    let chat_ids: Vec<i64> = store
+        .list_chats(None, i64::MAX, None, false, None, ChatSort::Recent)
+        .await?
+        .into_iter()
+        .filter(|c| folder.contains(c.id, &c.kind))
+        .map(|c| c.id)
+        .collect();
+    store
+        .set_folder_chats(folder.id, &folder.title, &chat_ids)
+        .await
+}
+
+/// Report the recent messages, then run the `--backfill` phase if asked to.
+/// The backfill runs as its own task, so the recent messages are already
+/// committed and reported when it starts and a Ctrl+C only stops the backfill.
+async fn finish_sync(
+    app: App,
+    common: &CommonSyncArgs,
+    opts: SyncOptions,
+    result: &SyncResult,
+    mode_str: &str,
+) -> Result<()> {
+    let Some(per_chat) = common.backfill else {
+        print_sync_result(common, result, mode_str, None);
+        return Ok(());
+    };
+    // JSON gets one object with both phases; text reports each as it ends
+    if !common.output.is_json() {
+        print_sync_result(common, result, mode_str, None);
+        eprintln!(
+            "Backfilling older history (up to {} messages per chat, most active chats first)...",
+            per_chat
+        );
+    }
+    let backfill = tokio::spawn(async move { app.backfill_history(&opts, per_chat).await })
+        .await
+        .context("Backfill task failed")??;
+    if common.output.is_json() {
+        print_sync_result(common, result, mode_str, Some(&backfill));
+    } else {
+        eprintln!(
+            "Backfill complete: {} older messages from {} chats",
+            backfill.messages, backfill.chats
+        );
+    }
+    Ok(())
 }
 
 fn print_sync_result(
     common: &CommonSyncArgs,
-    result: &crate::app::sync::SyncResult,
+    result: &SyncResult,
     mode_str: &str,
+    backfill: Option<&BackfillResult>,
 ) {
     if common.output.is_json() {
-        out::write_json(&serde_json::json!({
+        let mut json = serde_json::json!({
             "synced": true,
             "messages_stored": result.messages_stored,
             "chats_stored": result.chats_stored,
             "mode": mode_str,
             "per_chat": result.per_chat,
-        }))
-        .ok();
+        });
+        if let Some(backfill) = backfill {
+            json["backfill"] = serde_json::json!(backfill);
+        }
+        out::write_json(&json).ok();
     } else if common.quiet {
         eprintln!(
             "Sync complete ({}). Messages: {}, Chats: {}",
@@ -217,28 +384,78 @@ fn print_sync_result(
 }
 
 pub async fn run(cli: &Cli, args: &SyncArgs) -> Result<()> {
+    let config = Config::load(&cli.config_dir())?;
     match &args.command {
+        Some(SyncCommand::Status {
+            chat,
+            errors,
+            limit,
+        }) => {
+            let store = Store::open(&cli.store_dir()).await?;
+            let statuses = store.sync_status(*chat, *errors, *limit).await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "chats": statuses }))?;
+            } else if statuses.is_empty() {
+                println!(
+                    "No synced chats{}.",
+                    if *errors { " with errors" } else { "" }
+                );
+            } else {
+                cli.output.write(&statuses)?;
+            }
+        }
         Some(SyncCommand::Chats { common }) => {
             // Sync chats only (no messages)
             let mut app = App::new(cli).await?;
-            let opts = build_sync_options(common);
+            let mut opts = build_sync_options(common, &config);
+            apply_folder(&app, common, &mut opts).await?;
+            let folder = opts.folder.clone();
             let result = app.sync_chats(opts).await?;
-            print_sync_result(common, &result, "chats-only");
+            record_folder(&app, folder.as_ref()).await?;
+            print_sync_result(common, &result, "chats-only", None);
+        }
+        Some(SyncCommand::RetryFailed { common }) => {
+            let failed = Store::open(&cli.store_dir())
+                .await?
+                .list_sync_errors()
+                .await?;
+            if failed.is_empty() {
+                eprintln!("No failed chats to retry.");
+                return Ok(());
+            }
+            let mut app = App::new(cli).await?;
+            let mut opts = build_sync_options(common, &config);
+            opts.chat_filter = failed.iter().map(|e| e.chat_id).collect();
+            apply_folder(&app, common, &mut opts).await?;
+            let result = app.sync_msgs(opts).await?;
+            print_sync_result(common, &result, "retry-failed", None);
+            let still_failing = app.get_store().await?.list_sync_errors().await?.len();
+            if still_failing > 0 && !common.output.is_json() {
+                eprintln!(
+                    "{} of {} chats still failing; see `tgcli sync status --errors`.",
+                    still_failing,
+                    failed.len()
+                );
+            }
         }
         Some(SyncCommand::Msgs { common, chat }) => {
             // Sync messages only from local chats (uses stored access_hash, no iter_dialogs)
             let mut app = App::new(cli).await?;
-            let mut opts = build_sync_options(common);
-            opts.chat_filter = *chat;
-            let result = app.sync_msgs(opts).await?;
-            print_sync_result(common, &result, "msgs-only");
+            let mut opts = build_sync_options(common, &config);
+            opts.chat_filter = chat.iter().copied().collect();
+            apply_folder(&app, common, &mut opts).await?;
+            let result = app.sync_msgs(opts.clone()).await?;
+            record_folder(&app, opts.folder.as_ref()).await?;
+            finish_sync(app, common, opts, &result, "msgs-only").await?;
         }
         None => {
             // Default: sync both chats and messages
             let mut app = App::new(cli).await?;
-            let opts = build_sync_options(&args.common);
-            let result = app.sync(opts).await?;
-            print_sync_result(&args.common, &result, "incremental");
+            let mut opts = build_sync_options(&args.common, &config);
+            apply_folder(&app, &args.common, &mut opts).await?;
+            let result = app.sync(opts.clone()).await?;
+            record_folder(&app, opts.folder.as_ref()).await?;
+            finish_sync(app, &args.common, opts, &result, "incremental").await?;
         }
     }
 
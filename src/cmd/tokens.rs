@@ -0,0 +1,98 @@
+use crate::guard::{self, Scope};
+use crate::out;
+use crate::store::Store;
+use crate::Cli;
+use anyhow::Result;
+use clap::Subcommand;
+use rand::Rng;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TokensCommand {
+    /// Create a token for automation; pass it with `--token` or TGCLI_TOKEN
+    Create {
+        /// Name to recognize (and revoke) the token by
+        name: String,
+        /// Granted scope, repeatable: *, read:<messages|chats|contacts|account|metrics|*>,
+        /// send:chat:<id>, send:*, write:chat:<id>, write:*
+        #[arg(long = "scope", required = true)]
+        scopes: Vec<Scope>,
+    },
+    /// List tokens with their scopes and last use
+    List,
+    /// Revoke a token
+    Revoke { name: String },
+}
+
+/// Apply `--token` to this process: its scopes limit everything that follows.
+pub async fn activate(cli: &Cli, token: &str) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+    let Some(record) = store.api_token_by_hash(&guard::token_hash(token)).await? else {
+        anyhow::bail!("Unknown or revoked API token");
+    };
+    let scopes = record
+        .scopes
+        .iter()
+        .map(|s| s.parse::<Scope>().map_err(anyhow::Error::msg))
+        .collect::<Result<Vec<_>>>()?;
+    store.touch_api_token(record.id, chrono::Utc::now()).await?;
+    guard::restrict(scopes);
+    Ok(())
+}
+
+pub async fn run(cli: &Cli, cmd: &TokensCommand) -> Result<()> {
+    let store = Store::open(&cli.store_dir()).await?;
+
+    match cmd {
+        TokensCommand::Create { name, scopes } => {
+            let secret: [u8; 24] = rand::rng().random();
+            let token = format!(
+                "tgcli_{}",
+                secret
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>()
+            );
+            let scopes: Vec<String> = scopes.iter().map(Scope::to_string).collect();
+            let id = store
+                .add_api_token(name, &guard::token_hash(&token), &scopes)
+                .await?;
+
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({
+                    "id": id,
+                    "name": name,
+                    "scopes": scopes,
+                    "token": token,
+                }))?;
+            } else {
+                println!("{}", token);
+                eprintln!(
+                    "Created token '{}' ({}). It is shown only once.",
+                    name,
+                    scopes.join(" ")
+                );
+            }
+        }
+        TokensCommand::List => {
+            let tokens = store.list_api_tokens().await?;
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "tokens": tokens }))?;
+            } else if tokens.is_empty() {
+                println!("No tokens. Create one with `tgcli tokens create <name> --scope ...`.");
+            } else {
+                cli.output.write(&tokens)?;
+            }
+        }
+        TokensCommand::Revoke { name } => {
+            if !store.remove_api_token(name).await? {
+                anyhow::bail!("No token named '{}'", name);
+            }
+            if cli.output.is_json() {
+                out::write_json(&serde_json::json!({ "revoked": name }))?;
+            } else {
+                println!("Revoked token '{}'.", name);
+            }
+        }
+    }
+    Ok(())
+}
@@ -11,7 +11,7 @@ pub enum TopicsCommand {
     /// List topics in a forum group
     List {
         /// Chat ID (must be a forum group)
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Sync topics from Telegram before listing
         #[arg(long)]
@@ -20,7 +20,7 @@ pub enum TopicsCommand {
     /// Show messages from a specific topic
     Messages {
         /// Chat ID
-        #[arg(long)]
+        #[arg(long, value_parser = crate::config::parse_chat)]
         chat: i64,
         /// Topic ID
         #[arg(long)]
@@ -109,7 +109,7 @@ pub async fn run(cli: &Cli, cmd: &TopicsCommand) -> Result<()> {
                 .map(|t| t.name.as_str())
                 .unwrap_or("Unknown");
 
-            let msgs = store
+            let mut msgs = store
                 .list_messages(store::ListMessagesParams {
                     chat_id: Some(*chat),
                     topic_id: Some(*topic),
@@ -118,8 +118,11 @@ pub async fn run(cli: &Cli, cmd: &TopicsCommand) -> Result<()> {
                     before: before_ts,
                     ignore_chats: Vec::new(),
                     ignore_channels: false,
+                    with_location: false,
+                    cursor: None,
                 })
                 .await?;
+            msgs.iter_mut().for_each(out::redact::message);
 
             if cli.output.is_json() {
                 out::write_json(&serde_json::json!({
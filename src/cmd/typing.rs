@@ -7,7 +7,7 @@ use clap::Args;
 #[derive(Args, Debug, Clone)]
 pub struct TypingArgs {
     /// Chat ID
-    #[arg(long)]
+    #[arg(long, value_parser = crate::config::parse_chat)]
     pub chat: i64,
 
     /// Topic ID (for forum groups)
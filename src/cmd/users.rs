@@ -67,7 +67,6 @@ async fn show_user(cli: &Cli, user_id: i64) -> Result<()> {
 
     let result = app
         .tg
-        .client
         .invoke(&request)
         .await
         .with_context(|| format!("Failed to get user info for {}", user_id))?;
@@ -111,7 +110,10 @@ async fn show_user(cli: &Cli, user_id: i64) -> Result<()> {
             last_name: info.last_name.clone(),
             username: info.username.clone(),
             phone: info.phone.clone(),
-            bio: info.bio.clone(),
+            bio: info
+                .bio
+                .as_deref()
+                .map(|b| out::redact::text(b).into_owned()),
             is_bot: info.is_bot,
             is_verified: info.is_verified,
             is_premium: info.is_premium,
@@ -136,10 +138,10 @@ async fn show_user(cli: &Cli, user_id: i64) -> Result<()> {
             println!("Username: @{}", u);
         }
         if let Some(p) = &info.phone {
-            println!("Phone: +{}", p);
+            println!("Phone: {}", out::redact::phone(&format!("+{}", p)));
         }
         if let Some(b) = &info.bio {
-            println!("Bio: {}", b);
+            println!("Bio: {}", out::redact::text(b));
         }
 
         // Status flags
@@ -187,7 +189,6 @@ async fn block_user(cli: &Cli, user_id: i64, block: bool) -> Result<()> {
         };
 
         app.tg
-            .client
             .invoke(&request)
             .await
             .with_context(|| format!("Failed to block user {}", user_id))?;
@@ -198,7 +199,6 @@ async fn block_user(cli: &Cli, user_id: i64, block: bool) -> Result<()> {
         };
 
         app.tg
-            .client
             .invoke(&request)
             .await
             .with_context(|| format!("Failed to unblock user {}", user_id))?;
@@ -1,5 +1,78 @@
+use crate::cmd::self_update::{client, fetch_release, fetch_releases, parse_version};
+use crate::out;
 use crate::Cli;
+use anyhow::Result;
+use clap::Args;
 
-pub fn run(_cli: &Cli) {
-    println!("tgcli {}", env!("CARGO_PKG_VERSION"));
+#[derive(Args, Debug, Clone)]
+pub struct VersionArgs {
+    /// Check GitHub for a newer release and show what changed since this one
+    #[arg(long)]
+    pub check: bool,
+}
+
+pub async fn run(cli: &Cli, args: &VersionArgs) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    if !args.check {
+        if cli.output.is_json() {
+            out::write_json(&serde_json::json!({ "version": current }))?;
+        } else {
+            println!("tgcli {}", current);
+        }
+        return Ok(());
+    }
+
+    let client = client()?;
+    let latest = fetch_release(&client, None).await?;
+    let installed = parse_version(current);
+    let newer_than_installed =
+        |tag: &str| matches!((parse_version(tag), installed), (Some(v), Some(i)) if v > i);
+    let update_available = newer_than_installed(&latest.tag_name);
+
+    // Release notes of everything after the installed version, newest first
+    let changes = if update_available {
+        let latest_version = parse_version(&latest.tag_name);
+        fetch_releases(&client)
+            .await?
+            .into_iter()
+            .filter(|r| !r.prerelease && newer_than_installed(&r.tag_name))
+            .filter(|r| parse_version(&r.tag_name) <= latest_version)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    if cli.output.is_json() {
+        let changes: Vec<serde_json::Value> = changes
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "version": r.tag_name,
+                    "url": r.html_url,
+                    "notes": r.body,
+                })
+            })
+            .collect();
+        out::write_json(&serde_json::json!({
+            "version": current,
+            "latest": latest.tag_name,
+            "update_available": update_available,
+            "changes": changes,
+        }))?;
+    } else if update_available {
+        println!(
+            "tgcli {} (latest: {}). Update with `tgcli self-update`.",
+            current, latest.tag_name
+        );
+        for release in &changes {
+            println!("\n## {}\n", release.tag_name);
+            match release.body.as_deref().map(str::trim) {
+                Some(notes) if !notes.is_empty() => println!("{}", notes),
+                _ => println!("{}", release.html_url),
+            }
+        }
+    } else {
+        println!("tgcli {} is the latest release.", current);
+    }
+    Ok(())
 }
@@ -34,7 +34,7 @@ pub async fn run(cli: &Cli, args: &WipeArgs) -> Result<()> {
 
     // Show what will be deleted and confirm
     if !cli.output.is_json() && !args.yes {
-        println!("This will delete tgcli.db ({}).", format_size(db_size));
+        println!("This will delete tgcli.db ({}).", out::format_size(db_size));
         println!("Session and media will be preserved.");
         println!();
         print!("Are you sure? [y/N] ");
@@ -65,24 +65,8 @@ pub async fn run(cli: &Cli, args: &WipeArgs) -> Result<()> {
             "deleted_size": db_size
         }))?;
     } else {
-        println!("Wiped tgcli.db ({}).", format_size(db_size));
+        println!("Wiped tgcli.db ({}).", out::format_size(db_size));
     }
 
     Ok(())
 }
-
-fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-
-    if bytes >= GB {
-        format!("{:.1} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.1} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.1} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} bytes", bytes)
-    }
-}
@@ -0,0 +1,110 @@
+//! Persistent user settings stored as `config.json` in the config directory
+//! (the store directory with `--store`).
+//!
+//! Written by `tgcli init`; every field is optional so older or hand-edited
+//! files keep loading. Command-line flags always win over config values.
+
+use crate::out::OutputMode;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const CONFIG_FILE: &str = "config.json";
+
+/// Aliases loaded by `init_aliases` for `parse_chat`.
+static ALIASES: OnceLock<BTreeMap<String, i64>> = OnceLock::new();
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Default output mode when `--output` is not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputMode>,
+    /// SOCKS5 proxy URL used when `--proxy` is not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// API ID from my.telegram.org, saved by `tgcli auth --api-id`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_id: Option<i32>,
+    /// API hash paired with `api_id`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_hash: Option<String>,
+    /// Same as always passing `--read-only`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// Same as always passing `--redact`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub redact: bool,
+    /// Give runs without `--token` no scopes instead of full access
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub require_token: bool,
+    pub sync: SyncConfig,
+    /// Chat aliases (`tgcli alias`), accepted wherever a chat ID is
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, i64>,
+}
+
+/// Defaults applied to `tgcli sync`; `--no-*` flags override them, and
+/// `ignore_chat_ids` is appended to `--ignore`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub download_media: bool,
+    pub ignore_channels: bool,
+    pub skip_archived: bool,
+    /// Managed by `tgcli ignore`; also skipped by the daemon and hidden from
+    /// `messages list/search`
+    pub ignore_chat_ids: Vec<i64>,
+    /// Add contact cards shared in messages to `contacts`
+    pub save_shared_contacts: bool,
+}
+
+impl Config {
+    pub fn path(config_dir: &str) -> PathBuf {
+        PathBuf::from(config_dir).join(CONFIG_FILE)
+    }
+
+    /// Load the config, returning defaults when the file does not exist.
+    pub fn load(config_dir: &str) -> Result<Self> {
+        let path = Self::path(config_dir);
+        let data = match std::fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        serde_json::from_str(&data)
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn save(&self, config_dir: &str) -> Result<()> {
+        std::fs::create_dir_all(config_dir)
+            .with_context(|| format!("Failed to create config directory '{}'", config_dir))?;
+        let path = Self::path(config_dir);
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, format!("{}\n", json))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Load chat aliases for `parse_chat`. Runs before arguments are parsed, so
+/// `config_dir` comes from the raw `--store` argument.
+pub fn init_aliases(config_dir: &str) {
+    let aliases = Config::load(config_dir)
+        .map(|c| c.aliases)
+        .unwrap_or_default();
+    let _ = ALIASES.set(aliases);
+}
+
+/// Value parser for chat arguments: a chat ID or an alias.
+pub fn parse_chat(s: &str) -> Result<i64, String> {
+    if let Ok(id) = s.parse() {
+        return Ok(id);
+    }
+    ALIASES
+        .get()
+        .and_then(|aliases| aliases.get(s))
+        .copied()
+        .ok_or_else(|| format!("'{}' is not a chat ID or alias (see `tgcli alias list`)", s))
+}
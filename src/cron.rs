@@ -0,0 +1,190 @@
+//! Five-field cron expressions (`minute hour day-of-month month day-of-week`)
+//! for `tgcli schedule`, evaluated in local time.
+//!
+//! Each field takes `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps
+//! (`*/15`, `8-18/2`). Day of week runs 0-6 from Sunday (7 is Sunday too).
+//! As in classic cron, when both day fields are restricted a day matching
+//! either one is enough.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone, Timelike};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// Parse one field into a set of allowed values in `min..=max`.
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<Vec<bool>> {
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .with_context(|| format!("Invalid step '{}' in {} field", step, name))?;
+                if step == 0 {
+                    anyhow::bail!("Step can't be 0 in {} field", name);
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, name)?, parse_value(b, name)?)
+        } else {
+            let v = parse_value(range, name)?;
+            // "5/10" means from 5 to the end, every 10
+            (v, if part.contains('/') { max } else { v })
+        };
+        if start < min || end > max || start > end {
+            anyhow::bail!(
+                "'{}' is out of range for the {} field ({}-{})",
+                part,
+                name,
+                min,
+                max
+            );
+        }
+        for v in (start..=end).step_by(step as usize) {
+            allowed[v as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+fn parse_value(s: &str, name: &str) -> Result<u32> {
+    s.parse()
+        .with_context(|| format!("Invalid value '{}' in {} field", s, name))
+}
+
+impl std::str::FromStr for Cron {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            anyhow::bail!(
+                "Cron expression needs 5 fields (minute hour day month weekday), got '{}'",
+                s
+            );
+        };
+        let mut weekdays = parse_field(weekday, 0, 7, "day-of-week")?;
+        if weekdays[7] {
+            weekdays[0] = true;
+        }
+        weekdays.truncate(7);
+        Ok(Cron {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days: parse_field(day, 1, 31, "day-of-month")?,
+            months: parse_field(month, 1, 12, "month")?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+}
+
+impl Cron {
+    fn day_matches(&self, t: &DateTime<Local>) -> bool {
+        let day = self.days[t.day() as usize];
+        let weekday = self.weekdays[t.weekday().num_days_from_sunday() as usize];
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    /// The first minute strictly after `after` the expression fires in
+    /// (None if none within about four years, e.g. "0 0 31 2 *").
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut t = start.naive_local();
+        let limit = t + Duration::days(366 * 4 + 1);
+        while t < limit {
+            let local = Local.from_local_datetime(&t).earliest();
+            match local {
+                Some(local) if !self.months[local.month() as usize] => {
+                    // Skip to the first day of the next month
+                    let (y, m) = if t.month() == 12 {
+                        (t.year() + 1, 1)
+                    } else {
+                        (t.year(), t.month() + 1)
+                    };
+                    t = chrono::NaiveDate::from_ymd_opt(y, m, 1)?.and_hms_opt(0, 0, 0)?;
+                }
+                Some(local) if !self.day_matches(&local) => {
+                    t = (t.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+                }
+                Some(local) if !self.hours[local.hour() as usize] => {
+                    t = t.with_minute(0)? + Duration::hours(1);
+                }
+                Some(local) if self.minutes[local.minute() as usize] => return Some(local),
+                // Not this minute, or a local time skipped by a DST change
+                _ => t += Duration::minutes(1),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(s: &str) -> DateTime<Local> {
+        let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&naive).earliest().unwrap()
+    }
+
+    #[test]
+    fn next_after_finds_the_weekly_slot() {
+        // Mondays at 09:00; 2026-10-16 is a Friday
+        let cron: Cron = "0 9 * * 1".parse().unwrap();
+        assert_eq!(
+            cron.next_after(local("2026-10-16 12:30")),
+            Some(local("2026-10-19 09:00"))
+        );
+        // Strictly after: a run at 09:00 schedules the next week
+        assert_eq!(
+            cron.next_after(local("2026-10-19 09:00")),
+            Some(local("2026-10-26 09:00"))
+        );
+    }
+
+    #[test]
+    fn fields_take_lists_ranges_and_steps() {
+        let cron: Cron = "*/15 8-18/2 1,15 * *".parse().unwrap();
+        assert_eq!(
+            cron.next_after(local("2026-10-15 10:30")),
+            Some(local("2026-10-15 10:45"))
+        );
+        // 11:00 is outside the every-other-hour range, and the 16th isn't listed
+        assert_eq!(
+            cron.next_after(local("2026-10-15 10:45")),
+            Some(local("2026-10-15 12:00"))
+        );
+        assert_eq!(
+            cron.next_after(local("2026-10-15 18:45")),
+            Some(local("2026-11-01 08:00"))
+        );
+        assert!("0 9 * *".parse::<Cron>().is_err());
+        assert!("60 9 * * *".parse::<Cron>().is_err());
+        assert!("0 0 31 2 *"
+            .parse::<Cron>()
+            .unwrap()
+            .next_after(Local::now())
+            .is_none());
+    }
+}
@@ -0,0 +1,398 @@
+//! Permission guard: read-only mode and API token scopes.
+//!
+//! Read-only mode (`--read-only` or `"read_only": true` in the config) refuses
+//! every Telegram call that changes the account. An API token (`--token`, see
+//! `tgcli tokens`) narrows the process to the token's [`Scope`]s.
+//!
+//! High-level operations call [`check_send`], [`check_chat`] or [`check`]
+//! before doing anything; raw TL calls made through
+//! [`crate::tg::trace::invoke`] are filtered by [`allows_call`]; commands are
+//! gated by [`check_access`] before they run.
+//!
+//! Every change checked here, allowed or refused, is also noted as an
+//! [`Attempt`] for the audit log (`tgcli audit`).
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+/// Scopes of the token in use; unset means full access.
+static SCOPES: OnceLock<Vec<Scope>> = OnceLock::new();
+/// Changes checked since the last [`take_attempts`].
+static ATTEMPTS: Mutex<Vec<Attempt>> = Mutex::new(Vec::new());
+/// Sequence number of the next [`Attempt`].
+static NEXT_ATTEMPT: AtomicU64 = AtomicU64::new(0);
+
+/// TL method name prefixes that never change anything server-side.
+const READ_PREFIXES: &[&str] = &["Get", "Search", "Resolve", "Check", "Translate"];
+
+/// Raw TL calls that act on one chat. The operation making them has already
+/// checked that chat with [`check_send`] or [`check_chat`], so a chat-limited
+/// token may make them; every other change needs `write:*`.
+const CHAT_CALLS: &[&str] = &[
+    "messages.Send",
+    "messages.Forward",
+    "messages.Edit",
+    "messages.Delete",
+    "messages.Read",
+    "messages.SetTyping",
+    "messages.UpdatePinned",
+    "messages.Unpin",
+    "messages.Report",
+    "messages.SaveDraft",
+    "messages.ExportChatInvite",
+    "messages.ImportChatInvite",
+    "channels.Edit",
+    "channels.Read",
+    "channels.Join",
+    "channels.Leave",
+    "channels.Report",
+    "account.UpdateNotifySettings",
+    "premium.ApplyBoost",
+];
+
+/// What a `read:` scope can grant.
+pub const READ_AREAS: &[&str] = &["messages", "chats", "contacts", "account", "metrics"];
+
+/// A permission granted to an API token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// `*`: everything, including managing tgcli itself
+    All,
+    /// `read:<area>`, or `read:*` for every area
+    Read(Option<String>),
+    /// `send:chat:<id>`, or `send:*` for every chat
+    Send(Option<i64>),
+    /// `write:chat:<id>` or `write:*`: sending plus editing, deleting,
+    /// reacting, marking as read and admin actions
+    Write(Option<i64>),
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let chat = |rest: &str| match rest {
+            "*" => Ok(None),
+            _ => rest
+                .strip_prefix("chat:")
+                .ok_or_else(|| format!("Expected '*' or 'chat:<id>' in scope '{}'", s))
+                .and_then(crate::config::parse_chat)
+                .map(Some),
+        };
+        match s.split_once(':') {
+            None if s == "*" => Ok(Scope::All),
+            Some(("read", "*")) => Ok(Scope::Read(None)),
+            Some(("read", area)) if READ_AREAS.contains(&area) => {
+                Ok(Scope::Read(Some(area.to_string())))
+            }
+            Some(("read", area)) => Err(format!(
+                "Unknown read area '{}' (expected one of: {}, *)",
+                area,
+                READ_AREAS.join(", ")
+            )),
+            Some(("send", rest)) => chat(rest).map(Scope::Send),
+            Some(("write", rest)) => chat(rest).map(Scope::Write),
+            _ => Err(format!(
+                "Unknown scope '{}' (expected *, read:<area>, send:*, send:chat:<id>, write:* or write:chat:<id>)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let target = |chat: &Option<i64>| match chat {
+            Some(id) => format!("chat:{}", id),
+            None => "*".to_string(),
+        };
+        match self {
+            Scope::All => write!(f, "*"),
+            Scope::Read(area) => write!(f, "read:{}", area.as_deref().unwrap_or("*")),
+            Scope::Send(chat) => write!(f, "send:{}", target(chat)),
+            Scope::Write(chat) => write!(f, "write:{}", target(chat)),
+        }
+    }
+}
+
+impl Scope {
+    pub fn grants_read(&self, area: &str) -> bool {
+        match self {
+            Scope::All | Scope::Read(None) => true,
+            Scope::Read(Some(a)) => a == area,
+            _ => false,
+        }
+    }
+
+    fn grants_send(&self, chat_id: i64) -> bool {
+        match self {
+            Scope::All | Scope::Send(None) | Scope::Write(None) => true,
+            Scope::Send(Some(id)) | Scope::Write(Some(id)) => *id == chat_id,
+            Scope::Read(_) => false,
+        }
+    }
+
+    /// Changes other than sending, to `chat_id` or to the account as a whole.
+    fn grants_write(&self, chat_id: Option<i64>) -> bool {
+        match self {
+            Scope::All | Scope::Write(None) => true,
+            Scope::Write(Some(id)) => chat_id == Some(*id),
+            _ => false,
+        }
+    }
+}
+
+/// What a command needs from the token before it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Help, version, completions: always allowed
+    Open,
+    /// A `read:<area>` scope; changes are checked again where they happen
+    Read(&'static str),
+    /// Configuring tgcli itself (auth, rules, tokens, wiping): `*` only
+    Full,
+}
+
+/// A change that went through a check, for the audit log.
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    seq: u64,
+    pub ts: DateTime<Utc>,
+    /// What was attempted ("send", "delete messages", ...)
+    pub action: String,
+    pub chat_id: Option<i64>,
+    /// Why the guard refused it, or why it failed
+    pub error: Option<String>,
+}
+
+/// SHA-256 of a token; only this is stored.
+pub fn token_hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+pub fn set_read_only(enabled: bool) {
+    READ_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+/// Limit the rest of the process to `scopes`.
+pub fn restrict(scopes: Vec<Scope>) {
+    let _ = SCOPES.set(scopes);
+}
+
+fn granted(allows: impl Fn(&Scope) -> bool) -> bool {
+    SCOPES.get().is_none_or(|scopes| scopes.iter().any(allows))
+}
+
+fn note(action: &str, chat_id: Option<i64>, result: anyhow::Result<()>) -> anyhow::Result<()> {
+    if let Ok(mut attempts) = ATTEMPTS.lock() {
+        attempts.push(Attempt {
+            seq: NEXT_ATTEMPT.fetch_add(1, Ordering::Relaxed),
+            ts: Utc::now(),
+            action: action.to_string(),
+            chat_id,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+    }
+    result
+}
+
+/// Where an operation starts; changes it checks come after the mark.
+#[derive(Debug, Clone, Copy)]
+pub struct Mark(u64);
+
+/// Mark the start of an operation whose failure [`note_failure`] may record.
+pub fn mark() -> Mark {
+    Mark(NEXT_ATTEMPT.load(Ordering::Relaxed))
+}
+
+/// Record that the operation started at `mark` failed with `error` (e.g.
+/// Telegram rejected it), on the last change it checked. Refusals are
+/// recorded already, and nothing is recorded if it checked no change.
+pub fn note_failure(mark: Mark, error: &anyhow::Error) {
+    if let Ok(mut attempts) = ATTEMPTS.lock() {
+        if let Some(last) = attempts
+            .iter_mut()
+            .rev()
+            .take_while(|a| a.seq >= mark.0)
+            .next()
+            .filter(|a| a.error.is_none())
+        {
+            last.error = Some(format!("{:#}", error));
+        }
+    }
+}
+
+/// Changes checked since the last call, oldest first.
+pub fn take_attempts() -> Vec<Attempt> {
+    ATTEMPTS
+        .lock()
+        .map(|mut attempts| std::mem::take(&mut *attempts))
+        .unwrap_or_default()
+}
+
+fn write_check(action: &str, chat_id: Option<i64>, read_only: bool) -> anyhow::Result<()> {
+    if read_only {
+        anyhow::bail!("Refusing to {}: tgcli is in read-only mode", action);
+    }
+    if !granted(|s| s.grants_write(chat_id)) {
+        let needed = Scope::Write(chat_id);
+        anyhow::bail!(
+            "Refusing to {}: the API token lacks the {} scope",
+            action,
+            needed
+        );
+    }
+    Ok(())
+}
+
+/// Fail with a clear error when `action` (e.g. "create chats") isn't allowed.
+pub fn check(action: &str) -> anyhow::Result<()> {
+    note(action, None, write_check(action, None, is_read_only()))
+}
+
+/// Like [`check`], for a change to one chat (`write:chat:<id>` suffices).
+pub fn check_chat(action: &str, chat_id: i64) -> anyhow::Result<()> {
+    note(
+        action,
+        Some(chat_id),
+        write_check(action, Some(chat_id), is_read_only()),
+    )
+}
+
+fn send_check(chat_id: i64) -> anyhow::Result<()> {
+    if is_read_only() {
+        anyhow::bail!("Refusing to send messages: tgcli is in read-only mode");
+    }
+    if !granted(|s| s.grants_send(chat_id)) {
+        anyhow::bail!(
+            "Refusing to send to {}: the API token lacks the send:chat:{} scope",
+            chat_id,
+            chat_id
+        );
+    }
+    Ok(())
+}
+
+/// Sending to `chat_id` (`send:chat:<id>` suffices).
+pub fn check_send(chat_id: i64) -> anyhow::Result<()> {
+    note("send", Some(chat_id), send_check(chat_id))
+}
+
+/// Gate a command on the token's scopes.
+pub fn check_access(access: Access) -> anyhow::Result<()> {
+    match access {
+        Access::Open => Ok(()),
+        Access::Read(area) if granted(|s| s.grants_read(area)) => Ok(()),
+        Access::Read(area) => {
+            anyhow::bail!("This command needs the read:{} scope", area)
+        }
+        Access::Full if granted(|s| *s == Scope::All) => Ok(()),
+        Access::Full => anyhow::bail!("This command needs a token with the * scope"),
+    }
+}
+
+/// Whether a raw TL call (`messages.GetHistory`) may run.
+pub fn allows_call(tl_name: &str) -> bool {
+    call_allowed(tl_name, is_read_only())
+}
+
+fn call_allowed(tl_name: &str, read_only: bool) -> bool {
+    let method = tl_name.rsplit('.').next().unwrap_or(tl_name);
+    if READ_PREFIXES.iter().any(|p| method.starts_with(p)) {
+        return true;
+    }
+    if read_only {
+        return false;
+    }
+    if CHAT_CALLS.iter().any(|c| tl_name.starts_with(c)) {
+        granted(|s| matches!(s, Scope::All | Scope::Send(_) | Scope::Write(_)))
+    } else {
+        granted(|s| s.grants_write(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The read-only flag is passed in rather than set: tests run in parallel
+    #[test]
+    fn read_only_allows_only_read_calls() {
+        let read_only = |tl_name| call_allowed(tl_name, true);
+        assert!(read_only("messages.GetHistory"));
+        assert!(read_only("contacts.ResolveUsername"));
+        assert!(read_only("messages.SearchGlobal"));
+        assert!(!read_only("messages.SendMessage"));
+        assert!(!read_only("messages.ReadHistory"));
+        assert!(!read_only("channels.EditBanned"));
+        assert!(write_check("send messages", None, true).is_err());
+        assert!(call_allowed("messages.SendMessage", false));
+        assert!(write_check("send messages", None, false).is_ok());
+    }
+
+    #[test]
+    fn attempts_record_refusals_and_failures() {
+        let start = mark();
+        let refused = write_check("send messages", None, true);
+        assert!(note("send messages", None, refused).is_err());
+        let sent = mark();
+        note("send messages", None, Ok(())).unwrap();
+        note_failure(sent, &anyhow::anyhow!("FLOOD_WAIT"));
+        // A failure that checked nothing isn't pinned on an earlier change
+        note("pin messages", None, Ok(())).unwrap();
+        note_failure(mark(), &anyhow::anyhow!("database is locked"));
+
+        let attempts: Vec<Attempt> = take_attempts()
+            .into_iter()
+            .filter(|a| a.seq >= start.0)
+            .collect();
+        assert!(attempts[0].error.as_deref().unwrap().contains("read-only"));
+        assert_eq!(attempts[1].error.as_deref(), Some("FLOOD_WAIT"));
+        assert!(attempts[2].error.is_none());
+    }
+
+    #[test]
+    fn parses_and_prints_scopes() {
+        for s in [
+            "*",
+            "read:*",
+            "read:messages",
+            "send:*",
+            "send:chat:12345",
+            "write:chat:-100",
+        ] {
+            assert_eq!(s.parse::<Scope>().unwrap().to_string(), s);
+        }
+        assert!("read:everything".parse::<Scope>().is_err());
+        assert!("send:12345".parse::<Scope>().is_err());
+        assert!("delete:*".parse::<Scope>().is_err());
+    }
+
+    #[test]
+    fn scopes_grant_what_they_name() {
+        let send_one = Scope::Send(Some(12345));
+        assert!(send_one.grants_send(12345));
+        assert!(!send_one.grants_send(1));
+        assert!(!send_one.grants_write(Some(12345)));
+
+        let write_one = Scope::Write(Some(12345));
+        assert!(write_one.grants_send(12345));
+        assert!(write_one.grants_write(Some(12345)));
+        assert!(!write_one.grants_write(None));
+
+        let read = Scope::Read(Some("messages".to_string()));
+        assert!(read.grants_read("messages"));
+        assert!(!read.grants_read("contacts"));
+        assert!(!read.grants_send(12345));
+        assert!(Scope::All.grants_write(None));
+    }
+}
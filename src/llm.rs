@@ -0,0 +1,189 @@
+//! Minimal LLM backends used by `messages summarize`.
+//!
+//! - `openai`: any OpenAI-compatible chat completions API
+//!   (`OPENAI_API_KEY`, optional `OPENAI_BASE_URL`)
+//! - `ollama`: a local Ollama server (`OLLAMA_HOST`, default localhost:11434)
+//! - `cmd`: a command (run without a shell) that reads the prompt on stdin and
+//!   prints the answer
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+
+const OPENAI_DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const OPENAI_DEFAULT_MODEL: &str = "gpt-4o-mini";
+const OLLAMA_DEFAULT_HOST: &str = "http://localhost:11434";
+const OLLAMA_DEFAULT_MODEL: &str = "llama3.2";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    Openai,
+    Ollama,
+    Cmd,
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Openai => write!(f, "openai"),
+            Backend::Ollama => write!(f, "ollama"),
+            Backend::Cmd => write!(f, "cmd"),
+        }
+    }
+}
+
+pub struct LlmConfig {
+    pub backend: Backend,
+    /// Model name (openai/ollama); each backend has a default
+    pub model: Option<String>,
+    /// Command for the `cmd` backend: a program and whitespace-separated args
+    pub command: Option<String>,
+}
+
+impl LlmConfig {
+    /// Send one prompt and return the model's answer.
+    pub async fn complete(&self, system: &str, prompt: &str) -> Result<String> {
+        let answer = match self.backend {
+            Backend::Openai => self.complete_openai(system, prompt).await?,
+            Backend::Ollama => self.complete_ollama(system, prompt).await?,
+            Backend::Cmd => self.complete_cmd(system, prompt).await?,
+        };
+        Ok(answer.trim().to_string())
+    }
+
+    async fn complete_openai(&self, system: &str, prompt: &str) -> Result<String> {
+        let api_key = std::env::var("OPENAI_API_KEY")
+            .context("OPENAI_API_KEY is not set (required for --backend openai)")?;
+        let base_url = std::env::var("OPENAI_BASE_URL")
+            .unwrap_or_else(|_| OPENAI_DEFAULT_BASE_URL.to_string());
+        let body = json!({
+            "model": self.model.as_deref().unwrap_or(OPENAI_DEFAULT_MODEL),
+            "messages": [
+                {"role": "system", "content": system},
+                {"role": "user", "content": prompt},
+            ],
+        });
+
+        let resp: serde_json::Value = reqwest::Client::new()
+            .post(format!(
+                "{}/chat/completions",
+                base_url.trim_end_matches('/')
+            ))
+            .bearer_auth(api_key)
+            .json(&body)
+            .send()
+            .await
+            .context("OpenAI request failed")?
+            .error_for_status()
+            .context("OpenAI request failed")?
+            .json()
+            .await
+            .context("Invalid OpenAI response")?;
+
+        resp["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("OpenAI response has no message content"))
+    }
+
+    async fn complete_ollama(&self, system: &str, prompt: &str) -> Result<String> {
+        let host = std::env::var("OLLAMA_HOST").unwrap_or_else(|_| OLLAMA_DEFAULT_HOST.to_string());
+        let host = if host.starts_with("http://") || host.starts_with("https://") {
+            host
+        } else {
+            format!("http://{}", host)
+        };
+        let body = json!({
+            "model": self.model.as_deref().unwrap_or(OLLAMA_DEFAULT_MODEL),
+            "stream": false,
+            "messages": [
+                {"role": "system", "content": system},
+                {"role": "user", "content": prompt},
+            ],
+        });
+
+        let resp: serde_json::Value = reqwest::Client::new()
+            .post(format!("{}/api/chat", host.trim_end_matches('/')))
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("Ollama request to {} failed", host))?
+            .error_for_status()
+            .context("Ollama request failed")?
+            .json()
+            .await
+            .context("Invalid Ollama response")?;
+
+        resp["message"]["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("Ollama response has no message content"))
+    }
+
+    async fn complete_cmd(&self, system: &str, prompt: &str) -> Result<String> {
+        let command = self
+            .command
+            .as_deref()
+            .context("--cmd is required for --backend cmd")?;
+
+        // Run directly (no shell), like $PAGER: program and whitespace-split args
+        let mut parts = command.split_whitespace();
+        let program = parts.next().context("--cmd is empty")?;
+        let mut child = tokio::process::Command::new(program)
+            .args(parts)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run '{}'", command))?;
+
+        // Write the prompt while the output is read, so neither pipe fills up
+        let input = format!("{}\n\n{}", system, prompt);
+        let mut stdin = child.stdin.take().context("No stdin for the command")?;
+        let writer = tokio::spawn(async move {
+            // The command may exit without reading everything (broken pipe)
+            let _ = stdin.write_all(input.as_bytes()).await;
+        });
+        let output = child.wait_with_output().await?;
+        let _ = writer.await;
+        if !output.status.success() {
+            anyhow::bail!("'{}' exited with {}", command, output.status);
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Split lines into chunks of at most `max_chars` characters, never splitting a line.
+pub fn chunk_lines(lines: &[String], max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + line.len() + 1 > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cmd_backend_streams_large_prompts() {
+        let llm = LlmConfig {
+            backend: Backend::Cmd,
+            model: None,
+            command: Some("cat".to_string()),
+        };
+        // Far more than a pipe buffer each way
+        let prompt = "x".repeat(1 << 20);
+        let answer = llm.complete("system", &prompt).await.unwrap();
+        assert_eq!(answer.len(), "system\n\n".len() + prompt.len());
+    }
+}
@@ -0,0 +1,83 @@
+//! One Telegram connection per store at a time.
+//!
+//! Commands that connect to Telegram take an advisory lock on
+//! `<store>/tgcli.lock` (holding our pid) for the rest of the process, so
+//! two tgcli runs never share the session or sync into the database at
+//! once. Commands that only read the local store don't take it.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const LOCK_FILE: &str = "tgcli.lock";
+
+/// How often `--wait` checks whether the other instance is done.
+const WAIT_POLL: Duration = Duration::from_millis(500);
+
+/// The locked file, kept open until the process exits.
+static HELD: OnceLock<File> = OnceLock::new();
+
+/// Pid written by the instance holding the lock, if readable.
+fn holder(file: &mut File) -> Option<u32> {
+    let mut pid = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut pid).ok()?;
+    pid.trim().parse().ok()
+}
+
+/// Lock the store for this process. If another instance holds it, fail
+/// with its pid, or with `wait` poll until it exits (or Ctrl+C).
+pub async fn acquire(store_dir: &str, wait: bool) -> Result<()> {
+    if HELD.get().is_some() {
+        return Ok(());
+    }
+    let path = Path::new(store_dir).join(LOCK_FILE);
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("Failed to open lock file '{}'", path.display()))?;
+
+    let mut announced = false;
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) => {
+                let pid = holder(&mut file)
+                    .map(|pid| format!(" (pid {})", pid))
+                    .unwrap_or_default();
+                if !wait {
+                    anyhow::bail!(
+                        "Another tgcli instance is running{} on store '{}'. Wait for it to finish, or pass --wait to queue behind it.",
+                        pid,
+                        store_dir
+                    );
+                }
+                if !announced {
+                    eprintln!("Waiting for another tgcli instance{} to finish...", pid);
+                    announced = true;
+                }
+                let shutdown_ctrl = crate::shutdown::global();
+                tokio::select! {
+                    _ = tokio::time::sleep(WAIT_POLL) => {}
+                    _ = shutdown_ctrl.cancelled() => anyhow::bail!("Interrupted while waiting for the store lock"),
+                }
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock '{}'", path.display()));
+            }
+        }
+    }
+
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", std::process::id())?;
+    file.flush()?;
+    let _ = HELD.set(file);
+    Ok(())
+}
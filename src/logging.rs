@@ -0,0 +1,60 @@
+//! Logger setup for `--log-format`.
+//!
+//! `text` is env_logger's default format. `json` writes one object per line
+//! with `ts`, `level`, `module`, `msg`, plus any structured fields attached
+//! to the record (e.g. `log::info!(chat_id = id; "...")`), for journald/Loki.
+
+use clap::ValueEnum;
+use log::kv::{Key, Value, VisitSource};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    /// env_logger's human-readable format (default)
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Initialize the global logger (`RUST_LOG` still controls the level).
+pub fn init(format: LogFormat) {
+    let mut builder = env_logger::Builder::from_default_env();
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let mut obj = serde_json::Map::new();
+            obj.insert(
+                "ts".into(),
+                chrono::Utc::now()
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+                    .into(),
+            );
+            obj.insert("level".into(), record.level().as_str().into());
+            obj.insert(
+                "module".into(),
+                record
+                    .module_path()
+                    .unwrap_or_else(|| record.target())
+                    .into(),
+            );
+            obj.insert("msg".into(), record.args().to_string().into());
+            let _ = record.key_values().visit(&mut Fields(&mut obj));
+            writeln!(buf, "{}", serde_json::Value::Object(obj))
+        });
+    }
+    builder.init();
+}
+
+/// Copies structured log fields into the JSON object, keeping integers numeric.
+struct Fields<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        let value = match value.to_i64() {
+            Some(n) => n.into(),
+            None => value.to_string().into(),
+        };
+        self.0.insert(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
@@ -1,49 +1,220 @@
+mod alerts;
 mod app;
 mod cmd;
+mod config;
+mod cron;
 mod error;
+mod guard;
+mod llm;
+mod lock;
+mod logging;
+mod metrics;
 mod out;
+mod paths;
 mod shutdown;
 mod store;
 mod tg;
+mod tgs;
+mod words;
 
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "tgcli", version, about = "Telegram CLI (pure Rust, no TDLib)")]
 pub struct Cli {
-    /// Store directory (default: ~/.tgcli)
-    #[arg(long, global = true, default_value = "~/.tgcli")]
-    pub store: String,
+    /// Store directory holding session, database and config [default: XDG
+    /// data/config/cache dirs on Linux, ~/.tgcli on macOS and Windows]
+    #[arg(long, global = true)]
+    pub store: Option<String>,
 
-    /// Output mode: text (default), json, or none
-    #[arg(long, global = true, value_enum, default_value = "text")]
+    /// Output mode: text (default), json, markdown, llm, none, or sqlite:PATH
+    /// (a standalone database; messages list/search and export)
+    #[arg(long, global = true, value_parser = out::parse_mode, default_value = "text")]
     pub output: out::OutputMode,
 
+    /// SOCKS5 proxy for Telegram connections (socks5://[user:pass@]host:port)
+    #[arg(long, global = true, env = "TGCLI_PROXY")]
+    pub proxy: Option<String>,
+
+    /// Telegram API ID (from my.telegram.org); saved per store on `auth`
+    #[arg(long, global = true, env = "TG_API_ID", requires = "api_hash")]
+    pub api_id: Option<i32>,
+
+    /// Telegram API hash (from my.telegram.org)
+    #[arg(long, global = true, env = "TG_API_HASH", requires = "api_id")]
+    pub api_hash: Option<String>,
+
+    /// Refuse anything that changes the account (send, edit, delete, ban, read, ...)
+    #[arg(long, global = true, env = "TGCLI_READ_ONLY")]
+    pub read_only: bool,
+
+    /// Mask phone numbers and email addresses in all output and exports
+    #[arg(long, global = true, env = "TGCLI_REDACT")]
+    pub redact: bool,
+
+    /// API token limiting this run to its scopes (see `tgcli tokens`)
+    #[arg(long, global = true, env = "TGCLI_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// Use Telegram's test servers (test accounts only). Needs a dedicated
+    /// --store, since the session's DC table is rewritten
+    #[arg(long, global = true, requires = "store")]
+    pub test_dc: bool,
+
+    /// Force the home DC ID (for debugging DC migration). Needs a dedicated
+    /// --store, since the session's home DC is rewritten
+    #[arg(long, global = true, value_name = "N", requires = "store")]
+    pub dc: Option<i32>,
+
+    /// Limit media download speed, e.g. 2MB/s or 500KB/s
+    #[arg(long, global = true, value_name = "RATE", value_parser = tg::transfer::parse_rate)]
+    pub max_download_rate: Option<u64>,
+
+    /// Limit media upload speed, e.g. 1MB/s
+    #[arg(long, global = true, value_name = "RATE", value_parser = tg::transfer::parse_rate)]
+    pub max_upload_rate: Option<u64>,
+
+    /// Log every Telegram API call (type, duration, errors; no bodies) to <store>/logs/api.log
+    #[arg(long, global = true)]
+    pub debug_api: bool,
+
+    /// Log format on stderr: text (default) or json (one object per line)
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    pub log_format: logging::LogFormat,
+
+    /// Never pipe output through $PAGER
+    #[arg(long, global = true)]
+    pub no_pager: bool,
+
+    /// If another tgcli is connected with this store, wait for it instead of failing
+    #[arg(long, global = true)]
+    pub wait: bool,
+
     #[command(subcommand)]
     pub command: cmd::Command,
 }
 
 impl Cli {
+    pub fn dirs(&self) -> paths::Dirs {
+        paths::Dirs::resolve(self.store.as_deref())
+    }
+
+    /// Session, database, media and logs.
     pub fn store_dir(&self) -> String {
-        let s = &self.store;
-        if s.starts_with("~/") {
-            if let Some(home) = dirs_home() {
-                return format!("{}{}", home, &s[1..]);
+        self.dirs().data.to_string_lossy().into_owned()
+    }
+
+    /// Where `config.json` lives (the store directory with `--store`).
+    pub fn config_dir(&self) -> String {
+        self.dirs().config.to_string_lossy().into_owned()
+    }
+
+    /// Connection options from flags, falling back to the config file.
+    pub fn connect_options(&self) -> tg::ConnectOptions {
+        let config = config::Config::load(&self.config_dir()).unwrap_or_default();
+        let defaults = tg::ApiCredentials::default();
+        tg::ConnectOptions {
+            api: tg::ApiCredentials {
+                api_id: self.api_id.or(config.api_id).unwrap_or(defaults.api_id),
+                api_hash: self
+                    .api_hash
+                    .clone()
+                    .or(config.api_hash)
+                    .unwrap_or(defaults.api_hash),
+            },
+            proxy: self.proxy.clone().or(config.proxy),
+            test_dc: self.test_dc,
+            dc: self.dc,
+        }
+    }
+}
+
+/// `--store` from the raw arguments, for what's needed before parsing.
+fn raw_store_arg() -> Option<String> {
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--store" {
+            if let Some(value) = args.next() {
+                return Some(value.to_string_lossy().into_owned());
             }
+        } else if let Some(value) = arg.strip_prefix("--store=") {
+            return Some(value.to_string());
         }
-        s.clone()
     }
+    None
 }
 
-fn dirs_home() -> Option<String> {
-    std::env::var("HOME").ok()
+/// Move `~/.tgcli` into the platform layout under the store lock, and point
+/// the media paths recorded in its database at their new location.
+async fn move_legacy_store(dirs: &paths::Dirs) -> anyhow::Result<()> {
+    let data_dir = dirs.data.to_string_lossy();
+    std::fs::create_dir_all(&dirs.data)?;
+    lock::acquire(&data_dir, false).await?;
+    // Another instance may have moved it while we waited for the lock
+    let Some(legacy) = dirs.migrate_legacy()? else {
+        return Ok(());
+    };
+    eprintln!(
+        "Moved {} to {} (config: {})",
+        legacy.display(),
+        dirs.data.display(),
+        dirs.config.display()
+    );
+    if dirs.data.join("tgcli.db").exists() {
+        let sep = std::path::MAIN_SEPARATOR;
+        let store = store::Store::open(&data_dir).await?;
+        let moved = store
+            .move_media_paths(
+                &format!("{}{}", legacy.join("media").display(), sep),
+                &format!("{}{}", dirs.data.join("media").display(), sep),
+            )
+            .await?;
+        log::info!("Updated the media paths of {} messages", moved);
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() {
-    env_logger::init();
-
-    let cli = Cli::parse();
+    let store = raw_store_arg();
+    let dirs = paths::Dirs::resolve(store.as_deref());
+    let legacy = store.is_none().then(|| dirs.legacy_pending()).flatten();
+    // Chat aliases must be known while `--chat <alias>` is parsed; until the
+    // old store is moved (below), its config is still the current one
+    let alias_dir = legacy.clone().unwrap_or_else(|| dirs.config.clone());
+    config::init_aliases(&alias_dir.to_string_lossy());
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(cli.log_format);
+    if legacy.is_some() {
+        if let Err(e) = move_legacy_store(&dirs).await {
+            eprintln!("Warning: could not move the old store: {:#}", e);
+        }
+    }
+    let config = config::Config::load(&cli.config_dir()).unwrap_or_default();
+    // Fall back to the configured output mode when --output was not given
+    if matches.value_source("output") != Some(ValueSource::CommandLine) {
+        if let Some(output) = config.output {
+            cli.output = output;
+        }
+    }
+    guard::set_read_only(cli.read_only || config.read_only);
+    out::redact::set_enabled(cli.redact || config.redact);
+    out::pager::set_enabled(!cli.no_pager);
+    if let Some(rate) = cli.max_download_rate {
+        tg::transfer::set_download_rate(rate);
+    }
+    if let Some(rate) = cli.max_upload_rate {
+        tg::transfer::set_upload_rate(rate);
+    }
+    if cli.debug_api {
+        match tg::trace::enable(&cli.store_dir()) {
+            Ok(path) => log::info!("Tracing API calls to {}", path.display()),
+            Err(e) => eprintln!("Warning: --debug-api disabled: {:#}", e),
+        }
+    }
 
     // Set up global shutdown handler
     let shutdown = shutdown::ShutdownController::new();
@@ -58,7 +229,15 @@ async fn main() {
         }
     });
 
-    if let Err(e) = cmd::run(cli).await {
+    // Subcommand path, e.g. "messages delete", for the audit log
+    let mut name = Vec::new();
+    let mut sub = &matches;
+    while let Some((n, m)) = sub.subcommand() {
+        name.push(n);
+        sub = m;
+    }
+
+    if let Err(e) = cmd::run(cli, &name.join(" ")).await {
         // Don't report error if we're shutting down gracefully
         if shutdown.is_triggered() {
             std::process::exit(0);
@@ -0,0 +1,216 @@
+//! Prometheus metrics for `tgcli daemon --metrics-port`.
+//!
+//! Counters live in [`DaemonMetrics`] and are served in the Prometheus text
+//! exposition format by a minimal HTTP listener on `GET /metrics`. When API
+//! tokens exist, requests need `Authorization: Bearer <token>` with the
+//! `read:metrics` scope.
+
+use crate::guard::{self, Scope};
+use crate::shutdown::ShutdownController;
+use crate::store::Store;
+use crate::tg::trace;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+pub struct DaemonMetrics {
+    started: Instant,
+    /// Updates received from the update stream
+    pub updates_received: AtomicU64,
+    /// Messages written to the store from updates and background sync
+    pub messages_stored: AtomicU64,
+    /// Failed store writes and update stream errors
+    pub errors: AtomicU64,
+    pub backfill_running: AtomicBool,
+}
+
+impl Default for DaemonMetrics {
+    fn default() -> Self {
+        DaemonMetrics {
+            started: Instant::now(),
+            updates_received: AtomicU64::new(0),
+            messages_stored: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            backfill_running: AtomicBool::new(false),
+        }
+    }
+}
+
+impl DaemonMetrics {
+    /// Render all metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let api = trace::counters();
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+
+        metric(
+            "tgcli_uptime_seconds",
+            "gauge",
+            "Seconds since the daemon started.",
+            self.started.elapsed().as_secs(),
+        );
+        metric(
+            "tgcli_updates_received_total",
+            "counter",
+            "Updates received from Telegram (use rate() for updates/sec).",
+            self.updates_received.load(Ordering::Relaxed),
+        );
+        metric(
+            "tgcli_messages_synced_total",
+            "counter",
+            "Messages stored from real-time updates and background sync.",
+            self.messages_stored.load(Ordering::Relaxed),
+        );
+        metric(
+            "tgcli_daemon_errors_total",
+            "counter",
+            "Update stream and store errors.",
+            self.errors.load(Ordering::Relaxed),
+        );
+        metric(
+            "tgcli_backfill_running",
+            "gauge",
+            "1 while background sync is running.",
+            self.backfill_running.load(Ordering::Relaxed) as u64,
+        );
+        metric(
+            "tgcli_api_calls_total",
+            "counter",
+            "Raw Telegram API calls.",
+            api.calls,
+        );
+        metric(
+            "tgcli_api_errors_total",
+            "counter",
+            "Raw Telegram API calls that failed.",
+            api.errors,
+        );
+        metric(
+            "tgcli_api_flood_waits_total",
+            "counter",
+            "FLOOD_WAIT errors returned by Telegram.",
+            api.flood_waits,
+        );
+        out
+    }
+}
+
+/// Serve `/metrics` on `addr` until shutdown. Tokens are looked up in
+/// `store` on every request, so ones created or revoked meanwhile count.
+pub async fn serve(
+    addr: SocketAddr,
+    metrics: Arc<DaemonMetrics>,
+    store: Arc<Store>,
+    shutdown: ShutdownController,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind metrics listener on {}", addr))?;
+
+    tokio::spawn(async move {
+        loop {
+            let stream = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        log::warn!("Metrics listener accept failed: {}", e);
+                        continue;
+                    }
+                },
+            };
+            let metrics = Arc::clone(&metrics);
+            let store = Arc::clone(&store);
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream, &metrics, &store).await {
+                    log::debug!("Metrics request failed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// The bearer token of a raw HTTP request, if any.
+fn bearer_token(request: &str) -> Option<&str> {
+    request.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("authorization") {
+            return None;
+        }
+        value.trim().strip_prefix("Bearer ").map(str::trim)
+    })
+}
+
+/// Whether `request` may read metrics: anyone until a token exists, then
+/// only bearers of a token with `read:metrics`.
+async fn authorized(store: &Store, request: &str) -> Result<bool> {
+    let tokens = store.list_api_tokens().await?;
+    if tokens.is_empty() {
+        return Ok(true);
+    }
+    let Some(token) = bearer_token(request) else {
+        return Ok(false);
+    };
+    let hash = guard::token_hash(token);
+    Ok(tokens.iter().any(|t| {
+        t.token_hash == hash
+            && t.scopes
+                .iter()
+                .filter_map(|s| s.parse::<Scope>().ok())
+                .any(|s| s.grants_read("metrics"))
+    }))
+}
+
+async fn handle(mut stream: TcpStream, metrics: &DaemonMetrics, store: &Store) -> Result<()> {
+    let mut buf = [0u8; 4096];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let authorized = authorized(store, &request).await.unwrap_or_else(|e| {
+        log::warn!("Failed to check the metrics token: {:#}", e);
+        false
+    });
+
+    let (status, body) = if !authorized {
+        (
+            "401 Unauthorized",
+            "A token with the read:metrics scope is required\n".to_string(),
+        )
+    } else if path == "/metrics" {
+        ("200 OK", metrics.render())
+    } else {
+        ("404 Not Found", "Not found. Try /metrics\n".to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_bearer_tokens() {
+        let request =
+            "GET /metrics HTTP/1.1\r\nHost: x\r\nauthorization: Bearer tgcli_abc \r\n\r\n";
+        assert_eq!(bearer_token(request), Some("tgcli_abc"));
+        assert_eq!(bearer_token("GET /metrics HTTP/1.1\r\n\r\n"), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Export output that can be encrypted on the way to disk: the plain bytes
+//! go to `age` or `gpg` on a pipe, so only ciphertext is ever written.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Age,
+    Gpg,
+}
+
+impl Tool {
+    /// age recipients are `age1...` keys or SSH public keys; anything else
+    /// is a gpg key ID, fingerprint or email.
+    pub fn for_recipient(recipient: &str) -> Self {
+        if recipient.starts_with("age1") || recipient.starts_with("ssh-") {
+            Tool::Age
+        } else {
+            Tool::Gpg
+        }
+    }
+
+    /// Suffix for files this tool writes.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Tool::Age => "age",
+            Tool::Gpg => "gpg",
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Tool::Age => "age",
+            Tool::Gpg => "gpg",
+        }
+    }
+
+    fn command(self, recipient: &str, path: Option<&str>) -> Command {
+        let mut cmd = Command::new(self.program());
+        match self {
+            Tool::Age => cmd.args(["--encrypt", "--recipient", recipient]),
+            Tool::Gpg => cmd.args(["--batch", "--yes", "--encrypt", "--recipient", recipient]),
+        };
+        match path {
+            Some(path) => {
+                cmd.args(["--output", path]);
+            }
+            // Keep a terminal or pipe readable
+            None => {
+                cmd.arg("--armor");
+            }
+        }
+        cmd
+    }
+}
+
+/// Where an export is written: a file or stdout, through `age`/`gpg` when
+/// encrypting. Call `finish` to learn whether it all got there; a sink
+/// dropped without it stops the tool and removes the partial file.
+pub struct Sink {
+    out: Option<Out>,
+    /// Output file, until `finish` succeeds
+    path: Option<PathBuf>,
+}
+
+enum Out {
+    Plain(BufWriter<Box<dyn Write>>),
+    Encrypted {
+        tool: Tool,
+        stdin: BufWriter<ChildStdin>,
+        child: Child,
+    },
+}
+
+impl Sink {
+    pub fn open(path: Option<&str>, encrypt_to: Option<&str>) -> Result<Self> {
+        let Some(recipient) = encrypt_to else {
+            let out: Box<dyn Write> = match path {
+                Some(path) => Box::new(
+                    File::create(path).with_context(|| format!("Failed to create {}", path))?,
+                ),
+                None => Box::new(std::io::stdout()),
+            };
+            return Ok(Sink {
+                out: Some(Out::Plain(BufWriter::new(out))),
+                path: path.map(PathBuf::from),
+            });
+        };
+
+        let tool = Tool::for_recipient(recipient);
+        let mut child = tool
+            .command(recipient, path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to run {}; install it to encrypt to '{}'",
+                    tool.program(),
+                    recipient
+                )
+            })?;
+        let stdin = child
+            .stdin
+            .take()
+            .context("No stdin for the encryption tool")?;
+        Ok(Sink {
+            out: Some(Out::Encrypted {
+                tool,
+                stdin: BufWriter::new(stdin),
+                child,
+            }),
+            path: path.map(PathBuf::from),
+        })
+    }
+
+    /// Flush everything and, when encrypting, wait for the tool to finish.
+    pub fn finish(mut self) -> Result<()> {
+        match self.out.take() {
+            Some(Out::Plain(mut out)) => out.flush()?,
+            Some(Out::Encrypted {
+                tool,
+                stdin,
+                mut child,
+            }) => {
+                // Closing stdin tells the tool the input is complete; it must
+                // be dropped before waiting, or the tool waits for more
+                if let Err(e) = stdin.into_inner().map(drop) {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(e.into_error().into());
+                }
+                let status = child.wait()?;
+                if !status.success() {
+                    anyhow::bail!(
+                        "{} failed to encrypt the export ({})",
+                        tool.program(),
+                        status
+                    );
+                }
+            }
+            None => {}
+        }
+        self.path = None;
+        Ok(())
+    }
+
+    fn out(&mut self) -> &mut dyn Write {
+        match self.out.as_mut() {
+            Some(Out::Plain(out)) => out,
+            Some(Out::Encrypted { stdin, .. }) => stdin,
+            None => unreachable!("sink used after finish"),
+        }
+    }
+}
+
+impl Drop for Sink {
+    fn drop(&mut self) {
+        if let Some(Out::Encrypted { mut child, .. }) = self.out.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if let Some(path) = self.path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.out().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recipients_pick_the_tool() {
+        assert_eq!(
+            Tool::for_recipient("age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"),
+            Tool::Age
+        );
+        assert_eq!(Tool::for_recipient("ssh-ed25519 AAAAC3Nza..."), Tool::Age);
+        assert_eq!(Tool::for_recipient("alice@example.com"), Tool::Gpg);
+        assert_eq!(Tool::for_recipient("0x1234ABCD"), Tool::Gpg);
+    }
+
+    #[test]
+    fn unfinished_exports_are_removed() {
+        let dir = std::env::temp_dir().join(format!("tgcli-sink-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("export.json");
+        let path_str = path.to_string_lossy();
+
+        let mut sink = Sink::open(Some(&path_str), None).unwrap();
+        sink.write_all(b"[{").unwrap();
+        drop(sink);
+        assert!(!path.exists());
+
+        let mut sink = Sink::open(Some(&path_str), None).unwrap();
+        sink.write_all(b"[]").unwrap();
+        sink.finish().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"[]");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,150 @@
+//! Activity heatmaps for `stats heatmap`: message counts by weekday and
+//! hour of day, in local time.
+//!
+//! PNG rendering uses the `png` crate and is only available when built with
+//! `--features heatmap`.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use serde::Serialize;
+use std::path::Path;
+
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Shades from empty to busiest, for the terminal grid.
+const SHADES: [&str; 5] = ["  ", "░░", "▒▒", "▓▓", "██"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Heatmap {
+    /// `counts[weekday][hour]`, weekdays from Monday
+    pub counts: [[u64; 24]; 7],
+}
+
+impl Heatmap {
+    pub fn from_times(times: impl IntoIterator<Item = DateTime<Utc>>) -> Self {
+        let mut heatmap = Heatmap::default();
+        for ts in times {
+            let local = ts.with_timezone(&Local);
+            let day = local.weekday().num_days_from_monday() as usize;
+            heatmap.counts[day][local.hour() as usize] += 1;
+        }
+        heatmap
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().flatten().sum()
+    }
+
+    pub fn max(&self) -> u64 {
+        self.counts.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    /// One row per weekday, one column per hour.
+    pub fn to_csv(&self) -> String {
+        let hours: Vec<String> = (0..24).map(|h| format!("{:02}", h)).collect();
+        let mut csv = format!("weekday,{}\n", hours.join(","));
+        for (day, row) in WEEKDAYS.iter().zip(&self.counts) {
+            let counts: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+            csv.push_str(&format!("{},{}\n", day, counts.join(",")));
+        }
+        csv
+    }
+
+    /// A shaded grid for the terminal.
+    pub fn to_text(&self) -> String {
+        let max = self.max().max(1);
+        let mut text = String::from("    ");
+        for h in (0..24).step_by(3) {
+            text.push_str(&format!("{:<6}", format!("{:02}", h)));
+        }
+        text.push('\n');
+        for (day, row) in WEEKDAYS.iter().zip(&self.counts) {
+            text.push_str(day);
+            text.push(' ');
+            for &count in row {
+                // Any activity gets at least the lightest shade
+                let level = if count == 0 {
+                    0
+                } else {
+                    1 + (count * 3 / max) as usize
+                };
+                text.push_str(SHADES[level.min(4)]);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Size of a cell in the PNG, in pixels (including a 2px gap).
+#[cfg_attr(not(feature = "heatmap"), allow(dead_code))]
+const CELL: u32 = 24;
+
+/// Render the heatmap to a PNG: 24 columns (hours) by 7 rows (Monday
+/// first), from light grey (no messages) to dark blue (busiest hour).
+#[cfg(feature = "heatmap")]
+pub fn write_png(heatmap: &Heatmap, dest: &Path) -> Result<()> {
+    use anyhow::Context;
+
+    let (width, height) = (24 * CELL, 7 * CELL);
+    let max = heatmap.max().max(1) as f64;
+    let mut rgba = vec![255u8; (width * height * 4) as usize];
+    for (day, row) in heatmap.counts.iter().enumerate() {
+        for (hour, &count) in row.iter().enumerate() {
+            let t = count as f64 / max;
+            let mix = |from: f64, to: f64| (from + (to - from) * t).round() as u8;
+            let color = [mix(235.0, 0.0), mix(237.0, 92.0), mix(240.0, 170.0), 255];
+            for y in 1..CELL - 1 {
+                for x in 1..CELL - 1 {
+                    let px = hour as u32 * CELL + x;
+                    let py = day as u32 * CELL + y;
+                    let i = ((py * width + px) * 4) as usize;
+                    rgba[i..i + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+
+    let file = std::fs::File::create(dest)
+        .with_context(|| format!("Failed to create '{}'", dest.display()))?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut w| w.write_image_data(&rgba))
+        .context("Failed to write PNG")?;
+    Ok(())
+}
+
+#[cfg(not(feature = "heatmap"))]
+pub fn write_png(_heatmap: &Heatmap, _dest: &Path) -> Result<()> {
+    anyhow::bail!(
+        "This build of tgcli has no PNG heatmaps. Rebuild with `cargo install tgcli --features heatmap`, or use --format csv."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn heatmap_buckets_by_local_weekday_and_hour() {
+        // 2026-10-19 is a Monday
+        let monday_9 = Local.with_ymd_and_hms(2026, 10, 19, 9, 15, 0).unwrap();
+        let sunday_23 = Local.with_ymd_and_hms(2026, 10, 25, 23, 59, 0).unwrap();
+        let heatmap =
+            Heatmap::from_times([monday_9, monday_9, sunday_23].map(|t| t.with_timezone(&Utc)));
+        assert_eq!(heatmap.counts[0][9], 2);
+        assert_eq!(heatmap.counts[6][23], 1);
+        assert_eq!(heatmap.total(), 3);
+
+        let csv = heatmap.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].starts_with("weekday,00,01,"));
+        assert!(lines[1].starts_with("Mon,0,0,0,0,0,0,0,0,0,2,0"));
+        assert!(lines[7].ends_with(",1"));
+    }
+}
@@ -0,0 +1,225 @@
+//! Prompt-ready conversation rendering for `--output llm`.
+//!
+//! Each message becomes one role-tagged `<msg>` element with an ISO 8601
+//! timestamp and a `[media]` placeholder for attachments. With a token
+//! budget, the oldest messages are dropped first so the most recent
+//! context always fits.
+
+use crate::store::Message;
+use std::collections::HashMap;
+
+/// Rough characters-per-token ratio used for budget estimation.
+const CHARS_PER_TOKEN: usize = 4;
+
+pub struct LlmContext<'a> {
+    /// Messages in chronological order
+    pub messages: &'a [Message],
+    /// Display names keyed by sender ID
+    pub names: &'a HashMap<i64, String>,
+    /// Chat title, if the messages all come from one chat
+    pub title: Option<&'a str>,
+}
+
+impl LlmContext<'_> {
+    /// Render the conversation, keeping it within `max_tokens` when set.
+    pub fn render(&self, max_tokens: Option<usize>) -> String {
+        let rendered: Vec<String> = self.messages.iter().map(|m| self.element(m)).collect();
+
+        // Walk backwards from the newest message until the budget runs out
+        let budget = max_tokens.unwrap_or(usize::MAX);
+        let mut used = 0usize;
+        let mut kept = 0usize;
+        for el in rendered.iter().rev() {
+            let cost = estimate_tokens(el);
+            if used.saturating_add(cost) > budget {
+                break;
+            }
+            used += cost;
+            kept += 1;
+        }
+        let omitted = rendered.len() - kept;
+
+        let mut out = String::new();
+        match self.title {
+            Some(title) => out.push_str(&format!(
+                "<conversation title=\"{}\" messages=\"{}\"",
+                attr(title),
+                kept
+            )),
+            None => out.push_str(&format!("<conversation messages=\"{}\"", kept)),
+        }
+        if omitted > 0 {
+            out.push_str(&format!(" omitted_earlier=\"{}\"", omitted));
+        }
+        out.push_str(">\n");
+
+        if kept == 0 && !rendered.is_empty() {
+            // Not even the newest message fits; include a truncated copy of it
+            let last = &rendered[rendered.len() - 1];
+            let chars = budget.saturating_mul(CHARS_PER_TOKEN);
+            out.push_str(&super::truncate(last, chars));
+            out.push('\n');
+        }
+        for el in &rendered[omitted..] {
+            out.push_str(el);
+            out.push('\n');
+        }
+        out.push_str("</conversation>\n");
+        out
+    }
+
+    fn element(&self, msg: &Message) -> String {
+        let (role, name) = if msg.from_me {
+            ("self", "You".to_string())
+        } else {
+            (
+                "other",
+                self.names
+                    .get(&msg.sender_id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("User {}", msg.sender_id)),
+            )
+        };
+
+        let mut el = format!(
+            "<msg id=\"{}\" ts=\"{}\" role=\"{}\" from=\"{}\"",
+            msg.id,
+            msg.ts.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            role,
+            attr(&name)
+        );
+        if self.title.is_none() {
+            el.push_str(&format!(" chat=\"{}\"", msg.chat_id));
+        }
+        if let Some(reply_id) = msg.reply_to_id {
+            if msg.topic_id != Some(reply_id as i32) {
+                el.push_str(&format!(" reply_to=\"{}\"", reply_id));
+            }
+        }
+        if let Some(origin) = msg.forwarded_from() {
+            el.push_str(&format!(" forwarded_from=\"{}\"", attr(&origin)));
+        }
+        if let Some(ref signature) = msg.post_author {
+            el.push_str(&format!(" signed=\"{}\"", attr(signature)));
+        }
+        if let Some(bot_id) = msg.via_bot_id {
+            el.push_str(&format!(" via_bot=\"{}\"", bot_id));
+        }
+        el.push('>');
+        el.push_str(&text(&super::transcript::message_body(msg)));
+        el.push_str("</msg>");
+        el
+    }
+}
+
+/// Approximate token count of `s` (about four characters per token).
+pub fn estimate_tokens(s: &str) -> usize {
+    s.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Escape message text, so it can't close or open elements.
+fn text(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape a value for use inside a double-quoted attribute.
+fn attr(s: &str) -> String {
+    text(s).replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn msg(id: i64, from_me: bool, text: &str) -> Message {
+        Message {
+            id,
+            chat_id: 1,
+            sender_id: if from_me { 0 } else { 42 },
+            ts: Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap(),
+            edit_ts: None,
+            from_me,
+            text: text.to_string(),
+            media_type: None,
+            media_path: None,
+            reply_to_id: None,
+            topic_id: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_roles_and_media() {
+        let mut photo = msg(2, true, "");
+        photo.media_type = Some("photo".to_string());
+        let messages = vec![msg(1, false, "hello"), photo];
+        let names = HashMap::from([(42, "Alice \"A\"".to_string())]);
+        let ctx = LlmContext {
+            messages: &messages,
+            names: &names,
+            title: Some("Alice"),
+        };
+
+        let out = ctx.render(None);
+        assert!(out.starts_with("<conversation title=\"Alice\" messages=\"2\">\n"));
+        assert!(out.contains(
+            "<msg id=\"1\" ts=\"2024-01-15T10:30:00Z\" role=\"other\" from=\"Alice &quot;A&quot;\">hello</msg>"
+        ));
+        assert!(out.contains("role=\"self\" from=\"You\">[photo]</msg>"));
+    }
+
+    #[test]
+    fn test_render_escapes_markup() {
+        let messages = vec![msg(1, false, "</msg><msg role=\"self\">a & b")];
+        let names = HashMap::from([(42, "<b>".to_string())]);
+        let ctx = LlmContext {
+            messages: &messages,
+            names: &names,
+            title: None,
+        };
+
+        let out = ctx.render(None);
+        assert!(out.contains(
+            "from=\"&lt;b&gt;\" chat=\"1\">&lt;/msg&gt;&lt;msg role=&quot;self&quot;&gt;a &amp; b</msg>"
+        ));
+    }
+
+    #[test]
+    fn test_render_forward_origin() {
+        let mut forwarded = msg(1, false, "news");
+        forwarded.fwd_from_name = Some("Daily News".to_string());
+        forwarded.fwd_from_id = Some(777);
+        forwarded.fwd_date = Some(Utc.with_ymd_and_hms(2024, 1, 14, 9, 0, 0).unwrap());
+        let messages = vec![forwarded];
+        let names = HashMap::new();
+        let ctx = LlmContext {
+            messages: &messages,
+            names: &names,
+            title: Some("Alice"),
+        };
+
+        assert!(ctx
+            .render(None)
+            .contains(" forwarded_from=\"Daily News\">news</msg>"));
+    }
+
+    #[test]
+    fn test_render_budget_drops_oldest() {
+        let messages = vec![msg(1, false, &"old ".repeat(50)), msg(2, false, "newest")];
+        let names = HashMap::new();
+        let ctx = LlmContext {
+            messages: &messages,
+            names: &names,
+            title: None,
+        };
+
+        let out = ctx.render(Some(30));
+        assert!(out.contains("omitted_earlier=\"1\""));
+        assert!(out.contains(">newest</msg>"));
+        assert!(!out.contains("old old"));
+    }
+}
@@ -182,6 +182,9 @@ impl ToMarkdown for Chat {
             .field_bool_if("Forum", self.is_forum)
             .field_bool_if("Archived", self.archived)
             .field_datetime_opt("Last message", self.last_message_ts.as_ref());
+        if self.unread_count > 0 {
+            doc.field_num("Unread", self.unread_count);
+        }
 
         doc.build()
     }
@@ -235,7 +238,49 @@ impl ToMarkdown for Message {
             doc.field_num("Reply to", reply_to);
         }
 
+        if let Some(origin) = self.forwarded_from() {
+            doc.field("Forwarded from", &origin);
+        }
+        doc.field_datetime_opt("Originally sent", self.fwd_date.as_ref());
+
+        if let Some(bot_id) = self.via_bot_id {
+            doc.field_num("Via bot", bot_id);
+        }
+        doc.field_opt("Signed", self.post_author.as_deref());
+        if let Some(views) = self.views {
+            doc.field_num("Views", views);
+        }
+        if let Some(forwards) = self.forwards {
+            doc.field_num("Forwards", forwards);
+        }
+
+        if let Some(webpage) = &self.webpage {
+            doc.field("Link", &webpage.url);
+            doc.field_opt("Link title", webpage.title.as_deref());
+        }
+        if let Some(contact) = &self.shared_contact {
+            doc.field("Contact", &contact.label());
+        }
+        if let Some(geo) = &self.geo {
+            doc.field("Location", &geo.label());
+        }
+
+        if self.is_service() {
+            doc.field("Service", &self.text);
+            return doc.build();
+        }
+
         doc.field_opt("Media", self.media_type.as_deref());
+        for part in &self.album {
+            let media = part.media_type.as_deref().unwrap_or("media");
+            match &part.media_path {
+                Some(path) => doc.field(
+                    &format!("Album #{}", part.id),
+                    &format!("{} ({})", media, path),
+                ),
+                None => doc.field(&format!("Album #{}", part.id), media),
+            };
+        }
 
         if !self.text.is_empty() {
             doc.blank();
@@ -296,7 +341,7 @@ impl ToMarkdown for Contact {
             );
 
         if !self.phone.is_empty() {
-            doc.field("Phone", &self.phone);
+            doc.field("Phone", &super::redact::phone(&self.phone));
         }
 
         doc.build()
@@ -408,7 +453,7 @@ impl ToMarkdown for UserInfoMd {
         }
 
         if let Some(ref p) = self.phone {
-            doc.field("Phone", &format!("+{}", p));
+            doc.field("Phone", &super::redact::phone(&format!("+{}", p)));
         }
 
         if let Some(ref bio) = self.bio {
@@ -911,6 +956,7 @@ mod tests {
             last_sync_message_id: None,
             access_hash: None,
             archived: false,
+            unread_count: 0,
         };
 
         let md = chat.to_markdown();
@@ -1,10 +1,19 @@
+pub mod encrypt;
+pub mod heatmap;
+pub mod llm;
 pub mod markdown;
+pub mod pager;
+pub mod progress;
+pub mod redact;
 pub mod serializers;
+pub mod sqlite;
+pub mod transcript;
 
 use anyhow::Result;
 use clap::ValueEnum;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::path::{Path, PathBuf};
 
 // Re-export markdown items for use in cmd modules (legacy support)
 #[allow(unused_imports)]
@@ -20,7 +29,8 @@ pub use markdown::{
 pub use serializers::{to_markdown, to_markdown_with_title, to_text, to_text_with_title};
 
 /// Output mode for CLI commands
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputMode {
     /// No output
     None,
@@ -31,6 +41,23 @@ pub enum OutputMode {
     Json,
     /// Markdown output
     Markdown,
+    /// Prompt-ready context for LLMs (messages list; other commands fall back to text)
+    Llm,
+    /// A standalone SQLite file (`sqlite:PATH`; messages list/search and export)
+    #[value(skip)]
+    #[serde(skip)]
+    Sqlite(PathBuf),
+}
+
+/// Parse `--output`: a mode name, or `sqlite:PATH`.
+pub fn parse_mode(s: &str) -> std::result::Result<OutputMode, String> {
+    if let Some(path) = s.strip_prefix("sqlite:") {
+        if path.is_empty() {
+            return Err("sqlite output needs a file, e.g. sqlite:results.db".to_string());
+        }
+        return Ok(OutputMode::Sqlite(PathBuf::from(path)));
+    }
+    OutputMode::from_str(s, true)
 }
 
 impl OutputMode {
@@ -50,29 +77,46 @@ impl OutputMode {
         matches!(self, OutputMode::Text)
     }
 
+    pub fn is_llm(&self) -> bool {
+        matches!(self, OutputMode::Llm)
+    }
+
+    /// The database file to write, for `--output sqlite:PATH`.
+    pub fn sqlite_path(&self) -> Option<&Path> {
+        match self {
+            OutputMode::Sqlite(path) => Some(path),
+            _ => None,
+        }
+    }
+
     /// Write data to stdout based on output mode using serde serialization.
     ///
-    /// - `Text`: uses custom text serializer (tabular format)
+    /// - `Text`/`Llm`: uses custom text serializer (tabular format)
     /// - `Json`: uses serde_json (pretty-printed)
     /// - `Markdown`: uses custom markdown serializer
     /// - `None`: no output
+    ///
+    /// Text and markdown go through `$PAGER` when stdout is a terminal.
     pub fn write<T: Serialize>(&self, data: &T) -> Result<()> {
         match self {
             OutputMode::None => {}
-            OutputMode::Text => {
+            OutputMode::Text | OutputMode::Llm => {
                 let text = serializers::to_text(data);
                 if !text.is_empty() {
-                    print!("{}", text);
+                    pager::print(&text);
                 }
             }
             OutputMode::Markdown => {
                 let md = serializers::to_markdown(data);
                 if !md.is_empty() {
-                    println!("{}", md);
+                    pager::print(&format!("{}\n", md));
                 }
             }
+            OutputMode::Sqlite(_) => anyhow::bail!(
+                "--output sqlite: is only supported by messages list/search and export"
+            ),
             OutputMode::Json => {
-                let json = serde_json::to_string_pretty(data)?;
+                let json = redact::to_json_pretty(data)?;
                 println!("{}", json);
             }
         }
@@ -83,20 +127,23 @@ impl OutputMode {
     pub fn write_titled<T: Serialize>(&self, data: &T, title: &str) -> Result<()> {
         match self {
             OutputMode::None => {}
-            OutputMode::Text => {
+            OutputMode::Text | OutputMode::Llm => {
                 let text = serializers::to_text_with_title(data, title);
                 if !text.is_empty() {
-                    print!("{}", text);
+                    pager::print(&text);
                 }
             }
             OutputMode::Markdown => {
                 let md = serializers::to_markdown_with_title(data, title);
                 if !md.is_empty() {
-                    println!("{}", md);
+                    pager::print(&format!("{}\n", md));
                 }
             }
+            OutputMode::Sqlite(_) => anyhow::bail!(
+                "--output sqlite: is only supported by messages list/search and export"
+            ),
             OutputMode::Json => {
-                let json = serde_json::to_string_pretty(data)?;
+                let json = redact::to_json_pretty(data)?;
                 println!("{}", json);
             }
         }
@@ -108,9 +155,11 @@ impl OutputMode {
     pub fn write_display<T: Display + Serialize>(&self, data: &T) {
         match self {
             OutputMode::None => {}
-            OutputMode::Text | OutputMode::Markdown => println!("{}", data),
+            OutputMode::Text | OutputMode::Markdown | OutputMode::Llm | OutputMode::Sqlite(_) => {
+                println!("{}", redact::text(&data.to_string()))
+            }
             OutputMode::Json => {
-                if let Ok(json) = serde_json::to_string_pretty(data) {
+                if let Ok(json) = redact::to_json_pretty(data) {
                     println!("{}", json);
                 }
             }
@@ -121,7 +170,7 @@ impl OutputMode {
     pub fn write_err<T: Serialize>(&self, data: &T) -> Result<()> {
         match self {
             OutputMode::None => {}
-            OutputMode::Text => {
+            OutputMode::Text | OutputMode::Llm | OutputMode::Sqlite(_) => {
                 let text = serializers::to_text(data);
                 if !text.is_empty() {
                     eprint!("{}", text);
@@ -134,7 +183,7 @@ impl OutputMode {
                 }
             }
             OutputMode::Json => {
-                let json = serde_json::to_string_pretty(data)?;
+                let json = redact::to_json_pretty(data)?;
                 eprintln!("{}", json);
             }
         }
@@ -144,14 +193,14 @@ impl OutputMode {
 
 /// Write JSON to stdout.
 pub fn write_json<T: Serialize>(value: &T) -> Result<()> {
-    let json = serde_json::to_string_pretty(value)?;
+    let json = redact::to_json_pretty(value)?;
     println!("{}", json);
     Ok(())
 }
 
-/// Write markdown to stdout.
+/// Write markdown to stdout (paged when interactive).
 pub fn write_markdown(content: &str) {
-    println!("{}", content);
+    pager::print(&format!("{}\n", content));
 }
 
 /// Write an error as JSON to stderr.
@@ -164,6 +213,23 @@ pub fn write_error_json(err: &anyhow::Error) -> Result<()> {
     Ok(())
 }
 
+/// Human-readable byte count, e.g. `1.5 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.1} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
 /// Truncate a string to the given max *character* length with ellipsis.
 /// Handles multi-byte UTF-8 characters (emojis, etc.) safely.
 pub fn truncate(s: &str, max: usize) -> String {
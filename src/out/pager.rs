@@ -0,0 +1,60 @@
+//! Pipe long text/markdown output through `$PAGER` when stdout is a terminal.
+
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const DEFAULT_PAGER: &str = "less -R";
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable paging for the rest of the process (`--no-pager`).
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Print `content` to stdout, through the pager when running interactively.
+/// Falls back to plain stdout if the pager is disabled or cannot be started.
+/// `content` is printed as is; callers redact values before rendering.
+pub fn print(content: &str) {
+    if !ENABLED.load(Ordering::Relaxed) || !std::io::stdout().is_terminal() {
+        print!("{}", content);
+        return;
+    }
+    if !page(content) {
+        print!("{}", content);
+    }
+}
+
+/// Returns false if the pager could not be started.
+fn page(content: &str) -> bool {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        return false;
+    };
+    if program == "cat" {
+        return false;
+    }
+
+    let mut cmd = Command::new(program);
+    cmd.args(parts).stdin(Stdio::piped());
+    // Like git: let less exit immediately when the output fits on one screen
+    if std::env::var_os("LESS").is_none() {
+        cmd.env("LESS", "FRX");
+    }
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            log::debug!("Failed to start pager '{}': {}", pager, e);
+            return false;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user may quit the pager before reading everything (broken pipe)
+        let _ = stdin.write_all(content.as_bytes());
+    }
+    let _ = child.wait();
+    true
+}
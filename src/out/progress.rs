@@ -0,0 +1,90 @@
+//! Progress bars on stderr for sync, backfill, and downloads.
+//!
+//! Bars are drawn only when progress is requested and stderr is a terminal;
+//! otherwise every bar is hidden, so callers never need to check themselves.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+use std::time::Duration;
+
+const TICK: Duration = Duration::from_millis(120);
+
+/// A set of bars drawn together (one overall bar plus per-chat bars).
+#[derive(Clone)]
+pub struct Progress {
+    multi: MultiProgress,
+}
+
+impl Progress {
+    /// `show` is the caller's request (e.g. `!--no-progress`); bars are still
+    /// hidden when stderr is not a TTY.
+    pub fn new(show: bool) -> Self {
+        let target = if show && std::io::stderr().is_terminal() {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        Progress {
+            multi: MultiProgress::with_draw_target(target),
+        }
+    }
+
+    /// Indeterminate status line; update it with `set_message`.
+    pub fn spinner(&self, msg: impl Into<String>) -> ProgressBar {
+        let bar = self.multi.add(ProgressBar::new_spinner());
+        bar.set_style(style("{spinner:.cyan} {msg}"));
+        bar.set_message(msg.into());
+        bar.enable_steady_tick(TICK);
+        bar
+    }
+
+    /// Overall bar over `total` items with ETA; `{msg}` holds extra counters.
+    pub fn items(&self, total: u64, unit: &str) -> ProgressBar {
+        let bar = self.multi.add(ProgressBar::new(total));
+        bar.set_style(style(&format!(
+            "{{spinner:.cyan}} [{{bar:30.cyan/blue}}] {{pos}}/{{len}} {} {{msg}} (ETA {{eta}})",
+            unit
+        )));
+        bar.enable_steady_tick(TICK);
+        bar
+    }
+
+    /// Per-item counter with throughput, e.g. one per chat being synced.
+    pub fn task(&self, name: &str, unit: &str) -> ProgressBar {
+        let bar = self.multi.add(ProgressBar::new_spinner());
+        bar.set_style(style(&format!(
+            "  {{spinner:.green}} {{prefix:30!}} {{pos}} {} ({{per_sec}})",
+            unit
+        )));
+        bar.set_prefix(name.to_string());
+        bar.enable_steady_tick(TICK);
+        bar
+    }
+
+    /// Byte transfer bar with throughput and ETA (spinner if the size is unknown).
+    pub fn bytes(&self, total: Option<u64>, msg: impl Into<String>) -> ProgressBar {
+        let bar = match total {
+            Some(total) => {
+                let bar = self.multi.add(ProgressBar::new(total));
+                bar.set_style(style(
+                    "{msg} [{bar:30.cyan/blue}] {bytes}/{total_bytes} {bytes_per_sec} (ETA {eta})",
+                ));
+                bar
+            }
+            None => {
+                let bar = self.multi.add(ProgressBar::new_spinner());
+                bar.set_style(style("{spinner:.cyan} {msg} {bytes} {bytes_per_sec}"));
+                bar
+            }
+        };
+        bar.set_message(msg.into());
+        bar.enable_steady_tick(TICK);
+        bar
+    }
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ")
+}
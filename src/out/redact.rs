@@ -0,0 +1,335 @@
+//! Redaction mode (`--redact` or `"redact": true` in the config): phone
+//! numbers and email addresses are masked in everything tgcli prints or
+//! exports, for sharing transcripts or handing them to third-party LLMs.
+//!
+//! Values are redacted before they're rendered, never the rendered output,
+//! so timestamps and IDs in tables and transcripts are left alone:
+//! structured output goes through [`value`], messages for transcripts
+//! through [`messages`], and other free text through [`text`].
+
+use crate::store::Message;
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const PHONE_MASK: &str = "[phone]";
+pub const EMAIL_MASK: &str = "[email]";
+
+/// Fields that hold nothing but a phone number or email address.
+const PHONE_FIELDS: &[&str] = &["phone", "phone_number"];
+const EMAIL_FIELDS: &[&str] = &["email"];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Mask phone numbers and emails in free text.
+pub fn text(s: &str) -> Cow<'_, str> {
+    if !is_enabled() {
+        return Cow::Borrowed(s);
+    }
+    let masked = mask_phones(&mask_emails(s));
+    if masked == s {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(masked)
+    }
+}
+
+/// A value that is a phone number on its own (profile, contact card).
+pub fn phone(s: &str) -> Cow<'_, str> {
+    if is_enabled() && !s.is_empty() {
+        Cow::Borrowed(PHONE_MASK)
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Redact a message's text in place, for renderers that lay messages out
+/// themselves (transcripts, LLM context).
+pub fn message(msg: &mut Message) {
+    if !is_enabled() {
+        return;
+    }
+    let mask = |s: &mut String| *s = mask_phones(&mask_emails(s));
+    mask(&mut msg.text);
+    msg.fwd_from_name.iter_mut().for_each(mask);
+    msg.post_author.iter_mut().for_each(mask);
+    msg.edits.iter_mut().for_each(|e| mask(&mut e.text));
+    msg.buttons.iter_mut().for_each(|b| mask(&mut b.text));
+    if let Some(contact) = &mut msg.shared_contact {
+        if !contact.phone.is_empty() {
+            contact.phone = PHONE_MASK.to_string();
+        }
+        mask(&mut contact.first_name);
+        mask(&mut contact.last_name);
+    }
+    if let Some(webpage) = &mut msg.webpage {
+        webpage.title.iter_mut().for_each(mask);
+        webpage.description.iter_mut().for_each(mask);
+    }
+    if let Some(geo) = &mut msg.geo {
+        geo.title.iter_mut().for_each(mask);
+        geo.address.iter_mut().for_each(mask);
+    }
+    msg.service_action.iter_mut().for_each(redact_value);
+}
+
+/// Redact display names keyed by ID in place (an unnamed contact's name may
+/// be their phone number).
+pub fn names(names: &mut HashMap<i64, String>) {
+    if is_enabled() {
+        names
+            .values_mut()
+            .for_each(|name| *name = mask_phones(&mask_emails(name)));
+    }
+}
+
+/// Redact every string in `value`; phone and email fields are masked whole.
+pub fn value(value: &mut Value) {
+    if is_enabled() {
+        redact_value(value);
+    }
+}
+
+/// Pretty JSON of `data`, redacted. Field order is kept when redaction is off.
+pub fn to_json_pretty<T: Serialize>(data: &T) -> serde_json::Result<String> {
+    if !is_enabled() {
+        return serde_json::to_string_pretty(data);
+    }
+    let mut json = serde_json::to_value(data)?;
+    redact_value(&mut json);
+    serde_json::to_string_pretty(&json)
+}
+
+/// Single-line JSON of `data` (JSON Lines), redacted.
+pub fn to_json_line<T: Serialize>(data: &T) -> serde_json::Result<String> {
+    if !is_enabled() {
+        return serde_json::to_string(data);
+    }
+    let mut json = serde_json::to_value(data)?;
+    redact_value(&mut json);
+    serde_json::to_string(&json)
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(s) => {
+            let masked = mask_phones(&mask_emails(s));
+            *s = masked;
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                let whole = if PHONE_FIELDS.contains(&key.as_str()) {
+                    Some(PHONE_MASK)
+                } else if EMAIL_FIELDS.contains(&key.as_str()) {
+                    Some(EMAIL_MASK)
+                } else {
+                    None
+                };
+                match whole {
+                    Some(mask) if !v.is_null() && v.as_str() != Some("") => {
+                        *v = Value::String(mask.to_string());
+                    }
+                    _ => redact_value(v),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_email_local(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._%+-".contains(c)
+}
+
+fn is_email_domain(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '.' || c == '-'
+}
+
+/// Replace `local@domain.tld` with [`EMAIL_MASK`]. `@username` mentions have
+/// no local part and are left alone.
+fn mask_emails(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::with_capacity(s.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '@' {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        // The local part was already copied to `out`; find where it starts
+        let local_len = chars[..i]
+            .iter()
+            .rev()
+            .take_while(|c| is_email_local(**c))
+            .count();
+        let mut end = i + 1;
+        while end < chars.len() && is_email_domain(chars[end]) {
+            end += 1;
+        }
+        while end > i + 1 && matches!(chars[end - 1], '.' | '-') {
+            end -= 1;
+        }
+        let domain: String = chars[i + 1..end].iter().collect();
+        let tld_ok = domain.rsplit_once('.').is_some_and(|(host, tld)| {
+            !host.is_empty() && tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic())
+        });
+        if local_len > 0 && tld_ok {
+            let keep = out.chars().count() - local_len;
+            out = out.chars().take(keep).collect();
+            out.push_str(EMAIL_MASK);
+            i = end;
+        } else {
+            out.push('@');
+            i += 1;
+        }
+    }
+    out
+}
+
+fn is_phone_separator(c: char) -> bool {
+    matches!(c, ' ' | '-' | '.' | '(' | ')')
+}
+
+/// Replace phone numbers with [`PHONE_MASK`]: `+` and 7-15 digits, or 10-15
+/// digits split by spaces, dashes, dots or parentheses (`(415) 555-1234`).
+/// Bare digit runs are left alone, since they are usually IDs, and so are
+/// dates and times.
+fn mask_phones(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::with_capacity(s.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let starts = (c == '+' || c == '(' || c.is_ascii_digit())
+            && (i == 0 || !(chars[i - 1].is_alphanumeric() || chars[i - 1] == '+'));
+        if starts {
+            if let Some(end) = phone_end(&chars, i) {
+                out.push_str(PHONE_MASK);
+                i = end;
+                continue;
+            }
+        }
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+/// End of the phone number starting at `start`, if there is one.
+fn phone_end(chars: &[char], start: usize) -> Option<usize> {
+    let plus = chars[start] == '+';
+    let mut end = start + usize::from(plus);
+    let mut groups: Vec<usize> = Vec::new();
+    let mut run = 0;
+    while end < chars.len() {
+        let c = chars[end];
+        if c.is_ascii_digit() {
+            run += 1;
+        } else if is_phone_separator(c)
+            && end + 1 < chars.len()
+            // Two spaces are a gap between columns, not part of a number
+            && !(c == ' ' && chars[end + 1] == ' ')
+        {
+            if run > 0 {
+                groups.push(run);
+                run = 0;
+            }
+        } else {
+            break;
+        }
+        end += 1;
+    }
+    if run > 0 {
+        groups.push(run);
+    }
+    // Don't swallow trailing separators ("call +1 555 0100 (mobile)")
+    while end > start && !chars[end - 1].is_ascii_digit() {
+        end -= 1;
+    }
+    // A time (12:30) or a number run into a word isn't a phone number
+    if end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == ':') {
+        return None;
+    }
+    let digits: usize = groups.iter().sum();
+    if plus {
+        return (7..=15).contains(&digits).then_some(end);
+    }
+    // Nor is anything with an ISO date in it (2025-01-31 12, 7 2025-01-31)
+    let date = groups.windows(3).any(|g| g == [4, 2, 2]);
+    (groups.len() > 1 && (10..=15).contains(&digits) && !date).then_some(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_phone_numbers() {
+        assert_eq!(mask_phones("call +14155551234 now"), "call [phone] now");
+        assert_eq!(mask_phones("+44 20 7946 0958."), "[phone].");
+        assert_eq!(mask_phones("(415) 555-1234"), "[phone]");
+        assert_eq!(mask_phones("415.555.1234 (mobile)"), "[phone] (mobile)");
+        // IDs, dates, times and short numbers stay
+        assert_eq!(mask_phones("chat -1001234567890"), "chat -1001234567890");
+        assert_eq!(
+            mask_phones("chat 1234567890 (Work)"),
+            "chat 1234567890 (Work)"
+        );
+        assert_eq!(mask_phones("2025-01-31 12:30"), "2025-01-31 12:30");
+        assert_eq!(mask_phones("version 1.2.3"), "version 1.2.3");
+        assert_eq!(mask_phones("abc+14155551234"), "abc+14155551234");
+    }
+
+    #[test]
+    fn leaves_listing_timestamps_alone() {
+        // A `messages list` row: ID, TS and text columns
+        let row = "4821      2025-01-31 12:00:00  Alice  call me on (415) 555-1234";
+        assert_eq!(
+            mask_phones(row),
+            "4821      2025-01-31 12:00:00  Alice  call me on [phone]"
+        );
+        assert_eq!(
+            mask_phones("1234567  2025-01-31T12:00:00Z"),
+            "1234567  2025-01-31T12:00:00Z"
+        );
+        assert_eq!(mask_phones("07 2025 01 31 12:30"), "07 2025 01 31 12:30");
+    }
+
+    #[test]
+    fn masks_emails_but_not_mentions() {
+        assert_eq!(
+            mask_emails("write to ada.l+tg@example.co.uk."),
+            "write to [email]."
+        );
+        assert_eq!(mask_emails("ask @alice"), "ask @alice");
+        assert_eq!(mask_emails("a@b"), "a@b");
+    }
+
+    #[test]
+    fn redacts_json_fields() {
+        let mut json = serde_json::json!({
+            "id": 4155551234_i64,
+            "phone": "14155551234",
+            "text": "mail me: me@example.com",
+            "contacts": [{"phone": "", "name": "+1 415 555 1234"}],
+        });
+        redact_value(&mut json);
+        assert_eq!(json["id"], 4155551234_i64);
+        assert_eq!(json["phone"], PHONE_MASK);
+        assert_eq!(json["text"], "mail me: [email]");
+        assert_eq!(json["contacts"][0]["phone"], "");
+        assert_eq!(json["contacts"][0]["name"], PHONE_MASK);
+    }
+}
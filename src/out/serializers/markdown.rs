@@ -62,10 +62,11 @@ pub fn to_markdown_with_title<T: Serialize>(value: &T, title: &str) -> String {
 
 /// Convert a serializable value to markdown with full configuration.
 pub fn to_markdown_configured<T: Serialize>(value: &T, config: &MarkdownConfig) -> String {
-    let json = match serde_json::to_value(value) {
+    let mut json = match serde_json::to_value(value) {
         Ok(v) => v,
         Err(_) => return String::new(),
     };
+    crate::out::redact::value(&mut json);
 
     let mut output = String::new();
 
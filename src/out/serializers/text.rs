@@ -79,10 +79,11 @@ pub fn to_text_with_title<T: Serialize>(value: &T, _title: &str) -> String {
 
 /// Convert a serializable value to plain text with full configuration.
 pub fn to_text_configured<T: Serialize>(value: &T, config: &TextConfig) -> String {
-    let json = match serde_json::to_value(value) {
+    let mut json = match serde_json::to_value(value) {
         Ok(v) => v,
         Err(_) => return String::new(),
     };
+    crate::out::redact::value(&mut json);
 
     match json {
         Value::Array(arr) => format_table(&arr, config),
@@ -277,7 +278,16 @@ fn auto_detect_columns(items: &[Value]) -> Vec<ColumnDef> {
             if !key.starts_with('_')
                 && !matches!(
                     key.as_str(),
-                    "access_hash" | "last_sync_message_id" | "snippet" | "media_path"
+                    "access_hash"
+                        | "last_sync_message_id"
+                        | "snippet"
+                        | "media_path"
+                        | "service_action"
+                        | "album"
+                        | "geo"
+                        | "shared_contact"
+                        | "webpage"
+                        | "buttons"
                 )
             {
                 columns.push(ColumnDef::new(key));
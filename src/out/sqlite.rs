@@ -0,0 +1,224 @@
+//! `--output sqlite:PATH`: result rows written to a table of a standalone
+//! SQLite file, for querying with other tools.
+//!
+//! Columns follow the rows' JSON fields and are added as new fields show
+//! up, so rows can be inserted as they're read. Numbers and strings keep
+//! their type, booleans become 0/1 and nested values are stored as JSON.
+
+use super::redact;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use turso::{Builder, Connection, Database};
+
+/// A table being filled; call `finish` to commit it.
+pub struct Table {
+    _db: Database,
+    conn: Connection,
+    path: PathBuf,
+    name: String,
+    columns: Vec<String>,
+    rows: usize,
+}
+
+impl Table {
+    /// Create (or replace) table `name` in the database at `path`. Other
+    /// tables in the file are left alone.
+    pub async fn create(path: &Path, name: &str) -> Result<Self> {
+        let db = Builder::new_local(&path.to_string_lossy())
+            .build()
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let conn = db.connect()?;
+        conn.execute(&format!("DROP TABLE IF EXISTS {}", ident(name)), ())
+            .await?;
+        // Columns are added with the first row that has them
+        conn.execute(
+            &format!("CREATE TABLE {} (_row INTEGER PRIMARY KEY)", ident(name)),
+            (),
+        )
+        .await?;
+        conn.execute("BEGIN", ()).await?;
+        Ok(Table {
+            _db: db,
+            conn,
+            path: path.to_path_buf(),
+            name: name.to_string(),
+            columns: Vec::new(),
+            rows: 0,
+        })
+    }
+
+    pub async fn insert<T: Serialize>(&mut self, row: &T) -> Result<()> {
+        let mut row = serde_json::to_value(row)?;
+        redact::value(&mut row);
+        let Value::Object(fields) = row else {
+            anyhow::bail!("Only objects can be written as SQLite rows");
+        };
+
+        let mut names = Vec::with_capacity(fields.len());
+        let mut params = Vec::with_capacity(fields.len());
+        for (key, value) in fields {
+            if !self.columns.contains(&key) {
+                self.conn
+                    .execute(
+                        &format!(
+                            "ALTER TABLE {} ADD COLUMN {} {}",
+                            ident(&self.name),
+                            ident(&key),
+                            column_type(&value)
+                        ),
+                        (),
+                    )
+                    .await?;
+                self.columns.push(key.clone());
+            }
+            names.push(ident(&key));
+            params.push(sql_value(value));
+        }
+
+        let placeholders: Vec<String> = (1..=params.len()).map(|i| format!("?{}", i)).collect();
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    ident(&self.name),
+                    names.join(", "),
+                    placeholders.join(", ")
+                ),
+                turso::params_from_iter(params),
+            )
+            .await?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Commit the rows and say where they went (on stderr).
+    pub async fn finish(self) -> Result<()> {
+        self.conn.execute("COMMIT", ()).await?;
+        // Move the rows from the -wal sidecar into the file itself, so the
+        // .db file alone is a complete database
+        self.conn
+            .query("PRAGMA wal_checkpoint(TRUNCATE)", ())
+            .await?
+            .next()
+            .await?;
+        eprintln!(
+            "Wrote {} rows to table '{}' in {}",
+            self.rows,
+            self.name,
+            self.path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Write `rows` to table `name` of the database at `path`.
+pub async fn write_rows<T: Serialize>(path: &Path, name: &str, rows: &[T]) -> Result<()> {
+    let mut table = Table::create(path, name).await?;
+    for row in rows {
+        table.insert(row).await?;
+    }
+    table.finish().await
+}
+
+fn ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Declared type of a column first seen with `value`; none for a null, so
+/// later values are stored as they come.
+fn column_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "",
+        Value::Bool(_) => "INTEGER",
+        Value::Number(n) if n.is_i64() => "INTEGER",
+        Value::Number(_) => "REAL",
+        Value::String(_) => "TEXT",
+        Value::Array(_) | Value::Object(_) => "TEXT",
+    }
+}
+
+fn sql_value(value: Value) -> turso::Value {
+    match value {
+        Value::Null => turso::Value::Null,
+        Value::Bool(b) => turso::Value::Integer(b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => turso::Value::Integer(i),
+            None => turso::Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => turso::Value::Text(s),
+        nested => turso::Value::Text(nested.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rows_gain_columns_as_fields_appear() {
+        let dir = std::env::temp_dir().join(format!("tgcli-sqlite-out-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("results.db");
+
+        let rows = vec![
+            serde_json::json!({"id": 1, "text": "hi", "from_me": true}),
+            serde_json::json!({"id": 2, "text": "yo", "reactions": [{"reaction": "👍"}]}),
+        ];
+        write_rows(&path, "messages", &rows).await.unwrap();
+
+        let db = Builder::new_local(&path.to_string_lossy())
+            .build()
+            .await
+            .unwrap();
+        let conn = db.connect().unwrap();
+        let mut out = conn
+            .query(
+                "SELECT id, text, from_me, reactions FROM messages ORDER BY id",
+                (),
+            )
+            .await
+            .unwrap();
+        let first = out.next().await.unwrap().unwrap();
+        assert_eq!(first.get::<i64>(2).unwrap(), 1);
+        assert!(first.get::<Option<String>>(3).unwrap().is_none());
+        let second = out.next().await.unwrap().unwrap();
+        assert_eq!(second.get::<String>(1).unwrap(), "yo");
+        assert_eq!(second.get::<String>(3).unwrap(), r#"[{"reaction":"👍"}]"#);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn the_db_file_alone_holds_the_rows() {
+        let dir = std::env::temp_dir().join(format!("tgcli-sqlite-copy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("results.db");
+        let rows: Vec<_> = (1..=100)
+            .map(|id| serde_json::json!({"id": id, "text": "hi"}))
+            .collect();
+        write_rows(&path, "messages", &rows).await.unwrap();
+
+        // Only the .db file, as someone copying the export would
+        let copy_dir = dir.join("copy");
+        std::fs::create_dir_all(&copy_dir).unwrap();
+        let copy = copy_dir.join("results.db");
+        std::fs::copy(&path, &copy).unwrap();
+
+        let db = Builder::new_local(&copy.to_string_lossy())
+            .build()
+            .await
+            .unwrap();
+        let conn = db.connect().unwrap();
+        let mut out = conn
+            .query("SELECT COUNT(*) FROM messages", ())
+            .await
+            .unwrap();
+        let count: i64 = out.next().await.unwrap().unwrap().get(0).unwrap();
+        assert_eq!(count, 100);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,476 @@
+//! Chat-style transcript rendering for `messages view`.
+//!
+//! Messages are grouped under day separators; outgoing messages are
+//! right-aligned in text mode, and replies show a short preview of the
+//! message they answer. Bot keyboards are drawn under their message.
+
+use crate::store::{Message, MessageWebpage};
+use chrono::{DateTime, Local, Utc};
+use std::collections::HashMap;
+
+/// Default transcript width in columns (text mode).
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Max characters of a replied-to message shown in the preview line.
+const REPLY_PREVIEW_LEN: usize = 50;
+/// Characters of a link preview's description shown under the message.
+const LINK_DESCRIPTION_LEN: usize = 200;
+
+pub struct Transcript<'a> {
+    /// Messages in chronological order
+    pub messages: &'a [Message],
+    /// Display names keyed by sender ID
+    pub names: &'a HashMap<i64, String>,
+    /// Replied-to messages keyed by message ID
+    pub replies: &'a HashMap<i64, Message>,
+}
+
+impl Transcript<'_> {
+    /// Render as plain text, `width` columns wide.
+    pub fn to_text(&self, width: usize) -> String {
+        let width = width.max(20);
+        let wrap_at = width * 2 / 3;
+        let mut lines: Vec<String> = Vec::new();
+        let mut current_day: Option<String> = None;
+
+        for msg in self.messages {
+            let ts = local(msg.ts);
+            let day = ts.format("%A, %d %b %Y").to_string();
+            if current_day.as_ref() != Some(&day) {
+                if !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                lines.push(day_separator(&day, width));
+                current_day = Some(day);
+            }
+            lines.push(String::new());
+
+            // Service messages ("Alice pinned a message") are centered, no bubble
+            if msg.is_service() {
+                let line = format!("{} {} · {}", self.sender(msg), msg.text, ts.format("%H:%M"));
+                for line in wrap(&line, width) {
+                    let pad = width.saturating_sub(line.chars().count()) / 2;
+                    lines.push(format!("{}{}", " ".repeat(pad), line));
+                }
+                continue;
+            }
+
+            let mut block = vec![format!("{} · {}", self.author(msg), ts.format("%H:%M"))];
+            if let Some(origin) = msg.forwarded_from() {
+                block.push(format!("↪ Forwarded from {}", origin));
+            }
+            if let Some(preview) = self.reply_preview(msg) {
+                block.push(format!("↳ {}", preview));
+            }
+            for line in message_body(msg).lines() {
+                block.extend(wrap(line, wrap_at));
+            }
+            if let Some(webpage) = &msg.webpage {
+                for line in link_preview(webpage) {
+                    block.extend(wrap(&line, wrap_at).into_iter().map(|l| format!("┃ {}", l)));
+                }
+            }
+            block.extend(button_rows(msg));
+
+            for line in block {
+                if msg.from_me {
+                    let pad = width.saturating_sub(line.chars().count());
+                    lines.push(format!("{}{}", " ".repeat(pad), line));
+                } else {
+                    lines.push(line);
+                }
+            }
+        }
+
+        let mut out = lines.join("\n");
+        out.push('\n');
+        out
+    }
+
+    /// Render as markdown with `##` day headings.
+    pub fn to_markdown(&self, title: &str) -> String {
+        let mut out = format!("# {}\n", title);
+        let mut current_day: Option<String> = None;
+
+        for msg in self.messages {
+            let ts = local(msg.ts);
+            let day = ts.format("%A, %d %b %Y").to_string();
+            if current_day.as_ref() != Some(&day) {
+                out.push_str(&format!("\n## {}\n", day));
+                current_day = Some(day);
+            }
+
+            if msg.is_service() {
+                out.push_str(&format!(
+                    "\n*{} {}* · {}\n",
+                    self.sender(msg),
+                    msg.text,
+                    ts.format("%H:%M")
+                ));
+                continue;
+            }
+
+            let arrow = if msg.from_me { " →" } else { "" };
+            out.push_str(&format!(
+                "\n**{}** · {}{}\n",
+                self.author(msg),
+                ts.format("%H:%M"),
+                arrow
+            ));
+            if let Some(origin) = msg.forwarded_from() {
+                out.push_str(&format!("> ↪ Forwarded from {}\n\n", origin));
+            }
+            if let Some(preview) = self.reply_preview(msg) {
+                out.push_str(&format!("> ↳ {}\n\n", preview));
+            }
+            let text = message_body(msg);
+            if !text.is_empty() {
+                out.push_str(&text);
+                out.push('\n');
+            }
+            if let Some(webpage) = &msg.webpage {
+                let mut preview = link_preview(webpage);
+                preview[0] = format!("[{}]({})", preview[0], webpage.url);
+                out.push('\n');
+                for line in preview {
+                    out.push_str(&format!("> {}\n", line));
+                }
+            }
+            let rows = button_rows(msg);
+            if !rows.is_empty() {
+                out.push_str(&format!("\n{}\n", rows.join("  \n")));
+            }
+        }
+        out
+    }
+
+    fn sender(&self, msg: &Message) -> String {
+        if msg.from_me {
+            return "You".to_string();
+        }
+        self.names
+            .get(&msg.sender_id)
+            .cloned()
+            .unwrap_or_else(|| format!("User {}", msg.sender_id))
+    }
+
+    /// Sender plus channel signature and inline bot, for message headers.
+    fn author(&self, msg: &Message) -> String {
+        let mut author = self.sender(msg);
+        if let Some(ref signature) = msg.post_author {
+            author.push_str(&format!(" ({})", signature));
+        }
+        if let Some(bot_id) = msg.via_bot_id {
+            let bot = self
+                .names
+                .get(&bot_id)
+                .cloned()
+                .unwrap_or_else(|| format!("bot {}", bot_id));
+            author.push_str(&format!(" via {}", bot));
+        }
+        author
+    }
+
+    fn reply_preview(&self, msg: &Message) -> Option<String> {
+        let reply_id = msg.reply_to_id?;
+        // In forums, replies to the topic root are just "posted in topic"
+        if msg.topic_id == Some(reply_id as i32) {
+            return None;
+        }
+        Some(match self.replies.get(&reply_id) {
+            Some(orig) => {
+                let text = message_body(orig).replace('\n', " ");
+                format!(
+                    "{}: {}",
+                    self.sender(orig),
+                    super::truncate(&text, REPLY_PREVIEW_LEN)
+                )
+            }
+            None => format!("reply to #{}", reply_id),
+        })
+    }
+}
+
+fn local(ts: DateTime<Utc>) -> DateTime<Local> {
+    ts.with_timezone(&Local)
+}
+
+/// Message text with a `[media]` placeholder prepended for attachments
+/// (`[album: photo, video]` for albums). Service messages are set off as
+/// `— pinned a message —`.
+pub fn message_body(msg: &Message) -> String {
+    if msg.is_service() {
+        return format!("— {} —", msg.text);
+    }
+    if !msg.album.is_empty() {
+        let parts: Vec<&str> = msg
+            .album
+            .iter()
+            .map(|p| p.media_type.as_deref().unwrap_or("media"))
+            .collect();
+        return with_caption(format!("[album: {}]", parts.join(", ")), &msg.text);
+    }
+    if let Some(contact) = &msg.shared_contact {
+        return with_caption(format!("[contact: {}]", contact.label()), &msg.text);
+    }
+    if let Some(geo) = &msg.geo {
+        return with_caption(format!("[{}: {}]", geo.kind, geo.label()), &msg.text);
+    }
+    if let Some(stars) = msg.paid_stars {
+        return with_caption(format!("[paid media: {} stars]", stars), &msg.text);
+    }
+    match (&msg.media_type, msg.text.is_empty()) {
+        (Some(media), true) => format!("[{}]", media),
+        (Some(media), false) => format!("[{}] {}", media, msg.text),
+        (None, _) => msg.text.clone(),
+    }
+}
+
+/// Link preview lines: site and title (or the domain), then the description.
+fn link_preview(webpage: &MessageWebpage) -> Vec<String> {
+    let heading: Vec<&str> = [webpage.site_name.as_deref(), webpage.title.as_deref()]
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .collect();
+    let heading = if heading.is_empty() {
+        webpage
+            .domain
+            .clone()
+            .unwrap_or_else(|| webpage.url.clone())
+    } else {
+        heading.join(" · ")
+    };
+    let mut lines = vec![heading];
+    if let Some(description) = webpage.description.as_deref().filter(|d| !d.is_empty()) {
+        lines.push(super::truncate(
+            &description.replace('\n', " "),
+            LINK_DESCRIPTION_LEN,
+        ));
+    }
+    lines
+}
+
+/// Keyboard of a bot message, one line per row: `[ Yes ] [ No ] [ Docs ↗ ]`.
+fn button_rows(msg: &Message) -> Vec<String> {
+    let mut rows: Vec<String> = Vec::new();
+    let mut current_row = None;
+    for button in &msg.buttons {
+        let label = if button.url.is_some() {
+            format!("[ {} ↗ ]", button.text)
+        } else {
+            format!("[ {} ]", button.text)
+        };
+        match rows.last_mut() {
+            Some(line) if current_row == Some(button.row) => {
+                line.push(' ');
+                line.push_str(&label);
+            }
+            _ => rows.push(label),
+        }
+        current_row = Some(button.row);
+    }
+    rows
+}
+
+fn with_caption(label: String, text: &str) -> String {
+    if text.is_empty() {
+        label
+    } else {
+        format!("{} {}", label, text)
+    }
+}
+
+fn day_separator(day: &str, width: usize) -> String {
+    let label = format!(" {} ", day);
+    let fill = width.saturating_sub(label.chars().count());
+    let left = fill / 2;
+    format!("{}{}{}", "─".repeat(left), label, "─".repeat(fill - left))
+}
+
+/// Word-wrap a single line to at most `max` characters per line.
+fn wrap(line: &str, max: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        let needed = if current.is_empty() {
+            word.chars().count()
+        } else {
+            current.chars().count() + 1 + word.chars().count()
+        };
+        if needed > max && !current.is_empty() {
+            out.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() || out.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(id: i64, from_me: bool, text: &str, reply_to_id: Option<i64>) -> Message {
+        Message {
+            id,
+            chat_id: 1,
+            sender_id: if from_me { 0 } else { 42 },
+            ts: Utc::now(),
+            edit_ts: None,
+            from_me,
+            text: text.to_string(),
+            media_type: None,
+            media_path: None,
+            reply_to_id,
+            topic_id: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("one two three", 7), vec!["one two", "three"]);
+        assert_eq!(wrap("", 10), vec![""]);
+    }
+
+    #[test]
+    fn test_transcript_text() {
+        let first = msg(1, false, "hello", None);
+        let messages = vec![first.clone(), msg(2, true, "hi back", Some(1))];
+        let names = HashMap::from([(42, "Alice".to_string())]);
+        let replies = HashMap::from([(1, first)]);
+        let t = Transcript {
+            messages: &messages,
+            names: &names,
+            replies: &replies,
+        };
+
+        let text = t.to_text(40);
+        assert!(text.starts_with('─'));
+        assert!(text.contains("\nAlice · "));
+        assert!(text.contains("↳ Alice: hello"));
+        // Outgoing lines are right-aligned
+        assert!(text.contains(&format!("{}hi back", " ".repeat(33))));
+    }
+
+    #[test]
+    fn test_transcript_author_attribution() {
+        let mut post = msg(1, false, "gif", None);
+        post.post_author = Some("Bob".to_string());
+        post.via_bot_id = Some(7);
+        let messages = vec![post];
+        let names = HashMap::from([(42, "News".to_string()), (7, "@gif".to_string())]);
+        let replies = HashMap::new();
+        let t = Transcript {
+            messages: &messages,
+            names: &names,
+            replies: &replies,
+        };
+
+        assert!(t.to_text(60).contains("\nNews (Bob) via @gif · "));
+    }
+
+    #[test]
+    fn test_transcript_service_message() {
+        let mut pinned = msg(1, false, "pinned a message", None);
+        pinned.media_type = Some(crate::store::SERVICE_MEDIA_TYPE.to_string());
+        let messages = vec![pinned.clone()];
+        let names = HashMap::from([(42, "Alice".to_string())]);
+        let replies = HashMap::new();
+        let t = Transcript {
+            messages: &messages,
+            names: &names,
+            replies: &replies,
+        };
+
+        assert!(t
+            .to_markdown("Chat")
+            .contains("*Alice pinned a message* · "));
+        assert_eq!(message_body(&pinned), "— pinned a message —");
+    }
+
+    #[test]
+    fn test_transcript_link_preview() {
+        let mut link = msg(1, false, "look https://github.com/dgrr/tgcli", None);
+        link.webpage = Some(MessageWebpage {
+            url: "https://github.com/dgrr/tgcli".to_string(),
+            domain: Some("github.com".to_string()),
+            site_name: Some("GitHub".to_string()),
+            title: Some("dgrr/tgcli".to_string()),
+            description: Some("Telegram CLI".to_string()),
+        });
+        let messages = vec![link];
+        let names = HashMap::new();
+        let replies = HashMap::new();
+        let t = Transcript {
+            messages: &messages,
+            names: &names,
+            replies: &replies,
+        };
+
+        let text = t.to_text(80);
+        assert!(text.contains("\n┃ GitHub · dgrr/tgcli\n┃ Telegram CLI\n"));
+        assert!(t
+            .to_markdown("Chat")
+            .contains("> [GitHub · dgrr/tgcli](https://github.com/dgrr/tgcli)\n> Telegram CLI\n"));
+    }
+
+    #[test]
+    fn test_transcript_buttons() {
+        let mut prompt = msg(1, false, "Delete 3 files?", None);
+        let button = |row, col, text: &str, url: Option<&str>| crate::store::MessageButton {
+            row,
+            col,
+            text: text.to_string(),
+            kind: if url.is_some() { "url" } else { "callback" }.to_string(),
+            url: url.map(str::to_string),
+        };
+        prompt.buttons = vec![
+            button(0, 0, "Confirm", None),
+            button(0, 1, "Cancel", None),
+            button(1, 0, "Help", Some("https://example.com/help")),
+        ];
+        let messages = vec![prompt];
+        let names = HashMap::new();
+        let replies = HashMap::new();
+        let t = Transcript {
+            messages: &messages,
+            names: &names,
+            replies: &replies,
+        };
+
+        assert!(t
+            .to_text(80)
+            .contains("Delete 3 files?\n[ Confirm ] [ Cancel ]\n[ Help ↗ ]\n"));
+        assert!(t
+            .to_markdown("Chat")
+            .contains("\n[ Confirm ] [ Cancel ]  \n[ Help ↗ ]\n"));
+    }
+
+    #[test]
+    fn test_message_body_venue() {
+        let mut venue = msg(1, false, "", None);
+        venue.media_type = Some("venue".to_string());
+        venue.geo = Some(crate::store::MessageGeo {
+            kind: "venue".to_string(),
+            lat: 52.51714,
+            lon: 13.38855,
+            accuracy_radius: None,
+            title: Some("Café Einstein".to_string()),
+            address: Some("Unter den Linden 42".to_string()),
+            provider: None,
+            venue_id: None,
+            live_period: None,
+        });
+        assert_eq!(
+            message_body(&venue),
+            "[venue: Café Einstein, Unter den Linden 42 (52.517140, 13.388550)]"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Cross-platform path handling: `~` expansion, where tgcli keeps its files,
+//! and file names that are safe to create on every OS.
+//!
+//! Without `--store`, files follow the XDG base directory spec: session and
+//! database under `$XDG_DATA_HOME/tgcli`, `config.json` under
+//! `$XDG_CONFIG_HOME/tgcli`, thumbnails under `$XDG_CACHE_HOME/tgcli`. macOS
+//! and Windows keep the single `~/.tgcli` directory unless an XDG variable is
+//! set. `--store` always puts everything in one directory.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// The pre-XDG default store, relative to the home directory.
+const LEGACY_STORE: &str = ".tgcli";
+/// Entries of a legacy store that belong in the cache directory.
+const CACHE_ENTRIES: &[&str] = &["thumbnails"];
+/// Entries of a legacy store that belong in the config directory.
+const CONFIG_ENTRIES: &[&str] = &["config.json"];
+
+/// Names Windows refuses as file names, with or without an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Expand a leading `~` to the home directory (`~/x` and, on Windows, `~\x`).
+pub fn expand_home(path: &str) -> PathBuf {
+    let rest = if path == "~" {
+        Some("")
+    } else {
+        path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\"))
+    };
+    match (rest, dirs::home_dir()) {
+        (Some(""), Some(home)) => home,
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// The grammers session file inside the store.
+pub fn session_path(store_dir: &str) -> String {
+    Path::new(store_dir)
+        .join("session.db")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Where tgcli keeps its files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dirs {
+    /// Session, database, media and logs
+    pub data: PathBuf,
+    /// `config.json`
+    pub config: PathBuf,
+    /// Thumbnails and other files that can be rebuilt
+    pub cache: PathBuf,
+}
+
+impl Dirs {
+    /// Everything in one directory (`--store`, or `~/.tgcli`).
+    pub fn single(dir: PathBuf) -> Self {
+        Dirs {
+            data: dir.clone(),
+            config: dir.clone(),
+            cache: dir,
+        }
+    }
+
+    /// `--store` when given, the platform default otherwise.
+    pub fn resolve(store: Option<&str>) -> Self {
+        match store {
+            Some(store) => Dirs::single(expand_home(store)),
+            None => Dirs::default_layout(),
+        }
+    }
+
+    fn default_layout() -> Self {
+        let xdg_set = ["XDG_DATA_HOME", "XDG_CONFIG_HOME", "XDG_CACHE_HOME"]
+            .iter()
+            .any(|var| xdg_var(var).is_some());
+        let home = dirs::home_dir().unwrap_or_default();
+        if cfg!(any(target_os = "macos", windows)) && !xdg_set {
+            return Dirs::single(home.join(LEGACY_STORE));
+        }
+        let base = |var: &str, fallback: &str| {
+            xdg_var(var)
+                .unwrap_or_else(|| home.join(fallback))
+                .join("tgcli")
+        };
+        Dirs {
+            data: base("XDG_DATA_HOME", ".local/share"),
+            config: base("XDG_CONFIG_HOME", ".config"),
+            cache: base("XDG_CACHE_HOME", ".cache"),
+        }
+    }
+
+    /// An existing `~/.tgcli` still to be moved into this layout: only while
+    /// the data directory has no session or database yet.
+    pub fn legacy_pending(&self) -> Option<PathBuf> {
+        let legacy = dirs::home_dir()?.join(LEGACY_STORE);
+        if !legacy.is_dir()
+            || self.data == legacy
+            || self.data.join("session.db").exists()
+            || self.data.join("tgcli.db").exists()
+        {
+            return None;
+        }
+        Some(legacy)
+    }
+
+    /// Move an existing `~/.tgcli` into this layout, once (see
+    /// `legacy_pending`). Returns the legacy directory when something was
+    /// moved. Stored media paths still point into it; see
+    /// `Store::move_media_paths`.
+    pub fn migrate_legacy(&self) -> Result<Option<PathBuf>> {
+        let Some(legacy) = self.legacy_pending() else {
+            return Ok(None);
+        };
+
+        let entries = std::fs::read_dir(&legacy)
+            .with_context(|| format!("Failed to read {}", legacy.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name();
+            let target_dir = match name.to_str() {
+                Some(n) if CONFIG_ENTRIES.contains(&n) => &self.config,
+                Some(n) if CACHE_ENTRIES.contains(&n) => &self.cache,
+                _ => &self.data,
+            };
+            let target = target_dir.join(&name);
+            if target.exists() {
+                continue;
+            }
+            std::fs::create_dir_all(target_dir)
+                .with_context(|| format!("Failed to create {}", target_dir.display()))?;
+            move_path(&entry.path(), &target).with_context(|| {
+                format!(
+                    "Failed to move {} to {}",
+                    entry.path().display(),
+                    target.display()
+                )
+            })?;
+        }
+        // Only succeeds when everything was moved
+        let _ = std::fs::remove_dir(&legacy);
+        Ok(Some(legacy))
+    }
+}
+
+/// An XDG variable, ignored unless it is an absolute path (as the spec says).
+fn xdg_var(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+}
+
+/// Rename, falling back to copy and delete across filesystems.
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            move_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        std::fs::remove_dir(from)
+    } else {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)
+    }
+}
+
+/// Make a name from Telegram (document names, titles) usable as a file name:
+/// no path separators, no characters Windows rejects, no reserved device names.
+pub fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Windows strips trailing dots and spaces, which can change the name
+    let cleaned = cleaned.trim_end_matches(['.', ' ']).trim_start();
+    let cleaned = match cleaned {
+        "" | "." | ".." => "file".to_string(),
+        name => name.to_string(),
+    };
+    let stem = cleaned.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        format!("_{}", cleaned)
+    } else {
+        cleaned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_home_prefixes() {
+        let Some(home) = dirs::home_dir() else { return };
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("~/.tgcli"), home.join(".tgcli"));
+        assert_eq!(expand_home("~\\.tgcli"), home.join(".tgcli"));
+        assert_eq!(expand_home("/data/tgcli"), PathBuf::from("/data/tgcli"));
+        assert_eq!(expand_home("~other/x"), PathBuf::from("~other/x"));
+    }
+
+    #[test]
+    fn store_flag_keeps_everything_together() {
+        let dirs = Dirs::resolve(Some("/data/tgcli-work"));
+        assert_eq!(dirs.data, PathBuf::from("/data/tgcli-work"));
+        assert_eq!(dirs.config, dirs.data);
+        assert_eq!(dirs.cache, dirs.data);
+    }
+
+    #[test]
+    fn sanitizes_file_names() {
+        assert_eq!(sanitize_file_name("report.pdf"), "report.pdf");
+        assert_eq!(sanitize_file_name("../../etc/passwd"), ".._.._etc_passwd");
+        assert_eq!(sanitize_file_name("a:b?c*.txt"), "a_b_c_.txt");
+        assert_eq!(sanitize_file_name("notes. "), "notes");
+        assert_eq!(sanitize_file_name(".."), "file");
+        assert_eq!(sanitize_file_name("con.txt"), "_con.txt");
+        assert_eq!(sanitize_file_name("console.txt"), "console.txt");
+    }
+}
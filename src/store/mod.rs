@@ -1,12 +1,26 @@
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
-use turso::{Builder, Connection, Database, Row};
+use std::time::Duration;
+use turso::{Builder, Connection, Database, IntoParams, Row, Rows, Statement};
+
+/// Bumped whenever `migrate` changes the schema; stored in `PRAGMA user_version`.
+pub const SCHEMA_VERSION: i64 = 14;
+
+/// `media_type` of service messages (joins, pins, title changes, calls, ...).
+pub const SERVICE_MEDIA_TYPE: &str = "service";
 
 pub struct Store {
     db: Database, // Must keep Database alive - dropping it invalidates connections
     has_fts: bool,
+    fts_tokenizer: FtsTokenizer,
+    /// Connection for the statements sync runs once per message, each
+    /// prepared on first use and reused for as long as this `Store` lives
+    hot: Connection,
+    statements: tokio::sync::Mutex<HashMap<&'static str, Statement>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +40,302 @@ pub struct Chat {
     /// Whether this chat is in the Archive folder
     #[serde(default)]
     pub archived: bool,
+    /// Unread message count as of the last sync
+    #[serde(default)]
+    pub unread_count: i32,
+}
+
+/// Ordering for chat listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ChatSort {
+    /// Most recent activity first
+    #[default]
+    Recent,
+    /// Most unread messages first, then most recent
+    Unread,
+    /// Alphabetical by name
+    Name,
+}
+
+impl ChatSort {
+    fn order_by(self) -> &'static str {
+        match self {
+            ChatSort::Recent => "last_message_ts DESC",
+            ChatSort::Unread => "unread_count DESC, last_message_ts DESC",
+            ChatSort::Name => "name COLLATE NOCASE ASC",
+        }
+    }
+}
+
+/// How the FTS5 index splits message text into searchable tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FtsTokenizer {
+    /// Words separated by spaces and punctuation (FTS5's default)
+    #[default]
+    Unicode61,
+    /// Every 3-character sequence, so any substring of 3+ characters
+    /// matches; for Chinese, Japanese, Korean and emoji-heavy chats
+    Trigram,
+}
+
+impl FtsTokenizer {
+    pub fn name(self) -> &'static str {
+        match self {
+            FtsTokenizer::Unicode61 => "unicode61",
+            FtsTokenizer::Trigram => "trigram",
+        }
+    }
+
+    /// The FTS5 `tokenize` option; `tokenchars` are extra characters
+    /// unicode61 keeps inside words (e.g. `#@` for hashtags and mentions).
+    fn spec(self, tokenchars: Option<&str>) -> String {
+        match (self, tokenchars) {
+            (FtsTokenizer::Unicode61, Some(chars)) if !chars.is_empty() => {
+                format!("unicode61 tokenchars {}", sql_quote(chars))
+            }
+            _ => self.name().to_string(),
+        }
+    }
+}
+
+/// The tokenizer `messages_fts` was created with, read from its definition.
+async fn fts_tokenizer(conn: &Conn) -> FtsTokenizer {
+    match fts_definition(conn).await {
+        Some(sql) if sql.to_lowercase().contains("trigram") => FtsTokenizer::Trigram,
+        _ => FtsTokenizer::Unicode61,
+    }
+}
+
+async fn fts_definition(conn: &Conn) -> Option<String> {
+    let mut rows = conn
+        .query(
+            "SELECT sql FROM sqlite_schema WHERE name = 'messages_fts'",
+            (),
+        )
+        .await
+        .ok()?;
+    rows.next().await.ok()??.get(0).ok()
+}
+
+/// `s` as a single-quoted SQL string literal.
+fn sql_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// How a search query is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MatchMode {
+    /// Words must all appear; nothing in the query is special
+    Plain,
+    /// "quoted phrases", `term*` prefixes, AND/OR/NOT and parentheses
+    #[default]
+    Fts,
+}
+
+/// Order of search results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SearchSort {
+    /// Newest first
+    #[default]
+    Recent,
+    /// Best match first (FTS5 bm25), weighed down by age
+    Relevance,
+}
+
+/// A piece of a parsed search query.
+#[derive(Debug, Clone, PartialEq)]
+enum QueryToken {
+    /// A word or quoted phrase, `*` marking a prefix
+    Term {
+        text: String,
+        prefix: bool,
+    },
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+/// Split a search query into terms and, with `MatchMode::Fts`, operators
+/// and parentheses. Malformed queries are rejected here, since FTS5 errors
+/// are cryptic.
+fn parse_search_query(query: &str, mode: MatchMode) -> Result<Vec<QueryToken>> {
+    if query.trim().is_empty() {
+        anyhow::bail!("Search query is empty");
+    }
+    if mode == MatchMode::Plain {
+        return Ok(query
+            .split_whitespace()
+            .map(|word| QueryToken::Term {
+                text: word.to_string(),
+                prefix: false,
+            })
+            .collect());
+    }
+
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(if c == '(' {
+                QueryToken::Open
+            } else {
+                QueryToken::Close
+            });
+        } else if c == '"' {
+            // A phrase runs to the closing quote (or the end of the query)
+            chars.next();
+            let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            let prefix = chars.next_if_eq(&'*').is_some();
+            if !phrase.trim().is_empty() {
+                tokens.push(QueryToken::Term {
+                    text: phrase,
+                    prefix,
+                });
+            }
+        } else {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && !"()\"".contains(c)) {
+                word.push(c);
+            }
+            match word.as_str() {
+                "AND" => tokens.push(QueryToken::Op("AND")),
+                "OR" => tokens.push(QueryToken::Op("OR")),
+                "NOT" => tokens.push(QueryToken::Op("NOT")),
+                _ => {
+                    let stem = word.trim_end_matches('*');
+                    if !stem.is_empty() {
+                        tokens.push(QueryToken::Term {
+                            text: stem.to_string(),
+                            prefix: stem.len() < word.len(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let mut depth = 0usize;
+    // Whether the last token ends an operand (a term or a closing parenthesis)
+    let mut operand = false;
+    for token in &tokens {
+        match token {
+            QueryToken::Term { .. } => operand = true,
+            QueryToken::Op(op) => {
+                if !operand {
+                    anyhow::bail!(
+                        "{} needs a search term on both sides (use --match-mode plain to search for it)",
+                        op
+                    );
+                }
+                operand = false;
+            }
+            QueryToken::Open => {
+                depth += 1;
+                operand = false;
+            }
+            QueryToken::Close => {
+                if depth == 0 || !operand {
+                    anyhow::bail!("Unbalanced parentheses in search query");
+                }
+                depth -= 1;
+            }
+        }
+    }
+    match tokens.last() {
+        None => anyhow::bail!("Search query has no terms"),
+        _ if depth > 0 => anyhow::bail!("Unbalanced parentheses in search query"),
+        Some(QueryToken::Op(op)) => anyhow::bail!(
+            "{} needs a search term on both sides (use --match-mode plain to search for it)",
+            op
+        ),
+        _ => Ok(tokens),
+    }
+}
+
+/// The FTS5 MATCH expression for a search query. Every word is quoted, so
+/// input can't reach FTS5 syntax (column filters, NEAR, `^`, ...) other
+/// than what `MatchMode::Fts` lets through.
+fn fts_match_expr(query: &str, mode: MatchMode) -> Result<String> {
+    let expr: Vec<String> = parse_search_query(query, mode)?
+        .into_iter()
+        .map(|token| match token {
+            QueryToken::Term { text, prefix } => format!(
+                "\"{}\"{}",
+                text.replace('"', "\"\""),
+                if prefix { "*" } else { "" }
+            ),
+            QueryToken::Op(op) => op.to_string(),
+            QueryToken::Open => "(".to_string(),
+            QueryToken::Close => ")".to_string(),
+        })
+        .collect();
+    Ok(expr.join(" "))
+}
+
+/// The same query as `fts_match_expr`, as a condition on `m.text` for
+/// stores without FTS5: one LIKE per term, numbered from `?{first_param}`.
+/// Adjacent terms are ANDed, as FTS5 does; prefixes match anywhere. `%`
+/// and `_` in a term match themselves.
+fn like_match_expr(
+    query: &str,
+    mode: MatchMode,
+    first_param: usize,
+) -> Result<(String, Vec<String>)> {
+    let mut expr = Vec::new();
+    let mut patterns = Vec::new();
+    let mut operand = false;
+    for token in parse_search_query(query, mode)? {
+        if operand && matches!(token, QueryToken::Term { .. } | QueryToken::Open) {
+            expr.push("AND".to_string());
+        }
+        operand = matches!(token, QueryToken::Term { .. } | QueryToken::Close);
+        match token {
+            QueryToken::Term { text, .. } => {
+                expr.push(format!(
+                    "m.text LIKE ?{} ESCAPE '\\'",
+                    first_param + patterns.len()
+                ));
+                patterns.push(format!("%{}%", escape_like(&text)));
+            }
+            // FTS5's NOT is binary: "a NOT b" is a AND NOT b
+            QueryToken::Op("NOT") => expr.push("AND NOT".to_string()),
+            QueryToken::Op(op) => expr.push(op.to_string()),
+            QueryToken::Open => expr.push("(".to_string()),
+            QueryToken::Close => expr.push(")".to_string()),
+        }
+    }
+    Ok((format!("({})", expr.join(" ")), patterns))
+}
+
+/// Escape LIKE's wildcards, for a pattern with `ESCAPE '\'`.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Counter channel posts are ranked by in `top_messages`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRank {
+    /// Most viewed first
+    #[default]
+    Views,
+    /// Most forwarded first
+    Forwards,
+}
+
+impl MessageRank {
+    fn column(self) -> &'static str {
+        match self {
+            MessageRank::Views => "views",
+            MessageRank::Forwards => "forwards",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +346,203 @@ pub struct Topic {
     pub icon_color: i32,
     pub icon_emoji: Option<String>,
     pub unread_count: i32,
+    /// Latest message in the topic, as of the last topic sync or read
+    pub top_message: Option<i32>,
+}
+
+/// A member of a group or channel as recorded by `chats members --snapshot`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMember {
+    pub user_id: i64,
+    pub username: Option<String>,
+    pub name: String,
+    pub role: String,
+}
+
+/// A keyword alert rule evaluated by the daemon (`tgcli alerts`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: i64,
+    /// Alternatives separated by `|`, matched case-insensitively
+    pub pattern: String,
+    /// Only messages in this chat (None = any chat)
+    pub chat_id: Option<i64>,
+    /// `desktop`, `webhook` or `command`
+    pub notify: String,
+    /// Webhook URL or shell command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+/// An auto-forward rule run by the daemon (`tgcli forwards`), with its
+/// statistics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForwardRule {
+    pub id: i64,
+    pub from_chat_id: i64,
+    pub to_chat_id: i64,
+    /// Alternatives separated by `|`, matched case-insensitively (None = every message)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Messages forwarded so far
+    pub forwarded: i64,
+    /// Forwards that failed
+    pub failed: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_forwarded: Option<DateTime<Utc>>,
+}
+
+/// A recurring message sent by the daemon (`tgcli schedule`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: i64,
+    pub chat_id: i64,
+    /// Five-field cron expression, in local time
+    pub cron: String,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<DateTime<Utc>>,
+    /// Times the message was sent
+    pub runs: i64,
+}
+
+/// Away mode (`tgcli away on`): the daemon answers private messages with
+/// `message` until `until`, or until you send a message yourself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AwayMode {
+    pub message: String,
+    pub since: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+    /// Answer each sender at most once per this many minutes
+    pub interval_minutes: i64,
+    /// Only answer these users (empty = everyone)
+    pub allow: Vec<i64>,
+    /// Never answer these users
+    pub deny: Vec<i64>,
+}
+
+/// An API token (`tgcli tokens`). Only a hash of the secret is stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    #[serde(skip)]
+    pub token_hash: String,
+    /// Scopes as written (`read:messages`, `send:chat:12345`, ...)
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// A change made (or refused) through tgcli, from the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub ts: DateTime<Utc>,
+    /// The tgcli command, e.g. `messages delete` or `daemon`
+    pub command: String,
+    /// What was done, e.g. `send` or `ban users`
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_id: Option<i64>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How long local messages of a chat are kept (`tgcli retention`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub chat_id: i64,
+    pub keep_days: i64,
+    /// Also delete the downloaded media of pruned messages
+    pub delete_media: bool,
+    /// Messages pruned so far
+    pub pruned: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_pruned: Option<DateTime<Utc>>,
+}
+
+/// Where syncing a chat stands (`tgcli sync status`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatSyncStatus {
+    pub chat_id: i64,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_synced: Option<DateTime<Utc>>,
+    /// Newest message synced; incremental syncs continue after it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_message_id: Option<i64>,
+    /// Oldest stored message; backfills continue before it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backfill_cursor: Option<i64>,
+    /// Messages whose media was not downloaded yet
+    pub pending_media: i64,
+    /// Known size of that media, in bytes (files synced before sizes were
+    /// recorded count as 0)
+    pub pending_media_bytes: i64,
+    /// What went wrong the last time the chat was synced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A chat whose last sync failed (`tgcli sync retry-failed`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncError {
+    pub chat_id: i64,
+    pub name: String,
+    pub error: String,
+    /// Failed syncs in a row
+    pub attempts: i64,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// A sticker seen in search results, cached so it can be sent by emoji.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sticker {
+    pub doc_id: i64,
+    /// Portable `doc_id:access_hash:file_ref` string accepted by `send --sticker`
+    pub file_id: String,
+    pub emoji: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_name: Option<String>,
+    pub animated: bool,
+}
+
+/// A resolved custom emoji document (messages.getCustomEmojiDocuments).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomEmoji {
+    pub doc_id: i64,
+    /// Fallback emoji for the custom emoji
+    pub alt: String,
+    /// ID of the emoji pack, used to reuse resolved pack names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_id: Option<i64>,
+    /// Short name of the emoji pack, when known
+    pub set_name: Option<String>,
+}
+
+impl CustomEmoji {
+    /// Label shown in place of the emoji in message text, e.g. `[:PartyBlobs/🎉:]`.
+    pub fn label(&self) -> String {
+        match &self.set_name {
+            Some(set) if !self.alt.is_empty() => format!("[:{}/{}:]", set, self.alt),
+            Some(set) => format!("[:{}:]", set),
+            None if !self.alt.is_empty() => format!("[:{}:]", self.alt),
+            None => format!("[:custom_emoji_{}:]", self.doc_id),
+        }
+    }
+}
+
+/// A custom emoji in message text; `offset` and `length` are in UTF-16 code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomEmojiSpan {
+    pub offset: i64,
+    pub length: i64,
+    pub doc_id: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,7 +554,7 @@ pub struct Contact {
     pub phone: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Message {
     pub id: i64,
     pub chat_id: i64,
@@ -60,97 +567,752 @@ pub struct Message {
     pub media_path: Option<String>,
     pub reply_to_id: Option<i64>,
     pub topic_id: Option<i32>,
+    /// Details of a service message (see `SERVICE_MEDIA_TYPE`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_action: Option<serde_json::Value>,
+    /// Original author of a forwarded message (None if not forwarded or hidden)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fwd_from_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fwd_from_id: Option<i64>,
+    /// When the original message was sent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fwd_date: Option<DateTime<Utc>>,
+    /// Inline bot the message was sent through
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub via_bot_id: Option<i64>,
+    /// Signature of a channel post
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_author: Option<String>,
+    /// View count of a channel post, as of the last sync
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub views: Option<i64>,
+    /// Forward count of a channel post, as of the last sync
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forwards: Option<i64>,
+    /// Album (media group) the message belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grouped_id: Option<i64>,
+    /// Parts of the album this entry stands for (see `collapse_albums`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub album: Vec<AlbumPart>,
+    /// Contact card shared in the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared_contact: Option<SharedContact>,
+    /// Price in stars of paid media (see `messages unlock`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paid_stars: Option<i64>,
+    /// When the message was deleted on Telegram; it is kept here for history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// File name of a document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_name: Option<String>,
+    /// Size in bytes of a photo or document, whether downloaded or not
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_size: Option<i64>,
+    /// Length in seconds of a video, audio or voice message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_duration: Option<f64>,
+    /// Pixel dimensions of a photo, video or image document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_width: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_height: Option<i32>,
+    /// Earlier versions of the text (filled in by `message_edits` lookups)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub edits: Vec<MessageEdit>,
+    /// Link preview (filled in by `message_webpage` lookups)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webpage: Option<MessageWebpage>,
+    /// Shared location or venue (filled in by `message_geo` lookups)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo: Option<MessageGeo>,
+    /// Keyboard of a bot message (filled in by `message_buttons` lookups)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub buttons: Vec<MessageButton>,
+    /// Reactions and their counts (filled in by `message_reactions` lookups)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<MessageReaction>,
     #[serde(skip_serializing_if = "String::is_empty")]
     pub snippet: String,
 }
 
-pub struct ListMessagesParams {
-    pub chat_id: Option<i64>,
-    pub topic_id: Option<i32>,
-    pub limit: i64,
-    pub after: Option<DateTime<Utc>>,
-    pub before: Option<DateTime<Utc>>,
-    pub ignore_chats: Vec<i64>,
-    pub ignore_channels: bool,
-}
+impl Message {
+    /// Whether this is a service message (join, pin, title change, call, ...).
+    pub fn is_service(&self) -> bool {
+        self.media_type.as_deref() == Some(SERVICE_MEDIA_TYPE)
+    }
 
-pub struct SearchMessagesParams {
-    pub query: String,
-    pub chat_id: Option<i64>,
-    pub topic_id: Option<i32>,
-    pub from_id: Option<i64>,
-    pub limit: i64,
-    pub media_type: Option<String>,
-    pub ignore_chats: Vec<i64>,
-    pub ignore_channels: bool,
+    /// Who a forwarded message came from, for display.
+    pub fn forwarded_from(&self) -> Option<String> {
+        self.fwd_date?;
+        Some(match (&self.fwd_from_name, self.fwd_from_id) {
+            (Some(name), _) => name.clone(),
+            (None, Some(id)) => id.to_string(),
+            (None, None) => "hidden user".to_string(),
+        })
+    }
 }
 
-pub struct UpsertMessageParams {
+/// One message of an album collapsed into a single entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlbumPart {
     pub id: i64,
-    pub chat_id: i64,
-    pub sender_id: i64,
-    pub ts: DateTime<Utc>,
-    pub edit_ts: Option<DateTime<Utc>>,
-    pub from_me: bool,
-    pub text: String,
     pub media_type: Option<String>,
     pub media_path: Option<String>,
-    pub reply_to_id: Option<i64>,
-    pub topic_id: Option<i32>,
 }
 
-impl Store {
-    pub async fn open(store_dir: &str) -> Result<Self> {
-        std::fs::create_dir_all(store_dir)?;
-        let db_path = Path::new(store_dir).join("tgcli.db");
-        let db_path_str = db_path.to_string_lossy();
-        let db: Database = Builder::new_local(&db_path_str)
-            .build()
-            .await
-            .context("Failed to open database")?;
+/// Merge consecutive messages of the same album into one entry: the first
+/// part, carrying the album's caption and listing every part in `album`.
+/// Messages must be in chronological order.
+pub fn collapse_albums(msgs: Vec<Message>) -> Vec<Message> {
+    let mut out: Vec<Message> = Vec::with_capacity(msgs.len());
+    for mut msg in msgs {
+        if let (Some(grouped_id), Some(last)) = (msg.grouped_id, out.last_mut()) {
+            if last.grouped_id == Some(grouped_id) && last.chat_id == msg.chat_id {
+                if last.text.is_empty() {
+                    last.text = msg.text.clone();
+                }
+                last.album.push(AlbumPart::from(&msg));
+                continue;
+            }
+        }
+        if msg.grouped_id.is_some() {
+            msg.album.push(AlbumPart::from(&msg));
+        }
+        out.push(msg);
+    }
+    // A lone part (e.g. the rest of the album is on another page) stays as is
+    for msg in &mut out {
+        if msg.album.len() == 1 {
+            msg.album.clear();
+        }
+    }
+    out
+}
 
-        // Create a temporary connection for migrations and PRAGMAs
-        let conn = db.connect().context("Failed to connect for migration")?;
+impl From<&Message> for AlbumPart {
+    fn from(msg: &Message) -> Self {
+        AlbumPart {
+            id: msg.id,
+            media_type: msg.media_type.clone(),
+            media_path: msg.media_path.clone(),
+        }
+    }
+}
 
-        // PRAGMAs that set values return the new value, so use query and ignore results
-        let _ = conn.query("PRAGMA journal_mode=WAL", ()).await;
-        let _ = conn.query("PRAGMA busy_timeout=5000", ()).await;
-        let _ = conn.query("PRAGMA synchronous=NORMAL", ()).await;
-        let _ = conn.query("PRAGMA cache_size=-64000", ()).await;
+/// Origin of a forwarded message (from its `MessageFwdHeader`).
+#[derive(Debug, Clone)]
+pub struct ForwardOrigin {
+    /// Name given by Telegram (hidden senders, post signatures); if None the
+    /// name is looked up among known chats and contacts
+    pub name: Option<String>,
+    pub from_id: Option<i64>,
+    pub date: DateTime<Utc>,
+}
 
-        let mut store = Store { db, has_fts: false };
-        store.migrate(&conn).await?;
+/// Who besides the sender a message is attributed to.
+#[derive(Debug, Clone, Default)]
+pub struct Attribution {
+    pub via_bot_id: Option<i64>,
+    pub post_author: Option<String>,
+}
 
-        // Drop the migration connection immediately
-        drop(conn);
+/// Contact card shared in a message (`media_type = 'contact'`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedContact {
+    /// None if the contact isn't on Telegram
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    pub phone: String,
+    pub first_name: String,
+    pub last_name: String,
+}
 
-        Ok(store)
+impl SharedContact {
+    /// Name and phone number, e.g. `Ada Lovelace, +44 20 7946 0000`.
+    pub fn label(&self) -> String {
+        let name = format!("{} {}", self.first_name, self.last_name);
+        let name = name.trim();
+        match (name.is_empty(), self.phone.is_empty()) {
+            (false, false) => format!("{}, {}", name, self.phone),
+            (false, true) => name.to_string(),
+            (true, _) => self.phone.clone(),
+        }
     }
+}
 
-    /// Get a new connection for a single operation.
-    /// Connection is dropped automatically when it goes out of scope.
-    pub(crate) async fn get_conn(&self) -> Result<Connection> {
-        self.db.connect().context("Failed to create connection")
+/// Location shared in a message: a point, a venue, or a live location.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageGeo {
+    /// `geo`, `venue` or `geolive` (same as the message's media type)
+    pub kind: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Precision of the point in meters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accuracy_radius: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Venue database, e.g. `foursquare`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub venue_id: Option<String>,
+    /// How long a live location is shared, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live_period: Option<i32>,
+}
+
+impl MessageGeo {
+    /// Venue name and address followed by the coordinates, e.g.
+    /// `Café Einstein, Unter den Linden 42 (52.517140, 13.388550)`.
+    pub fn label(&self) -> String {
+        let coordinates = format!("{:.6}, {:.6}", self.lat, self.lon);
+        let place: Vec<&str> = [self.title.as_deref(), self.address.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if place.is_empty() {
+            coordinates
+        } else {
+            format!("{} ({})", place.join(", "), coordinates)
+        }
     }
+}
 
-    async fn migrate(&mut self, conn: &Connection) -> Result<()> {
-        // Create tables one at a time (turso execute doesn't support multiple statements)
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS chats (
-                id INTEGER PRIMARY KEY,
-                kind TEXT NOT NULL DEFAULT 'user',
-                name TEXT NOT NULL DEFAULT '',
-                username TEXT,
-                last_message_ts TEXT,
-                is_forum INTEGER DEFAULT 0,
-                last_sync_message_id INTEGER,
-                access_hash INTEGER,
-                archived INTEGER DEFAULT 0
-            )",
-            (),
-        )
-        .await
-        .context("Failed to create chats table")?;
+/// An earlier version of an edited message's text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageEdit {
+    pub text: String,
+    /// When this version was replaced
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// Link preview attached to a message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageWebpage {
+    pub url: String,
+    /// Host of `url` without `www.`, e.g. `github.com`
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A button of a bot message's keyboard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageButton {
+    pub row: i32,
+    pub col: i32,
+    pub text: String,
+    /// What the button does: `callback` (what `messages press` can press),
+    /// `url`, `switch_inline`, `keyboard` (sends its text), ...
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// One reaction on a message and how many people left it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageReaction {
+    /// The emoji, `custom:<document id>` for custom emoji, or `paid` for stars
+    pub reaction: String,
+    pub count: i32,
+    /// We left this reaction
+    pub chosen: bool,
+}
+
+/// Host of a URL, lowercased and without `www.` (what `--domain` matches).
+pub fn url_domain(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    if host.is_empty() {
+        return None;
+    }
+    Some(normalize_domain(host))
+}
+
+fn normalize_domain(domain: &str) -> String {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+    match domain.strip_prefix("www.") {
+        Some(rest) => rest.to_string(),
+        None => domain,
+    }
+}
+
+/// A poll sent in a message, with its results as of the last sync.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagePoll {
+    pub chat_id: i64,
+    pub msg_id: i64,
+    pub poll_id: i64,
+    pub question: String,
+    pub closed: bool,
+    pub multiple_choice: bool,
+    pub quiz: bool,
+    pub public_voters: bool,
+    /// None until Telegram has sent results
+    pub total_voters: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_date: Option<DateTime<Utc>>,
+    pub options: Vec<PollOption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PollOption {
+    /// Position in the poll (what `polls vote --option` takes)
+    pub index: i32,
+    pub text: String,
+    pub voters: Option<i32>,
+    /// You voted for this option
+    pub chosen: bool,
+    /// Right answer of a quiz
+    pub correct: bool,
+}
+
+/// Keyset position for paging through message listings (newest to oldest).
+/// A page fetched with a cursor contains only messages strictly older than it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageCursor {
+    pub ts: DateTime<Utc>,
+    pub chat_id: i64,
+    pub id: i64,
+}
+
+impl MessageCursor {
+    pub fn from_message(m: &Message) -> Self {
+        MessageCursor {
+            ts: m.ts,
+            chat_id: m.chat_id,
+            id: m.id,
+        }
+    }
+
+    /// Encode as an opaque continuation token.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}|{}|{}",
+            self.ts.to_rfc3339(),
+            self.chat_id,
+            self.id
+        ))
+    }
+
+    /// Decode a token produced by [`MessageCursor::encode`].
+    pub fn decode(token: &str) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid cursor '{}'", token);
+        let raw = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let mut parts = raw.splitn(3, '|');
+        let ts = parts
+            .next()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .ok_or_else(invalid)?
+            .with_timezone(&Utc);
+        let chat_id = parts
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+        let id = parts
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+        Ok(MessageCursor { ts, chat_id, id })
+    }
+
+    /// SQL condition selecting messages older than the cursor, using params ?idx..?idx+2.
+    fn condition(idx: usize) -> String {
+        format!(
+            "(m.ts < ?{0} OR (m.ts = ?{0} AND (m.chat_id < ?{1} OR (m.chat_id = ?{1} AND m.id < ?{2}))))",
+            idx,
+            idx + 1,
+            idx + 2
+        )
+    }
+
+    fn params(&self) -> [turso::Value; 3] {
+        [
+            turso::Value::Text(self.ts.to_rfc3339()),
+            turso::Value::Integer(self.chat_id),
+            turso::Value::Integer(self.id),
+        ]
+    }
+}
+
+/// Messages read lazily from a query, one row per `next` call, so whole
+/// archives can be listed or exported without holding them in memory.
+pub struct MessageRows {
+    rows: Rows,
+    /// The FTS snippet follows `MESSAGE_COLUMNS`
+    snippet: bool,
+    _conn: Conn,
+}
+
+impl MessageRows {
+    pub async fn next(&mut self) -> Result<Option<Message>> {
+        let Some(row) = self.rows.next().await? else {
+            return Ok(None);
+        };
+        let mut m = row_to_message(&row)?;
+        if self.snippet {
+            m.snippet = row.get::<String>(28).unwrap_or_default();
+        }
+        Ok(Some(m))
+    }
+
+    /// Read the remaining rows.
+    pub async fn into_vec(mut self) -> Result<Vec<Message>> {
+        let mut msgs = Vec::new();
+        while let Some(m) = self.next().await? {
+            msgs.push(m);
+        }
+        Ok(msgs)
+    }
+}
+
+pub struct ListMessagesParams {
+    pub chat_id: Option<i64>,
+    pub topic_id: Option<i32>,
+    pub limit: i64,
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub ignore_chats: Vec<i64>,
+    pub ignore_channels: bool,
+    /// Only messages with a stored location (see `message_geo`)
+    pub with_location: bool,
+    /// Only return messages older than this position
+    pub cursor: Option<MessageCursor>,
+}
+
+pub struct SearchMessagesParams {
+    pub query: String,
+    /// How `query` is turned into an FTS5 MATCH expression
+    pub match_mode: MatchMode,
+    pub sort: SearchSort,
+    /// With `SearchSort::Relevance`, how many days old a message is when its
+    /// score counts half (0: rank by relevance alone)
+    pub half_life_days: f64,
+    pub chat_id: Option<i64>,
+    pub topic_id: Option<i32>,
+    /// Only messages in forum topics whose name contains this, in any chat
+    pub topic_name: Option<String>,
+    pub from_id: Option<i64>,
+    pub limit: i64,
+    pub media_type: Option<String>,
+    pub ignore_chats: Vec<i64>,
+    pub ignore_channels: bool,
+    /// Only messages forwarded from this origin: a peer ID, an @username, or
+    /// part of the origin's name
+    pub forwarded_from: Option<String>,
+    /// Only messages sent through this inline bot
+    pub via_bot_id: Option<i64>,
+    /// Only messages with a link preview
+    pub has_link: bool,
+    /// Only messages linking to this domain or its subdomains
+    pub domain: Option<String>,
+    /// Only return messages older than this position
+    pub cursor: Option<MessageCursor>,
+}
+
+pub struct MissingMediaParams {
+    pub chat_id: Option<i64>,
+    pub media_type: Option<String>,
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub limit: i64,
+}
+
+/// What's known about a photo or document file without downloading it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaFileInfo {
+    /// Original file name (documents only)
+    pub name: Option<String>,
+    /// Size in bytes (of the largest size, for photos)
+    pub size: i64,
+    /// Length in seconds of videos and audio
+    pub duration: Option<f64>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+pub struct MediaMessagesParams {
+    pub chat_id: Option<i64>,
+    pub media_type: Option<String>,
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    /// Only media that hasn't been downloaded
+    pub missing_local: bool,
+    pub limit: i64,
+}
+
+/// Media types that have no file to download (polls, locations, link previews).
+const UNDOWNLOADABLE_MEDIA: &[&str] = &[
+    "poll",
+    "geo",
+    "dice",
+    "venue",
+    "geolive",
+    "webpage",
+    SERVICE_MEDIA_TYPE,
+];
+
+/// A message whose text or media also appears in other messages.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateMessage {
+    /// `text` or `media`
+    pub kind: &'static str,
+    /// Normalized text, or the SHA-256 of the media file
+    pub key: String,
+    pub chat_id: i64,
+    pub msg_id: i64,
+    pub ts: DateTime<Utc>,
+}
+
+/// A message's downloaded media file, as recorded in the store.
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaFile {
+    pub chat_id: i64,
+    pub msg_id: i64,
+    pub media_type: Option<String>,
+    pub path: String,
+    /// SHA-256 recorded when the file was downloaded
+    pub sha256: Option<String>,
+}
+
+/// Text of a stored message and who wrote it, for `stats words`.
+#[derive(Debug, Clone)]
+pub struct MessageText {
+    pub sender_id: i64,
+    pub from_me: bool,
+    pub topic_id: Option<i32>,
+    pub text: String,
+}
+
+/// Per-sender message counts for `stats senders`.
+#[derive(Debug, Clone)]
+pub struct SenderStats {
+    pub sender_id: i64,
+    pub from_me: bool,
+    pub messages: i64,
+    /// Average text length in characters, over messages with text
+    pub avg_length: Option<f64>,
+    pub media: i64,
+    /// Messages that reply to another message
+    pub replies: i64,
+    /// Replies by other people to this sender's messages
+    pub replied_to: i64,
+    pub first_ts: DateTime<Utc>,
+    pub last_ts: DateTime<Utc>,
+}
+
+/// First and most recent message exchanged with a user (`contacts timeline`):
+/// their private chat plus what they wrote in groups.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserTimeline {
+    pub user_id: i64,
+    pub first: Option<Message>,
+    pub last: Option<Message>,
+    /// Messages in the private chat, both directions
+    pub private_messages: i64,
+    /// Messages they wrote in other chats
+    pub group_messages: i64,
+    /// Other chats they wrote in
+    pub group_chats: i64,
+}
+
+pub struct UpsertMessageParams {
+    pub id: i64,
+    pub chat_id: i64,
+    pub sender_id: i64,
+    pub ts: DateTime<Utc>,
+    pub edit_ts: Option<DateTime<Utc>>,
+    pub from_me: bool,
+    pub text: String,
+    pub media_type: Option<String>,
+    pub media_path: Option<String>,
+    pub reply_to_id: Option<i64>,
+    pub topic_id: Option<i32>,
+}
+
+/// How long a statement waits on a locked database inside SQLite before
+/// failing with busy (per connection; also set as `PRAGMA busy_timeout`).
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Further attempts at a statement that still failed with busy.
+const BUSY_RETRIES: u32 = 6;
+
+/// 25ms, 50ms, 100ms, ... capped at 1s, plus up to half of that again at
+/// random so processes contending for the database don't retry in lockstep.
+fn busy_backoff(attempt: u32) -> Duration {
+    use rand::Rng;
+    let base = (25u64 << attempt.min(6)).min(1000);
+    Duration::from_millis(base + rand::rng().random_range(0..=base / 2))
+}
+
+/// Run `op` again while the database is busy (another process writing or
+/// checkpointing), backing off between attempts.
+async fn retry_busy<T, F, Fut>(mut op: F) -> turso::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = turso::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(turso::Error::Busy(_) | turso::Error::BusySnapshot(_))
+                if attempt < BUSY_RETRIES =>
+            {
+                attempt += 1;
+                log::debug!("Database busy, retrying (attempt {})", attempt);
+                tokio::time::sleep(busy_backoff(attempt)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A connection whose `execute` and `query` retry on busy (see
+/// `retry_busy`); everything else goes straight to turso's `Connection`.
+pub(crate) struct Conn(Connection);
+
+impl Conn {
+    pub async fn execute(
+        &self,
+        sql: impl AsRef<str>,
+        params: impl IntoParams,
+    ) -> turso::Result<u64> {
+        let params = params.into_params()?;
+        retry_busy(|| self.0.execute(sql.as_ref(), params.clone())).await
+    }
+
+    pub async fn query(
+        &self,
+        sql: impl AsRef<str>,
+        params: impl IntoParams,
+    ) -> turso::Result<Rows> {
+        let params = params.into_params()?;
+        retry_busy(|| self.0.query(sql.as_ref(), params.clone())).await
+    }
+}
+
+impl std::ops::Deref for Conn {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.0
+    }
+}
+
+impl Store {
+    pub async fn open(store_dir: &str) -> Result<Self> {
+        std::fs::create_dir_all(store_dir)?;
+        let db_path = Path::new(store_dir).join("tgcli.db");
+        let db_path_str = db_path.to_string_lossy();
+        let db: Database = Builder::new_local(&db_path_str)
+            .build()
+            .await
+            .context("Failed to open database")?;
+
+        // Create a temporary connection for migrations and PRAGMAs
+        let conn = Conn(db.connect().context("Failed to connect for migration")?);
+
+        // PRAGMAs that set values return the new value, so use query and ignore results
+        let _ = conn.query("PRAGMA journal_mode=WAL", ()).await;
+        let _ = conn
+            .query(
+                format!("PRAGMA busy_timeout={}", BUSY_TIMEOUT.as_millis()),
+                (),
+            )
+            .await;
+        let _ = conn.query("PRAGMA synchronous=NORMAL", ()).await;
+        let _ = conn.query("PRAGMA cache_size=-64000", ()).await;
+
+        let hot = db.connect().context("Failed to create connection")?;
+        hot.busy_timeout(BUSY_TIMEOUT)?;
+        let mut store = Store {
+            db,
+            has_fts: false,
+            fts_tokenizer: FtsTokenizer::default(),
+            hot,
+            statements: tokio::sync::Mutex::new(HashMap::new()),
+        };
+        store.migrate(&conn).await?;
+
+        // Drop the migration connection immediately
+        drop(conn);
+
+        Ok(store)
+    }
+
+    /// Get a new connection for a single operation.
+    /// Connection is dropped automatically when it goes out of scope.
+    pub(crate) async fn get_conn(&self) -> Result<Conn> {
+        let conn = self.db.connect().context("Failed to create connection")?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(Conn(conn))
+    }
+
+    /// Run a statement from the per-message hot path with a cached prepared
+    /// statement, so a long sync parses its SQL once rather than per message.
+    async fn execute_cached(&self, sql: &'static str, params: impl IntoParams) -> Result<u64> {
+        let params = params.into_params()?;
+        let mut statements = self.statements.lock().await;
+        let stmt = match statements.get(sql) {
+            Some(stmt) => stmt.clone(),
+            None => {
+                let stmt = self.hot.prepare(sql).await?;
+                statements.insert(sql, stmt.clone());
+                stmt
+            }
+        };
+        Ok(retry_busy(|| {
+            let mut stmt = stmt.clone();
+            let params = params.clone();
+            async move { stmt.execute(params).await }
+        })
+        .await?)
+    }
+
+    /// Fold the write-ahead log back into the database file and truncate it,
+    /// so it doesn't grow for as long as the daemon runs. Returns whether
+    /// readers kept it from finishing (it's retried on the next call).
+    pub async fn checkpoint(&self) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query("PRAGMA wal_checkpoint(TRUNCATE)", ()).await?;
+        let busy = match rows.next().await? {
+            Some(row) => row.get::<i64>(0)? != 0,
+            None => false,
+        };
+        Ok(busy)
+    }
+
+    async fn migrate(&mut self, conn: &Conn) -> Result<()> {
+        // Create tables one at a time (turso execute doesn't support multiple statements)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS chats (
+                id INTEGER PRIMARY KEY,
+                kind TEXT NOT NULL DEFAULT 'user',
+                name TEXT NOT NULL DEFAULT '',
+                username TEXT,
+                last_message_ts TEXT,
+                is_forum INTEGER DEFAULT 0,
+                last_sync_message_id INTEGER,
+                access_hash INTEGER,
+                archived INTEGER DEFAULT 0,
+                unread_count INTEGER DEFAULT 0
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create chats table")?;
 
         conn.execute(
             "CREATE TABLE IF NOT EXISTS contacts (
@@ -176,8 +1338,19 @@ impl Store {
                 text TEXT NOT NULL DEFAULT '',
                 media_type TEXT,
                 media_path TEXT,
+                media_sha256 TEXT,
                 reply_to_id INTEGER,
                 topic_id INTEGER,
+                thread_root_id INTEGER,
+                service_action TEXT,
+                fwd_from_name TEXT,
+                fwd_from_id INTEGER,
+                fwd_date TEXT,
+                via_bot_id INTEGER,
+                post_author TEXT,
+                views INTEGER,
+                forwards INTEGER,
+                grouped_id INTEGER,
                 PRIMARY KEY (chat_id, id)
             )",
             (),
@@ -201,30 +1374,368 @@ impl Store {
         .await
         .context("Failed to create topics table")?;
 
-        // Add media_path column if it doesn't exist (migration for existing DBs)
-        let _ = conn
-            .execute("ALTER TABLE messages ADD COLUMN media_path TEXT", ())
-            .await;
+        // Sticker cache (search results), used to send stickers by emoji
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS stickers (
+                doc_id INTEGER PRIMARY KEY,
+                file_id TEXT NOT NULL,
+                emoji TEXT NOT NULL DEFAULT '',
+                set_name TEXT,
+                animated INTEGER NOT NULL DEFAULT 0,
+                rank INTEGER NOT NULL DEFAULT 0,
+                cached_at TEXT NOT NULL
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create stickers table")?;
 
-        // Add is_forum column if it doesn't exist (migration for existing DBs)
-        let _ = conn
-            .execute(
-                "ALTER TABLE chats ADD COLUMN is_forum INTEGER DEFAULT 0",
-                (),
-            )
-            .await;
+        // Custom emoji documents referenced by message entities
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS custom_emoji (
+                doc_id INTEGER PRIMARY KEY,
+                alt TEXT NOT NULL DEFAULT '',
+                set_id INTEGER,
+                set_name TEXT,
+                cached_at TEXT NOT NULL
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create custom_emoji table")?;
 
-        // Ensure any NULL is_forum values are set to 0 (migration for existing DBs)
-        let _ = conn
-            .execute("UPDATE chats SET is_forum = 0 WHERE is_forum IS NULL", ())
-            .await;
+        // Where custom emoji sit in each message's text, labeled when it's shown
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_custom_emoji (
+                chat_id INTEGER NOT NULL,
+                msg_id INTEGER NOT NULL,
+                offset INTEGER NOT NULL,
+                length INTEGER NOT NULL,
+                doc_id INTEGER NOT NULL,
+                PRIMARY KEY (chat_id, msg_id, offset)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create message_custom_emoji table")?;
 
-        // Add topic_id column if it doesn't exist (migration for existing DBs)
-        let _ = conn
-            .execute("ALTER TABLE messages ADD COLUMN topic_id INTEGER", ())
-            .await;
+        // Locations and venues shared in messages
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_geo (
+                chat_id INTEGER NOT NULL,
+                msg_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                lat REAL NOT NULL,
+                lon REAL NOT NULL,
+                accuracy_radius INTEGER,
+                title TEXT,
+                address TEXT,
+                provider TEXT,
+                venue_id TEXT,
+                live_period INTEGER,
+                PRIMARY KEY (chat_id, msg_id)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create message_geo table")?;
 
-        // Add last_sync_message_id column if it doesn't exist (migration for existing DBs)
+        // Link previews, with the linked domain for filtering
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_webpages (
+                chat_id INTEGER NOT NULL,
+                msg_id INTEGER NOT NULL,
+                url TEXT NOT NULL,
+                domain TEXT,
+                site_name TEXT,
+                title TEXT,
+                description TEXT,
+                PRIMARY KEY (chat_id, msg_id)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create message_webpages table")?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_message_webpages_domain ON message_webpages(domain)",
+            (),
+        )
+        .await?;
+
+        // Bot keyboards, one row per button
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_buttons (
+                chat_id INTEGER NOT NULL,
+                msg_id INTEGER NOT NULL,
+                row_idx INTEGER NOT NULL,
+                col_idx INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                text TEXT NOT NULL,
+                url TEXT,
+                PRIMARY KEY (chat_id, msg_id, row_idx, col_idx)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create message_buttons table")?;
+
+        // Polls and their per-option results
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_polls (
+                chat_id INTEGER NOT NULL,
+                msg_id INTEGER NOT NULL,
+                poll_id INTEGER NOT NULL,
+                question TEXT NOT NULL,
+                closed INTEGER NOT NULL DEFAULT 0,
+                multiple_choice INTEGER NOT NULL DEFAULT 0,
+                quiz INTEGER NOT NULL DEFAULT 0,
+                public_voters INTEGER NOT NULL DEFAULT 0,
+                total_voters INTEGER,
+                close_date TEXT,
+                PRIMARY KEY (chat_id, msg_id)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create message_polls table")?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS poll_options (
+                chat_id INTEGER NOT NULL,
+                msg_id INTEGER NOT NULL,
+                idx INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                voters INTEGER,
+                chosen INTEGER NOT NULL DEFAULT 0,
+                correct INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (chat_id, msg_id, idx)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create poll_options table")?;
+
+        // Member lists of groups/channels, one set of rows per snapshot
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS chat_members (
+                chat_id INTEGER NOT NULL,
+                snapshot_ts TEXT NOT NULL,
+                user_id INTEGER NOT NULL,
+                username TEXT,
+                name TEXT NOT NULL DEFAULT '',
+                role TEXT NOT NULL DEFAULT 'member',
+                PRIMARY KEY (chat_id, snapshot_ts, user_id)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create chat_members table")?;
+
+        // Keyword alert rules for the daemon
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS alerts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                pattern TEXT NOT NULL,
+                chat_id INTEGER,
+                notify TEXT NOT NULL,
+                target TEXT
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create alerts table")?;
+
+        // Auto-forward rules for the daemon, with their statistics
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS forward_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                from_chat_id INTEGER NOT NULL,
+                to_chat_id INTEGER NOT NULL,
+                pattern TEXT,
+                forwarded INTEGER NOT NULL DEFAULT 0,
+                failed INTEGER NOT NULL DEFAULT 0,
+                last_forwarded TEXT
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create forward_rules table")?;
+
+        // Recurring messages for the daemon
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS schedules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                cron TEXT NOT NULL,
+                text TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                last_run TEXT,
+                runs INTEGER NOT NULL DEFAULT 0
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create schedules table")?;
+
+        // Away mode: a single row (id 1) while it's on, and who was answered when
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS away_mode (
+                id INTEGER PRIMARY KEY,
+                message TEXT NOT NULL,
+                since TEXT NOT NULL,
+                until TEXT,
+                interval_minutes INTEGER NOT NULL,
+                allow TEXT NOT NULL DEFAULT '[]',
+                deny TEXT NOT NULL DEFAULT '[]'
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create away_mode table")?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS away_replies (
+                user_id INTEGER PRIMARY KEY,
+                replied_at TEXT NOT NULL
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create away_replies table")?;
+
+        // Scoped API tokens; only hashes of the secrets are kept
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS api_tokens (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                token_hash TEXT NOT NULL UNIQUE,
+                scopes TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL,
+                last_used TEXT
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create api_tokens table")?;
+
+        // Changes made through tgcli (`tgcli audit`)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                ts TEXT NOT NULL,
+                command TEXT NOT NULL,
+                action TEXT NOT NULL,
+                chat_id INTEGER,
+                ok INTEGER NOT NULL,
+                error TEXT
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create audit_log table")?;
+        let _ = conn
+            .execute(
+                "CREATE INDEX IF NOT EXISTS idx_audit_log_ts ON audit_log(ts)",
+                (),
+            )
+            .await;
+
+        // Per-chat retention policies, applied by `store prune` and the daemon
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS retention_policies (
+                chat_id INTEGER PRIMARY KEY,
+                keep_days INTEGER NOT NULL,
+                delete_media INTEGER NOT NULL DEFAULT 0,
+                pruned INTEGER NOT NULL DEFAULT 0,
+                last_pruned TEXT
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create retention_policies table")?;
+
+        // Folder membership recorded by `sync --folder`
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS chat_folders (
+                folder_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                PRIMARY KEY (folder_id, chat_id)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create chat_folders table")?;
+
+        // Chats whose last sync failed, for `sync retry-failed`
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_errors (
+                chat_id INTEGER PRIMARY KEY,
+                error TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 1,
+                failed_at TEXT NOT NULL
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create sync_errors table")?;
+
+        // Earlier versions of edited messages
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_edits (
+                chat_id INTEGER NOT NULL,
+                msg_id INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                replaced_at TEXT NOT NULL
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create message_edits table")?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_message_edits ON message_edits(chat_id, msg_id)",
+            (),
+        )
+        .await
+        .context("Failed to create message_edits index")?;
+
+        // Reaction counts per message, replaced whenever Telegram sends new ones
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_reactions (
+                chat_id INTEGER NOT NULL,
+                msg_id INTEGER NOT NULL,
+                reaction TEXT NOT NULL,
+                count INTEGER NOT NULL,
+                chosen INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (chat_id, msg_id, reaction)
+            )",
+            (),
+        )
+        .await
+        .context("Failed to create message_reactions table")?;
+
+        // Add media_path column if it doesn't exist (migration for existing DBs)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN media_path TEXT", ())
+            .await;
+
+        // Add is_forum column if it doesn't exist (migration for existing DBs)
+        let _ = conn
+            .execute(
+                "ALTER TABLE chats ADD COLUMN is_forum INTEGER DEFAULT 0",
+                (),
+            )
+            .await;
+
+        // Ensure any NULL is_forum values are set to 0 (migration for existing DBs)
+        let _ = conn
+            .execute("UPDATE chats SET is_forum = 0 WHERE is_forum IS NULL", ())
+            .await;
+
+        // Add topic_id column if it doesn't exist (migration for existing DBs)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN topic_id INTEGER", ())
+            .await;
+
+        // Add last_sync_message_id column if it doesn't exist (migration for existing DBs)
         let _ = conn
             .execute(
                 "ALTER TABLE chats ADD COLUMN last_sync_message_id INTEGER",
@@ -253,6 +1764,99 @@ impl Store {
             )
             .await;
 
+        // Add unread_count column to chats if it doesn't exist (migration for existing DBs)
+        let _ = conn
+            .execute(
+                "ALTER TABLE chats ADD COLUMN unread_count INTEGER DEFAULT 0",
+                (),
+            )
+            .await;
+
+        // When each chat was last synced, for `sync status`
+        let _ = conn
+            .execute("ALTER TABLE chats ADD COLUMN last_synced_at TEXT", ())
+            .await;
+
+        // Add media_sha256 column if it doesn't exist (schema version 2)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN media_sha256 TEXT", ())
+            .await;
+
+        // Add thread_root_id column if it doesn't exist (schema version 3)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN thread_root_id INTEGER", ())
+            .await;
+
+        // Deletions seen by the daemon mark messages instead of removing them
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN deleted_at TEXT", ())
+            .await;
+
+        // Add service_action column if it doesn't exist (schema version 4)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN service_action TEXT", ())
+            .await;
+
+        // Add forward origin columns if they don't exist (schema version 5)
+        for column in ["fwd_from_name TEXT", "fwd_from_id INTEGER", "fwd_date TEXT"] {
+            let _ = conn
+                .execute(&format!("ALTER TABLE messages ADD COLUMN {}", column), ())
+                .await;
+        }
+
+        // Add attribution columns if they don't exist (schema version 6)
+        for column in ["via_bot_id INTEGER", "post_author TEXT"] {
+            let _ = conn
+                .execute(&format!("ALTER TABLE messages ADD COLUMN {}", column), ())
+                .await;
+        }
+
+        // Add view/forward counters if they don't exist (schema version 7)
+        for column in ["views INTEGER", "forwards INTEGER"] {
+            let _ = conn
+                .execute(&format!("ALTER TABLE messages ADD COLUMN {}", column), ())
+                .await;
+        }
+
+        // Add grouped_id column if it doesn't exist (schema version 8)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN grouped_id INTEGER", ())
+            .await;
+
+        // Add shared_contact column if it doesn't exist (schema version 9)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN shared_contact TEXT", ())
+            .await;
+
+        // Add paid_stars column if it doesn't exist (schema version 10)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN paid_stars INTEGER", ())
+            .await;
+
+        // Add topics.top_message if it doesn't exist (schema version 11)
+        let _ = conn
+            .execute("ALTER TABLE topics ADD COLUMN top_message INTEGER", ())
+            .await;
+
+        // Add media_name and media_size columns if they don't exist (schema version 12)
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN media_name TEXT", ())
+            .await;
+        let _ = conn
+            .execute("ALTER TABLE messages ADD COLUMN media_size INTEGER", ())
+            .await;
+
+        // Add media duration and dimension columns if they don't exist (schema version 13)
+        for column in [
+            "media_duration REAL",
+            "media_width INTEGER",
+            "media_height INTEGER",
+        ] {
+            let _ = conn
+                .execute(&format!("ALTER TABLE messages ADD COLUMN {}", column), ())
+                .await;
+        }
+
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_messages_chat_ts ON messages(chat_id, ts)",
             (),
@@ -268,15 +1872,52 @@ impl Store {
             (),
         )
         .await?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_messages_sender_ts ON messages(sender_id, ts)",
+            (),
+        )
+        .await?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_stickers_emoji ON stickers(emoji, rank)",
+            (),
+        )
+        .await?;
+
+        // Record the schema version, never lowering one written by a newer tgcli
+        let version: i64 = {
+            let mut rows = conn.query("PRAGMA user_version", ()).await?;
+            rows.next()
+                .await?
+                .map(|r| r.get(0).unwrap_or(0))
+                .unwrap_or(0)
+        };
+        if version < SCHEMA_VERSION {
+            let _ = conn
+                .query(&format!("PRAGMA user_version = {}", SCHEMA_VERSION), ())
+                .await;
+        }
+
+        // An existing index keeps the tokenizer it was built with
+        self.setup_fts(conn, None).await
+    }
 
+    /// Create the FTS5 index (with `tokenize`, or FTS5's default unicode61)
+    /// and its triggers if missing, and fill it if it's behind `messages`.
+    async fn setup_fts(&mut self, conn: &Conn, tokenize: Option<&str>) -> Result<()> {
         // Try to create FTS5 table
+        let tokenize = tokenize
+            .map(|t| format!(",\n                tokenize = {}", sql_quote(t)))
+            .unwrap_or_default();
         let fts_result = conn
             .execute(
-                "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+                &format!(
+                    "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
                 text,
                 content='messages',
-                content_rowid='rowid'
+                content_rowid='rowid'{}
             )",
+                    tokenize
+                ),
                 (),
             )
             .await;
@@ -320,6 +1961,7 @@ impl Store {
             log::warn!("FTS5 triggers failed, search will use LIKE fallback");
             return Ok(());
         }
+        self.fts_tokenizer = fts_tokenizer(conn).await;
 
         // Check if FTS index needs to be populated from existing messages
         let msg_count: i64 = {
@@ -361,10 +2003,71 @@ impl Store {
         Ok(())
     }
 
+    /// Rebuild the FTS5 index from scratch with another tokenizer. The
+    /// choice is kept in the index's definition, so later migrations leave
+    /// it alone. Returns the number of messages indexed.
+    pub async fn rebuild_fts(
+        &mut self,
+        tokenizer: FtsTokenizer,
+        tokenchars: Option<&str>,
+    ) -> Result<u64> {
+        if !self.has_fts {
+            anyhow::bail!("FTS5 is not available in this build; search uses LIKE");
+        }
+        let conn = self.get_conn().await?;
+        let spec = tokenizer.spec(tokenchars);
+        // One transaction, so a tokenizer FTS5 rejects leaves the old index
+        conn.execute("BEGIN", ()).await?;
+        let rebuilt = async {
+            for trigger in ["messages_ai", "messages_ad", "messages_au"] {
+                conn.execute(&format!("DROP TRIGGER IF EXISTS {}", trigger), ())
+                    .await?;
+            }
+            conn.execute("DROP TABLE IF EXISTS messages_fts", ())
+                .await
+                .context("Failed to drop the FTS5 index")?;
+            self.setup_fts(&conn, Some(&spec)).await?;
+            if !self.has_fts {
+                anyhow::bail!("FTS5 rejected the tokenizer '{}'", spec);
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = rebuilt {
+            let _ = conn.execute("ROLLBACK", ()).await;
+            self.has_fts = true;
+            return Err(e);
+        }
+        conn.execute("COMMIT", ()).await?;
+
+        let mut rows = conn.query("SELECT COUNT(*) FROM messages_fts", ()).await?;
+        Ok(rows
+            .next()
+            .await?
+            .map(|r| r.get::<i64>(0).unwrap_or(0) as u64)
+            .unwrap_or(0))
+    }
+
+    /// Tokenizer of the FTS5 index (meaningless without `has_fts`).
+    pub fn fts_tokenizer(&self) -> FtsTokenizer {
+        self.fts_tokenizer
+    }
+
     pub fn has_fts(&self) -> bool {
         self.has_fts
     }
 
+    /// Schema version recorded in the database (see `SCHEMA_VERSION`).
+    pub async fn schema_version(&self) -> Result<i64> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query("PRAGMA user_version", ()).await?;
+        Ok(rows
+            .next()
+            .await?
+            .map(|r| r.get(0).unwrap_or(0))
+            .unwrap_or(0))
+    }
+
     // --- Chats ---
 
     #[allow(clippy::too_many_arguments)]
@@ -383,8 +2086,7 @@ impl Store {
         let is_forum_int = is_forum as i64;
         let archived_int = archived as i64;
 
-        let conn = self.get_conn().await?;
-        conn.execute(
+        self.execute_cached(
             "INSERT INTO chats (id, kind, name, username, last_message_ts, is_forum, access_hash, archived)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
              ON CONFLICT(id) DO UPDATE SET
@@ -407,6 +2109,9 @@ impl Store {
         query: Option<&str>,
         limit: i64,
         archived_only: Option<bool>,
+        unread_only: bool,
+        folder_id: Option<i32>,
+        sort: ChatSort,
     ) -> Result<Vec<Chat>> {
         let conn = self.get_conn().await?;
         let mut chats = Vec::new();
@@ -417,14 +2122,30 @@ impl Store {
             Some(false) => " AND archived = 0",
             None => "",
         };
+        let unread_clause = if unread_only {
+            " AND unread_count > 0"
+        } else {
+            ""
+        };
+        let folder_clause = folder_id
+            .map(|id| {
+                format!(
+                    " AND id IN (SELECT chat_id FROM chat_folders WHERE folder_id = {})",
+                    id
+                )
+            })
+            .unwrap_or_default();
 
         if let Some(q) = query {
             let pattern = format!("%{}%", q);
             let sql = format!(
-                "SELECT id, kind, name, username, last_message_ts, is_forum, last_sync_message_id, access_hash, archived FROM chats
-                 WHERE (name LIKE ?1 OR username LIKE ?1){}
-                 ORDER BY last_message_ts DESC LIMIT ?2",
-                archived_clause
+                "SELECT id, kind, name, username, last_message_ts, is_forum, last_sync_message_id, access_hash, archived, unread_count FROM chats
+                 WHERE (name LIKE ?1 OR username LIKE ?1){}{}{}
+                 ORDER BY {} LIMIT ?2",
+                archived_clause,
+                unread_clause,
+                folder_clause,
+                sort.order_by()
             );
             let mut rows = conn.query(&sql, (pattern.as_str(), limit)).await?;
             while let Some(row) = rows.next().await? {
@@ -432,10 +2153,13 @@ impl Store {
             }
         } else {
             let sql = format!(
-                "SELECT id, kind, name, username, last_message_ts, is_forum, last_sync_message_id, access_hash, archived FROM chats
-                 WHERE 1=1{}
-                 ORDER BY last_message_ts DESC LIMIT ?1",
-                archived_clause
+                "SELECT id, kind, name, username, last_message_ts, is_forum, last_sync_message_id, access_hash, archived, unread_count FROM chats
+                 WHERE 1=1{}{}{}
+                 ORDER BY {} LIMIT ?1",
+                archived_clause,
+                unread_clause,
+                folder_clause,
+                sort.order_by()
             );
             let mut rows = conn.query(&sql, [limit]).await?;
             while let Some(row) = rows.next().await? {
@@ -449,7 +2173,7 @@ impl Store {
         let conn = self.get_conn().await?;
         let mut rows = conn
             .query(
-                "SELECT id, kind, name, username, last_message_ts, is_forum, last_sync_message_id, access_hash, archived FROM chats WHERE id = ?1",
+                "SELECT id, kind, name, username, last_message_ts, is_forum, last_sync_message_id, access_hash, archived, unread_count FROM chats WHERE id = ?1",
                 [id],
             )
             .await?;
@@ -460,22 +2184,50 @@ impl Store {
         }
     }
 
-    /// Delete a chat from local database. Returns true if a chat was deleted.
-    pub async fn delete_chat(&self, id: i64) -> Result<bool> {
+    /// Record the unread message count for a chat, as reported by its dialog.
+    pub async fn set_chat_unread_count(&self, chat_id: i64, unread_count: i32) -> Result<()> {
         let conn = self.get_conn().await?;
-        let affected = conn
-            .execute("DELETE FROM chats WHERE id = ?1", [id])
-            .await?;
-        Ok(affected > 0)
+        conn.execute(
+            "UPDATE chats SET unread_count = ?2 WHERE id = ?1",
+            (chat_id, unread_count as i64),
+        )
+        .await?;
+        Ok(())
     }
 
-    /// Get the last sync message ID for a chat.
-    pub async fn get_last_sync_message_id(&self, chat_id: i64) -> Result<Option<i64>> {
+    /// ID of the chat or contact with this username (without the `@`).
+    pub async fn find_peer_by_username(&self, username: &str) -> Result<Option<i64>> {
         let conn = self.get_conn().await?;
         let mut rows = conn
             .query(
-                "SELECT last_sync_message_id FROM chats WHERE id = ?1",
-                [chat_id],
+                "SELECT id FROM chats WHERE LOWER(username) = LOWER(?1)
+                 UNION SELECT user_id FROM contacts WHERE LOWER(username) = LOWER(?1)
+                 LIMIT 1",
+                [username],
+            )
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Delete a chat from local database. Returns true if a chat was deleted.
+    pub async fn delete_chat(&self, id: i64) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let affected = conn
+            .execute("DELETE FROM chats WHERE id = ?1", [id])
+            .await?;
+        Ok(affected > 0)
+    }
+
+    /// Get the last sync message ID for a chat.
+    pub async fn get_last_sync_message_id(&self, chat_id: i64) -> Result<Option<i64>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT last_sync_message_id FROM chats WHERE id = ?1",
+                [chat_id],
             )
             .await?;
         if let Some(row) = rows.next().await? {
@@ -490,7 +2242,7 @@ impl Store {
         let conn = self.get_conn().await?;
         let mut rows = conn
             .query(
-                "SELECT id, kind, name, username, last_message_ts, is_forum, last_sync_message_id, access_hash, archived 
+                "SELECT id, kind, name, username, last_message_ts, is_forum, last_sync_message_id, access_hash, archived, unread_count
                  FROM chats WHERE last_sync_message_id IS NOT NULL 
                  ORDER BY last_message_ts DESC",
                 (),
@@ -505,8 +2257,7 @@ impl Store {
 
     /// Update the last sync message ID for a chat.
     pub async fn update_last_sync_message_id(&self, chat_id: i64, new_id: i64) -> Result<()> {
-        let conn = self.get_conn().await?;
-        conn.execute(
+        self.execute_cached(
             "UPDATE chats SET last_sync_message_id = ?2 
              WHERE id = ?1 AND (last_sync_message_id IS NULL OR last_sync_message_id < ?2)",
             (chat_id, new_id),
@@ -515,6 +2266,126 @@ impl Store {
         Ok(())
     }
 
+    /// Note that a chat was synced at `ts`. A failure goes to the error
+    /// journal; a success clears the chat from it.
+    pub async fn record_chat_sync(
+        &self,
+        chat_id: i64,
+        ts: DateTime<Utc>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE chats SET last_synced_at = ?2 WHERE id = ?1",
+            (chat_id, ts.to_rfc3339()),
+        )
+        .await?;
+        match error {
+            Some(error) => {
+                conn.execute(
+                    "INSERT INTO sync_errors (chat_id, error, failed_at) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(chat_id) DO UPDATE SET
+                        error = excluded.error,
+                        attempts = attempts + 1,
+                        failed_at = excluded.failed_at",
+                    (chat_id, error, ts.to_rfc3339()),
+                )
+                .await?;
+            }
+            None => {
+                conn.execute("DELETE FROM sync_errors WHERE chat_id = ?1", [chat_id])
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Chats in the error journal, most recent failure first.
+    pub async fn list_sync_errors(&self) -> Result<Vec<SyncError>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT e.chat_id, COALESCE(c.name, ''), e.error, e.attempts, e.failed_at
+                 FROM sync_errors e LEFT JOIN chats c ON c.id = e.chat_id
+                 ORDER BY e.failed_at DESC",
+                (),
+            )
+            .await?;
+        let mut errors = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let failed_at: String = row.get(4)?;
+            errors.push(SyncError {
+                chat_id: row.get(0)?,
+                name: row.get(1)?,
+                error: row.get(2)?,
+                attempts: row.get(3)?,
+                failed_at: DateTime::parse_from_rfc3339(&failed_at)
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(errors)
+    }
+
+    /// Sync progress of every chat that was synced (or failed to), most
+    /// recently active first.
+    pub async fn sync_status(
+        &self,
+        chat_id: Option<i64>,
+        errors_only: bool,
+        limit: i64,
+    ) -> Result<Vec<ChatSyncStatus>> {
+        let mut conditions = vec![
+            "(c.last_sync_message_id IS NOT NULL OR c.last_synced_at IS NOT NULL)".to_string(),
+        ];
+        if let Some(id) = chat_id {
+            conditions.push(format!("c.id = {}", id));
+        }
+        if errors_only {
+            conditions.push("e.error IS NOT NULL".to_string());
+        }
+        let pending = format!(
+            "m.media_type IS NOT NULL AND m.media_path IS NULL AND m.media_type NOT IN ({})",
+            UNDOWNLOADABLE_MEDIA
+                .iter()
+                .map(|t| format!("'{}'", t))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let sql = format!(
+            "SELECT c.id, c.name, c.last_synced_at, c.last_sync_message_id,
+                (SELECT MIN(m.id) FROM messages m WHERE m.chat_id = c.id),
+                (SELECT COUNT(*) FROM messages m WHERE m.chat_id = c.id AND {pending}),
+                (SELECT COALESCE(SUM(m.media_size), 0) FROM messages m
+                    WHERE m.chat_id = c.id AND {pending}),
+                e.error
+             FROM chats c LEFT JOIN sync_errors e ON e.chat_id = c.id WHERE {conditions}
+             ORDER BY c.last_message_ts DESC LIMIT ?1",
+            conditions = conditions.join(" AND ")
+        );
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query(&sql, [limit]).await?;
+        let mut statuses = Vec::new();
+        while let Some(row) = rows.next().await? {
+            statuses.push(ChatSyncStatus {
+                chat_id: row.get(0)?,
+                name: row.get(1)?,
+                last_synced: row
+                    .get::<Option<String>>(2)
+                    .ok()
+                    .flatten()
+                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|t| t.with_timezone(&Utc)),
+                last_message_id: row.get(3)?,
+                backfill_cursor: row.get(4)?,
+                pending_media: row.get(5)?,
+                pending_media_bytes: row.get(6)?,
+                error: row.get(7)?,
+            });
+        }
+        Ok(statuses)
+    }
+
     /// Get the highest message ID we have stored for a chat.
     #[allow(dead_code)]
     pub async fn get_highest_message_id(&self, chat_id: i64) -> Result<Option<i64>> {
@@ -538,8 +2409,242 @@ impl Store {
         Ok(affected)
     }
 
+    /// Delete a chat's messages sent before `before`.
+    pub async fn delete_messages_before(&self, chat_id: i64, before: DateTime<Utc>) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let before = before.to_rfc3339();
+        // Count first: turso's change count for this DELETE includes index rows
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) FROM messages WHERE chat_id = ?1 AND ts < ?2",
+                (chat_id, before.as_str()),
+            )
+            .await?;
+        let count: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+        drop(rows);
+        conn.execute(
+            "DELETE FROM messages WHERE chat_id = ?1 AND ts < ?2",
+            (chat_id, before.as_str()),
+        )
+        .await?;
+        Ok(count as u64)
+    }
+
+    /// Mark a message as a comment on channel post `root_id` (the post lives
+    /// in the channel; the comment in its linked discussion group).
+    pub async fn set_thread_root(&self, chat_id: i64, msg_id: i64, root_id: i64) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET thread_root_id = ?1 WHERE chat_id = ?2 AND id = ?3",
+            (root_id, chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Mark a message as a service message and record what happened.
+    pub async fn set_service_action(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        action: &serde_json::Value,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET media_type = ?1, service_action = ?2 WHERE chat_id = ?3 AND id = ?4",
+            (SERVICE_MEDIA_TYPE, action.to_string(), chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record where a forwarded message came from.
+    pub async fn set_forward_origin(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        origin: &ForwardOrigin,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET
+                fwd_from_name = COALESCE(
+                    ?1,
+                    (SELECT name FROM chats WHERE id = ?2),
+                    (SELECT TRIM(first_name || ' ' || last_name) FROM contacts WHERE user_id = ?2)
+                ),
+                fwd_from_id = ?2,
+                fwd_date = ?3
+             WHERE chat_id = ?4 AND id = ?5",
+            (
+                origin.name.as_deref(),
+                origin.from_id,
+                origin.date.to_rfc3339(),
+                chat_id,
+                msg_id,
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record the inline bot and channel signature of a message.
+    pub async fn set_attribution(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        attribution: &Attribution,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET via_bot_id = ?1, post_author = ?2 WHERE chat_id = ?3 AND id = ?4",
+            (
+                attribution.via_bot_id,
+                attribution.post_author.as_deref(),
+                chat_id,
+                msg_id,
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record the view and forward counts of a channel post. Missing counts
+    /// keep their previous value.
+    pub async fn set_counters(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        views: Option<i64>,
+        forwards: Option<i64>,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET views = COALESCE(?1, views), forwards = COALESCE(?2, forwards)
+             WHERE chat_id = ?3 AND id = ?4",
+            (views, forwards, chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Posts of a chat with the highest view or forward counts.
+    pub async fn top_messages(
+        &self,
+        chat_id: i64,
+        by: MessageRank,
+        after: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<Message>> {
+        let conn = self.get_conn().await?;
+        let sql = format!(
+            "SELECT {} FROM messages m
+             WHERE chat_id = ?1 AND {1} IS NOT NULL AND (?2 IS NULL OR ts >= ?2)
+             ORDER BY {1} DESC, id DESC LIMIT ?3",
+            MESSAGE_COLUMNS,
+            by.column()
+        );
+        let after = after.map(|t| t.to_rfc3339());
+        let mut rows = conn.query(&sql, (chat_id, after, limit)).await?;
+        let mut msgs = Vec::new();
+        while let Some(row) = rows.next().await? {
+            msgs.push(row_to_message(&row)?);
+        }
+        Ok(msgs)
+    }
+
+    /// Record the contact card shared in a message.
+    pub async fn set_shared_contact(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        contact: &SharedContact,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET shared_contact = ?1 WHERE chat_id = ?2 AND id = ?3",
+            (serde_json::to_string(contact)?, chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record the star price of a paid media message. Paid media has no
+    /// regular media, so `media_type` becomes `paid`.
+    pub async fn set_paid_media(&self, chat_id: i64, msg_id: i64, stars: i64) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET paid_stars = ?1, media_type = COALESCE(media_type, 'paid')
+             WHERE chat_id = ?2 AND id = ?3",
+            (stars, chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record the file name, size, duration and dimensions of a message's
+    /// photo or document.
+    pub async fn set_media_file_info(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        info: &MediaFileInfo,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET media_name = ?1, media_size = ?2, media_duration = ?3,
+                media_width = ?4, media_height = ?5
+             WHERE chat_id = ?6 AND id = ?7",
+            (
+                info.name.as_deref(),
+                info.size,
+                info.duration,
+                info.width,
+                info.height,
+                chat_id,
+                msg_id,
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record the album (media group) a message belongs to.
+    pub async fn set_grouped_id(&self, chat_id: i64, msg_id: i64, grouped_id: i64) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET grouped_id = ?1 WHERE chat_id = ?2 AND id = ?3",
+            (grouped_id, chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Comments stored for a channel post, oldest first.
+    pub async fn list_thread(&self, chat_id: i64, root_id: i64) -> Result<Vec<Message>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM messages m WHERE chat_id = ?1 AND thread_root_id = ?2 ORDER BY ts, id",
+                    MESSAGE_COLUMNS
+                ),
+                (chat_id, root_id),
+            )
+            .await?;
+        let mut msgs = Vec::new();
+        while let Some(row) = rows.next().await? {
+            msgs.push(row_to_message(&row)?);
+        }
+        Ok(msgs)
+    }
+
     // --- Topics ---
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn upsert_topic(
         &self,
         chat_id: i64,
@@ -548,16 +2653,18 @@ impl Store {
         icon_color: i32,
         icon_emoji: Option<&str>,
         unread_count: i32,
+        top_message: Option<i32>,
     ) -> Result<()> {
         let conn = self.get_conn().await?;
         conn.execute(
-            "INSERT INTO topics (chat_id, topic_id, name, icon_color, icon_emoji, unread_count)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "INSERT INTO topics (chat_id, topic_id, name, icon_color, icon_emoji, unread_count, top_message)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
              ON CONFLICT(chat_id, topic_id) DO UPDATE SET
                 name = CASE WHEN excluded.name != '' THEN excluded.name ELSE name END,
                 icon_color = excluded.icon_color,
                 icon_emoji = COALESCE(excluded.icon_emoji, icon_emoji),
-                unread_count = excluded.unread_count",
+                unread_count = excluded.unread_count,
+                top_message = COALESCE(excluded.top_message, top_message)",
             (
                 chat_id,
                 topic_id,
@@ -565,17 +2672,52 @@ impl Store {
                 icon_color,
                 icon_emoji,
                 unread_count,
+                top_message,
             ),
         )
         .await?;
         Ok(())
     }
 
+    /// Record the latest message of a topic.
+    pub async fn set_topic_top_message(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+        top_message: i32,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE topics SET top_message = ?1 WHERE chat_id = ?2 AND topic_id = ?3",
+            (top_message, chat_id, topic_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record that a topic was read up to `read_max_id`: its unread count is
+    /// cleared when that covers the latest message.
+    pub async fn mark_topic_read(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+        read_max_id: i32,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE topics SET unread_count = 0
+             WHERE chat_id = ?1 AND topic_id = ?2 AND COALESCE(top_message, 0) <= ?3",
+            (chat_id, topic_id, read_max_id),
+        )
+        .await?;
+        Ok(())
+    }
+
     pub async fn list_topics(&self, chat_id: i64) -> Result<Vec<Topic>> {
         let conn = self.get_conn().await?;
         let mut rows = conn
             .query(
-                "SELECT chat_id, topic_id, name, icon_color, icon_emoji, unread_count FROM topics
+                "SELECT chat_id, topic_id, name, icon_color, icon_emoji, unread_count, top_message FROM topics
                  WHERE chat_id = ?1 ORDER BY topic_id",
                 [chat_id],
             )
@@ -587,11 +2729,29 @@ impl Store {
         Ok(topics)
     }
 
+    /// Search forum topics by name across all chats.
+    pub async fn search_topics(&self, query: &str, limit: i64) -> Result<Vec<Topic>> {
+        let pattern = format!("%{}%", query);
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT chat_id, topic_id, name, icon_color, icon_emoji, unread_count, top_message FROM topics
+                 WHERE name LIKE ?1 ORDER BY name LIMIT ?2",
+                (pattern.as_str(), limit),
+            )
+            .await?;
+        let mut topics = Vec::new();
+        while let Some(row) = rows.next().await? {
+            topics.push(row_to_topic(&row)?);
+        }
+        Ok(topics)
+    }
+
     pub async fn get_topic(&self, chat_id: i64, topic_id: i32) -> Result<Option<Topic>> {
         let conn = self.get_conn().await?;
         let mut rows = conn
             .query(
-                "SELECT chat_id, topic_id, name, icon_color, icon_emoji, unread_count FROM topics
+                "SELECT chat_id, topic_id, name, icon_color, icon_emoji, unread_count, top_message FROM topics
                  WHERE chat_id = ?1 AND topic_id = ?2",
                 (chat_id, topic_id),
             )
@@ -603,287 +2763,823 @@ impl Store {
         }
     }
 
-    // --- Contacts ---
+    // --- Member snapshots ---
 
-    pub async fn upsert_contact(
+    /// Record the member list of a chat as of `ts`.
+    pub async fn save_member_snapshot(
         &self,
-        user_id: i64,
-        username: Option<&str>,
-        first_name: &str,
-        last_name: &str,
-        phone: &str,
+        chat_id: i64,
+        ts: DateTime<Utc>,
+        members: &[ChatMember],
     ) -> Result<()> {
         let conn = self.get_conn().await?;
+        let ts = ts.to_rfc3339();
+        // Replace the snapshot as a whole; turso's INSERT OR REPLACE can
+        // update the wrong row under a composite primary key
         conn.execute(
-            "INSERT INTO contacts (user_id, username, first_name, last_name, phone)
-             VALUES (?1, ?2, ?3, ?4, ?5)
-             ON CONFLICT(user_id) DO UPDATE SET
-                username = COALESCE(excluded.username, username),
-                first_name = CASE WHEN excluded.first_name != '' THEN excluded.first_name ELSE first_name END,
-                last_name = CASE WHEN excluded.last_name != '' THEN excluded.last_name ELSE last_name END,
-                phone = CASE WHEN excluded.phone != '' THEN excluded.phone ELSE phone END",
-            (user_id, username, first_name, last_name, phone),
+            "DELETE FROM chat_members WHERE chat_id = ?1 AND snapshot_ts = ?2",
+            (chat_id, ts.as_str()),
         )
         .await?;
+        for m in members {
+            conn.execute(
+                "INSERT INTO chat_members (chat_id, snapshot_ts, user_id, username, name, role)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                (
+                    chat_id,
+                    ts.as_str(),
+                    m.user_id,
+                    m.username.as_deref(),
+                    m.name.as_str(),
+                    m.role.as_str(),
+                ),
+            )
+            .await?;
+        }
         Ok(())
     }
 
-    pub async fn search_contacts(&self, query: &str, limit: i64) -> Result<Vec<Contact>> {
-        let pattern = format!("%{}%", query);
+    /// Times of the member snapshots of a chat, oldest first.
+    pub async fn member_snapshots(&self, chat_id: i64) -> Result<Vec<DateTime<Utc>>> {
         let conn = self.get_conn().await?;
         let mut rows = conn
             .query(
-                "SELECT user_id, username, first_name, last_name, phone FROM contacts
-                 WHERE first_name LIKE ?1 OR last_name LIKE ?1 OR username LIKE ?1 OR phone LIKE ?1
-                 ORDER BY first_name LIMIT ?2",
-                (pattern.as_str(), limit),
+                "SELECT DISTINCT snapshot_ts FROM chat_members WHERE chat_id = ?1
+                 ORDER BY snapshot_ts",
+                [chat_id],
             )
             .await?;
-        let mut contacts = Vec::new();
+        let mut snapshots = Vec::new();
         while let Some(row) = rows.next().await? {
-            contacts.push(row_to_contact(&row)?);
+            let ts: String = row.get(0)?;
+            if let Ok(ts) = DateTime::parse_from_rfc3339(&ts) {
+                snapshots.push(ts.with_timezone(&Utc));
+            }
         }
-        Ok(contacts)
+        Ok(snapshots)
     }
 
-    pub async fn get_contact(&self, user_id: i64) -> Result<Option<Contact>> {
+    /// Members recorded in one snapshot.
+    pub async fn member_snapshot(
+        &self,
+        chat_id: i64,
+        ts: DateTime<Utc>,
+    ) -> Result<Vec<ChatMember>> {
         let conn = self.get_conn().await?;
         let mut rows = conn
             .query(
-                "SELECT user_id, username, first_name, last_name, phone FROM contacts WHERE user_id = ?1",
-                [user_id],
+                "SELECT user_id, username, name, role FROM chat_members
+                 WHERE chat_id = ?1 AND snapshot_ts = ?2 ORDER BY user_id",
+                (chat_id, ts.to_rfc3339()),
             )
             .await?;
-        if let Some(row) = rows.next().await? {
-            Ok(Some(row_to_contact(&row)?))
-        } else {
-            Ok(None)
+        let mut members = Vec::new();
+        while let Some(row) = rows.next().await? {
+            members.push(ChatMember {
+                user_id: row.get(0)?,
+                username: row.get::<Option<String>>(1).ok().flatten(),
+                name: row.get(2)?,
+                role: row.get(3)?,
+            });
         }
+        Ok(members)
     }
 
-    pub async fn list_contacts(&self, limit: Option<i64>) -> Result<Vec<Contact>> {
-        let query = match limit {
-            Some(l) => format!(
-                "SELECT user_id, username, first_name, last_name, phone FROM contacts ORDER BY first_name LIMIT {}",
-                l
-            ),
-            None => "SELECT user_id, username, first_name, last_name, phone FROM contacts ORDER BY first_name".to_string(),
-        };
+    // --- Alerts ---
+
+    /// Add an alert rule, returns its ID.
+    pub async fn add_alert(
+        &self,
+        pattern: &str,
+        chat_id: Option<i64>,
+        notify: &str,
+        target: Option<&str>,
+    ) -> Result<i64> {
         let conn = self.get_conn().await?;
-        let mut rows = conn.query(&query, ()).await?;
-        let mut contacts = Vec::new();
+        conn.execute(
+            "INSERT INTO alerts (pattern, chat_id, notify, target) VALUES (?1, ?2, ?3, ?4)",
+            (pattern, chat_id, notify, target),
+        )
+        .await?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub async fn list_alerts(&self) -> Result<Vec<Alert>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT id, pattern, chat_id, notify, target FROM alerts ORDER BY id",
+                (),
+            )
+            .await?;
+        let mut alerts = Vec::new();
         while let Some(row) = rows.next().await? {
-            contacts.push(row_to_contact(&row)?);
+            alerts.push(Alert {
+                id: row.get(0)?,
+                pattern: row.get(1)?,
+                chat_id: row.get::<Option<i64>>(2).ok().flatten(),
+                notify: row.get(3)?,
+                target: row.get::<Option<String>>(4).ok().flatten(),
+            });
         }
-        Ok(contacts)
+        Ok(alerts)
     }
 
-    // --- Messages ---
+    /// Remove an alert rule. Returns false if there was none with that ID.
+    pub async fn remove_alert(&self, id: i64) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let affected = conn
+            .execute("DELETE FROM alerts WHERE id = ?1", [id])
+            .await?;
+        Ok(affected > 0)
+    }
 
-    pub async fn upsert_message(&self, p: UpsertMessageParams) -> Result<()> {
-        let ts_str = p.ts.to_rfc3339();
-        let edit_ts_str = p.edit_ts.map(|t| t.to_rfc3339());
-        let from_me_int = p.from_me as i64;
+    // --- Forward rules ---
 
+    /// Add an auto-forward rule, returns its ID.
+    pub async fn add_forward_rule(
+        &self,
+        from_chat_id: i64,
+        to_chat_id: i64,
+        pattern: Option<&str>,
+    ) -> Result<i64> {
         let conn = self.get_conn().await?;
         conn.execute(
-            "INSERT INTO messages (id, chat_id, sender_id, ts, edit_ts, from_me, text, media_type, media_path, reply_to_id, topic_id)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
-             ON CONFLICT(chat_id, id) DO UPDATE SET
-                sender_id = excluded.sender_id,
-                ts = excluded.ts,
-                edit_ts = COALESCE(excluded.edit_ts, edit_ts),
-                from_me = excluded.from_me,
-                text = CASE WHEN excluded.text != '' THEN excluded.text ELSE text END,
-                media_type = COALESCE(excluded.media_type, media_type),
-                media_path = COALESCE(excluded.media_path, media_path),
-                reply_to_id = COALESCE(excluded.reply_to_id, reply_to_id),
-                topic_id = COALESCE(excluded.topic_id, topic_id)",
-            (
-                p.id,
-                p.chat_id,
-                p.sender_id,
-                ts_str.as_str(),
-                edit_ts_str.as_deref(),
-                from_me_int,
-                p.text.as_str(),
-                p.media_type.as_deref(),
-                p.media_path.as_deref(),
-                p.reply_to_id,
-                p.topic_id,
-            ),
+            "INSERT INTO forward_rules (from_chat_id, to_chat_id, pattern) VALUES (?1, ?2, ?3)",
+            (from_chat_id, to_chat_id, pattern),
         )
         .await?;
-        Ok(())
+        Ok(conn.last_insert_rowid())
     }
 
-    pub async fn list_messages(&self, p: ListMessagesParams) -> Result<Vec<Message>> {
+    pub async fn list_forward_rules(&self) -> Result<Vec<ForwardRule>> {
         let conn = self.get_conn().await?;
-
-        // Build dynamic SQL using positional parameters
-        let mut conditions = vec!["1=1".to_string()];
-        let mut param_idx = 1;
-
-        let chat_filter = p.chat_id.map(|_| {
-            let cond = format!("m.chat_id = ?{}", param_idx);
-            param_idx += 1;
-            cond
-        });
-        if let Some(c) = &chat_filter {
-            conditions.push(c.clone());
+        let mut rows = conn
+            .query(
+                "SELECT id, from_chat_id, to_chat_id, pattern, forwarded, failed, last_forwarded
+                 FROM forward_rules ORDER BY id",
+                (),
+            )
+            .await?;
+        let mut rules = Vec::new();
+        while let Some(row) = rows.next().await? {
+            rules.push(ForwardRule {
+                id: row.get(0)?,
+                from_chat_id: row.get(1)?,
+                to_chat_id: row.get(2)?,
+                pattern: row.get::<Option<String>>(3).ok().flatten(),
+                forwarded: row.get(4)?,
+                failed: row.get(5)?,
+                last_forwarded: row
+                    .get::<Option<String>>(6)
+                    .ok()
+                    .flatten()
+                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|t| t.with_timezone(&Utc)),
+            });
         }
+        Ok(rules)
+    }
 
-        let topic_filter = p.topic_id.map(|_| {
-            let cond = format!("m.topic_id = ?{}", param_idx);
-            param_idx += 1;
-            cond
-        });
-        if let Some(c) = &topic_filter {
-            conditions.push(c.clone());
-        }
+    /// Remove a forward rule. Returns false if there was none with that ID.
+    pub async fn remove_forward_rule(&self, id: i64) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let affected = conn
+            .execute("DELETE FROM forward_rules WHERE id = ?1", [id])
+            .await?;
+        Ok(affected > 0)
+    }
 
-        let after_filter = p.after.as_ref().map(|_| {
-            let cond = format!("m.ts > ?{}", param_idx);
-            param_idx += 1;
-            cond
-        });
-        if let Some(c) = &after_filter {
-            conditions.push(c.clone());
+    /// Count a forward made by a rule at `ts`, or a failed one.
+    pub async fn record_forward(&self, id: i64, ok: bool, ts: DateTime<Utc>) -> Result<()> {
+        let conn = self.get_conn().await?;
+        if ok {
+            conn.execute(
+                "UPDATE forward_rules SET forwarded = forwarded + 1, last_forwarded = ?2
+                 WHERE id = ?1",
+                (id, ts.to_rfc3339()),
+            )
+            .await?;
+        } else {
+            conn.execute(
+                "UPDATE forward_rules SET failed = failed + 1 WHERE id = ?1",
+                [id],
+            )
+            .await?;
         }
+        Ok(())
+    }
 
-        let before_filter = p.before.as_ref().map(|_| {
-            let cond = format!("m.ts < ?{}", param_idx);
-            param_idx += 1;
-            cond
-        });
-        if let Some(c) = &before_filter {
-            conditions.push(c.clone());
-        }
+    // --- Recurring messages ---
 
-        // For ignore_chats, we'll use NOT IN with literal values (safe since they're i64)
-        if !p.ignore_chats.is_empty() {
-            let ids: Vec<String> = p.ignore_chats.iter().map(|id| id.to_string()).collect();
-            conditions.push(format!("m.chat_id NOT IN ({})", ids.join(",")));
-        }
+    /// Add a recurring message, returns its ID.
+    pub async fn add_schedule(&self, chat_id: i64, cron: &str, text: &str) -> Result<i64> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO schedules (chat_id, cron, text, created_at) VALUES (?1, ?2, ?3, ?4)",
+            (chat_id, cron, text, Utc::now().to_rfc3339()),
+        )
+        .await?;
+        Ok(conn.last_insert_rowid())
+    }
 
-        if p.ignore_channels {
-            conditions.push("COALESCE(c.kind, '') != 'channel'".to_string());
+    pub async fn list_schedules(&self) -> Result<Vec<Schedule>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT id, chat_id, cron, text, created_at, last_run, runs FROM schedules
+                 ORDER BY id",
+                (),
+            )
+            .await?;
+        let parse = |s: String| {
+            DateTime::parse_from_rfc3339(&s)
+                .ok()
+                .map(|t| t.with_timezone(&Utc))
+        };
+        let mut schedules = Vec::new();
+        while let Some(row) = rows.next().await? {
+            schedules.push(Schedule {
+                id: row.get(0)?,
+                chat_id: row.get(1)?,
+                cron: row.get(2)?,
+                text: row.get(3)?,
+                created_at: parse(row.get(4)?).unwrap_or_default(),
+                last_run: row.get::<Option<String>>(5).ok().flatten().and_then(parse),
+                runs: row.get(6)?,
+            });
         }
+        Ok(schedules)
+    }
 
-        // Limit param
-        let limit_param_idx = param_idx;
-
-        let sql = format!(
-            "SELECT m.id, m.chat_id, m.sender_id, m.ts, m.edit_ts, m.from_me, m.text, m.media_type, m.media_path, m.reply_to_id, m.topic_id
-             FROM messages m
-             LEFT JOIN chats c ON c.id = m.chat_id
-             WHERE {} ORDER BY m.ts DESC LIMIT ?{}",
-            conditions.join(" AND "),
-            limit_param_idx
-        );
-
-        // Build params tuple dynamically
-        use turso::Value;
-        let mut params: Vec<Value> = Vec::new();
+    /// Remove a recurring message. Returns false if there was none with that ID.
+    pub async fn remove_schedule(&self, id: i64) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let affected = conn
+            .execute("DELETE FROM schedules WHERE id = ?1", [id])
+            .await?;
+        Ok(affected > 0)
+    }
 
-        if let Some(chat_id) = p.chat_id {
-            params.push(Value::Integer(chat_id));
-        }
-        if let Some(topic_id) = p.topic_id {
-            params.push(Value::Integer(topic_id as i64));
-        }
-        if let Some(ref after) = p.after {
-            params.push(Value::Text(after.to_rfc3339()));
-        }
-        if let Some(ref before) = p.before {
-            params.push(Value::Text(before.to_rfc3339()));
-        }
-        params.push(Value::Integer(p.limit));
+    /// Record that a recurring message was due at `ts`; `sent` counts it as
+    /// a run (missed or failed runs only move `last_run` forward).
+    pub async fn record_schedule_run(&self, id: i64, ts: DateTime<Utc>, sent: bool) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE schedules SET last_run = ?2, runs = runs + ?3 WHERE id = ?1",
+            (id, ts.to_rfc3339(), sent as i64),
+        )
+        .await?;
+        Ok(())
+    }
 
-        let mut rows = conn.query(&sql, turso::params_from_iter(params)).await?;
+    // --- Away mode ---
 
-        let mut msgs = Vec::new();
-        while let Some(row) = rows.next().await? {
-            msgs.push(row_to_message(&row)?);
-        }
-        msgs.reverse(); // chronological order
-        Ok(msgs)
+    /// Turn away mode on (replacing any previous settings).
+    pub async fn set_away_mode(&self, away: &AwayMode) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute("DELETE FROM away_replies", ()).await?;
+        conn.execute(
+            "INSERT OR REPLACE INTO away_mode (id, message, since, until, interval_minutes, allow, deny)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                away.message.as_str(),
+                away.since.to_rfc3339(),
+                away.until.map(|t| t.to_rfc3339()),
+                away.interval_minutes,
+                serde_json::to_string(&away.allow)?,
+                serde_json::to_string(&away.deny)?,
+            ),
+        )
+        .await?;
+        Ok(())
     }
 
-    pub async fn search_messages(&self, p: SearchMessagesParams) -> Result<Vec<Message>> {
-        if self.has_fts {
-            self.search_messages_fts(p).await
-        } else {
-            self.search_messages_like(p).await
-        }
+    /// Away mode settings (None when it's off).
+    pub async fn away_mode(&self) -> Result<Option<AwayMode>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT message, since, until, interval_minutes, allow, deny FROM away_mode",
+                (),
+            )
+            .await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+        let parse = |s: String| {
+            DateTime::parse_from_rfc3339(&s)
+                .ok()
+                .map(|t| t.with_timezone(&Utc))
+        };
+        let allow: String = row.get(4)?;
+        let deny: String = row.get(5)?;
+        Ok(Some(AwayMode {
+            message: row.get(0)?,
+            since: parse(row.get(1)?).unwrap_or_default(),
+            until: row.get::<Option<String>>(2).ok().flatten().and_then(parse),
+            interval_minutes: row.get(3)?,
+            allow: serde_json::from_str(&allow).unwrap_or_default(),
+            deny: serde_json::from_str(&deny).unwrap_or_default(),
+        }))
     }
 
-    async fn search_messages_fts(&self, p: SearchMessagesParams) -> Result<Vec<Message>> {
-        use turso::Value;
+    /// Turn away mode off. Returns how many people were answered.
+    pub async fn clear_away_mode(&self) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        conn.execute("DELETE FROM away_mode", ()).await?;
+        let replied = conn.execute("DELETE FROM away_replies", ()).await?;
+        Ok(replied)
+    }
 
-        let mut conditions = vec!["messages_fts MATCH ?1".to_string()];
-        let mut params: Vec<Value> = vec![Value::Text(p.query.clone())];
-        let mut param_idx = 2;
+    /// When away mode last answered this user.
+    pub async fn away_last_reply(&self, user_id: i64) -> Result<Option<DateTime<Utc>>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT replied_at FROM away_replies WHERE user_id = ?1",
+                [user_id],
+            )
+            .await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+        let ts: String = row.get(0)?;
+        Ok(DateTime::parse_from_rfc3339(&ts)
+            .ok()
+            .map(|t| t.with_timezone(&Utc)))
+    }
 
-        if let Some(chat_id) = p.chat_id {
-            conditions.push(format!("m.chat_id = ?{}", param_idx));
-            params.push(Value::Integer(chat_id));
-            param_idx += 1;
-        }
-        if let Some(topic_id) = p.topic_id {
-            conditions.push(format!("m.topic_id = ?{}", param_idx));
-            params.push(Value::Integer(topic_id as i64));
-            param_idx += 1;
-        }
-        if let Some(from_id) = p.from_id {
-            conditions.push(format!("m.sender_id = ?{}", param_idx));
-            params.push(Value::Integer(from_id));
-            param_idx += 1;
-        }
-        if let Some(ref media_type) = p.media_type {
-            conditions.push(format!("m.media_type = ?{}", param_idx));
-            params.push(Value::Text(media_type.clone()));
-            param_idx += 1;
-        }
+    pub async fn record_away_reply(&self, user_id: i64, ts: DateTime<Utc>) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT OR REPLACE INTO away_replies (user_id, replied_at) VALUES (?1, ?2)",
+            (user_id, ts.to_rfc3339()),
+        )
+        .await?;
+        Ok(())
+    }
 
-        if !p.ignore_chats.is_empty() {
-            let ids: Vec<String> = p.ignore_chats.iter().map(|id| id.to_string()).collect();
-            conditions.push(format!("m.chat_id NOT IN ({})", ids.join(",")));
-        }
-        if p.ignore_channels {
-            conditions.push("COALESCE(c.kind, '') != 'channel'".to_string());
+    /// How many people away mode has answered since it was turned on.
+    pub async fn count_away_replies(&self) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query("SELECT COUNT(*) FROM away_replies", ()).await?;
+        if let Some(row) = rows.next().await? {
+            Ok(row.get::<i64>(0)? as u64)
+        } else {
+            Ok(0)
         }
+    }
 
-        let sql = format!(
-            "SELECT m.id, m.chat_id, m.sender_id, m.ts, m.edit_ts, m.from_me, m.text, m.media_type, m.media_path, m.reply_to_id, m.topic_id,
-                    snippet(messages_fts, 0, '»', '«', '…', 40) as snippet
-             FROM messages m
-             JOIN messages_fts ON messages_fts.rowid = m.rowid
-             LEFT JOIN chats c ON c.id = m.chat_id
-             WHERE {} ORDER BY m.ts DESC LIMIT ?{}",
-            conditions.join(" AND "),
-            param_idx
-        );
-        params.push(Value::Integer(p.limit));
+    // --- API tokens ---
 
+    /// Add an API token by the hash of its secret, returns its ID.
+    pub async fn add_api_token(
+        &self,
+        name: &str,
+        token_hash: &str,
+        scopes: &[String],
+    ) -> Result<i64> {
         let conn = self.get_conn().await?;
-        let mut rows = conn.query(&sql, turso::params_from_iter(params)).await?;
+        conn.execute(
+            "INSERT INTO api_tokens (name, token_hash, scopes, created_at) VALUES (?1, ?2, ?3, ?4)",
+            (
+                name,
+                token_hash,
+                serde_json::to_string(scopes)?,
+                Utc::now().to_rfc3339(),
+            ),
+        )
+        .await
+        .with_context(|| format!("Failed to add token '{}' (name taken?)", name))?;
+        Ok(conn.last_insert_rowid())
+    }
 
-        let mut msgs = Vec::new();
-        while let Some(row) = rows.next().await? {
-            let mut m = row_to_message(&row)?;
-            m.snippet = row.get::<String>(11).unwrap_or_default();
-            msgs.push(m);
-        }
-        Ok(msgs)
+    pub async fn list_api_tokens(&self) -> Result<Vec<ApiToken>> {
+        self.query_api_tokens("ORDER BY id", ()).await
     }
 
-    async fn search_messages_like(&self, p: SearchMessagesParams) -> Result<Vec<Message>> {
+    pub async fn api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        Ok(self
+            .query_api_tokens("WHERE token_hash = ?1", [token_hash])
+            .await?
+            .pop())
+    }
+
+    async fn query_api_tokens(
+        &self,
+        filter: &str,
+        params: impl turso::IntoParams,
+    ) -> Result<Vec<ApiToken>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT id, name, token_hash, scopes, created_at, last_used FROM api_tokens {}",
+                    filter
+                ),
+                params,
+            )
+            .await?;
+        let parse = |s: String| {
+            DateTime::parse_from_rfc3339(&s)
+                .ok()
+                .map(|t| t.with_timezone(&Utc))
+        };
+        let mut tokens = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let scopes: String = row.get(3)?;
+            tokens.push(ApiToken {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                token_hash: row.get(2)?,
+                scopes: serde_json::from_str(&scopes).unwrap_or_default(),
+                created_at: parse(row.get(4)?).unwrap_or_default(),
+                last_used: row.get::<Option<String>>(5).ok().flatten().and_then(parse),
+            });
+        }
+        Ok(tokens)
+    }
+
+    pub async fn touch_api_token(&self, id: i64, ts: DateTime<Utc>) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE api_tokens SET last_used = ?2 WHERE id = ?1",
+            (id, ts.to_rfc3339()),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Revoke a token. Returns false if there was none with that name.
+    pub async fn remove_api_token(&self, name: &str) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let affected = conn
+            .execute("DELETE FROM api_tokens WHERE name = ?1", [name])
+            .await?;
+        Ok(affected > 0)
+    }
+
+    // --- Audit log ---
+
+    pub async fn add_audit_entry(
+        &self,
+        ts: DateTime<Utc>,
+        command: &str,
+        action: &str,
+        chat_id: Option<i64>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO audit_log (ts, command, action, chat_id, ok, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                ts.to_rfc3339(),
+                command,
+                action,
+                chat_id,
+                error.is_none() as i64,
+                error,
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Audit log entries, newest first.
+    pub async fn audit_entries(
+        &self,
+        since: Option<DateTime<Utc>>,
+        chat_id: Option<i64>,
+        failed_only: bool,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT id, ts, command, action, chat_id, ok, error FROM audit_log
+                 WHERE (?1 IS NULL OR ts >= ?1)
+                   AND (?2 IS NULL OR chat_id = ?2)
+                   AND (?3 = 0 OR ok = 0)
+                 ORDER BY ts DESC, id DESC
+                 LIMIT ?4",
+                (
+                    since.map(|t| t.to_rfc3339()),
+                    chat_id,
+                    failed_only as i64,
+                    limit,
+                ),
+            )
+            .await?;
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let ts: String = row.get(1)?;
+            entries.push(AuditEntry {
+                id: row.get(0)?,
+                ts: DateTime::parse_from_rfc3339(&ts)
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_default(),
+                command: row.get(2)?,
+                action: row.get(3)?,
+                chat_id: row.get::<Option<i64>>(4).ok().flatten(),
+                ok: row.get::<i64>(5)? != 0,
+                error: row.get::<Option<String>>(6).ok().flatten(),
+            });
+        }
+        Ok(entries)
+    }
+
+    // --- Retention policies ---
+
+    /// Set (or replace) a chat's retention policy.
+    pub async fn set_retention_policy(
+        &self,
+        chat_id: i64,
+        keep_days: i64,
+        delete_media: bool,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO retention_policies (chat_id, keep_days, delete_media)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(chat_id) DO UPDATE SET
+                keep_days = excluded.keep_days,
+                delete_media = excluded.delete_media",
+            (chat_id, keep_days, delete_media as i64),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list_retention_policies(&self) -> Result<Vec<RetentionPolicy>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT chat_id, keep_days, delete_media, pruned, last_pruned
+                 FROM retention_policies ORDER BY chat_id",
+                (),
+            )
+            .await?;
+        let mut policies = Vec::new();
+        while let Some(row) = rows.next().await? {
+            policies.push(RetentionPolicy {
+                chat_id: row.get(0)?,
+                keep_days: row.get(1)?,
+                delete_media: row.get::<i64>(2)? != 0,
+                pruned: row.get(3)?,
+                last_pruned: row
+                    .get::<Option<String>>(4)
+                    .ok()
+                    .flatten()
+                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|t| t.with_timezone(&Utc)),
+            });
+        }
+        Ok(policies)
+    }
+
+    /// Remove a chat's retention policy. Returns false if it had none.
+    pub async fn remove_retention_policy(&self, chat_id: i64) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let affected = conn
+            .execute(
+                "DELETE FROM retention_policies WHERE chat_id = ?1",
+                [chat_id],
+            )
+            .await?;
+        Ok(affected > 0)
+    }
+
+    /// Count `deleted` messages pruned from a chat at `ts`.
+    pub async fn record_prune(&self, chat_id: i64, deleted: u64, ts: DateTime<Utc>) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE retention_policies SET pruned = pruned + ?2, last_pruned = ?3
+             WHERE chat_id = ?1",
+            (chat_id, deleted as i64, ts.to_rfc3339()),
+        )
+        .await?;
+        Ok(())
+    }
+
+    // --- Chat folders ---
+
+    /// Replace the recorded members of a folder.
+    pub async fn set_folder_chats(
+        &self,
+        folder_id: i32,
+        title: &str,
+        chat_ids: &[i64],
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute("DELETE FROM chat_folders WHERE folder_id = ?1", [folder_id])
+            .await?;
+        for chat_id in chat_ids {
+            conn.execute(
+                "INSERT OR IGNORE INTO chat_folders (folder_id, title, chat_id) VALUES (?1, ?2, ?3)",
+                (folder_id, title, *chat_id),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// ID of a recorded folder by title (case-insensitive) or numeric ID.
+    pub async fn find_folder_id(&self, name: &str) -> Result<Option<i32>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT folder_id FROM chat_folders
+                 WHERE title = ?1 COLLATE NOCASE OR CAST(folder_id AS TEXT) = ?1
+                 LIMIT 1",
+                [name],
+            )
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get::<i64>(0)? as i32)),
+            None => Ok(None),
+        }
+    }
+
+    // --- Contacts ---
+
+    pub async fn upsert_contact(
+        &self,
+        user_id: i64,
+        username: Option<&str>,
+        first_name: &str,
+        last_name: &str,
+        phone: &str,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO contacts (user_id, username, first_name, last_name, phone)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(user_id) DO UPDATE SET
+                username = COALESCE(excluded.username, username),
+                first_name = CASE WHEN excluded.first_name != '' THEN excluded.first_name ELSE first_name END,
+                last_name = CASE WHEN excluded.last_name != '' THEN excluded.last_name ELSE last_name END,
+                phone = CASE WHEN excluded.phone != '' THEN excluded.phone ELSE phone END",
+            (user_id, username, first_name, last_name, phone),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn search_contacts(&self, query: &str, limit: i64) -> Result<Vec<Contact>> {
+        let pattern = format!("%{}%", query);
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT user_id, username, first_name, last_name, phone FROM contacts
+                 WHERE first_name LIKE ?1 OR last_name LIKE ?1 OR username LIKE ?1 OR phone LIKE ?1
+                 ORDER BY first_name LIMIT ?2",
+                (pattern.as_str(), limit),
+            )
+            .await?;
+        let mut contacts = Vec::new();
+        while let Some(row) = rows.next().await? {
+            contacts.push(row_to_contact(&row)?);
+        }
+        Ok(contacts)
+    }
+
+    pub async fn get_contact(&self, user_id: i64) -> Result<Option<Contact>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT user_id, username, first_name, last_name, phone FROM contacts WHERE user_id = ?1",
+                [user_id],
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            Ok(Some(row_to_contact(&row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn list_contacts(&self, limit: Option<i64>) -> Result<Vec<Contact>> {
+        let query = match limit {
+            Some(l) => format!(
+                "SELECT user_id, username, first_name, last_name, phone FROM contacts ORDER BY first_name LIMIT {}",
+                l
+            ),
+            None => "SELECT user_id, username, first_name, last_name, phone FROM contacts ORDER BY first_name".to_string(),
+        };
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query(&query, ()).await?;
+        let mut contacts = Vec::new();
+        while let Some(row) = rows.next().await? {
+            contacts.push(row_to_contact(&row)?);
+        }
+        Ok(contacts)
+    }
+
+    // --- Messages ---
+
+    pub async fn upsert_message(&self, p: UpsertMessageParams) -> Result<()> {
+        let ts_str = p.ts.to_rfc3339();
+        let edit_ts_str = p.edit_ts.map(|t| t.to_rfc3339());
+        let from_me_int = p.from_me as i64;
+
+        self.execute_cached(
+            "INSERT INTO messages (id, chat_id, sender_id, ts, edit_ts, from_me, text, media_type, media_path, reply_to_id, topic_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(chat_id, id) DO UPDATE SET
+                sender_id = excluded.sender_id,
+                ts = excluded.ts,
+                edit_ts = COALESCE(excluded.edit_ts, edit_ts),
+                from_me = excluded.from_me,
+                text = CASE WHEN excluded.text != '' THEN excluded.text ELSE text END,
+                media_type = COALESCE(excluded.media_type, media_type),
+                media_path = COALESCE(excluded.media_path, media_path),
+                reply_to_id = COALESCE(excluded.reply_to_id, reply_to_id),
+                topic_id = COALESCE(excluded.topic_id, topic_id)",
+            (
+                p.id,
+                p.chat_id,
+                p.sender_id,
+                ts_str.as_str(),
+                edit_ts_str.as_deref(),
+                from_me_int,
+                p.text.as_str(),
+                p.media_type.as_deref(),
+                p.media_path.as_deref(),
+                p.reply_to_id,
+                p.topic_id,
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list_messages(&self, p: ListMessagesParams) -> Result<Vec<Message>> {
+        let (sql, params) = list_messages_query(&p, "DESC");
+        let mut msgs = self
+            .message_rows(&sql, params, false)
+            .await?
+            .into_vec()
+            .await?;
+        msgs.reverse(); // chronological order
+        Ok(msgs)
+    }
+
+    /// Like `list_messages`, but oldest first and read one row at a time, for
+    /// listings and exports of whole archives. A `limit` of 0 means all.
+    pub async fn stream_messages(&self, p: ListMessagesParams) -> Result<MessageRows> {
+        let (sql, params) = list_messages_query(&p, "ASC");
+        self.message_rows(&sql, params, false).await
+    }
+
+    /// Run a query selecting `MESSAGE_COLUMNS` (and the FTS snippet after
+    /// them with `snippet`), without reading the rows yet.
+    async fn message_rows(
+        &self,
+        sql: &str,
+        params: Vec<turso::Value>,
+        snippet: bool,
+    ) -> Result<MessageRows> {
+        let conn = self.get_conn().await?;
+        let rows = conn.query(sql, turso::params_from_iter(params)).await?;
+        Ok(MessageRows {
+            rows,
+            snippet,
+            _conn: conn,
+        })
+    }
+
+    pub async fn search_messages(&self, p: SearchMessagesParams) -> Result<Vec<Message>> {
+        self.stream_search(p).await?.into_vec().await
+    }
+
+    /// Like `search_messages` (newest first), but read one row at a time. A
+    /// `limit` of 0 means every match.
+    pub async fn stream_search(&self, p: SearchMessagesParams) -> Result<MessageRows> {
+        if self.has_fts && !self.fts_misses(&p.query) {
+            self.search_rows_fts(p).await
+        } else {
+            self.search_rows_like(p).await
+        }
+    }
+
+    /// Whether the FTS index can't answer `query`: trigrams never match a
+    /// term shorter than 3 characters (most Chinese and Japanese words), so
+    /// those searches scan with LIKE instead.
+    fn fts_misses(&self, query: &str) -> bool {
+        self.fts_tokenizer == FtsTokenizer::Trigram
+            && query
+                .split_whitespace()
+                .filter(|t| !matches!(*t, "AND" | "OR" | "NOT"))
+                .any(|t| t.trim_matches(|c| c == '"' || c == '*').chars().count() < 3)
+    }
+
+    async fn search_rows_fts(&self, p: SearchMessagesParams) -> Result<MessageRows> {
         use turso::Value;
 
-        let pattern = format!("%{}%", p.query);
-        let mut conditions = vec!["m.text LIKE ?1".to_string()];
-        let mut params: Vec<Value> = vec![Value::Text(pattern)];
+        let mut conditions = vec!["messages_fts MATCH ?1".to_string()];
+        let mut params: Vec<Value> = vec![Value::Text(fts_match_expr(&p.query, p.match_mode)?)];
         let mut param_idx = 2;
 
         if let Some(chat_id) = p.chat_id {
@@ -896,6 +3592,11 @@ impl Store {
             params.push(Value::Integer(topic_id as i64));
             param_idx += 1;
         }
+        if let Some(ref name) = p.topic_name {
+            conditions.push(format!("t.name LIKE ?{}", param_idx));
+            params.push(Value::Text(format!("%{}%", name)));
+            param_idx += 1;
+        }
         if let Some(from_id) = p.from_id {
             conditions.push(format!("m.sender_id = ?{}", param_idx));
             params.push(Value::Integer(from_id));
@@ -906,6 +3607,28 @@ impl Store {
             params.push(Value::Text(media_type.clone()));
             param_idx += 1;
         }
+        if let Some(ref origin) = p.forwarded_from {
+            let (condition, value) = forwarded_from_condition(origin, param_idx);
+            conditions.push(condition);
+            params.push(value);
+            param_idx += 1;
+        }
+        if let Some(via_bot_id) = p.via_bot_id {
+            conditions.push(format!("m.via_bot_id = ?{}", param_idx));
+            params.push(Value::Integer(via_bot_id));
+            param_idx += 1;
+        }
+        if p.has_link {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM message_webpages w WHERE w.chat_id = m.chat_id AND w.msg_id = m.id)"
+                    .to_string(),
+            );
+        }
+        if let Some(ref domain) = p.domain {
+            conditions.push(domain_condition(param_idx));
+            params.push(Value::Text(normalize_domain(domain)));
+            param_idx += 1;
+        }
 
         if !p.ignore_chats.is_empty() {
             let ids: Vec<String> = p.ignore_chats.iter().map(|id| id.to_string()).collect();
@@ -914,25 +3637,136 @@ impl Store {
         if p.ignore_channels {
             conditions.push("COALESCE(c.kind, '') != 'channel'".to_string());
         }
+        if let Some(ref cursor) = p.cursor {
+            conditions.push(MessageCursor::condition(param_idx));
+            params.extend(cursor.params());
+            param_idx += 3;
+        }
 
+        // bm25() is negative, better matches lower; dividing by 1 + age /
+        // half-life pulls old messages toward 0, i.e. down the ranking
+        let rank = match p.sort {
+            SearchSort::Recent => String::new(),
+            SearchSort::Relevance if p.half_life_days > 0.0 => {
+                let rank = format!(
+                    "bm25(messages_fts) / (1.0 + MAX(julianday('now') - julianday(m.ts), 0) / ?{}), ",
+                    param_idx
+                );
+                params.push(Value::Real(p.half_life_days));
+                param_idx += 1;
+                rank
+            }
+            SearchSort::Relevance => "bm25(messages_fts), ".to_string(),
+        };
         let sql = format!(
-            "SELECT m.id, m.chat_id, m.sender_id, m.ts, m.edit_ts, m.from_me, m.text, m.media_type, m.media_path, m.reply_to_id, m.topic_id
+            "SELECT {},
+                    snippet(messages_fts, 0, '»', '«', '…', 40) as snippet
              FROM messages m
+             JOIN messages_fts ON messages_fts.rowid = m.rowid
              LEFT JOIN chats c ON c.id = m.chat_id
-             WHERE {} ORDER BY m.ts DESC LIMIT ?{}",
+             LEFT JOIN topics t ON t.chat_id = m.chat_id AND t.topic_id = m.topic_id
+             WHERE {} ORDER BY {}m.ts DESC, m.chat_id DESC, m.id DESC{}",
+            MESSAGE_COLUMNS,
             conditions.join(" AND "),
-            param_idx
+            rank,
+            limit_clause(p.limit, param_idx),
         );
-        params.push(Value::Integer(p.limit));
+        if p.limit > 0 {
+            params.push(Value::Integer(p.limit));
+        }
+        self.message_rows(&sql, params, true).await
+    }
 
-        let conn = self.get_conn().await?;
-        let mut rows = conn.query(&sql, turso::params_from_iter(params)).await?;
+    async fn search_rows_like(&self, p: SearchMessagesParams) -> Result<MessageRows> {
+        use turso::Value;
 
-        let mut msgs = Vec::new();
-        while let Some(row) = rows.next().await? {
-            msgs.push(row_to_message(&row)?);
+        if p.sort == SearchSort::Relevance {
+            anyhow::bail!(if self.has_fts {
+                "--sort relevance can't rank terms shorter than 3 characters with the trigram index"
+            } else {
+                "--sort relevance needs the FTS5 index, which this build doesn't have"
+            });
         }
-        Ok(msgs)
+        let (condition, patterns) = like_match_expr(&p.query, p.match_mode, 1)?;
+        let mut param_idx = patterns.len() + 1;
+        let mut conditions = vec![condition];
+        let mut params: Vec<Value> = patterns.into_iter().map(Value::Text).collect();
+
+        if let Some(chat_id) = p.chat_id {
+            conditions.push(format!("m.chat_id = ?{}", param_idx));
+            params.push(Value::Integer(chat_id));
+            param_idx += 1;
+        }
+        if let Some(topic_id) = p.topic_id {
+            conditions.push(format!("m.topic_id = ?{}", param_idx));
+            params.push(Value::Integer(topic_id as i64));
+            param_idx += 1;
+        }
+        if let Some(ref name) = p.topic_name {
+            conditions.push(format!("t.name LIKE ?{}", param_idx));
+            params.push(Value::Text(format!("%{}%", name)));
+            param_idx += 1;
+        }
+        if let Some(from_id) = p.from_id {
+            conditions.push(format!("m.sender_id = ?{}", param_idx));
+            params.push(Value::Integer(from_id));
+            param_idx += 1;
+        }
+        if let Some(ref media_type) = p.media_type {
+            conditions.push(format!("m.media_type = ?{}", param_idx));
+            params.push(Value::Text(media_type.clone()));
+            param_idx += 1;
+        }
+        if let Some(ref origin) = p.forwarded_from {
+            let (condition, value) = forwarded_from_condition(origin, param_idx);
+            conditions.push(condition);
+            params.push(value);
+            param_idx += 1;
+        }
+        if let Some(via_bot_id) = p.via_bot_id {
+            conditions.push(format!("m.via_bot_id = ?{}", param_idx));
+            params.push(Value::Integer(via_bot_id));
+            param_idx += 1;
+        }
+        if p.has_link {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM message_webpages w WHERE w.chat_id = m.chat_id AND w.msg_id = m.id)"
+                    .to_string(),
+            );
+        }
+        if let Some(ref domain) = p.domain {
+            conditions.push(domain_condition(param_idx));
+            params.push(Value::Text(normalize_domain(domain)));
+            param_idx += 1;
+        }
+
+        if !p.ignore_chats.is_empty() {
+            let ids: Vec<String> = p.ignore_chats.iter().map(|id| id.to_string()).collect();
+            conditions.push(format!("m.chat_id NOT IN ({})", ids.join(",")));
+        }
+        if p.ignore_channels {
+            conditions.push("COALESCE(c.kind, '') != 'channel'".to_string());
+        }
+        if let Some(ref cursor) = p.cursor {
+            conditions.push(MessageCursor::condition(param_idx));
+            params.extend(cursor.params());
+            param_idx += 3;
+        }
+
+        let sql = format!(
+            "SELECT {}
+             FROM messages m
+             LEFT JOIN chats c ON c.id = m.chat_id
+             LEFT JOIN topics t ON t.chat_id = m.chat_id AND t.topic_id = m.topic_id
+             WHERE {} ORDER BY m.ts DESC, m.chat_id DESC, m.id DESC{}",
+            MESSAGE_COLUMNS,
+            conditions.join(" AND "),
+            limit_clause(p.limit, param_idx),
+        );
+        if p.limit > 0 {
+            params.push(Value::Integer(p.limit));
+        }
+        self.message_rows(&sql, params, false).await
     }
 
     pub async fn message_context(
@@ -962,8 +3796,10 @@ impl Store {
             let conn = self.get_conn().await?;
             let mut before_rows = conn
                 .query(
-                    "SELECT id, chat_id, sender_id, ts, edit_ts, from_me, text, media_type, media_path, reply_to_id, topic_id
-                     FROM messages WHERE chat_id = ?1 AND ts < ?2 ORDER BY ts DESC LIMIT ?3",
+                    &format!(
+                        "SELECT {} FROM messages m WHERE chat_id = ?1 AND ts < ?2 ORDER BY ts DESC LIMIT ?3",
+                        MESSAGE_COLUMNS
+                    ),
                     (chat_id, ts.as_str(), before),
                 )
                 .await?;
@@ -980,8 +3816,10 @@ impl Store {
             let conn = self.get_conn().await?;
             let mut target_rows = conn
                 .query(
-                    "SELECT id, chat_id, sender_id, ts, edit_ts, from_me, text, media_type, media_path, reply_to_id, topic_id
-                     FROM messages WHERE chat_id = ?1 AND id = ?2",
+                    &format!(
+                        "SELECT {} FROM messages m WHERE chat_id = ?1 AND id = ?2",
+                        MESSAGE_COLUMNS
+                    ),
                     (chat_id, msg_id),
                 )
                 .await?;
@@ -996,8 +3834,10 @@ impl Store {
             let conn = self.get_conn().await?;
             let mut after_rows = conn
                 .query(
-                    "SELECT id, chat_id, sender_id, ts, edit_ts, from_me, text, media_type, media_path, reply_to_id, topic_id
-                     FROM messages WHERE chat_id = ?1 AND ts > ?2 ORDER BY ts ASC LIMIT ?3",
+                    &format!(
+                        "SELECT {} FROM messages m WHERE chat_id = ?1 AND ts > ?2 ORDER BY ts ASC LIMIT ?3",
+                        MESSAGE_COLUMNS
+                    ),
                     (chat_id, ts.as_str(), after),
                 )
                 .await?;
@@ -1014,37 +3854,1166 @@ impl Store {
         Ok(result)
     }
 
-    /// Update a message's text (for edits)
+    /// Update a message's text (for edits), keeping the old text in
+    /// `message_edits` if it changed.
     pub async fn update_message_text(
         &self,
         chat_id: i64,
         msg_id: i64,
         new_text: &str,
+        edit_ts: DateTime<Utc>,
+    ) -> Result<()> {
+        let edit_ts = edit_ts.to_rfc3339();
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO message_edits (chat_id, msg_id, text, replaced_at)
+             SELECT chat_id, id, text, ?1 FROM messages
+             WHERE chat_id = ?2 AND id = ?3 AND text != ?4",
+            (edit_ts.as_str(), chat_id, msg_id, new_text),
+        )
+        .await?;
+        conn.execute(
+            "UPDATE messages SET text = ?1, edit_ts = ?2 WHERE chat_id = ?3 AND id = ?4",
+            (new_text, edit_ts.as_str(), chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Earlier versions of a message's text, oldest first.
+    pub async fn message_edits(&self, chat_id: i64, msg_id: i64) -> Result<Vec<MessageEdit>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT text, replaced_at FROM message_edits
+                 WHERE chat_id = ?1 AND msg_id = ?2 ORDER BY replaced_at",
+                (chat_id, msg_id),
+            )
+            .await?;
+        let mut edits = Vec::new();
+        while let Some(row) = rows.next().await? {
+            edits.push(MessageEdit {
+                text: row.get(0)?,
+                replaced_at: parse_ts(&row.get::<String>(1)?),
+            });
+        }
+        Ok(edits)
+    }
+
+    /// Mark messages deleted on Telegram at `ts`. Channel and supergroup
+    /// deletions name their chat; other deletions only carry message IDs,
+    /// which are unique across private chats and basic groups (the chats
+    /// without an access hash of their own, besides users).
+    pub async fn mark_messages_deleted(
+        &self,
+        channel_id: Option<i64>,
+        msg_ids: &[i32],
+        ts: DateTime<Utc>,
+    ) -> Result<u64> {
+        if msg_ids.is_empty() {
+            return Ok(0);
+        }
+        let ids = msg_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let conn = self.get_conn().await?;
+        let chats = match channel_id {
+            Some(id) => format!("chat_id = {}", id),
+            None => {
+                // turso can't run a subquery in an UPDATE's WHERE clause
+                let mut rows = conn
+                    .query(
+                        "SELECT id FROM chats
+                         WHERE kind = 'user' OR (kind = 'group' AND access_hash IS NULL)",
+                        (),
+                    )
+                    .await?;
+                let mut chat_ids = Vec::new();
+                while let Some(row) = rows.next().await? {
+                    chat_ids.push(row.get::<i64>(0)?.to_string());
+                }
+                if chat_ids.is_empty() {
+                    return Ok(0);
+                }
+                format!("chat_id IN ({})", chat_ids.join(","))
+            }
+        };
+        let affected = conn
+            .execute(
+                &format!(
+                    "UPDATE messages SET deleted_at = ?1
+                     WHERE {} AND id IN ({}) AND deleted_at IS NULL",
+                    chats, ids
+                ),
+                [ts.to_rfc3339()],
+            )
+            .await?;
+        Ok(affected)
+    }
+
+    pub async fn get_message(&self, chat_id: i64, msg_id: i64) -> Result<Option<Message>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM messages m WHERE chat_id = ?1 AND id = ?2",
+                    MESSAGE_COLUMNS
+                ),
+                (chat_id, msg_id),
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            Ok(Some(row_to_message(&row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Time of the oldest stored message, optionally in one chat.
+    pub async fn oldest_message_ts(&self, chat_id: Option<i64>) -> Result<Option<DateTime<Utc>>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT MIN(ts) FROM messages WHERE (?1 IS NULL OR chat_id = ?1)",
+                [chat_id],
+            )
+            .await?;
+        let ts = match rows.next().await? {
+            Some(row) => row.get::<Option<String>>(0)?,
+            None => None,
+        };
+        Ok(ts
+            .and_then(|ts| DateTime::parse_from_rfc3339(&ts).ok())
+            .map(|ts| ts.with_timezone(&Utc)))
+    }
+
+    /// Oldest messages in a chat (newest with `last`), service messages
+    /// excluded, in chronological order.
+    pub async fn edge_messages(
+        &self,
+        chat_id: i64,
+        last: bool,
+        limit: i64,
+    ) -> Result<Vec<Message>> {
+        let conn = self.get_conn().await?;
+        let order = if last { "DESC" } else { "ASC" };
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM messages m
+                     WHERE chat_id = ?1 AND COALESCE(media_type, '') != ?2
+                     ORDER BY ts {order}, id {order} LIMIT ?3",
+                    MESSAGE_COLUMNS
+                ),
+                (chat_id, SERVICE_MEDIA_TYPE, limit),
+            )
+            .await?;
+        let mut msgs = Vec::new();
+        while let Some(row) = rows.next().await? {
+            msgs.push(row_to_message(&row)?);
+        }
+        if last {
+            msgs.reverse();
+        }
+        Ok(msgs)
+    }
+
+    /// Oldest (newest with `last`) non-service message where `column`
+    /// ("chat_id" or "sender_id") is `id`.
+    async fn edge_message_by(&self, column: &str, id: i64, last: bool) -> Result<Option<Message>> {
+        let conn = self.get_conn().await?;
+        let order = if last { "DESC" } else { "ASC" };
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM messages m
+                     WHERE {column} = ?1 AND COALESCE(media_type, '') != ?2
+                     ORDER BY ts {order} LIMIT 1",
+                    MESSAGE_COLUMNS
+                ),
+                (id, SERVICE_MEDIA_TYPE),
+            )
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row_to_message(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn user_timeline(&self, user_id: i64) -> Result<UserTimeline> {
+        // Separate lookups so each one uses its (chat_id, ts) or (sender_id, ts) index
+        let mut first = None;
+        let mut last = None;
+        for column in ["chat_id", "sender_id"] {
+            if let Some(m) = self.edge_message_by(column, user_id, false).await? {
+                if first.as_ref().is_none_or(|f: &Message| m.ts < f.ts) {
+                    first = Some(m);
+                }
+            }
+            if let Some(m) = self.edge_message_by(column, user_id, true).await? {
+                if last.as_ref().is_none_or(|l: &Message| m.ts > l.ts) {
+                    last = Some(m);
+                }
+            }
+        }
+
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) FROM messages WHERE chat_id = ?1 AND COALESCE(media_type, '') != ?2",
+                (user_id, SERVICE_MEDIA_TYPE),
+            )
+            .await?;
+        let private_messages: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*), COUNT(DISTINCT chat_id) FROM messages
+                 WHERE sender_id = ?1 AND chat_id != ?1 AND COALESCE(media_type, '') != ?2",
+                (user_id, SERVICE_MEDIA_TYPE),
+            )
+            .await?;
+        let (group_messages, group_chats) = match rows.next().await? {
+            Some(row) => (row.get(0)?, row.get(1)?),
+            None => (0, 0),
+        };
+
+        Ok(UserTimeline {
+            user_id,
+            first,
+            last,
+            private_messages,
+            group_messages,
+            group_chats,
+        })
+    }
+
+    // --- Media files ---
+
+    /// Record where a message's media was saved and its SHA-256. `media_type`
+    /// replaces the stored type when given (e.g. the "media" placeholder).
+    pub async fn set_media_file(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        media_type: Option<&str>,
+        path: &str,
+        sha256: &str,
     ) -> Result<()> {
-        let edit_ts = Utc::now().to_rfc3339();
         let conn = self.get_conn().await?;
-        conn.execute(
-            "UPDATE messages SET text = ?1, edit_ts = ?2 WHERE chat_id = ?3 AND id = ?4",
-            (new_text, edit_ts.as_str(), chat_id, msg_id),
-        )
-        .await?;
-        Ok(())
+        conn.execute(
+            "UPDATE messages SET media_type = COALESCE(?1, media_type), media_path = ?2, media_sha256 = ?3
+             WHERE chat_id = ?4 AND id = ?5",
+            (media_type, path, sha256, chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Forget a message's media file (e.g. after it was deleted from disk).
+    pub async fn clear_media_file(&self, chat_id: i64, msg_id: i64) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "UPDATE messages SET media_path = NULL, media_sha256 = NULL WHERE chat_id = ?1 AND id = ?2",
+            (chat_id, msg_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Point media paths recorded under `old_dir` at `new_dir`, after the
+    /// store was moved. Returns how many messages were updated.
+    pub async fn move_media_paths(&self, old_dir: &str, new_dir: &str) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        // substr instead of LIKE: paths may contain '_' and '%'
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) FROM messages WHERE substr(media_path, 1, ?1) = ?2",
+                (old_dir.chars().count() as i64, old_dir),
+            )
+            .await?;
+        let count: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+        drop(rows);
+        conn.execute(
+            "UPDATE messages SET media_path = ?3 || substr(media_path, ?1 + 1)
+             WHERE substr(media_path, 1, ?1) = ?2",
+            (old_dir.chars().count() as i64, old_dir, new_dir),
+        )
+        .await?;
+        Ok(count as u64)
+    }
+
+    /// Send times of messages (service messages excluded), optionally
+    /// limited to one chat and to messages after `since`.
+    pub async fn message_times(
+        &self,
+        chat_id: Option<i64>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<DateTime<Utc>>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT ts FROM messages
+                 WHERE (?1 IS NULL OR chat_id = ?1) AND (?2 IS NULL OR ts >= ?2)
+                   AND COALESCE(media_type, '') != ?3",
+                (chat_id, since.map(|t| t.to_rfc3339()), SERVICE_MEDIA_TYPE),
+            )
+            .await?;
+        let mut times = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let ts: String = row.get(0)?;
+            if let Ok(ts) = DateTime::parse_from_rfc3339(&ts) {
+                times.push(ts.with_timezone(&Utc));
+            }
+        }
+        Ok(times)
+    }
+
+    /// Non-empty message texts (service messages excluded), optionally
+    /// limited to one chat and to messages after `since`.
+    pub async fn message_texts(
+        &self,
+        chat_id: Option<i64>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<MessageText>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT sender_id, from_me, topic_id, text FROM messages
+                 WHERE (?1 IS NULL OR chat_id = ?1) AND (?2 IS NULL OR ts >= ?2)
+                   AND text != '' AND COALESCE(media_type, '') != ?3",
+                (chat_id, since.map(|t| t.to_rfc3339()), SERVICE_MEDIA_TYPE),
+            )
+            .await?;
+        let mut texts = Vec::new();
+        while let Some(row) = rows.next().await? {
+            texts.push(MessageText {
+                sender_id: row.get(0)?,
+                from_me: row.get::<i64>(1)? != 0,
+                topic_id: row.get::<Option<i32>>(2)?,
+                text: row.get(3)?,
+            });
+        }
+        Ok(texts)
+    }
+
+    /// Message counts per sender (service messages excluded), most active
+    /// first, optionally limited to one chat and to messages after `since`.
+    pub async fn sender_stats(
+        &self,
+        chat_id: Option<i64>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<SenderStats>> {
+        let conn = self.get_conn().await?;
+        let since = since.map(|t| t.to_rfc3339());
+
+        let mut replied_to: HashMap<i64, i64> = HashMap::new();
+        let mut rows = conn
+            .query(
+                "SELECT p.sender_id, COUNT(*) FROM messages r
+                 JOIN messages p ON p.chat_id = r.chat_id AND p.id = r.reply_to_id
+                 WHERE (?1 IS NULL OR r.chat_id = ?1) AND (?2 IS NULL OR r.ts >= ?2)
+                   AND r.sender_id != p.sender_id
+                 GROUP BY p.sender_id",
+                (chat_id, since.clone()),
+            )
+            .await?;
+        while let Some(row) = rows.next().await? {
+            replied_to.insert(row.get(0)?, row.get(1)?);
+        }
+
+        let mut rows = conn
+            .query(
+                "SELECT sender_id, MAX(from_me), COUNT(*),
+                        AVG(CASE WHEN text != '' THEN LENGTH(text) END),
+                        SUM(CASE WHEN media_type IS NOT NULL THEN 1 ELSE 0 END),
+                        SUM(CASE WHEN reply_to_id IS NOT NULL THEN 1 ELSE 0 END),
+                        MIN(ts), MAX(ts)
+                 FROM messages
+                 WHERE (?1 IS NULL OR chat_id = ?1) AND (?2 IS NULL OR ts >= ?2)
+                   AND COALESCE(media_type, '') != ?3
+                 GROUP BY sender_id
+                 ORDER BY COUNT(*) DESC, sender_id",
+                (chat_id, since, SERVICE_MEDIA_TYPE),
+            )
+            .await?;
+        let mut stats = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let sender_id: i64 = row.get(0)?;
+            let first_ts: String = row.get(6)?;
+            let last_ts: String = row.get(7)?;
+            stats.push(SenderStats {
+                sender_id,
+                from_me: row.get::<i64>(1)? != 0,
+                messages: row.get(2)?,
+                avg_length: row.get::<Option<f64>>(3)?,
+                media: row.get(4)?,
+                replies: row.get(5)?,
+                replied_to: replied_to.get(&sender_id).copied().unwrap_or(0),
+                first_ts: DateTime::parse_from_rfc3339(&first_ts)?.with_timezone(&Utc),
+                last_ts: DateTime::parse_from_rfc3339(&last_ts)?.with_timezone(&Utc),
+            });
+        }
+        Ok(stats)
+    }
+
+    /// Messages with a `media_path`, optionally limited to one chat.
+    pub async fn list_media_files(&self, chat_id: Option<i64>) -> Result<Vec<MediaFile>> {
+        let conn = self.get_conn().await?;
+        let sql = "SELECT chat_id, id, media_type, media_path, media_sha256 FROM messages
+                   WHERE media_path IS NOT NULL AND (?1 IS NULL OR chat_id = ?1)
+                   ORDER BY chat_id, id";
+        let mut rows = conn.query(sql, [chat_id]).await?;
+        let mut files = Vec::new();
+        while let Some(row) = rows.next().await? {
+            files.push(MediaFile {
+                chat_id: row.get(0)?,
+                msg_id: row.get(1)?,
+                media_type: row.get::<Option<String>>(2)?,
+                path: row.get(3)?,
+                sha256: row.get::<Option<String>>(4)?,
+            });
+        }
+        Ok(files)
+    }
+
+    /// Downloaded media of a chat's messages sent before `before`.
+    pub async fn media_files_before(
+        &self,
+        chat_id: i64,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<MediaFile>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT chat_id, id, media_type, media_path, media_sha256 FROM messages
+                 WHERE media_path IS NOT NULL AND chat_id = ?1 AND ts < ?2
+                 ORDER BY id",
+                (chat_id, before.to_rfc3339()),
+            )
+            .await?;
+        let mut files = Vec::new();
+        while let Some(row) = rows.next().await? {
+            files.push(MediaFile {
+                chat_id: row.get(0)?,
+                msg_id: row.get(1)?,
+                media_type: row.get::<Option<String>>(2)?,
+                path: row.get(3)?,
+                sha256: row.get::<Option<String>>(4)?,
+            });
+        }
+        Ok(files)
+    }
+
+    /// Messages sharing their text (trimmed, case-insensitive, at least
+    /// `min_text_len` characters) or media checksum with another message,
+    /// ordered by kind, key and time.
+    pub async fn duplicate_messages(&self, min_text_len: i64) -> Result<Vec<DuplicateMessage>> {
+        let conn = self.get_conn().await?;
+        let queries = [
+            (
+                "text",
+                "SELECT lower(trim(text)), chat_id, id, ts FROM messages
+                 WHERE lower(trim(text)) IN (
+                     SELECT lower(trim(text)) FROM messages
+                     WHERE length(trim(text)) >= ?1
+                     GROUP BY lower(trim(text)) HAVING COUNT(*) > 1
+                 )
+                 ORDER BY lower(trim(text)), ts",
+            ),
+            (
+                "media",
+                "SELECT media_sha256, chat_id, id, ts FROM messages
+                 WHERE media_sha256 IN (
+                     SELECT media_sha256 FROM messages
+                     WHERE media_sha256 IS NOT NULL
+                     GROUP BY media_sha256 HAVING COUNT(*) > 1
+                 )
+                 ORDER BY media_sha256, ts",
+            ),
+        ];
+        let mut dups = Vec::new();
+        for (kind, sql) in queries {
+            let params = match kind {
+                "text" => vec![turso::Value::Integer(min_text_len)],
+                _ => Vec::new(),
+            };
+            let mut rows = conn.query(sql, turso::params_from_iter(params)).await?;
+            while let Some(row) = rows.next().await? {
+                let ts: String = row.get(3)?;
+                dups.push(DuplicateMessage {
+                    kind,
+                    key: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    msg_id: row.get(2)?,
+                    ts: DateTime::parse_from_rfc3339(&ts)
+                        .map(|t| t.with_timezone(&Utc))
+                        .unwrap_or_default(),
+                });
+            }
+        }
+        Ok(dups)
+    }
+
+    /// Delete one message from the local database (not from Telegram).
+    pub async fn delete_message(&self, chat_id: i64, msg_id: i64) -> Result<bool> {
+        let conn = self.get_conn().await?;
+        let affected = conn
+            .execute(
+                "DELETE FROM messages WHERE chat_id = ?1 AND id = ?2",
+                (chat_id, msg_id),
+            )
+            .await?;
+        Ok(affected > 0)
+    }
+
+    /// Messages with downloadable media that was never saved (synced without
+    /// `--download-media`), newest first.
+    ///
+    /// A `media_type` filter also matches the "media" placeholder such syncs
+    /// store, since the real type is only known once the message is fetched.
+    /// Messages with a downloadable file (photos, videos, documents, ...),
+    /// newest first.
+    pub async fn list_media_messages(&self, p: MediaMessagesParams) -> Result<Vec<Message>> {
+        use turso::Value;
+
+        let mut conditions = vec![
+            "media_type IS NOT NULL".to_string(),
+            format!(
+                "media_type NOT IN ({})",
+                UNDOWNLOADABLE_MEDIA
+                    .iter()
+                    .map(|t| format!("'{}'", t))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        ];
+        let mut params: Vec<Value> = Vec::new();
+
+        if p.missing_local {
+            conditions.push("media_path IS NULL".to_string());
+        }
+        if let Some(chat_id) = p.chat_id {
+            params.push(Value::Integer(chat_id));
+            conditions.push(format!("chat_id = ?{}", params.len()));
+        }
+        if let Some(media_type) = p.media_type {
+            params.push(Value::Text(media_type));
+            conditions.push(format!("media_type = ?{}", params.len()));
+        }
+        if let Some(after) = p.after {
+            params.push(Value::Text(after.to_rfc3339()));
+            conditions.push(format!("ts > ?{}", params.len()));
+        }
+        if let Some(before) = p.before {
+            params.push(Value::Text(before.to_rfc3339()));
+            conditions.push(format!("ts < ?{}", params.len()));
+        }
+
+        let sql = format!(
+            "SELECT {} FROM messages m WHERE {} ORDER BY ts DESC, chat_id DESC, id DESC{}",
+            MESSAGE_COLUMNS,
+            conditions.join(" AND "),
+            limit_clause(p.limit, params.len() + 1)
+        );
+        if p.limit > 0 {
+            params.push(Value::Integer(p.limit));
+        }
+        self.message_rows(&sql, params, false)
+            .await?
+            .into_vec()
+            .await
+    }
+
+    pub async fn list_missing_media(&self, p: MissingMediaParams) -> Result<Vec<Message>> {
+        use turso::Value;
+
+        let mut conditions = vec![
+            "media_type IS NOT NULL".to_string(),
+            "media_path IS NULL".to_string(),
+            format!(
+                "media_type NOT IN ({})",
+                UNDOWNLOADABLE_MEDIA
+                    .iter()
+                    .map(|t| format!("'{}'", t))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        ];
+        let mut params: Vec<Value> = Vec::new();
+
+        if let Some(chat_id) = p.chat_id {
+            params.push(Value::Integer(chat_id));
+            conditions.push(format!("chat_id = ?{}", params.len()));
+        }
+        if let Some(media_type) = p.media_type {
+            params.push(Value::Text(media_type));
+            conditions.push(format!(
+                "(media_type = ?{} OR media_type = 'media')",
+                params.len()
+            ));
+        }
+        if let Some(after) = p.after {
+            params.push(Value::Text(after.to_rfc3339()));
+            conditions.push(format!("ts > ?{}", params.len()));
+        }
+        if let Some(before) = p.before {
+            params.push(Value::Text(before.to_rfc3339()));
+            conditions.push(format!("ts < ?{}", params.len()));
+        }
+        params.push(Value::Integer(p.limit));
+
+        let sql = format!(
+            "SELECT {} FROM messages m WHERE {} ORDER BY ts DESC, chat_id DESC, id DESC LIMIT ?{}",
+            MESSAGE_COLUMNS,
+            conditions.join(" AND "),
+            params.len()
+        );
+        let conn = self.get_conn().await?;
+        let mut rows = conn.query(&sql, turso::params_from_iter(params)).await?;
+        let mut msgs = Vec::new();
+        while let Some(row) = rows.next().await? {
+            msgs.push(row_to_message(&row)?);
+        }
+        Ok(msgs)
+    }
+
+    // --- Stickers ---
+
+    /// Cache a sticker from search results. Lower `rank` means a better match.
+    pub async fn upsert_sticker(&self, sticker: &Sticker, rank: i64) -> Result<()> {
+        let cached_at = Utc::now().to_rfc3339();
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO stickers (doc_id, file_id, emoji, set_name, animated, rank, cached_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(doc_id) DO UPDATE SET
+                file_id = excluded.file_id,
+                emoji = CASE WHEN excluded.emoji != '' THEN excluded.emoji ELSE emoji END,
+                set_name = COALESCE(excluded.set_name, set_name),
+                animated = excluded.animated,
+                rank = excluded.rank,
+                cached_at = excluded.cached_at",
+            (
+                sticker.doc_id,
+                sticker.file_id.as_str(),
+                sticker.emoji.as_str(),
+                sticker.set_name.as_deref(),
+                sticker.animated as i64,
+                rank,
+                cached_at.as_str(),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Cached stickers for an emoji, best match first.
+    pub async fn find_stickers_by_emoji(&self, emoji: &str, limit: i64) -> Result<Vec<Sticker>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT doc_id, file_id, emoji, set_name, animated FROM stickers
+                 WHERE emoji = ?1 ORDER BY rank ASC, cached_at DESC LIMIT ?2",
+                (emoji, limit),
+            )
+            .await?;
+        let mut stickers = Vec::new();
+        while let Some(row) = rows.next().await? {
+            stickers.push(Sticker {
+                doc_id: row.get(0)?,
+                file_id: row.get(1)?,
+                emoji: row.get(2)?,
+                set_name: row.get::<Option<String>>(3)?,
+                animated: row.get::<i64>(4).unwrap_or(0) != 0,
+            });
+        }
+        Ok(stickers)
+    }
+
+    // --- Custom emoji ---
+
+    pub async fn upsert_custom_emoji(&self, emoji: &CustomEmoji) -> Result<()> {
+        let cached_at = Utc::now().to_rfc3339();
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO custom_emoji (doc_id, alt, set_id, set_name, cached_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(doc_id) DO UPDATE SET
+                alt = excluded.alt,
+                set_id = COALESCE(excluded.set_id, set_id),
+                set_name = COALESCE(excluded.set_name, set_name),
+                cached_at = excluded.cached_at",
+            (
+                emoji.doc_id,
+                emoji.alt.as_str(),
+                emoji.set_id,
+                emoji.set_name.as_deref(),
+                cached_at.as_str(),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_custom_emoji(&self, doc_id: i64) -> Result<Option<CustomEmoji>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT doc_id, alt, set_id, set_name FROM custom_emoji WHERE doc_id = ?1",
+                [doc_id],
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            Ok(Some(CustomEmoji {
+                doc_id: row.get(0)?,
+                alt: row.get(1)?,
+                set_id: row.get::<Option<i64>>(2)?,
+                set_name: row.get::<Option<String>>(3)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Short name of an emoji pack already resolved for another custom emoji.
+    pub async fn get_custom_emoji_set_name(&self, set_id: i64) -> Result<Option<String>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT set_name FROM custom_emoji
+                 WHERE set_id = ?1 AND set_name IS NOT NULL LIMIT 1",
+                [set_id],
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            Ok(Some(row.get(0)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Replace the custom emoji recorded for a message.
+    pub async fn set_message_custom_emoji(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        spans: &[CustomEmojiSpan],
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "DELETE FROM message_custom_emoji WHERE chat_id = ?1 AND msg_id = ?2",
+            (chat_id, msg_id),
+        )
+        .await?;
+        for span in spans {
+            conn.execute(
+                "INSERT OR REPLACE INTO message_custom_emoji (chat_id, msg_id, offset, length, doc_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                (chat_id, msg_id, span.offset, span.length, span.doc_id),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Custom emoji in a message's text, with their resolved documents when cached.
+    pub async fn message_custom_emoji(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+    ) -> Result<Vec<(CustomEmojiSpan, Option<CustomEmoji>)>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT s.offset, s.length, s.doc_id, e.alt, e.set_id, e.set_name
+                 FROM message_custom_emoji s
+                 LEFT JOIN custom_emoji e ON e.doc_id = s.doc_id
+                 WHERE s.chat_id = ?1 AND s.msg_id = ?2
+                 ORDER BY s.offset",
+                (chat_id, msg_id),
+            )
+            .await?;
+        let mut spans = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let span = CustomEmojiSpan {
+                offset: row.get(0)?,
+                length: row.get(1)?,
+                doc_id: row.get(2)?,
+            };
+            let emoji = row.get::<Option<String>>(3)?.map(|alt| CustomEmoji {
+                doc_id: span.doc_id,
+                alt,
+                set_id: row.get::<Option<i64>>(4).ok().flatten(),
+                set_name: row.get::<Option<String>>(5).ok().flatten(),
+            });
+            spans.push((span, emoji));
+        }
+        Ok(spans)
+    }
+
+    /// Record the link preview of a message. A pending preview (URL only)
+    /// doesn't erase details stored earlier.
+    pub async fn upsert_message_webpage(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        webpage: &MessageWebpage,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO message_webpages (chat_id, msg_id, url, domain, site_name, title, description)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(chat_id, msg_id) DO UPDATE SET
+                url = excluded.url,
+                domain = excluded.domain,
+                site_name = COALESCE(excluded.site_name, site_name),
+                title = COALESCE(excluded.title, title),
+                description = COALESCE(excluded.description, description)",
+            (
+                chat_id,
+                msg_id,
+                webpage.url.as_str(),
+                webpage.domain.as_deref(),
+                webpage.site_name.as_deref(),
+                webpage.title.as_deref(),
+                webpage.description.as_deref(),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Link preview of a message, if any.
+    pub async fn message_webpage(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+    ) -> Result<Option<MessageWebpage>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT url, domain, site_name, title, description
+                 FROM message_webpages WHERE chat_id = ?1 AND msg_id = ?2",
+                (chat_id, msg_id),
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            Ok(Some(MessageWebpage {
+                url: row.get(0)?,
+                domain: row.get::<Option<String>>(1).ok().flatten(),
+                site_name: row.get::<Option<String>>(2).ok().flatten(),
+                title: row.get::<Option<String>>(3).ok().flatten(),
+                description: row.get::<Option<String>>(4).ok().flatten(),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Replace the keyboard stored for a message.
+    pub async fn set_message_buttons(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        buttons: &[MessageButton],
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "DELETE FROM message_buttons WHERE chat_id = ?1 AND msg_id = ?2",
+            (chat_id, msg_id),
+        )
+        .await?;
+        for button in buttons {
+            conn.execute(
+                "INSERT INTO message_buttons (chat_id, msg_id, row_idx, col_idx, kind, text, url)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                (
+                    chat_id,
+                    msg_id,
+                    button.row,
+                    button.col,
+                    button.kind.as_str(),
+                    button.text.as_str(),
+                    button.url.as_deref(),
+                ),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Keyboard of a message, row by row (empty if it has none).
+    pub async fn message_buttons(&self, chat_id: i64, msg_id: i64) -> Result<Vec<MessageButton>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT row_idx, col_idx, kind, text, url FROM message_buttons
+                 WHERE chat_id = ?1 AND msg_id = ?2 ORDER BY row_idx, col_idx",
+                (chat_id, msg_id),
+            )
+            .await?;
+        let mut buttons = Vec::new();
+        while let Some(row) = rows.next().await? {
+            buttons.push(MessageButton {
+                row: row.get(0)?,
+                col: row.get(1)?,
+                kind: row.get(2)?,
+                text: row.get(3)?,
+                url: row.get::<Option<String>>(4).ok().flatten(),
+            });
+        }
+        Ok(buttons)
+    }
+
+    /// Replace the reactions of a message (an empty slice clears them).
+    pub async fn set_message_reactions(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        reactions: &[MessageReaction],
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "DELETE FROM message_reactions WHERE chat_id = ?1 AND msg_id = ?2",
+            (chat_id, msg_id),
+        )
+        .await?;
+        for reaction in reactions {
+            conn.execute(
+                "INSERT INTO message_reactions (chat_id, msg_id, reaction, count, chosen)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                (
+                    chat_id,
+                    msg_id,
+                    reaction.reaction.as_str(),
+                    reaction.count,
+                    reaction.chosen as i64,
+                ),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Reactions of a message, most popular first.
+    pub async fn message_reactions(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+    ) -> Result<Vec<MessageReaction>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT reaction, count, chosen FROM message_reactions
+                 WHERE chat_id = ?1 AND msg_id = ?2 ORDER BY count DESC, reaction",
+                (chat_id, msg_id),
+            )
+            .await?;
+        let mut reactions = Vec::new();
+        while let Some(row) = rows.next().await? {
+            reactions.push(MessageReaction {
+                reaction: row.get(0)?,
+                count: row.get(1)?,
+                chosen: row.get::<i64>(2)? != 0,
+            });
+        }
+        Ok(reactions)
+    }
+
+    /// Record the location or venue shared in a message.
+    pub async fn upsert_message_geo(
+        &self,
+        chat_id: i64,
+        msg_id: i64,
+        geo: &MessageGeo,
+    ) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT OR REPLACE INTO message_geo
+                (chat_id, msg_id, kind, lat, lon, accuracy_radius, title, address, provider, venue_id, live_period)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            (
+                chat_id,
+                msg_id,
+                geo.kind.as_str(),
+                geo.lat,
+                geo.lon,
+                geo.accuracy_radius,
+                geo.title.as_deref(),
+                geo.address.as_deref(),
+                geo.provider.as_deref(),
+                geo.venue_id.as_deref(),
+                geo.live_period,
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Location or venue shared in a message, if any.
+    pub async fn message_geo(&self, chat_id: i64, msg_id: i64) -> Result<Option<MessageGeo>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT kind, lat, lon, accuracy_radius, title, address, provider, venue_id, live_period
+                 FROM message_geo WHERE chat_id = ?1 AND msg_id = ?2",
+                (chat_id, msg_id),
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            Ok(Some(MessageGeo {
+                kind: row.get(0)?,
+                lat: row.get(1)?,
+                lon: row.get(2)?,
+                accuracy_radius: row.get::<Option<i32>>(3).ok().flatten(),
+                title: row.get::<Option<String>>(4).ok().flatten(),
+                address: row.get::<Option<String>>(5).ok().flatten(),
+                provider: row.get::<Option<String>>(6).ok().flatten(),
+                venue_id: row.get::<Option<String>>(7).ok().flatten(),
+                live_period: row.get::<Option<i32>>(8).ok().flatten(),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Record a poll and its results. Results missing from `poll` (Telegram
+    /// didn't send any) keep their previous values.
+    pub async fn upsert_message_poll(&self, poll: &MessagePoll) -> Result<()> {
+        let conn = self.get_conn().await?;
+        conn.execute(
+            "INSERT INTO message_polls
+                (chat_id, msg_id, poll_id, question, closed, multiple_choice, quiz, public_voters, total_voters, close_date)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(chat_id, msg_id) DO UPDATE SET
+                poll_id = excluded.poll_id,
+                question = excluded.question,
+                closed = excluded.closed,
+                multiple_choice = excluded.multiple_choice,
+                quiz = excluded.quiz,
+                public_voters = excluded.public_voters,
+                total_voters = COALESCE(excluded.total_voters, total_voters),
+                close_date = excluded.close_date",
+            (
+                poll.chat_id,
+                poll.msg_id,
+                poll.poll_id,
+                poll.question.as_str(),
+                poll.closed as i64,
+                poll.multiple_choice as i64,
+                poll.quiz as i64,
+                poll.public_voters as i64,
+                poll.total_voters,
+                poll.close_date.map(|t| t.to_rfc3339()),
+            ),
+        )
+        .await?;
+        for option in &poll.options {
+            conn.execute(
+                "INSERT INTO poll_options (chat_id, msg_id, idx, text, voters, chosen, correct)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(chat_id, msg_id, idx) DO UPDATE SET
+                    text = excluded.text,
+                    voters = COALESCE(excluded.voters, voters),
+                    chosen = CASE WHEN excluded.voters IS NULL THEN chosen ELSE excluded.chosen END,
+                    correct = CASE WHEN excluded.voters IS NULL THEN correct ELSE excluded.correct END",
+                (
+                    poll.chat_id,
+                    poll.msg_id,
+                    option.index,
+                    option.text.as_str(),
+                    option.voters,
+                    option.chosen as i64,
+                    option.correct as i64,
+                ),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// The poll in a message, if it has been synced.
+    pub async fn get_message_poll(&self, chat_id: i64, msg_id: i64) -> Result<Option<MessagePoll>> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM message_polls WHERE chat_id = ?1 AND msg_id = ?2",
+                    POLL_COLUMNS
+                ),
+                (chat_id, msg_id),
+            )
+            .await?;
+        let poll = match rows.next().await? {
+            Some(row) => row_to_poll(&row)?,
+            None => return Ok(None),
+        };
+        drop(rows);
+        Ok(Some(self.with_poll_options(poll).await?))
+    }
+
+    /// Polls whose question contains `query`, newest first.
+    pub async fn search_polls(
+        &self,
+        query: &str,
+        chat_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<MessagePoll>> {
+        let conn = self.get_conn().await?;
+        let columns = POLL_COLUMNS
+            .split(',')
+            .map(|c| format!("p.{}", c.trim()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "SELECT {} FROM message_polls p
+             LEFT JOIN messages m ON m.chat_id = p.chat_id AND m.id = p.msg_id
+             WHERE p.question LIKE ?1 AND (?2 IS NULL OR p.chat_id = ?2)
+             ORDER BY m.ts DESC, p.msg_id DESC
+             LIMIT ?3",
+            columns
+        );
+        let pattern = format!("%{}%", query);
+        let mut rows = conn.query(&sql, (pattern, chat_id, limit)).await?;
+        let mut polls = Vec::new();
+        while let Some(row) = rows.next().await? {
+            polls.push(row_to_poll(&row)?);
+        }
+        drop(rows);
+        let mut out = Vec::with_capacity(polls.len());
+        for poll in polls {
+            out.push(self.with_poll_options(poll).await?);
+        }
+        Ok(out)
     }
 
-    pub async fn get_message(&self, chat_id: i64, msg_id: i64) -> Result<Option<Message>> {
+    async fn with_poll_options(&self, mut poll: MessagePoll) -> Result<MessagePoll> {
         let conn = self.get_conn().await?;
         let mut rows = conn
             .query(
-                "SELECT id, chat_id, sender_id, ts, edit_ts, from_me, text, media_type, media_path, reply_to_id, topic_id
-                 FROM messages WHERE chat_id = ?1 AND id = ?2",
-                (chat_id, msg_id),
+                "SELECT idx, text, voters, chosen, correct FROM poll_options
+                 WHERE chat_id = ?1 AND msg_id = ?2 ORDER BY idx",
+                (poll.chat_id, poll.msg_id),
             )
             .await?;
-        if let Some(row) = rows.next().await? {
-            Ok(Some(row_to_message(&row)?))
-        } else {
-            Ok(None)
+        while let Some(row) = rows.next().await? {
+            poll.options.push(PollOption {
+                index: row.get(0)?,
+                text: row.get(1)?,
+                voters: row.get::<Option<i32>>(2).ok().flatten(),
+                chosen: row.get::<i64>(3)? != 0,
+                correct: row.get::<i64>(4)? != 0,
+            });
         }
+        Ok(poll)
     }
 
     // --- Count methods (for clear command) ---
@@ -1059,6 +5028,21 @@ impl Store {
         }
     }
 
+    pub async fn count_chat_messages(&self, chat_id: i64) -> Result<u64> {
+        let conn = self.get_conn().await?;
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) FROM messages WHERE chat_id = ?1",
+                [chat_id],
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            Ok(row.get::<i64>(0)? as u64)
+        } else {
+            Ok(0)
+        }
+    }
+
     pub async fn count_chats(&self) -> Result<u64> {
         let conn = self.get_conn().await?;
         let mut rows = conn.query("SELECT COUNT(*) FROM chats", ()).await?;
@@ -1213,6 +5197,7 @@ fn row_to_chat(row: &Row) -> Result<Chat> {
         last_sync_message_id: row.get::<Option<i64>>(6).ok().flatten(),
         access_hash: row.get::<Option<i64>>(7).ok().flatten(),
         archived: row.get::<i64>(8).unwrap_or(0) != 0,
+        unread_count: row.get::<i64>(9).unwrap_or(0) as i32,
     })
 }
 
@@ -1234,9 +5219,159 @@ fn row_to_topic(row: &Row) -> Result<Topic> {
         icon_color: row.get(3)?,
         icon_emoji: row.get::<Option<String>>(4)?,
         unread_count: row.get::<Option<i32>>(5)?.unwrap_or(0),
+        top_message: row.get::<Option<i32>>(6).ok().flatten(),
     })
 }
 
+/// Condition for `SearchMessagesParams::domain`, bound to `?idx`: the domain
+/// itself or any of its subdomains.
+fn domain_condition(idx: usize) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM message_webpages w WHERE w.chat_id = m.chat_id AND w.msg_id = m.id
+         AND (w.domain = ?{0} OR w.domain LIKE '%.' || ?{0}))",
+        idx
+    )
+}
+
+/// Condition for `SearchMessagesParams::forwarded_from`, bound to `?idx`.
+fn forwarded_from_condition(origin: &str, idx: usize) -> (String, turso::Value) {
+    use turso::Value;
+
+    if let Ok(id) = origin.parse::<i64>() {
+        return (format!("m.fwd_from_id = ?{}", idx), Value::Integer(id));
+    }
+    match origin.strip_prefix('@') {
+        Some(username) => (
+            format!(
+                "m.fwd_from_id IN (SELECT id FROM chats WHERE LOWER(username) = LOWER(?{0})
+                 UNION SELECT user_id FROM contacts WHERE LOWER(username) = LOWER(?{0}))",
+                idx
+            ),
+            Value::Text(username.to_string()),
+        ),
+        None => (
+            format!("m.fwd_from_name LIKE ?{}", idx),
+            Value::Text(format!("%{}%", origin)),
+        ),
+    }
+}
+
+/// Columns read by `row_to_message`, in order (messages aliased as `m`).
+const MESSAGE_COLUMNS: &str = "m.id, m.chat_id, m.sender_id, m.ts, m.edit_ts, m.from_me, m.text, \
+     m.media_type, m.media_path, m.reply_to_id, m.topic_id, m.service_action, \
+     m.fwd_from_name, m.fwd_from_id, m.fwd_date, m.via_bot_id, m.post_author, m.views, m.forwards, \
+     m.grouped_id, m.shared_contact, m.paid_stars, m.deleted_at, m.media_name, m.media_size, \
+     m.media_duration, m.media_width, m.media_height";
+
+/// ` LIMIT ?n` for a positive `limit`; 0 (or less) selects every row.
+fn limit_clause(limit: i64, param_idx: usize) -> String {
+    if limit > 0 {
+        format!(" LIMIT ?{}", param_idx)
+    } else {
+        String::new()
+    }
+}
+
+/// SQL and parameters of `list_messages`, ordered by time `order` (`ASC`
+/// or `DESC`).
+fn list_messages_query(p: &ListMessagesParams, order: &str) -> (String, Vec<turso::Value>) {
+    // Build dynamic SQL using positional parameters
+    let mut conditions = vec!["1=1".to_string()];
+    let mut param_idx = 1;
+
+    let chat_filter = p.chat_id.map(|_| {
+        let cond = format!("m.chat_id = ?{}", param_idx);
+        param_idx += 1;
+        cond
+    });
+    if let Some(c) = &chat_filter {
+        conditions.push(c.clone());
+    }
+
+    let topic_filter = p.topic_id.map(|_| {
+        let cond = format!("m.topic_id = ?{}", param_idx);
+        param_idx += 1;
+        cond
+    });
+    if let Some(c) = &topic_filter {
+        conditions.push(c.clone());
+    }
+
+    let after_filter = p.after.as_ref().map(|_| {
+        let cond = format!("m.ts > ?{}", param_idx);
+        param_idx += 1;
+        cond
+    });
+    if let Some(c) = &after_filter {
+        conditions.push(c.clone());
+    }
+
+    let before_filter = p.before.as_ref().map(|_| {
+        let cond = format!("m.ts < ?{}", param_idx);
+        param_idx += 1;
+        cond
+    });
+    if let Some(c) = &before_filter {
+        conditions.push(c.clone());
+    }
+
+    // For ignore_chats, we'll use NOT IN with literal values (safe since they're i64)
+    if !p.ignore_chats.is_empty() {
+        let ids: Vec<String> = p.ignore_chats.iter().map(|id| id.to_string()).collect();
+        conditions.push(format!("m.chat_id NOT IN ({})", ids.join(",")));
+    }
+
+    if p.ignore_channels {
+        conditions.push("COALESCE(c.kind, '') != 'channel'".to_string());
+    }
+
+    if p.with_location {
+        conditions.push(
+            "EXISTS (SELECT 1 FROM message_geo g WHERE g.chat_id = m.chat_id AND g.msg_id = m.id)"
+                .to_string(),
+        );
+    }
+
+    if p.cursor.is_some() {
+        conditions.push(MessageCursor::condition(param_idx));
+        param_idx += 3;
+    }
+
+    let sql = format!(
+        "SELECT {}
+         FROM messages m
+         LEFT JOIN chats c ON c.id = m.chat_id
+         WHERE {} ORDER BY m.ts {order}, m.chat_id {order}, m.id {order}{}",
+        MESSAGE_COLUMNS,
+        conditions.join(" AND "),
+        limit_clause(p.limit, param_idx),
+    );
+
+    // Build params tuple dynamically
+    use turso::Value;
+    let mut params: Vec<Value> = Vec::new();
+
+    if let Some(chat_id) = p.chat_id {
+        params.push(Value::Integer(chat_id));
+    }
+    if let Some(topic_id) = p.topic_id {
+        params.push(Value::Integer(topic_id as i64));
+    }
+    if let Some(ref after) = p.after {
+        params.push(Value::Text(after.to_rfc3339()));
+    }
+    if let Some(ref before) = p.before {
+        params.push(Value::Text(before.to_rfc3339()));
+    }
+    if let Some(ref cursor) = p.cursor {
+        params.extend(cursor.params());
+    }
+    if p.limit > 0 {
+        params.push(Value::Integer(p.limit));
+    }
+    (sql, params)
+}
+
 fn row_to_message(row: &Row) -> Result<Message> {
     Ok(Message {
         id: row.get(0)?,
@@ -1250,6 +5385,989 @@ fn row_to_message(row: &Row) -> Result<Message> {
         media_path: row.get::<Option<String>>(8)?,
         reply_to_id: row.get::<Option<i64>>(9)?,
         topic_id: row.get::<Option<i32>>(10).ok().flatten(),
+        service_action: row
+            .get::<Option<String>>(11)
+            .ok()
+            .flatten()
+            .and_then(|s| serde_json::from_str(&s).ok()),
+        fwd_from_name: row.get::<Option<String>>(12).ok().flatten(),
+        fwd_from_id: row.get::<Option<i64>>(13).ok().flatten(),
+        fwd_date: row
+            .get::<Option<String>>(14)
+            .ok()
+            .flatten()
+            .map(|s| parse_ts(&s)),
+        via_bot_id: row.get::<Option<i64>>(15).ok().flatten(),
+        post_author: row.get::<Option<String>>(16).ok().flatten(),
+        views: row.get::<Option<i64>>(17).ok().flatten(),
+        forwards: row.get::<Option<i64>>(18).ok().flatten(),
+        grouped_id: row.get::<Option<i64>>(19).ok().flatten(),
+        shared_contact: row
+            .get::<Option<String>>(20)
+            .ok()
+            .flatten()
+            .and_then(|s| serde_json::from_str(&s).ok()),
+        paid_stars: row.get::<Option<i64>>(21).ok().flatten(),
+        deleted_at: row
+            .get::<Option<String>>(22)
+            .ok()
+            .flatten()
+            .map(|s| parse_ts(&s)),
+        media_name: row.get::<Option<String>>(23).ok().flatten(),
+        media_size: row.get::<Option<i64>>(24).ok().flatten(),
+        media_duration: row.get::<Option<f64>>(25).ok().flatten(),
+        media_width: row.get::<Option<i32>>(26).ok().flatten(),
+        media_height: row.get::<Option<i32>>(27).ok().flatten(),
+        edits: Vec::new(),
+        album: Vec::new(),
+        webpage: None,
+        geo: None,
+        buttons: Vec::new(),
+        reactions: Vec::new(),
         snippet: String::new(),
     })
 }
+
+/// Columns read by `row_to_poll`, in order.
+const POLL_COLUMNS: &str = "chat_id, msg_id, poll_id, question, closed, multiple_choice, quiz, \
+     public_voters, total_voters, close_date";
+
+/// A poll without its options (see `with_poll_options`).
+fn row_to_poll(row: &Row) -> Result<MessagePoll> {
+    Ok(MessagePoll {
+        chat_id: row.get(0)?,
+        msg_id: row.get(1)?,
+        poll_id: row.get(2)?,
+        question: row.get(3)?,
+        closed: row.get::<i64>(4)? != 0,
+        multiple_choice: row.get::<i64>(5)? != 0,
+        quiz: row.get::<i64>(6)? != 0,
+        public_voters: row.get::<i64>(7)? != 0,
+        total_voters: row.get::<Option<i32>>(8).ok().flatten(),
+        close_date: row
+            .get::<Option<String>>(9)
+            .ok()
+            .flatten()
+            .map(|s| parse_ts(&s)),
+        options: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A store in a directory removed when the guard drops, even on a panic
+    async fn temp_store() -> (tempfile::TempDir, Store) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::open(&dir.path().to_string_lossy()).await.unwrap();
+        (dir, store)
+    }
+
+    /// A plain text message from user 42; override other fields with `..msg(..)`
+    fn msg(chat_id: i64, id: i64, ts: DateTime<Utc>, text: &str) -> UpsertMessageParams {
+        UpsertMessageParams {
+            id,
+            chat_id,
+            sender_id: 42,
+            ts,
+            edit_ts: None,
+            from_me: false,
+            text: text.to_string(),
+            media_type: None,
+            media_path: None,
+            reply_to_id: None,
+            topic_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_emoji_spans_join_cached_documents() {
+        let (_dir, store) = temp_store().await;
+        let span = |offset, doc_id| CustomEmojiSpan {
+            offset,
+            length: 2,
+            doc_id,
+        };
+        store
+            .set_message_custom_emoji(1, 1, &[span(3, 20), span(0, 10)])
+            .await
+            .unwrap();
+        let party = CustomEmoji {
+            doc_id: 10,
+            alt: "🎉".to_string(),
+            set_id: Some(5),
+            set_name: Some("PartyBlobs".to_string()),
+        };
+        store.upsert_custom_emoji(&party).await.unwrap();
+
+        let spans = store.message_custom_emoji(1, 1).await.unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].0, span(0, 10));
+        assert_eq!(
+            spans[0].1.as_ref().map(|e| e.label()).as_deref(),
+            Some("[:PartyBlobs/🎉:]")
+        );
+        assert_eq!(spans[1].0, span(3, 20));
+        assert!(spans[1].1.is_none());
+
+        // Re-recording replaces the spans, e.g. after an edit
+        store.set_message_custom_emoji(1, 1, &[]).await.unwrap();
+        assert!(store.message_custom_emoji(1, 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn member_snapshots_are_listed_in_order() {
+        let (_dir, store) = temp_store().await;
+        let member = |user_id, role: &str| ChatMember {
+            user_id,
+            username: None,
+            name: format!("User {}", user_id),
+            role: role.to_string(),
+        };
+        let first = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let second = DateTime::parse_from_rfc3339("2026-02-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        store
+            .save_member_snapshot(7, second, &[member(2, "admin"), member(3, "member")])
+            .await
+            .unwrap();
+        store
+            .save_member_snapshot(7, first, &[member(1, "member"), member(2, "member")])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.member_snapshots(7).await.unwrap(),
+            vec![first, second]
+        );
+        assert!(store.member_snapshots(8).await.unwrap().is_empty());
+        assert_eq!(
+            store.member_snapshot(7, second).await.unwrap(),
+            vec![member(2, "admin"), member(3, "member")]
+        );
+    }
+
+    #[test]
+    fn url_domain_strips_www_and_port() {
+        assert_eq!(
+            url_domain("https://www.GitHub.com/dgrr/tgcli").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(
+            url_domain("http://user@docs.rs:8080?q=1").as_deref(),
+            Some("docs.rs")
+        );
+        assert_eq!(
+            url_domain("example.org/page").as_deref(),
+            Some("example.org")
+        );
+        assert_eq!(url_domain("https:///nothing"), None);
+    }
+
+    #[test]
+    fn collapse_albums_merges_parts() {
+        let part = |id, grouped_id, text: &str| Message {
+            id,
+            chat_id: 1,
+            text: text.to_string(),
+            media_type: Some("photo".to_string()),
+            grouped_id,
+            ..Default::default()
+        };
+        let msgs = vec![
+            part(1, None, "before"),
+            part(2, Some(9), ""),
+            part(3, Some(9), "caption"),
+            part(4, Some(9), ""),
+            part(5, Some(10), "lone"),
+        ];
+
+        let collapsed = collapse_albums(msgs);
+        assert_eq!(collapsed.len(), 3);
+        assert_eq!(collapsed[1].id, 2);
+        assert_eq!(collapsed[1].text, "caption");
+        let ids: Vec<i64> = collapsed[1].album.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![2, 3, 4]);
+        assert!(collapsed[2].album.is_empty());
+    }
+
+    #[tokio::test]
+    async fn duplicate_messages_match_normalized_text() {
+        let (_dir, store) = temp_store().await;
+        for (chat_id, id, text) in [
+            (1, 1, "Buy cheap followers now!"),
+            (2, 5, "  buy CHEAP followers now! "),
+            (3, 9, "Something else entirely"),
+            (4, 2, "ok"),
+            (5, 3, "ok"),
+        ] {
+            store
+                .upsert_message(msg(
+                    chat_id,
+                    id,
+                    Utc::now() + chrono::Duration::seconds(id),
+                    text,
+                ))
+                .await
+                .unwrap();
+        }
+
+        let dups = store.duplicate_messages(10).await.unwrap();
+        let found: Vec<(i64, i64)> = dups.iter().map(|d| (d.chat_id, d.msg_id)).collect();
+        assert_eq!(found, vec![(1, 1), (2, 5)]);
+        assert!(dups.iter().all(|d| d.kind == "text"));
+        assert_eq!(dups[0].key, "buy cheap followers now!");
+
+        assert!(store.delete_message(2, 5).await.unwrap());
+        assert!(store.duplicate_messages(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sender_stats_count_replies_both_ways() {
+        let (_dir, store) = temp_store().await;
+        for (id, sender_id, text, media_type, reply_to_id) in [
+            (1, 10, "hello there", None, None),
+            (2, 20, "hi", None, Some(1)),
+            (3, 10, "", Some("photo"), None),
+            (4, 10, "same person", None, Some(3)),
+        ] {
+            store
+                .upsert_message(UpsertMessageParams {
+                    sender_id,
+                    media_type: media_type.map(str::to_string),
+                    reply_to_id,
+                    ..msg(1, id, Utc::now() + chrono::Duration::seconds(id), text)
+                })
+                .await
+                .unwrap();
+        }
+
+        let stats = store.sender_stats(Some(1), None).await.unwrap();
+        assert_eq!(stats.len(), 2);
+        let top = &stats[0];
+        assert_eq!((top.sender_id, top.messages, top.media), (10, 3, 1));
+        assert_eq!(top.avg_length, Some(11.0));
+        // A reply to your own message doesn't count as being replied to
+        assert_eq!((top.replies, top.replied_to), (1, 1));
+        assert_eq!((stats[1].replies, stats[1].replied_to), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn service_messages_keep_their_action() {
+        let (_dir, store) = temp_store().await;
+        store
+            .upsert_message(msg(1, 7, Utc::now(), "changed the title to \"Team\""))
+            .await
+            .unwrap();
+        let action = serde_json::json!({"type": "chat_edit_title", "title": "Team"});
+        store.set_service_action(1, 7, &action).await.unwrap();
+
+        let msg = store.get_message(1, 7).await.unwrap().unwrap();
+        assert!(msg.is_service());
+        assert_eq!(msg.service_action, Some(action));
+    }
+
+    #[tokio::test]
+    async fn poll_results_survive_updates_without_results() {
+        let (_dir, store) = temp_store().await;
+        let option = |index, text: &str, voters| PollOption {
+            index,
+            text: text.to_string(),
+            voters,
+            chosen: voters == Some(3),
+            correct: false,
+        };
+        let mut poll = MessagePoll {
+            chat_id: 1,
+            msg_id: 5,
+            poll_id: 99,
+            question: "Lunch today?".to_string(),
+            closed: false,
+            multiple_choice: false,
+            quiz: false,
+            public_voters: false,
+            total_voters: Some(4),
+            close_date: None,
+            options: vec![option(0, "Yes", Some(3)), option(1, "No", Some(1))],
+        };
+        store.upsert_message_poll(&poll).await.unwrap();
+
+        // A later sync without results (e.g. a min poll) keeps the counts
+        poll.closed = true;
+        poll.total_voters = None;
+        poll.options = vec![option(0, "Yes", None), option(1, "No", None)];
+        store.upsert_message_poll(&poll).await.unwrap();
+
+        let stored = store.get_message_poll(1, 5).await.unwrap().unwrap();
+        assert!(stored.closed);
+        assert_eq!(stored.total_voters, Some(4));
+        assert_eq!(stored.options[0].voters, Some(3));
+        assert!(stored.options[0].chosen);
+        assert_eq!(stored.options[1].voters, Some(1));
+
+        let found = store.search_polls("lunch", None, 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(store
+            .search_polls("dinner", None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn api_tokens_are_found_by_hash() {
+        let (_dir, store) = temp_store().await;
+        let scopes = vec!["read:messages".to_string(), "send:chat:5".to_string()];
+        store.add_api_token("bot", "abc123", &scopes).await.unwrap();
+        assert!(store.add_api_token("bot", "def456", &[]).await.is_err());
+
+        let token = store.api_token_by_hash("abc123").await.unwrap().unwrap();
+        assert_eq!(token.name, "bot");
+        assert_eq!(token.scopes, scopes);
+        assert!(store.api_token_by_hash("nope").await.unwrap().is_none());
+
+        assert!(store.remove_api_token("bot").await.unwrap());
+        assert!(store.list_api_tokens().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn audit_entries_filter_by_time_chat_and_result() {
+        let (_dir, store) = temp_store().await;
+        let now = Utc::now();
+        let hour = chrono::Duration::hours(1);
+        store
+            .add_audit_entry(now - hour * 48, "send", "send", Some(1), None)
+            .await
+            .unwrap();
+        store
+            .add_audit_entry(
+                now - hour,
+                "messages delete",
+                "delete messages",
+                Some(1),
+                None,
+            )
+            .await
+            .unwrap();
+        store
+            .add_audit_entry(now, "send", "send", Some(2), Some("read-only"))
+            .await
+            .unwrap();
+
+        let recent = store
+            .audit_entries(Some(now - hour * 24), None, false, 100)
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].chat_id, Some(2));
+        assert!(!recent[0].ok);
+
+        let chat = store
+            .audit_entries(None, Some(1), false, 100)
+            .await
+            .unwrap();
+        assert_eq!(chat.len(), 2);
+        let failed = store.audit_entries(None, None, true, 100).await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].error.as_deref(), Some("read-only"));
+    }
+
+    #[tokio::test]
+    async fn retention_policies_find_old_media() {
+        let (_dir, store) = temp_store().await;
+        let now = Utc::now();
+        for (id, age_days, media) in [(1, 100, Some("/m/old.jpg")), (2, 5, Some("/m/new.jpg"))] {
+            store
+                .upsert_message(UpsertMessageParams {
+                    media_type: Some("photo".to_string()),
+                    media_path: media.map(str::to_string),
+                    ..msg(1, id, now - chrono::Duration::days(age_days), "")
+                })
+                .await
+                .unwrap();
+        }
+
+        store.set_retention_policy(1, 30, false).await.unwrap();
+        store.set_retention_policy(1, 90, true).await.unwrap();
+        let policies = store.list_retention_policies().await.unwrap();
+        assert_eq!(policies.len(), 1);
+        assert_eq!(
+            (policies[0].keep_days, policies[0].delete_media),
+            (90, true)
+        );
+
+        let before = now - chrono::Duration::days(90);
+        let files = store.media_files_before(1, before).await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "/m/old.jpg");
+        assert_eq!(store.delete_messages_before(1, before).await.unwrap(), 1);
+        store.record_prune(1, 1, now).await.unwrap();
+        assert_eq!(store.list_retention_policies().await.unwrap()[0].pruned, 1);
+
+        assert!(store.remove_retention_policy(1).await.unwrap());
+        assert!(!store.remove_retention_policy(1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn edits_keep_history_and_deletions_are_marked() {
+        let (_dir, store) = temp_store().await;
+        store
+            .upsert_chat(1, "user", "Ada", None, None, false, Some(7), false)
+            .await
+            .unwrap();
+        for (chat_id, id) in [(1, 10), (2, 10)] {
+            store
+                .upsert_message(msg(chat_id, id, Utc::now(), "first"))
+                .await
+                .unwrap();
+        }
+
+        let now = Utc::now();
+        store
+            .update_message_text(1, 10, "second", now)
+            .await
+            .unwrap();
+        // Re-applying the same edit adds no history
+        store
+            .update_message_text(1, 10, "second", now)
+            .await
+            .unwrap();
+        let edits = store.message_edits(1, 10).await.unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].text, "first");
+        let msg = store.get_message(1, 10).await.unwrap().unwrap();
+        assert_eq!(msg.text, "second");
+
+        // Chat 2 is not a known private chat or basic group
+        assert_eq!(
+            store.mark_messages_deleted(None, &[10], now).await.unwrap(),
+            1
+        );
+        assert!(store
+            .get_message(1, 10)
+            .await
+            .unwrap()
+            .unwrap()
+            .deleted_at
+            .is_some());
+        assert!(store
+            .get_message(2, 10)
+            .await
+            .unwrap()
+            .unwrap()
+            .deleted_at
+            .is_none());
+        assert_eq!(
+            store
+                .mark_messages_deleted(Some(2), &[10], now)
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_status_reports_cursors_and_errors() {
+        let (_dir, store) = temp_store().await;
+        for id in [1, 2] {
+            store
+                .upsert_chat(id, "group", "Team", None, None, false, None, false)
+                .await
+                .unwrap();
+        }
+        for (id, media_type) in [(5, Some("photo")), (7, Some("poll")), (9, None)] {
+            store
+                .upsert_message(UpsertMessageParams {
+                    media_type: media_type.map(str::to_string),
+                    ..msg(1, id, Utc::now(), "")
+                })
+                .await
+                .unwrap();
+        }
+        store.update_last_sync_message_id(1, 9).await.unwrap();
+        store
+            .record_chat_sync(1, Utc::now(), Some("FLOOD_WAIT"))
+            .await
+            .unwrap();
+
+        // Chat 2 was never synced
+        let statuses = store.sync_status(None, false, 10).await.unwrap();
+        assert_eq!(statuses.len(), 1);
+        let status = &statuses[0];
+        assert_eq!(status.last_message_id, Some(9));
+        assert_eq!(status.backfill_cursor, Some(5));
+        assert_eq!(status.pending_media, 1);
+        // Its size was never recorded
+        assert_eq!(status.pending_media_bytes, 0);
+        assert_eq!(status.error.as_deref(), Some("FLOOD_WAIT"));
+
+        store
+            .record_chat_sync(1, Utc::now(), Some("FLOOD_WAIT_2"))
+            .await
+            .unwrap();
+        let errors = store.list_sync_errors().await.unwrap();
+        assert_eq!(
+            (errors[0].attempts, errors[0].error.as_str()),
+            (2, "FLOOD_WAIT_2")
+        );
+
+        store.record_chat_sync(1, Utc::now(), None).await.unwrap();
+        assert!(store.sync_status(None, true, 10).await.unwrap().is_empty());
+        assert!(store.list_sync_errors().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reactions_are_replaced_per_message() {
+        let (_dir, store) = temp_store().await;
+        let reaction = |emoji: &str, count, chosen| MessageReaction {
+            reaction: emoji.to_string(),
+            count,
+            chosen,
+        };
+        store
+            .set_message_reactions(1, 10, &[reaction("👍", 1, false)])
+            .await
+            .unwrap();
+        store
+            .set_message_reactions(1, 10, &[reaction("👍", 2, true), reaction("🔥", 5, false)])
+            .await
+            .unwrap();
+        assert_eq!(
+            store.message_reactions(1, 10).await.unwrap(),
+            vec![reaction("🔥", 5, false), reaction("👍", 2, true)]
+        );
+
+        store.set_message_reactions(1, 10, &[]).await.unwrap();
+        assert!(store.message_reactions(1, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn busy_statements_are_retried() {
+        let mut calls = 0;
+        let result = retry_busy(|| {
+            calls += 1;
+            let n = calls;
+            async move {
+                if n < 3 {
+                    Err(turso::Error::Busy("database is locked".to_string()))
+                } else {
+                    Ok(n)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let result: turso::Result<()> =
+            retry_busy(|| async { Err(turso::Error::Busy("database is locked".to_string())) })
+                .await;
+        assert!(matches!(result, Err(turso::Error::Busy(_))));
+
+        let (_dir, store) = temp_store().await;
+        store.checkpoint().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stream_messages_reads_all_oldest_first() {
+        let (_dir, store) = temp_store().await;
+        let start = Utc::now() - chrono::Duration::hours(1);
+        for id in 1..=5 {
+            store
+                .upsert_message(msg(
+                    1,
+                    id,
+                    start + chrono::Duration::minutes(id),
+                    &format!("message {}", id),
+                ))
+                .await
+                .unwrap();
+        }
+        let params = || ListMessagesParams {
+            chat_id: Some(1),
+            topic_id: None,
+            limit: 0,
+            after: None,
+            before: None,
+            ignore_chats: vec![],
+            ignore_channels: false,
+            with_location: false,
+            cursor: None,
+        };
+
+        let mut rows = store.stream_messages(params()).await.unwrap();
+        let mut ids = Vec::new();
+        while let Some(m) = rows.next().await.unwrap() {
+            ids.push(m.id);
+        }
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+
+        let limited = store
+            .list_messages(ListMessagesParams {
+                limit: 2,
+                ..params()
+            })
+            .await
+            .unwrap();
+        assert_eq!(limited.iter().map(|m| m.id).collect::<Vec<_>>(), vec![4, 5]);
+    }
+
+    #[tokio::test]
+    async fn trigram_index_finds_cjk_substrings() {
+        let (dir, mut store) = temp_store().await;
+        store
+            .upsert_message(msg(1, 1, Utc::now(), "明日は東京駅で会いましょう"))
+            .await
+            .unwrap();
+        let search = |query: &str| SearchMessagesParams {
+            query: query.to_string(),
+            match_mode: MatchMode::Fts,
+            sort: SearchSort::Recent,
+            half_life_days: 0.0,
+            chat_id: None,
+            topic_id: None,
+            topic_name: None,
+            from_id: None,
+            limit: 10,
+            media_type: None,
+            ignore_chats: vec![],
+            ignore_channels: false,
+            forwarded_from: None,
+            via_bot_id: None,
+            has_link: false,
+            domain: None,
+            cursor: None,
+        };
+
+        if !store.has_fts() {
+            // Nothing to rebuild, and LIKE finds substrings anyway
+            assert!(store
+                .rebuild_fts(FtsTokenizer::Trigram, None)
+                .await
+                .is_err());
+            assert!(!store.has_fts());
+            for query in ["東京駅", "東京"] {
+                assert_eq!(store.search_messages(search(query)).await.unwrap().len(), 1);
+            }
+            return;
+        }
+        assert_eq!(store.fts_tokenizer(), FtsTokenizer::Unicode61);
+        assert_eq!(
+            store
+                .rebuild_fts(FtsTokenizer::Trigram, None)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(store.fts_tokenizer(), FtsTokenizer::Trigram);
+        assert_eq!(
+            store.search_messages(search("東京駅")).await.unwrap().len(),
+            1
+        );
+        // Too short for a trigram, answered by LIKE instead
+        assert_eq!(
+            store.search_messages(search("東京")).await.unwrap().len(),
+            1
+        );
+
+        // The tokenizer survives reopening the store
+        drop(store);
+        let store = Store::open(&dir.path().to_string_lossy()).await.unwrap();
+        assert_eq!(store.fts_tokenizer(), FtsTokenizer::Trigram);
+    }
+
+    #[test]
+    fn fts_match_expr_quotes_user_input() {
+        let fts = |q: &str| fts_match_expr(q, MatchMode::Fts);
+        assert_eq!(fts("hello world").unwrap(), r#""hello" "world""#);
+        assert_eq!(
+            fts(r#""release notes" OR deploy* NOT (test AND ci)"#).unwrap(),
+            r#""release notes" OR "deploy"* NOT ( "test" AND "ci" )"#
+        );
+        // Column filters and stray quotes stay plain text
+        assert_eq!(fts(r#"text:foo "bar"#).unwrap(), r#""text:foo" "bar""#);
+        assert!(fts("OR cats").is_err());
+        assert!(fts("cats AND").is_err());
+        assert!(fts("(cats").is_err());
+        assert!(fts("cats)").is_err());
+
+        assert_eq!(
+            fts_match_expr(r#"say "hi" AND*"#, MatchMode::Plain).unwrap(),
+            r#""say" """hi""" "AND*""#
+        );
+        assert!(fts_match_expr("  ", MatchMode::Plain).is_err());
+    }
+
+    #[test]
+    fn like_match_expr_follows_fts_operators() {
+        let (expr, patterns) = like_match_expr(
+            r#""release notes" OR deploy* NOT (test ci)"#,
+            MatchMode::Fts,
+            2,
+        )
+        .unwrap();
+        let like = |n| format!("m.text LIKE ?{} ESCAPE '\\'", n);
+        assert_eq!(
+            expr,
+            format!(
+                "({} OR {} AND NOT ( {} AND {} ))",
+                like(2),
+                like(3),
+                like(4),
+                like(5)
+            )
+        );
+        assert_eq!(patterns, ["%release notes%", "%deploy%", "%test%", "%ci%"]);
+
+        let (expr, patterns) = like_match_expr("cats OR dogs", MatchMode::Plain, 1).unwrap();
+        assert_eq!(
+            expr,
+            format!("({} AND {} AND {})", like(1), like(2), like(3))
+        );
+        assert_eq!(patterns, ["%cats%", "%OR%", "%dogs%"]);
+        assert!(like_match_expr("cats AND", MatchMode::Fts, 1).is_err());
+
+        let (_, patterns) = like_match_expr(r"50% foo_bar C:\tmp", MatchMode::Plain, 1).unwrap();
+        assert_eq!(patterns, [r"%50\%%", r"%foo\_bar%", r"%C:\\tmp%"]);
+    }
+
+    #[tokio::test]
+    async fn like_search_matches_wildcards_literally() {
+        let (_dir, store) = temp_store().await;
+        for (id, text) in [
+            (1, "50% off"),
+            (2, "500 off"),
+            (3, "foo_bar"),
+            (4, "fooXbar"),
+        ] {
+            store
+                .upsert_message(msg(1, id, Utc::now(), text))
+                .await
+                .unwrap();
+        }
+        let search = |query: &str| SearchMessagesParams {
+            query: query.to_string(),
+            match_mode: MatchMode::Plain,
+            sort: SearchSort::Recent,
+            half_life_days: 0.0,
+            chat_id: None,
+            topic_id: None,
+            topic_name: None,
+            from_id: None,
+            limit: 10,
+            media_type: None,
+            ignore_chats: vec![],
+            ignore_channels: false,
+            forwarded_from: None,
+            via_bot_id: None,
+            has_link: false,
+            domain: None,
+            cursor: None,
+        };
+
+        for (query, id) in [("50%", 1), ("foo_bar", 3)] {
+            let found = store.search_rows_like(search(query)).await.unwrap();
+            let found = found.into_vec().await.unwrap();
+            assert_eq!(found.iter().map(|m| m.id).collect::<Vec<_>>(), vec![id]);
+        }
+    }
+
+    #[tokio::test]
+    async fn relevance_sort_blends_bm25_with_age() {
+        let (_dir, store) = temp_store().await;
+        let texts = [
+            (1, 1000, "deploy deploy deploy"),
+            (
+                2,
+                1,
+                "the deploy went out today after a long round of checks",
+            ),
+        ];
+        for (id, days_ago, text) in texts {
+            store
+                .upsert_message(msg(
+                    1,
+                    id,
+                    Utc::now() - chrono::Duration::days(days_ago),
+                    text,
+                ))
+                .await
+                .unwrap();
+        }
+        let search = |sort: SearchSort, half_life_days: f64| SearchMessagesParams {
+            query: "deploy".to_string(),
+            match_mode: MatchMode::Fts,
+            sort,
+            half_life_days,
+            chat_id: None,
+            topic_id: None,
+            topic_name: None,
+            from_id: None,
+            limit: 10,
+            media_type: None,
+            ignore_chats: vec![],
+            ignore_channels: false,
+            forwarded_from: None,
+            via_bot_id: None,
+            has_link: false,
+            domain: None,
+            cursor: None,
+        };
+        let ids = |msgs: Vec<Message>| msgs.iter().map(|m| m.id).collect::<Vec<_>>();
+
+        let recent = store.search_messages(search(SearchSort::Recent, 0.0)).await;
+        assert_eq!(ids(recent.unwrap()), vec![2, 1]);
+        if !store.has_fts() {
+            // Without bm25 there's nothing to rank by
+            let err = store
+                .search_messages(search(SearchSort::Relevance, 0.0))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("FTS5"), "{}", err);
+            return;
+        }
+        let best = store
+            .search_messages(search(SearchSort::Relevance, 0.0))
+            .await;
+        assert_eq!(ids(best.unwrap()), vec![1, 2]);
+        // A short half-life buries the old match
+        let blended = store
+            .search_messages(search(SearchSort::Relevance, 1.0))
+            .await;
+        assert_eq!(ids(blended.unwrap()), vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn search_filters_by_topic_name_across_chats() {
+        let (_dir, store) = temp_store().await;
+        // (chat, topic, topic name, message id)
+        let posts = [
+            (1, 10, "Releases", 1),
+            (2, 20, "Old releases", 2),
+            (2, 30, "Random", 3),
+        ];
+        for (chat_id, topic_id, name, id) in posts {
+            store
+                .upsert_topic(chat_id, topic_id, name, 0, None, 0, None)
+                .await
+                .unwrap();
+            store
+                .upsert_message(UpsertMessageParams {
+                    topic_id: Some(topic_id),
+                    ..msg(chat_id, id, Utc::now(), "shipping v2 today")
+                })
+                .await
+                .unwrap();
+        }
+
+        let mut msgs = store
+            .search_messages(SearchMessagesParams {
+                query: "shipping".to_string(),
+                match_mode: MatchMode::Fts,
+                sort: SearchSort::Recent,
+                half_life_days: 0.0,
+                chat_id: None,
+                topic_id: None,
+                topic_name: Some("release".to_string()),
+                from_id: None,
+                limit: 10,
+                media_type: None,
+                ignore_chats: vec![],
+                ignore_channels: false,
+                forwarded_from: None,
+                via_bot_id: None,
+                has_link: false,
+                domain: None,
+                cursor: None,
+            })
+            .await
+            .unwrap();
+        msgs.sort_by_key(|m| m.id);
+        assert_eq!(msgs.iter().map(|m| m.id).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn media_listing_includes_file_info() {
+        let (_dir, store) = temp_store().await;
+        let media = [
+            (1, Some("document"), Some("/tmp/report.pdf")),
+            (2, Some("photo"), None),
+            (3, Some("poll"), None),
+            (4, None, None),
+        ];
+        for (id, media_type, media_path) in media {
+            store
+                .upsert_message(UpsertMessageParams {
+                    media_type: media_type.map(str::to_string),
+                    media_path: media_path.map(str::to_string),
+                    ..msg(1, id, Utc::now() - chrono::Duration::minutes(10 - id), "")
+                })
+                .await
+                .unwrap();
+        }
+        let pdf = MediaFileInfo {
+            name: Some("report.pdf".to_string()),
+            size: 52_000,
+            ..Default::default()
+        };
+        store.set_media_file_info(1, 1, &pdf).await.unwrap();
+        let photo = MediaFileInfo {
+            size: 180_000,
+            width: Some(1280),
+            height: Some(960),
+            ..Default::default()
+        };
+        store.set_media_file_info(1, 2, &photo).await.unwrap();
+        let params = |missing_local| MediaMessagesParams {
+            chat_id: Some(1),
+            media_type: None,
+            after: None,
+            before: None,
+            missing_local,
+            limit: 0,
+        };
+
+        let all = store.list_media_messages(params(false)).await.unwrap();
+        assert_eq!(all.iter().map(|m| m.id).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(all[1].media_name.as_deref(), Some("report.pdf"));
+        assert_eq!(all[1].media_size, Some(52_000));
+        assert_eq!(
+            (all[0].media_width, all[0].media_height),
+            (Some(1280), Some(960))
+        );
+
+        let missing = store.list_media_messages(params(true)).await.unwrap();
+        assert_eq!(missing.iter().map(|m| m.id).collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn media_paths_follow_a_moved_store() {
+        let (_dir, store) = temp_store().await;
+        for (id, path) in [(1, "/home/me/.tgcli/media/1/1.jpg"), (2, "/tmp/sent_1.jpg")] {
+            store
+                .upsert_message(UpsertMessageParams {
+                    media_type: Some("photo".to_string()),
+                    media_path: Some(path.to_string()),
+                    ..msg(1, id, Utc::now(), "")
+                })
+                .await
+                .unwrap();
+        }
+
+        let moved = store
+            .move_media_paths("/home/me/.tgcli/", "/home/me/.local/share/tgcli/")
+            .await
+            .unwrap();
+        assert_eq!(moved, 1);
+        let msg = store.get_message(1, 1).await.unwrap().unwrap();
+        assert_eq!(
+            msg.media_path.as_deref(),
+            Some("/home/me/.local/share/tgcli/media/1/1.jpg")
+        );
+        let msg = store.get_message(1, 2).await.unwrap().unwrap();
+        assert_eq!(msg.media_path.as_deref(), Some("/tmp/sent_1.jpg"));
+    }
+}
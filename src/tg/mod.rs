@@ -1,14 +1,96 @@
+pub mod trace;
+pub mod transfer;
+
 use anyhow::{Context, Result};
 use grammers_client::Client;
-use grammers_mtsender::SenderPool;
+use grammers_mtsender::{ConnectionParams, InvocationError, SenderPool};
+use grammers_session::defs::DcOption;
 use grammers_session::storages::SqliteSession;
 use grammers_session::updates::UpdatesLike;
+use grammers_session::Session;
+use grammers_tl_types::RemoteCall;
+use std::net::{SocketAddrV4, SocketAddrV6};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
 pub const API_ID: i32 = 32529142;
 pub const API_HASH: &str = "cf7543485b4c077f67423f57fe42911f";
 
+/// Telegram's test environment: (DC ID, IPv4, IPv6), all on port 443.
+const TEST_DCS: [(i32, &str, &str); 3] = [
+    (1, "149.154.175.10:443", "[2001:b28:f23d:f001::e]:443"),
+    (2, "149.154.167.40:443", "[2001:67c:4e8:f002::e]:443"),
+    (3, "149.154.175.117:443", "[2001:b28:f23d:f003::e]:443"),
+];
+
+/// Telegram API credentials (https://my.telegram.org); defaults to tgcli's own.
+#[derive(Debug, Clone)]
+pub struct ApiCredentials {
+    pub api_id: i32,
+    pub api_hash: String,
+}
+
+impl Default for ApiCredentials {
+    fn default() -> Self {
+        ApiCredentials {
+            api_id: API_ID,
+            api_hash: API_HASH.to_string(),
+        }
+    }
+}
+
+/// How to reach Telegram's servers.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    pub api: ApiCredentials,
+    /// SOCKS5 proxy URL (`socks5://[user:pass@]host:port`)
+    pub proxy: Option<String>,
+    /// Use Telegram's test servers instead of production
+    pub test_dc: bool,
+    /// Force the home DC (1-5; 1-3 on test servers)
+    pub dc: Option<i32>,
+}
+
+impl ConnectOptions {
+    /// Check the DC ID and proxy URL up front so a typo fails fast instead of timing out.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(dc) = self.dc {
+            let max = if self.test_dc { 3 } else { 5 };
+            if !(1..=max).contains(&dc) {
+                anyhow::bail!("Invalid --dc {}. Expected a DC ID from 1 to {}", dc, max);
+            }
+        }
+        let Some(proxy) = &self.proxy else {
+            return Ok(());
+        };
+        let (scheme, rest) = proxy.split_once("://").with_context(|| {
+            format!("Invalid proxy URL '{}'. Expected socks5://host:port", proxy)
+        })?;
+        match scheme {
+            "socks5" | "socks5h" => {}
+            "mtproto" | "tg" => anyhow::bail!(
+                "MTProto proxies are not supported by the grammers transport; use a SOCKS5 proxy (socks5://host:port)"
+            ),
+            _ => anyhow::bail!(
+                "Unsupported proxy scheme '{}'. Expected socks5://host:port",
+                scheme
+            ),
+        }
+        let host_port = rest.rsplit('@').next().unwrap_or(rest);
+        let port = host_port.rsplit_once(':').map(|(_, p)| p);
+        if port
+            .and_then(|p| p.trim_end_matches('/').parse::<u16>().ok())
+            .is_none()
+        {
+            anyhow::bail!(
+                "Proxy URL '{}' is missing a port (socks5://host:port)",
+                proxy
+            );
+        }
+        Ok(())
+    }
+}
+
 /// A connected Telegram client with its pool runner handle.
 pub struct TgClient {
     pub client: Client,
@@ -22,7 +104,9 @@ impl TgClient {
     /// Returns the client and an updates receiver.
     pub fn connect_with_updates(
         session_path: &str,
+        opts: &ConnectOptions,
     ) -> Result<(Self, mpsc::UnboundedReceiver<UpdatesLike>)> {
+        opts.validate()?;
         let session = Arc::new(SqliteSession::open(session_path).with_context(|| {
             format!(
                 "Failed to open session database at '{}'. Check file permissions.",
@@ -30,7 +114,22 @@ impl TgClient {
             )
         })?);
 
-        let pool = SenderPool::new(Arc::clone(&session) as Arc<SqliteSession>, API_ID);
+        if opts.test_dc {
+            use_test_servers(&session)?;
+        }
+        if let Some(dc) = opts.dc {
+            session.set_home_dc_id(dc);
+        }
+
+        let params = ConnectionParams {
+            proxy_url: opts.proxy.clone(),
+            ..Default::default()
+        };
+        let pool = SenderPool::with_configuration(
+            Arc::clone(&session) as Arc<SqliteSession>,
+            opts.api.api_id,
+            params,
+        );
         let client = Client::new(&pool);
 
         let SenderPool {
@@ -52,6 +151,33 @@ impl TgClient {
     }
 }
 
+impl TgClient {
+    /// Invoke a raw TL function (traced with `--debug-api`).
+    pub async fn invoke<R: RemoteCall>(&self, request: &R) -> Result<R::Return, InvocationError> {
+        trace::invoke(&self.client, request).await
+    }
+}
+
+/// Point the session's DC table at the test environment.
+/// Auth keys are dropped for any DC whose address changes, since keys are per-server.
+fn use_test_servers(session: &SqliteSession) -> Result<()> {
+    for (id, ipv4, ipv6) in TEST_DCS {
+        let ipv4: SocketAddrV4 = ipv4.parse()?;
+        let ipv6: SocketAddrV6 = ipv6.parse()?;
+        let auth_key = session
+            .dc_option(id)
+            .filter(|dc| dc.ipv4 == ipv4)
+            .and_then(|dc| dc.auth_key);
+        session.set_dc_option(&DcOption {
+            id,
+            ipv4,
+            ipv6,
+            auth_key,
+        });
+    }
+    Ok(())
+}
+
 impl Drop for TgClient {
     fn drop(&mut self) {
         self.client.disconnect();
@@ -0,0 +1,196 @@
+//! API request tracing for `--debug-api`.
+//!
+//! Calls, errors, and flood waits are always counted (see [`counters`]) for
+//! daemon metrics. With `--debug-api`, every raw TL call made through [`invoke`]
+//! is also logged with its request and
+//! response type, duration, and outcome to `{store}/logs/api.log`. Request and
+//! response bodies are never written. The file rotates at `MAX_LOG_BYTES`,
+//! keeping `KEEP_ROTATED` older files (`api.log.1`, `api.log.2`, ...).
+
+use anyhow::{Context, Result};
+use grammers_client::Client;
+use grammers_mtsender::InvocationError;
+use grammers_tl_types::RemoteCall;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+const KEEP_ROTATED: usize = 3;
+
+static TRACE: OnceLock<Mutex<TraceFile>> = OnceLock::new();
+
+static CALLS: AtomicU64 = AtomicU64::new(0);
+static ERRORS: AtomicU64 = AtomicU64::new(0);
+static FLOOD_WAITS: AtomicU64 = AtomicU64::new(0);
+
+/// Process-wide API call counters.
+#[derive(Debug, Clone, Copy)]
+pub struct Counters {
+    pub calls: u64,
+    pub errors: u64,
+    pub flood_waits: u64,
+}
+
+pub fn counters() -> Counters {
+    Counters {
+        calls: CALLS.load(Ordering::Relaxed),
+        errors: ERRORS.load(Ordering::Relaxed),
+        flood_waits: FLOOD_WAITS.load(Ordering::Relaxed),
+    }
+}
+
+fn is_flood_wait(e: &InvocationError) -> bool {
+    matches!(e, InvocationError::Rpc(rpc) if rpc.name.starts_with("FLOOD_WAIT"))
+}
+
+struct TraceFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl TraceFile {
+    fn open(path: PathBuf) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open API trace log {}", path.display()))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(TraceFile { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size + line.len() as u64 + 1 > MAX_LOG_BYTES {
+            if let Err(e) = self.rotate() {
+                log::warn!("Failed to rotate API trace log: {}", e);
+            }
+        }
+        if writeln!(self.file, "{}", line).is_ok() {
+            self.size += line.len() as u64 + 1;
+        }
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let rotated = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        for n in (1..KEEP_ROTATED).rev() {
+            let _ = fs::rename(rotated(n), rotated(n + 1));
+        }
+        fs::rename(&self.path, rotated(1))?;
+        *self = TraceFile::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+/// Start tracing API calls to `{store_dir}/logs/api.log` for the rest of the process.
+pub fn enable(store_dir: &str) -> Result<PathBuf> {
+    let dir = PathBuf::from(store_dir).join("logs");
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+    let path = dir.join("api.log");
+    let trace = TraceFile::open(path.clone())?;
+    let _ = TRACE.set(Mutex::new(trace));
+    Ok(path)
+}
+
+fn record(line: String) {
+    if let Some(trace) = TRACE.get() {
+        if let Ok(mut trace) = trace.lock() {
+            trace.write_line(&line);
+        }
+    }
+}
+
+/// Invoke a raw TL function, tracing it when `--debug-api` is on. Calls that
+/// change the account are refused in read-only mode.
+pub async fn invoke<R: RemoteCall>(
+    client: &Client,
+    request: &R,
+) -> Result<R::Return, InvocationError> {
+    let request_type = tl_name(std::any::type_name::<R>());
+    if !crate::guard::allows_call(&request_type) {
+        return Err(InvocationError::Io(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("refusing {} in read-only mode", request_type),
+        )));
+    }
+    let started = Instant::now();
+    let result = client.invoke(request).await;
+
+    CALLS.fetch_add(1, Ordering::Relaxed);
+    if let Err(e) = &result {
+        ERRORS.fetch_add(1, Ordering::Relaxed);
+        if is_flood_wait(e) {
+            FLOOD_WAITS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    if TRACE.get().is_none() {
+        return result;
+    }
+
+    let elapsed_ms = started.elapsed().as_millis();
+    let ts = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+    let outcome = match &result {
+        Ok(_) => format!("ok -> {}", tl_name(std::any::type_name::<R::Return>())),
+        Err(InvocationError::Rpc(rpc)) if rpc.name.starts_with("FLOOD_WAIT") => {
+            format!("FLOOD_WAIT {}s (code {})", rpc.value.unwrap_or(0), rpc.code)
+        }
+        Err(InvocationError::Rpc(rpc)) => format!("rpc error {} (code {})", rpc.name, rpc.code),
+        Err(e) => format!("error: {}", e),
+    };
+    record(format!(
+        "{} {} {}ms {}",
+        ts, request_type, elapsed_ms, outcome
+    ));
+    result
+}
+
+/// `grammers_tl_types::generated::functions::messages::GetHistory` -> `messages.GetHistory`
+fn tl_name(full: &str) -> String {
+    let path = full.split('<').next().unwrap_or(full);
+    let parts: Vec<&str> = path.split("::").collect();
+    let tail = match parts
+        .iter()
+        .position(|p| *p == "functions" || *p == "enums" || *p == "types")
+    {
+        Some(i) => &parts[i + 1..],
+        None => &parts[parts.len().saturating_sub(1)..],
+    };
+    let name = tail.join(".");
+    if full.contains('<') {
+        // Generic returns such as Vec<...>
+        format!(
+            "{}<{}>",
+            name,
+            tl_name(&full[full.find('<').unwrap() + 1..full.len() - 1])
+        )
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tl_name() {
+        assert_eq!(
+            tl_name("grammers_tl_types::generated::functions::messages::GetHistory"),
+            "messages.GetHistory"
+        );
+        assert_eq!(
+            tl_name("grammers_tl_types::generated::enums::Updates"),
+            "Updates"
+        );
+        assert_eq!(
+            tl_name("alloc::vec::Vec<grammers_tl_types::generated::enums::Document>"),
+            "Vec<Document>"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Media transfers with optional bandwidth limits (`--max-download-rate`,
+//! `--max-upload-rate`) and upload progress.
+//!
+//! Each direction has a process-wide token bucket holding at most one second
+//! of traffic. Transfers take tokens after every chunk and sleep off any
+//! shortfall, so a long sync averages out to the configured rate.
+
+use crate::out::progress::Progress;
+use anyhow::{Context, Result};
+use grammers_client::types::Downloadable;
+use grammers_client::Client;
+use indicatif::ProgressBar;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+use std::task::{ready, Context as TaskContext, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWriteExt, ReadBuf};
+use tokio::time::Sleep;
+
+static DOWNLOAD: OnceLock<Bucket> = OnceLock::new();
+static UPLOAD: OnceLock<Bucket> = OnceLock::new();
+
+struct Bucket {
+    /// Bytes per second
+    rate: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl Bucket {
+    fn new(rate: u64) -> Self {
+        Bucket {
+            rate: rate as f64,
+            state: Mutex::new((rate as f64, Instant::now())),
+        }
+    }
+
+    /// Take `bytes` from the bucket; returns how long to wait if it ran dry.
+    fn take(&self, bytes: usize) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (tokens, last) = &mut *state;
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.rate).min(self.rate);
+        *last = now;
+        *tokens -= bytes as f64;
+        (*tokens < 0.0).then(|| Duration::from_secs_f64(-*tokens / self.rate))
+    }
+}
+
+/// Cap media downloads at `bytes_per_sec` for the rest of the process.
+pub fn set_download_rate(bytes_per_sec: u64) {
+    let _ = DOWNLOAD.set(Bucket::new(bytes_per_sec));
+}
+
+/// Cap media uploads at `bytes_per_sec` for the rest of the process.
+pub fn set_upload_rate(bytes_per_sec: u64) {
+    let _ = UPLOAD.set(Bucket::new(bytes_per_sec));
+}
+
+/// Wait until `bytes` more of download traffic is allowed.
+pub async fn throttle_download(bytes: usize) {
+    if let Some(wait) = DOWNLOAD.get().and_then(|b| b.take(bytes)) {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// A file written by [`download_to_file`].
+pub struct Downloaded {
+    pub size: u64,
+    /// Hex SHA-256 of the file contents
+    pub sha256: String,
+}
+
+/// Download media to `path` chunk by chunk, honouring `--max-download-rate`
+/// and hashing the contents on the way. A partially written file is removed
+/// on failure.
+pub async fn download_to_file<D: Downloadable>(
+    client: &Client,
+    media: &D,
+    path: &Path,
+) -> Result<Downloaded> {
+    let result = async {
+        let mut file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create file '{}'", path.display()))?;
+        let mut hasher = Sha256::new();
+        let mut size: u64 = 0;
+        let mut chunks = client.iter_download(media);
+        while let Some(chunk) = chunks.next().await.context("Failed to download chunk")? {
+            file.write_all(&chunk).context("Failed to write to file")?;
+            hasher.update(&chunk);
+            size += chunk.len() as u64;
+            throttle_download(chunk.len()).await;
+        }
+        Ok(Downloaded {
+            size,
+            sha256: format!("{:x}", hasher.finalize()),
+        })
+    }
+    .await;
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+/// Hex SHA-256 of a file on disk.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// A file opened for `Client::upload_stream`: the reader, its size and the
+/// name Telegram shows.
+pub struct Upload {
+    pub reader: UploadReader<tokio::fs::File>,
+    pub size: usize,
+    pub name: String,
+    /// Removes the stdin spool file once the upload is done with
+    _spool: Option<tempfile::NamedTempFile>,
+}
+
+/// Open a file to upload, honouring `--max-upload-rate` and showing
+/// percent/throughput on stderr when `show_progress` is set. `name` overrides
+/// the file name Telegram shows (defaults to the path's file name).
+pub async fn open_upload(path: &Path, name: Option<&str>, show_progress: bool) -> Result<Upload> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open '{}'", path.display()))?;
+    let size = file.metadata().await?.len();
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".to_string()),
+    };
+    let bar = Progress::new(show_progress).bytes(Some(size), "Uploading");
+    Ok(Upload {
+        reader: UploadReader::new(file, bar),
+        size: size as usize,
+        name,
+        _spool: None,
+    })
+}
+
+/// Spool everything piped to stdin and open it to upload as `name`.
+///
+/// Telegram needs the total size before the first part is sent, so the data is
+/// spooled to a private temporary file that is removed afterwards.
+pub async fn spool_stdin(name: &str, show_progress: bool) -> Result<Upload> {
+    // Created with a random name, readable only by us, and removed on drop
+    let spool = tempfile::Builder::new()
+        .prefix("tgcli-stdin-")
+        .tempfile()
+        .context("Failed to create a temporary file for stdin")?;
+    let mut file = tokio::fs::File::from_std(spool.reopen()?);
+    let size = tokio::io::copy(&mut tokio::io::stdin(), &mut file).await?;
+    file.flush().await?;
+    if size == 0 {
+        anyhow::bail!("nothing was read from stdin");
+    }
+    let mut upload = open_upload(spool.path(), Some(name), show_progress).await?;
+    upload._spool = Some(spool);
+    Ok(upload)
+}
+
+/// An `AsyncRead` that advances a progress bar with every chunk read and
+/// pauses between reads to stay under `--max-upload-rate`. The bar is
+/// cleared once everything is read, or when the reader is dropped.
+pub struct UploadReader<R> {
+    inner: R,
+    bucket: Option<&'static Bucket>,
+    delay: Option<Pin<Box<Sleep>>>,
+    progress: ProgressBar,
+}
+
+impl<R> UploadReader<R> {
+    pub fn new(inner: R, progress: ProgressBar) -> Self {
+        UploadReader {
+            inner,
+            bucket: UPLOAD.get(),
+            delay: None,
+            progress,
+        }
+    }
+}
+
+impl<R> Drop for UploadReader<R> {
+    fn drop(&mut self) {
+        self.progress.finish_and_clear();
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for UploadReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if let Some(delay) = self.delay.as_mut() {
+            ready!(delay.as_mut().poll(cx));
+            self.delay = None;
+        }
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        let read = buf.filled().len() - before;
+        self.progress.inc(read as u64);
+        let position = self.progress.position();
+        if self.progress.length().is_some_and(|len| position >= len) {
+            self.progress.finish_and_clear();
+        }
+        if let Some(wait) = self.bucket.and_then(|b| b.take(read)) {
+            self.delay = Some(Box::pin(tokio::time::sleep(wait)));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Parse a transfer rate such as `2MB/s`, `500K`, or `1048576` into bytes/sec.
+/// Units are binary (1 KB = 1024 bytes) to match the sizes tgcli prints.
+pub fn parse_rate(s: &str) -> Result<u64, String> {
+    let lower = s.trim().to_ascii_lowercase();
+    let body = lower.strip_suffix("/s").unwrap_or(&lower).trim();
+    let split = body
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(body.len());
+    let (number, unit) = body.split_at(split);
+    let multiplier: u64 = match unit.trim() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        other => return Err(format!("unknown unit '{}' (use B, KB, MB, or GB)", other)),
+    };
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid rate '{}' (e.g. 2MB/s)", s))?;
+    let rate = (value * multiplier as f64) as u64;
+    if rate == 0 {
+        return Err("rate must be greater than zero".to_string());
+    }
+    Ok(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("2MB/s"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_rate("500k"), Ok(500 * 1024));
+        assert_eq!(parse_rate("1.5 MB"), Ok(1536 * 1024));
+        assert_eq!(parse_rate("4096"), Ok(4096));
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("2TB/s").is_err());
+    }
+
+    #[test]
+    fn test_bucket_waits_when_empty() {
+        let bucket = Bucket::new(1000);
+        assert_eq!(bucket.take(1000), None);
+        let wait = bucket.take(500).expect("bucket should be empty");
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,140 @@
+//! Rendering of Telegram animated stickers (TGS) to GIF/PNG.
+//!
+//! A TGS file is a gzip-compressed Lottie JSON animation. Rendering uses
+//! rlottie and is only available when built with `--features tgs`.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/// Default output size in pixels (TGS canvases are 512x512).
+pub const DEFAULT_SIZE: u16 = 512;
+
+#[cfg_attr(not(feature = "tgs"), allow(dead_code))]
+pub struct RenderOptions {
+    /// Width and height of the output in pixels
+    pub size: u16,
+    /// Render only this frame (PNG output); GIF output renders all frames
+    pub frame: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(not(feature = "tgs"), allow(dead_code))]
+pub struct RenderResult {
+    pub path: String,
+    pub format: String,
+    pub frames: usize,
+    pub fps: f64,
+    pub size: u16,
+}
+
+/// Render `tgs` bytes to `dest`. The format is picked from the extension:
+/// `.gif` for an animation, `.png` for a single frame.
+#[cfg(feature = "tgs")]
+pub fn render(tgs: &[u8], dest: &Path, opts: &RenderOptions) -> Result<RenderResult> {
+    use anyhow::Context;
+    use std::io::Read;
+
+    let format = output_format(dest)?;
+
+    let mut json = Vec::new();
+    flate2::read::GzDecoder::new(tgs)
+        .read_to_end(&mut json)
+        .context("Not a TGS file (gzip decode failed)")?;
+
+    let mut anim = rlottie::Animation::from_data(json, "tgcli", "")
+        .ok_or_else(|| anyhow::anyhow!("Invalid Lottie animation data"))?;
+    let total_frames = anim.totalframe();
+    let fps = anim.framerate();
+    let side = usize::from(opts.size);
+    let mut surface = rlottie::Surface::new(rlottie::Size::new(side, side));
+
+    let mut render_frame = |n: usize| -> Vec<u8> {
+        anim.render(n, &mut surface);
+        surface
+            .data()
+            .iter()
+            .flat_map(|p| unpremultiply(p.r, p.g, p.b, p.a))
+            .collect()
+    };
+
+    let file = std::fs::File::create(dest)
+        .with_context(|| format!("Failed to create '{}'", dest.display()))?;
+    let writer = std::io::BufWriter::new(file);
+
+    let frames_written = if format == "png" {
+        let n = opts.frame.unwrap_or(0).min(total_frames.saturating_sub(1));
+        let rgba = render_frame(n);
+        let mut encoder = png::Encoder::new(writer, opts.size.into(), opts.size.into());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut w| w.write_image_data(&rgba))
+            .context("Failed to write PNG")?;
+        1
+    } else {
+        // GIF delays are in 1/100s and players clamp anything below 2,
+        // so drop every other frame for 60fps animations
+        let step = if fps > 50.0 { 2 } else { 1 };
+        let delay = ((100.0 * step as f64) / fps.max(1.0)).round() as u16;
+
+        let mut encoder =
+            gif::Encoder::new(writer, opts.size, opts.size, &[]).context("Failed to start GIF")?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .context("Failed to write GIF")?;
+        let mut written = 0;
+        for n in (0..total_frames).step_by(step) {
+            let mut rgba = render_frame(n);
+            let mut frame = gif::Frame::from_rgba_speed(opts.size, opts.size, &mut rgba, 10);
+            frame.delay = delay.max(2);
+            frame.dispose = gif::DisposalMethod::Background;
+            encoder.write_frame(&frame).context("Failed to write GIF")?;
+            written += 1;
+        }
+        written
+    };
+
+    Ok(RenderResult {
+        path: dest.to_string_lossy().to_string(),
+        format: format.to_string(),
+        frames: frames_written,
+        fps,
+        size: opts.size,
+    })
+}
+
+#[cfg(not(feature = "tgs"))]
+pub fn render(_tgs: &[u8], dest: &Path, _opts: &RenderOptions) -> Result<RenderResult> {
+    output_format(dest)?;
+    anyhow::bail!(
+        "This build of tgcli has no TGS support. Rebuild with `cargo install tgcli --features tgs` (requires rlottie)."
+    )
+}
+
+fn output_format(dest: &Path) -> Result<&'static str> {
+    match dest
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("gif") => Ok("gif"),
+        Some("png") => Ok("png"),
+        _ => anyhow::bail!(
+            "Unsupported output '{}': use a .gif or .png destination",
+            dest.display()
+        ),
+    }
+}
+
+/// rlottie renders premultiplied BGRA; GIF/PNG encoders expect straight RGBA.
+#[cfg(feature = "tgs")]
+fn unpremultiply(r: u8, g: u8, b: u8, a: u8) -> [u8; 4] {
+    if a == 0 {
+        return [0, 0, 0, 0];
+    }
+    let f = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+    [f(r), f(g), f(b), a]
+}
@@ -0,0 +1,110 @@
+//! Word and bigram counts for `tgcli stats words`.
+//!
+//! Tokens are runs of Unicode letters and digits (apostrophes inside a word
+//! are kept), lowercased. Links, @mentions, bare numbers and single
+//! characters are dropped.
+
+use std::collections::HashMap;
+
+/// Common English words left out with `--exclude-stopwords`.
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+    "be", "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does",
+    "doing", "don't", "for", "from", "get", "got", "had", "has", "have", "he", "her", "here",
+    "him", "his", "how", "i", "i'm", "if", "in", "into", "is", "it", "it's", "its", "just", "like",
+    "me", "more", "my", "no", "not", "now", "of", "ok", "on", "one", "only", "or", "our", "out",
+    "so", "some", "than", "that", "that's", "the", "their", "them", "then", "there", "these",
+    "they", "this", "to", "too", "up", "us", "very", "was", "we", "were", "what", "when", "where",
+    "which", "who", "why", "will", "with", "would", "yes", "you", "your",
+];
+
+pub fn is_stopword(word: &str) -> bool {
+    STOPWORDS.contains(&word)
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for chunk in text.split_whitespace() {
+        if chunk.starts_with('@') || chunk.contains("://") || chunk.starts_with("www.") {
+            continue;
+        }
+        let chunk = chunk.replace('’', "'");
+        for word in chunk.split(|c: char| !c.is_alphanumeric() && c != '\'') {
+            let word = word.trim_matches('\'').to_lowercase();
+            if word.chars().count() < 2 || word.chars().all(|c| c.is_numeric()) {
+                continue;
+            }
+            tokens.push(word);
+        }
+    }
+    tokens
+}
+
+#[derive(Debug, Default)]
+pub struct WordCounts {
+    pub messages: u64,
+    words: HashMap<String, u64>,
+    bigrams: HashMap<String, u64>,
+}
+
+impl WordCounts {
+    pub fn add(&mut self, text: &str, exclude_stopwords: bool) {
+        self.messages += 1;
+        let tokens = tokenize(text);
+        let keep = |t: &str| !exclude_stopwords || !is_stopword(t);
+        for token in tokens.iter().filter(|t| keep(t)) {
+            *self.words.entry(token.clone()).or_default() += 1;
+        }
+        for pair in tokens.windows(2) {
+            if keep(&pair[0]) && keep(&pair[1]) {
+                *self
+                    .bigrams
+                    .entry(format!("{} {}", pair[0], pair[1]))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    pub fn top_words(&self, n: usize) -> Vec<(String, u64)> {
+        top(&self.words, n)
+    }
+
+    pub fn top_bigrams(&self, n: usize) -> Vec<(String, u64)> {
+        top(&self.bigrams, n)
+    }
+}
+
+/// Most frequent first, ties alphabetically.
+fn top(counts: &HashMap<String, u64>, n: usize) -> Vec<(String, u64)> {
+    let mut top: Vec<(String, u64)> = counts.iter().map(|(k, v)| (k.clone(), *v)).collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top.truncate(n);
+    top
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_is_unicode_aware() {
+        assert_eq!(
+            tokenize("Café MÜNCHEN! don’t see https://x.com @bob 2024 a Привет,мир"),
+            vec!["café", "münchen", "don't", "see", "привет", "мир"]
+        );
+    }
+
+    #[test]
+    fn counts_words_and_bigrams() {
+        let mut counts = WordCounts::default();
+        counts.add("The release is out", true);
+        counts.add("new release out now", true);
+        assert_eq!(counts.messages, 2);
+        assert_eq!(
+            counts.top_words(2),
+            vec![("release".to_string(), 2), ("new".to_string(), 1)]
+        );
+        // Pairs with a stopword in them ("release is", "release out") are skipped
+        assert_eq!(counts.top_bigrams(5), vec![("new release".to_string(), 1)]);
+    }
+}